@@ -43,6 +43,65 @@ pub struct PaginatedResponse<T> {
     pub has_more: bool,
 }
 
+/// 角色引用 RoleRef 结构
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RoleRef {
+    pub role_id: String,
+    pub role_name: String,
+}
+
+/// 带来权限的角色分配 RoleAssignmentSource 结构
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RoleAssignmentSource {
+    pub role_id: String,
+    pub role_name: String,
+    /// 生效范围 (organization / client_id)，为空表示全局分配
+    pub context: Option<serde_json::Value>,
+    pub expires_at: Option<String>,
+    pub assigned_by: Option<String>,
+    pub assigned_at: String,
+}
+
+/// 权限来源 PermissionSource 结构: 角色分配 → 继承路径 → 授予权限的角色
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PermissionSource {
+    pub assignment: RoleAssignmentSource,
+    pub path: Vec<RoleRef>,
+    /// 附加条件，不为空时在请求时求值
+    pub conditions: Option<serde_json::Value>,
+}
+
+/// 有效权限 EffectivePermission 结构
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EffectivePermission {
+    pub permission: String,
+    pub sources: Vec<PermissionSource>,
+}
+
+/// 可以授予权限的角色 GrantingRole 结构
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GrantingRole {
+    pub role_id: String,
+    pub role_name: String,
+    pub path: Vec<RoleRef>,
+    pub conditions: Option<serde_json::Value>,
+}
+
+/// 缺少的权限 MissingPermission 结构
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MissingPermission {
+    pub permission: String,
+    pub granting_roles: Vec<GrantingRole>,
+}
+
+/// 用户有效权限的解释 PermissionExplanation 结构
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PermissionExplanation {
+    pub user_id: String,
+    pub permissions: Vec<EffectivePermission>,
+    pub missing: Vec<MissingPermission>,
+}
+
 /// RBAC 模块 RbacModule
 #[derive(Debug)]
 pub struct RbacModule {
@@ -98,6 +157,34 @@ impl RbacModule {
             .map_err(|e| crate::napi::error::SDKError::new("PARSE_ERROR", e.to_string()))
     }
 
+    /// 解释用户的有效权限 explain_user_permissions
+    /// GET /api/v2/admin/users/{user_id}/permissions?client_id=...&permissions=a,b
+    pub async fn explain_user_permissions(
+        &self,
+        user_id: String,
+        client_id: Option<String>,
+        permissions: Option<Vec<String>>,
+    ) -> SDKResult<PermissionExplanation> {
+        let mut query_params = Vec::new();
+        if let Some(client_id) = client_id {
+            query_params.push(format!("client_id={}", urlencoding::encode(&client_id)));
+        }
+        if let Some(permissions) = permissions.filter(|p| !p.is_empty()) {
+            query_params.push(format!(
+                "permissions={}",
+                urlencoding::encode(&permissions.join(","))
+            ));
+        }
+        let mut path = format!("/api/v2/admin/users/{user_id}/permissions");
+        if !query_params.is_empty() {
+            path.push('?');
+            path.push_str(&query_params.join("&"));
+        }
+        let response = self.http_client.get(&path).await?;
+        serde_json::from_value::<PermissionExplanation>(response)
+            .map_err(|e| crate::napi::error::SDKError::new("PARSE_ERROR", e.to_string()))
+    }
+
     /// 撤销用户角色 revoke_role_from_user
     /// DELETE /api/v2/rbac/users/{user_id}/roles/{role_id}
     pub async fn revoke_role_from_user(&self, user_id: String, role_id: String) -> SDKResult<bool> {
@@ -210,4 +297,50 @@ mod tests {
         assert_eq!(json["user_id"], "user123");
         assert_eq!(json["role_id"], "role456");
     }
+
+    #[test]
+    fn test_permission_explanation_deserialization() {
+        // 验证服务端的权限解释响应可以解析 - Verifies the explanation response parses
+        let json = serde_json::json!({
+            "user_id": "user123",
+            "context": {},
+            "permissions": [{
+                "permission": "users:read",
+                "sources": [{
+                    "assignment": {
+                        "user_id": "user123",
+                        "role_id": "role1",
+                        "role_name": "editor",
+                        "role_display_name": "Editor",
+                        "context": null,
+                        "expires_at": null,
+                        "assigned_by": "admin",
+                        "assigned_at": "2025-01-01T00:00:00Z"
+                    },
+                    "path": [
+                        {"role_id": "role1", "role_name": "editor"},
+                        {"role_id": "role2", "role_name": "viewer"}
+                    ],
+                    "conditions": null
+                }]
+            }],
+            "missing": [{
+                "permission": "users:delete",
+                "granting_roles": [{
+                    "role_id": "role3",
+                    "role_name": "admin",
+                    "path": [{"role_id": "role3", "role_name": "admin"}],
+                    "conditions": {"ip_range": ["10.0.0.0/8"]}
+                }]
+            }]
+        });
+
+        let explanation: PermissionExplanation = serde_json::from_value(json).unwrap();
+
+        let source = &explanation.permissions[0].sources[0];
+        assert_eq!(source.assignment.assigned_by.as_deref(), Some("admin"));
+        assert_eq!(source.path.len(), 2);
+        assert_eq!(source.path[1].role_name, "viewer");
+        assert_eq!(explanation.missing[0].granting_roles[0].role_name, "admin");
+    }
 }
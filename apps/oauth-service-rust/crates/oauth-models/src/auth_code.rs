@@ -13,6 +13,8 @@ pub struct AuthCode {
     pub code_challenge: Option<String>,
     pub code_challenge_method: Option<String>,
     pub nonce: Option<String>,
+    /// 授权请求指定的资源服务器 (RFC 8707)
+    pub resource: Option<String>,
     pub is_used: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
@@ -44,6 +44,8 @@ pub struct OAuthClient {
     pub strict_redirect_uri_matching: bool,
     pub allow_localhost_redirect: bool,
     pub require_https_redirect: bool,
+    /// Owning tenant; `None` for platform-wide clients usable by every tenant.
+    pub tenant_id: Option<String>,
 }
 
 /// Represents a fully detailed OAuth2 client, including its related entities.
@@ -5,15 +5,27 @@
 
 pub mod auth_code;
 pub mod client;
+pub mod menu;
 pub mod permission;
 pub mod refresh_token;
+pub mod resource_server;
 pub mod role;
+pub mod scope;
+pub mod system_config;
+pub mod tenant;
 pub mod user;
 
 // Re-export commonly used types
 pub use auth_code::AuthCode;
 pub use client::{ClientType, OAuthClient, OAuthClientDetails};
+pub use menu::{Menu, MenuNode};
 pub use permission::{Permission, PermissionType};
 pub use refresh_token::RefreshToken;
-pub use role::Role;
+pub use resource_server::ResourceServer;
+pub use role::{
+    InheritedPermission, Role, RoleAssignmentContext, RolePermissions, UserRoleAssignment,
+};
+pub use scope::{Scope, ScopeConsentInfo, ScopeLocalization};
+pub use system_config::SystemConfiguration;
+pub use tenant::Tenant;
 pub use user::User;
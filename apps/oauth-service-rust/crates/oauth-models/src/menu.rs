@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 代表管理后台导航中的一个菜单项，对应 `menus` 表
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Menu {
+    /// 菜单的唯一标识符 (UUID)
+    pub id: String,
+    /// 菜单的显示名称
+    pub name: String,
+    /// 菜单的唯一键，前端用于路由匹配
+    pub key: String,
+    /// 前端路由路径 (分组菜单可为空)
+    pub path: Option<String>,
+    /// 前端组件名称
+    pub component: Option<String>,
+    /// 菜单图标
+    pub icon: Option<String>,
+    /// 同级菜单中的排序值，越小越靠前
+    pub order: i32,
+    /// 是否在导航中隐藏 (路由仍然可访问)
+    pub is_hidden: bool,
+    /// 是否激活
+    pub is_active: bool,
+    /// 父菜单ID，顶级菜单为空
+    pub parent_id: Option<String>,
+    /// 记录创建时间
+    pub created_at: DateTime<Utc>,
+    /// 记录更新时间
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 菜单树节点，包含按排序值排列的子菜单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MenuNode {
+    #[serde(flatten)]
+    pub menu: Menu,
+    /// 访问此菜单所需的权限 (满足其一即可，为空表示所有登录用户可见)
+    pub permissions: Vec<String>,
+    pub children: Vec<MenuNode>,
+}
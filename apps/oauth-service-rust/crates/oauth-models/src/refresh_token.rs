@@ -16,4 +16,8 @@ pub struct RefreshToken {
     pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub previous_token_id: Option<String>,
+    /// 令牌的目标资源服务器 (RFC 8707)，刷新时沿用
+    pub resource: Option<String>,
+    /// 公共客户端的刷新令牌绑定的 DPoP 密钥指纹 (RFC 9449)，刷新时必须使用同一把密钥
+    pub dpop_jkt: Option<String>,
 }
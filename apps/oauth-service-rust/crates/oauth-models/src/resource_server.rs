@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 代表一个已注册的资源服务器 (受保护的 API)，对应 `resource_servers` 表
+///
+/// 授权和令牌请求通过 `resource` 参数 (RFC 8707) 指定资源服务器，签发的访问令牌
+/// 的 `aud` 即为其标识符。
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceServer {
+    /// 资源服务器的唯一标识符
+    pub id: String,
+    /// 资源标识符 (绝对 URI，不含 fragment)，创建后不可修改
+    pub identifier: String,
+    /// 资源服务器的显示名称
+    pub name: String,
+    /// 资源服务器接受的 scope (空格分隔)
+    pub allowed_scopes: String,
+    /// 资源服务器调用内省端点时使用的客户端 ID，只能内省以该资源服务器为受众的令牌
+    pub client_id: Option<String>,
+    /// 是否激活，停用的资源服务器不能作为令牌的目标
+    pub is_active: bool,
+    /// 记录创建时间
+    pub created_at: DateTime<Utc>,
+    /// 记录更新时间
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ResourceServer {
+    /// 资源服务器是否接受该 scope
+    pub fn allows_scope(&self, scope: &str) -> bool {
+        self.allowed_scopes.split_whitespace().any(|allowed| allowed == scope)
+    }
+}
@@ -21,4 +21,80 @@ pub struct Role {
     pub created_at: DateTime<Utc>,
     /// 记录更新时间
     pub updated_at: DateTime<Utc>,
+    /// 所属租户，为空表示所有租户共用的全局角色
+    pub tenant_id: Option<String>,
+}
+
+/// 角色的权限视图，区分直接分配的权限与从父角色继承的权限
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RolePermissions {
+    /// 直接分配给该角色的权限名称
+    pub direct: Vec<String>,
+    /// 从父角色 (含祖先角色) 继承、且未直接分配的权限
+    pub inherited: Vec<InheritedPermission>,
+}
+
+impl RolePermissions {
+    /// 角色的全部有效权限 (直接 + 继承)
+    pub fn effective(&self) -> Vec<String> {
+        self.direct
+            .iter()
+            .cloned()
+            .chain(self.inherited.iter().map(|p| p.name.clone()))
+            .collect()
+    }
+}
+
+/// 继承得到的权限及其来源角色
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InheritedPermission {
+    /// 权限名称
+    pub name: String,
+    /// 提供该权限的祖先角色名称
+    pub inherited_from: Vec<String>,
+}
+
+/// 角色分配的生效范围，存储在 `user_roles.context` (JSON)。
+/// 所有已设置的字段都必须与请求上下文匹配，分配才会生效；
+/// 未设置上下文的分配为全局分配。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoleAssignmentContext {
+    /// 仅在用户属于该组织时生效 (对应 `users.organization`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+    /// 仅在通过该客户端 (`oauth_clients.client_id`) 签发令牌时生效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+}
+
+impl RoleAssignmentContext {
+    /// 是否未限定任何范围
+    pub fn is_empty(&self) -> bool {
+        self.organization.is_none() && self.client_id.is_none()
+    }
+}
+
+/// 用户的一条角色分配记录，对应 `user_roles` 表并附带角色信息
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct UserRoleAssignment {
+    pub user_id: String,
+    pub role_id: String,
+    /// 角色的唯一名称
+    pub role_name: String,
+    /// 角色的显示名称
+    pub role_display_name: String,
+    /// 生效范围，为空表示全局分配
+    pub context: Option<sqlx::types::Json<RoleAssignmentContext>>,
+    /// 过期时间，为空表示永久有效
+    pub expires_at: Option<DateTime<Utc>>,
+    /// 执行分配的用户 (或客户端) ID
+    pub assigned_by: Option<String>,
+    pub assigned_at: DateTime<Utc>,
+}
+
+impl UserRoleAssignment {
+    /// 分配是否已过期 (尚未被后台清理任务删除)
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= Utc::now())
+    }
 }
@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 代表一个 OAuth 权限范围 (scope)，对应 `scopes` 表
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Scope {
+    /// 权限范围的唯一标识符
+    pub id: String,
+    /// 权限范围名称 (如 "openid"、"read:users")，创建后不可修改
+    pub name: String,
+    /// 权限范围的描述
+    pub description: Option<String>,
+    /// 是否允许任何客户端申请
+    pub is_public: bool,
+    /// 是否为 OpenID Connect 标准权限范围
+    pub is_oidc_scope: bool,
+    /// 是否激活，未激活的权限范围不会映射任何权限
+    pub is_active: bool,
+    /// 记录创建时间
+    pub created_at: DateTime<Utc>,
+    /// 记录更新时间
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 权限范围在某个语言环境下的同意页面文案，对应 `scope_localizations` 表
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopeLocalization {
+    /// 语言环境 (如 "zh-CN"、"en")
+    pub locale: String,
+    /// 显示名称
+    pub display_name: String,
+    /// 同意页面上展示的说明
+    pub description: String,
+    /// 风险等级: "low"、"medium"、"high"
+    pub risk_level: String,
+    /// 分类 (如 "Identity"、"Profile"、"Access")
+    pub category: Option<String>,
+}
+
+/// 同意页面上展示的一个权限范围
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopeConsentInfo {
+    pub name: String,
+    pub display_name: String,
+    pub description: String,
+    pub risk_level: String,
+    pub category: Option<String>,
+}
@@ -0,0 +1,49 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 敏感配置项对外展示时使用的掩码
+pub const SENSITIVE_VALUE_MASK: &str = "******";
+
+/// 代表一个运行时系统配置项，对应 `system_configurations` 表
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemConfiguration {
+    /// 配置项的唯一标识符
+    pub id: String,
+    /// 配置键 (如 "auth.token.access_ttl")
+    pub key: String,
+    /// 配置值 (JSON 文本)
+    pub value: String,
+    /// 配置项的描述
+    pub description: Option<String>,
+    /// 值类型: "string"、"number"、"boolean"、"array"、"object"
+    #[sqlx(rename = "type")]
+    pub value_type: Option<String>,
+    /// 是否允许通过管理接口修改
+    pub is_editable: bool,
+    /// 是否为敏感配置，敏感配置的值不会通过管理接口返回
+    pub is_sensitive: bool,
+    /// 分类 (如 "auth"、"security"、"rate_limit")
+    pub category: Option<String>,
+    /// 记录创建时间
+    pub created_at: DateTime<Utc>,
+    /// 记录更新时间
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SystemConfiguration {
+    /// 解析后的配置值，无法解析为 JSON 时按字符串处理
+    pub fn parsed_value(&self) -> serde_json::Value {
+        serde_json::from_str(&self.value)
+            .unwrap_or_else(|_| serde_json::Value::String(self.value.clone()))
+    }
+
+    /// 对外展示的配置值，敏感配置使用掩码代替
+    pub fn display_value(&self) -> serde_json::Value {
+        if self.is_sensitive {
+            serde_json::Value::String(SENSITIVE_VALUE_MASK.to_string())
+        } else {
+            self.parsed_value()
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 代表一个租户 (业务单元)，对应 `tenants` 表
+///
+/// 用户、客户端、角色和审计日志通过 `tenant_id` 归属于租户，为空表示平台级资源。
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Tenant {
+    /// 租户 ID (小写字母、数字和连字符)，同时用作令牌的 tenant 声明和 issuer 路径，创建后不可修改
+    pub id: String,
+    /// 租户的显示名称
+    pub name: String,
+    /// 是否激活，停用的租户中的用户和客户端无法获取令牌
+    pub is_active: bool,
+    /// 记录创建时间
+    pub created_at: DateTime<Utc>,
+    /// 记录更新时间
+    pub updated_at: DateTime<Utc>,
+}
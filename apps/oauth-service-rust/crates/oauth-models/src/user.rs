@@ -23,4 +23,6 @@ pub struct User {
     pub failed_login_attempts: i32,
     pub locked_until: Option<chrono::DateTime<chrono::Utc>>,
     pub created_by: Option<String>,
+    /// 所属租户，为空表示平台级用户
+    pub tenant_id: Option<String>,
 }
@@ -274,6 +274,33 @@ impl NapiOAuthSDK {
             .map_err(|e| napi::Error::new(napi::Status::GenericFailure, e.to_string()))
     }
 
+    /// 解释用户的有效权限 (Explain User's Effective Permissions)
+    ///
+    /// # Arguments
+    /// * `user_id` - 用户ID (User ID)
+    /// * `client_id` - 按该客户端的范围求值 (Evaluate for this client, optional)
+    /// * `permissions` - 需要解释的权限 (Permissions to explain when missing, optional)
+    ///
+    /// # Returns
+    /// * `Result<serde_json::Value>` - 权限来源和可授予缺少权限的角色 (Permission sources and granting roles)
+    #[napi]
+    pub async fn rbac_explain_permissions(
+        &self,
+        user_id: String,
+        client_id: Option<String>,
+        permissions: Option<Vec<String>>,
+    ) -> Result<serde_json::Value> {
+        let result = self
+            .sdk
+            .rbac
+            .explain_user_permissions(user_id, client_id, permissions)
+            .await
+            .map_err(|e| napi::Error::new(napi::Status::GenericFailure, e.to_string()))?;
+
+        serde_json::to_value(result)
+            .map_err(|e| napi::Error::new(napi::Status::GenericFailure, e.to_string()))
+    }
+
     /// 获取客户端列表 (Get Client List)
     ///
     /// # Arguments
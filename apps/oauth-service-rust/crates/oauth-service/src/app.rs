@@ -1,13 +1,19 @@
 use axum::{
     http::Method,
-    routing::{get, post},
+    routing::{delete, get, post, put},
     Router,
 };
-use std::sync::Arc;
-use tower_http::{cors::CorsLayer, services::ServeDir, trace::TraceLayer};
+use std::{sync::Arc, time::Duration};
+use tower_http::{
+    cors::{AllowOrigin, CorsLayer},
+    services::ServeDir,
+};
 
 use crate::{config::Config, middleware, routes, state::AppState};
 
+/// 过期角色分配的清理间隔
+const EXPIRED_ROLE_ASSIGNMENT_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
 pub async fn create_app(pool: Arc<sqlx::SqlitePool>, config: Arc<Config>) -> Router {
     let app_state = Arc::new(
         AppState::new_with_pool_and_config(pool, config.clone())
@@ -15,6 +21,39 @@ pub async fn create_app(pool: Arc<sqlx::SqlitePool>, config: Arc<Config>) -> Rou
             .expect("Failed to create AppState"),
     );
 
+    // 安装 Prometheus 记录器，之后记录的指标才会被导出
+    crate::metrics::handle();
+
+    // 后台清理过期的临时角色分配
+    crate::services::role_service::spawn_expired_assignment_sweeper(
+        app_state.role_service.clone(),
+        EXPIRED_ROLE_ASSIGNMENT_SWEEP_INTERVAL,
+    );
+
+    // 定期清理过期的授权码、令牌、黑名单等数据
+    if config.maintenance.enabled {
+        crate::services::maintenance_service::spawn_maintenance_scheduler(
+            app_state.maintenance_service.clone(),
+            Duration::from_secs(config.maintenance.interval_secs),
+        );
+    }
+
+    // 运行时配置变更后立即应用到限流器等长期存在的组件
+    let settings_state = app_state.clone();
+    crate::services::system_config_service::spawn_settings_watcher(
+        app_state.system_config_service.clone(),
+        move |settings| settings_state.apply_runtime_settings(settings),
+    );
+
+    // CORS 允许的源从运行时配置读取，修改后无需重启
+    let cors_config = app_state.system_config_service.clone();
+    let cors_origins = AllowOrigin::predicate(move |origin, _| {
+        origin
+            .to_str()
+            .map(|origin| cors_config.current().is_cors_origin_allowed(origin))
+            .unwrap_or(false)
+    });
+
     // 定义API路由
     // 将层应用与路由定义分开，以提高可读性
     let api_router = Router::new()
@@ -38,6 +77,38 @@ pub async fn create_app(pool: Arc<sqlx::SqlitePool>, config: Arc<Config>) -> Rou
         // 认证端点 (公开)
         .route("/api/v2/auth/login", post(routes::oauth::login_endpoint))
         .route("/api/v2/auth/authenticate", post(routes::oauth::authenticate_endpoint))
+        // 联合登录 (上游 OIDC 身份提供方)
+        .route(
+            "/api/v2/auth/federated/providers",
+            get(routes::federation::list_providers),
+        )
+        .route(
+            "/api/v2/auth/federated/:provider/login",
+            get(routes::federation::federated_login),
+        )
+        .route(
+            "/api/v2/auth/federated/:provider/callback",
+            get(routes::federation::federated_callback),
+        )
+        // 通行密钥 (WebAuthn)
+        .route(
+            "/api/v2/auth/webauthn/register/options",
+            post(routes::webauthn::registration_options),
+        )
+        .route("/api/v2/auth/webauthn/register", post(routes::webauthn::register))
+        .route(
+            "/api/v2/auth/webauthn/credentials",
+            get(routes::webauthn::list_credentials),
+        )
+        .route(
+            "/api/v2/auth/webauthn/credentials/:id",
+            delete(routes::webauthn::delete_credential),
+        )
+        .route(
+            "/api/v2/auth/webauthn/login/options",
+            post(routes::webauthn::login_options),
+        )
+        .route("/api/v2/auth/webauthn/login", post(routes::webauthn::login))
         // 同意页面端点 (需要认证)
         .route(
             "/api/v2/oauth/consent/info",
@@ -58,6 +129,10 @@ pub async fn create_app(pool: Arc<sqlx::SqlitePool>, config: Arc<Config>) -> Rou
                 .put(routes::clients::update_client)
                 .delete(routes::clients::delete_client),
         )
+        .route(
+            "/api/v2/admin/clients/:client_id/revoke-tokens",
+            post(routes::clients::revoke_client_tokens),
+        )
         // 用户管理端点
         .route(
             "/api/v2/admin/users",
@@ -69,16 +144,36 @@ pub async fn create_app(pool: Arc<sqlx::SqlitePool>, config: Arc<Config>) -> Rou
                 .put(routes::users::update_user)
                 .delete(routes::users::delete_user),
         )
+        .route(
+            "/api/v2/admin/users/:user_id/impersonate",
+            post(routes::users::impersonate_user),
+        )
+        .route(
+            "/api/v2/admin/users/:user_id/revoke-tokens",
+            post(routes::users::revoke_user_tokens),
+        )
         // 当前用户端点
         .route(
             "/api/v2/users/me",
             get(routes::users::get_current_user),
         )
+        .route(
+            "/api/v2/users/me/impersonation/end",
+            post(routes::users::end_impersonation),
+        )
+        .route(
+            "/api/v2/users/me/menus",
+            get(routes::menus::get_current_user_menus),
+        )
         // 权限管理端点
         .route(
             "/api/v2/admin/permissions",
             get(routes::permissions::list_permissions).post(routes::permissions::create_permission),
         )
+        .route(
+            "/api/v2/admin/permissions/evaluate",
+            post(routes::permissions::evaluate_permission),
+        )
         .route(
             "/api/v2/admin/permissions/:permission_id",
             get(routes::permissions::get_permission)
@@ -102,12 +197,143 @@ pub async fn create_app(pool: Arc<sqlx::SqlitePool>, config: Arc<Config>) -> Rou
                 .post(routes::roles::assign_permissions_to_role)
                 .delete(routes::roles::remove_permissions_from_role),
         )
+        .route(
+            "/api/v2/admin/roles/:role_id/permissions/:permission_id/conditions",
+            put(routes::roles::set_permission_conditions),
+        )
+        .route(
+            "/api/v2/admin/roles/:role_id/parents",
+            get(routes::roles::get_parent_roles).put(routes::roles::set_parent_roles),
+        )
         .route(
             "/api/v2/admin/users/:user_id/roles",
             get(routes::roles::get_user_roles)
                 .post(routes::roles::assign_role_to_user)
                 .delete(routes::roles::remove_role_from_user),
         )
+        .route(
+            "/api/v2/admin/users/:user_id/permissions",
+            get(routes::roles::explain_user_permissions),
+        )
+        .route(
+            "/api/v2/admin/roles/:role_id/approvers",
+            get(routes::roles::get_role_approvers).put(routes::roles::set_role_approvers),
+        )
+        // 角色申请端点 (用户申请、审批人审批、管理员查看)
+        .route(
+            "/api/v2/users/me/access-requests",
+            get(routes::access_requests::list_my_requests)
+                .post(routes::access_requests::create_my_request),
+        )
+        .route(
+            "/api/v2/users/me/access-requests/:request_id/cancel",
+            post(routes::access_requests::cancel_my_request),
+        )
+        .route(
+            "/api/v2/access-requests",
+            get(routes::access_requests::list_approvals),
+        )
+        .route(
+            "/api/v2/access-requests/:request_id/approve",
+            post(routes::access_requests::approve_request),
+        )
+        .route(
+            "/api/v2/access-requests/:request_id/reject",
+            post(routes::access_requests::reject_request),
+        )
+        .route(
+            "/api/v2/admin/access-requests",
+            get(routes::access_requests::list_access_requests),
+        )
+        // 权限范围管理端点
+        .route(
+            "/api/v2/admin/scopes",
+            get(routes::scopes::list_scopes).post(routes::scopes::create_scope),
+        )
+        .route(
+            "/api/v2/admin/scopes/:scope_id",
+            get(routes::scopes::get_scope)
+                .put(routes::scopes::update_scope)
+                .delete(routes::scopes::delete_scope),
+        )
+        .route(
+            "/api/v2/admin/scopes/:scope_id/permissions",
+            get(routes::scopes::get_scope_permissions).put(routes::scopes::set_scope_permissions),
+        )
+        .route(
+            "/api/v2/admin/scopes/:scope_id/localizations",
+            get(routes::scopes::get_scope_localizations)
+                .put(routes::scopes::set_scope_localizations),
+        )
+        // 系统配置管理端点
+        .route(
+            "/api/v2/admin/system/configurations",
+            get(routes::system_config::list_configurations),
+        )
+        .route(
+            "/api/v2/admin/system/configurations/reload",
+            post(routes::system_config::reload_configurations),
+        )
+        .route(
+            "/api/v2/admin/system/configurations/:key",
+            get(routes::system_config::get_configuration)
+                .put(routes::system_config::update_configuration),
+        )
+        // 数据维护端点
+        .route(
+            "/api/v2/admin/system/maintenance/run",
+            post(routes::maintenance::run_maintenance),
+        )
+        // 租户管理端点
+        .route(
+            "/api/v2/admin/tenants",
+            get(routes::tenants::list_tenants).post(routes::tenants::create_tenant),
+        )
+        .route(
+            "/api/v2/admin/tenants/:tenant_id",
+            get(routes::tenants::get_tenant)
+                .put(routes::tenants::update_tenant)
+                .delete(routes::tenants::delete_tenant),
+        )
+        // 资源服务器管理端点
+        .route(
+            "/api/v2/admin/resource-servers",
+            get(routes::resource_servers::list_resource_servers)
+                .post(routes::resource_servers::create_resource_server),
+        )
+        .route(
+            "/api/v2/admin/resource-servers/:server_id",
+            get(routes::resource_servers::get_resource_server)
+                .put(routes::resource_servers::update_resource_server)
+                .delete(routes::resource_servers::delete_resource_server),
+        )
+        // 授权决策端点 (资源服务器和前端按权限判断能否执行操作)
+        .route("/api/v2/authz/check", post(routes::authz::check))
+        .route("/api/v2/authz/check/batch", post(routes::authz::check_batch))
+        // 租户的 OIDC 发现端点 (公开)
+        .route(
+            "/tenants/:tenant_id/.well-known/openid-configuration",
+            get(routes::tenants::openid_configuration),
+        )
+        // 菜单管理端点
+        .route(
+            "/api/v2/admin/menus",
+            get(routes::menus::list_menus).post(routes::menus::create_menu),
+        )
+        .route(
+            "/api/v2/admin/menus/tree",
+            get(routes::menus::get_menu_tree),
+        )
+        .route(
+            "/api/v2/admin/menus/:menu_id",
+            get(routes::menus::get_menu)
+                .put(routes::menus::update_menu)
+                .delete(routes::menus::delete_menu),
+        )
+        .route(
+            "/api/v2/admin/menus/:menu_id/permissions",
+            get(routes::menus::get_menu_permissions).put(routes::menus::set_menu_permissions),
+        )
         // 审计日志端点 (Audit logs export endpoints)
         .route(
             "/api/v2/admin/audit-logs",
@@ -117,6 +343,34 @@ pub async fn create_app(pool: Arc<sqlx::SqlitePool>, config: Arc<Config>) -> Rou
             "/api/v2/admin/audit-logs/export",
             get(routes::audit_logs::export_audit_logs),
         )
+        // SCIM 2.0 同步端点
+        .route(
+            "/scim/v2/Users",
+            get(routes::scim::list_users).post(routes::scim::create_user),
+        )
+        .route(
+            "/scim/v2/Users/:id",
+            get(routes::scim::get_user)
+                .put(routes::scim::replace_user)
+                .patch(routes::scim::patch_user)
+                .delete(routes::scim::delete_user),
+        )
+        .route(
+            "/scim/v2/Groups",
+            get(routes::scim::list_groups).post(routes::scim::create_group),
+        )
+        .route(
+            "/scim/v2/Groups/:id",
+            get(routes::scim::get_group)
+                .put(routes::scim::replace_group)
+                .patch(routes::scim::patch_group)
+                .delete(routes::scim::delete_group),
+        )
+        .route(
+            "/scim/v2/ServiceProviderConfig",
+            get(routes::scim::service_provider_config),
+        )
+        .route("/scim/v2/ResourceTypes", get(routes::scim::resource_types))
         // ===== Web UI 路由 =====
         // 登录页面
         .route("/login", get(routes::templates::login_handler))
@@ -134,40 +388,38 @@ pub async fn create_app(pool: Arc<sqlx::SqlitePool>, config: Arc<Config>) -> Rou
     // 采用简化的架构：直接在 Router 上应用层，避免 ServiceBuilder 的复杂类型
     // 注意: 中间件按反向顺序执行，最后添加的layer最先处理请求
     // 因此应按相反顺序添加，以便按所需顺序执行
-    
-
-    api_router
+    let app = api_router
         .with_state(app_state.clone())
-        // 7. 安全头部 - 最先执行，为所有响应添加安全头部
+        // 6. 安全头部 - 最先执行，为所有响应添加安全头部
         .layer(axum::middleware::from_fn(
             middleware::security_headers::security_headers_middleware,
         ))
-        // 6. 审计中间件 - 在安全头部之后执行
+        // 5. 审计中间件 - 在安全头部之后执行
         .layer(axum::middleware::from_fn(
             middleware::audit::audit_middleware,
         ))
-        // 5. 追踪和日志 - 在审计之后执行
-        .layer(TraceLayer::new_for_http())
         // 4. CORS - 处理跨域请求 (SECURITY FIX: Restricted origins)
         .layer(
             CorsLayer::new()
-                // Allow specific origins from config (for production)
-                // For development, can add localhost
-                .allow_origin([
-                    "http://localhost:3002".parse().unwrap(),  // Admin Portal
-                    "http://localhost:6188".parse().unwrap(),  // Pingora Proxy
-                    // Add production domains here
-                ])
+                // Allowed origins come from the `cors.allowed_origins` runtime setting
+                .allow_origin(cors_origins)
                 .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
                 .allow_headers([
                     axum::http::header::AUTHORIZATION,
                     axum::http::header::CONTENT_TYPE,
                     axum::http::header::ACCEPT,
+                    axum::http::HeaderName::from_static("traceparent"),
+                    axum::http::HeaderName::from_static("tracestate"),
+                    axum::http::HeaderName::from_static(middleware::request_context::REQUEST_ID_HEADER),
                 ])
+                .expose_headers([axum::http::HeaderName::from_static(
+                    middleware::request_context::REQUEST_ID_HEADER,
+                )])
                 .allow_credentials(true)  // Important for cookies and Authorization headers
         )
         // 3. 权限检查中间件 - 在认证之后执行（代码中在前）
-        .layer(axum::middleware::from_fn(
+        .layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
             middleware::permission::permission_middleware,
         ))
         // 2. 认证中间件 - 在限流之后执行（代码中在前）
@@ -181,4 +433,25 @@ pub async fn create_app(pool: Arc<sqlx::SqlitePool>, config: Arc<Config>) -> Rou
             app_state.clone(),
             middleware::rate_limit::rate_limit_middleware,
         ))
+        // 0. 请求指标 - 最外层，被限流或认证拒绝的请求也计入
+        .layer(axum::middleware::from_fn(
+            crate::metrics::http_metrics_middleware,
+        ));
+
+    // /metrics 不经过认证和限流，由 IP 白名单保护；配置了独立地址时只在该地址提供
+    let metrics = &config.metrics;
+    let app = match (metrics.enabled, metrics.listen_addr) {
+        (false, _) => app,
+        (true, None) => app.merge(crate::metrics::router(app_state)),
+        (true, Some(addr)) => {
+            crate::server::spawn_metrics_listener(crate::metrics::router(app_state), addr);
+            app
+        }
+    };
+
+    // 请求上下文 - 最外层 (包括未匹配的路由)，建立请求 span (遵循传入的 traceparent)
+    // 并为所有响应附加请求 ID
+    app.layer(axum::middleware::from_fn(
+        middleware::request_context::request_context_middleware,
+    ))
 }
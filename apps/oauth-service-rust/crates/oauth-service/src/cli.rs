@@ -0,0 +1,998 @@
+// 管理命令行 (Administrative CLI)
+// 直接使用配置的数据库和服务层完成初始化和运维操作，无需管理员令牌
+
+use crate::error::ServiceError;
+use crate::routes::clients::CreateClientRequest;
+use crate::services::rbac_definition_service::{RbacChangeAction, RbacDocument, RbacImportOptions};
+use crate::services::role_service::RoleAssignmentOptions;
+use crate::services::token_revocation_service::{RevocationActor, RevocationSummary};
+use crate::state::AppState;
+use crate::utils::crypto;
+use crate::models::user::User;
+use chrono::{Duration, Utc};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+pub const ADMIN_USAGE: &str = "\
+Admin commands:
+  user create <username> [--password <pw>] [--display-name <name>] [--role <role>]...
+  user lock <username> [--minutes <n>]       Lock an account (until unlocked if --minutes is omitted)
+  user unlock <username>
+  user reset-password <username> [--password <pw>]
+  client create <name> [--type confidential|public] [--redirect-uri <uri>]...
+                [--grant-type <type>]... [--scope <scope>]... [--permission <perm>]...
+  role assign <username> <role>
+  role remove <username> <role>
+  tokens revoke (--user <username> | --client <client_id>)   Revoke all tokens and sessions
+  rbac export [--format json|yaml] [--output <path>]
+  rbac import <path> [--dry-run] [--prune]   Apply an RBAC document (--prune removes undeclared items)
+  purge                                      Delete expired codes, tokens and role assignments";
+
+/// 未指定密码时生成的随机密码长度
+const GENERATED_PASSWORD_LENGTH: usize = 20;
+
+/// RBAC 定义文件格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RbacFormat {
+    Json,
+    Yaml,
+}
+
+impl RbacFormat {
+    /// 根据文件扩展名判断格式，默认为 JSON
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml") | Some("yml") => Self::Yaml,
+            _ => Self::Json,
+        }
+    }
+}
+
+/// 管理子命令
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdminCommand {
+    CreateUser {
+        username: String,
+        password: Option<String>,
+        display_name: Option<String>,
+        roles: Vec<String>,
+    },
+    LockUser {
+        username: String,
+        minutes: Option<i64>,
+    },
+    UnlockUser {
+        username: String,
+    },
+    ResetPassword {
+        username: String,
+        password: Option<String>,
+    },
+    CreateClient {
+        name: String,
+        client_type: String,
+        redirect_uris: Vec<String>,
+        grant_types: Vec<String>,
+        scopes: Vec<String>,
+        /// client_credentials 令牌携带的权限 (如 scim:provision)
+        permissions: Vec<String>,
+    },
+    AssignRole {
+        username: String,
+        role: String,
+    },
+    RemoveRole {
+        username: String,
+        role: String,
+    },
+    RevokeUserTokens {
+        username: String,
+    },
+    RevokeClientTokens {
+        client_id: String,
+    },
+    ExportRbac {
+        format: Option<RbacFormat>,
+        output: Option<PathBuf>,
+    },
+    ImportRbac {
+        path: PathBuf,
+        /// 只输出差异，不写入
+        dry_run: bool,
+        /// 删除文档中未声明的对象和关联
+        prune: bool,
+    },
+    Purge,
+}
+
+/// 子命令参数：位置参数、`--flag value` 形式的选项 (选项可重复) 和不带值的开关
+struct CommandArgs {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+    switches: Vec<String>,
+}
+
+impl CommandArgs {
+    fn parse(args: &[String], allowed: &[&str]) -> Result<Self, anyhow::Error> {
+        Self::parse_with_switches(args, allowed, &[])
+    }
+
+    fn parse_with_switches(
+        args: &[String],
+        allowed: &[&str],
+        switches: &[&str],
+    ) -> Result<Self, anyhow::Error> {
+        let mut parsed = Self {
+            positional: Vec::new(),
+            options: Vec::new(),
+            switches: Vec::new(),
+        };
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            if !arg.starts_with("--") {
+                parsed.positional.push(arg.clone());
+                continue;
+            }
+            if switches.contains(&arg.as_str()) {
+                parsed.switches.push(arg.clone());
+                continue;
+            }
+            let (flag, value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag, value.to_string()),
+                None => (
+                    arg.as_str(),
+                    iter.next()
+                        .cloned()
+                        .ok_or_else(|| anyhow::anyhow!("Missing value for {arg}\n{ADMIN_USAGE}"))?,
+                ),
+            };
+            if !allowed.contains(&flag) {
+                anyhow::bail!("Unknown option '{flag}'\n{ADMIN_USAGE}");
+            }
+            parsed.options.push((flag.to_string(), value));
+        }
+
+        Ok(parsed)
+    }
+
+    /// 取出唯一的位置参数
+    fn single(&self, name: &str) -> Result<String, anyhow::Error> {
+        match self.positional.as_slice() {
+            [value] => Ok(value.clone()),
+            _ => anyhow::bail!("Expected <{name}>\n{ADMIN_USAGE}"),
+        }
+    }
+
+    fn pair(&self, first: &str, second: &str) -> Result<(String, String), anyhow::Error> {
+        match self.positional.as_slice() {
+            [a, b] => Ok((a.clone(), b.clone())),
+            _ => anyhow::bail!("Expected <{first}> <{second}>\n{ADMIN_USAGE}"),
+        }
+    }
+
+    fn none(&self) -> Result<(), anyhow::Error> {
+        if !self.positional.is_empty() {
+            anyhow::bail!("Unexpected argument '{}'\n{ADMIN_USAGE}", self.positional[0]);
+        }
+        Ok(())
+    }
+
+    fn last(&self, flag: &str) -> Option<String> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(f, _)| f == flag)
+            .map(|(_, v)| v.clone())
+    }
+
+    fn has(&self, switch: &str) -> bool {
+        self.switches.iter().any(|s| s == switch)
+    }
+
+    fn all(&self, flag: &str) -> Vec<String> {
+        self.options
+            .iter()
+            .filter(|(f, _)| f == flag)
+            .map(|(_, v)| v.clone())
+            .collect()
+    }
+}
+
+impl AdminCommand {
+    /// 解析管理子命令，第一个参数不是管理命令时返回 None
+    pub fn parse(args: &[String]) -> Result<Option<Self>, anyhow::Error> {
+        let (Some(group), rest) = (args.first(), args.get(1..).unwrap_or_default()) else {
+            return Ok(None);
+        };
+        if !matches!(group.as_str(), "user" | "client" | "role" | "tokens" | "rbac" | "purge") {
+            return Ok(None);
+        }
+
+        let action = rest.first().map(String::as_str).unwrap_or_default();
+        let action_args = rest.get(1..).unwrap_or_default();
+
+        let command = match (group.as_str(), action) {
+            ("user", "create") => {
+                let args =
+                    CommandArgs::parse(action_args, &["--password", "--display-name", "--role"])?;
+                Self::CreateUser {
+                    username: args.single("username")?,
+                    password: args.last("--password"),
+                    display_name: args.last("--display-name"),
+                    roles: args.all("--role"),
+                }
+            }
+            ("user", "lock") => {
+                let args = CommandArgs::parse(action_args, &["--minutes"])?;
+                let minutes = match args.last("--minutes") {
+                    Some(minutes) => match minutes.parse::<i64>() {
+                        Ok(minutes) if minutes > 0 => Some(minutes),
+                        _ => anyhow::bail!("Invalid --minutes '{minutes}'"),
+                    },
+                    None => None,
+                };
+                Self::LockUser {
+                    username: args.single("username")?,
+                    minutes,
+                }
+            }
+            ("user", "unlock") => Self::UnlockUser {
+                username: CommandArgs::parse(action_args, &[])?.single("username")?,
+            },
+            ("user", "reset-password") => {
+                let args = CommandArgs::parse(action_args, &["--password"])?;
+                Self::ResetPassword {
+                    username: args.single("username")?,
+                    password: args.last("--password"),
+                }
+            }
+            ("client", "create") => {
+                let args = CommandArgs::parse(
+                    action_args,
+                    &["--type", "--redirect-uri", "--grant-type", "--scope", "--permission"],
+                )?;
+                let grant_types = match args.all("--grant-type") {
+                    grant_types if grant_types.is_empty() => {
+                        vec!["authorization_code".to_string(), "refresh_token".to_string()]
+                    }
+                    grant_types => grant_types,
+                };
+                Self::CreateClient {
+                    name: args.single("name")?,
+                    client_type: args
+                        .last("--type")
+                        .unwrap_or_else(|| "confidential".to_string())
+                        .to_uppercase(),
+                    redirect_uris: args.all("--redirect-uri"),
+                    grant_types,
+                    scopes: args.all("--scope"),
+                    permissions: args.all("--permission"),
+                }
+            }
+            ("role", "assign") | ("role", "remove") => {
+                let (username, role) =
+                    CommandArgs::parse(action_args, &[])?.pair("username", "role")?;
+                if action == "assign" {
+                    Self::AssignRole { username, role }
+                } else {
+                    Self::RemoveRole { username, role }
+                }
+            }
+            ("tokens", "revoke") => {
+                let args = CommandArgs::parse(action_args, &["--user", "--client"])?;
+                args.none()?;
+                match (args.last("--user"), args.last("--client")) {
+                    (Some(username), None) => Self::RevokeUserTokens { username },
+                    (None, Some(client_id)) => Self::RevokeClientTokens { client_id },
+                    _ => anyhow::bail!("Specify exactly one of --user or --client\n{ADMIN_USAGE}"),
+                }
+            }
+            ("rbac", "export") => {
+                let args = CommandArgs::parse(action_args, &["--format", "--output"])?;
+                args.none()?;
+                let format = match args.last("--format").as_deref() {
+                    None => None,
+                    Some("json") => Some(RbacFormat::Json),
+                    Some("yaml") | Some("yml") => Some(RbacFormat::Yaml),
+                    Some(other) => anyhow::bail!("Unsupported format '{other}', expected json or yaml"),
+                };
+                Self::ExportRbac {
+                    format,
+                    output: args.last("--output").map(PathBuf::from),
+                }
+            }
+            ("rbac", "import") => {
+                let args =
+                    CommandArgs::parse_with_switches(action_args, &[], &["--dry-run", "--prune"])?;
+                Self::ImportRbac {
+                    path: PathBuf::from(args.single("path")?),
+                    dry_run: args.has("--dry-run"),
+                    prune: args.has("--prune"),
+                }
+            }
+            ("purge", _) => {
+                CommandArgs::parse(rest, &[])?.none()?;
+                Self::Purge
+            }
+            _ => anyhow::bail!("Unknown command '{}'\n{ADMIN_USAGE}", args.join(" ")),
+        };
+
+        Ok(Some(command))
+    }
+}
+
+async fn find_user(state: &AppState, username: &str) -> Result<User, ServiceError> {
+    state
+        .user_service
+        .find_by_username(username)
+        .await?
+        .ok_or_else(|| ServiceError::NotFound(format!("User '{username}' not found")))
+}
+
+async fn find_role_id(state: &AppState, name: &str) -> Result<String, ServiceError> {
+    state
+        .role_service
+        .find_role_by_name(name)
+        .await?
+        .map(|role| role.id)
+        .ok_or_else(|| ServiceError::NotFound(format!("Role '{name}' not found")))
+}
+
+/// 命令行发起的吊销在审计日志中记录为系统操作
+fn cli_actor() -> RevocationActor {
+    RevocationActor {
+        actor_type: "system".to_string(),
+        actor_id: "cli".to_string(),
+    }
+}
+
+fn write_revocation_summary(out: &mut String, summary: &RevocationSummary) -> std::fmt::Result {
+    writeln!(
+        out,
+        "Ended {} session(s), invalidated {} authorization code(s); tokens issued before {} are rejected",
+        summary.sessions_ended,
+        summary.authorization_codes_invalidated,
+        summary.revoked_at.to_rfc3339()
+    )
+}
+
+/// 执行管理命令，返回需要输出给操作者的文本
+pub async fn run(state: &AppState, command: AdminCommand) -> Result<String, anyhow::Error> {
+    let mut out = String::new();
+
+    match command {
+        AdminCommand::CreateUser {
+            username,
+            password,
+            display_name,
+            roles,
+        } => {
+            // 先确认角色存在，避免创建出缺少角色的用户
+            let mut role_ids = Vec::new();
+            for role in &roles {
+                role_ids.push(find_role_id(state, role).await?);
+            }
+
+            let generated = password.is_none();
+            let password =
+                password.unwrap_or_else(|| crypto::generate_random_string(GENERATED_PASSWORD_LENGTH));
+            let user = state
+                .user_service
+                .create_user(username, password.clone(), display_name)
+                .await?;
+            if generated {
+                // 生成的密码只用于首次登录
+                state.user_service.set_password(&user.id, &password, true).await?;
+            }
+            for role_id in &role_ids {
+                state
+                    .role_service
+                    .assign_role_to_user(&user.id, role_id, RoleAssignmentOptions::default())
+                    .await?;
+            }
+
+            writeln!(out, "Created user '{}' ({})", user.username, user.id)?;
+            if !roles.is_empty() {
+                writeln!(out, "roles: {}", roles.join(", "))?;
+            }
+            if generated {
+                writeln!(out, "password: {password}")?;
+            }
+        }
+        AdminCommand::LockUser { username, minutes } => {
+            let user = find_user(state, &username).await?;
+            // 未指定时长时锁定到手动解锁为止
+            let until = Utc::now() + minutes.map_or(Duration::days(365 * 100), Duration::minutes);
+            state.user_service.set_locked_until(&user.id, Some(until)).await?;
+            match minutes {
+                Some(_) => writeln!(out, "Locked user '{username}' until {}", until.to_rfc3339())?,
+                None => writeln!(out, "Locked user '{username}' until unlocked")?,
+            }
+        }
+        AdminCommand::UnlockUser { username } => {
+            let user = find_user(state, &username).await?;
+            state.user_service.set_locked_until(&user.id, None).await?;
+            writeln!(out, "Unlocked user '{username}'")?;
+        }
+        AdminCommand::ResetPassword { username, password } => {
+            let user = find_user(state, &username).await?;
+            let generated = password.is_none();
+            let password =
+                password.unwrap_or_else(|| crypto::generate_random_string(GENERATED_PASSWORD_LENGTH));
+            state.user_service.set_password(&user.id, &password, true).await?;
+
+            writeln!(out, "Password of user '{username}' reset; it must be changed at next login")?;
+            if generated {
+                writeln!(out, "password: {password}")?;
+            }
+        }
+        AdminCommand::CreateClient {
+            name,
+            client_type,
+            redirect_uris,
+            grant_types,
+            scopes,
+            permissions,
+        } => {
+            let response_types = if grant_types.iter().any(|g| g == "authorization_code") {
+                vec!["code".to_string()]
+            } else {
+                Vec::new()
+            };
+            let settings = state.system_config_service.current();
+            let request = CreateClientRequest {
+                name,
+                client_type,
+                redirect_uris,
+                grant_types,
+                response_types,
+                allowed_scopes: scopes,
+                client_permissions: (!permissions.is_empty()).then_some(permissions),
+                access_token_ttl: Some(settings.access_token_ttl),
+                refresh_token_ttl: Some(settings.refresh_token_ttl),
+                tenant_id: None,
+            };
+            let (client, secret) = state.client_service.create_client(request).await?;
+
+            writeln!(out, "Created client '{}'", client.client.name)?;
+            writeln!(out, "client_id: {}", client.client.client_id)?;
+            if !secret.is_empty() {
+                writeln!(out, "client_secret: {secret}")?;
+            }
+        }
+        AdminCommand::AssignRole { username, role } => {
+            let user = find_user(state, &username).await?;
+            let role_id = find_role_id(state, &role).await?;
+            state
+                .role_service
+                .assign_role_to_user(&user.id, &role_id, RoleAssignmentOptions::default())
+                .await?;
+            writeln!(out, "Assigned role '{role}' to user '{username}'")?;
+        }
+        AdminCommand::RemoveRole { username, role } => {
+            let user = find_user(state, &username).await?;
+            let role_id = find_role_id(state, &role).await?;
+            state.role_service.remove_role_from_user(&user.id, &role_id).await?;
+            writeln!(out, "Removed role '{role}' from user '{username}'")?;
+        }
+        AdminCommand::RevokeUserTokens { username } => {
+            let user = find_user(state, &username).await?;
+            let summary = state
+                .token_revocation_service
+                .revoke_user(&user.id, &cli_actor(), None)
+                .await?;
+            writeln!(out, "Revoked {} refresh token(s) of user '{username}'", summary.refresh_tokens_revoked)?;
+            write_revocation_summary(&mut out, &summary)?;
+        }
+        AdminCommand::RevokeClientTokens { client_id } => {
+            let summary = state
+                .token_revocation_service
+                .revoke_client(&client_id, &cli_actor(), None)
+                .await?;
+            writeln!(out, "Revoked {} refresh token(s) of client '{client_id}'", summary.refresh_tokens_revoked)?;
+            write_revocation_summary(&mut out, &summary)?;
+        }
+        AdminCommand::ExportRbac { format, output } => {
+            let document = state.rbac_definition_service.export_definitions().await?;
+            let format = format
+                .or_else(|| output.as_deref().map(RbacFormat::from_path))
+                .unwrap_or(RbacFormat::Json);
+            let content = match format {
+                RbacFormat::Json => serde_json::to_string_pretty(&document)? + "\n",
+                RbacFormat::Yaml => serde_yaml::to_string(&document)?,
+            };
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, content).map_err(|e| {
+                        anyhow::anyhow!("Failed to write '{}': {e}", path.display())
+                    })?;
+                    writeln!(
+                        out,
+                        "Exported {} permission(s), {} role(s), {} scope(s) and {} client(s) to {}",
+                        document.permissions.len(),
+                        document.roles.len(),
+                        document.scopes.len(),
+                        document.clients.len(),
+                        path.display()
+                    )?;
+                }
+                None => out.push_str(&content),
+            }
+        }
+        AdminCommand::ImportRbac {
+            path,
+            dry_run,
+            prune,
+        } => {
+            let content = std::fs::read_to_string(&path)
+                .map_err(|e| anyhow::anyhow!("Failed to read '{}': {e}", path.display()))?;
+            let document: RbacDocument = match RbacFormat::from_path(&path) {
+                RbacFormat::Json => serde_json::from_str(&content)?,
+                RbacFormat::Yaml => serde_yaml::from_str(&content)?,
+            };
+            let report = state
+                .rbac_definition_service
+                .import_definitions(&document, RbacImportOptions { dry_run, prune })
+                .await?;
+            for change in &report.changes {
+                writeln!(out, "{change}")?;
+            }
+            for (client_id, secret) in &report.client_secrets {
+                writeln!(out, "Client secret of '{client_id}' (shown once): {secret}")?;
+            }
+            let summary = format!(
+                "{} created, {} updated, {} deleted",
+                report.count(RbacChangeAction::Create),
+                report.count(RbacChangeAction::Update),
+                report.count(RbacChangeAction::Delete)
+            );
+            if dry_run {
+                writeln!(out, "Dry run of {}: {summary} (nothing written)", path.display())?;
+            } else {
+                writeln!(out, "Imported {}: {summary}", path.display())?;
+            }
+        }
+        AdminCommand::Purge => {
+            let report = state.maintenance_service.purge_expired_data().await?;
+            for (table, count) in &report.tables {
+                writeln!(out, "{table:<24} {count}")?;
+            }
+            writeln!(out, "Purged {} expired row(s)", report.total())?;
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::permission::PermissionType;
+    use crate::services::rbac_definition_service::{PermissionDefinition, RoleDefinition};
+    use crate::state::test_support::setup_state;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(AdminCommand::parse(&args("migrate up")).unwrap(), None);
+        assert_eq!(AdminCommand::parse(&[]).unwrap(), None);
+
+        assert_eq!(
+            AdminCommand::parse(&args("user create alice --role admin --role=viewer")).unwrap(),
+            Some(AdminCommand::CreateUser {
+                username: "alice".to_string(),
+                password: None,
+                display_name: None,
+                roles: vec!["admin".to_string(), "viewer".to_string()],
+            })
+        );
+        assert_eq!(
+            AdminCommand::parse(&args("tokens revoke --client web")).unwrap(),
+            Some(AdminCommand::RevokeClientTokens {
+                client_id: "web".to_string()
+            })
+        );
+        assert_eq!(
+            AdminCommand::parse(&args(
+                "client create hr-scim --grant-type client_credentials --permission scim:provision"
+            ))
+            .unwrap(),
+            Some(AdminCommand::CreateClient {
+                name: "hr-scim".to_string(),
+                client_type: "CONFIDENTIAL".to_string(),
+                redirect_uris: Vec::new(),
+                grant_types: vec!["client_credentials".to_string()],
+                scopes: Vec::new(),
+                permissions: vec!["scim:provision".to_string()],
+            })
+        );
+        assert_eq!(
+            AdminCommand::parse(&args("rbac export --output rbac.yaml")).unwrap(),
+            Some(AdminCommand::ExportRbac {
+                format: None,
+                output: Some(PathBuf::from("rbac.yaml")),
+            })
+        );
+        assert_eq!(
+            AdminCommand::parse(&args("rbac import rbac.yaml --dry-run --prune")).unwrap(),
+            Some(AdminCommand::ImportRbac {
+                path: PathBuf::from("rbac.yaml"),
+                dry_run: true,
+                prune: true,
+            })
+        );
+
+        assert!(AdminCommand::parse(&args("user lock alice --minutes 0")).is_err());
+        assert!(AdminCommand::parse(&args("user create")).is_err());
+        assert!(AdminCommand::parse(&args("user create alice --bogus x")).is_err());
+        assert!(AdminCommand::parse(&args("tokens revoke --user a --client b")).is_err());
+        assert!(AdminCommand::parse(&args("client delete x")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_user_lifecycle() {
+        let (state, _pool) = setup_state().await;
+        let run_line = |line: &str| {
+            let command = AdminCommand::parse(&args(line)).unwrap().unwrap();
+            run(&state, command)
+        };
+
+        let output = run_line("user create alice --role user").await.unwrap();
+        let password = output
+            .lines()
+            .find_map(|line| line.strip_prefix("password: "))
+            .unwrap()
+            .to_string();
+        let user = state.user_service.authenticate("alice", &password).await.unwrap();
+        assert!(user.must_change_password);
+        let roles = state.role_service.get_user_roles(&user.id).await.unwrap();
+        assert_eq!(roles.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), ["user"]);
+
+        run_line("user lock alice").await.unwrap();
+        assert!(state.user_service.authenticate("alice", &password).await.is_err());
+        run_line("user unlock alice").await.unwrap();
+        run_line("user reset-password alice --password n3w-Passw0rd").await.unwrap();
+        assert!(state.user_service.authenticate("alice", "n3w-Passw0rd").await.is_ok());
+
+        run_line("role remove alice user").await.unwrap();
+        assert!(state.role_service.get_user_roles(&user.id).await.unwrap().is_empty());
+        assert!(run_line("role assign alice no-such-role").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_client_tokens_and_purge() {
+        let (state, pool) = setup_state().await;
+
+        let output = run(
+            &state,
+            AdminCommand::parse(&args(
+                "client create cli-app --redirect-uri http://localhost/cb --scope openid",
+            ))
+            .unwrap()
+            .unwrap(),
+        )
+        .await
+        .unwrap();
+        let client_id = output
+            .lines()
+            .find_map(|line| line.strip_prefix("client_id: "))
+            .unwrap()
+            .to_string();
+        assert!(output.contains("client_secret: "));
+        let client = state.client_service.find_by_client_id(&client_id).await.unwrap().unwrap();
+
+        // 直接写入一个有效和一个已过期的刷新令牌
+        let user = state
+            .user_service
+            .create_user("bob".to_string(), "password123".to_string(), None)
+            .await
+            .unwrap();
+        for (id, expires_at) in [
+            ("rt-active", Utc::now() + Duration::hours(1)),
+            ("rt-expired", Utc::now() - Duration::hours(1)),
+        ] {
+            sqlx::query(
+                "INSERT INTO refresh_tokens (id, token_hash, jti, user_id, client_id, scope, expires_at)
+                 VALUES (?, ?, ?, ?, ?, 'openid', ?)",
+            )
+            .bind(id)
+            .bind(id)
+            .bind(id)
+            .bind(&user.id)
+            .bind(&client.client.id)
+            .bind(expires_at)
+            .execute(&*pool)
+            .await
+            .unwrap();
+        }
+
+        let output = run(
+            &state,
+            AdminCommand::RevokeClientTokens {
+                client_id: client_id.clone(),
+            },
+        )
+        .await
+        .unwrap();
+        assert!(output.starts_with("Revoked 1 refresh token(s)"));
+        assert!(output.contains("Ended 0 session(s)"));
+        assert!(state.token_service.is_token_revoked("rt-active").await.unwrap());
+
+        let output = run(&state, AdminCommand::Purge).await.unwrap();
+        assert!(output.contains("refresh_tokens"));
+        let remaining: Vec<String> = sqlx::query_scalar("SELECT id FROM refresh_tokens")
+            .fetch_all(&*pool)
+            .await
+            .unwrap();
+        assert_eq!(remaining, ["rt-active"]);
+    }
+
+    #[tokio::test]
+    async fn test_rbac_export_import_round_trip() {
+        let (source, _) = setup_state().await;
+        let (target, _) = setup_state().await;
+
+        let auditor = source
+            .role_service
+            .create_role("auditor".to_string(), Some("Reads audit logs".to_string()))
+            .await
+            .unwrap();
+        let parent = source.role_service.find_role_by_name("user").await.unwrap().unwrap();
+        source
+            .role_service
+            .set_parent_roles(&auditor.id, vec![parent.id])
+            .await
+            .unwrap();
+        source
+            .client_service
+            .create_client(CreateClientRequest {
+                name: "Reporting".to_string(),
+                client_type: "CONFIDENTIAL".to_string(),
+                redirect_uris: vec![],
+                grant_types: vec!["client_credentials".to_string()],
+                response_types: vec![],
+                allowed_scopes: vec![],
+                client_permissions: Some(vec!["users:read".to_string()]),
+                access_token_ttl: None,
+                refresh_token_ttl: None,
+                tenant_id: None,
+            })
+            .await
+            .unwrap();
+        let exported = source.rbac_definition_service.export_definitions().await.unwrap();
+        assert!(exported
+            .roles
+            .iter()
+            .any(|r| r.name == "auditor" && r.parents == ["user"]));
+
+        let dir = std::env::temp_dir().join(format!("rbac-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("rbac.yaml");
+        run(
+            &source,
+            AdminCommand::ExportRbac {
+                format: None,
+                output: Some(path.clone()),
+            },
+        )
+        .await
+        .unwrap();
+
+        let client_id = exported
+            .clients
+            .iter()
+            .find(|c| c.name == "Reporting")
+            .map(|c| c.client_id.clone())
+            .unwrap();
+
+        // 试运行只输出差异
+        let before = target.rbac_definition_service.export_definitions().await.unwrap();
+        let output = run(
+            &target,
+            AdminCommand::ImportRbac {
+                path: path.clone(),
+                dry_run: true,
+                prune: false,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(output.contains("+ role auditor\n    + parent user"));
+        assert!(output.contains(&format!("+ client {client_id}")));
+        assert!(output.contains("(nothing written)"));
+        assert_eq!(
+            target.rbac_definition_service.export_definitions().await.unwrap(),
+            before
+        );
+
+        let output = run(
+            &target,
+            AdminCommand::ImportRbac {
+                path: path.clone(),
+                dry_run: false,
+                prune: false,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(output.contains(&format!("Client secret of '{client_id}'")));
+        assert_eq!(
+            target.rbac_definition_service.export_definitions().await.unwrap(),
+            exported
+        );
+
+        // 重复导入不产生任何变化
+        let report = target
+            .rbac_definition_service
+            .import_definitions(&exported, RbacImportOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(report, Default::default());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rbac_import_prune() {
+        let (state, pool) = setup_state().await;
+        let mut document = state.rbac_definition_service.export_definitions().await.unwrap();
+
+        let auditor = state
+            .role_service
+            .create_role("auditor".to_string(), None)
+            .await
+            .unwrap();
+        let user_role = document.roles.iter_mut().find(|r| r.name == "user").unwrap();
+        let dropped = user_role.permissions.pop().unwrap();
+
+        // 不清理时只新增，未声明的角色和关联保持不变
+        let report = state
+            .rbac_definition_service
+            .import_definitions(&document, RbacImportOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(report, Default::default());
+
+        let options = RbacImportOptions {
+            dry_run: true,
+            prune: true,
+        };
+        let planned = state
+            .rbac_definition_service
+            .import_definitions(&document, options)
+            .await
+            .unwrap();
+        assert!(planned.dry_run);
+        assert!(state.role_service.find_role_by_id(&auditor.id).await.unwrap().is_some());
+
+        let report = state
+            .rbac_definition_service
+            .import_definitions(&document, RbacImportOptions { dry_run: false, prune: true })
+            .await
+            .unwrap();
+        assert_eq!(report.changes, planned.changes);
+        assert!(report
+            .changes
+            .iter()
+            .any(|c| c.name == "user" && c.details == [format!("- permission {dropped}")]));
+        assert!(report
+            .changes
+            .iter()
+            .any(|c| c.name == "auditor" && c.action == RbacChangeAction::Delete));
+        assert!(state.role_service.find_role_by_id(&auditor.id).await.unwrap().is_none());
+        assert_eq!(state.rbac_definition_service.export_definitions().await.unwrap(), document);
+
+        let logged: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM audit_logs WHERE action LIKE 'rbac.%'")
+                .fetch_one(&*pool)
+                .await
+                .unwrap();
+        assert_eq!(logged as usize, report.changes.len());
+    }
+
+    #[tokio::test]
+    async fn test_rbac_import_failure_rolls_back() {
+        let (state, pool) = setup_state().await;
+        let before = state.rbac_definition_service.export_definitions().await.unwrap();
+
+        // 两个新角色互相继承: 写入第二条继承关系时才发现环路
+        let mut document = before.clone();
+        document.permissions.push(PermissionDefinition {
+            name: "reports:read".to_string(),
+            description: None,
+            r#type: PermissionType::API,
+        });
+        for (name, parent) in [("analyst", "reviewer"), ("reviewer", "analyst")] {
+            document.roles.push(RoleDefinition {
+                name: name.to_string(),
+                description: None,
+                tenant_id: None,
+                permissions: vec!["reports:read".to_string()],
+                parents: vec![parent.to_string()],
+            });
+        }
+
+        let result = state
+            .rbac_definition_service
+            .import_definitions(&document, RbacImportOptions::default())
+            .await;
+        assert!(matches!(result, Err(ServiceError::ValidationError(_))));
+        assert_eq!(state.rbac_definition_service.export_definitions().await.unwrap(), before);
+
+        let logged: Vec<(String, String)> = sqlx::query_as(
+            "SELECT action, status FROM audit_logs WHERE action LIKE 'rbac.%'",
+        )
+        .fetch_all(&*pool)
+        .await
+        .unwrap();
+        assert_eq!(logged, [("rbac.import".to_string(), "failure".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_rbac_tenant_roles_round_trip() {
+        let (source, _) = setup_state().await;
+        let (target, _) = setup_state().await;
+        source
+            .tenant_service
+            .create_tenant("acme".to_string(), "Acme".to_string())
+            .await
+            .unwrap();
+        source
+            .role_service
+            .create_role_in_tenant("acme-auditor".to_string(), None, "acme")
+            .await
+            .unwrap();
+        let exported = source.rbac_definition_service.export_definitions().await.unwrap();
+        assert!(exported
+            .roles
+            .iter()
+            .any(|r| r.name == "acme-auditor" && r.tenant_id.as_deref() == Some("acme")));
+
+        // 目标环境中租户不存在时拒绝导入
+        assert!(target
+            .rbac_definition_service
+            .import_definitions(&exported, RbacImportOptions::default())
+            .await
+            .is_err());
+        target
+            .tenant_service
+            .create_tenant("acme".to_string(), "Acme".to_string())
+            .await
+            .unwrap();
+        target
+            .rbac_definition_service
+            .import_definitions(&exported, RbacImportOptions::default())
+            .await
+            .unwrap();
+        let role = target
+            .role_service
+            .find_role_by_name("acme-auditor")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(role.tenant_id.as_deref(), Some("acme"));
+
+        // 已有角色的租户不能通过导入修改
+        let mut moved = exported.clone();
+        let role = moved.roles.iter_mut().find(|r| r.name == "acme-auditor").unwrap();
+        role.tenant_id = None;
+        let result = target
+            .rbac_definition_service
+            .import_definitions(&moved, RbacImportOptions::default())
+            .await;
+        assert!(matches!(result, Err(ServiceError::ValidationError(_))));
+
+        // 全局角色不能继承租户角色
+        let mut inheriting = exported;
+        let user_role = inheriting.roles.iter_mut().find(|r| r.name == "user").unwrap();
+        user_role.parents.push("acme-auditor".to_string());
+        let result = target
+            .rbac_definition_service
+            .import_definitions(&inheriting, RbacImportOptions::default())
+            .await;
+        assert!(matches!(result, Err(ServiceError::ValidationError(_))));
+    }
+}
@@ -1,16 +1,64 @@
-use serde::Deserialize;
+//! 服务配置
+//!
+//! 配置按以下顺序分层加载，后面的层覆盖前面的层:
+//! 1. 内置默认值
+//! 2. 配置文件 (TOML 或 YAML，由 `--config`、`OAUTH_CONFIG` 指定，或当前目录下的 `oauth-service.toml`)
+//! 3. 环境变量 (包括 `./.env`)：兼容原有的 `DATABASE_URL`、`JWT_*`、`ISSUER`、`COOKIE_DOMAIN` 等变量，
+//!    任意配置项都可以用 `OAUTH__<SECTION>__<KEY>` 覆盖 (如 `OAUTH__SERVER__LISTEN_ADDR`)
+//! 4. 命令行参数 (`--listen`、`--database-url`、`--set <key>=<value>`)
+//!
+//! 令牌有效期、限流和 CORS 配置只作为 `system_configurations` 表的初始值，
+//! 之后以数据库中的运行时配置为准。
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use jsonwebtoken::{EncodingKey, DecodingKey};
 use crate::error::ServiceError;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+
+/// 未通过参数或环境变量指定时，自动加载的配置文件名
+pub const DEFAULT_CONFIG_FILE: &str = "oauth-service.toml";
+
+/// 通用环境变量覆盖的前缀，`OAUTH__SERVER__LISTEN_ADDR` 对应 `server.listen_addr`
+const ENV_OVERRIDE_PREFIX: &str = "OAUTH__";
+
+/// 打印配置时替换敏感内容的掩码
+const REDACTED: &str = "******";
+
+/// 命令行参数说明
+pub const CONFIG_USAGE: &str = "\
+Options:
+  --config <path>          Load configuration from a TOML or YAML file
+  --listen <addr>          Listen address (server.listen_addr)
+  --database-url <url>     Database URL (database_url)
+  --set <key>=<value>      Override any configuration key, e.g. --set cookie.secure=true
+  --print-config           Print the effective configuration with secrets redacted and exit";
+
+/// 兼容原有部署方式 (以及 OpenTelemetry 标准) 的环境变量及其对应的配置项
+const LEGACY_ENV_VARS: [(&str, &str); 10] = [
+    ("DATABASE_URL", "database_url"),
+    ("JWT_PRIVATE_KEY_PATH", "jwt_private_key_path"),
+    ("JWT_PUBLIC_KEY_PATH", "jwt_public_key_path"),
+    ("ISSUER", "issuer"),
+    ("JWT_ALGORITHM", "jwt_algorithm"),
+    ("COOKIE_DOMAIN", "cookie.domain"),
+    ("NEXT_PUBLIC_ADMIN_PORTAL_URL", "admin_portal.url"),
+    ("ADMIN_PORTAL_URL", "admin_portal.proxy_url"),
+    ("OTEL_EXPORTER_OTLP_ENDPOINT", "tracing.otlp_endpoint"),
+    ("OTEL_SERVICE_NAME", "tracing.service_name"),
+];
 
 /// JWT签名算法配置
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 #[derive(Default)]
 pub enum JwtAlgorithm {
     /// HMAC with SHA-256 (使用共享密钥)
     #[default]
+    #[serde(alias = "hs256")]
     HS256,
     /// RSA with SHA-256 (使用公钥/私钥对)
+    #[serde(alias = "rs256")]
     RS256,
 }
 
@@ -24,15 +72,436 @@ impl JwtAlgorithm {
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Config {
     pub database_url: String,
     pub jwt_private_key_path: String,
     pub jwt_public_key_path: String,
     pub issuer: String,
     /// JWT签名算法，默认为HS256。可配置为RS256用于生产环境
-    #[serde(default)]
     pub jwt_algorithm: JwtAlgorithm,
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub cookie: CookieConfig,
+    pub tokens: TokenConfig,
+    pub rate_limits: RateLimitConfig,
+    pub cors: CorsConfig,
+    pub login: LoginConfig,
+    pub admin_portal: AdminPortalConfig,
+    pub metrics: MetricsConfig,
+    pub tracing: TracingConfig,
+    pub maintenance: MaintenanceConfig,
+    pub federation: FederationConfig,
+    pub webauthn: WebAuthnConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            database_url: "sqlite:oauth.db?mode=rwc".to_string(),
+            jwt_private_key_path: String::new(),
+            jwt_public_key_path: String::new(),
+            issuer: "http://127.0.0.1:3001".to_string(),
+            jwt_algorithm: JwtAlgorithm::default(),
+            server: ServerConfig::default(),
+            database: DatabaseConfig::default(),
+            cookie: CookieConfig::default(),
+            tokens: TokenConfig::default(),
+            rate_limits: RateLimitConfig::default(),
+            cors: CorsConfig::default(),
+            login: LoginConfig::default(),
+            admin_portal: AdminPortalConfig::default(),
+            metrics: MetricsConfig::default(),
+            tracing: TracingConfig::default(),
+            maintenance: MaintenanceConfig::default(),
+            federation: FederationConfig::default(),
+            webauthn: WebAuthnConfig::default(),
+        }
+    }
+}
+
+/// HTTP 服务监听配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// 监听地址
+    pub listen_addr: SocketAddr,
+    /// 配置后使用 HTTPS 提供服务
+    pub tls: Option<TlsConfig>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: SocketAddr::from(([127, 0, 0, 1], 3001)),
+            tls: None,
+        }
+    }
+}
+
+/// TLS 证书配置 (PEM 格式)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// 证书链文件
+    pub cert_path: PathBuf,
+    /// 私钥文件 (PKCS#8 或 RSA)
+    pub key_path: PathBuf,
+}
+
+/// 数据库连接池配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// 连接池最大连接数
+    pub max_connections: u32,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self { max_connections: 10 }
+    }
+}
+
+/// 会话 Cookie 配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CookieConfig {
+    /// Cookie 的 domain 属性，本地开发使用 .localhost，生产环境使用实际域名
+    pub domain: String,
+    /// 是否只通过 HTTPS 发送 (生产环境必须开启)
+    pub secure: bool,
+}
+
+impl Default for CookieConfig {
+    fn default() -> Self {
+        Self {
+            domain: ".localhost".to_string(),
+            secure: false,
+        }
+    }
+}
+
+/// 新建客户端默认的令牌有效期（秒）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TokenConfig {
+    pub access_token_ttl: i64,
+    pub refresh_token_ttl: i64,
+}
+
+impl Default for TokenConfig {
+    fn default() -> Self {
+        Self {
+            access_token_ttl: 3600,
+            refresh_token_ttl: 2_592_000,
+        }
+    }
+}
+
+/// 一个限流器的参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    /// 时间窗口内允许的最大请求数
+    pub max_requests: usize,
+    /// 时间窗口（秒）
+    pub window_secs: u64,
+}
+
+/// 按 IP 限流的配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// 全局 API 限流
+    pub api: RateLimit,
+    /// 令牌端点限流
+    pub token: RateLimit,
+    /// 登录端点限流
+    pub login: RateLimit,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            api: RateLimit {
+                max_requests: 100,
+                window_secs: 60,
+            },
+            token: RateLimit {
+                max_requests: 20,
+                window_secs: 60,
+            },
+            login: RateLimit {
+                max_requests: 5,
+                window_secs: 300,
+            },
+        }
+    }
+}
+
+/// 跨域配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// 允许跨域访问的源
+    pub allowed_origins: Vec<String>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        let portal = AdminPortalConfig::default();
+        Self {
+            allowed_origins: vec![portal.url, portal.proxy_url],
+        }
+    }
+}
+
+/// 登录配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoginConfig {
+    /// 登录成功后允许重定向到的源
+    pub redirect_origins: Vec<String>,
+}
+
+impl Default for LoginConfig {
+    fn default() -> Self {
+        Self {
+            redirect_origins: vec![
+                "http://localhost:3002".to_string(),
+                "http://localhost:3001".to_string(),
+                "http://127.0.0.1:3002".to_string(),
+                "http://127.0.0.1:3001".to_string(),
+            ],
+        }
+    }
+}
+
+/// Admin Portal 地址
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminPortalConfig {
+    /// 登录和同意页面所在的前端地址
+    pub url: String,
+    /// 经 Pingora 代理访问的地址
+    pub proxy_url: String,
+}
+
+impl Default for AdminPortalConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:3002".to_string(),
+            proxy_url: "http://localhost:6188".to_string(),
+        }
+    }
+}
+
+/// Prometheus 指标端点 (`/metrics`) 配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    /// 是否提供 `/metrics`
+    pub enabled: bool,
+    /// 设置后 `/metrics` 只在这个独立的地址上提供 (纯 HTTP)，不再挂在主服务上
+    pub listen_addr: Option<SocketAddr>,
+    /// 允许抓取指标的客户端 IP (按连接地址判断，不信任转发头)，为空表示不限制
+    pub allowed_ips: Vec<IpAddr>,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            listen_addr: None,
+            allowed_ips: vec![IpAddr::V4(Ipv4Addr::LOCALHOST), IpAddr::V6(Ipv6Addr::LOCALHOST)],
+        }
+    }
+}
+
+/// 分布式追踪 (OpenTelemetry) 配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TracingConfig {
+    /// OTLP/HTTP 收集器地址 (如 `http://otel-collector:4318`)，未设置时不导出 span
+    pub otlp_endpoint: Option<String>,
+    /// 上报的 `service.name`
+    pub service_name: String,
+    /// 没有上游追踪上下文时新 trace 的采样比例 (0.0 - 1.0)
+    pub sample_ratio: f64,
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            service_name: "oauth-service".to_string(),
+            sample_ratio: 1.0,
+        }
+    }
+}
+
+/// 后台数据维护任务配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MaintenanceConfig {
+    /// 是否定期执行维护任务 (关闭后仍可通过管理 API 手动触发)
+    pub enabled: bool,
+    /// 执行间隔 (秒)
+    pub interval_secs: u64,
+    /// 每批删除的过期记录数，避免长时间持有写锁
+    pub batch_size: u32,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 3600,
+            batch_size: 500,
+        }
+    }
+}
+
+/// 联合登录配置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FederationConfig {
+    /// 上游 OIDC 身份提供方，登录入口为 `/api/v2/auth/federated/<id>/login`
+    pub providers: Vec<OidcProviderConfig>,
+}
+
+/// 一个上游 OIDC 身份提供方 (如企业 IdP)
+///
+/// 需要在 IdP 中登记回调地址 `<issuer>/api/v2/auth/federated/<id>/callback`。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OidcProviderConfig {
+    /// 提供方标识，出现在登录和回调地址中
+    pub id: String,
+    /// 登录页面上显示的名称
+    pub display_name: String,
+    /// IdP 的 issuer，元数据从 `<issuer>/.well-known/openid-configuration` 获取
+    pub issuer: String,
+    pub client_id: String,
+    /// 机密客户端的密钥，公共客户端 (只使用 PKCE) 时留空
+    pub client_secret: Option<String>,
+    /// 请求的 scope，必须包含 openid
+    pub scopes: Vec<String>,
+    /// 首次登录时用于关联已有用户的声明: `email` 对应用户邮箱，其他声明对应用户名
+    pub link_claim: String,
+    /// 只有 `<link_claim>_verified` (如 email_verified) 为 true 时才按声明关联已有用户
+    pub link_requires_verified: bool,
+    /// 没有可关联的用户时自动创建用户
+    pub jit_provisioning: bool,
+    /// 包含上游组的声明
+    pub groups_claim: String,
+    /// 上游组 -> 本地角色名；登录时按映射同步由该提供方分配的角色
+    pub role_mappings: std::collections::BTreeMap<String, Vec<String>>,
+}
+
+impl Default for OidcProviderConfig {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            display_name: String::new(),
+            issuer: String::new(),
+            client_id: String::new(),
+            client_secret: None,
+            scopes: vec!["openid".to_string(), "profile".to_string(), "email".to_string()],
+            link_claim: "email".to_string(),
+            link_requires_verified: true,
+            jit_provisioning: true,
+            groups_claim: "groups".to_string(),
+            role_mappings: Default::default(),
+        }
+    }
+}
+
+/// 通行密钥 (WebAuthn) 配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebAuthnConfig {
+    /// 依赖方 ID (RP ID)，通行密钥与其绑定；未设置时使用 issuer 的主机名
+    pub rp_id: Option<String>,
+    /// 认证器中显示的依赖方名称
+    pub rp_name: String,
+    /// 允许发起仪式的页面源，为空时使用 issuer 和 admin_portal 的地址
+    pub origins: Vec<String>,
+}
+
+impl Default for WebAuthnConfig {
+    fn default() -> Self {
+        Self {
+            rp_id: None,
+            rp_name: "OAuth 授权系统".to_string(),
+            origins: Vec::new(),
+        }
+    }
+}
+
+/// 命令行中与配置相关的参数
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ConfigArgs {
+    /// 配置文件路径
+    pub config_file: Option<PathBuf>,
+    /// `key=value` 形式的覆盖项，按出现顺序应用
+    pub overrides: Vec<(String, String)>,
+    /// 打印生效的配置后退出
+    pub print_config: bool,
+    /// 其余参数 (子命令)
+    pub remaining: Vec<String>,
+}
+
+impl ConfigArgs {
+    /// 解析命令行参数，未识别的参数原样保留给子命令
+    pub fn parse(args: &[String]) -> Result<Self, anyhow::Error> {
+        let mut parsed = Self::default();
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = |name: &str| {
+                inline_value
+                    .clone()
+                    .or_else(|| iter.next().cloned())
+                    .ok_or_else(|| anyhow::anyhow!("Missing value for {name}\n{CONFIG_USAGE}"))
+            };
+
+            match flag {
+                "--config" => parsed.config_file = Some(PathBuf::from(value("--config")?)),
+                "--listen" => parsed
+                    .overrides
+                    .push(("server.listen_addr".to_string(), value("--listen")?)),
+                "--database-url" => parsed
+                    .overrides
+                    .push(("database_url".to_string(), value("--database-url")?)),
+                "--set" => {
+                    let assignment = value("--set")?;
+                    let (key, value) = assignment.split_once('=').ok_or_else(|| {
+                        anyhow::anyhow!("Invalid --set '{assignment}', expected <key>=<value>")
+                    })?;
+                    parsed.overrides.push((key.trim().to_string(), value.to_string()));
+                }
+                "--print-config" => parsed.print_config = true,
+                _ => parsed.remaining.push(arg.clone()),
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+/// 租户的 issuer (`<issuer>/tenants/<id>`)，`tenant_id` 为空时即为 `issuer`
+pub fn issuer_for_tenant(issuer: &str, tenant_id: Option<&str>) -> String {
+    match tenant_id {
+        Some(tenant_id) => format!("{}/tenants/{tenant_id}", issuer.trim_end_matches('/')),
+        None => issuer.to_string(),
+    }
 }
 
 impl Config {
@@ -111,55 +580,551 @@ impl Config {
             }
         }
     }
-}
 
-impl Config {
-    /// Loads configuration.
-    ///
-    /// This function specifically reads the DATABASE_URL from the local .env file
-    /// to bypass any shell environment variables that may be set.
-    /// Other variables are read from the environment as usual.
+    /// 按 默认值 -> 配置文件 -> 环境变量 -> 命令行参数 的顺序加载并校验配置
+    pub fn load(args: &ConfigArgs) -> Result<Self, anyhow::Error> {
+        // 加载 ./.env 中的变量 (不覆盖已有的环境变量)
+        dotenvy::dotenv().ok();
+
+        let mut tree = serde_json::to_value(Self::default())?;
+
+        let config_file = args
+            .config_file
+            .clone()
+            .or_else(|| std::env::var_os("OAUTH_CONFIG").map(PathBuf::from))
+            .or_else(|| {
+                let default_file = PathBuf::from(DEFAULT_CONFIG_FILE);
+                default_file.exists().then_some(default_file)
+            });
+        if let Some(path) = &config_file {
+            merge(&mut tree, read_config_file(path)?);
+        }
+
+        for (key, value) in env_overrides(std::env::vars()) {
+            set_path(&mut tree, &key, &value)?;
+        }
+        for (key, value) in &args.overrides {
+            set_path(&mut tree, key, value)?;
+        }
+
+        let config: Self = serde_json::from_value(tree)
+            .map_err(|e| anyhow::anyhow!("Invalid configuration: {e}"))?;
+
+        let errors = config.validate();
+        if !errors.is_empty() {
+            anyhow::bail!("Invalid configuration:\n  - {}", errors.join("\n  - "));
+        }
+
+        Ok(config)
+    }
+
+    /// 兼容原有调用方式，仅使用默认值、配置文件和环境变量
     pub fn from_env() -> Result<Self, anyhow::Error> {
-        // Read the content of the local .env file
-        let env_content = std::fs::read_to_string("./.env")
-            .map_err(|e| anyhow::anyhow!("Failed to read .env file: {e}"))?;
+        Self::load(&ConfigArgs::default())
+    }
 
-        // Manually parse the content to get the DATABASE_URL
-        let env_map = dotenv_parser::parse_dotenv(&env_content)
-            .map_err(|e| anyhow::anyhow!("Failed to parse .env content: {e}"))?;
+    /// 租户的 issuer (`<issuer>/tenants/<id>`)，平台级令牌使用全局 issuer
+    pub fn tenant_issuer(&self, tenant_id: Option<&str>) -> String {
+        issuer_for_tenant(&self.issuer, tenant_id)
+    }
 
-        let database_url = env_map
-            .get("DATABASE_URL")
-            .cloned()
-            .ok_or_else(|| anyhow::anyhow!("DATABASE_URL must be set in .env file"))?;
+    /// 实际使用的 WebAuthn RP ID
+    pub fn webauthn_rp_id(&self) -> String {
+        self.webauthn.rp_id.clone().unwrap_or_else(|| {
+            url::Url::parse(&self.issuer)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string))
+                .unwrap_or_default()
+        })
+    }
 
-        // Load other variables from the environment (which might be set by a .env file higher up or by the shell)
-        dotenvy::dotenv().ok();
+    /// 实际允许的 WebAuthn 页面源
+    pub fn webauthn_origins(&self) -> Vec<String> {
+        if !self.webauthn.origins.is_empty() {
+            return self.webauthn.origins.clone();
+        }
+        let mut origins = Vec::new();
+        for url in [&self.issuer, &self.admin_portal.url, &self.admin_portal.proxy_url] {
+            if let Ok(url) = url::Url::parse(url) {
+                let origin = url.origin().ascii_serialization();
+                if !origins.contains(&origin) {
+                    origins.push(origin);
+                }
+            }
+        }
+        origins
+    }
+
+    /// 校验配置，返回所有错误
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if !self.database_url.starts_with("sqlite:") {
+            errors.push("database_url: only sqlite: URLs are supported".to_string());
+        }
+        if self.database.max_connections == 0 {
+            errors.push("database.max_connections must be at least 1".to_string());
+        }
+        if url::Url::parse(&self.issuer).is_err() {
+            errors.push(format!("issuer: '{}' is not a valid URL", self.issuer));
+        }
+
+        match self.jwt_algorithm {
+            JwtAlgorithm::RS256 => {
+                for (key, path) in [
+                    ("jwt_private_key_path", &self.jwt_private_key_path),
+                    ("jwt_public_key_path", &self.jwt_public_key_path),
+                ] {
+                    if path.is_empty() {
+                        errors.push(format!("{key} must be set for RS256"));
+                    } else if !Path::new(path).is_file() {
+                        errors.push(format!("{key}: file '{path}' does not exist"));
+                    }
+                }
+            }
+            JwtAlgorithm::HS256 => {
+                if self.jwt_private_key_path.is_empty() {
+                    if std::env::var("JWT_SECRET").is_err() {
+                        errors.push(
+                            "jwt_private_key_path or the JWT_SECRET environment variable must be set for HS256"
+                                .to_string(),
+                        );
+                    }
+                } else if !Path::new(&self.jwt_private_key_path).is_file() {
+                    errors.push(format!(
+                        "jwt_private_key_path: file '{}' does not exist",
+                        self.jwt_private_key_path
+                    ));
+                }
+            }
+        }
+
+        if let Some(tls) = &self.server.tls {
+            for (key, path) in [("cert_path", &tls.cert_path), ("key_path", &tls.key_path)] {
+                if !path.is_file() {
+                    errors.push(format!("server.tls.{key}: file '{}' does not exist", path.display()));
+                }
+            }
+        }
+
+        if self.metrics.listen_addr == Some(self.server.listen_addr) {
+            errors.push("metrics.listen_addr must differ from server.listen_addr".to_string());
+        }
+
+        if let Some(endpoint) = &self.tracing.otlp_endpoint {
+            let scheme = url::Url::parse(endpoint).map(|url| url.scheme().to_string());
+            if !matches!(scheme.as_deref(), Ok("http") | Ok("https")) {
+                errors.push(format!("tracing.otlp_endpoint: '{endpoint}' is not a valid http(s) URL"));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.tracing.sample_ratio) {
+            errors.push("tracing.sample_ratio must be between 0.0 and 1.0".to_string());
+        }
+
+        if self.maintenance.interval_secs == 0 {
+            errors.push("maintenance.interval_secs must be at least 1".to_string());
+        }
+        if self.maintenance.batch_size == 0 {
+            errors.push("maintenance.batch_size must be at least 1".to_string());
+        }
+
+        let mut provider_ids = std::collections::HashSet::new();
+        for (i, provider) in self.federation.providers.iter().enumerate() {
+            let key = format!("federation.providers[{i}]");
+            let valid_id = !provider.id.is_empty()
+                && provider
+                    .id
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_');
+            if !valid_id {
+                errors.push(format!("{key}.id must consist of lowercase letters, digits, '-' or '_'"));
+            } else if !provider_ids.insert(provider.id.as_str()) {
+                errors.push(format!("{key}.id: duplicate provider '{}'", provider.id));
+            }
+            let issuer_valid = url::Url::parse(&provider.issuer)
+                .map(|url| matches!(url.scheme(), "http" | "https"))
+                .unwrap_or(false);
+            if !issuer_valid {
+                errors.push(format!("{key}.issuer: '{}' is not a valid http(s) URL", provider.issuer));
+            }
+            if provider.client_id.is_empty() {
+                errors.push(format!("{key}.client_id must not be empty"));
+            }
+            if !provider.scopes.iter().any(|scope| scope == "openid") {
+                errors.push(format!("{key}.scopes must include openid"));
+            }
+        }
+
+        let rp_id = self.webauthn_rp_id();
+        let valid_rp_id = !rp_id.is_empty()
+            && rp_id
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'.');
+        if !valid_rp_id {
+            errors.push(format!("webauthn.rp_id: '{rp_id}' is not a valid domain"));
+        }
+        for origin in &self.webauthn.origins {
+            // 页面源的主机名必须等于 RP ID 或是其子域名
+            let host = parse_origin(origin)
+                .and_then(|origin| url::Url::parse(&origin).ok())
+                .and_then(|url| url.host_str().map(str::to_string));
+            match host {
+                Some(host) if host == rp_id || host.ends_with(&format!(".{rp_id}")) => {}
+                Some(_) => errors.push(format!(
+                    "webauthn.origins: '{origin}' is not within the RP ID '{rp_id}'"
+                )),
+                None => errors.push(format!("webauthn.origins: '{origin}' is not a valid http(s) origin")),
+            }
+        }
+
+        if self.cookie.domain.is_empty() {
+            errors.push("cookie.domain must not be empty".to_string());
+        }
+
+        for (key, ttl) in [
+            ("tokens.access_token_ttl", self.tokens.access_token_ttl),
+            ("tokens.refresh_token_ttl", self.tokens.refresh_token_ttl),
+        ] {
+            if ttl <= 0 {
+                errors.push(format!("{key} must be positive"));
+            }
+        }
+
+        for (name, limit) in [
+            ("api", self.rate_limits.api),
+            ("token", self.rate_limits.token),
+            ("login", self.rate_limits.login),
+        ] {
+            if limit.max_requests == 0 || limit.window_secs == 0 {
+                errors.push(format!(
+                    "rate_limits.{name}: max_requests and window_secs must be positive"
+                ));
+            }
+        }
+
+        for (key, origins) in [
+            ("cors.allowed_origins", &self.cors.allowed_origins),
+            ("login.redirect_origins", &self.login.redirect_origins),
+        ] {
+            for origin in origins {
+                if parse_origin(origin).is_none() {
+                    errors.push(format!("{key}: '{origin}' is not a valid http(s) origin"));
+                }
+            }
+        }
+
+        for (key, url) in [
+            ("admin_portal.url", &self.admin_portal.url),
+            ("admin_portal.proxy_url", &self.admin_portal.proxy_url),
+        ] {
+            let valid = url::Url::parse(url)
+                .map(|parsed| matches!(parsed.scheme(), "http" | "https"))
+                .unwrap_or(false);
+            if !valid {
+                errors.push(format!("{key}: '{url}' is not a valid http(s) URL"));
+            }
+        }
 
-        let jwt_private_key_path = std::env::var("JWT_PRIVATE_KEY_PATH")
-            .map_err(|_| anyhow::anyhow!("JWT_PRIVATE_KEY_PATH must be set"))?;
-
-        let jwt_public_key_path = std::env::var("JWT_PUBLIC_KEY_PATH")
-            .map_err(|_| anyhow::anyhow!("JWT_PUBLIC_KEY_PATH must be set"))?;
-
-        let issuer =
-            std::env::var("ISSUER").unwrap_or_else(|_| "http://127.0.0.1:3001".to_string());
-
-        let jwt_algorithm = std::env::var("JWT_ALGORITHM")
-            .ok()
-            .and_then(|s| match s.to_uppercase().as_str() {
-                "RS256" => Some(JwtAlgorithm::RS256),
-                "HS256" => Some(JwtAlgorithm::HS256),
-                _ => None,
-            })
-            .unwrap_or_default();
-
-        Ok(Self {
-            database_url,
-            jwt_private_key_path,
-            jwt_public_key_path,
-            issuer,
-            jwt_algorithm,
+        errors
+    }
+
+    /// 以 TOML 格式输出配置，数据库连接串中的密码被替换为掩码
+    pub fn to_redacted_toml(&self) -> Result<String, anyhow::Error> {
+        let mut redacted = self.clone();
+        redacted.database_url = redact_url_password(&self.database_url);
+        for provider in &mut redacted.federation.providers {
+            if provider.client_secret.is_some() {
+                provider.client_secret = Some(REDACTED.to_string());
+            }
+        }
+        Ok(toml::to_string_pretty(&redacted)?)
+    }
+}
+
+/// 解析 http(s) 源 (如 "https://admin.example.com")，不允许包含路径
+pub(crate) fn parse_origin(origin: &str) -> Option<String> {
+    let url = url::Url::parse(origin).ok()?;
+    let serialized = url.origin().ascii_serialization();
+    (matches!(url.scheme(), "http" | "https") && serialized == origin.trim_end_matches('/'))
+        .then_some(serialized)
+}
+
+/// 读取配置文件，按扩展名选择 TOML 或 YAML 格式
+fn read_config_file(path: &Path) -> Result<Value, anyhow::Error> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read config file '{}': {e}", path.display()))?;
+    let parse_error =
+        |e: &dyn std::fmt::Display| anyhow::anyhow!("Failed to parse config file '{}': {e}", path.display());
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&content).map_err(|e| parse_error(&e)),
+        _ => toml::from_str(&content).map_err(|e| parse_error(&e)),
+    }
+}
+
+/// 从环境变量中收集配置覆盖项: 先应用兼容变量，再应用 `OAUTH__` 前缀的变量
+fn env_overrides(vars: impl Iterator<Item = (String, String)>) -> Vec<(String, String)> {
+    let vars: Vec<(String, String)> = vars.collect();
+    let mut overrides = Vec::new();
+
+    for (env_name, key) in LEGACY_ENV_VARS {
+        if let Some((_, value)) = vars.iter().find(|(name, _)| name == env_name) {
+            overrides.push((key.to_string(), value.clone()));
+        }
+    }
+    // 原有部署通过 NODE_ENV=production 开启 Secure Cookie
+    if vars.iter().any(|(name, value)| name == "NODE_ENV" && value == "production") {
+        overrides.push(("cookie.secure".to_string(), "true".to_string()));
+    }
+
+    let mut prefixed: Vec<(String, String)> = vars
+        .iter()
+        .filter_map(|(name, value)| {
+            let path = name.strip_prefix(ENV_OVERRIDE_PREFIX)?;
+            Some((path.to_ascii_lowercase().replace("__", "."), value.clone()))
         })
+        .collect();
+    prefixed.sort();
+    overrides.extend(prefixed);
+
+    overrides
+}
+
+/// 将配置文件的内容合并到配置树上
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// 按点分路径设置配置项
+///
+/// 原值为字符串的配置项直接使用原始文本，其他配置项按 JSON 解析 (数字、布尔、数组)，
+/// 解析失败时作为字符串。
+fn set_path(tree: &mut Value, key: &str, raw: &str) -> Result<(), anyhow::Error> {
+    let parsed = serde_json::from_str::<Value>(raw)
+        .ok()
+        .filter(|value| !value.is_string())
+        .unwrap_or_else(|| Value::String(raw.to_string()));
+
+    let mut node = tree;
+    let mut segments = key.split('.').peekable();
+    while let Some(segment) = segments.next() {
+        if segment.is_empty() {
+            anyhow::bail!("Invalid configuration key '{key}'");
+        }
+        if !node.is_object() {
+            *node = Value::Object(Default::default());
+        }
+        let object = node.as_object_mut().expect("node is an object");
+        if segments.peek().is_none() {
+            let value = match object.get(segment) {
+                Some(Value::String(_)) => Value::String(raw.to_string()),
+                _ => parsed,
+            };
+            object.insert(segment.to_string(), value);
+            return Ok(());
+        }
+        node = object
+            .entry(segment.to_string())
+            .or_insert_with(|| Value::Object(Default::default()));
+    }
+
+    Ok(())
+}
+
+/// 替换连接串中的密码
+fn redact_url_password(database_url: &str) -> String {
+    match url::Url::parse(database_url) {
+        Ok(mut url) if url.password().is_some() => {
+            let _ = url.set_password(Some(REDACTED));
+            url.to_string()
+        }
+        _ => database_url.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = ConfigArgs::parse(&args(&[
+            "--config",
+            "/etc/oauth.yaml",
+            "--listen=0.0.0.0:8443",
+            "--set",
+            "cookie.secure=true",
+            "--print-config",
+            "migrate",
+            "status",
+        ]))
+        .unwrap();
+
+        assert_eq!(parsed.config_file, Some(PathBuf::from("/etc/oauth.yaml")));
+        assert_eq!(
+            parsed.overrides,
+            vec![
+                ("server.listen_addr".to_string(), "0.0.0.0:8443".to_string()),
+                ("cookie.secure".to_string(), "true".to_string()),
+            ]
+        );
+        assert!(parsed.print_config);
+        assert_eq!(parsed.remaining, args(&["migrate", "status"]));
+
+        assert!(ConfigArgs::parse(&args(&["--set", "no-equals"])).is_err());
+        assert!(ConfigArgs::parse(&args(&["--config"])).is_err());
+    }
+
+    #[test]
+    fn test_layering() {
+        let mut tree = serde_json::to_value(Config::default()).unwrap();
+
+        let file: Value = toml::from_str(
+            r#"
+            database_url = "postgres://oauth:hunter2@db/oauth"
+
+            [server]
+            listen_addr = "0.0.0.0:3001"
+
+            [rate_limits.token]
+            max_requests = 50
+            window_secs = 60
+
+            [[federation.providers]]
+            id = "corp"
+            issuer = "https://idp.example.com"
+            client_id = "oauth-service"
+            client_secret = "idp-s3cret"
+            role_mappings = { engineering = ["user"] }
+
+            [webauthn]
+            rp_id = "example.com"
+            "#,
+        )
+        .unwrap();
+        merge(&mut tree, file);
+
+        let env = env_overrides(
+            vec![
+                ("COOKIE_DOMAIN".to_string(), ".example.com".to_string()),
+                ("NODE_ENV".to_string(), "production".to_string()),
+                ("OAUTH__DATABASE__MAX_CONNECTIONS".to_string(), "32".to_string()),
+                ("PATH".to_string(), "/usr/bin".to_string()),
+            ]
+            .into_iter(),
+        );
+        for (key, value) in env {
+            set_path(&mut tree, &key, &value).unwrap();
+        }
+        set_path(&mut tree, "server.listen_addr", "0.0.0.0:8443").unwrap();
+        set_path(&mut tree, "cors.allowed_origins", r#"["https://admin.example.com"]"#).unwrap();
+
+        let config: Config = serde_json::from_value(tree).unwrap();
+        assert_eq!(config.database_url, "postgres://oauth:hunter2@db/oauth");
+        assert_eq!(config.server.listen_addr, "0.0.0.0:8443".parse().unwrap());
+        assert_eq!(config.database.max_connections, 32);
+        assert_eq!(config.cookie.domain, ".example.com");
+        assert!(config.cookie.secure);
+        assert_eq!(config.rate_limits.token.max_requests, 50);
+        // 文件中未出现的配置项保留默认值
+        assert_eq!(config.rate_limits.login.window_secs, 300);
+        assert_eq!(config.cors.allowed_origins, vec!["https://admin.example.com"]);
+        let provider = &config.federation.providers[0];
+        assert_eq!(provider.scopes, vec!["openid", "profile", "email"]);
+        assert_eq!(provider.role_mappings["engineering"], vec!["user"]);
+        assert_eq!(config.webauthn_rp_id(), "example.com");
+        assert_eq!(
+            config.webauthn_origins(),
+            vec!["http://127.0.0.1:3001", "http://localhost:3002", "http://localhost:6188"]
+        );
+
+        let printed = config.to_redacted_toml().unwrap();
+        assert!(!printed.contains("hunter2"));
+        assert!(!printed.contains("idp-s3cret"));
+        assert!(printed.contains("listen_addr = \"0.0.0.0:8443\""));
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        let mut unknown = serde_json::to_value(Config::default()).unwrap();
+        set_path(&mut unknown, "server.port", "8080").unwrap();
+        assert!(serde_json::from_value::<Config>(unknown).is_err());
+
+        let config = Config {
+            database_url: "mssql://localhost".to_string(),
+            jwt_algorithm: JwtAlgorithm::RS256,
+            server: ServerConfig {
+                tls: Some(TlsConfig {
+                    cert_path: PathBuf::from("/nonexistent/cert.pem"),
+                    key_path: PathBuf::from("/nonexistent/key.pem"),
+                }),
+                ..Default::default()
+            },
+            database: DatabaseConfig { max_connections: 0 },
+            cors: CorsConfig {
+                allowed_origins: vec!["https://admin.example.com/path".to_string()],
+            },
+            tracing: TracingConfig {
+                otlp_endpoint: Some("collector:4318".to_string()),
+                sample_ratio: 1.5,
+                ..Default::default()
+            },
+            maintenance: MaintenanceConfig {
+                batch_size: 0,
+                ..Default::default()
+            },
+            federation: FederationConfig {
+                providers: vec![OidcProviderConfig {
+                    id: "Corp IdP".to_string(),
+                    issuer: "https://idp.example.com".to_string(),
+                    scopes: vec!["profile".to_string()],
+                    ..Default::default()
+                }],
+            },
+            webauthn: WebAuthnConfig {
+                rp_id: Some("example.com".to_string()),
+                origins: vec!["https://login.example.org".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let errors = config.validate();
+        for expected in [
+            "database_url",
+            "database.max_connections",
+            "jwt_private_key_path must be set for RS256",
+            "server.tls.cert_path",
+            "server.tls.key_path",
+            "cors.allowed_origins",
+            "tracing.otlp_endpoint",
+            "tracing.sample_ratio",
+            "maintenance.batch_size",
+            "federation.providers[0].id",
+            "federation.providers[0].client_id",
+            "federation.providers[0].scopes",
+            "webauthn.origins",
+        ] {
+            assert!(
+                errors.iter().any(|error| error.starts_with(expected)),
+                "missing error for {expected}: {errors:?}"
+            );
+        }
     }
 }
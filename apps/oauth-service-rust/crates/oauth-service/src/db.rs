@@ -1,96 +1,68 @@
 #![allow(clippy::uninlined_format_args)]
 
 // Database initialization and seeding for OAuth Service
-use sqlx::{sqlite::SqlitePool, migrate::MigrateDatabase, Sqlite};
+use sqlx::{migrate::MigrateDatabase, sqlite::{SqlitePool, SqlitePoolOptions}, Sqlite};
+use crate::config::Config;
 use crate::error::ServiceError;
+use crate::migrations::Migrator;
+use crate::services::system_config_service::{default_configurations, RuntimeSettings};
 use std::path::Path;
-
-/// Initialize database: run migrations and seed data
-pub async fn initialize_database(database_url: &str) -> Result<SqlitePool, ServiceError> {
-    // 1. Create database if it doesn't exist
-    if !Sqlite::database_exists(database_url).await.unwrap_or(false) {
-        tracing::info!("Creating database: {}", database_url);
+use std::sync::Arc;
+
+/// Directory containing the versioned migrations, relative to the working directory
+pub const MIGRATIONS_DIR: &str = "migrations";
+
+/// Create the database if it doesn't exist and open a connection pool
+pub async fn connect_database(config: &Config) -> Result<SqlitePool, ServiceError> {
+    let database_url = &config.database_url;
+    if !database_url.contains(":memory:")
+        && !Sqlite::database_exists(database_url).await.unwrap_or(false)
+    {
+        tracing::info!("Creating database");
         Sqlite::create_database(database_url)
             .await
             .map_err(|e| ServiceError::Internal(format!("Failed to create database: {e}")))?;
     }
 
-    // 2. Create connection pool
-    let pool = SqlitePool::connect(database_url)
+    SqlitePoolOptions::new()
+        // 每个 SQLite 内存数据库连接都是独立的数据库，只能使用单个连接
+        .max_connections(if database_url.contains(":memory:") {
+            1
+        } else {
+            config.database.max_connections
+        })
+        .connect(database_url)
         .await
-        .map_err(|e| ServiceError::Internal(format!("Failed to connect to database: {e}")))?;
+        .map_err(|e| ServiceError::Internal(format!("Failed to connect to database: {e}")))
+}
 
-    // 3. Run migrations and seed data (conditionally)
+/// Initialize database: run migrations and seed data
+pub async fn initialize_database(config: &Config) -> Result<SqlitePool, ServiceError> {
+    // 1. Create database if it doesn't exist and create connection pool
+    let pool = Arc::new(connect_database(config).await?);
+
+    // 2. Run migrations and seed data (conditionally)
     let skip_db_init = std::env::var("SKIP_DB_INIT").unwrap_or_else(|_| "false".to_string());
     if &skip_db_init != "true" && &skip_db_init != "1" {
         tracing::info!("Running migrations and seeding data...");
-        run_migrations(&pool, "migrations").await?;
-        seed_initial_data(&pool).await?;
+        let applied = Migrator::load(pool.clone(), Path::new(MIGRATIONS_DIR))?
+            .up()
+            .await?;
+        tracing::info!("Applied {} new migration(s)", applied.len());
+        seed_initial_data(&pool, &RuntimeSettings::from_config(config)).await?;
     } else {
         tracing::info!("SKIP_DB_INIT is set, skipping migrations and seeding.");
     }
 
     tracing::info!("Database initialization completed successfully");
-    Ok(pool)
-}
-
-/// Run migrations from SQL files
-async fn run_migrations(pool: &SqlitePool, migrations_dir: &str) -> Result<(), ServiceError> {
-    tracing::info!("Running database migrations from: {}", migrations_dir);
-
-    // Read migration files
-    let migration_path = Path::new(migrations_dir);
-    if !migration_path.exists() {
-        tracing::warn!("Migrations directory not found: {}", migrations_dir);
-        return Ok(());
-    }
-
-    // Get all migration files (*.sql) sorted by name
-    let mut entries = std::fs::read_dir(migration_path)
-        .map_err(|e| ServiceError::Internal(format!("Failed to read migrations directory: {e}")))?
-        .filter_map(|e| e.ok())
-        .filter(|e| {
-            e.path()
-                .extension()
-                .map(|ext| ext == "sql")
-                .unwrap_or(false)
-        })
-        .collect::<Vec<_>>();
-
-    entries.sort_by_key(|e| e.path());
-
-    // Execute each migration
-    for entry in entries {
-        let path = entry.path();
-        if let Some(filename) = path.file_name() {
-            let sql = std::fs::read_to_string(&path)
-                .map_err(|e| ServiceError::Internal(format!(
-                    "Failed to read migration file {filename:?}: {e}"
-                )))?;
-
-            tracing::info!("Executing migration: {:?}", filename);
-
-            // Split by semicolons and execute each statement
-            for statement in sql.split(';') {
-                let trimmed = statement.trim();
-                if !trimmed.is_empty() {
-                    sqlx::raw_sql(trimmed)
-                        .execute(pool)
-                        .await
-                        .map_err(|e| ServiceError::Internal(format!(
-                            "Failed to execute migration statement in {filename:?}: {e}"
-                        )))?;
-                }
-            }
-        }
-    }
-
-    tracing::info!("All migrations completed");
-    Ok(())
+    Ok((*pool).clone())
 }
 
 /// Seed initial data: create default users, roles, and clients
-async fn seed_initial_data(pool: &SqlitePool) -> Result<(), ServiceError> {
+async fn seed_initial_data(
+    pool: &SqlitePool,
+    runtime_defaults: &RuntimeSettings,
+) -> Result<(), ServiceError> {
     tracing::info!("Seeding initial data");
 
     // 1. Create default admin user
@@ -111,10 +83,53 @@ async fn seed_initial_data(pool: &SqlitePool) -> Result<(), ServiceError> {
     // 6. Create default scopes
     seed_default_scopes(pool).await?;
 
+    // 7. Store consent texts for known scopes
+    seed_scope_localizations(pool).await?;
+
+    // 8. Map scopes to the permissions they grant
+    seed_scope_permissions(pool).await?;
+
+    // 9. Create default admin portal menus
+    seed_default_menus(pool).await?;
+
+    // 10. Store runtime settings (rate limits, CORS origins, portal URLs)
+    seed_runtime_configurations(pool, runtime_defaults).await?;
+
     tracing::info!("Initial data seeding completed");
     Ok(())
 }
 
+/// Seed runtime settings into `system_configurations`, using the startup configuration
+/// as initial values
+///
+/// Existing values are kept, so settings changed through the admin API survive restarts.
+async fn seed_runtime_configurations(
+    pool: &SqlitePool,
+    defaults: &RuntimeSettings,
+) -> Result<(), ServiceError> {
+    for definition in default_configurations(defaults) {
+        sqlx::query(
+            "INSERT OR IGNORE INTO system_configurations
+                (id, key, value, description, type, category, is_editable, is_sensitive)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(definition.key)
+        .bind(definition.value.to_string())
+        .bind(definition.description)
+        .bind(definition.value_type)
+        .bind(definition.category)
+        .bind(true)
+        .bind(false)
+        .execute(pool)
+        .await
+        .map_err(|e| ServiceError::Internal(format!("Failed to seed configuration {}: {e}", definition.key)))?;
+    }
+
+    tracing::info!("Runtime configurations seeded successfully");
+    Ok(())
+}
+
 /// Seed default admin user
 async fn seed_admin_user(pool: &SqlitePool) -> Result<(), ServiceError> {
     // Check if admin user already exists
@@ -226,6 +241,18 @@ async fn seed_default_permissions(pool: &SqlitePool) -> Result<(), ServiceError>
         ("clients:update", "Update Client", "Edit OAuth client", "clients", "update", "API"),
         ("clients:delete", "Delete Client", "Delete OAuth client", "clients", "delete", "API"),
 
+        // Menu management
+        ("menus:read", "Read Menus", "View menu configuration", "menus", "read", "API"),
+        ("menus:create", "Create Menu", "Create new menu", "menus", "create", "API"),
+        ("menus:update", "Update Menu", "Edit menu and its permissions", "menus", "update", "API"),
+        ("menus:delete", "Delete Menu", "Delete menu", "menus", "delete", "API"),
+
+        // Scope management
+        ("scopes:read", "Read Scopes", "View OAuth scopes", "scopes", "read", "API"),
+        ("scopes:create", "Create Scope", "Create new OAuth scope", "scopes", "create", "API"),
+        ("scopes:update", "Update Scope", "Edit OAuth scope, its permissions and consent texts", "scopes", "update", "API"),
+        ("scopes:delete", "Delete Scope", "Delete OAuth scope", "scopes", "delete", "API"),
+
         // System management
         ("system:config", "System Configuration", "Manage system configuration", "system", "config", "API"),
         ("audit:list", "List Audit Logs", "View audit logs", "audit", "list", "API"),
@@ -236,6 +263,7 @@ async fn seed_default_permissions(pool: &SqlitePool) -> Result<(), ServiceError>
         ("menu:system:permission:view", "View Permission Management Menu", "Show permission management menu", "menu", "system:permission", "MENU"),
         ("menu:system:client:view", "View Client Management Menu", "Show client management menu", "menu", "system:client", "MENU"),
         ("menu:system:audit:view", "View Audit Log Menu", "Show audit log menu", "menu", "system:audit", "MENU"),
+        ("menu:system:menu:view", "View Menu Management Menu", "Show menu management menu", "menu", "system:menu", "MENU"),
         ("dashboard:view", "View Dashboard", "Access dashboard", "dashboard", "view", "MENU"),
     ];
 
@@ -510,3 +538,170 @@ async fn seed_default_scopes(pool: &SqlitePool) -> Result<(), ServiceError> {
     tracing::info!("Default scopes seeded successfully");
     Ok(())
 }
+
+/// Seed consent texts for scopes from the built-in scope metadata
+async fn seed_scope_localizations(pool: &SqlitePool) -> Result<(), ServiceError> {
+    let scopes = sqlx::query_as::<_, (String, String)>("SELECT id, name FROM scopes")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| ServiceError::Internal(format!("Failed to load scopes: {}", e)))?;
+
+    for (scope_id, name) in scopes {
+        let Some(meta) = crate::utils::scopes::get_scope_metadata(&name) else {
+            continue;
+        };
+
+        for (locale, description) in [("zh-CN", &meta.description), ("en", &meta.description_en)] {
+            // Keep texts edited by administrators
+            sqlx::query(
+                "INSERT OR IGNORE INTO scope_localizations (scope_id, locale, display_name, description, risk_level, category)
+                 VALUES (?, ?, ?, ?, ?, ?)"
+            )
+            .bind(&scope_id)
+            .bind(locale)
+            .bind(&meta.display_name)
+            .bind(description)
+            .bind(&meta.risk_level)
+            .bind(&meta.category)
+            .execute(pool)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("Failed to localize scope '{}': {}", name, e)))?;
+        }
+    }
+
+    tracing::info!("Scope localizations seeded successfully");
+    Ok(())
+}
+
+/// Seed default scope to permission mappings
+async fn seed_scope_permissions(pool: &SqlitePool) -> Result<(), ServiceError> {
+    // (scope name, permission name pattern)
+    let mappings = vec![
+        ("admin", "%"),
+        ("manage_users", "users:%"),
+        ("manage_roles", "roles:%"),
+        ("manage_roles", "permissions:%"),
+        ("manage_clients", "clients:%"),
+        ("audit", "audit:%"),
+        ("system_config", "system:%"),
+    ];
+
+    // Scopes that already have permissions are managed by administrators
+    let configured: Vec<String> = sqlx::query_scalar(
+        "SELECT DISTINCT s.name FROM scopes s JOIN scope_permissions sp ON sp.scope_id = s.id"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| ServiceError::Internal(format!("Failed to check scope permissions: {}", e)))?;
+
+    for (scope, pattern) in mappings {
+        if configured.iter().any(|name| name == scope) {
+            tracing::debug!("Scope '{}' already has permissions assigned", scope);
+            continue;
+        }
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO scope_permissions (scope_id, permission_id)
+             SELECT s.id, p.id FROM scopes s, permissions p
+             WHERE s.name = ? AND p.name LIKE ?"
+        )
+        .bind(scope)
+        .bind(pattern)
+        .execute(pool)
+        .await
+        .map_err(|e| ServiceError::Internal(format!("Failed to map permissions to scope '{}': {}", scope, e)))?;
+    }
+
+    tracing::info!("Scope permissions seeded successfully");
+    Ok(())
+}
+
+/// (key, name, path, icon, order, parent key, required permission)
+type MenuSeed = (
+    &'static str,
+    &'static str,
+    Option<&'static str>,
+    &'static str,
+    i32,
+    Option<&'static str>,
+    Option<&'static str>,
+);
+
+/// Seed default admin portal menus and bind them to their MENU permissions
+async fn seed_default_menus(pool: &SqlitePool) -> Result<(), ServiceError> {
+    let menus: Vec<MenuSeed> = vec![
+        ("dashboard", "Overview", Some("/admin"), "Home", 0, None, Some("dashboard:view")),
+        ("system", "System", None, "Settings", 10, None, None),
+        ("system:user", "Users", Some("/admin/users"), "Users", 0, Some("system"), Some("menu:system:user:view")),
+        ("system:role", "Roles", Some("/admin/system/roles"), "Shield", 1, Some("system"), Some("menu:system:role:view")),
+        ("system:permission", "Permissions", Some("/admin/system/permissions"), "Key", 2, Some("system"), Some("menu:system:permission:view")),
+        ("system:client", "Clients", Some("/admin/system/clients"), "Briefcase", 3, Some("system"), Some("menu:system:client:view")),
+        ("system:menu", "Menus", Some("/admin/system/menus"), "Menu", 4, Some("system"), Some("menu:system:menu:view")),
+        ("system:audit", "Audit Logs", Some("/admin/audit"), "FileText", 5, Some("system"), Some("menu:system:audit:view")),
+    ];
+
+    for (key, name, path, icon, order, parent_key, permission) in menus {
+        // Check if menu already exists
+        let existing = sqlx::query_scalar::<_, String>(
+            "SELECT id FROM menus WHERE key = ? LIMIT 1"
+        )
+        .bind(key)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| ServiceError::Internal(format!("Failed to check menu: {}", e)))?;
+
+        if existing.is_some() {
+            tracing::debug!("Menu '{}' already exists", key);
+            continue;
+        }
+
+        // Parents are listed before their children, so the parent row already exists
+        let parent_id = match parent_key {
+            Some(parent_key) => sqlx::query_scalar::<_, String>(
+                "SELECT id FROM menus WHERE key = ? LIMIT 1"
+            )
+            .bind(parent_key)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("Failed to get parent menu: {}", e)))?,
+            None => None,
+        };
+
+        let menu_id = uuid::Uuid::new_v4().to_string();
+
+        sqlx::query(
+            "INSERT INTO menus (id, name, key, path, icon, \"order\", is_hidden, is_active, parent_id)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&menu_id)
+        .bind(name)
+        .bind(key)
+        .bind(path)
+        .bind(icon)
+        .bind(order)
+        .bind(false)  // Visible
+        .bind(true)   // Active
+        .bind(&parent_id)
+        .execute(pool)
+        .await
+        .map_err(|e| ServiceError::Internal(format!("Failed to create menu '{}': {}", key, e)))?;
+
+        if let Some(permission) = permission {
+            sqlx::query(
+                "INSERT OR IGNORE INTO menu_permissions (id, permission_id, menu_id)
+                 SELECT ?, id, ? FROM permissions WHERE name = ?"
+            )
+            .bind(uuid::Uuid::new_v4().to_string())
+            .bind(&menu_id)
+            .bind(permission)
+            .execute(pool)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("Failed to bind menu permission: {}", e)))?;
+        }
+
+        tracing::debug!("Menu '{}' created", key);
+    }
+
+    tracing::info!("Default menus seeded successfully");
+    Ok(())
+}
@@ -67,6 +67,14 @@ pub enum ServiceError {
     #[error("Invalid scope: {0}")]
     InvalidScope(String),
 
+    #[error("Invalid target: {0}")]
+    InvalidTarget(String),
+    #[error("Invalid DPoP proof: {0}")]
+    InvalidDpopProof(String),
+    /// The DPoP proof must carry the server-provided nonce (RFC 9449 §8); holds a fresh nonce.
+    #[error("DPoP nonce required")]
+    UseDpopNonce(String),
+
     #[error("Password hashing error: {0}")]
     PasswordError(String),
 
@@ -75,6 +83,9 @@ pub enum ServiceError {
 
     #[error("Rate limit exceeded: {0}")]
     RateLimitExceeded(String),
+
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
 }
 
 impl From<crate::cache::CacheError> for ServiceError {
@@ -128,6 +139,17 @@ impl IntoResponse for AppError {
                     )
                 },
                 ServiceError::InvalidScope(msg) => (StatusCode::BAD_REQUEST, msg),
+                ServiceError::InvalidTarget(msg) => (StatusCode::BAD_REQUEST, msg),
+                ServiceError::InvalidDpopProof(msg) => (StatusCode::BAD_REQUEST, msg),
+                ServiceError::UseDpopNonce(nonce) => {
+                    let body = Json(json!({ "error": "use_dpop_nonce" }));
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        [(crate::utils::dpop::DPOP_NONCE_HEADER, nonce)],
+                        body,
+                    )
+                        .into_response();
+                }
                 // SECURITY FIX: Don't expose cache implementation details
                 ServiceError::CacheError(e) => {
                     tracing::error!("Cache error: {}", e);
@@ -137,6 +159,7 @@ impl IntoResponse for AppError {
                     )
                 },
                 ServiceError::RateLimitExceeded(msg) => (StatusCode::TOO_MANY_REQUESTS, msg),
+                ServiceError::PreconditionFailed(msg) => (StatusCode::PRECONDITION_FAILED, msg),
             },
             AppError::Auth(auth_error) => match auth_error {
                 AuthError::InvalidCredentials | AuthError::InvalidToken => {
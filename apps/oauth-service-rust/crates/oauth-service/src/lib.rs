@@ -1,13 +1,18 @@
 // 库模块声明
 pub mod app;
 pub mod cache;
+pub mod cli;
 pub mod config;
 pub mod db;
 pub mod error;
+pub mod metrics;
 pub mod middleware;
+pub mod migrations;
 pub mod routes;
+pub mod server;
 pub mod services;
 pub mod state;
+pub mod telemetry;
 pub mod templates;
 pub mod utils;
 
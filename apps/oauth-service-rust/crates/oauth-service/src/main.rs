@@ -1,21 +1,51 @@
-use oauth_service_rust::{config, create_app, initialize_database};
-use std::{net::SocketAddr, sync::Arc};
-use tokio::net::TcpListener;
+use oauth_service::{
+    cli::{self, AdminCommand},
+    config::{self, ConfigArgs},
+    create_app,
+    db::{connect_database, MIGRATIONS_DIR},
+    initialize_database,
+    migrations::Migrator,
+    server,
+    telemetry,
+    AppState,
+};
+use std::{path::Path, sync::Arc};
+
+const USAGE: &str = "Usage: oauth-service [options] [migrate <status|up|down [steps]> | <admin command>]";
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
-    // 日志和跟踪初始化
-    tracing_subscriber::fmt::init();
+    // 解析命令行参数
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args = ConfigArgs::parse(&args)?;
 
-    tracing::info!("=== OAuth 2.1 Service Starting ===");
+    // 加载配置 (默认值 -> 配置文件 -> 环境变量 -> 命令行参数)
+    let config = config::Config::load(&args)?;
+    if args.print_config {
+        print!("{}", config.to_redacted_toml()?);
+        return Ok(());
+    }
+
+    // 日志和跟踪初始化 (子命令的输出写到 stdout，日志写到 stderr)
+    let _telemetry = telemetry::init(&config.tracing, !args.remaining.is_empty())?;
+    let config = Arc::new(config);
 
-    // 加载配置
-    let config = Arc::new(config::Config::from_env()?);
+    tracing::info!("=== OAuth 2.1 Service Starting ===");
     tracing::info!("Configuration loaded successfully");
 
+    // 子命令
+    match args.remaining.first().map(String::as_str) {
+        None => {}
+        Some("migrate") => return run_migrate_command(&config, &args.remaining[1..]).await,
+        Some(_) => match AdminCommand::parse(&args.remaining)? {
+            Some(command) => return run_admin_command(config, command).await,
+            None => anyhow::bail!("{USAGE}\n\n{}\n\n{}", config::CONFIG_USAGE, cli::ADMIN_USAGE),
+        },
+    }
+
     // 初始化数据库（包括迁移和种子数据）
     tracing::info!("Initializing database...");
-    let pool = Arc::new(initialize_database(&config.database_url).await?);
+    let pool = Arc::new(initialize_database(&config).await?);
     tracing::info!("✅ Database initialized successfully (migrations + seed data)");
 
     // 创建应用
@@ -24,16 +54,61 @@ async fn main() -> Result<(), anyhow::Error> {
     tracing::info!("✅ JWT keys loaded");
 
     // 启动服务器
-    let addr = SocketAddr::from(([127, 0, 0, 1], 3001));
-    tracing::info!("✅ OAuth service listening on http://{}", addr);
     tracing::info!("=== OAuth 2.1 Service Ready ===\n");
+    server::serve(app, &config.server).await?;
+
+    Ok(())
+}
+
+/// 管理命令：初始化数据库后直接通过服务层执行
+async fn run_admin_command(
+    config: Arc<config::Config>,
+    command: AdminCommand,
+) -> Result<(), anyhow::Error> {
+    let pool = Arc::new(initialize_database(&config).await?);
+    let state = AppState::new_with_pool_and_config(pool, config).await?;
+    print!("{}", cli::run(&state, command).await?);
+    Ok(())
+}
+
+/// `oauth-service migrate <status|up|down [steps]>`
+async fn run_migrate_command(config: &config::Config, args: &[String]) -> Result<(), anyhow::Error> {
+    let pool = Arc::new(connect_database(config).await?);
+    let migrator = Migrator::load(pool, Path::new(MIGRATIONS_DIR))?;
 
-    let listener = TcpListener::bind(&addr).await?;
-    axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .await?;
+    match args.first().map(String::as_str) {
+        Some("status") => {
+            for status in migrator.status().await? {
+                let applied_at = status
+                    .applied_at
+                    .map(|t| t.to_rfc3339())
+                    .unwrap_or_else(|| "-".to_string());
+                println!(
+                    "{:>4}  {:<8}  {:<40}  {}{}",
+                    status.version,
+                    status.state.as_str(),
+                    status.name,
+                    applied_at,
+                    if status.reversible { "" } else { "  (irreversible)" }
+                );
+            }
+        }
+        Some("up") => {
+            let applied = migrator.up().await?;
+            println!("Applied {} migration(s): {:?}", applied.len(), applied);
+        }
+        Some("down") => {
+            let steps = match args.get(1) {
+                Some(steps) => steps
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid steps '{steps}'\n{USAGE}"))?,
+                None => 1,
+            };
+            let rolled_back = migrator.down(steps).await?;
+            println!("Rolled back {} migration(s): {:?}", rolled_back.len(), rolled_back);
+        }
+        _ => anyhow::bail!(USAGE),
+    }
 
     Ok(())
 }
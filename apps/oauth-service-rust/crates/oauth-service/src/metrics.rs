@@ -0,0 +1,284 @@
+//! Prometheus 指标
+//!
+//! 业务代码通过本模块的 `record_*` 函数记录指标，`/metrics` 在抓取时补充
+//! 权限缓存和数据库连接池等即时状态。端点不对外公开：可以配置独立的监听地址
+//! (`metrics.listen_addr`)，并且只允许 `metrics.allowed_ips` 中的地址访问。
+
+use crate::state::AppState;
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, OnceLock},
+    time::Instant,
+};
+
+pub const HTTP_REQUESTS_TOTAL: &str = "oauth_http_requests_total";
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "oauth_http_request_duration_seconds";
+pub const TOKENS_ISSUED_TOTAL: &str = "oauth_tokens_issued_total";
+pub const TOKEN_REFRESH_FAILURES_TOTAL: &str = "oauth_token_refresh_failures_total";
+pub const LOGINS_TOTAL: &str = "oauth_logins_total";
+pub const RATE_LIMIT_REJECTIONS_TOTAL: &str = "oauth_rate_limit_rejections_total";
+pub const PERMISSION_CACHE_HITS_TOTAL: &str = "oauth_permission_cache_hits_total";
+pub const PERMISSION_CACHE_MISSES_TOTAL: &str = "oauth_permission_cache_misses_total";
+pub const PERMISSION_CACHE_ENTRIES: &str = "oauth_permission_cache_entries";
+pub const DB_POOL_CONNECTIONS: &str = "oauth_db_pool_connections";
+pub const DB_POOL_MAX_CONNECTIONS: &str = "oauth_db_pool_max_connections";
+pub const MAINTENANCE_RUNS_TOTAL: &str = "oauth_maintenance_runs_total";
+pub const MAINTENANCE_ROWS_PURGED_TOTAL: &str = "oauth_maintenance_rows_purged_total";
+pub const MAINTENANCE_LAST_SUCCESS_TIMESTAMP: &str = "oauth_maintenance_last_success_timestamp_seconds";
+
+/// 请求耗时直方图的分桶 (秒)
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// 安装全局 Prometheus 记录器，重复调用返回同一个句柄
+pub fn handle() -> &'static PrometheusHandle {
+    static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+    HANDLE.get_or_init(|| {
+        let recorder = PrometheusBuilder::new()
+            .set_buckets_for_metric(
+                Matcher::Full(HTTP_REQUEST_DURATION_SECONDS.to_string()),
+                LATENCY_BUCKETS,
+            )
+            .expect("latency buckets are not empty")
+            .build_recorder();
+        let handle = recorder.handle();
+        if let Err(e) = ::metrics::set_global_recorder(recorder) {
+            tracing::warn!("Metrics recorder already installed: {}", e);
+        }
+        describe_metrics();
+        handle
+    })
+}
+
+fn describe_metrics() {
+    ::metrics::describe_counter!(HTTP_REQUESTS_TOTAL, "HTTP requests by route and status");
+    ::metrics::describe_histogram!(
+        HTTP_REQUEST_DURATION_SECONDS,
+        ::metrics::Unit::Seconds,
+        "HTTP request latency by route and status"
+    );
+    ::metrics::describe_counter!(TOKENS_ISSUED_TOTAL, "Tokens issued by grant type and client");
+    ::metrics::describe_counter!(TOKEN_REFRESH_FAILURES_TOTAL, "Failed refresh token grants");
+    ::metrics::describe_counter!(LOGINS_TOTAL, "Login attempts by result");
+    ::metrics::describe_counter!(RATE_LIMIT_REJECTIONS_TOTAL, "Requests rejected by rate limiters");
+    ::metrics::describe_counter!(PERMISSION_CACHE_HITS_TOTAL, "Permission cache hits");
+    ::metrics::describe_counter!(PERMISSION_CACHE_MISSES_TOTAL, "Permission cache misses");
+    ::metrics::describe_gauge!(PERMISSION_CACHE_ENTRIES, "Users in the permission cache");
+    ::metrics::describe_gauge!(DB_POOL_CONNECTIONS, "Database pool connections by state");
+    ::metrics::describe_gauge!(DB_POOL_MAX_CONNECTIONS, "Database pool size limit");
+    ::metrics::describe_counter!(MAINTENANCE_RUNS_TOTAL, "Maintenance runs by trigger and result");
+    ::metrics::describe_counter!(MAINTENANCE_ROWS_PURGED_TOTAL, "Expired rows purged by table");
+    ::metrics::describe_gauge!(
+        MAINTENANCE_LAST_SUCCESS_TIMESTAMP,
+        ::metrics::Unit::Seconds,
+        "Unix time of the last successful maintenance run"
+    );
+}
+
+/// 令牌端点成功发放令牌
+pub fn record_tokens_issued(grant_type: &str, client_id: &str) {
+    ::metrics::counter!(
+        TOKENS_ISSUED_TOTAL,
+        "grant_type" => grant_type.to_string(),
+        "client_id" => client_id.to_string()
+    )
+    .increment(1);
+}
+
+/// 刷新令牌失败，`reason` 为错误类别
+pub fn record_refresh_failure(reason: &'static str) {
+    ::metrics::counter!(TOKEN_REFRESH_FAILURES_TOTAL, "reason" => reason).increment(1);
+}
+
+/// 登录结果
+pub fn record_login(success: bool) {
+    let result = if success { "success" } else { "failure" };
+    ::metrics::counter!(LOGINS_TOTAL, "result" => result).increment(1);
+}
+
+/// 限流拒绝，`limiter` 为 api / token / login
+pub fn record_rate_limit_rejection(limiter: &'static str) {
+    ::metrics::counter!(RATE_LIMIT_REJECTIONS_TOTAL, "limiter" => limiter).increment(1);
+}
+
+/// 一次维护任务的结果，`trigger` 为 scheduler / admin_api，`purged` 为 None 表示执行失败
+pub fn record_maintenance_run(trigger: &'static str, purged: Option<&[(String, u64)]>) {
+    let result = if purged.is_some() { "success" } else { "failure" };
+    ::metrics::counter!(MAINTENANCE_RUNS_TOTAL, "trigger" => trigger, "result" => result).increment(1);
+    if let Some(tables) = purged {
+        for (table, rows) in tables {
+            ::metrics::counter!(MAINTENANCE_ROWS_PURGED_TOTAL, "table" => table.clone()).increment(*rows);
+        }
+        ::metrics::gauge!(MAINTENANCE_LAST_SUCCESS_TIMESTAMP).set(chrono::Utc::now().timestamp() as f64);
+    }
+}
+
+/// 记录每个请求的次数和耗时，路由取匹配的路由模板以避免路径参数造成标签爆炸
+pub async fn http_metrics_middleware(request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(request).await;
+
+    let labels = [
+        ("method", method),
+        ("route", route),
+        ("status", response.status().as_u16().to_string()),
+    ];
+    ::metrics::counter!(HTTP_REQUESTS_TOTAL, &labels).increment(1);
+    ::metrics::histogram!(HTTP_REQUEST_DURATION_SECONDS, &labels)
+        .record(started.elapsed().as_secs_f64());
+
+    response
+}
+
+/// 只允许 `metrics.allowed_ips` 中的连接地址访问
+async fn allowlist_middleware(
+    State(allowed_ips): State<Arc<Vec<IpAddr>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if allowed_ips.is_empty() {
+        return next.run(request).await;
+    }
+
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip().to_canonical());
+    match peer {
+        Some(ip) if allowed_ips.contains(&ip) => next.run(request).await,
+        _ => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+async fn metrics_handler(State(state): State<Arc<AppState>>) -> Response {
+    let handle = handle();
+
+    let stats = state.permission_cache.stats().await;
+    ::metrics::counter!(PERMISSION_CACHE_HITS_TOTAL).absolute(stats.hits);
+    ::metrics::counter!(PERMISSION_CACHE_MISSES_TOTAL).absolute(stats.misses);
+    ::metrics::gauge!(PERMISSION_CACHE_ENTRIES).set(stats.total_entries as f64);
+
+    let pool = &state.db_pool;
+    let idle = pool.num_idle() as f64;
+    ::metrics::gauge!(DB_POOL_CONNECTIONS, "state" => "idle").set(idle);
+    ::metrics::gauge!(DB_POOL_CONNECTIONS, "state" => "in_use").set(pool.size() as f64 - idle);
+    ::metrics::gauge!(DB_POOL_MAX_CONNECTIONS).set(pool.options().get_max_connections() as f64);
+
+    handle.run_upkeep();
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        handle.render(),
+    )
+        .into_response()
+}
+
+/// `/metrics` 路由 (已包含 IP 白名单)
+pub fn router(state: Arc<AppState>) -> Router {
+    let allowed_ips = Arc::new(state.config.metrics.allowed_ips.clone());
+    Router::new()
+        .route("/metrics", get(metrics_handler))
+        .layer(axum::middleware::from_fn_with_state(
+            allowed_ips,
+            allowlist_middleware,
+        ))
+        .with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    async fn setup_state(config: Config) -> Arc<AppState> {
+        let pool = Arc::new(sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap());
+        sqlx::query(
+            "CREATE TABLE system_configurations (
+                id TEXT PRIMARY KEY, key TEXT UNIQUE NOT NULL, value TEXT NOT NULL,
+                description TEXT, type TEXT, is_editable BOOLEAN DEFAULT 1,
+                is_sensitive BOOLEAN DEFAULT 0, category TEXT,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+        )
+        .execute(&*pool)
+        .await
+        .unwrap();
+        Arc::new(
+            AppState::new_with_pool_and_config(pool, Arc::new(config))
+                .await
+                .unwrap(),
+        )
+    }
+
+    fn scrape_from(ip: [u8; 4]) -> Request {
+        let mut request = Request::get("/metrics").body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from((ip, 40000))));
+        request
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_renders_recorded_metrics() {
+        let state = setup_state(Config::default()).await;
+        handle();
+        record_tokens_issued("client_credentials", "test-client");
+        record_login(false);
+        record_rate_limit_rejection("token");
+
+        let response = router(state).oneshot(scrape_from([127, 0, 0, 1])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body.contains(
+            r#"oauth_tokens_issued_total{grant_type="client_credentials",client_id="test-client"}"#
+        ));
+        assert!(body.contains(r#"oauth_logins_total{result="failure"}"#));
+        assert!(body.contains(r#"oauth_rate_limit_rejections_total{limiter="token"}"#));
+        assert!(body.contains("oauth_permission_cache_hits_total"));
+        assert!(body.contains(r#"oauth_db_pool_connections{state="idle"}"#));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_rejects_other_addresses() {
+        let state = setup_state(Config::default()).await;
+        let app = router(state);
+
+        let response = app.clone().oneshot(scrape_from([10, 1, 2, 3])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // 没有连接信息时无法判断来源，同样拒绝
+        let request = Request::get("/metrics").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // 白名单为空表示不限制
+        let mut config = Config::default();
+        config.metrics.allowed_ips.clear();
+        let response = router(setup_state(config).await)
+            .oneshot(scrape_from([10, 1, 2, 3]))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
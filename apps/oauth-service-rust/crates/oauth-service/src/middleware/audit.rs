@@ -1,5 +1,8 @@
-use crate::middleware::auth::AuthContext;
-use axum::{extract::Request, middleware::Next, response::Response};
+use crate::middleware::{auth::AuthContext, request_context::RequestId};
+use crate::services::audit_log_service::NewAuditLogEntry;
+use crate::state::AppState;
+use axum::{extract::Request, http::HeaderMap, middleware::Next, response::Response};
+use std::net::IpAddr;
 use std::time::Instant;
 
 /// Sanitizes query parameters to remove sensitive data for logging
@@ -88,6 +91,7 @@ fn sanitize_auth_header(auth: Option<&str>) -> String {
 /// - Records response status code and processing time
 /// - Uses structured logging for audit information
 /// - Adjusts log level based on status code (ERROR/WARN/INFO)
+/// - Correlates entries with the request id and trace id set by the request context middleware
 pub async fn audit_middleware(request: Request, next: Next) -> Response {
     let method = request.method().clone();
     let uri = request.uri().clone();
@@ -106,6 +110,18 @@ pub async fn audit_middleware(request: Request, next: Next) -> Response {
         .map(|ctx| ctx.client_id.clone())
         .unwrap_or_else(|| "unknown".to_string());
 
+    let impersonator = auth_context
+        .as_ref()
+        .and_then(|ctx| ctx.actor_id.clone())
+        .unwrap_or_else(|| "none".to_string());
+
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .map(|id| id.0.clone())
+        .unwrap_or_else(|| "none".to_string());
+    let trace_id = crate::telemetry::current_trace_id().unwrap_or_else(|| "none".to_string());
+
     // Extract and sanitize auth header for logging
     let sanitized_auth = request
         .headers()
@@ -120,6 +136,9 @@ pub async fn audit_middleware(request: Request, next: Next) -> Response {
         query = %sanitize_query(uri.query()),
         user_id = %user_id,
         client_id = %client_id,
+        impersonator = %impersonator,
+        request_id = %request_id,
+        trace_id = %trace_id,
         authorization = %sanitize_auth_header(sanitized_auth),
         remote_addr = ?request.headers().get("x-forwarded-for"),
         user_agent = ?request.headers().get("user-agent").and_then(|v| v.to_str().ok()),
@@ -145,6 +164,8 @@ pub async fn audit_middleware(request: Request, next: Next) -> Response {
                 duration_us = duration.as_micros() as u64,
                 user_id = %user_id,
                 client_id = %client_id,
+                request_id = %request_id,
+                trace_id = %trace_id,
                 "HTTP request completed"
             );
         }
@@ -158,6 +179,8 @@ pub async fn audit_middleware(request: Request, next: Next) -> Response {
                 duration_us = duration.as_micros() as u64,
                 user_id = %user_id,
                 client_id = %client_id,
+                request_id = %request_id,
+                trace_id = %trace_id,
                 "HTTP request completed"
             );
         }
@@ -171,6 +194,8 @@ pub async fn audit_middleware(request: Request, next: Next) -> Response {
                 duration_us = duration.as_micros() as u64,
                 user_id = %user_id,
                 client_id = %client_id,
+                request_id = %request_id,
+                trace_id = %trace_id,
                 "HTTP request completed"
             );
         }
@@ -184,6 +209,8 @@ pub async fn audit_middleware(request: Request, next: Next) -> Response {
                 duration_us = duration.as_micros() as u64,
                 user_id = %user_id,
                 client_id = %client_id,
+                request_id = %request_id,
+                trace_id = %trace_id,
                 "HTTP request completed"
             );
         }
@@ -197,6 +224,8 @@ pub async fn audit_middleware(request: Request, next: Next) -> Response {
                 duration_us = duration.as_micros() as u64,
                 user_id = %user_id,
                 client_id = %client_id,
+                request_id = %request_id,
+                trace_id = %trace_id,
                 "HTTP request completed"
             );
         }
@@ -210,6 +239,7 @@ pub async fn audit_middleware(request: Request, next: Next) -> Response {
                 status = status,
                 path = %uri.path(),
                 user_id = %user_id,
+                request_id = %request_id,
                 "Server error occurred"
             );
         }
@@ -241,6 +271,54 @@ pub async fn audit_middleware(request: Request, next: Next) -> Response {
     response
 }
 
+/// 把模拟令牌发起的请求写入审计日志 (`impersonation.request`)，记录真实操作人和被模拟的用户
+///
+/// 由认证中间件在请求处理完成后调用，因此被权限检查拒绝的请求同样会被记录。
+/// `ip_address` 取自连接的对端地址 (`ConnectInfo`)，不信任客户端可伪造的 `x-forwarded-for`。
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn record_impersonated_request(
+    state: &AppState,
+    auth: &AuthContext,
+    method: &str,
+    path: &str,
+    headers: &HeaderMap,
+    ip_address: Option<IpAddr>,
+    request_id: Option<&RequestId>,
+    status: u16,
+) {
+    let Some(actor_id) = &auth.actor_id else {
+        return;
+    };
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let entry = NewAuditLogEntry {
+        user_id: auth.user_id.clone(),
+        actor_type: "user".to_string(),
+        actor_id: actor_id.clone(),
+        action: "impersonation.request".to_string(),
+        resource_type: Some("user".to_string()),
+        resource_id: auth.user_id.clone(),
+        details: Some(serde_json::json!({
+            "method": method,
+            "path": path,
+            "status": status,
+            "client_id": auth.client_id,
+            "request_id": request_id.map(|id| id.0.as_str()),
+        })),
+        status: if status < 400 { "success" } else { "failure" }.to_string(),
+        ip_address: ip_address.map(|ip| ip.to_string()),
+        user_agent: header("user-agent"),
+        tenant_id: auth.tenant_id.clone(),
+    };
+    if let Err(e) = state.audit_log_service.record(entry).await {
+        tracing::warn!("Failed to write impersonation audit log: {}", e);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,11 +1,16 @@
-use crate::error::{AppError, AuthError};
+use crate::error::{AppError, AuthError, ServiceError};
+use crate::middleware::audit::record_impersonated_request;
+use crate::middleware::request_context::RequestId;
+use crate::services::dpop_service::DpopRequest;
 use crate::state::AppState;
+use crate::utils::{dpop, jwt::TokenClaims};
 use axum::{
-    extract::{Request, State},
-    http::header,
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, Method},
     middleware::Next,
     response::Response,
 };
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 /// Represents the authenticated context that can be extracted from a request.
@@ -14,10 +19,104 @@ pub struct AuthContext {
     pub client_id: String,
     pub user_id: Option<String>,
     pub permissions: Vec<String>,
+    /// Space-separated scopes the token was granted.
+    pub scope: String,
+    /// Tenant of the token; `None` for platform-level callers, which may manage every tenant.
+    pub tenant_id: Option<String>,
+    /// Real user behind an impersonation token (the `act` claim); `None` for regular tokens.
+    pub actor_id: Option<String>,
+    /// JWT ID of the bearer token.
+    pub token_id: String,
+}
+
+impl AuthContext {
+    /// Whether the caller may see or modify a resource owned by `resource_tenant`.
+    pub fn can_access_tenant(&self, resource_tenant: Option<&str>) -> bool {
+        crate::services::tenant_service::can_access(self.tenant_id.as_deref(), resource_tenant)
+    }
+
+    /// Tenant for a resource the caller creates or lists: tenant callers are always
+    /// confined to their own tenant, platform callers may pick one (or none).
+    pub fn effective_tenant(&self, requested: Option<String>) -> Option<String> {
+        self.tenant_id.clone().or(requested)
+    }
+}
+
+/// The scheme an access token was presented with in the `Authorization` header.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TokenScheme {
+    Bearer,
+    /// `Authorization: DPoP <token>` together with a `DPoP` proof header (RFC 9449 §7.1).
+    Dpop,
+}
+
+fn parse_authorization(value: &str) -> Option<(TokenScheme, &str)> {
+    if let Some(token) = value.strip_prefix("Bearer ") {
+        Some((TokenScheme::Bearer, token))
+    } else {
+        value
+            .strip_prefix("DPoP ")
+            .map(|token| (TokenScheme::Dpop, token))
+    }
+}
+
+/// Checks that a DPoP-bound token is presented with a valid proof from the bound key,
+/// and that unbound tokens are not presented with the DPoP scheme.
+async fn verify_dpop_binding(
+    state: &AppState,
+    claims: &TokenClaims,
+    scheme: TokenScheme,
+    token: &str,
+    method: &Method,
+    path: &str,
+    headers: &HeaderMap,
+) -> Result<(), AppError> {
+    let bound_jkt = match (&claims.cnf, scheme) {
+        (None, TokenScheme::Bearer) => return Ok(()),
+        (Some(cnf), TokenScheme::Dpop) => cnf.jkt.as_str(),
+        (Some(_), TokenScheme::Bearer) => {
+            return Err(ServiceError::Unauthorized(
+                "DPoP-bound token must be presented with the DPoP scheme".to_string(),
+            )
+            .into())
+        }
+        (None, TokenScheme::Dpop) => {
+            return Err(ServiceError::Unauthorized("Token is not DPoP-bound".to_string()).into())
+        }
+    };
+
+    let proof = headers
+        .get(dpop::DPOP_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| ServiceError::Unauthorized("Missing DPoP proof".to_string()))?;
+    let uri = format!("{}{}", state.config.issuer.trim_end_matches('/'), path);
+    let jkt = state
+        .dpop_service
+        .validate_proof(DpopRequest {
+            proof,
+            method: method.as_str(),
+            uri: &uri,
+            access_token: Some(token),
+            require_nonce: false,
+        })
+        .await
+        .map_err(|e| match e {
+            ServiceError::InvalidDpopProof(msg) => ServiceError::Unauthorized(msg),
+            other => other,
+        })?;
+    if jkt != bound_jkt {
+        return Err(ServiceError::Unauthorized(
+            "DPoP proof key does not match the token binding".to_string(),
+        )
+        .into());
+    }
+    Ok(())
 }
 
 /// Authentication middleware to validate Bearer tokens and set AuthContext.
 ///
+/// DPoP-bound tokens (RFC 9449) must use the `DPoP` scheme with a proof signed by the bound key.
+///
 /// # Design Note: Permission Checking Strategy
 ///
 /// Permission checking is intentionally implemented at the middleware level
@@ -33,7 +132,8 @@ pub struct AuthContext {
 /// The flow is:
 /// 1. `rate_limit_middleware` - early exit for rate-limited clients
 /// 2. `auth_middleware` (this) - extract and validate token, populate AuthContext
-/// 3. `permission_middleware` - check AuthContext.permissions against route requirements
+/// 3. `permission_middleware` - check AuthContext.permissions against route requirements,
+///    re-evaluating conditional role permissions against the request context
 /// 4. Route handler - business logic (permissions already validated)
 pub async fn auth_middleware(
     State(state): State<Arc<AppState>>,
@@ -53,31 +153,102 @@ pub async fn auth_middleware(
 
     let path = request.uri().path();
 
-    // Skip authentication for public paths
-    if public_paths.contains(&path) {
+    // Skip authentication for public paths (联合登录的入口和回调同样无需认证，
+    // 通行密钥端点自行从 Bearer 令牌或 session_token cookie 中识别用户)
+    if public_paths.contains(&path)
+        || path.starts_with("/api/v2/auth/federated/")
+        || path.starts_with("/api/v2/auth/webauthn/")
+        || path.starts_with("/tenants/")
+    {
         return Ok(next.run(request).await);
     }
 
-    // Extract Bearer token from Authorization header
+    // Extract the access token from the Authorization header (Bearer or DPoP scheme)
     let auth_header = request
         .headers()
         .get(header::AUTHORIZATION)
         .and_then(|h| h.to_str().ok())
         .ok_or(AuthError::InvalidToken)?;
 
-    let token = auth_header
-        .strip_prefix("Bearer ")
-        .ok_or(AuthError::InvalidToken)?;
+    let (scheme, token) = parse_authorization(auth_header).ok_or(AuthError::InvalidToken)?;
+
+    // Introspect the token to get claims; only access tokens issued for this service are accepted
+    let claims = state
+        .token_service
+        .verify_access_token(token, &state.config.issuer)
+        .await?;
+    verify_dpop_binding(
+        &state,
+        &claims,
+        scheme,
+        token,
+        request.method(),
+        request.uri().path(),
+        request.headers(),
+    )
+    .await?;
 
-    // Introspect the token to get claims
-    let claims = state.token_service.introspect_token(token).await?;
+    // 用户被停用或删除 (如通过 SCIM 取消配置) 后，其未过期的访问令牌立即失效
+    if let Some(user_id) = &claims.sub {
+        match state.user_service.find_by_id(user_id).await? {
+            Some(user) if user.is_active => {}
+            _ => return Err(AuthError::InvalidToken.into()),
+        }
+    }
+
+    // 模拟令牌: 真实操作人被停用或删除后同样立即失效
+    if let Some(actor) = &claims.act {
+        match state.user_service.find_by_id(&actor.sub).await? {
+            Some(user) if user.is_active => {}
+            _ => return Err(AuthError::InvalidToken.into()),
+        }
+    }
+
+    // 租户被停用后，其令牌立即失效
+    if state
+        .tenant_service
+        .ensure_active(claims.tenant.as_deref())
+        .await
+        .is_err()
+    {
+        return Err(AuthError::InvalidToken.into());
+    }
 
     // Create AuthContext and insert into request extensions
     let auth_context = AuthContext {
         client_id: claims.client_id,
         user_id: claims.sub,
         permissions: claims.permissions,
+        scope: claims.scope,
+        tenant_id: claims.tenant,
+        actor_id: claims.act.map(|act| act.sub),
+        token_id: claims.jti,
     };
+    // 模拟令牌的请求在处理完成后写入审计日志 (包括被权限检查拒绝的请求)
+    if auth_context.actor_id.is_some() {
+        let method = request.method().clone();
+        let path = request.uri().path().to_string();
+        let headers = request.headers().clone();
+        let ip_address = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let request_id = request.extensions().get::<RequestId>().cloned();
+        request.extensions_mut().insert(auth_context.clone());
+        let response = next.run(request).await;
+        record_impersonated_request(
+            &state,
+            &auth_context,
+            method.as_str(),
+            &path,
+            &headers,
+            ip_address,
+            request_id.as_ref(),
+            response.status().as_u16(),
+        )
+        .await;
+        return Ok(response);
+    }
     request.extensions_mut().insert(auth_context);
 
     // TODO: Implement permission checking based on required permissions for the route
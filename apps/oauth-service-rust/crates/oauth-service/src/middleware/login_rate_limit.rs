@@ -1,21 +1,24 @@
 // 登陆端点速率限制
-// Login endpoint specific rate limiting - 5 attempts per 5 minutes per IP
+// Login endpoint specific rate limiting - 5 attempts per 5 minutes per IP by default
 
 use std::{
     collections::HashMap,
     net::IpAddr,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 use tokio::sync::RwLock;
 
 /// 登陆专用速率限制器
-/// 更严格的限制：5 attempts / 5 minutes per IP
+/// 更严格的限制：默认 5 attempts / 5 minutes per IP，可在运行时调整
 #[derive(Clone)]
 pub struct LoginRateLimiter {
     state: Arc<RwLock<HashMap<IpAddr, Vec<Instant>>>>,
-    max_attempts: usize,
-    window: Duration,
+    max_attempts: Arc<AtomicUsize>,
+    window_millis: Arc<AtomicU64>,
 }
 
 impl LoginRateLimiter {
@@ -23,11 +26,7 @@ impl LoginRateLimiter {
     /// max_attempts: 5 (尝试次数)
     /// window: 5分钟 (时间窗口)
     pub fn new() -> Self {
-        Self {
-            state: Arc::new(RwLock::new(HashMap::new())),
-            max_attempts: 5,
-            window: Duration::from_secs(300), // 5 minutes
-        }
+        Self::with_limits(5, Duration::from_secs(300)) // 5 minutes
     }
 
     /// 创建自定义参数的登陆速率限制器（主要用于测试）
@@ -35,28 +34,48 @@ impl LoginRateLimiter {
     /// window: 时间窗口
     #[cfg(test)]
     pub fn with_window(max_attempts: usize, window: Duration) -> Self {
+        Self::with_limits(max_attempts, window)
+    }
+
+    fn with_limits(max_attempts: usize, window: Duration) -> Self {
         Self {
             state: Arc::new(RwLock::new(HashMap::new())),
-            max_attempts,
-            window,
+            max_attempts: Arc::new(AtomicUsize::new(max_attempts)),
+            window_millis: Arc::new(AtomicU64::new(window.as_millis() as u64)),
         }
     }
 
+    /// 调整限制参数，立即对后续登陆尝试生效
+    pub fn set_limits(&self, max_attempts: usize, window: Duration) {
+        self.max_attempts.store(max_attempts, Ordering::Relaxed);
+        self.window_millis
+            .store(window.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    fn max_attempts(&self) -> usize {
+        self.max_attempts.load(Ordering::Relaxed)
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_millis(self.window_millis.load(Ordering::Relaxed))
+    }
+
     /// 检查是否允许登陆尝试
     /// 返回 true 表示允许继续登陆
     /// 返回 false 表示超过速率限制
     pub async fn check_login_attempt(&self, ip: IpAddr) -> bool {
         let now = Instant::now();
+        let window = self.window();
         let mut state = self.state.write().await;
 
         // 获取或创建该IP的登陆尝试历史
         let attempts = state.entry(ip).or_insert_with(Vec::new);
 
-        // 清理过期的尝试记录（5分钟以外的）
-        attempts.retain(|&time| now.duration_since(time) < self.window);
+        // 清理过期的尝试记录（时间窗口以外的）
+        attempts.retain(|&time| now.duration_since(time) < window);
 
         // 检查是否超过限制
-        if attempts.len() >= self.max_attempts {
+        if attempts.len() >= self.max_attempts() {
             return false;
         }
 
@@ -68,10 +87,11 @@ impl LoginRateLimiter {
     /// 清理过期数据（应在后台任务中定期调用）
     pub async fn cleanup(&self) {
         let now = Instant::now();
+        let window = self.window();
         let mut state = self.state.write().await;
 
         state.retain(|_, attempts| {
-            attempts.retain(|&time| now.duration_since(time) < self.window);
+            attempts.retain(|&time| now.duration_since(time) < window);
             !attempts.is_empty()
         });
     }
@@ -79,15 +99,16 @@ impl LoginRateLimiter {
     /// 获取剩余尝试次数
     pub async fn get_remaining_attempts(&self, ip: IpAddr) -> usize {
         let now = Instant::now();
+        let window = self.window();
         let mut state = self.state.write().await;
 
         if let Some(attempts) = state.get_mut(&ip) {
             // 清理过期的尝试记录
-            attempts.retain(|&time| now.duration_since(time) < self.window);
-            return self.max_attempts.saturating_sub(attempts.len());
+            attempts.retain(|&time| now.duration_since(time) < window);
+            return self.max_attempts().saturating_sub(attempts.len());
         }
 
-        self.max_attempts
+        self.max_attempts()
     }
 }
 
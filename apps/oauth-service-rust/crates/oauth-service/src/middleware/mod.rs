@@ -3,4 +3,5 @@ pub mod auth;
 pub mod login_rate_limit;
 pub mod permission;
 pub mod rate_limit;
+pub mod request_context;
 pub mod security_headers;
@@ -1,7 +1,16 @@
 use crate::error::{AppError, AuthError};
 use crate::middleware::auth::AuthContext;
-use axum::{extract::Request, http::Method, middleware::Next, response::Response};
+use crate::state::AppState;
+use crate::utils::permission_conditions::AccessContext;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
 use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
 
 /// 定义路由权限映射
 /// 格式: (HTTP方法, 路径) -> 所需权限
@@ -26,6 +35,10 @@ pub fn get_route_permissions() -> HashMap<(Method, &'static str), Vec<&'static s
         (Method::DELETE, "/api/v2/admin/clients/:client_id"),
         vec!["clients:delete"],
     );
+    permissions.insert(
+        (Method::POST, "/api/v2/admin/clients/:client_id/revoke-tokens"),
+        vec!["tokens:revoke"],
+    );
 
     // 用户管理权限
     permissions.insert((Method::GET, "/api/v2/admin/users"), vec!["users:read"]);
@@ -52,6 +65,10 @@ pub fn get_route_permissions() -> HashMap<(Method, &'static str), Vec<&'static s
         (Method::POST, "/api/v2/admin/permissions"),
         vec!["permissions:create"],
     );
+    permissions.insert(
+        (Method::POST, "/api/v2/admin/permissions/evaluate"),
+        vec!["permissions:read", "roles:read"],
+    );
     permissions.insert(
         (Method::GET, "/api/v2/admin/permissions/:permission_id"),
         vec!["permissions:read"],
@@ -92,6 +109,21 @@ pub fn get_route_permissions() -> HashMap<(Method, &'static str), Vec<&'static s
         (Method::DELETE, "/api/v2/admin/roles/:role_id/permissions"),
         vec!["roles:manage"],
     );
+    permissions.insert(
+        (
+            Method::PUT,
+            "/api/v2/admin/roles/:role_id/permissions/:permission_id/conditions",
+        ),
+        vec!["roles:manage"],
+    );
+    permissions.insert(
+        (Method::GET, "/api/v2/admin/roles/:role_id/parents"),
+        vec!["roles:read"],
+    );
+    permissions.insert(
+        (Method::PUT, "/api/v2/admin/roles/:role_id/parents"),
+        vec!["roles:manage"],
+    );
     permissions.insert(
         (Method::POST, "/api/v2/admin/users/:user_id/roles"),
         vec!["users:manage_roles"],
@@ -104,10 +136,206 @@ pub fn get_route_permissions() -> HashMap<(Method, &'static str), Vec<&'static s
         (Method::DELETE, "/api/v2/admin/users/:user_id/roles"),
         vec!["users:manage_roles"],
     );
+    permissions.insert(
+        (Method::GET, "/api/v2/admin/users/:user_id/permissions"),
+        vec!["users:read", "roles:read"],
+    );
+    permissions.insert(
+        (Method::POST, "/api/v2/admin/users/:user_id/impersonate"),
+        vec!["users:impersonate"],
+    );
+    permissions.insert(
+        (Method::POST, "/api/v2/admin/users/:user_id/revoke-tokens"),
+        vec!["tokens:revoke"],
+    );
+    permissions.insert(
+        (Method::GET, "/api/v2/admin/roles/:role_id/approvers"),
+        vec!["roles:read"],
+    );
+    permissions.insert(
+        (Method::PUT, "/api/v2/admin/roles/:role_id/approvers"),
+        vec!["access_requests:manage"],
+    );
+
+    // 角色申请管理权限 (申请和审批本身只需要登录，由服务层校验审批人)
+    permissions.insert(
+        (Method::GET, "/api/v2/admin/access-requests"),
+        vec!["access_requests:read"],
+    );
+
+    // 授权决策: 任何已认证的请求方都可以检查自身权限，
+    // 检查其他主体 (请求体中的 subject) 需要 authz:check，由处理函数校验
+    permissions.insert((Method::POST, "/api/v2/authz/check"), vec![]);
+    permissions.insert((Method::POST, "/api/v2/authz/check/batch"), vec![]);
+
+    // 权限范围管理权限
+    permissions.insert((Method::GET, "/api/v2/admin/scopes"), vec!["scopes:read"]);
+    permissions.insert((Method::POST, "/api/v2/admin/scopes"), vec!["scopes:create"]);
+    permissions.insert(
+        (Method::GET, "/api/v2/admin/scopes/:scope_id"),
+        vec!["scopes:read"],
+    );
+    permissions.insert(
+        (Method::PUT, "/api/v2/admin/scopes/:scope_id"),
+        vec!["scopes:update"],
+    );
+    permissions.insert(
+        (Method::DELETE, "/api/v2/admin/scopes/:scope_id"),
+        vec!["scopes:delete"],
+    );
+    permissions.insert(
+        (Method::GET, "/api/v2/admin/scopes/:scope_id/permissions"),
+        vec!["scopes:read"],
+    );
+    permissions.insert(
+        (Method::PUT, "/api/v2/admin/scopes/:scope_id/permissions"),
+        vec!["scopes:update"],
+    );
+    permissions.insert(
+        (Method::GET, "/api/v2/admin/scopes/:scope_id/localizations"),
+        vec!["scopes:read"],
+    );
+    permissions.insert(
+        (Method::PUT, "/api/v2/admin/scopes/:scope_id/localizations"),
+        vec!["scopes:update"],
+    );
+
+    // 系统配置管理权限
+    permissions.insert(
+        (Method::GET, "/api/v2/admin/system/configurations"),
+        vec!["system:config"],
+    );
+    permissions.insert(
+        (Method::POST, "/api/v2/admin/system/configurations/reload"),
+        vec!["system:config"],
+    );
+    permissions.insert(
+        (Method::GET, "/api/v2/admin/system/configurations/:key"),
+        vec!["system:config"],
+    );
+    permissions.insert(
+        (Method::PUT, "/api/v2/admin/system/configurations/:key"),
+        vec!["system:config"],
+    );
+    permissions.insert(
+        (Method::POST, "/api/v2/admin/system/maintenance/run"),
+        vec!["system:config"],
+    );
+
+    // SCIM 同步权限 (仅授予身份源使用的专用客户端)
+    for (method, path) in [
+        (Method::GET, "/scim/v2/Users"),
+        (Method::POST, "/scim/v2/Users"),
+        (Method::GET, "/scim/v2/Users/:id"),
+        (Method::PUT, "/scim/v2/Users/:id"),
+        (Method::PATCH, "/scim/v2/Users/:id"),
+        (Method::DELETE, "/scim/v2/Users/:id"),
+        (Method::GET, "/scim/v2/Groups"),
+        (Method::POST, "/scim/v2/Groups"),
+        (Method::GET, "/scim/v2/Groups/:id"),
+        (Method::PUT, "/scim/v2/Groups/:id"),
+        (Method::PATCH, "/scim/v2/Groups/:id"),
+        (Method::DELETE, "/scim/v2/Groups/:id"),
+        (Method::GET, "/scim/v2/ServiceProviderConfig"),
+        (Method::GET, "/scim/v2/ResourceTypes"),
+    ] {
+        permissions.insert((method, path), vec!["scim:provision"]);
+    }
+
+    // 租户管理权限
+    permissions.insert((Method::GET, "/api/v2/admin/tenants"), vec!["tenants:read"]);
+    permissions.insert(
+        (Method::POST, "/api/v2/admin/tenants"),
+        vec!["tenants:create"],
+    );
+    permissions.insert(
+        (Method::GET, "/api/v2/admin/tenants/:tenant_id"),
+        vec!["tenants:read"],
+    );
+    permissions.insert(
+        (Method::PUT, "/api/v2/admin/tenants/:tenant_id"),
+        vec!["tenants:update"],
+    );
+    permissions.insert(
+        (Method::DELETE, "/api/v2/admin/tenants/:tenant_id"),
+        vec!["tenants:delete"],
+    );
+
+    // 资源服务器管理权限
+    permissions.insert(
+        (Method::GET, "/api/v2/admin/resource-servers"),
+        vec!["resource_servers:read"],
+    );
+    permissions.insert(
+        (Method::POST, "/api/v2/admin/resource-servers"),
+        vec!["resource_servers:create"],
+    );
+    permissions.insert(
+        (Method::GET, "/api/v2/admin/resource-servers/:server_id"),
+        vec!["resource_servers:read"],
+    );
+    permissions.insert(
+        (Method::PUT, "/api/v2/admin/resource-servers/:server_id"),
+        vec!["resource_servers:update"],
+    );
+    permissions.insert(
+        (Method::DELETE, "/api/v2/admin/resource-servers/:server_id"),
+        vec!["resource_servers:delete"],
+    );
+
+    // 菜单管理权限
+    permissions.insert((Method::GET, "/api/v2/admin/menus"), vec!["menus:read"]);
+    permissions.insert((Method::POST, "/api/v2/admin/menus"), vec!["menus:create"]);
+    permissions.insert((Method::GET, "/api/v2/admin/menus/tree"), vec!["menus:read"]);
+    permissions.insert(
+        (Method::GET, "/api/v2/admin/menus/:menu_id"),
+        vec!["menus:read"],
+    );
+    permissions.insert(
+        (Method::PUT, "/api/v2/admin/menus/:menu_id"),
+        vec!["menus:update"],
+    );
+    permissions.insert(
+        (Method::DELETE, "/api/v2/admin/menus/:menu_id"),
+        vec!["menus:delete"],
+    );
+    permissions.insert(
+        (Method::GET, "/api/v2/admin/menus/:menu_id/permissions"),
+        vec!["menus:read"],
+    );
+    permissions.insert(
+        (Method::PUT, "/api/v2/admin/menus/:menu_id/permissions"),
+        vec!["menus:update"],
+    );
 
     permissions
 }
 
+/// 租户管理员可以访问的管理端点前缀，处理函数负责把数据限定在其租户内
+const TENANT_ADMIN_PREFIXES: [&str; 5] = [
+    "/api/v2/admin/users",
+    "/api/v2/admin/clients",
+    "/api/v2/admin/roles",
+    "/api/v2/admin/audit-logs",
+    "/api/v2/admin/access-requests",
+];
+
+/// 租户内的请求方能否访问该管理端点
+///
+/// 平台级配置 (权限定义、scope、菜单、系统配置、租户、SCIM 等) 只对平台级请求方开放，
+/// 权限定义对租户只读，便于租户管理员给自己的角色分配权限。
+fn is_tenant_accessible(method: &Method, path: &str) -> bool {
+    if !path.starts_with("/api/v2/admin/") && !path.starts_with("/scim/") {
+        return true;
+    }
+    if path == "/api/v2/admin/permissions" {
+        return method == Method::GET;
+    }
+    TENANT_ADMIN_PREFIXES
+        .iter()
+        .any(|prefix| path == *prefix || path.starts_with(&format!("{prefix}/")))
+}
+
 /// 检查用户是否拥有所需权限
 fn has_permissions(user_permissions: &[String], required_permissions: &[&str]) -> bool {
     for required_perm in required_permissions {
@@ -121,8 +349,81 @@ fn has_permissions(user_permissions: &[String], required_permissions: &[&str]) -
     true
 }
 
+/// 对令牌中缺少的权限按请求上下文重新求值
+///
+/// 带附加条件的权限不会写入令牌，因此令牌中缺少的权限需要针对本次请求
+/// (时间、来源 IP、客户端) 检查条件是否满足。来源 IP 取自连接地址。
+/// 只对带条件的授予求值，并且权限必须在令牌的 scope 和客户端允许的范围之内，
+/// 否则收窄过的令牌会重新获得用户的全部权限。
+async fn has_conditional_permissions(
+    state: &AppState,
+    auth_context: &AuthContext,
+    ip_address: Option<IpAddr>,
+    required_permissions: &[&str],
+) -> Result<bool, AppError> {
+    let Some(user_id) = &auth_context.user_id else {
+        return Ok(false);
+    };
+    // 模拟令牌只使用签发时授予的权限，不对被模拟用户的附加条件权限求值
+    if auth_context.actor_id.is_some() {
+        return Ok(false);
+    }
+
+    let missing: Vec<String> = required_permissions
+        .iter()
+        .filter(|required_perm| !auth_context.permissions.iter().any(|perm| perm == *required_perm))
+        .map(|required_perm| required_perm.to_string())
+        .collect();
+
+    // 令牌的 scope 所映射的权限
+    let allowed_by_scope = state
+        .scope_service
+        .restrict_permissions_to_scope(&auth_context.scope, missing.clone())
+        .await?;
+    if allowed_by_scope.len() != missing.len() {
+        return Ok(false);
+    }
+
+    // 配置了客户端权限的客户端只能使用其中列出的权限
+    let Some(client) = state
+        .client_service
+        .find_by_client_id(&auth_context.client_id)
+        .await?
+    else {
+        return Ok(false);
+    };
+    if !client.client_permissions.is_empty()
+        && !missing
+            .iter()
+            .all(|perm| client.client_permissions.contains(perm))
+    {
+        return Ok(false);
+    }
+
+    let access_context = AccessContext {
+        ip_address,
+        client_id: Some(auth_context.client_id.clone()),
+        ..Default::default()
+    };
+
+    for required_perm in &missing {
+        if !state
+            .rbac_service
+            .has_conditional_permission(user_id, required_perm, &access_context)
+            .await?
+        {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
 /// 权限检查中间件
-pub async fn permission_middleware(request: Request, next: Next) -> Result<Response, AppError> {
+pub async fn permission_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
     // 公开路径列表，不需要权限检查
     let public_paths = [
         "/health",
@@ -136,8 +437,12 @@ pub async fn permission_middleware(request: Request, next: Next) -> Result<Respo
 
     let path = request.uri().path();
 
-    // 跳过公开路径的权限检查
-    if public_paths.contains(&path) {
+    // 跳过公开路径的权限检查 (联合登录和通行密钥端点与认证中间件保持一致)
+    if public_paths.contains(&path)
+        || path.starts_with("/api/v2/auth/federated/")
+        || path.starts_with("/api/v2/auth/webauthn/")
+        || path.starts_with("/tenants/")
+    {
         return Ok(next.run(request).await);
     }
 
@@ -145,8 +450,23 @@ pub async fn permission_middleware(request: Request, next: Next) -> Result<Respo
     let auth_context = request
         .extensions()
         .get::<AuthContext>()
+        .cloned()
         .ok_or(AuthError::InvalidToken)?;
 
+    if auth_context.tenant_id.is_some() && !is_tenant_accessible(request.method(), path) {
+        tracing::warn!(
+            user_id = ?auth_context.user_id,
+            tenant_id = ?auth_context.tenant_id,
+            path = path,
+            "Tenant caller denied access to platform endpoint"
+        );
+        return Err(AuthError::InsufficientPermissions.into());
+    }
+    let ip_address = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
     // 获取路由权限映射
     let route_permissions = get_route_permissions();
     let method = request.method().clone();
@@ -155,7 +475,10 @@ pub async fn permission_middleware(request: Request, next: Next) -> Result<Respo
     for ((route_method, route_path), required_perms) in &route_permissions {
         if method == route_method && path_matches(path, route_path) {
             // 检查权限
-            if !has_permissions(&auth_context.permissions, required_perms) {
+            if !has_permissions(&auth_context.permissions, required_perms)
+                && !has_conditional_permissions(&state, &auth_context, ip_address, required_perms)
+                    .await?
+            {
                 tracing::warn!(
                     user_id = ?auth_context.user_id,
                     path = path,
@@ -285,5 +608,22 @@ mod tests {
         // Verify some other routes are configured
         assert!(permissions.contains_key(&(Method::GET, "/api/v2/admin/users")));
         assert!(permissions.contains_key(&(Method::POST, "/api/v2/admin/roles")));
+        assert!(permissions.contains_key(&(Method::POST, "/api/v2/authz/check")));
+        assert!(permissions.contains_key(&(Method::POST, "/api/v2/authz/check/batch")));
+    }
+
+    #[test]
+    fn test_is_tenant_accessible() {
+        assert!(is_tenant_accessible(&Method::GET, "/api/v2/admin/users"));
+        assert!(is_tenant_accessible(&Method::POST, "/api/v2/admin/users/abc/roles"));
+        assert!(is_tenant_accessible(&Method::DELETE, "/api/v2/admin/clients/abc"));
+        assert!(is_tenant_accessible(&Method::GET, "/api/v2/admin/permissions"));
+        assert!(is_tenant_accessible(&Method::GET, "/api/v2/users/me"));
+
+        assert!(!is_tenant_accessible(&Method::POST, "/api/v2/admin/permissions"));
+        assert!(!is_tenant_accessible(&Method::GET, "/api/v2/admin/tenants"));
+        assert!(!is_tenant_accessible(&Method::GET, "/api/v2/admin/scopes"));
+        assert!(!is_tenant_accessible(&Method::GET, "/api/v2/admin/usersx"));
+        assert!(!is_tenant_accessible(&Method::GET, "/scim/v2/Users"));
     }
 }
@@ -14,17 +14,22 @@ use axum::{
 use std::{
     collections::HashMap,
     net::SocketAddr,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 use tokio::sync::RwLock;
 
 /// Rate limiter 状态
+///
+/// 限流参数可在运行时通过 `set_limits` 调整，克隆出的实例共享同一份参数。
 #[derive(Clone)]
 pub struct RateLimiter {
     state: Arc<RwLock<HashMap<String, Vec<Instant>>>>,
-    max_requests: usize,
-    window: Duration,
+    max_requests: Arc<AtomicUsize>,
+    window_secs: Arc<AtomicU64>,
 }
 
 impl RateLimiter {
@@ -36,24 +41,35 @@ impl RateLimiter {
     pub fn new(max_requests: usize, window_secs: u64) -> Self {
         Self {
             state: Arc::new(RwLock::new(HashMap::new())),
-            max_requests,
-            window: Duration::from_secs(window_secs),
+            max_requests: Arc::new(AtomicUsize::new(max_requests)),
+            window_secs: Arc::new(AtomicU64::new(window_secs)),
         }
     }
 
+    /// 调整限流参数，立即对后续请求生效
+    pub fn set_limits(&self, max_requests: usize, window_secs: u64) {
+        self.max_requests.store(max_requests, Ordering::Relaxed);
+        self.window_secs.store(window_secs, Ordering::Relaxed);
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.window_secs.load(Ordering::Relaxed))
+    }
+
     /// 检查是否允许请求
     pub async fn check_rate_limit(&self, key: &str) -> bool {
         let now = Instant::now();
+        let window = self.window();
         let mut state = self.state.write().await;
 
         // 获取或创建该 key 的请求历史
         let requests = state.entry(key.to_string()).or_insert_with(Vec::new);
 
         // 清理过期的请求记录
-        requests.retain(|&time| now.duration_since(time) < self.window);
+        requests.retain(|&time| now.duration_since(time) < window);
 
         // 检查是否超过限制
-        if requests.len() >= self.max_requests {
+        if requests.len() >= self.max_requests.load(Ordering::Relaxed) {
             return false;
         }
 
@@ -65,11 +81,12 @@ impl RateLimiter {
     /// 定期清理过期数据（可选，在后台任务中调用）
     pub async fn cleanup(&self) {
         let now = Instant::now();
+        let window = self.window();
         let mut state = self.state.write().await;
 
         // 清理所有过期的条目
         state.retain(|_, requests| {
-            requests.retain(|&time| now.duration_since(time) < self.window);
+            requests.retain(|&time| now.duration_since(time) < window);
             !requests.is_empty()
         });
     }
@@ -101,6 +118,7 @@ pub async fn rate_limit_middleware(
     // CRITICAL FIX: Use shared rate limiter from AppState
     // Previously created new instance per request, making rate limiting ineffective
     if !state.rate_limiter.check_rate_limit(&key).await {
+        crate::metrics::record_rate_limit_rejection("api");
         return Err((
             StatusCode::TOO_MANY_REQUESTS,
             "Rate limit exceeded. Please try again later.".to_string(),
@@ -146,4 +164,19 @@ mod tests {
         // IP1 应该被限制
         assert!(!limiter.check_rate_limit("ip1").await);
     }
+
+    #[tokio::test]
+    async fn test_set_limits_applies_to_clones() {
+        let limiter = RateLimiter::new(1, 60);
+        let shared = limiter.clone();
+
+        assert!(limiter.check_rate_limit("ip").await);
+        assert!(!limiter.check_rate_limit("ip").await);
+
+        // 调整后的上限对所有克隆实例立即生效
+        shared.set_limits(3, 60);
+        assert!(limiter.check_rate_limit("ip").await);
+        assert!(limiter.check_rate_limit("ip").await);
+        assert!(!limiter.check_rate_limit("ip").await);
+    }
 }
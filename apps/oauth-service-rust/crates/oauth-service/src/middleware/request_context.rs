@@ -0,0 +1,217 @@
+//! 请求上下文中间件
+//!
+//! - 遵循传入的 W3C `traceparent` / `tracestate`，请求 span 作为上游 span 的子 span
+//! - 沿用合法的 `X-Request-Id`，没有时生成一个，并在响应头中返回
+//! - 请求 ID 放入请求扩展 (`RequestId`)，供审计日志等关联使用
+
+use axum::{
+    extract::{MatchedPath, Request},
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use opentelemetry::{global, propagation::Extractor};
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// 传入的请求 ID 最大长度
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// 当前请求的 ID
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestId(pub String);
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+/// 只接受可安全写入日志和响应头的请求 ID
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'))
+}
+
+pub async fn request_context_middleware(mut request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+
+    let method = request.method().clone();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+
+    let span = tracing::info_span!(
+        "http.request",
+        otel.name = %format!("{method} {route}"),
+        otel.kind = "server",
+        otel.status_code = tracing::field::Empty,
+        http.method = %method,
+        http.route = %route,
+        http.status_code = tracing::field::Empty,
+        request_id = %request_id,
+    );
+    span.set_parent(parent);
+
+    request.extensions_mut().insert(RequestId(request_id.clone()));
+    let mut response = next.run(request).instrument(span.clone()).await;
+
+    let status = response.status();
+    span.record("http.status_code", status.as_u16());
+    if status.is_server_error() {
+        span.record("otel.status_code", "ERROR");
+    }
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TracingConfig;
+    use axum::{body::Body, routing::get, Router};
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+    use prost::Message;
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+    /// 进程内的 OTLP/HTTP 收集器，返回地址和收到的导出请求
+    async fn start_collector() -> (String, Arc<Mutex<Vec<ExportTraceServiceRequest>>>) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let app = Router::new().route(
+            "/v1/traces",
+            axum::routing::post(move |body: axum::body::Bytes| async move {
+                sink.lock()
+                    .unwrap()
+                    .push(ExportTraceServiceRequest::decode(body).unwrap());
+                ""
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{addr}"), received)
+    }
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/users/:id",
+                get(|| async {
+                    let _db = crate::telemetry::db_span("SELECT", "users").entered();
+                    "ok"
+                }),
+            )
+            .layer(axum::middleware::from_fn(request_context_middleware))
+    }
+
+    #[test]
+    fn test_request_id_validation() {
+        assert!(is_valid_request_id("abc-123_x.y:z"));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("has space"));
+        assert!(!is_valid_request_id("line\nbreak"));
+        assert!(!is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LEN + 1)));
+    }
+
+    #[tokio::test]
+    async fn test_request_id_generated_or_echoed() {
+        let response = app()
+            .oneshot(Request::get("/users/1").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let generated = response.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+        assert!(uuid::Uuid::parse_str(generated).is_ok());
+
+        let response = app()
+            .oneshot(
+                Request::get("/users/1")
+                    .header(REQUEST_ID_HEADER, "upstream-42")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "upstream-42");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_traceparent_honored_and_exported() {
+        let (endpoint, received) = start_collector().await;
+        global::set_text_map_propagator(
+            opentelemetry_sdk::propagation::TraceContextPropagator::new(),
+        );
+        let provider = crate::telemetry::build_tracer_provider(&TracingConfig {
+            otlp_endpoint: Some(endpoint),
+            ..Default::default()
+        })
+        .unwrap();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let guard = tracing::subscriber::set_default(subscriber);
+
+        let response = app()
+            .oneshot(
+                Request::get("/users/7")
+                    .header("traceparent", format!("00-{TRACE_ID}-00f067aa0ba902b7-01"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        drop(guard);
+
+        tokio::task::spawn_blocking(move || provider.force_flush())
+            .await
+            .unwrap();
+
+        let spans: Vec<_> = received
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|request| &request.resource_spans)
+            .flat_map(|resource| &resource.scope_spans)
+            .flat_map(|scope| scope.spans.clone())
+            .collect();
+        let server = spans.iter().find(|s| s.name == "GET /users/:id").unwrap();
+        let db = spans.iter().find(|s| s.name == "SELECT users").unwrap();
+
+        let trace_id: String = server.trace_id.iter().map(|b| format!("{b:02x}")).collect();
+        assert_eq!(trace_id, TRACE_ID);
+        assert_eq!(server.parent_span_id, [0x00, 0xf0, 0x67, 0xaa, 0x0b, 0xa9, 0x02, 0xb7]);
+        assert_eq!(db.trace_id, server.trace_id);
+        assert_eq!(db.parent_span_id, server.span_id);
+    }
+}
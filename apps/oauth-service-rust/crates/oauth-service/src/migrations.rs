@@ -0,0 +1,526 @@
+// 版本化数据库迁移
+//
+// 迁移文件命名为 `NNN_description.sql`，可选的回滚脚本命名为 `NNN_description.down.sql`。
+// 已执行的迁移连同文件校验和记录在 `schema_migrations` 表中:
+// - 启动时只执行尚未记录的迁移，每个迁移在一个事务内执行并记录
+// - 已执行迁移的文件被修改 (校验和不一致) 或被删除时拒绝启动
+use crate::error::ServiceError;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+
+const DOWN_SUFFIX: &str = ".down.sql";
+
+/// 迁移目录中的一个迁移
+#[derive(Debug, Clone)]
+pub struct Migration {
+    pub version: i64,
+    pub name: String,
+    pub checksum: String,
+    up_sql: String,
+    down_sql: Option<String>,
+}
+
+impl Migration {
+    pub fn has_down(&self) -> bool {
+        self.down_sql.is_some()
+    }
+}
+
+/// `schema_migrations` 表中的一条记录
+#[derive(Debug, Clone)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub name: String,
+    pub checksum: String,
+    pub applied_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationState {
+    /// 已执行且校验和一致
+    Applied,
+    /// 尚未执行
+    Pending,
+    /// 已执行但文件内容已被修改
+    Drifted,
+    /// 已执行但迁移文件已不存在
+    Missing,
+}
+
+impl MigrationState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Applied => "applied",
+            Self::Pending => "pending",
+            Self::Drifted => "drifted",
+            Self::Missing => "missing",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: String,
+    pub state: MigrationState,
+    pub applied_at: Option<DateTime<Utc>>,
+    pub reversible: bool,
+}
+
+pub struct Migrator {
+    pool: Arc<SqlitePool>,
+    migrations: Vec<Migration>,
+}
+
+/// 计算迁移脚本的 SHA-256 校验和
+fn checksum(sql: &str) -> String {
+    format!("{:x}", Sha256::digest(sql.as_bytes()))
+}
+
+/// 解析 `NNN_description` 形式的文件名
+fn parse_file_stem(stem: &str) -> Option<(i64, String)> {
+    let (version, name) = stem.split_once('_')?;
+    Some((version.parse().ok()?, name.to_string()))
+}
+
+/// 读取迁移目录，按版本号排序
+pub fn load_migrations(dir: &Path) -> Result<Vec<Migration>, ServiceError> {
+    if !dir.exists() {
+        tracing::warn!("Migrations directory not found: {}", dir.display());
+        return Ok(Vec::new());
+    }
+
+    let read_error = |e: std::io::Error| {
+        ServiceError::Internal(format!("Failed to read migrations directory {}: {e}", dir.display()))
+    };
+
+    let mut ups = BTreeMap::new();
+    let mut downs = BTreeMap::new();
+    for entry in std::fs::read_dir(dir).map_err(read_error)? {
+        let path = entry.map_err(read_error)?.path();
+        let Some(file_name) = path.file_name().and_then(|f| f.to_str()) else {
+            continue;
+        };
+        if !path.is_file() || !file_name.ends_with(".sql") {
+            continue;
+        }
+
+        let (stem, is_down) = match file_name.strip_suffix(DOWN_SUFFIX) {
+            Some(stem) => (stem, true),
+            None => (file_name.trim_end_matches(".sql"), false),
+        };
+        let (version, name) = parse_file_stem(stem).ok_or_else(|| {
+            ServiceError::Internal(format!(
+                "Invalid migration file name '{file_name}', expected NNN_description.sql"
+            ))
+        })?;
+        let sql = std::fs::read_to_string(&path).map_err(read_error)?;
+
+        let target = if is_down { &mut downs } else { &mut ups };
+        if target.insert(version, (name, sql)).is_some() {
+            return Err(ServiceError::Internal(format!(
+                "Duplicate migration version {version} in {}",
+                dir.display()
+            )));
+        }
+    }
+
+    if let Some(version) = downs.keys().find(|v| !ups.contains_key(v)) {
+        return Err(ServiceError::Internal(format!(
+            "Down migration {version} has no matching up migration"
+        )));
+    }
+
+    Ok(ups
+        .into_iter()
+        .map(|(version, (name, up_sql))| Migration {
+            version,
+            name,
+            checksum: checksum(&up_sql),
+            down_sql: downs.remove(&version).map(|(_, sql)| sql),
+            up_sql,
+        })
+        .collect())
+}
+
+impl Migrator {
+    /// 加载迁移目录中的迁移
+    pub fn load(pool: Arc<SqlitePool>, base_dir: &Path) -> Result<Self, ServiceError> {
+        let migrations = load_migrations(base_dir)?;
+        Ok(Self { pool, migrations })
+    }
+
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    async fn ensure_version_table(&self) -> Result<(), ServiceError> {
+        let ddl = "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            checksum TEXT NOT NULL,
+            execution_time_ms INTEGER NOT NULL,
+            applied_at DATETIME NOT NULL
+        )";
+
+        sqlx::raw_sql(ddl)
+            .execute(&*self.pool)
+            .await
+            .map(|_| ())
+            .map_err(|e| ServiceError::Internal(format!("Failed to create schema_migrations: {e}")))
+    }
+
+    /// 读取已执行的迁移记录
+    pub async fn applied(&self) -> Result<Vec<AppliedMigration>, ServiceError> {
+        self.ensure_version_table().await?;
+
+        let sql = "SELECT version, name, checksum, applied_at FROM schema_migrations ORDER BY version";
+        let rows: Vec<(i64, String, String, DateTime<Utc>)> =
+            sqlx::query_as(sql).fetch_all(&*self.pool).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(version, name, checksum, applied_at)| AppliedMigration {
+                version,
+                name,
+                checksum,
+                applied_at,
+            })
+            .collect())
+    }
+
+    /// 每个迁移 (包括已执行但文件已删除的) 的状态
+    pub async fn status(&self) -> Result<Vec<MigrationStatus>, ServiceError> {
+        let mut applied: BTreeMap<i64, AppliedMigration> = self
+            .applied()
+            .await?
+            .into_iter()
+            .map(|m| (m.version, m))
+            .collect();
+
+        let mut statuses: Vec<MigrationStatus> = self
+            .migrations
+            .iter()
+            .map(|migration| {
+                let record = applied.remove(&migration.version);
+                let state = match &record {
+                    None => MigrationState::Pending,
+                    Some(r) if r.checksum != migration.checksum => MigrationState::Drifted,
+                    Some(_) => MigrationState::Applied,
+                };
+                MigrationStatus {
+                    version: migration.version,
+                    name: migration.name.clone(),
+                    state,
+                    applied_at: record.map(|r| r.applied_at),
+                    reversible: migration.has_down(),
+                }
+            })
+            .collect();
+
+        statuses.extend(applied.into_values().map(|record| MigrationStatus {
+            version: record.version,
+            name: record.name,
+            state: MigrationState::Missing,
+            applied_at: Some(record.applied_at),
+            reversible: false,
+        }));
+        statuses.sort_by_key(|s| s.version);
+
+        Ok(statuses)
+    }
+
+    /// 校验已执行迁移的校验和，发现偏差时返回错误
+    pub async fn verify(&self) -> Result<(), ServiceError> {
+        let problems: Vec<String> = self
+            .status()
+            .await?
+            .into_iter()
+            .filter_map(|s| match s.state {
+                MigrationState::Drifted => Some(format!(
+                    "migration {} ({}) was modified after it was applied",
+                    s.version, s.name
+                )),
+                MigrationState::Missing => Some(format!(
+                    "migration {} ({}) was applied but its file is missing",
+                    s.version, s.name
+                )),
+                MigrationState::Applied | MigrationState::Pending => None,
+            })
+            .collect();
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ServiceError::Internal(format!(
+                "Database schema has drifted from migrations: {}",
+                problems.join("; ")
+            )))
+        }
+    }
+
+    /// 执行所有尚未执行的迁移，返回执行的版本号
+    pub async fn up(&self) -> Result<Vec<i64>, ServiceError> {
+        self.verify().await?;
+
+        let applied: Vec<i64> = self.applied().await?.iter().map(|m| m.version).collect();
+        let mut executed = Vec::new();
+
+        for migration in self.migrations.iter().filter(|m| !applied.contains(&m.version)) {
+            tracing::info!("Applying migration {} ({})", migration.version, migration.name);
+            let started = Instant::now();
+
+            let mut tx = self.pool.begin().await?;
+            sqlx::raw_sql(&migration.up_sql)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| migration_error(migration, "apply", e))?;
+            sqlx::query(
+                "INSERT INTO schema_migrations (version, name, checksum, execution_time_ms, applied_at) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(migration.version)
+            .bind(&migration.name)
+            .bind(&migration.checksum)
+            .bind(started.elapsed().as_millis() as i64)
+            .bind(Utc::now())
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+
+            executed.push(migration.version);
+        }
+
+        Ok(executed)
+    }
+
+    /// 按版本倒序回滚最近执行的 `steps` 个迁移，返回回滚的版本号
+    pub async fn down(&self, steps: usize) -> Result<Vec<i64>, ServiceError> {
+        self.verify().await?;
+
+        let applied = self.applied().await?;
+        let mut rolled_back = Vec::new();
+
+        for record in applied.iter().rev().take(steps) {
+            let migration = self
+                .migrations
+                .iter()
+                .find(|m| m.version == record.version)
+                .ok_or_else(|| {
+                    ServiceError::Internal(format!("Migration {} file is missing", record.version))
+                })?;
+            let down_sql = migration.down_sql.as_deref().ok_or_else(|| {
+                ServiceError::ValidationError(format!(
+                    "Migration {} ({}) has no down migration",
+                    migration.version, migration.name
+                ))
+            })?;
+
+            tracing::info!("Rolling back migration {} ({})", migration.version, migration.name);
+            let mut tx = self.pool.begin().await?;
+            sqlx::raw_sql(down_sql)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| migration_error(migration, "roll back", e))?;
+            sqlx::query("DELETE FROM schema_migrations WHERE version = ?")
+                .bind(migration.version)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+
+            rolled_back.push(migration.version);
+        }
+
+        Ok(rolled_back)
+    }
+}
+
+fn migration_error(migration: &Migration, action: &str, e: sqlx::Error) -> ServiceError {
+    ServiceError::Internal(format!(
+        "Failed to {action} migration {} ({}): {e}",
+        migration.version, migration.name
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn migrations_base_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../../migrations")
+    }
+
+    /// 在临时目录中写入迁移文件
+    fn write_migrations(files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("oauth-migrations-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (name, sql) in files {
+            std::fs::write(dir.join(name), sql).unwrap();
+        }
+        dir
+    }
+
+    async fn memory_pool() -> Arc<SqlitePool> {
+        let config = crate::config::Config {
+            database_url: "sqlite::memory:".to_string(),
+            ..Default::default()
+        };
+        Arc::new(crate::db::connect_database(&config).await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_repository_migrations_apply() {
+        let migrator = Migrator::load(memory_pool().await, &migrations_base_dir()).unwrap();
+
+        migrator.up().await.expect("Repository migrations failed");
+        // 第二次执行时没有待执行的迁移
+        assert!(migrator.up().await.unwrap().is_empty());
+        assert!(migrator
+            .status()
+            .await
+            .unwrap()
+            .iter()
+            .all(|s| s.state == MigrationState::Applied));
+    }
+
+    #[tokio::test]
+    async fn test_upgraded_database_maps_new_permissions_to_admin_scope() {
+        // 先执行到 007 并按旧版本映射权限范围，再升级到最新版本
+        let dir = std::env::temp_dir().join(format!("oauth-migrations-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for entry in std::fs::read_dir(migrations_base_dir()).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            if path.is_file() && name.as_str() < "008" {
+                std::fs::copy(&path, dir.join(&name)).unwrap();
+            }
+        }
+        let pool = memory_pool().await;
+        Migrator::load(pool.clone(), &dir).unwrap().up().await.unwrap();
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO scope_permissions (scope_id, permission_id)
+             SELECT s.id, p.id FROM scopes s, permissions p WHERE s.name = 'admin'",
+        )
+        .execute(&*pool)
+        .await
+        .unwrap();
+
+        Migrator::load(pool.clone(), &migrations_base_dir())
+            .unwrap()
+            .up()
+            .await
+            .unwrap();
+
+        let unmapped: Vec<String> = sqlx::query_scalar(
+            "SELECT p.name FROM permissions p
+             WHERE NOT EXISTS (
+                 SELECT 1 FROM scope_permissions sp JOIN scopes s ON s.id = sp.scope_id
+                 WHERE s.name = 'admin' AND sp.permission_id = p.id
+             )",
+        )
+        .fetch_all(&*pool)
+        .await
+        .unwrap();
+        assert!(unmapped.is_empty(), "not mapped to admin scope: {unmapped:?}");
+    }
+
+    #[tokio::test]
+    async fn test_up_applies_only_pending_migrations() {
+        let dir = write_migrations(&[(
+            "001_create_items.sql",
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, note TEXT);
+             INSERT INTO items (note) VALUES ('semicolon; inside a literal');",
+        )]);
+        let pool = memory_pool().await;
+
+        let migrator = Migrator::load(pool.clone(), &dir).unwrap();
+        assert_eq!(migrator.up().await.unwrap(), vec![1]);
+
+        std::fs::write(
+            dir.join("002_add_tags.sql"),
+            "CREATE TABLE tags (id INTEGER PRIMARY KEY)",
+        )
+        .unwrap();
+        let migrator = Migrator::load(pool.clone(), &dir).unwrap();
+        // 001 不会被重复执行 (CREATE TABLE 没有 IF NOT EXISTS)
+        assert_eq!(migrator.up().await.unwrap(), vec![2]);
+
+        let note: String = sqlx::query_scalar("SELECT note FROM items")
+            .fetch_one(&*pool)
+            .await
+            .unwrap();
+        assert_eq!(note, "semicolon; inside a literal");
+    }
+
+    #[tokio::test]
+    async fn test_failed_migration_is_rolled_back() {
+        let dir = write_migrations(&[(
+            "001_broken.sql",
+            "CREATE TABLE items (id INTEGER PRIMARY KEY);
+             INSERT INTO missing_table VALUES (1);",
+        )]);
+        let pool = memory_pool().await;
+        let migrator = Migrator::load(pool.clone(), &dir).unwrap();
+
+        assert!(migrator.up().await.is_err());
+        assert!(migrator.applied().await.unwrap().is_empty());
+
+        let tables: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE name = 'items'")
+                .fetch_one(&*pool)
+                .await
+                .unwrap();
+        assert_eq!(tables, 0);
+    }
+
+    #[tokio::test]
+    async fn test_drifted_checksum_is_refused() {
+        let dir = write_migrations(&[("001_create_items.sql", "CREATE TABLE items (id INTEGER)")]);
+        let pool = memory_pool().await;
+        Migrator::load(pool.clone(), &dir).unwrap().up().await.unwrap();
+
+        std::fs::write(
+            dir.join("001_create_items.sql"),
+            "CREATE TABLE items (id INTEGER, name TEXT)",
+        )
+        .unwrap();
+        let migrator = Migrator::load(pool, &dir).unwrap();
+
+        let status = migrator.status().await.unwrap();
+        assert_eq!(status[0].state, MigrationState::Drifted);
+        assert!(migrator.up().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_down_rolls_back_latest_migrations() {
+        let dir = write_migrations(&[
+            ("001_create_items.sql", "CREATE TABLE items (id INTEGER)"),
+            ("002_create_tags.sql", "CREATE TABLE tags (id INTEGER)"),
+            ("002_create_tags.down.sql", "DROP TABLE tags"),
+        ]);
+        let pool = memory_pool().await;
+        let migrator = Migrator::load(pool, &dir).unwrap();
+        migrator.up().await.unwrap();
+
+        assert_eq!(migrator.down(1).await.unwrap(), vec![2]);
+        let status = migrator.status().await.unwrap();
+        assert_eq!(status[1].state, MigrationState::Pending);
+
+        // 001 没有回滚脚本
+        assert!(matches!(
+            migrator.down(1).await,
+            Err(ServiceError::ValidationError(_))
+        ));
+
+        // 回滚后可以重新执行
+        assert_eq!(migrator.up().await.unwrap(), vec![2]);
+    }
+}
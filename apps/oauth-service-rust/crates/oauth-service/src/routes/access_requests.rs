@@ -0,0 +1,216 @@
+// 角色申请 API: 用户申请角色，审批人批准或拒绝，管理员查看所有申请
+use crate::{
+    error::{AppError, ServiceError},
+    middleware::auth::AuthContext,
+    services::access_request_service::{
+        AccessRequest, AccessRequestQuery, AccessRequestStatus, NewAccessRequest,
+    },
+    state::AppState,
+};
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Deserialize, Debug)]
+pub struct ListAccessRequestsQuery {
+    /// 页码，从1开始
+    #[serde(default = "default_page")]
+    pub page: u32,
+    /// 每页数量，最多100条
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+    pub status: Option<AccessRequestStatus>,
+    pub role_id: Option<String>,
+    /// 申请人过滤 (查看自己的申请时忽略)
+    pub user_id: Option<String>,
+    /// 租户过滤 (租户管理员始终只能看到本租户的申请)
+    pub tenant_id: Option<String>,
+}
+
+fn default_page() -> u32 {
+    1
+}
+
+fn default_limit() -> u32 {
+    50
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CreateAccessRequestRequest {
+    pub role_id: String,
+    pub justification: String,
+    /// 批准后角色分配的有效期 (秒)，省略表示永久有效
+    pub duration_secs: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DecisionRequest {
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct AccessRequestListResponse {
+    pub data: Vec<AccessRequest>,
+    pub total: i64,
+    pub page: u32,
+    pub page_size: u32,
+}
+
+/// 申请和审批都以真实用户身份进行，模拟会话和客户端令牌不能使用
+fn acting_user(auth: &AuthContext) -> Result<String, AppError> {
+    if auth.actor_id.is_some() {
+        return Err(ServiceError::Forbidden(
+            "Access requests cannot be made or decided during an impersonation session".to_string(),
+        )
+        .into());
+    }
+    auth.user_id
+        .clone()
+        .ok_or_else(|| ServiceError::Unauthorized("User not authenticated".to_string()).into())
+}
+
+async fn list(
+    state: &AppState,
+    query: ListAccessRequestsQuery,
+    filter: AccessRequestQuery,
+) -> Result<Json<AccessRequestListResponse>, AppError> {
+    let page = query.page.max(1);
+    let page_size = query.limit.clamp(1, 100);
+    let result = state
+        .access_request_service
+        .list_requests(AccessRequestQuery {
+            page,
+            limit: page_size,
+            status: query.status,
+            role_id: query.role_id,
+            ..filter
+        })
+        .await?;
+    Ok(Json(AccessRequestListResponse {
+        data: result.data,
+        total: result.total,
+        page,
+        page_size,
+    }))
+}
+
+/// 申请角色
+/// POST /api/v2/users/me/access-requests
+pub async fn create_my_request(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    Json(payload): Json<CreateAccessRequestRequest>,
+) -> Result<Json<AccessRequest>, AppError> {
+    let user_id = acting_user(&auth)?;
+    let request = state
+        .access_request_service
+        .create_request(NewAccessRequest {
+            user_id,
+            role_id: payload.role_id,
+            justification: payload.justification,
+            duration_secs: payload.duration_secs,
+        })
+        .await?;
+    Ok(Json(request))
+}
+
+/// 列出自己的申请
+/// GET /api/v2/users/me/access-requests?status=pending
+pub async fn list_my_requests(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListAccessRequestsQuery>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<Json<AccessRequestListResponse>, AppError> {
+    let user_id = auth
+        .user_id
+        .clone()
+        .ok_or_else(|| ServiceError::Unauthorized("User not authenticated".to_string()))?;
+    let filter = AccessRequestQuery {
+        user_id: Some(user_id),
+        ..Default::default()
+    };
+    list(&state, query, filter).await
+}
+
+/// 撤回自己待审批的申请
+/// POST /api/v2/users/me/access-requests/:request_id/cancel
+pub async fn cancel_my_request(
+    State(state): State<Arc<AppState>>,
+    Path(request_id): Path<String>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<Json<AccessRequest>, AppError> {
+    let user_id = acting_user(&auth)?;
+    let request = state
+        .access_request_service
+        .cancel_request(&request_id, &user_id)
+        .await?;
+    Ok(Json(request))
+}
+
+/// 列出由请求方审批的申请
+/// GET /api/v2/access-requests?status=pending
+pub async fn list_approvals(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListAccessRequestsQuery>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<Json<AccessRequestListResponse>, AppError> {
+    let approver_id = acting_user(&auth)?;
+    let filter = AccessRequestQuery {
+        user_id: query.user_id.clone(),
+        approver_id: Some(approver_id),
+        tenant_id: auth.tenant_id.clone(),
+        ..Default::default()
+    };
+    list(&state, query, filter).await
+}
+
+/// 批准申请，申请人获得角色
+/// POST /api/v2/access-requests/:request_id/approve
+pub async fn approve_request(
+    State(state): State<Arc<AppState>>,
+    Path(request_id): Path<String>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    Json(payload): Json<DecisionRequest>,
+) -> Result<Json<AccessRequest>, AppError> {
+    let approver_id = acting_user(&auth)?;
+    let request = state
+        .access_request_service
+        .approve_request(&request_id, &approver_id, payload.comment)
+        .await?;
+    Ok(Json(request))
+}
+
+/// 拒绝申请
+/// POST /api/v2/access-requests/:request_id/reject
+pub async fn reject_request(
+    State(state): State<Arc<AppState>>,
+    Path(request_id): Path<String>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    Json(payload): Json<DecisionRequest>,
+) -> Result<Json<AccessRequest>, AppError> {
+    let approver_id = acting_user(&auth)?;
+    let request = state
+        .access_request_service
+        .reject_request(&request_id, &approver_id, payload.comment)
+        .await?;
+    Ok(Json(request))
+}
+
+/// 管理员查看所有申请
+/// GET /api/v2/admin/access-requests?status=approved&role_id=xxx&user_id=xxx
+pub async fn list_access_requests(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListAccessRequestsQuery>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<Json<AccessRequestListResponse>, AppError> {
+    let filter = AccessRequestQuery {
+        user_id: query.user_id.clone(),
+        tenant_id: auth.effective_tenant(query.tenant_id.clone()),
+        ..Default::default()
+    };
+    list(&state, query, filter).await
+}
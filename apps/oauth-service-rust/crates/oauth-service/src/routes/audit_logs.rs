@@ -34,6 +34,8 @@ pub struct ListAuditLogsQuery {
     pub start_date: Option<String>,
     /// 结束日期 (ISO 8601 格式，例如：2025-12-31T23:59:59Z)
     pub end_date: Option<String>,
+    /// 租户过滤 (租户管理员始终只能看到本租户的日志)
+    pub tenant_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -49,6 +51,8 @@ pub struct ExportQuery {
     pub action: Option<String>,
     /// 用户ID过滤
     pub user_id: Option<String>,
+    /// 租户过滤
+    pub tenant_id: Option<String>,
 }
 
 fn default_page() -> u32 {
@@ -86,6 +90,7 @@ pub struct AuditLogResponseEntry {
     pub status: String,
     pub ip_address: Option<String>,
     pub user_agent: Option<String>,
+    pub tenant_id: Option<String>,
 }
 
 /// 获取审计日志列表 - 支持分页和过滤
@@ -93,7 +98,7 @@ pub struct AuditLogResponseEntry {
 pub async fn list_audit_logs(
     State(state): State<Arc<AppState>>,
     Query(query): Query<ListAuditLogsQuery>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
 ) -> Result<Json<AuditLogResponse>, AppError> {
     let audit_query = AuditLogQuery {
         page: query.page,
@@ -103,6 +108,7 @@ pub async fn list_audit_logs(
         resource_type: query.resource_type,
         start_date: query.start_date,
         end_date: query.end_date,
+        tenant_id: auth.effective_tenant(query.tenant_id),
     };
 
     let result = state.audit_log_service.list_audit_logs(audit_query).await?;
@@ -123,6 +129,7 @@ pub async fn list_audit_logs(
             status: log.status,
             ip_address: log.ip_address,
             user_agent: log.user_agent,
+            tenant_id: log.tenant_id,
         })
         .collect();
 
@@ -140,7 +147,7 @@ pub async fn list_audit_logs(
 pub async fn export_audit_logs(
     State(state): State<Arc<AppState>>,
     Query(query): Query<ExportQuery>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
 ) -> Result<impl IntoResponse, AppError> {
     let format = query.format.to_lowercase();
 
@@ -152,6 +159,7 @@ pub async fn export_audit_logs(
         resource_type: None,
         start_date: query.start_date,
         end_date: query.end_date,
+        tenant_id: auth.effective_tenant(query.tenant_id),
     };
 
     let logs = state.audit_log_service.export_audit_logs(audit_query).await?;
@@ -174,6 +182,7 @@ pub async fn export_audit_logs(
                 status: log.status,
                 ip_address: log.ip_address,
                 user_agent: log.user_agent,
+                tenant_id: log.tenant_id,
             })
             .collect();
         Ok((StatusCode::OK, Json(entries)).into_response())
@@ -183,12 +192,12 @@ pub async fn export_audit_logs(
 /// 将审计日志转换为CSV格式
 fn export_as_csv(logs: Vec<crate::services::audit_log_service::AuditLogEntry>) -> impl IntoResponse {
     let mut csv = String::from(
-        "ID,Timestamp,User ID,Actor Type,Actor ID,Action,Resource Type,Resource ID,Details,Status,IP Address,User Agent\n",
+        "ID,Timestamp,User ID,Actor Type,Actor ID,Action,Resource Type,Resource ID,Details,Status,IP Address,User Agent,Tenant ID\n",
     );
 
     for log in logs {
         csv.push_str(&format!(
-            "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
+            "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
             escape_csv(&log.id),
             escape_csv(&log.timestamp),
             log.user_id.as_deref().unwrap_or(""),
@@ -201,6 +210,7 @@ fn export_as_csv(logs: Vec<crate::services::audit_log_service::AuditLogEntry>) -
             escape_csv(&log.status),
             log.ip_address.as_deref().unwrap_or(""),
             log.user_agent.as_deref().unwrap_or(""),
+            log.tenant_id.as_deref().unwrap_or(""),
         ));
     }
 
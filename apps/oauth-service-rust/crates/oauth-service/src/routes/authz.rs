@@ -0,0 +1,165 @@
+// 授权决策 API: 资源服务器和前端询问主体能否执行某个操作
+use crate::{
+    error::{AppError, AuthError, ServiceError},
+    middleware::auth::AuthContext,
+    services::authz_service::{
+        AuthzCheck, AuthzDecision, Subject, SubjectType, AUTHZ_CHECK_PERMISSION,
+    },
+    state::AppState,
+    utils::permission_conditions::AccessContext,
+};
+use axum::{
+    extract::{ConnectInfo, State},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+/// 要检查的权限: 完整的权限名，或资源和操作 (组合为 `resource:action`)
+#[derive(Deserialize, Debug)]
+pub struct PermissionQuery {
+    pub permission: Option<String>,
+    pub resource: Option<String>,
+    pub action: Option<String>,
+}
+
+impl PermissionQuery {
+    fn permission_name(&self) -> Result<String, ServiceError> {
+        match (&self.permission, &self.resource, &self.action) {
+            (Some(permission), None, None) if !permission.is_empty() => Ok(permission.clone()),
+            (None, Some(resource), Some(action)) if !resource.is_empty() && !action.is_empty() => {
+                Ok(format!("{resource}:{action}"))
+            }
+            _ => Err(ServiceError::ValidationError(
+                "Either permission or resource and action must be provided".to_string(),
+            )),
+        }
+    }
+}
+
+/// 调用方可以提供的请求上下文，用于求值权限的附加条件
+///
+/// 请求时间和用户属性 (organization、department 等) 总是以服务端为准，不接受调用方提供。
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct CheckContext {
+    pub ip_address: Option<IpAddr>,
+    pub client_id: Option<String>,
+}
+
+impl From<CheckContext> for AccessContext {
+    fn from(context: CheckContext) -> Self {
+        AccessContext {
+            ip_address: context.ip_address,
+            client_id: context.client_id,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CheckRequest {
+    /// 被检查的主体，省略时为请求方自身
+    pub subject: Option<Subject>,
+    #[serde(flatten)]
+    pub query: PermissionQuery,
+    /// 请求上下文，用于求值权限的附加条件
+    pub context: Option<CheckContext>,
+    /// 是否总是返回授予该权限的角色
+    #[serde(default)]
+    pub explain: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BatchCheckRequest {
+    pub subject: Option<Subject>,
+    pub checks: Vec<PermissionQuery>,
+    pub context: Option<CheckContext>,
+    #[serde(default)]
+    pub explain: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BatchCheckResponse {
+    pub results: Vec<AuthzDecision>,
+}
+
+/// 构建检查请求: 检查其他主体需要 `authz:check` 权限
+///
+/// 检查请求方自身时，客户端和来源 IP 总是取自本次请求，忽略调用方提供的值，
+/// 否则请求方可以声称来自办公网络或其他客户端，得到自己实际无法使用的条件授予。
+fn build_check(
+    auth: &AuthContext,
+    subject: Option<Subject>,
+    context: Option<CheckContext>,
+    explain: bool,
+    ip_address: Option<IpAddr>,
+) -> Result<AuthzCheck, AppError> {
+    let caller = match &auth.user_id {
+        Some(user_id) => Subject {
+            subject_type: SubjectType::User,
+            id: user_id.clone(),
+        },
+        None => Subject {
+            subject_type: SubjectType::Client,
+            id: auth.client_id.clone(),
+        },
+    };
+    let subject = subject.unwrap_or_else(|| caller.clone());
+    let mut context = AccessContext::from(context.unwrap_or_default());
+    if subject == caller {
+        context.client_id = Some(auth.client_id.clone());
+        context.ip_address = ip_address;
+    } else if !auth.permissions.iter().any(|p| p == AUTHZ_CHECK_PERMISSION) {
+        return Err(AuthError::InsufficientPermissions.into());
+    }
+
+    Ok(AuthzCheck {
+        subject,
+        caller_tenant: auth.tenant_id.clone(),
+        context,
+        explain,
+    })
+}
+
+/// 检查主体能否执行一个操作
+pub async fn check(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Json(payload): Json<CheckRequest>,
+) -> Result<Json<AuthzDecision>, AppError> {
+    let permission = payload.query.permission_name()?;
+    let check = build_check(
+        &auth,
+        payload.subject,
+        payload.context,
+        payload.explain,
+        connect_info.map(|ConnectInfo(addr)| addr.ip()),
+    )?;
+    Ok(Json(state.authz_service.check(&check, &permission).await?))
+}
+
+/// 一次检查主体的多个权限，结果按请求顺序返回
+pub async fn check_batch(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Json(payload): Json<BatchCheckRequest>,
+) -> Result<Json<BatchCheckResponse>, AppError> {
+    let permissions = payload
+        .checks
+        .iter()
+        .map(PermissionQuery::permission_name)
+        .collect::<Result<Vec<_>, _>>()?;
+    let check = build_check(
+        &auth,
+        payload.subject,
+        payload.context,
+        payload.explain,
+        connect_info.map(|ConnectInfo(addr)| addr.ip()),
+    )?;
+    let results = state.authz_service.check_many(&check, &permissions).await?;
+    Ok(Json(BatchCheckResponse { results }))
+}
@@ -3,6 +3,8 @@ use crate::{
     error::{AppError, ServiceError},
     middleware::auth::AuthContext,
     models::client::OAuthClientDetails,
+    routes::users::{revocation_actor, RevokeTokensRequest},
+    services::token_revocation_service::RevocationSummary,
     state::AppState,
 };
 use axum::{
@@ -21,12 +23,23 @@ pub struct CreateClientRequest {
     pub response_types: Vec<String>,
     pub allowed_scopes: Vec<String>,
     pub client_permissions: Option<Vec<String>>,
+    /// 访问令牌有效期（秒），未提供时使用 `auth.token.access_ttl` 配置
+    #[serde(default)]
+    pub access_token_ttl: Option<i64>,
+    /// 刷新令牌有效期（秒），未提供时使用 `auth.token.refresh_ttl` 配置
+    #[serde(default)]
+    pub refresh_token_ttl: Option<i64>,
+    /// 所属租户，为空表示平台级客户端；租户管理员创建的客户端始终属于其租户
+    #[serde(default)]
+    pub tenant_id: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct ListClientsQuery {
     pub limit: Option<i32>,
     pub offset: Option<i32>,
+    /// 只列出该租户的客户端 (租户管理员始终只能看到本租户)
+    pub tenant_id: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -47,6 +60,7 @@ pub struct ClientResponse {
     pub redirect_uris: Vec<String>,
     pub grant_types: Vec<String>,
     pub allowed_scopes: Vec<String>,
+    pub tenant_id: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -67,19 +81,39 @@ impl From<OAuthClientDetails> for ClientResponse {
             redirect_uris: details.redirect_uris,
             grant_types: details.grant_types,
             allowed_scopes: details.allowed_scopes,
+            tenant_id: details.client.tenant_id,
         }
     }
 }
 
+/// 查找请求方可见的客户端，其他租户的客户端视为不存在
+async fn find_client_in_scope(
+    state: &AppState,
+    auth: &AuthContext,
+    client_id: &str,
+) -> Result<OAuthClientDetails, AppError> {
+    state
+        .client_service
+        .find_by_client_id(client_id)
+        .await?
+        .filter(|client| auth.can_access_tenant(client.client.tenant_id.as_deref()))
+        .ok_or_else(|| ServiceError::NotFound("Client not found".to_string()).into())
+}
+
 pub async fn list_clients(
     State(state): State<Arc<AppState>>,
     Query(query): Query<ListClientsQuery>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
 ) -> Result<Json<Vec<ClientResponse>>, AppError> {
-    let clients = state
-        .client_service
-        .list_clients(query.limit, query.offset)
-        .await?;
+    let clients = match auth.effective_tenant(query.tenant_id) {
+        Some(tenant_id) => {
+            state
+                .client_service
+                .list_clients_in_tenant(&tenant_id, query.limit, query.offset)
+                .await?
+        }
+        None => state.client_service.list_clients(query.limit, query.offset).await?,
+    };
 
     let response: Vec<ClientResponse> = clients.into_iter().map(Into::into).collect();
 
@@ -88,12 +122,26 @@ pub async fn list_clients(
 
 pub async fn create_client(
     State(state): State<Arc<AppState>>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
     Json(payload): Json<CreateClientRequest>,
 ) -> Result<Json<ClientWithSecretResponse>, AppError> {
     if payload.name.trim().is_empty() {
         return Err(ServiceError::ValidationError("Client name is required".to_string()).into());
     }
+    if [payload.access_token_ttl, payload.refresh_token_ttl]
+        .iter()
+        .flatten()
+        .any(|ttl| *ttl <= 0)
+    {
+        return Err(ServiceError::ValidationError("Token TTL must be positive".to_string()).into());
+    }
+
+    // 未指定令牌有效期时使用当前运行时配置的默认值
+    let settings = state.system_config_service.current();
+    let mut payload = payload;
+    payload.access_token_ttl.get_or_insert(settings.access_token_ttl);
+    payload.refresh_token_ttl.get_or_insert(settings.refresh_token_ttl);
+    payload.tenant_id = auth.effective_tenant(payload.tenant_id);
 
     let (client_details, plain_secret) = state.client_service.create_client(payload).await?;
 
@@ -106,13 +154,9 @@ pub async fn create_client(
 pub async fn get_client(
     State(state): State<Arc<AppState>>,
     Path(client_id): Path<String>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
 ) -> Result<Json<ClientResponse>, AppError> {
-    let client = state
-        .client_service
-        .find_by_client_id(&client_id)
-        .await?
-        .ok_or_else(|| ServiceError::NotFound("Client not found".to_string()))?;
+    let client = find_client_in_scope(&state, &auth, &client_id).await?;
 
     Ok(Json(client.into()))
 }
@@ -120,9 +164,10 @@ pub async fn get_client(
 pub async fn update_client(
     State(state): State<Arc<AppState>>,
     Path(client_id): Path<String>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
     Json(payload): Json<UpdateClientRequest>,
 ) -> Result<Json<ClientResponse>, AppError> {
+    find_client_in_scope(&state, &auth, &client_id).await?;
     let updated_client = state
         .client_service
         .update_client(
@@ -140,8 +185,9 @@ pub async fn update_client(
 pub async fn delete_client(
     State(state): State<Arc<AppState>>,
     Path(client_id): Path<String>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
 ) -> Result<Json<serde_json::Value>, AppError> {
+    find_client_in_scope(&state, &auth, &client_id).await?;
     state.client_service.delete_client(&client_id).await?;
 
     Ok(Json(serde_json::json!({
@@ -149,3 +195,20 @@ pub async fn delete_client(
         "client_id": client_id
     })))
 }
+
+/// 吊销客户端签发的全部令牌和会话 (如客户端密钥泄露)
+/// POST /api/v2/admin/clients/:client_id/revoke-tokens
+pub async fn revoke_client_tokens(
+    State(state): State<Arc<AppState>>,
+    Path(client_id): Path<String>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    payload: Option<Json<RevokeTokensRequest>>,
+) -> Result<Json<RevocationSummary>, AppError> {
+    find_client_in_scope(&state, &auth, &client_id).await?;
+    let reason = payload.and_then(|Json(payload)| payload.reason);
+    let summary = state
+        .token_revocation_service
+        .revoke_client(&client_id, &revocation_actor(&auth), reason)
+        .await?;
+    Ok(Json(summary))
+}
@@ -1,13 +1,16 @@
 #![allow(clippy::uninlined_format_args)]
 use crate::error::{AppError, ServiceError};
+use crate::services::scope_service::DEFAULT_SCOPE_LOCALE;
 use crate::state::AppState;
+use crate::utils::permission_conditions::AccessContext;
 use axum::{
-    extract::{Json as JsonExtractor, Query, State},
+    extract::{ConnectInfo, Json as JsonExtractor, Query, State},
     http::HeaderMap,
     response::Json,
 };
 use axum_extra::extract::cookie::CookieJar;
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 // --- Request/Response Structs ---
@@ -22,6 +25,10 @@ pub struct ConsentInfoRequest {
     pub code_challenge: Option<String>,
     pub code_challenge_method: Option<String>,
     pub nonce: Option<String>,
+    /// 目标资源服务器 (RFC 8707)
+    pub resource: Option<String>,
+    /// 同意文案的语言环境，未提供时使用 Accept-Language 请求头
+    pub locale: Option<String>,
 }
 
 #[derive(Serialize, Debug)]
@@ -35,7 +42,11 @@ pub struct ClientInfo {
 #[derive(Serialize, Debug)]
 pub struct ScopeInfo {
     pub name: String,
+    pub display_name: String,
     pub description: String,
+    pub risk_level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
 }
 
 #[derive(Serialize, Debug)]
@@ -61,6 +72,8 @@ pub struct ConsentInfoResponse {
     pub code_challenge_method: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub nonce: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
     pub consent_form_action_url: String,
 }
 
@@ -79,6 +92,8 @@ pub struct ConsentSubmitRequest {
     pub code_challenge_method: Option<String>,
     #[serde(default)]
     pub nonce: Option<String>,
+    #[serde(default)]
+    pub resource: Option<String>,
 }
 
 #[derive(Serialize, Debug)]
@@ -109,6 +124,7 @@ pub async fn get_consent_info(
     jar: CookieJar,
     Query(request): Query<ConsentInfoRequest>,
     headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
 ) -> Result<Json<ConsentInfoResponse>, AppError> {
     // 1. 验证用户已认证
     let user_id = super::oauth::extract_user_id_from_request(&state, &jar, &headers).await?;
@@ -128,9 +144,14 @@ pub async fn get_consent_info(
 
     // 2. 检查用户是否有权限使用 OAuth 同意流程
     // 防止权限提升攻击：确保用户被授权使用此功能
+    let access_context = AccessContext {
+        ip_address: connect_info.map(|ConnectInfo(addr)| addr.ip()),
+        client_id: Some(request.client_id.clone()),
+        ..Default::default()
+    };
     let has_oauth_permission = state
         .rbac_service
-        .has_permission(&user_id, "oauth:consent")
+        .has_permission(&user_id, "oauth:consent", &access_context)
         .await
         .unwrap_or(false);
 
@@ -151,22 +172,35 @@ pub async fn get_consent_info(
     // 4. 验证重定向URI
     crate::utils::validation::validate_redirect_uri(&request.redirect_uri, &client_details.redirect_uris)?;
 
-    // 5. 验证scope
+    // 5. 验证scope 和目标资源
     crate::utils::validation::validate_scope(&request.scope, &client_details.allowed_scopes)?;
-
-    // 5. 构建权限范围信息
-    let scopes: Vec<&str> = request.scope.split_whitespace().collect();
-    let requested_scopes = scopes
-        .iter()
+    state
+        .resource_server_service
+        .resolve_resource(request.resource.as_deref(), &request.scope)
+        .await?;
+
+    // 5. 构建权限范围信息 (按语言环境从权限范围注册表读取同意文案)
+    let locale = request
+        .locale
+        .clone()
+        .or_else(|| preferred_locale(&headers))
+        .unwrap_or_else(|| DEFAULT_SCOPE_LOCALE.to_string());
+    let requested_scopes = state
+        .scope_service
+        .get_consent_scopes(&request.scope, &locale)
+        .await?
+        .into_iter()
         .map(|scope| ScopeInfo {
-            name: scope.to_string(),
-            description: get_scope_description(scope),
+            name: scope.name,
+            display_name: scope.display_name,
+            description: scope.description,
+            risk_level: scope.risk_level,
+            category: scope.category,
         })
         .collect();
 
     // 6. 获取Admin Portal URL
-    let admin_portal_url = std::env::var("NEXT_PUBLIC_ADMIN_PORTAL_URL")
-        .unwrap_or_else(|_| "http://localhost:3002".to_string());
+    let admin_portal_url = state.system_config_service.current().admin_portal_url.clone();
 
     // 返回同意信息
     Ok(Json(ConsentInfoResponse {
@@ -188,6 +222,7 @@ pub async fn get_consent_info(
         code_challenge: request.code_challenge,
         code_challenge_method: request.code_challenge_method,
         nonce: request.nonce,
+        resource: request.resource,
         consent_form_action_url: format!("{}/api/v2/oauth/consent/submit", admin_portal_url),
     }))
 }
@@ -217,6 +252,7 @@ pub async fn submit_consent(
     State(state): State<Arc<AppState>>,
     jar: CookieJar,
     headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     JsonExtractor(request): JsonExtractor<ConsentSubmitRequest>,
 ) -> Result<Json<ConsentSubmitResponse>, AppError> {
     // 1. 验证用户已认证
@@ -236,9 +272,14 @@ pub async fn submit_consent(
 
     // 2. 检查用户是否有权限使用 OAuth 同意流程
     // 防止权限提升攻击：确保用户被授权使用此功能
+    let access_context = AccessContext {
+        ip_address: connect_info.map(|ConnectInfo(addr)| addr.ip()),
+        client_id: Some(request.client_id.clone()),
+        ..Default::default()
+    };
     let has_oauth_permission = state
         .rbac_service
-        .has_permission(&user_id, "oauth:consent")
+        .has_permission(&user_id, "oauth:consent", &access_context)
         .await
         .unwrap_or(false);
 
@@ -259,8 +300,12 @@ pub async fn submit_consent(
     // 4. 验证重定向URI和scope
     crate::utils::validation::validate_redirect_uri(&request.redirect_uri, &client_details.redirect_uris)?;
 
-    // 5. 验证scope
+    // 5. 验证scope 和目标资源
     crate::utils::validation::validate_scope(&request.scope, &client_details.allowed_scopes)?;
+    state
+        .resource_server_service
+        .resolve_resource(request.resource.as_deref(), &request.scope)
+        .await?;
 
     // 5. 处理同意决定
     let mut redirect_url = url::Url::parse(&request.redirect_uri)
@@ -283,6 +328,7 @@ pub async fn submit_consent(
             code_challenge: request.code_challenge.unwrap_or_default(),
             code_challenge_method: request.code_challenge_method.unwrap_or_else(|| "S256".to_string()),
             nonce: request.nonce.clone(),
+            resource: request.resource.clone(),
         };
 
         // 生成授权码
@@ -335,8 +381,14 @@ pub async fn submit_consent(
     }))
 }
 
-/// 获取权限范围的中文描述
-/// 使用 scopes 模块获取权限元数据
-fn get_scope_description(scope: &str) -> String {
-    crate::utils::scopes::get_scope_description_cn(scope)
+/// 从 Accept-Language 请求头中取出优先级最高的语言环境
+fn preferred_locale(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(axum::http::header::ACCEPT_LANGUAGE)?
+        .to_str()
+        .ok()?
+        .split(',')
+        .map(|tag| tag.split(';').next().unwrap_or("").trim())
+        .find(|tag| !tag.is_empty() && *tag != "*")
+        .map(str::to_string)
 }
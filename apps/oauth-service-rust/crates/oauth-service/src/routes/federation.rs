@@ -0,0 +1,110 @@
+// 联合登录 (上游 OIDC 身份提供方) 端点
+use crate::{
+    error::{AppError, ServiceError},
+    metrics,
+    routes::oauth::issue_session_cookie,
+    services::federation_service::FederatedProvider,
+    state::AppState,
+};
+use axum::{
+    extract::{Path, Query, State},
+    response::{Html, IntoResponse, Json, Redirect, Response},
+};
+use axum_extra::extract::cookie::CookieJar;
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize, Debug)]
+pub struct FederatedLoginQuery {
+    /// 登录完成后返回的地址 (通常是原始的 /authorize 请求)
+    pub redirect: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FederatedCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+/// 列出可用的身份提供方，供登录页面展示
+pub async fn list_providers(State(state): State<Arc<AppState>>) -> Json<Vec<FederatedProvider>> {
+    Json(state.federation_service.providers())
+}
+
+/// 跳转到上游身份提供方登录
+pub async fn federated_login(
+    State(state): State<Arc<AppState>>,
+    Path(provider_id): Path<String>,
+    Query(query): Query<FederatedLoginQuery>,
+) -> Result<Redirect, AppError> {
+    // 与密码登录相同，只允许站内路径或 `auth.login.redirect_origins` 中配置的源
+    let redirect = query
+        .redirect
+        .map(|redirect| redirect.trim().to_string())
+        .filter(|redirect| !redirect.is_empty());
+    if let Some(redirect) = &redirect {
+        if !state.system_config_service.current().is_login_redirect_allowed(redirect) {
+            return Err(ServiceError::ValidationError("无效的重定向 URL".to_string()).into());
+        }
+    }
+
+    let authorization_url = state
+        .federation_service
+        .begin_login(&provider_id, redirect)
+        .await?;
+    Ok(Redirect::to(&authorization_url))
+}
+
+/// 处理上游回调：登录成功后设置 session_token 并返回发起登录时的地址
+pub async fn federated_callback(
+    State(state): State<Arc<AppState>>,
+    jar: CookieJar,
+    Path(provider_id): Path<String>,
+    Query(query): Query<FederatedCallbackQuery>,
+) -> Result<Response, AppError> {
+    if let Some(error) = &query.error {
+        metrics::record_login(false);
+        tracing::warn!(
+            "Identity provider '{}' returned error: {} {}",
+            provider_id,
+            error,
+            query.error_description.as_deref().unwrap_or_default()
+        );
+        return Err(ServiceError::Unauthorized(format!("Identity provider returned error: {error}")).into());
+    }
+    let (Some(code), Some(login_state)) = (&query.code, &query.state) else {
+        return Err(ServiceError::ValidationError("Missing code or state".to_string()).into());
+    };
+
+    let login = state
+        .federation_service
+        .complete_login(&provider_id, code, login_state)
+        .await;
+    metrics::record_login(login.is_ok());
+    let login = login?;
+
+    let cookie = issue_session_cookie(&state, &login.user.id).await?;
+    state.user_service.update_last_login(&login.user.id).await?;
+
+    tracing::info!(
+        "Federated login via {} for user: {}{}",
+        login.provider_id,
+        login.user.username,
+        if login.created { " (provisioned)" } else { "" }
+    );
+
+    let redirect_url = login
+        .redirect
+        .unwrap_or_else(|| state.system_config_service.current().admin_portal_url.clone());
+
+    // 回调请求由 IdP 发起 (跨站)，SameSite=Strict 的 session_token 在同一跳转链中不会被发送，
+    // 因此通过页面内跳转回到原地址，使后续请求成为同站请求
+    let target = html_escape::encode_double_quoted_attribute(&redirect_url);
+    let page = Html(format!(
+        "<!DOCTYPE html><html><head><meta http-equiv=\"refresh\" content=\"0;url={target}\"></head>\
+         <body><a href=\"{target}\">Continue</a></body></html>"
+    ));
+    Ok((jar.add(cookie), page).into_response())
+}
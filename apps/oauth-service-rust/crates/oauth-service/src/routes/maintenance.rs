@@ -0,0 +1,24 @@
+// 数据维护管理 API
+use crate::{
+    error::AppError,
+    middleware::auth::AuthContext,
+    services::maintenance_service::{MaintenanceReport, MaintenanceTrigger},
+    state::AppState,
+};
+use axum::{extract::State, response::Json};
+use std::sync::Arc;
+
+/// 立即执行一次数据维护 (与定时任务相同：清理过期数据并整理限流器)
+pub async fn run_maintenance(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<Json<MaintenanceReport>, AppError> {
+    let report = state
+        .maintenance_service
+        .run(MaintenanceTrigger::Admin {
+            user_id: auth.user_id,
+            client_id: auth.client_id,
+        })
+        .await?;
+    Ok(Json(report))
+}
@@ -0,0 +1,176 @@
+// 菜单管理 API
+use crate::{
+    error::{AppError, ServiceError},
+    middleware::auth::AuthContext,
+    models::menu::{Menu, MenuNode},
+    state::AppState,
+};
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use serde::{Deserialize, Deserializer};
+use std::sync::Arc;
+
+#[derive(Deserialize, Debug)]
+pub struct CreateMenuRequest {
+    pub name: String,
+    pub key: String,
+    pub path: Option<String>,
+    pub component: Option<String>,
+    pub icon: Option<String>,
+    pub order: Option<i32>,
+    pub is_hidden: Option<bool>,
+    pub is_active: Option<bool>,
+    pub parent_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct UpdateMenuRequest {
+    pub name: Option<String>,
+    pub key: Option<String>,
+    /// 省略表示不修改，传 null 表示清空
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub path: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub component: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub icon: Option<Option<String>>,
+    pub order: Option<i32>,
+    pub is_hidden: Option<bool>,
+    pub is_active: Option<bool>,
+    /// 新的父菜单ID，传空字符串表示移动到顶级
+    pub parent_id: Option<String>,
+}
+
+/// 区分字段缺省 (`None`) 与显式 null (`Some(None)`)
+fn deserialize_nullable<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Option<String>>, D::Error> {
+    Option::<String>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SetMenuPermissionsRequest {
+    pub permission_ids: Vec<String>,
+}
+
+/// 列出所有菜单 (扁平列表)
+pub async fn list_menus(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<Vec<Menu>>, AppError> {
+    let menus = state.menu_service.list_menus().await?;
+    Ok(Json(menus))
+}
+
+/// 获取完整菜单树
+pub async fn get_menu_tree(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<Vec<MenuNode>>, AppError> {
+    let tree = state.menu_service.get_menu_tree().await?;
+    Ok(Json(tree))
+}
+
+/// 创建菜单
+pub async fn create_menu(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+    Json(payload): Json<CreateMenuRequest>,
+) -> Result<Json<Menu>, AppError> {
+    // 验证输入
+    if payload.name.trim().is_empty() || payload.key.trim().is_empty() {
+        return Err(
+            ServiceError::ValidationError("Menu name and key are required".to_string()).into(),
+        );
+    }
+
+    let menu = state.menu_service.create_menu(payload).await?;
+    Ok(Json(menu))
+}
+
+/// 获取菜单详情
+pub async fn get_menu(
+    State(state): State<Arc<AppState>>,
+    Path(menu_id): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<Menu>, AppError> {
+    let menu = state
+        .menu_service
+        .find_menu_by_id(&menu_id)
+        .await?
+        .ok_or_else(|| ServiceError::NotFound("Menu not found".to_string()))?;
+
+    Ok(Json(menu))
+}
+
+/// 更新菜单
+pub async fn update_menu(
+    State(state): State<Arc<AppState>>,
+    Path(menu_id): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+    Json(payload): Json<UpdateMenuRequest>,
+) -> Result<Json<Menu>, AppError> {
+    let menu = state.menu_service.update_menu(&menu_id, payload).await?;
+    Ok(Json(menu))
+}
+
+/// 删除菜单
+pub async fn delete_menu(
+    State(state): State<Arc<AppState>>,
+    Path(menu_id): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    state.menu_service.delete_menu(&menu_id).await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Menu deleted successfully",
+        "menu_id": menu_id
+    })))
+}
+
+/// 获取访问菜单所需的权限
+pub async fn get_menu_permissions(
+    State(state): State<Arc<AppState>>,
+    Path(menu_id): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let permissions = state.menu_service.get_menu_permissions(&menu_id).await?;
+
+    Ok(Json(serde_json::json!({
+        "menu_id": menu_id,
+        "permissions": permissions
+    })))
+}
+
+/// 设置访问菜单所需的权限
+pub async fn set_menu_permissions(
+    State(state): State<Arc<AppState>>,
+    Path(menu_id): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+    Json(payload): Json<SetMenuPermissionsRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    state
+        .menu_service
+        .set_menu_permissions(&menu_id, payload.permission_ids)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Menu permissions updated successfully",
+        "menu_id": menu_id
+    })))
+}
+
+/// 获取当前用户可见的菜单树
+pub async fn get_current_user_menus(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<Json<Vec<MenuNode>>, AppError> {
+    let user_id = auth
+        .user_id
+        .ok_or_else(|| ServiceError::Unauthorized("User not authenticated".to_string()))?;
+
+    let tree = state.menu_service.get_user_menu_tree(&user_id).await?;
+    Ok(Json(tree))
+}
@@ -1,8 +1,19 @@
+pub mod access_requests;
 pub mod audit_logs;
+pub mod authz;
 pub mod clients;
 pub mod consent;
+pub mod federation;
+pub mod maintenance;
+pub mod menus;
 pub mod oauth;
 pub mod permissions;
+pub mod resource_servers;
 pub mod roles;
+pub mod scim;
+pub mod scopes;
+pub mod system_config;
 pub mod templates;
+pub mod tenants;
 pub mod users;
+pub mod webauthn;
@@ -1,8 +1,12 @@
 #![allow(clippy::uninlined_format_args)]
 use crate::error::{AppError, ServiceError};
+use crate::metrics;
 use crate::models::client::OAuthClientDetails;
+use crate::services::dpop_service::DpopRequest;
+use crate::services::tenant_service::can_use_client;
+use crate::services::webauthn_service::{Ceremony, CredentialRequestOptions};
 use crate::state::AppState;
-use crate::utils::{pkce, validation};
+use crate::utils::{dpop, pkce, validation};
 use axum::{
     extract::{Form, Json as JsonExtractor, Query, State},
     http::StatusCode,
@@ -27,7 +31,21 @@ pub struct LoginRequest {
 #[derive(Serialize, Debug)]
 pub struct LoginResponse {
     success: bool,
-    redirect_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redirect_url: Option<String>,
+    /// 需要通行密钥作为第二因素时返回的断言选项
+    #[serde(skip_serializing_if = "Option::is_none")]
+    passkey_challenge: Option<CredentialRequestOptions>,
+}
+
+impl LoginResponse {
+    pub(crate) fn success(redirect_url: String) -> Self {
+        Self {
+            success: true,
+            redirect_url: Some(redirect_url),
+            passkey_challenge: None,
+        }
+    }
 }
 
 // --- Token Endpoint Structs ---
@@ -43,6 +61,8 @@ pub struct TokenRequest {
     client_id: String,
     client_secret: Option<String>,
     scope: Option<String>,
+    /// Target resource server (RFC 8707)
+    resource: Option<String>,
 }
 
 #[derive(Serialize, Debug)]
@@ -66,6 +86,8 @@ pub struct AuthorizeRequest {
     pub code_challenge: String,
     pub code_challenge_method: String,
     pub nonce: Option<String>,
+    /// Target resource server (RFC 8707); becomes the access token's `aud`
+    pub resource: Option<String>,
 }
 
 // --- UserInfo Endpoint Structs ---
@@ -81,6 +103,11 @@ pub struct UserInfoResponse {
 #[derive(Deserialize, Debug)]
 pub struct IntrospectRequest {
     token: String,
+    /// The resource server introspecting the token authenticates as its registered client
+    client_id: String,
+    client_secret: Option<String>,
+    /// Audience the token must have; defaults to this service's issuer
+    resource: Option<String>,
 }
 
 #[derive(Serialize, Debug)]
@@ -96,6 +123,12 @@ pub struct IntrospectResponse {
     sub: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     exp: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tenant: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iss: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aud: Option<String>,
 }
 
 // --- Revoke Endpoint Structs ---
@@ -166,18 +199,11 @@ pub async fn login_endpoint(
     let validated_redirect = if let Some(ref redirect) = request.redirect {
         let url = redirect.trim();
         if !url.is_empty() {
-            // 允许的重定向源
-            let allowed_origins = [
-                "http://localhost:3002",
-                "http://localhost:3001",
-                "http://127.0.0.1:3002",
-                "http://127.0.0.1:3001",
-                "/",
-            ];
-
-            let is_valid = allowed_origins.iter().any(|origin| {
-                url.starts_with(origin)
-            }) || url.starts_with("/");
+            // 只允许站内路径或 `auth.login.redirect_origins` 中配置的源
+            let is_valid = state
+                .system_config_service
+                .current()
+                .is_login_redirect_allowed(url);
 
             if !is_valid {
                 return Err(ServiceError::ValidationError(
@@ -195,8 +221,8 @@ pub async fn login_endpoint(
     // 用验证后的重定向替换原来的
     request.redirect = validated_redirect;
 
-    // 1. 速率限制检查 - 5 次尝试每 5 分钟每 IP
-    // Rate limiting check - 5 attempts per 5 minutes per IP
+    // 1. 速率限制检查 - 默认 5 次尝试每 5 分钟每 IP (rate_limit.login.*)
+    // Rate limiting check - 5 attempts per 5 minutes per IP by default
     // Extract client IP from headers (X-Forwarded-For or X-Real-IP) or connection
     let client_ip = extract_client_ip(&headers)?;
 
@@ -208,58 +234,63 @@ pub async fn login_endpoint(
             client_ip,
             remaining
         );
+        metrics::record_rate_limit_rejection("login");
         return Err(ServiceError::RateLimitExceeded(
-            "Too many login attempts. Please try again in 5 minutes.".to_string(),
+            "Too many login attempts. Please try again later.".to_string(),
         ).into());
     }
 
     // 1. Authenticate the user
-    let user = state
+    let user = match state
         .user_service
         .authenticate(&request.username, &request.password)
-        .await?;
+        .await
+    {
+        Ok(user) => user,
+        Err(e) => {
+            metrics::record_login(false);
+            return Err(e.into());
+        }
+    };
 
-    // 2. Issue a short-lived access token to be used as the session token
-    let client = state.client_service.get_internal_client().await?;
-    let permissions = state.rbac_service.get_user_permissions(&user.id).await?;
-    let token_pair = state
-        .token_service
-        .issue_tokens(&client, Some(user.id), "session".to_string(), permissions, None)
-        .await?;
+    // 1.5 已注册通行密钥的用户还需要完成 WebAuthn 断言 (第二因素)，
+    // 此时不设置 session cookie，由 /api/v2/auth/webauthn/login 完成登录
+    if !state.webauthn_service.list_credentials(&user.id).await?.is_empty() {
+        let options = state
+            .webauthn_service
+            .start_authentication(Ceremony::SecondFactor, Some(&user.id), request.redirect.clone())
+            .await?;
+        tracing::info!("Password verified for user: {}, awaiting passkey", request.username);
+        return Ok((jar, Json(LoginResponse {
+            success: false,
+            redirect_url: None,
+            passkey_challenge: Some(options),
+        })));
+    }
+    metrics::record_login(true);
 
+    // 2. Issue a short-lived access token to be used as the session token
     // 3. Set the session cookie with enhanced security attributes
-    let is_production = std::env::var("NODE_ENV")
-        .unwrap_or_else(|_| "development".to_string()) == "production";
-
-    // 从环境变量读取 Cookie domain
-    // 本地开发使用 .localhost，生产环境使用实际域名
-    let cookie_domain = std::env::var("COOKIE_DOMAIN")
-        .unwrap_or_else(|_| {
-            if is_production {
-                tracing::warn!("COOKIE_DOMAIN not set in production! Using default .localhost. This may fail!");
-                ".localhost".to_string()
-            } else {
-                ".localhost".to_string()
-            }
-        });
-
-    // 显式设置 domain 属性，确保 cookie 跨子域正确工作
-    let session_cookie = Cookie::build(("session_token", token_pair.access_token))
-        .domain(cookie_domain)     // ✅ 显式设置 domain，避免浏览器推断失败
-        .path("/")
-        .http_only(true)           // ✅ Prevent XSS attacks - JavaScript cannot access this cookie
-        .secure(is_production)     // ✅ Enforce HTTPS in production
-        .same_site(SameSite::Strict) // ✅ CSRF protection - Strict is more secure than Lax
-        .max_age(time::Duration::hours(1)); // Session expires in 1 hour
-
-    let updated_jar = jar.add(session_cookie);
+    let updated_jar = jar.add(issue_session_cookie(&state, &user.id).await?);
 
     // 4. Return JSON response with redirect URL instead of 302 redirect
     // This ensures the Set-Cookie header is properly received by the browser
+    let redirect_url = login_redirect_url(&state, request.redirect.as_deref());
 
+    tracing::info!(
+        "Login successful for user: {}, redirecting to consent page: {}",
+        request.username,
+        redirect_url
+    );
+
+    Ok((updated_jar, Json(LoginResponse::success(redirect_url))))
+}
+
+/// 登录完成后前端跳转的地址：把原始 /authorize 请求中的 OAuth 参数带到 Admin Portal 的同意页面
+pub(crate) fn login_redirect_url(state: &AppState, redirect: Option<&str>) -> String {
     // 解析原始的 /authorize URL 来提取 OAuth 参数
     // Parse the original /authorize URL to extract OAuth parameters
-    let redirect_url = if let Some(auth_url) = &request.redirect {
+    if let Some(auth_url) = redirect {
         // 使用 url 库来解析 URL 和查询参数
         // Parse the authorize URL to extract query parameters
         if let Ok(parsed_url) = url::Url::parse(auth_url) {
@@ -268,10 +299,9 @@ pub async fn login_endpoint(
                 .map(|(k, v)| (k.to_string(), v.to_string()))
                 .collect();
 
-            // 获取 Admin Portal URL，默认使用 localhost:6188（通过 Pingora 代理）
-            // Get Admin Portal URL from environment or use default via Pingora proxy
-            let admin_portal_url = std::env::var("ADMIN_PORTAL_URL")
-                .unwrap_or_else(|_| "http://localhost:6188".to_string());
+            // 获取 Admin Portal URL，使用经 Pingora 代理的地址 (system.admin_portal_proxy_url)
+            // Get Admin Portal URL (via Pingora proxy) from runtime settings
+            let admin_portal_url = state.system_config_service.current().admin_portal_proxy_url.clone();
 
             // 构建指向 Admin Portal 同意页面的 URL
             // Construct URL to Admin Portal's consent page with OAuth parameters
@@ -305,37 +335,59 @@ pub async fn login_endpoint(
             if let Some(nonce) = params.get("nonce") {
                 query_parts.push(format!("nonce={}", urlencoding::encode(nonce)));
             }
+            if let Some(resource) = params.get("resource") {
+                query_parts.push(format!("resource={}", urlencoding::encode(resource)));
+            }
 
             let query_string = query_parts.join("&");
             format!("{}/oauth/consent?{}", admin_portal_url, query_string)
         } else {
             // 如果无法解析 URL，使用默认回退 (使用 Admin Portal URL)
             // If URL parsing fails, fall back to default
-            let admin_portal_url = std::env::var("ADMIN_PORTAL_URL")
-                .unwrap_or_else(|_| "http://localhost:6188".to_string());
+            let admin_portal_url = state.system_config_service.current().admin_portal_proxy_url.clone();
             format!("{}/oauth/consent", admin_portal_url)
         }
     } else {
         // 如果没有提供 redirect URL，默认指向同意页面 (使用 Admin Portal URL)
         // If no redirect URL provided, default to consent page
-        let admin_portal_url = std::env::var("ADMIN_PORTAL_URL")
-            .unwrap_or_else(|_| "http://localhost:6188".to_string());
+        let admin_portal_url = state.system_config_service.current().admin_portal_proxy_url.clone();
         format!("{}/oauth/consent", admin_portal_url)
-    };
-
-    tracing::info!(
-        "Login successful for user: {}, redirecting to consent page: {}",
-        request.username,
-        redirect_url
-    );
+    }
+}
 
-    Ok((updated_jar, Json(LoginResponse {
-        success: true,
-        redirect_url,
-    })))
+/// 为已认证的用户签发短期访问令牌作为会话令牌，返回对应的 session_token cookie
+pub(crate) async fn issue_session_cookie(
+    state: &AppState,
+    user_id: &str,
+) -> Result<Cookie<'static>, AppError> {
+    let client = state.client_service.get_internal_client().await?;
+    let permissions = state.rbac_service.get_user_permissions(user_id).await?;
+    let token_pair = state
+        .token_service
+        .issue_tokens(&client, Some(user_id.to_string()), "session".to_string(), permissions, None, None, None)
+        .await?;
+    Ok(session_cookie(state, token_pair.access_token))
 }
 
 
+/// Builds the `session_token` cookie for a freshly authenticated user.
+///
+/// Cookie domain 和 Secure 标志来自启动配置 (cookie.domain / cookie.secure)，
+/// 本地开发使用 .localhost，生产环境使用实际域名并开启 Secure
+pub(crate) fn session_cookie(state: &AppState, session_token: String) -> Cookie<'static> {
+    let cookie_config = &state.config.cookie;
+
+    // 显式设置 domain 属性，确保 cookie 跨子域正确工作
+    Cookie::build(("session_token", session_token))
+        .domain(cookie_config.domain.clone()) // ✅ 显式设置 domain，避免浏览器推断失败
+        .path("/")
+        .http_only(true)           // ✅ Prevent XSS attacks - JavaScript cannot access this cookie
+        .secure(cookie_config.secure) // ✅ Enforce HTTPS in production
+        .same_site(SameSite::Strict) // ✅ CSRF protection - Strict is more secure than Lax
+        .max_age(time::Duration::hours(1)) // Session expires in 1 hour
+        .build()
+}
+
 /// Handles `/api/v2/oauth/token`
 pub async fn token_endpoint(
     State(state): State<Arc<AppState>>,
@@ -347,6 +399,7 @@ pub async fn token_endpoint(
 
     if !state.token_rate_limiter.check_rate_limit(&client_ip.to_string()).await {
         tracing::warn!("Token endpoint rate limit exceeded for IP: {}", client_ip);
+        metrics::record_rate_limit_rejection("token");
         return Err(ServiceError::RateLimitExceeded(
             "Too many token requests. Please try again later.".to_string(),
         ).into());
@@ -357,12 +410,45 @@ pub async fn token_endpoint(
         .authenticate_client(&request.client_id, request.client_secret.as_deref())
         .await?;
 
-    match request.grant_type.as_str() {
-        "authorization_code" => handle_authorization_code_grant(state, client, request).await,
-        "refresh_token" => handle_refresh_token_grant(state, request).await,
-        "client_credentials" => handle_client_credentials_grant(state, client, request).await,
-        _ => Err(ServiceError::ValidationError("Unsupported grant type".to_string()).into()),
+    // RFC 9449: a DPoP proof binds the issued tokens to the client's key
+    let dpop_jkt = match headers.get(dpop::DPOP_HEADER) {
+        Some(proof) => {
+            let proof = proof.to_str().map_err(|_| {
+                ServiceError::InvalidDpopProof("Malformed DPoP header".to_string())
+            })?;
+            let token_uri = format!("{}/api/v2/oauth/token", state.config.issuer.trim_end_matches('/'));
+            let jkt = state
+                .dpop_service
+                .validate_proof(DpopRequest {
+                    proof,
+                    method: "POST",
+                    uri: &token_uri,
+                    access_token: None,
+                    require_nonce: true,
+                })
+                .await?;
+            Some(jkt)
+        }
+        None => None,
+    };
+
+    let grant_type = request.grant_type.clone();
+    let client_id = client.client.client_id.clone();
+    let response = match grant_type.as_str() {
+        "authorization_code" => {
+            handle_authorization_code_grant(state, client, request, dpop_jkt).await
+        }
+        "refresh_token" => handle_refresh_token_grant(state, request, dpop_jkt).await,
+        "client_credentials" => {
+            handle_client_credentials_grant(state, client, request, dpop_jkt).await
+        }
+        _ => return Err(ServiceError::ValidationError("Unsupported grant type".to_string()).into()),
+    };
+
+    if response.is_ok() {
+        metrics::record_tokens_issued(&grant_type, &client_id);
     }
+    response
 }
 
 /// Handles `/api/v2/oauth/authorize`
@@ -385,6 +471,10 @@ pub async fn authorize_endpoint(
     if !client_details.response_types.contains(&request.response_type) {
         return Err(ServiceError::ValidationError("Unsupported response_type".to_string()).into());
     }
+    state
+        .resource_server_service
+        .resolve_resource(request.resource.as_deref(), &request.scope)
+        .await?;
 
     // 2. Extract authenticated user from session cookie or Authorization header
     let user_id = match extract_user_id_from_request(&state, &jar, &headers).await {
@@ -409,13 +499,12 @@ pub async fn authorize_endpoint(
             // 8. 此服务现在看到有效的 session_token，处理授权流程
             //
             // 安全考虑：
-            // - Admin Portal URL 通过 NEXT_PUBLIC_ADMIN_PORTAL_URL 环境变量配置
+            // - Admin Portal URL 通过 system.admin_portal_url 运行时配置项配置
             // - Admin Portal 的 /login 必须验证 redirect 参数（防止 open redirect 攻击）
             // - redirect 参数包含原始的 /authorize URL，保留所有 PKCE 参数
             // - 凭证仅在内存中暂存，不被持久化到 Admin Portal
             //
-            let admin_portal_url = std::env::var("NEXT_PUBLIC_ADMIN_PORTAL_URL")
-                .unwrap_or_else(|_| "http://localhost:3002".to_string());
+            let admin_portal_url = state.system_config_service.current().admin_portal_url.clone();
 
             // Build the return URL that will redirect back to authorize after login
             // This preserves all OAuth parameters including PKCE code_challenge
@@ -432,6 +521,9 @@ pub async fn authorize_endpoint(
             if let Some(nonce) = &request.nonce {
                 authorize_params.push(("nonce", nonce.as_str()));
             }
+            if let Some(resource) = &request.resource {
+                authorize_params.push(("resource", resource.as_str()));
+            }
             let authorize_url = build_url(&authorize_base, "/api/v2/oauth/authorize", &authorize_params)?;
 
             // Redirect to Admin Portal's /login with the authorize URL as the return destination
@@ -441,6 +533,19 @@ pub async fn authorize_endpoint(
         }
     };
 
+    // 租户的客户端只对同一租户的用户开放
+    let user_tenant = state
+        .user_service
+        .find_by_id(&user_id)
+        .await?
+        .and_then(|user| user.tenant_id);
+    if !can_use_client(client_details.client.tenant_id.as_deref(), user_tenant.as_deref()) {
+        return Err(ServiceError::Unauthorized(
+            "Client is not available to the user's tenant".to_string(),
+        )
+        .into());
+    }
+
     // 3. 检查是否需要显示同意页面 (require_consent)
     //
     // OAuth 2.1 同意流程：
@@ -466,8 +571,7 @@ pub async fn authorize_endpoint(
             request.client_id
         );
 
-        let admin_portal_url = std::env::var("NEXT_PUBLIC_ADMIN_PORTAL_URL")
-            .unwrap_or_else(|_| "http://localhost:3002".to_string());
+        let admin_portal_url = state.system_config_service.current().admin_portal_url.clone();
 
         // 构建同意页面 URL，携带所有 OAuth 参数
         let mut consent_params = vec![
@@ -481,6 +585,9 @@ pub async fn authorize_endpoint(
         if let Some(nonce) = &request.nonce {
             consent_params.push(("nonce", nonce.as_str()));
         }
+        if let Some(resource) = &request.resource {
+            consent_params.push(("resource", resource.as_str()));
+        }
         let consent_url = build_url(&admin_portal_url, "/oauth/consent", &consent_params)?;
 
         return Ok(Redirect::to(consent_url.as_str()).into_response());
@@ -528,11 +635,38 @@ pub async fn userinfo_endpoint(
 }
 
 /// Handles `/api/v2/oauth/introspect`
+///
+/// The caller must authenticate as a client (RFC 7662 §2.1). A token is only reported
+/// active for its audience: tokens for this service by default, or tokens for the
+/// resource server registered with the calling client when that server's `resource` is sent.
 pub async fn introspect_endpoint(
     State(state): State<Arc<AppState>>,
     Form(request): Form<IntrospectRequest>,
 ) -> Result<Json<IntrospectResponse>, AppError> {
-    match state.token_service.introspect_token(&request.token).await {
+    let client = state
+        .client_service
+        .authenticate_client(&request.client_id, request.client_secret.as_deref())
+        .await
+        .map_err(|_| ServiceError::Unauthorized("Invalid client credentials".to_string()))?;
+
+    let audience = request.resource.as_deref().unwrap_or(&state.config.issuer);
+    let allowed = audience == state.config.issuer
+        || state
+            .resource_server_service
+            .find_by_client_id(&client.client.client_id)
+            .await?
+            .is_some_and(|server| server.is_active && server.identifier == audience);
+    let result = if allowed {
+        state
+            .token_service
+            .verify_access_token(&request.token, audience)
+            .await
+    } else {
+        Err(ServiceError::Unauthorized(
+            "Client may not introspect tokens for this resource".to_string(),
+        ))
+    };
+    match result {
         Ok(claims) => Ok(Json(IntrospectResponse {
             active: true,
             scope: Some(claims.scope),
@@ -540,6 +674,9 @@ pub async fn introspect_endpoint(
             username: claims.sub.clone(),
             sub: claims.sub,
             exp: Some(claims.exp),
+            tenant: claims.tenant,
+            iss: claims.iss,
+            aud: claims.aud,
         })),
         Err(_) => Ok(Json(IntrospectResponse {
             active: false,
@@ -548,6 +685,9 @@ pub async fn introspect_endpoint(
             username: None,
             sub: None,
             exp: None,
+            tenant: None,
+            iss: None,
+            aud: None,
         })),
     }
 }
@@ -597,7 +737,13 @@ pub async fn authenticate_endpoint(
     let user = state
         .user_service
         .authenticate(&request.username, &request.password)
-        .await?;
+        .await;
+    metrics::record_login(user.is_ok());
+    let user = user?;
+    // 已注册通行密钥的用户必须通过 /api/v2/auth/login 完成第二因素
+    if !state.webauthn_service.list_credentials(&user.id).await?.is_empty() {
+        return Err(ServiceError::Unauthorized("Passkey verification required".to_string()).into());
+    }
     let _ = state.user_service.update_last_login(&user.id).await;
     let permissions = state
         .rbac_service
@@ -620,6 +766,8 @@ pub async fn authenticate_endpoint(
             "admin".to_string(),
             permissions,
             None,
+            None,
+            None,
         )
         .await?;
 
@@ -637,6 +785,7 @@ async fn handle_authorization_code_grant(
     state: Arc<AppState>,
     client: OAuthClientDetails,
     request: TokenRequest,
+    dpop_jkt: Option<String>,
 ) -> Result<Json<TokenResponse>, AppError> {
     let code = request
         .code
@@ -662,10 +811,30 @@ async fn handle_authorization_code_grant(
         .into());
     }
 
-    // 4. Get user permissions and issue tokens
+    // 4. RFC 8707: the token request may repeat (but not change) the resource of the authorization
+    let resource = match (auth_code.resource.as_deref(), request.resource.as_deref()) {
+        (Some(granted), Some(requested)) if granted != requested => {
+            return Err(ServiceError::InvalidTarget(
+                "Resource does not match the authorization request".to_string(),
+            )
+            .into());
+        }
+        (granted, requested) => granted.or(requested),
+    };
+    let resource = state
+        .resource_server_service
+        .resolve_resource(resource, &auth_code.scope)
+        .await?;
+
+    // 5. Get user permissions (including roles scoped to this client), restricted to the
+    //    permissions mapped to the granted scopes, and issue tokens
     let permissions = state
         .rbac_service
-        .get_user_permissions(&auth_code.user_id)
+        .get_user_permissions_for_client(&auth_code.user_id, &client.client.client_id)
+        .await?;
+    let permissions = state
+        .scope_service
+        .restrict_permissions_to_scope(&auth_code.scope, permissions)
         .await?;
     let token_pair = state
         .token_service
@@ -675,12 +844,14 @@ async fn handle_authorization_code_grant(
             auth_code.scope,
             permissions,
             auth_code.nonce,
+            resource,
+            dpop_jkt.clone(),
         )
         .await?;
 
     Ok(Json(TokenResponse {
         access_token: token_pair.access_token,
-        token_type: "Bearer".to_string(),
+        token_type: token_type(dpop_jkt.as_deref()),
         expires_in: token_pair.expires_in,
         refresh_token: token_pair.refresh_token,
         scope: request.scope.unwrap_or_default(),
@@ -691,16 +862,35 @@ async fn handle_authorization_code_grant(
 async fn handle_refresh_token_grant(
     state: Arc<AppState>,
     request: TokenRequest,
+    dpop_jkt: Option<String>,
 ) -> Result<Json<TokenResponse>, AppError> {
-    let refresh_token = request
-        .refresh_token
-        .ok_or_else(|| ServiceError::ValidationError("Missing refresh_token".to_string()))?;
+    let Some(refresh_token) = request.refresh_token else {
+        metrics::record_refresh_failure("missing_token");
+        return Err(ServiceError::ValidationError("Missing refresh_token".to_string()).into());
+    };
 
-    let token_pair = state.token_service.refresh_token(&refresh_token).await?;
+    let token_pair = match state
+        .token_service
+        .refresh_token(&refresh_token, request.resource.as_deref(), dpop_jkt.as_deref())
+        .await
+    {
+        Ok(token_pair) => token_pair,
+        Err(e) => {
+            metrics::record_refresh_failure(match &e {
+                ServiceError::Unauthorized(_) => "rejected",
+                ServiceError::JwtError(_) => "invalid_token",
+                ServiceError::NotFound(_) => "not_found",
+                ServiceError::InvalidTarget(_) => "invalid_target",
+                ServiceError::InvalidDpopProof(_) => "invalid_dpop_proof",
+                _ => "error",
+            });
+            return Err(e.into());
+        }
+    };
 
     Ok(Json(TokenResponse {
         access_token: token_pair.access_token,
-        token_type: "Bearer".to_string(),
+        token_type: token_type(dpop_jkt.as_deref()),
         expires_in: token_pair.expires_in,
         refresh_token: token_pair.refresh_token,
         scope: request.scope.unwrap_or_default(),
@@ -712,6 +902,7 @@ async fn handle_client_credentials_grant(
     state: Arc<AppState>,
     client: OAuthClientDetails,
     request: TokenRequest,
+    dpop_jkt: Option<String>,
 ) -> Result<Json<TokenResponse>, AppError> {
     // For client credentials, permissions are derived from the client's own configuration
     let permissions = client.client_permissions.clone();
@@ -719,15 +910,19 @@ async fn handle_client_credentials_grant(
 
     // Validate that the requested scope is a subset of the client's allowed scopes
     validation::validate_scope(&scope, &client.allowed_scopes)?;
+    let resource = state
+        .resource_server_service
+        .resolve_resource(request.resource.as_deref(), &scope)
+        .await?;
 
     let token_pair = state
         .token_service
-        .issue_tokens(&client, None, scope.clone(), permissions, None)
+        .issue_tokens(&client, None, scope.clone(), permissions, None, resource, dpop_jkt.clone())
         .await?;
 
     Ok(Json(TokenResponse {
         access_token: token_pair.access_token,
-        token_type: "Bearer".to_string(),
+        token_type: token_type(dpop_jkt.as_deref()),
         expires_in: token_pair.expires_in,
         refresh_token: None, // No refresh token for client credentials
         scope,
@@ -737,6 +932,14 @@ async fn handle_client_credentials_grant(
 
 // --- Helper Functions ---
 
+/// The `token_type` of issued access tokens: `DPoP` when bound to a DPoP key (RFC 9449 §5).
+fn token_type(dpop_jkt: Option<&str>) -> String {
+    match dpop_jkt {
+        Some(_) => "DPoP".to_string(),
+        None => "Bearer".to_string(),
+    }
+}
+
 /// Extracts user_id from session cookie (priority) or Authorization header.
 pub async fn extract_user_id_from_request(
     state: &Arc<AppState>,
@@ -752,7 +955,11 @@ pub async fn extract_user_id_from_request(
     // 1. Try to authenticate via session cookie
     if let Some(cookie) = jar.get("session_token") {
         tracing::info!("Found session_token cookie, verifying...");
-        match state.token_service.introspect_token(cookie.value()).await {
+        match state
+            .token_service
+            .verify_access_token(cookie.value(), &state.config.issuer)
+            .await
+        {
             Ok(claims) => {
                 if let Some(user_id) = claims.sub {
                     tracing::info!("Session token validated successfully for user: {}", user_id);
@@ -780,7 +987,14 @@ pub async fn extract_user_id_from_request(
         .strip_prefix("Bearer ")
         .ok_or(AppError::Auth(crate::error::AuthError::InvalidToken))?;
 
-    let claims = state.token_service.introspect_token(token).await?;
+    let claims = state
+        .token_service
+        .verify_access_token(token, &state.config.issuer)
+        .await?;
+    // DPoP-bound tokens are only accepted with a proof (see `auth_middleware`)
+    if claims.cnf.is_some() {
+        return Err(AppError::Auth(crate::error::AuthError::InvalidToken));
+    }
     claims.sub.ok_or_else(|| {
         ServiceError::Unauthorized("Token does not represent a user".to_string()).into()
     })
@@ -790,7 +1004,7 @@ pub async fn extract_user_id_from_request(
 
 /// Safely extracts client IP from headers, falling back to default IP if extraction fails.
 /// Returns an AppError if IP parsing fails and no default can be used.
-fn extract_client_ip(headers: &axum::http::HeaderMap) -> Result<std::net::IpAddr, AppError> {
+pub(crate) fn extract_client_ip(headers: &axum::http::HeaderMap) -> Result<std::net::IpAddr, AppError> {
     // Try to extract from X-Forwarded-For header
     if let Some(forwarded_for) = headers.get("x-forwarded-for") {
         if let Ok(header_value) = forwarded_for.to_str() {
@@ -3,7 +3,9 @@ use crate::{
     error::{AppError, ServiceError},
     middleware::auth::AuthContext,
     models::permission::{Permission, PermissionType},
+    services::rbac_service::PermissionEvaluation,
     state::AppState,
+    utils::permission_conditions::AccessContext,
 };
 use axum::{
     extract::{Path, Query, State},
@@ -142,3 +144,32 @@ pub async fn delete_permission(
         "permission_id": permission_id
     })))
 }
+
+#[derive(Deserialize, Debug)]
+pub struct EvaluatePermissionRequest {
+    pub user_id: String,
+    pub permission: String,
+    /// 模拟的请求上下文 (时间、来源 IP、客户端、用户属性)
+    #[serde(default)]
+    pub context: AccessContext,
+}
+
+/// 模拟一次权限检查 (dry-run)，返回各授予来源的条件求值结果
+pub async fn evaluate_permission(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+    Json(payload): Json<EvaluatePermissionRequest>,
+) -> Result<Json<PermissionEvaluation>, AppError> {
+    state
+        .user_service
+        .find_by_id(&payload.user_id)
+        .await?
+        .ok_or_else(|| ServiceError::NotFound("User not found".to_string()))?;
+
+    let evaluation = state
+        .rbac_service
+        .evaluate_permission(&payload.user_id, &payload.permission, &payload.context)
+        .await?;
+
+    Ok(Json(evaluation))
+}
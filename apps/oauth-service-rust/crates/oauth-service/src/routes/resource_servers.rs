@@ -0,0 +1,113 @@
+// 资源服务器管理 API (RFC 8707 资源指示符的目标)
+use crate::{
+    error::{AppError, ServiceError},
+    middleware::auth::AuthContext,
+    models::resource_server::ResourceServer,
+    state::AppState,
+};
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateResourceServerRequest {
+    /// 资源标识符 (绝对 URI)，即访问令牌的 `aud`，创建后不可修改
+    pub identifier: String,
+    pub name: String,
+    /// 该资源服务器接受的 scope
+    #[serde(default)]
+    pub allowed_scopes: Vec<String>,
+    /// 资源服务器调用内省端点时认证的客户端
+    pub client_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateResourceServerRequest {
+    pub name: Option<String>,
+    pub allowed_scopes: Option<Vec<String>>,
+    pub client_id: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+/// 列出所有资源服务器
+pub async fn list_resource_servers(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<Vec<ResourceServer>>, AppError> {
+    Ok(Json(
+        state.resource_server_service.list_resource_servers().await?,
+    ))
+}
+
+/// 注册资源服务器
+pub async fn create_resource_server(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+    Json(payload): Json<CreateResourceServerRequest>,
+) -> Result<Json<ResourceServer>, AppError> {
+    let server = state
+        .resource_server_service
+        .create_resource_server(
+            payload.identifier,
+            payload.name,
+            payload.allowed_scopes,
+            payload.client_id,
+        )
+        .await?;
+    Ok(Json(server))
+}
+
+/// 获取资源服务器详情
+pub async fn get_resource_server(
+    State(state): State<Arc<AppState>>,
+    Path(server_id): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<ResourceServer>, AppError> {
+    let server = state
+        .resource_server_service
+        .find_resource_server(&server_id)
+        .await?
+        .ok_or_else(|| ServiceError::NotFound("Resource server not found".to_string()))?;
+    Ok(Json(server))
+}
+
+/// 重命名资源服务器、替换其允许的 scope 或内省客户端，或启用、停用
+pub async fn update_resource_server(
+    State(state): State<Arc<AppState>>,
+    Path(server_id): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+    Json(payload): Json<UpdateResourceServerRequest>,
+) -> Result<Json<ResourceServer>, AppError> {
+    let server = state
+        .resource_server_service
+        .update_resource_server(
+            &server_id,
+            payload.name,
+            payload.allowed_scopes,
+            payload.client_id,
+            payload.is_active,
+        )
+        .await?;
+    Ok(Json(server))
+}
+
+/// 删除资源服务器 (已签发的令牌在过期前仍以其标识符为受众)
+pub async fn delete_resource_server(
+    State(state): State<Arc<AppState>>,
+    Path(server_id): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    state
+        .resource_server_service
+        .delete_resource_server(&server_id)
+        .await?;
+    Ok(Json(serde_json::json!({
+        "message": "Resource server deleted successfully",
+        "id": server_id
+    })))
+}
@@ -2,13 +2,19 @@
 use crate::{
     error::{AppError, ServiceError},
     middleware::auth::AuthContext,
-    models::role::Role,
+    models::role::{Role, RoleAssignmentContext, UserRoleAssignment},
+    routes::users::find_user_in_scope,
+    services::access_request_service::RoleApprover,
+    services::rbac_service::PermissionExplanation,
+    services::role_service::RoleAssignmentOptions,
     state::AppState,
+    utils::permission_conditions::PermissionCondition,
 };
 use axum::{
     extract::{Path, Query, State},
     response::Json,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -16,12 +22,16 @@ use std::sync::Arc;
 pub struct ListRolesQuery {
     pub limit: Option<i32>,
     pub offset: Option<i32>,
+    /// 列出该租户可见的角色 (全局角色和租户自己的角色)
+    pub tenant_id: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct CreateRoleRequest {
     pub name: String,
     pub description: Option<String>,
+    /// 所属租户，为空表示全局角色；租户管理员创建的角色始终属于其租户
+    pub tenant_id: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -35,9 +45,37 @@ pub struct AssignPermissionsRequest {
     pub permission_ids: Vec<String>,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct SetParentRolesRequest {
+    pub parent_role_ids: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SetRoleApproversRequest {
+    pub user_ids: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SetPermissionConditionsRequest {
+    /// 条件表达式，为空表示取消条件
+    pub conditions: Option<PermissionCondition>,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct AssignRoleRequest {
     pub role_id: String,
+    /// 过期时间 (RFC 3339)，为空表示永久有效
+    pub expires_at: Option<DateTime<Utc>>,
+    /// 生效范围 (organization / client_id)，为空表示全局分配
+    pub context: Option<RoleAssignmentContext>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ExplainPermissionsQuery {
+    /// 按通过该客户端签发令牌时的范围求值 (包括用户所属组织)，为空时只考虑全局分配
+    pub client_id: Option<String>,
+    /// 需要解释的权限 (逗号分隔)，用户不具备时列出可以授予它们的角色
+    pub permissions: Option<String>,
 }
 
 #[derive(Serialize, Debug)]
@@ -46,6 +84,34 @@ pub struct RoleResponse {
     pub name: String,
     pub description: Option<String>,
     pub created_at: String,
+    pub tenant_id: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct UserRoleResponse {
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+    pub context: Option<RoleAssignmentContext>,
+    pub expires_at: Option<String>,
+    pub is_expired: bool,
+    pub assigned_by: Option<String>,
+    pub assigned_at: String,
+}
+
+impl From<UserRoleAssignment> for UserRoleResponse {
+    fn from(assignment: UserRoleAssignment) -> Self {
+        Self {
+            is_expired: assignment.is_expired(),
+            id: assignment.role_id,
+            name: assignment.role_name,
+            display_name: assignment.role_display_name,
+            context: assignment.context.map(|context| context.0),
+            expires_at: assignment.expires_at.map(|t| t.to_rfc3339()),
+            assigned_by: assignment.assigned_by,
+            assigned_at: assignment.assigned_at.to_rfc3339(),
+        }
+    }
 }
 
 impl From<Role> for RoleResponse {
@@ -55,21 +121,57 @@ impl From<Role> for RoleResponse {
             name: role.name,
             description: role.description,
             created_at: role.created_at.to_rfc3339(),
+            tenant_id: role.tenant_id,
         }
     }
 }
 
+/// 查找请求方可见的角色: 全局角色和本租户的角色，其他租户的角色视为不存在
+async fn find_visible_role(
+    state: &AppState,
+    auth: &AuthContext,
+    role_id: &str,
+) -> Result<Role, AppError> {
+    state
+        .role_service
+        .find_role_by_id(role_id)
+        .await?
+        .filter(|role| role.tenant_id.is_none() || auth.can_access_tenant(role.tenant_id.as_deref()))
+        .ok_or_else(|| ServiceError::NotFound("Role not found".to_string()).into())
+}
+
+/// 查找请求方可以修改的角色，全局角色对租户管理员只读
+async fn find_managed_role(
+    state: &AppState,
+    auth: &AuthContext,
+    role_id: &str,
+) -> Result<Role, AppError> {
+    let role = find_visible_role(state, auth, role_id).await?;
+    if auth.tenant_id.is_some() && role.tenant_id.is_none() {
+        return Err(ServiceError::Forbidden(
+            "Global roles cannot be modified by tenant administrators".to_string(),
+        )
+        .into());
+    }
+    Ok(role)
+}
+
 /// 列出所有角色
 pub async fn list_roles(
     State(state): State<Arc<AppState>>,
     Query(query): Query<ListRolesQuery>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
 ) -> Result<Json<Vec<RoleResponse>>, AppError> {
 
-    let roles = state
-        .role_service
-        .list_roles(query.limit, query.offset)
-        .await?;
+    let roles = match auth.effective_tenant(query.tenant_id) {
+        Some(tenant_id) => {
+            state
+                .role_service
+                .list_roles_in_tenant(&tenant_id, query.limit, query.offset)
+                .await?
+        }
+        None => state.role_service.list_roles(query.limit, query.offset).await?,
+    };
 
     let response: Vec<RoleResponse> = roles.into_iter().map(RoleResponse::from).collect();
 
@@ -79,7 +181,7 @@ pub async fn list_roles(
 /// 创建新角色
 pub async fn create_role(
     State(state): State<Arc<AppState>>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
     Json(payload): Json<CreateRoleRequest>,
 ) -> Result<Json<RoleResponse>, AppError> {
 
@@ -88,10 +190,20 @@ pub async fn create_role(
         return Err(ServiceError::ValidationError("Role name is required".to_string()).into());
     }
 
-    let role = state
-        .role_service
-        .create_role(payload.name, payload.description)
-        .await?;
+    let role = match auth.effective_tenant(payload.tenant_id) {
+        Some(tenant_id) => {
+            state
+                .role_service
+                .create_role_in_tenant(payload.name, payload.description, &tenant_id)
+                .await?
+        }
+        None => {
+            state
+                .role_service
+                .create_role(payload.name, payload.description)
+                .await?
+        }
+    };
 
     Ok(Json(RoleResponse::from(role)))
 }
@@ -100,14 +212,10 @@ pub async fn create_role(
 pub async fn get_role(
     State(state): State<Arc<AppState>>,
     Path(role_id): Path<String>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
 ) -> Result<Json<RoleResponse>, AppError> {
 
-    let role = state
-        .role_service
-        .find_role_by_id(&role_id)
-        .await?
-        .ok_or_else(|| ServiceError::NotFound("Role not found".to_string()))?;
+    let role = find_visible_role(&state, &auth, &role_id).await?;
 
     Ok(Json(RoleResponse::from(role)))
 }
@@ -116,10 +224,11 @@ pub async fn get_role(
 pub async fn update_role(
     State(state): State<Arc<AppState>>,
     Path(role_id): Path<String>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
     Json(payload): Json<UpdateRoleRequest>,
 ) -> Result<Json<RoleResponse>, AppError> {
 
+    find_managed_role(&state, &auth, &role_id).await?;
     let updated_role = state
         .role_service
         .update_role(&role_id, payload.name, payload.description)
@@ -132,9 +241,10 @@ pub async fn update_role(
 pub async fn delete_role(
     State(state): State<Arc<AppState>>,
     Path(role_id): Path<String>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
 ) -> Result<Json<serde_json::Value>, AppError> {
 
+    find_managed_role(&state, &auth, &role_id).await?;
     state.role_service.delete_role(&role_id).await?;
 
     Ok(Json(serde_json::json!({
@@ -147,25 +257,95 @@ pub async fn delete_role(
 pub async fn get_role_permissions(
     State(state): State<Arc<AppState>>,
     Path(role_id): Path<String>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
 ) -> Result<Json<serde_json::Value>, AppError> {
 
+    find_visible_role(&state, &auth, &role_id).await?;
     let permissions = state.role_service.get_role_permissions(&role_id).await?;
 
     Ok(Json(serde_json::json!({
         "role_id": role_id,
-        "permissions": permissions
+        "permissions": permissions.direct,
+        "inherited_permissions": permissions.inherited,
+        "effective_permissions": permissions.effective()
     })))
 }
 
+/// 获取角色的父角色
+pub async fn get_parent_roles(
+    State(state): State<Arc<AppState>>,
+    Path(role_id): Path<String>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<Json<Vec<RoleResponse>>, AppError> {
+
+    find_visible_role(&state, &auth, &role_id).await?;
+    let roles = state.role_service.get_parent_roles(&role_id).await?;
+
+    let response: Vec<RoleResponse> = roles.into_iter().map(RoleResponse::from).collect();
+
+    Ok(Json(response))
+}
+
+/// 设置角色的父角色
+pub async fn set_parent_roles(
+    State(state): State<Arc<AppState>>,
+    Path(role_id): Path<String>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    Json(payload): Json<SetParentRolesRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+
+    find_managed_role(&state, &auth, &role_id).await?;
+    state
+        .role_service
+        .set_parent_roles(&role_id, payload.parent_role_ids)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Parent roles updated successfully",
+        "role_id": role_id
+    })))
+}
+
+/// 获取角色申请的审批人
+pub async fn get_role_approvers(
+    State(state): State<Arc<AppState>>,
+    Path(role_id): Path<String>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<Json<Vec<RoleApprover>>, AppError> {
+
+    find_visible_role(&state, &auth, &role_id).await?;
+    let approvers = state.access_request_service.get_approvers(&role_id).await?;
+
+    Ok(Json(approvers))
+}
+
+/// 设置角色申请的审批人，没有审批人的角色不接受申请
+pub async fn set_role_approvers(
+    State(state): State<Arc<AppState>>,
+    Path(role_id): Path<String>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    Json(payload): Json<SetRoleApproversRequest>,
+) -> Result<Json<Vec<RoleApprover>>, AppError> {
+    find_managed_role(&state, &auth, &role_id).await?;
+    // 模拟会话中记录真实管理员，而不是被模拟的用户
+    let actor_id = auth.actor_id.or(auth.user_id).unwrap_or(auth.client_id);
+    let approvers = state
+        .access_request_service
+        .set_approvers(&role_id, payload.user_ids, &actor_id)
+        .await?;
+
+    Ok(Json(approvers))
+}
+
 /// 给角色分配权限
 pub async fn assign_permissions_to_role(
     State(state): State<Arc<AppState>>,
     Path(role_id): Path<String>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
     Json(payload): Json<AssignPermissionsRequest>,
 ) -> Result<Json<serde_json::Value>, AppError> {
 
+    find_managed_role(&state, &auth, &role_id).await?;
     state
         .role_service
         .assign_permissions_to_role(&role_id, payload.permission_ids)
@@ -181,10 +361,11 @@ pub async fn assign_permissions_to_role(
 pub async fn remove_permissions_from_role(
     State(state): State<Arc<AppState>>,
     Path(role_id): Path<String>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
     Json(payload): Json<AssignPermissionsRequest>,
 ) -> Result<Json<serde_json::Value>, AppError> {
 
+    find_managed_role(&state, &auth, &role_id).await?;
     state
         .role_service
         .remove_permissions_from_role(&role_id, payload.permission_ids)
@@ -196,37 +377,110 @@ pub async fn remove_permissions_from_role(
     })))
 }
 
-/// 获取用户的所有角色
+/// 设置角色权限的附加条件
+pub async fn set_permission_conditions(
+    State(state): State<Arc<AppState>>,
+    Path((role_id, permission_id)): Path<(String, String)>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    Json(payload): Json<SetPermissionConditionsRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+
+    find_managed_role(&state, &auth, &role_id).await?;
+    state
+        .role_service
+        .set_permission_conditions(&role_id, &permission_id, payload.conditions.clone())
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Permission conditions updated successfully",
+        "role_id": role_id,
+        "permission_id": permission_id,
+        "conditions": payload.conditions
+    })))
+}
+
+/// 获取用户的所有角色分配 (含生效范围、过期时间和分配人)
 pub async fn get_user_roles(
     State(state): State<Arc<AppState>>,
     Path(user_id): Path<String>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
-) -> Result<Json<Vec<RoleResponse>>, AppError> {
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<Json<Vec<UserRoleResponse>>, AppError> {
 
-    let roles = state.role_service.get_user_roles(&user_id).await?;
+    find_user_in_scope(&state, &auth, &user_id).await?;
+    let assignments = state.role_service.get_user_role_assignments(&user_id).await?;
 
-    let response: Vec<RoleResponse> = roles.into_iter().map(RoleResponse::from).collect();
+    let response: Vec<UserRoleResponse> =
+        assignments.into_iter().map(UserRoleResponse::from).collect();
 
     Ok(Json(response))
 }
 
+/// 解释用户的有效权限: 每个权限来自哪个角色分配、经过哪些继承的角色，
+/// 以及请求的权限中用户缺少的权限可以由哪些角色授予
+pub async fn explain_user_permissions(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+    Query(query): Query<ExplainPermissionsQuery>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<Json<PermissionExplanation>, AppError> {
+    let user = find_user_in_scope(&state, &auth, &user_id).await?;
+    let context = match query.client_id {
+        Some(client_id) => {
+            state
+                .client_service
+                .find_by_client_id(&client_id)
+                .await?
+                .filter(|client| auth.can_access_tenant(client.client.tenant_id.as_deref()))
+                .ok_or_else(|| ServiceError::NotFound("Client not found".to_string()))?;
+            RoleAssignmentContext {
+                organization: user.organization,
+                client_id: Some(client_id),
+            }
+        }
+        None => RoleAssignmentContext::default(),
+    };
+    let requested: Vec<String> = query
+        .permissions
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|permission| !permission.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    let explanation = state
+        .rbac_service
+        .explain_permissions(&user_id, &context, &requested)
+        .await?;
+    Ok(Json(explanation))
+}
+
 /// 给用户分配角色
 pub async fn assign_role_to_user(
     State(state): State<Arc<AppState>>,
     Path(user_id): Path<String>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
     Json(payload): Json<AssignRoleRequest>,
 ) -> Result<Json<serde_json::Value>, AppError> {
 
+    find_user_in_scope(&state, &auth, &user_id).await?;
+    find_visible_role(&state, &auth, &payload.role_id).await?;
+    let options = RoleAssignmentOptions {
+        expires_at: payload.expires_at,
+        context: payload.context,
+        assigned_by: Some(auth.user_id.unwrap_or(auth.client_id)),
+    };
     state
         .role_service
-        .assign_role_to_user(&user_id, &payload.role_id)
+        .assign_role_to_user(&user_id, &payload.role_id, options)
         .await?;
 
     Ok(Json(serde_json::json!({
         "message": "Role assigned to user successfully",
         "user_id": user_id,
-        "role_id": payload.role_id
+        "role_id": payload.role_id,
+        "expires_at": payload.expires_at
     })))
 }
 
@@ -234,10 +488,12 @@ pub async fn assign_role_to_user(
 pub async fn remove_role_from_user(
     State(state): State<Arc<AppState>>,
     Path(user_id): Path<String>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
     Json(payload): Json<AssignRoleRequest>,
 ) -> Result<Json<serde_json::Value>, AppError> {
 
+    find_user_in_scope(&state, &auth, &user_id).await?;
+    find_visible_role(&state, &auth, &payload.role_id).await?;
     state
         .role_service
         .remove_role_from_user(&user_id, &payload.role_id)
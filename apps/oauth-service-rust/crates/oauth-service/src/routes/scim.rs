@@ -0,0 +1,401 @@
+// SCIM 2.0 用户与组同步 API (RFC 7643 / RFC 7644)
+//
+// 请求和响应使用 `application/scim+json`，错误按 SCIM Error 格式返回。
+// 资源响应带有 `ETag` 头，修改和删除时可通过 `If-Match` 避免覆盖并发修改。
+use crate::{
+    error::ServiceError,
+    services::scim_service::{
+        ScimFilter, ScimGroup, ScimListResponse, ScimPatchRequest, ScimUser, ERROR_SCHEMA,
+        GROUP_SCHEMA, MAX_PAGE_SIZE, PATCH_OP_SCHEMA, USER_SCHEMA,
+    },
+    state::AppState,
+};
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub const SCIM_CONTENT_TYPE: &str = "application/scim+json";
+
+/// 未指定 `count` 时每页返回的资源数
+const DEFAULT_PAGE_SIZE: u32 = 100;
+
+/// SCIM 错误响应
+#[derive(Debug)]
+pub struct ScimError {
+    status: StatusCode,
+    scim_type: Option<&'static str>,
+    detail: String,
+}
+
+impl ScimError {
+    fn bad_request(scim_type: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            scim_type: Some(scim_type),
+            detail: detail.into(),
+        }
+    }
+}
+
+impl From<ServiceError> for ScimError {
+    fn from(err: ServiceError) -> Self {
+        let (status, scim_type, detail) = match err {
+            ServiceError::ValidationError(msg) => {
+                (StatusCode::BAD_REQUEST, Some("invalidValue"), msg)
+            }
+            ServiceError::Conflict(msg) => (StatusCode::CONFLICT, Some("uniqueness"), msg),
+            ServiceError::NotFound(msg) => (StatusCode::NOT_FOUND, None, msg),
+            ServiceError::PreconditionFailed(msg) => (StatusCode::PRECONDITION_FAILED, None, msg),
+            ServiceError::Forbidden(msg) => (StatusCode::FORBIDDEN, None, msg),
+            ServiceError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, None, msg),
+            other => {
+                tracing::error!("SCIM request failed: {}", other);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    None,
+                    "An internal error occurred. Please try again later.".to_string(),
+                )
+            }
+        };
+        Self {
+            status,
+            scim_type,
+            detail,
+        }
+    }
+}
+
+impl IntoResponse for ScimError {
+    fn into_response(self) -> Response {
+        let mut body = json!({
+            "schemas": [ERROR_SCHEMA],
+            "status": self.status.as_u16().to_string(),
+            "detail": self.detail,
+        });
+        if let Some(scim_type) = self.scim_type {
+            body["scimType"] = json!(scim_type);
+        }
+        scim_response(self.status, &body, None)
+    }
+}
+
+fn scim_response<T: Serialize>(status: StatusCode, body: &T, etag: Option<&str>) -> Response {
+    let mut response = (status, serde_json::to_vec(body).unwrap_or_default()).into_response();
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(SCIM_CONTENT_TYPE));
+    if let Some(value) = etag.and_then(|etag| HeaderValue::from_str(etag).ok()) {
+        headers.insert(header::ETAG, value);
+    }
+    response
+}
+
+/// 返回单个资源，带 `ETag` 和 (新建时的) `Location` 头
+fn resource_response<T: Serialize>(
+    status: StatusCode,
+    resource: &T,
+    version: Option<&str>,
+    location: Option<&str>,
+) -> Response {
+    let mut response = scim_response(status, resource, version);
+    if let Some(value) = location.and_then(|location| HeaderValue::from_str(location).ok()) {
+        response.headers_mut().insert(header::LOCATION, value);
+    }
+    response
+}
+
+/// 请求体可能以 `application/scim+json` 或 `application/json` 发送，统一按 JSON 解析
+fn parse_body<T: DeserializeOwned>(body: &Bytes) -> Result<T, ScimError> {
+    serde_json::from_slice(body)
+        .map_err(|e| ScimError::bad_request("invalidSyntax", format!("Invalid request body: {e}")))
+}
+
+fn if_match(headers: &HeaderMap) -> Option<&str> {
+    headers.get(header::IF_MATCH).and_then(|v| v.to_str().ok())
+}
+
+/// 资源未修改时返回 304
+fn not_modified(headers: &HeaderMap, version: Option<&str>) -> bool {
+    match (headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()), version) {
+        (Some(expected), Some(version)) => expected
+            .split(',')
+            .any(|candidate| candidate.trim() == version || candidate.trim() == "*"),
+        _ => false,
+    }
+}
+
+/// 解析列表查询参数 `filter`、`startIndex`、`count`
+fn list_params(params: &HashMap<String, String>) -> Result<(Option<ScimFilter>, u32, u32), ScimError> {
+    let filter = params
+        .get("filter")
+        .filter(|filter| !filter.trim().is_empty())
+        .map(|filter| ScimFilter::parse(filter))
+        .transpose()
+        .map_err(|e| ScimError::bad_request("invalidFilter", e))?;
+    let number = |name: &str, default: u32| {
+        params
+            .get(name)
+            .map(|value| {
+                value.parse::<i64>().map_err(|_| {
+                    ScimError::bad_request("invalidValue", format!("{name} must be an integer"))
+                })
+            })
+            .transpose()
+            .map(|value| value.map_or(default, |v| v.clamp(0, u32::MAX as i64) as u32))
+    };
+    let start_index = number("startIndex", 1)?.max(1);
+    let count = number("count", DEFAULT_PAGE_SIZE)?.min(MAX_PAGE_SIZE);
+    Ok((filter, start_index, count))
+}
+
+fn check_patch_schema(request: &ScimPatchRequest) -> Result<(), ScimError> {
+    if !request.schemas.is_empty() && !request.schemas.iter().any(|s| s == PATCH_OP_SCHEMA) {
+        return Err(ScimError::bad_request(
+            "invalidSyntax",
+            format!("PATCH requests must use the {PATCH_OP_SCHEMA} schema"),
+        ));
+    }
+    Ok(())
+}
+
+/// 过滤表达式中不支持的属性作为 invalidFilter 返回
+fn filter_error(err: ServiceError) -> ScimError {
+    match err {
+        ServiceError::ValidationError(msg) => ScimError::bad_request("invalidFilter", msg),
+        other => other.into(),
+    }
+}
+
+// ===============================
+// Users
+// ===============================
+
+pub async fn list_users(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Response, ScimError> {
+    let (filter, start_index, count) = list_params(&params)?;
+    let (users, total) = state
+        .scim_service
+        .list_users(filter.as_ref(), start_index, count)
+        .await
+        .map_err(filter_error)?;
+    Ok(scim_response(
+        StatusCode::OK,
+        &ScimListResponse::new(users, total, start_index),
+        None,
+    ))
+}
+
+pub async fn get_user(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ScimError> {
+    let user = state.scim_service.get_user(&id).await?;
+    let version = user.meta.as_ref().map(|m| m.version.as_str());
+    if not_modified(&headers, version) {
+        return Ok(StatusCode::NOT_MODIFIED.into_response());
+    }
+    Ok(resource_response(StatusCode::OK, &user, version, None))
+}
+
+pub async fn create_user(
+    State(state): State<Arc<AppState>>,
+    body: Bytes,
+) -> Result<Response, ScimError> {
+    let user: ScimUser = parse_body(&body)?;
+    let user = state.scim_service.create_user(user).await?;
+    let meta = user.meta.as_ref();
+    Ok(resource_response(
+        StatusCode::CREATED,
+        &user,
+        meta.map(|m| m.version.as_str()),
+        meta.map(|m| m.location.as_str()),
+    ))
+}
+
+pub async fn replace_user(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ScimError> {
+    let user: ScimUser = parse_body(&body)?;
+    let user = state
+        .scim_service
+        .replace_user(&id, user, if_match(&headers))
+        .await?;
+    let version = user.meta.as_ref().map(|m| m.version.as_str());
+    Ok(resource_response(StatusCode::OK, &user, version, None))
+}
+
+pub async fn patch_user(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ScimError> {
+    let request: ScimPatchRequest = parse_body(&body)?;
+    check_patch_schema(&request)?;
+    let user = state
+        .scim_service
+        .patch_user(&id, &request.operations, if_match(&headers))
+        .await?;
+    let version = user.meta.as_ref().map(|m| m.version.as_str());
+    Ok(resource_response(StatusCode::OK, &user, version, None))
+}
+
+pub async fn delete_user(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ScimError> {
+    state
+        .scim_service
+        .delete_user(&id, if_match(&headers))
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ===============================
+// Groups
+// ===============================
+
+pub async fn list_groups(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Response, ScimError> {
+    let (filter, start_index, count) = list_params(&params)?;
+    let (groups, total) = state
+        .scim_service
+        .list_groups(filter.as_ref(), start_index, count)
+        .await
+        .map_err(filter_error)?;
+    Ok(scim_response(
+        StatusCode::OK,
+        &ScimListResponse::new(groups, total, start_index),
+        None,
+    ))
+}
+
+pub async fn get_group(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, ScimError> {
+    let group = state.scim_service.get_group(&id).await?;
+    let version = group.meta.as_ref().map(|m| m.version.as_str());
+    if not_modified(&headers, version) {
+        return Ok(StatusCode::NOT_MODIFIED.into_response());
+    }
+    Ok(resource_response(StatusCode::OK, &group, version, None))
+}
+
+pub async fn create_group(
+    State(state): State<Arc<AppState>>,
+    body: Bytes,
+) -> Result<Response, ScimError> {
+    let group: ScimGroup = parse_body(&body)?;
+    let group = state.scim_service.create_group(group).await?;
+    let meta = group.meta.as_ref();
+    Ok(resource_response(
+        StatusCode::CREATED,
+        &group,
+        meta.map(|m| m.version.as_str()),
+        meta.map(|m| m.location.as_str()),
+    ))
+}
+
+pub async fn replace_group(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ScimError> {
+    let group: ScimGroup = parse_body(&body)?;
+    let group = state
+        .scim_service
+        .replace_group(&id, group, if_match(&headers))
+        .await?;
+    let version = group.meta.as_ref().map(|m| m.version.as_str());
+    Ok(resource_response(StatusCode::OK, &group, version, None))
+}
+
+pub async fn patch_group(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ScimError> {
+    let request: ScimPatchRequest = parse_body(&body)?;
+    check_patch_schema(&request)?;
+    let group = state
+        .scim_service
+        .patch_group(&id, &request.operations, if_match(&headers))
+        .await?;
+    let version = group.meta.as_ref().map(|m| m.version.as_str());
+    Ok(resource_response(StatusCode::OK, &group, version, None))
+}
+
+pub async fn delete_group(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ScimError> {
+    state
+        .scim_service
+        .delete_group(&id, if_match(&headers))
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ===============================
+// 服务发现
+// ===============================
+
+/// 声明支持的 SCIM 功能
+pub async fn service_provider_config() -> Response {
+    let body = json!({
+        "schemas": ["urn:ietf:params:scim:schemas:core:2.0:ServiceProviderConfig"],
+        "patch": { "supported": true },
+        "bulk": { "supported": false, "maxOperations": 0, "maxPayloadSize": 0 },
+        "filter": { "supported": true, "maxResults": MAX_PAGE_SIZE },
+        "changePassword": { "supported": true },
+        "sort": { "supported": false },
+        "etag": { "supported": true },
+        "authenticationSchemes": [{
+            "type": "oauthbearertoken",
+            "name": "OAuth Bearer Token",
+            "description": "Access token issued via the client_credentials grant to a client holding the scim:provision permission",
+            "primary": true
+        }]
+    });
+    scim_response(StatusCode::OK, &body, None)
+}
+
+pub async fn resource_types() -> Response {
+    let resources = vec![
+        json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:ResourceType"],
+            "id": "User",
+            "name": "User",
+            "endpoint": "/Users",
+            "schema": USER_SCHEMA
+        }),
+        json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:ResourceType"],
+            "id": "Group",
+            "name": "Group",
+            "endpoint": "/Groups",
+            "schema": GROUP_SCHEMA
+        }),
+    ];
+    scim_response(StatusCode::OK, &ScimListResponse::new(resources, 2, 1), None)
+}
@@ -0,0 +1,168 @@
+// 权限范围 (scope) 管理 API
+use crate::{
+    error::{AppError, ServiceError},
+    middleware::auth::AuthContext,
+    models::scope::{Scope, ScopeLocalization},
+    state::AppState,
+};
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize, Debug)]
+pub struct CreateScopeRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub is_public: Option<bool>,
+    pub is_oidc_scope: Option<bool>,
+    pub is_active: Option<bool>,
+}
+
+/// 权限范围名称会被客户端配置和已签发的令牌引用，因此不允许修改
+#[derive(Deserialize, Debug, Default)]
+pub struct UpdateScopeRequest {
+    pub description: Option<String>,
+    pub is_public: Option<bool>,
+    pub is_oidc_scope: Option<bool>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SetScopePermissionsRequest {
+    pub permission_ids: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SetScopeLocalizationsRequest {
+    pub localizations: Vec<ScopeLocalization>,
+}
+
+/// 列出所有权限范围
+pub async fn list_scopes(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<Vec<Scope>>, AppError> {
+    let scopes = state.scope_service.list_scopes().await?;
+    Ok(Json(scopes))
+}
+
+/// 创建权限范围
+pub async fn create_scope(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+    Json(payload): Json<CreateScopeRequest>,
+) -> Result<Json<Scope>, AppError> {
+    // scope 名称不能为空，也不能包含空白 (RFC 6749 §3.3)
+    if payload.name.is_empty() || payload.name.chars().any(char::is_whitespace) {
+        return Err(ServiceError::ValidationError(
+            "Scope name must be non-empty and must not contain whitespace".to_string(),
+        )
+        .into());
+    }
+
+    let scope = state.scope_service.create_scope(payload).await?;
+    Ok(Json(scope))
+}
+
+/// 获取权限范围详情
+pub async fn get_scope(
+    State(state): State<Arc<AppState>>,
+    Path(scope_id): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<Scope>, AppError> {
+    let scope = state
+        .scope_service
+        .find_scope_by_id(&scope_id)
+        .await?
+        .ok_or_else(|| ServiceError::NotFound("Scope not found".to_string()))?;
+
+    Ok(Json(scope))
+}
+
+/// 更新权限范围
+pub async fn update_scope(
+    State(state): State<Arc<AppState>>,
+    Path(scope_id): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+    Json(payload): Json<UpdateScopeRequest>,
+) -> Result<Json<Scope>, AppError> {
+    let scope = state.scope_service.update_scope(&scope_id, payload).await?;
+    Ok(Json(scope))
+}
+
+/// 删除权限范围
+pub async fn delete_scope(
+    State(state): State<Arc<AppState>>,
+    Path(scope_id): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    state.scope_service.delete_scope(&scope_id).await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Scope deleted successfully",
+        "scope_id": scope_id
+    })))
+}
+
+/// 获取权限范围映射的权限
+pub async fn get_scope_permissions(
+    State(state): State<Arc<AppState>>,
+    Path(scope_id): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let permissions = state.scope_service.get_scope_permissions(&scope_id).await?;
+
+    Ok(Json(serde_json::json!({
+        "scope_id": scope_id,
+        "permissions": permissions
+    })))
+}
+
+/// 设置权限范围映射的权限
+pub async fn set_scope_permissions(
+    State(state): State<Arc<AppState>>,
+    Path(scope_id): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+    Json(payload): Json<SetScopePermissionsRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    state
+        .scope_service
+        .set_scope_permissions(&scope_id, payload.permission_ids)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Scope permissions updated successfully",
+        "scope_id": scope_id
+    })))
+}
+
+/// 获取权限范围的多语言同意文案
+pub async fn get_scope_localizations(
+    State(state): State<Arc<AppState>>,
+    Path(scope_id): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<Vec<ScopeLocalization>>, AppError> {
+    let localizations = state.scope_service.get_scope_localizations(&scope_id).await?;
+    Ok(Json(localizations))
+}
+
+/// 设置权限范围的多语言同意文案
+pub async fn set_scope_localizations(
+    State(state): State<Arc<AppState>>,
+    Path(scope_id): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+    Json(payload): Json<SetScopeLocalizationsRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    state
+        .scope_service
+        .set_scope_localizations(&scope_id, payload.localizations)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Scope localizations updated successfully",
+        "scope_id": scope_id
+    })))
+}
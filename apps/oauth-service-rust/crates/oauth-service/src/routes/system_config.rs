@@ -0,0 +1,111 @@
+// 运行时系统配置管理 API
+use crate::{
+    error::{AppError, ServiceError},
+    middleware::auth::AuthContext,
+    models::system_config::SystemConfiguration,
+    services::system_config_service::RuntimeSettings,
+    state::AppState,
+};
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Deserialize, Debug)]
+pub struct ListConfigurationsQuery {
+    pub category: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UpdateConfigurationRequest {
+    pub value: serde_json::Value,
+}
+
+/// 配置项响应，敏感配置的值使用掩码代替
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigurationResponse {
+    pub key: String,
+    pub value: serde_json::Value,
+    pub value_type: Option<String>,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub is_editable: bool,
+    pub is_sensitive: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<SystemConfiguration> for ConfigurationResponse {
+    fn from(configuration: SystemConfiguration) -> Self {
+        Self {
+            value: configuration.display_value(),
+            key: configuration.key,
+            value_type: configuration.value_type,
+            description: configuration.description,
+            category: configuration.category,
+            is_editable: configuration.is_editable,
+            is_sensitive: configuration.is_sensitive,
+            updated_at: configuration.updated_at,
+        }
+    }
+}
+
+/// 列出系统配置项，可按分类过滤
+pub async fn list_configurations(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListConfigurationsQuery>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<Vec<ConfigurationResponse>>, AppError> {
+    let configurations = state
+        .system_config_service
+        .list_configurations(query.category.as_deref())
+        .await?;
+    Ok(Json(configurations.into_iter().map(Into::into).collect()))
+}
+
+/// 获取单个系统配置项
+pub async fn get_configuration(
+    State(state): State<Arc<AppState>>,
+    Path(key): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<ConfigurationResponse>, AppError> {
+    let configuration = state
+        .system_config_service
+        .find_configuration(&key)
+        .await?
+        .ok_or_else(|| ServiceError::NotFound(format!("Configuration '{key}' not found")))?;
+    Ok(Json(configuration.into()))
+}
+
+/// 更新系统配置项，新值立即生效
+pub async fn update_configuration(
+    State(state): State<Arc<AppState>>,
+    Path(key): Path<String>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    Json(payload): Json<UpdateConfigurationRequest>,
+) -> Result<Json<ConfigurationResponse>, AppError> {
+    let configuration = state
+        .system_config_service
+        .update_configuration(&key, payload.value)
+        .await?;
+
+    tracing::info!(
+        user_id = ?auth.user_id,
+        key = %key,
+        "System configuration updated"
+    );
+
+    Ok(Json(configuration.into()))
+}
+
+/// 从数据库重新加载运行时配置
+pub async fn reload_configurations(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<RuntimeSettings>, AppError> {
+    let settings = state.system_config_service.reload().await?;
+    Ok(Json((*settings).clone()))
+}
@@ -179,6 +179,8 @@ pub struct ConsentQuery {
     pub code_challenge_method: Option<String>,
     /// OpenID Connect nonce
     pub nonce: Option<String>,
+    /// 目标资源服务器 (RFC 8707)
+    pub resource: Option<String>,
 }
 
 /// 处理权限同意页面请求
@@ -190,7 +192,11 @@ pub async fn consent_handler(
 ) -> Result<impl IntoResponse, AppError> {
     // 1. 验证用户已认证（从session_token cookie）
     let user_id = if let Some(cookie) = jar.get("session_token") {
-        match state.token_service.introspect_token(cookie.value()).await {
+        match state
+            .token_service
+            .verify_access_token(cookie.value(), &state.config.issuer)
+            .await
+        {
             Ok(claims) => claims.sub.ok_or_else(|| {
                 AppError::Service(ServiceError::Unauthorized("Token does not represent a user".to_string()))
             })?,
@@ -252,10 +258,13 @@ pub async fn consent_handler(
         })
         .unwrap_or_else(|| format!("{}@example.com", user.username));
 
+    let passkey_count = state.webauthn_service.list_credentials(&user.id).await?.len();
+
     let template = ConsentTemplate {
         client_name: client_details.client.name.clone(),
         user_email: user_display,
         scope_list,
+        passkey_count,
     };
 
     tracing::info!(
@@ -292,6 +301,9 @@ pub struct ConsentSubmitRequest {
     /// OpenID Connect nonce
     #[serde(default)]
     pub nonce: Option<String>,
+    /// 目标资源服务器 (RFC 8707)
+    #[serde(default)]
+    pub resource: Option<String>,
     /// 记住选择
     #[serde(default)]
     pub remember: Option<String>,
@@ -302,11 +314,16 @@ pub struct ConsentSubmitRequest {
 pub async fn consent_submit_handler(
     State(state): State<Arc<AppState>>,
     jar: CookieJar,
+    connect_info: Option<axum::extract::ConnectInfo<std::net::SocketAddr>>,
     Form(request): Form<ConsentSubmitRequest>,
 ) -> Result<Redirect, AppError> {
     // 1. 验证用户已认证（从session_token cookie）
     let user_id = if let Some(cookie) = jar.get("session_token") {
-        match state.token_service.introspect_token(cookie.value()).await {
+        match state
+            .token_service
+            .verify_access_token(cookie.value(), &state.config.issuer)
+            .await
+        {
             Ok(claims) => claims.sub.ok_or_else(|| {
                 AppError::Service(ServiceError::Unauthorized("Token does not represent a user".to_string()))
             })?,
@@ -338,6 +355,7 @@ pub async fn consent_submit_handler(
         code_challenge: request.code_challenge.clone(),
         code_challenge_method: request.code_challenge_method.clone(),
         nonce: request.nonce.clone(),
+        resource: request.resource.clone(),
     };
 
     // 调用consent submit API处理业务逻辑
@@ -345,6 +363,7 @@ pub async fn consent_submit_handler(
         State(state),
         jar,
         axum::http::HeaderMap::new(),
+        connect_info,
         axum::extract::Json(consent_request),
     ).await?;
 
@@ -429,6 +448,7 @@ mod tests {
             code_challenge: Some("challenge123".to_string()),
             code_challenge_method: Some("S256".to_string()),
             nonce: Some("nonce789".to_string()),
+            resource: None,
         };
 
         assert_eq!(query.client_id, Some("test-client".to_string()));
@@ -448,6 +468,7 @@ mod tests {
             code_challenge: None,
             code_challenge_method: None,
             nonce: None,
+            resource: None,
             remember: Some("true".to_string()),
         };
 
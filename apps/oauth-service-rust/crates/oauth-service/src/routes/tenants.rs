@@ -0,0 +1,123 @@
+// 租户管理 API 与租户的 OIDC 发现端点
+use crate::{
+    error::{AppError, ServiceError},
+    middleware::auth::AuthContext,
+    models::tenant::Tenant,
+    state::AppState,
+};
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize, Debug)]
+pub struct CreateTenantRequest {
+    /// 租户 ID (小写字母、数字和连字符)，创建后不可修改
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateTenantRequest {
+    pub name: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+/// 列出所有租户
+pub async fn list_tenants(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<Vec<Tenant>>, AppError> {
+    Ok(Json(state.tenant_service.list_tenants().await?))
+}
+
+/// 创建租户
+pub async fn create_tenant(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+    Json(payload): Json<CreateTenantRequest>,
+) -> Result<Json<Tenant>, AppError> {
+    if payload.name.trim().is_empty() {
+        return Err(ServiceError::ValidationError("Tenant name is required".to_string()).into());
+    }
+    let tenant = state
+        .tenant_service
+        .create_tenant(payload.id, payload.name)
+        .await?;
+    Ok(Json(tenant))
+}
+
+/// 获取租户详情
+pub async fn get_tenant(
+    State(state): State<Arc<AppState>>,
+    Path(tenant_id): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<Tenant>, AppError> {
+    let tenant = state
+        .tenant_service
+        .find_tenant(&tenant_id)
+        .await?
+        .ok_or_else(|| ServiceError::NotFound(format!("Tenant '{tenant_id}' not found")))?;
+    Ok(Json(tenant))
+}
+
+/// 重命名、启用或停用租户
+pub async fn update_tenant(
+    State(state): State<Arc<AppState>>,
+    Path(tenant_id): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+    Json(payload): Json<UpdateTenantRequest>,
+) -> Result<Json<Tenant>, AppError> {
+    let tenant = state
+        .tenant_service
+        .update_tenant(&tenant_id, payload.name, payload.is_active)
+        .await?;
+    Ok(Json(tenant))
+}
+
+/// 删除租户 (租户下仍有用户、客户端或角色时拒绝)
+pub async fn delete_tenant(
+    State(state): State<Arc<AppState>>,
+    Path(tenant_id): Path<String>,
+    axum::Extension(_auth): axum::Extension<AuthContext>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    state.tenant_service.delete_tenant(&tenant_id).await?;
+    Ok(Json(serde_json::json!({
+        "message": "Tenant deleted successfully",
+        "tenant_id": tenant_id
+    })))
+}
+
+/// 租户的 OpenID Connect 发现文档 (公开)
+///
+/// 租户共用同一组端点，只有 issuer 不同，与租户令牌中的 `iss` 一致。
+pub async fn openid_configuration(
+    State(state): State<Arc<AppState>>,
+    Path(tenant_id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    match state.tenant_service.find_tenant(&tenant_id).await? {
+        Some(tenant) if tenant.is_active => {}
+        _ => {
+            return Err(ServiceError::NotFound(format!("Tenant '{tenant_id}' not found")).into())
+        }
+    }
+
+    let base = state.config.issuer.trim_end_matches('/');
+    Ok(Json(serde_json::json!({
+        "issuer": state.config.tenant_issuer(Some(&tenant_id)),
+        "authorization_endpoint": format!("{base}/api/v2/oauth/authorize"),
+        "token_endpoint": format!("{base}/api/v2/oauth/token"),
+        "userinfo_endpoint": format!("{base}/api/v2/oauth/userinfo"),
+        "introspection_endpoint": format!("{base}/api/v2/oauth/introspect"),
+        "revocation_endpoint": format!("{base}/api/v2/oauth/revoke"),
+        "response_types_supported": ["code"],
+        "grant_types_supported": ["authorization_code", "refresh_token", "client_credentials"],
+        "subject_types_supported": ["public"],
+        "id_token_signing_alg_values_supported": [state.config.jwt_algorithm.as_str()],
+        "code_challenge_methods_supported": ["S256"],
+        "dpop_signing_alg_values_supported": crate::utils::dpop::SUPPORTED_ALGORITHMS,
+    })))
+}
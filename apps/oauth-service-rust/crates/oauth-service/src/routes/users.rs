@@ -2,6 +2,9 @@
 use crate::{
     error::{AppError, ServiceError},
     middleware::auth::AuthContext,
+    models::user::User,
+    services::impersonation_service::StartImpersonation,
+    services::token_revocation_service::{RevocationActor, RevocationSummary},
     state::AppState,
 };
 use axum::{
@@ -15,6 +18,8 @@ use std::sync::Arc;
 pub struct ListUsersQuery {
     pub limit: Option<i32>,
     pub offset: Option<i32>,
+    /// 只列出该租户的用户 (租户管理员始终只能看到本租户)
+    pub tenant_id: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -22,6 +27,8 @@ pub struct CreateUserRequest {
     pub username: String,
     pub password: String,
     pub display_name: Option<String>,
+    /// 所属租户，租户管理员创建的用户始终属于其租户
+    pub tenant_id: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -38,17 +45,86 @@ pub struct UserResponse {
     pub is_active: bool,
     pub created_at: String,
     pub last_login_at: Option<String>,
+    pub tenant_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ImpersonateUserRequest {
+    /// 模拟原因，写入审计日志
+    pub reason: String,
+    /// 会话时长 (秒)，超过上限时按上限签发
+    pub duration_secs: Option<i64>,
+    /// 签发令牌的客户端，默认为当前令牌的客户端
+    pub client_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RevokeTokensRequest {
+    /// 吊销原因，写入审计日志
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ImpersonationResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: u64,
+    pub session_id: String,
+    pub impersonated_user_id: String,
+    pub expires_at: String,
+}
+
+/// 模拟会话中真实操作人的信息，供前端显示模拟横幅
+#[derive(Serialize, Debug)]
+pub struct ImpersonatorInfo {
+    pub id: String,
+    pub username: String,
+    pub expires_at: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CurrentUserResponse {
+    #[serde(flatten)]
+    pub user: UserResponse,
+    /// 当前令牌是否为模拟令牌
+    pub impersonated: bool,
+    pub impersonator: Option<ImpersonatorInfo>,
+}
+
+/// 查找请求方可见的用户，其他租户的用户视为不存在
+pub(crate) async fn find_user_in_scope(
+    state: &AppState,
+    auth: &AuthContext,
+    user_id: &str,
+) -> Result<User, AppError> {
+    state
+        .user_service
+        .find_by_id(user_id)
+        .await?
+        .filter(|user| auth.can_access_tenant(user.tenant_id.as_deref()))
+        .ok_or_else(|| ServiceError::NotFound("User not found".to_string()).into())
 }
 
 pub async fn list_users(
     State(state): State<Arc<AppState>>,
     Query(query): Query<ListUsersQuery>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
 ) -> Result<Json<Vec<UserResponse>>, AppError> {
-    let users = state
-        .user_service
-        .list_users(query.limit, query.offset)
-        .await?;
+    let users = match auth.effective_tenant(query.tenant_id) {
+        Some(tenant_id) => {
+            state
+                .user_service
+                .list_users_in_tenant(&tenant_id, query.limit, query.offset)
+                .await?
+        }
+        None => {
+            state
+                .user_service
+                .list_users(query.limit, query.offset)
+                .await?
+        }
+    };
 
     let response: Vec<UserResponse> = users
         .into_iter()
@@ -59,6 +135,7 @@ pub async fn list_users(
             is_active: user.is_active,
             created_at: user.created_at.to_rfc3339(),
             last_login_at: user.last_login_at.map(|dt| dt.to_rfc3339()),
+            tenant_id: user.tenant_id,
         })
         .collect();
 
@@ -67,7 +144,7 @@ pub async fn list_users(
 
 pub async fn create_user(
     State(state): State<Arc<AppState>>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
     Json(payload): Json<CreateUserRequest>,
 ) -> Result<Json<UserResponse>, AppError> {
     // 验证输入
@@ -82,10 +159,25 @@ pub async fn create_user(
         .into());
     }
 
-    let user = state
-        .user_service
-        .create_user(payload.username, payload.password, payload.display_name)
-        .await?;
+    let user = match auth.effective_tenant(payload.tenant_id) {
+        Some(tenant_id) => {
+            state
+                .user_service
+                .create_user_in_tenant(
+                    payload.username,
+                    payload.password,
+                    payload.display_name,
+                    &tenant_id,
+                )
+                .await?
+        }
+        None => {
+            state
+                .user_service
+                .create_user(payload.username, payload.password, payload.display_name)
+                .await?
+        }
+    };
 
     Ok(Json(UserResponse {
         id: user.id,
@@ -94,19 +186,16 @@ pub async fn create_user(
         is_active: user.is_active,
         created_at: user.created_at.to_rfc3339(),
         last_login_at: user.last_login_at.map(|dt| dt.to_rfc3339()),
+        tenant_id: user.tenant_id,
     }))
 }
 
 pub async fn get_user(
     State(state): State<Arc<AppState>>,
     Path(user_id): Path<String>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
 ) -> Result<Json<UserResponse>, AppError> {
-    let user = state
-        .user_service
-        .find_by_id(&user_id)
-        .await?
-        .ok_or_else(|| ServiceError::NotFound("User not found".to_string()))?;
+    let user = find_user_in_scope(&state, &auth, &user_id).await?;
 
     Ok(Json(UserResponse {
         id: user.id,
@@ -115,15 +204,17 @@ pub async fn get_user(
         is_active: user.is_active,
         created_at: user.created_at.to_rfc3339(),
         last_login_at: user.last_login_at.map(|dt| dt.to_rfc3339()),
+        tenant_id: user.tenant_id,
     }))
 }
 
 pub async fn update_user(
     State(state): State<Arc<AppState>>,
     Path(user_id): Path<String>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
     Json(payload): Json<UpdateUserRequest>,
 ) -> Result<Json<UserResponse>, AppError> {
+    find_user_in_scope(&state, &auth, &user_id).await?;
     let updated_user = state
         .user_service
         .update_user(&user_id, payload.display_name, payload.is_active)
@@ -136,14 +227,16 @@ pub async fn update_user(
         is_active: updated_user.is_active,
         created_at: updated_user.created_at.to_rfc3339(),
         last_login_at: updated_user.last_login_at.map(|dt| dt.to_rfc3339()),
+        tenant_id: updated_user.tenant_id,
     }))
 }
 
 pub async fn delete_user(
     State(state): State<Arc<AppState>>,
     Path(user_id): Path<String>,
-    axum::Extension(_auth): axum::Extension<AuthContext>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
 ) -> Result<Json<serde_json::Value>, AppError> {
+    find_user_in_scope(&state, &auth, &user_id).await?;
     state.user_service.delete_user(&user_id).await?;
 
     Ok(Json(serde_json::json!({
@@ -152,11 +245,44 @@ pub async fn delete_user(
     })))
 }
 
+/// 审计日志中记录的吊销操作人: 模拟会话中记录真实操作人
+pub(crate) fn revocation_actor(auth: &AuthContext) -> RevocationActor {
+    match auth.actor_id.as_ref().or(auth.user_id.as_ref()) {
+        Some(user_id) => RevocationActor {
+            actor_type: "user".to_string(),
+            actor_id: user_id.clone(),
+        },
+        None => RevocationActor {
+            actor_type: "client".to_string(),
+            actor_id: auth.client_id.clone(),
+        },
+    }
+}
+
+/// 吊销用户的全部令牌和会话 (如设备丢失)
+/// POST /api/v2/admin/users/:user_id/revoke-tokens
+pub async fn revoke_user_tokens(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    payload: Option<Json<RevokeTokensRequest>>,
+) -> Result<Json<RevocationSummary>, AppError> {
+    find_user_in_scope(&state, &auth, &user_id).await?;
+    let reason = payload.and_then(|Json(payload)| payload.reason);
+    let summary = state
+        .token_revocation_service
+        .revoke_user(&user_id, &revocation_actor(&auth), reason)
+        .await?;
+    Ok(Json(summary))
+}
+
 pub async fn get_current_user(
     State(state): State<Arc<AppState>>,
     axum::Extension(auth): axum::Extension<AuthContext>,
-) -> Result<Json<UserResponse>, AppError> {
-    let user_id = auth.user_id.ok_or_else(|| ServiceError::Unauthorized("User not authenticated".to_string()))?;
+) -> Result<Json<CurrentUserResponse>, AppError> {
+    let user_id = auth
+        .user_id
+        .ok_or_else(|| ServiceError::Unauthorized("User not authenticated".to_string()))?;
 
     let user = state
         .user_service
@@ -164,12 +290,95 @@ pub async fn get_current_user(
         .await?
         .ok_or_else(|| ServiceError::NotFound("User not found".to_string()))?;
 
-    Ok(Json(UserResponse {
-        id: user.id,
-        username: user.username,
-        display_name: user.display_name,
-        is_active: user.is_active,
-        created_at: user.created_at.to_rfc3339(),
-        last_login_at: user.last_login_at.map(|dt| dt.to_rfc3339()),
+    let impersonator = match &auth.actor_id {
+        Some(actor_id) => {
+            let session = state
+                .impersonation_service
+                .find_active_session(&auth.token_id)
+                .await?;
+            let actor = state.user_service.find_by_id(actor_id).await?;
+            Some(ImpersonatorInfo {
+                id: actor_id.clone(),
+                username: actor.map(|actor| actor.username).unwrap_or_default(),
+                expires_at: session.map(|session| session.expires_at.to_rfc3339()),
+            })
+        }
+        None => None,
+    };
+
+    Ok(Json(CurrentUserResponse {
+        user: UserResponse {
+            id: user.id,
+            username: user.username,
+            display_name: user.display_name,
+            is_active: user.is_active,
+            created_at: user.created_at.to_rfc3339(),
+            last_login_at: user.last_login_at.map(|dt| dt.to_rfc3339()),
+            tenant_id: user.tenant_id,
+        },
+        impersonated: impersonator.is_some(),
+        impersonator,
     }))
 }
+
+/// 以目标用户的身份获取短期访问令牌 (模拟登录)
+pub async fn impersonate_user(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    Json(payload): Json<ImpersonateUserRequest>,
+) -> Result<Json<ImpersonationResponse>, AppError> {
+    // 模拟令牌不能再发起模拟
+    if auth.actor_id.is_some() {
+        return Err(ServiceError::Forbidden(
+            "Cannot start an impersonation from an impersonation session".to_string(),
+        )
+        .into());
+    }
+    let actor_user_id = auth
+        .user_id
+        .ok_or_else(|| ServiceError::Unauthorized("User not authenticated".to_string()))?;
+
+    let grant = state
+        .impersonation_service
+        .start(StartImpersonation {
+            actor_user_id,
+            actor_tenant: auth.tenant_id,
+            client_id: payload.client_id.unwrap_or(auth.client_id),
+            target_user_id: user_id,
+            reason: payload.reason,
+            duration_secs: payload.duration_secs,
+        })
+        .await?;
+
+    Ok(Json(ImpersonationResponse {
+        access_token: grant.access_token,
+        token_type: "Bearer".to_string(),
+        expires_in: grant.expires_in,
+        session_id: grant.session.id,
+        impersonated_user_id: grant.session.target_user_id,
+        expires_at: grant.session.expires_at.to_rfc3339(),
+    }))
+}
+
+/// 结束当前的模拟会话，吊销正在使用的模拟令牌
+pub async fn end_impersonation(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    if auth.actor_id.is_none() {
+        return Err(ServiceError::ValidationError(
+            "The current token is not an impersonation token".to_string(),
+        )
+        .into());
+    }
+    let session = state
+        .impersonation_service
+        .end_session(&auth.token_id)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Impersonation ended",
+        "session_id": session.id
+    })))
+}
@@ -0,0 +1,191 @@
+// 通行密钥 (WebAuthn) 端点
+//
+// 注册和管理通行密钥需要已登录的用户：既接受 Bearer 令牌 (Admin Portal)，
+// 也接受 session_token cookie (登录和同意页面)。cookie 为 SameSite=Strict，
+// 且端点只接受 JSON 请求体，跨站表单无法伪造这些请求。
+use crate::{
+    error::{AppError, ServiceError},
+    metrics,
+    models::user::User,
+    routes::oauth::{extract_client_ip, issue_session_cookie, login_redirect_url, LoginResponse},
+    services::webauthn_service::{
+        AuthenticationCredential, Ceremony, CredentialCreationOptions, CredentialRequestOptions,
+        RegistrationCredential, WebAuthnCredential,
+    },
+    state::AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::Json,
+};
+use axum_extra::extract::cookie::CookieJar;
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize, Debug)]
+pub struct RegistrationRequest {
+    pub challenge_id: String,
+    /// 通行密钥的名称，留空时自动生成
+    pub name: Option<String>,
+    pub credential: RegistrationCredential,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PasskeyLoginOptionsRequest {
+    /// 指定用户名时只允许该用户的通行密钥，否则使用可发现凭据
+    pub username: Option<String>,
+    /// 登录完成后返回的地址 (通常是原始的 /authorize 请求)
+    pub redirect: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PasskeyLoginRequest {
+    pub challenge_id: String,
+    pub credential: AuthenticationCredential,
+}
+
+/// 从 Bearer 令牌或 session_token cookie 中解析当前用户
+async fn current_user(state: &AppState, headers: &HeaderMap, jar: &CookieJar) -> Result<User, AppError> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let token = bearer
+        .or_else(|| jar.get("session_token").map(|cookie| cookie.value()))
+        .ok_or_else(|| ServiceError::Unauthorized("Not authenticated".to_string()))?;
+
+    let claims = state
+        .token_service
+        .verify_access_token(token, &state.config.issuer)
+        .await?;
+    // DPoP 绑定的令牌只能连同证明一起出示
+    if claims.cnf.is_some() {
+        return Err(ServiceError::Unauthorized("DPoP proof required".to_string()).into());
+    }
+    let user_id = claims
+        .sub
+        .ok_or_else(|| ServiceError::Unauthorized("Token does not represent a user".to_string()))?;
+    match state.user_service.find_by_id(&user_id).await? {
+        Some(user) if user.is_active => Ok(user),
+        _ => Err(ServiceError::Unauthorized("User account is inactive".to_string()).into()),
+    }
+}
+
+/// 生成注册选项，传给 navigator.credentials.create()
+pub async fn registration_options(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    jar: CookieJar,
+) -> Result<Json<CredentialCreationOptions>, AppError> {
+    let user = current_user(&state, &headers, &jar).await?;
+    Ok(Json(state.webauthn_service.start_registration(&user).await?))
+}
+
+/// 完成注册，保存通行密钥
+pub async fn register(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Json(request): Json<RegistrationRequest>,
+) -> Result<(StatusCode, Json<WebAuthnCredential>), AppError> {
+    let user = current_user(&state, &headers, &jar).await?;
+    let credential = state
+        .webauthn_service
+        .finish_registration(&user.id, &request.challenge_id, request.name, &request.credential)
+        .await?;
+    Ok((StatusCode::CREATED, Json(credential)))
+}
+
+/// 列出当前用户的通行密钥
+pub async fn list_credentials(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    jar: CookieJar,
+) -> Result<Json<Vec<WebAuthnCredential>>, AppError> {
+    let user = current_user(&state, &headers, &jar).await?;
+    Ok(Json(state.webauthn_service.list_credentials(&user.id).await?))
+}
+
+/// 删除当前用户的一个通行密钥
+pub async fn delete_credential(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let user = current_user(&state, &headers, &jar).await?;
+    state.webauthn_service.delete_credential(&user.id, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 生成无密码登录的断言选项，传给 navigator.credentials.get()
+pub async fn login_options(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<PasskeyLoginOptionsRequest>,
+) -> Result<Json<CredentialRequestOptions>, AppError> {
+    // 与密码登录相同，只允许站内路径或 `auth.login.redirect_origins` 中配置的源
+    let redirect = request
+        .redirect
+        .map(|redirect| redirect.trim().to_string())
+        .filter(|redirect| !redirect.is_empty());
+    if let Some(redirect) = &redirect {
+        if !state.system_config_service.current().is_login_redirect_allowed(redirect) {
+            return Err(ServiceError::ValidationError("无效的重定向 URL".to_string()).into());
+        }
+    }
+
+    // 用户不存在或没有通行密钥时退回可发现凭据，不暴露用户名是否存在
+    let mut user_id = None;
+    if let Some(username) = request.username.as_deref().map(str::trim).filter(|name| !name.is_empty()) {
+        if let Some(user) = state.user_service.find_by_username(username).await? {
+            if !state.webauthn_service.list_credentials(&user.id).await?.is_empty() {
+                user_id = Some(user.id);
+            }
+        }
+    }
+
+    let options = state
+        .webauthn_service
+        .start_authentication(Ceremony::Passwordless, user_id.as_deref(), redirect)
+        .await?;
+    Ok(Json(options))
+}
+
+/// 完成通行密钥断言 (无密码登录或密码登录后的第二因素)，设置 session_token
+pub async fn login(
+    State(state): State<Arc<AppState>>,
+    jar: CookieJar,
+    headers: HeaderMap,
+    Json(request): Json<PasskeyLoginRequest>,
+) -> Result<(CookieJar, Json<LoginResponse>), AppError> {
+    let client_ip = extract_client_ip(&headers)?;
+    if !state.login_rate_limiter.check_login_attempt(client_ip).await {
+        tracing::warn!("Login rate limit exceeded for IP: {}", client_ip);
+        metrics::record_rate_limit_rejection("login");
+        return Err(ServiceError::RateLimitExceeded(
+            "Too many login attempts. Please try again later.".to_string(),
+        )
+        .into());
+    }
+
+    let login = state
+        .webauthn_service
+        .finish_authentication(&request.challenge_id, &request.credential)
+        .await;
+    metrics::record_login(login.is_ok());
+    let login = login?;
+
+    let cookie = issue_session_cookie(&state, &login.user.id).await?;
+    state.user_service.update_last_login(&login.user.id).await?;
+
+    let redirect_url = login_redirect_url(&state, login.redirect.as_deref());
+    tracing::info!(
+        "Passkey login ({}) successful for user: {}, redirecting to: {}",
+        if login.ceremony == Ceremony::SecondFactor { "second factor" } else { "passwordless" },
+        login.user.username,
+        redirect_url
+    );
+
+    Ok((jar.add(cookie), Json(LoginResponse::success(redirect_url))))
+}
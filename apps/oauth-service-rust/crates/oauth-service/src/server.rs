@@ -0,0 +1,121 @@
+// HTTP / HTTPS 服务监听
+use crate::config::{ServerConfig, TlsConfig};
+use axum::{extract::ConnectInfo, Extension, Router};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::conn::auto,
+    service::TowerToHyperService,
+};
+use std::{fs::File, io::BufReader, net::SocketAddr, sync::Arc};
+use tokio::net::TcpListener;
+use tokio_rustls::{rustls, TlsAcceptor};
+
+/// 按服务配置监听并处理请求，配置了 TLS 时使用 HTTPS
+pub async fn serve(app: Router, server: &ServerConfig) -> Result<(), anyhow::Error> {
+    let listener = TcpListener::bind(server.listen_addr).await?;
+
+    match &server.tls {
+        None => {
+            tracing::info!("✅ OAuth service listening on http://{}", server.listen_addr);
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await?;
+        }
+        Some(tls) => {
+            let acceptor = TlsAcceptor::from(Arc::new(load_tls_config(tls)?));
+            tracing::info!("✅ OAuth service listening on https://{}", server.listen_addr);
+            serve_tls(listener, acceptor, app).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// 在独立地址上提供指标等内部端点 (纯 HTTP)
+pub fn spawn_metrics_listener(app: Router, addr: SocketAddr) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let listener = match TcpListener::bind(addr).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!("Failed to bind metrics listener on {}: {}", addr, e);
+                return;
+            }
+        };
+        tracing::info!("✅ Metrics available on http://{}/metrics", addr);
+        if let Err(e) = axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        {
+            tracing::error!("Metrics listener stopped: {}", e);
+        }
+    })
+}
+
+async fn serve_tls(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    app: Router,
+) -> Result<(), anyhow::Error> {
+    loop {
+        let (stream, peer_addr) = listener.accept().await?;
+        let acceptor = acceptor.clone();
+        // 与 HTTP 模式一致，通过 ConnectInfo 向中间件提供客户端地址
+        let service = TowerToHyperService::new(app.clone().layer(Extension(ConnectInfo(peer_addr))));
+
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    tracing::debug!("TLS handshake with {} failed: {}", peer_addr, e);
+                    return;
+                }
+            };
+
+            if let Err(e) = auto::Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                .await
+            {
+                tracing::debug!("Connection from {} closed with error: {}", peer_addr, e);
+            }
+        });
+    }
+}
+
+/// 读取 PEM 格式的证书链和私钥
+fn load_tls_config(tls: &TlsConfig) -> Result<rustls::ServerConfig, anyhow::Error> {
+    let open = |path: &std::path::Path| {
+        File::open(path)
+            .map(BufReader::new)
+            .map_err(|e| anyhow::anyhow!("Failed to open '{}': {e}", path.display()))
+    };
+
+    let certs: Vec<rustls::Certificate> = rustls_pemfile::certs(&mut open(&tls.cert_path)?)?
+        .into_iter()
+        .map(rustls::Certificate)
+        .collect();
+    if certs.is_empty() {
+        anyhow::bail!("No certificates found in '{}'", tls.cert_path.display());
+    }
+
+    let key = rustls_pemfile::read_all(&mut open(&tls.key_path)?)?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(rustls::PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow::anyhow!("No private key found in '{}'", tls.key_path.display()))?;
+
+    let mut config = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(config)
+}
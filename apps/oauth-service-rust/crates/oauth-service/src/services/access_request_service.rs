@@ -0,0 +1,944 @@
+// 角色申请服务 (Access Request Service)
+//
+// 用户可以申请角色，由该角色指定的审批人批准或拒绝:
+// - 申请需要填写理由，可以指定有效期 (秒)；批准时从批准时刻开始计算过期时间
+// - 没有审批人的角色不接受申请，审批人不能审批自己的申请
+// - 批准后通过 `RoleService::assign_role_to_user` 创建角色分配，过期后由后台任务清理
+// - 申请、撤回、批准、拒绝和审批人变更都写入审计日志
+
+use crate::error::ServiceError;
+use crate::services::audit_log_service::{AuditLogService, NewAuditLogEntry};
+use crate::services::role_service::{RoleAssignmentOptions, RoleService};
+use crate::services::tenant_service::{can_access, can_use_role};
+use crate::services::user_service::UserService;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 申请的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum AccessRequestStatus {
+    Pending,
+    Approved,
+    Rejected,
+    Cancelled,
+}
+
+impl AccessRequestStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// 角色申请，对应 `access_requests` 表 (附带角色名和申请人用户名)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AccessRequest {
+    pub id: String,
+    pub user_id: String,
+    pub username: String,
+    pub role_id: String,
+    pub role_name: String,
+    pub justification: String,
+    pub duration_secs: Option<i64>,
+    pub status: AccessRequestStatus,
+    pub decided_by: Option<String>,
+    pub decision_comment: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+    /// 批准后角色分配的过期时间，为空表示永久有效
+    pub expires_at: Option<DateTime<Utc>>,
+    pub tenant_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 角色的审批人
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct RoleApprover {
+    pub user_id: String,
+    pub username: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 提交申请的请求
+#[derive(Debug, Clone)]
+pub struct NewAccessRequest {
+    pub user_id: String,
+    pub role_id: String,
+    pub justification: String,
+    pub duration_secs: Option<i64>,
+}
+
+/// 申请列表的查询条件
+#[derive(Debug, Clone, Default)]
+pub struct AccessRequestQuery {
+    pub page: u32,
+    pub limit: u32,
+    pub status: Option<AccessRequestStatus>,
+    pub role_id: Option<String>,
+    pub user_id: Option<String>,
+    /// 只返回该用户担任审批人的角色的申请
+    pub approver_id: Option<String>,
+    /// 只返回该租户用户的申请
+    pub tenant_id: Option<String>,
+}
+
+/// 申请列表
+#[derive(Debug, Serialize)]
+pub struct AccessRequestList {
+    pub data: Vec<AccessRequest>,
+    pub total: i64,
+}
+
+#[async_trait]
+pub trait AccessRequestService: Send + Sync {
+    /// 提交角色申请
+    async fn create_request(&self, request: NewAccessRequest)
+        -> Result<AccessRequest, ServiceError>;
+    async fn find_request(&self, request_id: &str) -> Result<Option<AccessRequest>, ServiceError>;
+    /// 按条件分页列出申请，最新的在前
+    async fn list_requests(
+        &self,
+        query: AccessRequestQuery,
+    ) -> Result<AccessRequestList, ServiceError>;
+    /// 申请人撤回待审批的申请
+    async fn cancel_request(
+        &self,
+        request_id: &str,
+        user_id: &str,
+    ) -> Result<AccessRequest, ServiceError>;
+    /// 批准申请并分配角色
+    async fn approve_request(
+        &self,
+        request_id: &str,
+        approver_id: &str,
+        comment: Option<String>,
+    ) -> Result<AccessRequest, ServiceError>;
+    async fn reject_request(
+        &self,
+        request_id: &str,
+        approver_id: &str,
+        comment: Option<String>,
+    ) -> Result<AccessRequest, ServiceError>;
+    async fn get_approvers(&self, role_id: &str) -> Result<Vec<RoleApprover>, ServiceError>;
+    /// 设置角色的审批人 (覆盖原有审批人)，审批人必须能够持有该角色
+    async fn set_approvers(
+        &self,
+        role_id: &str,
+        user_ids: Vec<String>,
+        actor_id: &str,
+    ) -> Result<Vec<RoleApprover>, ServiceError>;
+}
+
+pub struct AccessRequestServiceImpl {
+    db: Arc<SqlitePool>,
+    user_service: Arc<dyn UserService>,
+    role_service: Arc<dyn RoleService>,
+    audit_log_service: Arc<dyn AuditLogService>,
+}
+
+const REQUEST_COLUMNS: &str = "ar.id, ar.user_id, u.username, ar.role_id, r.name AS role_name, \
+     ar.justification, ar.duration_secs, ar.status, ar.decided_by, ar.decision_comment, \
+     ar.decided_at, ar.expires_at, ar.tenant_id, ar.created_at";
+
+const REQUEST_JOINS: &str = "FROM access_requests ar \
+     JOIN users u ON u.id = ar.user_id \
+     JOIN roles r ON r.id = ar.role_id";
+
+impl AccessRequestServiceImpl {
+    pub fn new(
+        db: Arc<SqlitePool>,
+        user_service: Arc<dyn UserService>,
+        role_service: Arc<dyn RoleService>,
+        audit_log_service: Arc<dyn AuditLogService>,
+    ) -> Self {
+        Self {
+            db,
+            user_service,
+            role_service,
+            audit_log_service,
+        }
+    }
+
+    async fn require_request(&self, request_id: &str) -> Result<AccessRequest, ServiceError> {
+        self.find_request(request_id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("Access request '{request_id}' not found")))
+    }
+
+    /// 审批人能否处理该申请: 必须是角色的审批人、不是申请人，且能访问申请人所在的租户
+    async fn ensure_approver(
+        &self,
+        request: &AccessRequest,
+        approver_id: &str,
+    ) -> Result<(), ServiceError> {
+        let approver_tenant: Option<Option<String>> =
+            sqlx::query_scalar("SELECT tenant_id FROM users WHERE id = ?")
+                .bind(approver_id)
+                .fetch_optional(&*self.db)
+                .await?;
+        let is_approver = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM role_approvers WHERE role_id = ? AND user_id = ?)",
+        )
+        .bind(&request.role_id)
+        .bind(approver_id)
+        .fetch_one(&*self.db)
+        .await?;
+
+        let visible = approver_tenant
+            .is_some_and(|tenant| can_access(tenant.as_deref(), request.tenant_id.as_deref()));
+        if !is_approver || !visible {
+            // 不暴露其他人的申请是否存在
+            return Err(ServiceError::NotFound(format!(
+                "Access request '{}' not found",
+                request.id
+            )));
+        }
+        if request.user_id == approver_id {
+            return Err(ServiceError::Forbidden(
+                "Approvers cannot decide their own access requests".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 把待审批的申请改为最终状态，已被处理的申请返回 Conflict
+    async fn finish(
+        &self,
+        request: &AccessRequest,
+        status: AccessRequestStatus,
+        decided_by: Option<&str>,
+        comment: Option<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(), ServiceError> {
+        let result = sqlx::query(
+            "UPDATE access_requests
+             SET status = ?, decided_by = ?, decision_comment = ?, decided_at = ?, expires_at = ?
+             WHERE id = ? AND status = 'pending'",
+        )
+        .bind(status)
+        .bind(decided_by)
+        .bind(comment)
+        .bind(Utc::now())
+        .bind(expires_at)
+        .bind(&request.id)
+        .execute(&*self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(ServiceError::Conflict(format!(
+                "Access request '{}' is no longer pending",
+                request.id
+            )));
+        }
+        Ok(())
+    }
+
+    async fn audit(&self, action: &str, actor_id: &str, request: &AccessRequest) {
+        let entry = NewAuditLogEntry {
+            user_id: Some(request.user_id.clone()),
+            actor_type: "user".to_string(),
+            actor_id: actor_id.to_string(),
+            action: action.to_string(),
+            resource_type: Some("access_request".to_string()),
+            resource_id: Some(request.id.clone()),
+            details: Some(serde_json::json!({
+                "role_id": request.role_id,
+                "role_name": request.role_name,
+                "justification": request.justification,
+                "duration_secs": request.duration_secs,
+                "status": request.status,
+                "decision_comment": request.decision_comment,
+                "expires_at": request.expires_at.map(|at| at.to_rfc3339()),
+            })),
+            status: "success".to_string(),
+            tenant_id: request.tenant_id.clone(),
+            ..Default::default()
+        };
+        if let Err(e) = self.audit_log_service.record(entry).await {
+            tracing::warn!("Failed to write access request audit log: {}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl AccessRequestService for AccessRequestServiceImpl {
+    async fn create_request(
+        &self,
+        request: NewAccessRequest,
+    ) -> Result<AccessRequest, ServiceError> {
+        let justification = request.justification.trim().to_string();
+        if justification.is_empty() {
+            return Err(ServiceError::ValidationError(
+                "A justification is required to request a role".to_string(),
+            ));
+        }
+        if request.duration_secs.is_some_and(|secs| secs <= 0) {
+            return Err(ServiceError::ValidationError(
+                "duration_secs must be positive".to_string(),
+            ));
+        }
+
+        let user = self
+            .user_service
+            .find_by_id(&request.user_id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("User '{}' not found", request.user_id)))?;
+        let role = self
+            .role_service
+            .find_role_by_id(&request.role_id)
+            .await?
+            .filter(|role| can_use_role(role.tenant_id.as_deref(), user.tenant_id.as_deref()))
+            .ok_or_else(|| ServiceError::NotFound("Role not found".to_string()))?;
+
+        if self.get_approvers(&role.id).await?.is_empty() {
+            return Err(ServiceError::ValidationError(format!(
+                "Role '{}' has no approvers and cannot be requested",
+                role.name
+            )));
+        }
+        let has_role = self
+            .role_service
+            .get_user_role_assignments(&user.id)
+            .await?
+            .iter()
+            .any(|assignment| assignment.role_id == role.id && !assignment.is_expired());
+        if has_role {
+            return Err(ServiceError::Conflict(format!(
+                "User already has role '{}'",
+                role.name
+            )));
+        }
+        let pending = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM access_requests WHERE user_id = ? AND role_id = ? AND status = 'pending')",
+        )
+        .bind(&user.id)
+        .bind(&role.id)
+        .fetch_one(&*self.db)
+        .await?;
+        if pending {
+            return Err(ServiceError::Conflict(format!(
+                "A request for role '{}' is already pending",
+                role.name
+            )));
+        }
+
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO access_requests (id, user_id, role_id, justification, duration_secs, status, tenant_id, created_at)
+             VALUES (?, ?, ?, ?, ?, 'pending', ?, ?)",
+        )
+        .bind(&id)
+        .bind(&user.id)
+        .bind(&role.id)
+        .bind(&justification)
+        .bind(request.duration_secs)
+        .bind(&user.tenant_id)
+        .bind(Utc::now())
+        .execute(&*self.db)
+        .await?;
+
+        let created = self.require_request(&id).await?;
+        self.audit("access_request.create", &user.id, &created).await;
+        Ok(created)
+    }
+
+    async fn find_request(&self, request_id: &str) -> Result<Option<AccessRequest>, ServiceError> {
+        let request = sqlx::query_as::<_, AccessRequest>(&format!(
+            "SELECT {REQUEST_COLUMNS} {REQUEST_JOINS} WHERE ar.id = ?"
+        ))
+        .bind(request_id)
+        .fetch_optional(&*self.db)
+        .await?;
+        Ok(request)
+    }
+
+    async fn list_requests(
+        &self,
+        query: AccessRequestQuery,
+    ) -> Result<AccessRequestList, ServiceError> {
+        let page = query.page.max(1);
+        let limit = query.limit.clamp(1, 100);
+        let offset = (page - 1) * limit;
+
+        let mut conditions = String::from(" WHERE 1=1");
+        let mut args: Vec<String> = vec![];
+        if let Some(status) = query.status {
+            conditions.push_str(" AND ar.status = ?");
+            args.push(status.as_str().to_string());
+        }
+        if let Some(role_id) = query.role_id {
+            conditions.push_str(" AND ar.role_id = ?");
+            args.push(role_id);
+        }
+        if let Some(user_id) = query.user_id {
+            conditions.push_str(" AND ar.user_id = ?");
+            args.push(user_id);
+        }
+        if let Some(approver_id) = query.approver_id {
+            conditions.push_str(
+                " AND ar.role_id IN (SELECT role_id FROM role_approvers WHERE user_id = ?)",
+            );
+            args.push(approver_id);
+        }
+        if let Some(tenant_id) = query.tenant_id {
+            conditions.push_str(" AND ar.tenant_id = ?");
+            args.push(tenant_id);
+        }
+
+        let count_sql = format!("SELECT COUNT(*) {REQUEST_JOINS}{conditions}");
+        let mut count_query = sqlx::query_scalar::<_, i64>(&count_sql);
+        for arg in &args {
+            count_query = count_query.bind(arg);
+        }
+        let total = count_query.fetch_one(&*self.db).await?;
+
+        let sql = format!(
+            "SELECT {REQUEST_COLUMNS} {REQUEST_JOINS}{conditions} ORDER BY ar.created_at DESC LIMIT ? OFFSET ?"
+        );
+        let mut data_query = sqlx::query_as::<_, AccessRequest>(&sql);
+        for arg in &args {
+            data_query = data_query.bind(arg);
+        }
+        let data = data_query
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&*self.db)
+            .await?;
+
+        Ok(AccessRequestList { data, total })
+    }
+
+    async fn cancel_request(
+        &self,
+        request_id: &str,
+        user_id: &str,
+    ) -> Result<AccessRequest, ServiceError> {
+        let request = self
+            .find_request(request_id)
+            .await?
+            .filter(|request| request.user_id == user_id)
+            .ok_or_else(|| ServiceError::NotFound(format!("Access request '{request_id}' not found")))?;
+        self.finish(&request, AccessRequestStatus::Cancelled, None, None, None)
+            .await?;
+
+        let cancelled = self.require_request(request_id).await?;
+        self.audit("access_request.cancel", user_id, &cancelled).await;
+        Ok(cancelled)
+    }
+
+    async fn approve_request(
+        &self,
+        request_id: &str,
+        approver_id: &str,
+        comment: Option<String>,
+    ) -> Result<AccessRequest, ServiceError> {
+        let request = self.require_request(request_id).await?;
+        self.ensure_approver(&request, approver_id).await?;
+
+        let expires_at = request
+            .duration_secs
+            .map(|secs| Utc::now() + Duration::seconds(secs));
+        // 先占用申请，避免两个审批人同时批准
+        self.finish(
+            &request,
+            AccessRequestStatus::Approved,
+            Some(approver_id),
+            comment,
+            expires_at,
+        )
+        .await?;
+
+        // 已过期但尚未清理的分配会阻止重新分配
+        let expired = self
+            .role_service
+            .get_user_role_assignments(&request.user_id)
+            .await?
+            .iter()
+            .any(|assignment| assignment.role_id == request.role_id && assignment.is_expired());
+        if expired {
+            self.role_service
+                .remove_role_from_user(&request.user_id, &request.role_id)
+                .await?;
+        }
+        let options = RoleAssignmentOptions {
+            expires_at,
+            context: None,
+            assigned_by: Some(approver_id.to_string()),
+        };
+        if let Err(e) = self
+            .role_service
+            .assign_role_to_user(&request.user_id, &request.role_id, options)
+            .await
+        {
+            // 分配失败时恢复为待审批，便于处理后重新批准
+            sqlx::query(
+                "UPDATE access_requests
+                 SET status = 'pending', decided_by = NULL, decision_comment = NULL, decided_at = NULL, expires_at = NULL
+                 WHERE id = ?",
+            )
+            .bind(request_id)
+            .execute(&*self.db)
+            .await?;
+            return Err(e);
+        }
+
+        let approved = self.require_request(request_id).await?;
+        self.audit("access_request.approve", approver_id, &approved).await;
+        Ok(approved)
+    }
+
+    async fn reject_request(
+        &self,
+        request_id: &str,
+        approver_id: &str,
+        comment: Option<String>,
+    ) -> Result<AccessRequest, ServiceError> {
+        let request = self.require_request(request_id).await?;
+        self.ensure_approver(&request, approver_id).await?;
+        self.finish(
+            &request,
+            AccessRequestStatus::Rejected,
+            Some(approver_id),
+            comment,
+            None,
+        )
+        .await?;
+
+        let rejected = self.require_request(request_id).await?;
+        self.audit("access_request.reject", approver_id, &rejected).await;
+        Ok(rejected)
+    }
+
+    async fn get_approvers(&self, role_id: &str) -> Result<Vec<RoleApprover>, ServiceError> {
+        let approvers = sqlx::query_as::<_, RoleApprover>(
+            "SELECT ra.user_id, u.username, ra.created_at FROM role_approvers ra
+             JOIN users u ON u.id = ra.user_id
+             WHERE ra.role_id = ?
+             ORDER BY u.username",
+        )
+        .bind(role_id)
+        .fetch_all(&*self.db)
+        .await?;
+        Ok(approvers)
+    }
+
+    async fn set_approvers(
+        &self,
+        role_id: &str,
+        user_ids: Vec<String>,
+        actor_id: &str,
+    ) -> Result<Vec<RoleApprover>, ServiceError> {
+        let role = self
+            .role_service
+            .find_role_by_id(role_id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("Role '{role_id}' not found")))?;
+
+        let user_ids: BTreeSet<String> = user_ids.into_iter().collect();
+        for user_id in &user_ids {
+            let user = self
+                .user_service
+                .find_by_id(user_id)
+                .await?
+                .ok_or_else(|| ServiceError::NotFound(format!("User '{user_id}' not found")))?;
+            if !can_use_role(role.tenant_id.as_deref(), user.tenant_id.as_deref()) {
+                return Err(ServiceError::ValidationError(format!(
+                    "User '{user_id}' belongs to another tenant than role '{}'",
+                    role.name
+                )));
+            }
+        }
+
+        let mut tx = self.db.begin().await?;
+        sqlx::query("DELETE FROM role_approvers WHERE role_id = ?")
+            .bind(role_id)
+            .execute(&mut *tx)
+            .await?;
+        for user_id in &user_ids {
+            sqlx::query("INSERT INTO role_approvers (role_id, user_id, created_at) VALUES (?, ?, ?)")
+                .bind(role_id)
+                .bind(user_id)
+                .bind(Utc::now())
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        let entry = NewAuditLogEntry {
+            actor_type: "user".to_string(),
+            actor_id: actor_id.to_string(),
+            action: "access_request.approvers.update".to_string(),
+            resource_type: Some("role".to_string()),
+            resource_id: Some(role.id.clone()),
+            details: Some(serde_json::json!({
+                "role_name": role.name,
+                "approvers": user_ids,
+            })),
+            status: "success".to_string(),
+            tenant_id: role.tenant_id.clone(),
+            ..Default::default()
+        };
+        if let Err(e) = self.audit_log_service.record(entry).await {
+            tracing::warn!("Failed to write access request audit log: {}", e);
+        }
+
+        self.get_approvers(role_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::test_support::{client_request, create_client, create_user, setup_state};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_request_approve_and_reject() {
+        let (state, pool) = setup_state().await;
+        let service = &state.access_request_service;
+        let alice = create_user(&state, "alice").await;
+        let bob = create_user(&state, "bob").await;
+        let role = state
+            .role_service
+            .create_role("auditor".to_string(), None)
+            .await
+            .unwrap();
+
+        let request = NewAccessRequest {
+            user_id: alice.clone(),
+            role_id: role.id.clone(),
+            justification: "Quarterly audit".to_string(),
+            duration_secs: Some(3600),
+        };
+        // 没有审批人的角色不接受申请
+        assert!(matches!(
+            service.create_request(request.clone()).await,
+            Err(ServiceError::ValidationError(_))
+        ));
+
+        service
+            .set_approvers(&role.id, vec![bob.clone(), alice.clone()], "admin")
+            .await
+            .unwrap();
+        let created = service.create_request(request.clone()).await.unwrap();
+        assert_eq!(created.status, AccessRequestStatus::Pending);
+        assert_eq!(created.role_name, "auditor");
+        assert!(matches!(
+            service.create_request(request.clone()).await,
+            Err(ServiceError::Conflict(_))
+        ));
+
+        // 审批人不能批准自己的申请
+        assert!(matches!(
+            service.approve_request(&created.id, &alice, None).await,
+            Err(ServiceError::Forbidden(_))
+        ));
+        let pending = service
+            .list_requests(AccessRequestQuery {
+                status: Some(AccessRequestStatus::Pending),
+                approver_id: Some(bob.clone()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(pending.total, 1);
+
+        let approved = service
+            .approve_request(&created.id, &bob, Some("ok".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(approved.status, AccessRequestStatus::Approved);
+        assert_eq!(approved.decided_by.as_deref(), Some(bob.as_str()));
+        let assignment = state
+            .role_service
+            .get_user_role_assignments(&alice)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|a| a.role_id == role.id)
+            .unwrap();
+        assert_eq!(assignment.expires_at, approved.expires_at);
+        assert!(assignment.expires_at.is_some());
+        assert!(matches!(
+            service.reject_request(&created.id, &bob, None).await,
+            Err(ServiceError::Conflict(_))
+        ));
+
+        // 已持有角色时不能再次申请；移除后重新申请并被拒绝
+        assert!(matches!(
+            service.create_request(request.clone()).await,
+            Err(ServiceError::Conflict(_))
+        ));
+        state
+            .role_service
+            .remove_role_from_user(&alice, &role.id)
+            .await
+            .unwrap();
+        let second = service.create_request(request).await.unwrap();
+        let rejected = service.reject_request(&second.id, &bob, None).await.unwrap();
+        assert_eq!(rejected.status, AccessRequestStatus::Rejected);
+        assert!(state.role_service.get_user_roles(&alice).await.unwrap().is_empty());
+
+        let actions: Vec<String> = sqlx::query_scalar(
+            "SELECT action FROM audit_logs WHERE action LIKE 'access_request.%' ORDER BY rowid",
+        )
+        .fetch_all(&*pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            actions,
+            [
+                "access_request.approvers.update",
+                "access_request.create",
+                "access_request.approve",
+                "access_request.create",
+                "access_request.reject",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancel_request() {
+        let (state, _) = setup_state().await;
+        let service = &state.access_request_service;
+        let alice = create_user(&state, "alice").await;
+        let bob = create_user(&state, "bob").await;
+        let role = state
+            .role_service
+            .create_role("auditor".to_string(), None)
+            .await
+            .unwrap();
+        service
+            .set_approvers(&role.id, vec![bob.clone()], "admin")
+            .await
+            .unwrap();
+        let created = service
+            .create_request(NewAccessRequest {
+                user_id: alice.clone(),
+                role_id: role.id.clone(),
+                justification: "Need access".to_string(),
+                duration_secs: None,
+            })
+            .await
+            .unwrap();
+
+        // 只有申请人可以撤回
+        assert!(matches!(
+            service.cancel_request(&created.id, &bob).await,
+            Err(ServiceError::NotFound(_))
+        ));
+        let cancelled = service.cancel_request(&created.id, &alice).await.unwrap();
+        assert_eq!(cancelled.status, AccessRequestStatus::Cancelled);
+        assert!(matches!(
+            service.approve_request(&created.id, &bob, None).await,
+            Err(ServiceError::Conflict(_))
+        ));
+
+        let mine = service
+            .list_requests(AccessRequestQuery {
+                user_id: Some(alice),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(mine.total, 1);
+        assert_eq!(mine.data[0].status, AccessRequestStatus::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_access_request_routes() {
+        let (state, pool) = setup_state().await;
+        let alice = create_user(&state, "alice").await;
+        let bob = create_user(&state, "bob").await;
+        let carol = create_user(&state, "carol").await;
+        let role = state
+            .role_service
+            .create_role("auditor".to_string(), None)
+            .await
+            .unwrap();
+        let (client, _) = create_client(&state, client_request("portal")).await;
+        let mut tokens = Vec::new();
+        for (user_id, permissions) in [
+            (&alice, vec![]),
+            (&bob, vec![]),
+            (
+                &carol,
+                vec![
+                    "access_requests:manage".to_string(),
+                    "access_requests:read".to_string(),
+                ],
+            ),
+        ] {
+            let token = state
+                .token_service
+                .issue_tokens(
+                    &client,
+                    Some(user_id.clone()),
+                    "".to_string(),
+                    permissions,
+                    None,
+                    None,
+                    None,
+                )
+                .await
+                .unwrap()
+                .access_token;
+            tokens.push(token);
+        }
+        let (alice_token, bob_token, carol_token) = (&tokens[0], &tokens[1], &tokens[2]);
+
+        let app = crate::app::create_app(pool.clone(), state.config.clone()).await;
+        let call = |method: &str, uri: String, token: &str, body: String| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("authorization", format!("Bearer {token}"))
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let json = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        // 只有具备 access_requests:manage 权限的管理员可以指定审批人
+        let approvers = format!(r#"{{"user_ids": ["{bob}"]}}"#);
+        let uri = format!("/api/v2/admin/roles/{}/approvers", role.id);
+        let response = app
+            .clone()
+            .oneshot(call("PUT", uri.clone(), bob_token, approvers.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(call("PUT", uri.clone(), carol_token, approvers.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // 模拟会话中审计记录的是真实管理员，而不是被模拟的用户
+        let (impersonation_token, _) = state
+            .token_service
+            .issue_impersonation_token(
+                &client,
+                &alice,
+                &carol,
+                vec!["access_requests:manage".to_string()],
+                600,
+            )
+            .await
+            .unwrap();
+        let response = app
+            .clone()
+            .oneshot(call("PUT", uri, &impersonation_token, approvers))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let actor_id: String = sqlx::query_scalar(
+            "SELECT actor_id FROM audit_logs WHERE action = 'access_request.approvers.update'
+             ORDER BY rowid DESC LIMIT 1",
+        )
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+        assert_eq!(actor_id, carol);
+
+        let body = format!(
+            r#"{{"role_id": "{}", "justification": "Quarterly audit", "duration_secs": 3600}}"#,
+            role.id
+        );
+        let response = app
+            .clone()
+            .oneshot(call(
+                "POST",
+                "/api/v2/users/me/access-requests".to_string(),
+                alice_token,
+                body,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let created = json(response).await;
+        assert_eq!(created["status"], "pending");
+        let request_id = created["id"].as_str().unwrap().to_string();
+
+        // 审批人在待审批列表中看到申请，非审批人看不到
+        let response = app
+            .clone()
+            .oneshot(call(
+                "GET",
+                "/api/v2/access-requests?status=pending".to_string(),
+                bob_token,
+                String::new(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json(response).await["total"], 1);
+        let response = app
+            .clone()
+            .oneshot(call(
+                "POST",
+                format!("/api/v2/access-requests/{request_id}/approve"),
+                carol_token,
+                "{}".to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app
+            .clone()
+            .oneshot(call(
+                "POST",
+                format!("/api/v2/access-requests/{request_id}/approve"),
+                bob_token,
+                r#"{"comment": "ok"}"#.to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json(response).await["status"], "approved");
+        assert_eq!(state.role_service.get_user_roles(&alice).await.unwrap().len(), 1);
+
+        // 管理员列表需要 access_requests:read 权限
+        let response = app
+            .clone()
+            .oneshot(call(
+                "GET",
+                "/api/v2/admin/access-requests?status=approved".to_string(),
+                alice_token,
+                String::new(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(call(
+                "GET",
+                "/api/v2/admin/access-requests?status=approved".to_string(),
+                carol_token,
+                String::new(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let list = json(response).await;
+        assert_eq!(list["total"], 1);
+        assert_eq!(list["data"][0]["decided_by"], bob.as_str());
+    }
+}
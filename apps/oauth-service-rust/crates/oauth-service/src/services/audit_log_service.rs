@@ -21,6 +21,25 @@ pub struct AuditLogEntry {
     pub status: String,
     pub ip_address: Option<String>,
     pub user_agent: Option<String>,
+    pub tenant_id: Option<String>,
+}
+
+/// 待写入的审计日志项
+#[derive(Debug, Clone, Default)]
+pub struct NewAuditLogEntry {
+    pub user_id: Option<String>,
+    /// `user` / `client` / `system`
+    pub actor_type: String,
+    pub actor_id: String,
+    pub action: String,
+    pub resource_type: Option<String>,
+    pub resource_id: Option<String>,
+    pub details: Option<serde_json::Value>,
+    /// `success` / `failure`
+    pub status: String,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub tenant_id: Option<String>,
 }
 
 /// 审计日志查询条件
@@ -32,6 +51,8 @@ pub struct AuditLogQuery {
     pub resource_type: Option<String>,
     pub start_date: Option<String>,
     pub end_date: Option<String>,
+    /// 只返回该租户的日志
+    pub tenant_id: Option<String>,
 }
 
 /// 审计日志查询结果
@@ -47,6 +68,8 @@ pub struct AuditLogQueryResult {
 pub trait AuditLogService: Send + Sync {
     async fn list_audit_logs(&self, query: AuditLogQuery) -> Result<AuditLogQueryResult, ServiceError>;
     async fn export_audit_logs(&self, query: AuditLogQuery) -> Result<Vec<AuditLogEntry>, ServiceError>;
+    /// 写入一条审计日志
+    async fn record(&self, entry: NewAuditLogEntry) -> Result<(), ServiceError>;
 }
 
 pub struct AuditLogServiceImpl {
@@ -68,7 +91,7 @@ impl AuditLogService for AuditLogServiceImpl {
 
         // 构建查询条件
         let mut sql = String::from(
-            "SELECT id, timestamp, user_id, actor_type, actor_id, action, resource_type, resource_id, details, status, ip_address, user_agent FROM audit_logs WHERE 1=1"
+            "SELECT id, timestamp, user_id, actor_type, actor_id, action, resource_type, resource_id, details, status, ip_address, user_agent, tenant_id FROM audit_logs WHERE 1=1"
         );
         let mut count_sql = String::from("SELECT COUNT(*) as total FROM audit_logs WHERE 1=1");
         let mut args: Vec<String> = vec![];
@@ -98,6 +121,11 @@ impl AuditLogService for AuditLogServiceImpl {
             count_sql.push_str(" AND timestamp < ?");
             args.push(end_date.clone());
         }
+        if let Some(tenant_id) = &query.tenant_id {
+            sql.push_str(" AND tenant_id = ?");
+            count_sql.push_str(" AND tenant_id = ?");
+            args.push(tenant_id.clone());
+        }
 
         sql.push_str(" ORDER BY timestamp DESC LIMIT ? OFFSET ?");
 
@@ -140,7 +168,7 @@ impl AuditLogService for AuditLogServiceImpl {
     async fn export_audit_logs(&self, query: AuditLogQuery) -> Result<Vec<AuditLogEntry>, ServiceError> {
         // 构建查询条件
         let mut sql = String::from(
-            "SELECT id, timestamp, user_id, actor_type, actor_id, action, resource_type, resource_id, details, status, ip_address, user_agent FROM audit_logs WHERE 1=1"
+            "SELECT id, timestamp, user_id, actor_type, actor_id, action, resource_type, resource_id, details, status, ip_address, user_agent, tenant_id FROM audit_logs WHERE 1=1"
         );
         let mut args: Vec<String> = vec![];
 
@@ -164,6 +192,10 @@ impl AuditLogService for AuditLogServiceImpl {
             sql.push_str(" AND timestamp < ?");
             args.push(end_date.clone());
         }
+        if let Some(tenant_id) = &query.tenant_id {
+            sql.push_str(" AND tenant_id = ?");
+            args.push(tenant_id.clone());
+        }
 
         sql.push_str(" ORDER BY timestamp DESC");
 
@@ -181,4 +213,26 @@ impl AuditLogService for AuditLogServiceImpl {
 
         Ok(logs)
     }
+
+    async fn record(&self, entry: NewAuditLogEntry) -> Result<(), ServiceError> {
+        sqlx::query(
+            "INSERT INTO audit_logs (id, user_id, actor_type, actor_id, action, resource_type, resource_id, details, status, ip_address, user_agent, tenant_id)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(&entry.user_id)
+        .bind(&entry.actor_type)
+        .bind(&entry.actor_id)
+        .bind(&entry.action)
+        .bind(&entry.resource_type)
+        .bind(&entry.resource_id)
+        .bind(entry.details.as_ref().map(|details| details.to_string()))
+        .bind(&entry.status)
+        .bind(&entry.ip_address)
+        .bind(&entry.user_agent)
+        .bind(&entry.tenant_id)
+        .execute(&*self.db)
+        .await?;
+        Ok(())
+    }
 }
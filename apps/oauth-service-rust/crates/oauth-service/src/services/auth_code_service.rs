@@ -70,7 +70,7 @@ impl AuthCodeService for AuthCodeServiceImpl {
         let created_at = Utc::now();
 
         sqlx::query(
-            "INSERT INTO authorization_codes (id, user_id, client_id, code, redirect_uri, scope, expires_at, code_challenge, code_challenge_method, nonce, is_used, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            "INSERT INTO authorization_codes (id, user_id, client_id, code, redirect_uri, scope, expires_at, code_challenge, code_challenge_method, nonce, resource, is_used, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         )
         .bind(&id)
         .bind(user_id)
@@ -82,6 +82,7 @@ impl AuthCodeService for AuthCodeServiceImpl {
         .bind(&params.code_challenge)
         .bind(&params.code_challenge_method)
         .bind(&params.nonce)
+        .bind(&params.resource)
         .bind(false)
         .bind(created_at)
         .execute(&*self.db)
@@ -100,7 +101,7 @@ impl AuthCodeService for AuthCodeServiceImpl {
         let auth_code =
             sqlx::query_as::<_, AuthCode>(
                 "SELECT id, code, user_id, client_id, redirect_uri, scope, expires_at, \
-                 code_challenge, code_challenge_method, nonce, is_used, created_at \
+                 code_challenge, code_challenge_method, nonce, resource, is_used, created_at \
                  FROM authorization_codes WHERE code = ?"
             )
                 .bind(code)
@@ -175,6 +176,9 @@ mod tests {
             response_types: vec!["code".to_string()],
             allowed_scopes: vec!["read".to_string()],
             client_permissions: None,
+            access_token_ttl: None,
+            refresh_token_ttl: None,
+            tenant_id: None,
         };
         let (client, _) = client_service.create_client(request).await.unwrap();
 
@@ -199,6 +203,7 @@ mod tests {
             code_challenge: "test_challenge".to_string(),
             code_challenge_method: "S256".to_string(),
             nonce: Some("test_nonce".to_string()),
+            resource: None,
         }
     }
 
@@ -218,7 +223,7 @@ mod tests {
         let auth_code =
             sqlx::query_as::<_, AuthCode>(
                 "SELECT id, code, user_id, client_id, redirect_uri, scope, expires_at, \
-                 code_challenge, code_challenge_method, nonce, is_used, created_at \
+                 code_challenge, code_challenge_method, nonce, resource, is_used, created_at \
                  FROM authorization_codes WHERE code = ?"
             )
                 .bind(&code)
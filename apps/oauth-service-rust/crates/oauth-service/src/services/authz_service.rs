@@ -0,0 +1,573 @@
+// 授权决策服务 (Authorization Decision Service)
+//
+// 资源服务器通过 `/api/v2/authz/check` 询问"主体 X 能否对资源 Z 执行操作 Y"，而不是自行解码访问令牌:
+// - 用户主体按角色授予求值 (包括继承、限定范围的分配和附加条件)；无条件的全局权限直接命中权限缓存
+// - 客户端主体按客户端权限 (`client_permissions`) 判断
+// - 决策附带原因和授予该权限的角色，一次请求可以检查多个权限 (前端按权限显示菜单和按钮)
+
+use crate::error::ServiceError;
+use crate::services::client_service::ClientService;
+use crate::services::rbac_service::RBACService;
+use crate::services::tenant_service::can_access;
+use crate::services::user_service::UserService;
+use crate::utils::permission_conditions::AccessContext;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// 检查其他主体的权限所需的权限，检查请求方自身无需该权限
+pub const AUTHZ_CHECK_PERMISSION: &str = "authz:check";
+
+/// 批量检查一次最多包含的权限数量
+pub const MAX_BATCH_CHECKS: usize = 100;
+
+/// 主体类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubjectType {
+    User,
+    Client,
+}
+
+/// 被检查的主体: 用户 ID 或公开的 client_id
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subject {
+    #[serde(rename = "type")]
+    pub subject_type: SubjectType,
+    pub id: String,
+}
+
+/// 决策原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecisionReason {
+    /// 用户的角色授予了该权限
+    RoleGrant,
+    /// 客户端被授予了该权限
+    ClientGrant,
+    /// 有角色授予该权限，但附加条件在本次上下文中不满足
+    ConditionsNotMet,
+    /// 没有任何授予来源
+    NoGrant,
+    /// 主体已被停用
+    SubjectInactive,
+    /// 主体不存在 (或不属于请求方的租户)
+    SubjectNotFound,
+}
+
+/// 授予权限的角色
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MatchedRole {
+    pub role_id: String,
+    pub role_name: String,
+}
+
+/// 一次权限检查的决策
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthzDecision {
+    pub allowed: bool,
+    pub permission: String,
+    pub reason: DecisionReason,
+    /// 授予该权限的角色，仅用户主体有；权限缓存命中且未要求解释时省略
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matched_roles: Option<Vec<MatchedRole>>,
+}
+
+/// 授权检查请求
+#[derive(Debug, Clone)]
+pub struct AuthzCheck {
+    pub subject: Subject,
+    /// 请求方所属租户，租户内的请求方只能检查本租户的主体
+    pub caller_tenant: Option<String>,
+    /// 请求上下文 (时间、来源 IP、客户端、用户属性)，用于求值附加条件
+    pub context: AccessContext,
+    /// 是否总是返回授予该权限的角色 (会跳过权限缓存)
+    pub explain: bool,
+}
+
+#[async_trait]
+pub trait AuthzService: Send + Sync {
+    /// 检查主体是否拥有一个权限
+    async fn check(
+        &self,
+        check: &AuthzCheck,
+        permission: &str,
+    ) -> Result<AuthzDecision, ServiceError>;
+    /// 检查主体是否拥有多个权限，按传入顺序返回决策；主体只解析一次
+    async fn check_many(
+        &self,
+        check: &AuthzCheck,
+        permissions: &[String],
+    ) -> Result<Vec<AuthzDecision>, ServiceError>;
+}
+
+pub struct AuthzServiceImpl {
+    user_service: Arc<dyn UserService>,
+    client_service: Arc<dyn ClientService>,
+    rbac_service: Arc<dyn RBACService>,
+}
+
+/// 解析后的主体状态
+enum ResolvedSubject {
+    /// 用户主体及其无条件的全局权限 (来自权限缓存)
+    User {
+        id: String,
+        cached: Vec<String>,
+    },
+    Client {
+        client_id: String,
+    },
+    Denied(DecisionReason),
+}
+
+impl AuthzServiceImpl {
+    pub fn new(
+        user_service: Arc<dyn UserService>,
+        client_service: Arc<dyn ClientService>,
+        rbac_service: Arc<dyn RBACService>,
+    ) -> Self {
+        Self {
+            user_service,
+            client_service,
+            rbac_service,
+        }
+    }
+
+    async fn resolve(&self, check: &AuthzCheck) -> Result<ResolvedSubject, ServiceError> {
+        let caller_tenant = check.caller_tenant.as_deref();
+        let (is_active, tenant_id) = match check.subject.subject_type {
+            SubjectType::User => match self.user_service.find_by_id(&check.subject.id).await? {
+                Some(user) => (user.is_active, user.tenant_id),
+                None => return Ok(ResolvedSubject::Denied(DecisionReason::SubjectNotFound)),
+            },
+            SubjectType::Client => {
+                match self
+                    .client_service
+                    .find_by_client_id(&check.subject.id)
+                    .await?
+                {
+                    Some(client) => (client.client.is_active, client.client.tenant_id),
+                    None => return Ok(ResolvedSubject::Denied(DecisionReason::SubjectNotFound)),
+                }
+            }
+        };
+        // 其他租户的主体对请求方不可见
+        if !can_access(caller_tenant, tenant_id.as_deref()) {
+            return Ok(ResolvedSubject::Denied(DecisionReason::SubjectNotFound));
+        }
+        if !is_active {
+            return Ok(ResolvedSubject::Denied(DecisionReason::SubjectInactive));
+        }
+
+        Ok(match check.subject.subject_type {
+            SubjectType::User => ResolvedSubject::User {
+                id: check.subject.id.clone(),
+                cached: self
+                    .rbac_service
+                    .get_user_permissions(&check.subject.id)
+                    .await?,
+            },
+            SubjectType::Client => ResolvedSubject::Client {
+                client_id: check.subject.id.clone(),
+            },
+        })
+    }
+
+    async fn decide(
+        &self,
+        subject: &ResolvedSubject,
+        check: &AuthzCheck,
+        permission: &str,
+    ) -> Result<AuthzDecision, ServiceError> {
+        let decision = |allowed, reason, matched_roles| AuthzDecision {
+            allowed,
+            permission: permission.to_string(),
+            reason,
+            matched_roles,
+        };
+        match subject {
+            ResolvedSubject::Denied(reason) => Ok(decision(false, *reason, None)),
+            ResolvedSubject::Client { client_id } => {
+                let allowed = self
+                    .rbac_service
+                    .has_permission_for_client(client_id, permission)
+                    .await?;
+                let reason = if allowed {
+                    DecisionReason::ClientGrant
+                } else {
+                    DecisionReason::NoGrant
+                };
+                Ok(decision(allowed, reason, None))
+            }
+            ResolvedSubject::User { id, cached } => {
+                // 无条件的全局授予与上下文无关，直接由缓存回答
+                if !check.explain && cached.iter().any(|p| p == permission) {
+                    return Ok(decision(true, DecisionReason::RoleGrant, None));
+                }
+                let evaluation = self
+                    .rbac_service
+                    .evaluate_permission(id, permission, &check.context)
+                    .await?;
+                let reason = if evaluation.granted {
+                    DecisionReason::RoleGrant
+                } else if evaluation.grants.is_empty() {
+                    DecisionReason::NoGrant
+                } else {
+                    DecisionReason::ConditionsNotMet
+                };
+                let matched_roles = evaluation
+                    .grants
+                    .into_iter()
+                    .filter(|grant| grant.matched)
+                    .map(|grant| MatchedRole {
+                        role_id: grant.role_id,
+                        role_name: grant.role_name,
+                    })
+                    .collect();
+                Ok(decision(evaluation.granted, reason, Some(matched_roles)))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl AuthzService for AuthzServiceImpl {
+    async fn check(
+        &self,
+        check: &AuthzCheck,
+        permission: &str,
+    ) -> Result<AuthzDecision, ServiceError> {
+        let subject = self.resolve(check).await?;
+        self.decide(&subject, check, permission).await
+    }
+
+    async fn check_many(
+        &self,
+        check: &AuthzCheck,
+        permissions: &[String],
+    ) -> Result<Vec<AuthzDecision>, ServiceError> {
+        if permissions.len() > MAX_BATCH_CHECKS {
+            return Err(ServiceError::ValidationError(format!(
+                "At most {MAX_BATCH_CHECKS} permissions can be checked at once"
+            )));
+        }
+        let subject = self.resolve(check).await?;
+        let mut decisions = Vec::with_capacity(permissions.len());
+        for permission in permissions {
+            decisions.push(self.decide(&subject, check, permission).await?);
+        }
+        Ok(decisions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::clients::CreateClientRequest;
+    use crate::services::role_service::RoleAssignmentOptions;
+    use crate::state::AppState;
+    use crate::state::test_support::{client_request, create_client, create_user, setup_state};
+    use crate::utils::permission_conditions::PermissionCondition;
+    use axum::body::Body;
+    use axum::extract::ConnectInfo;
+    use axum::http::{header, Request, StatusCode};
+    use std::net::SocketAddr;
+    use tower::ServiceExt;
+
+    /// 创建拥有普通用户角色的用户
+    async fn create_member(state: &AppState, username: &str) -> String {
+        let user_id = create_user(state, username).await;
+        state
+            .role_service
+            .assign_role_to_user(&user_id, "clh3000003", RoleAssignmentOptions::default())
+            .await
+            .unwrap();
+        user_id
+    }
+
+    fn user_check(user_id: &str, context: AccessContext, explain: bool) -> AuthzCheck {
+        AuthzCheck {
+            subject: Subject {
+                subject_type: SubjectType::User,
+                id: user_id.to_string(),
+            },
+            caller_tenant: None,
+            context,
+            explain,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_decisions() {
+        let (state, pool) = setup_state().await;
+        let service = &state.authz_service;
+        let user_id = create_member(&state, "alice").await;
+
+        // 无条件的全局授予由权限缓存回答，要求解释时返回授予的角色
+        let check = user_check(&user_id, AccessContext::default(), false);
+        let decision = service.check(&check, "users:read").await.unwrap();
+        assert!(decision.allowed);
+        assert_eq!(decision.reason, DecisionReason::RoleGrant);
+        assert!(decision.matched_roles.is_none());
+        let decision = service
+            .check(&user_check(&user_id, AccessContext::default(), true), "users:read")
+            .await
+            .unwrap();
+        assert_eq!(
+            decision.matched_roles.unwrap(),
+            vec![MatchedRole {
+                role_id: "clh3000003".to_string(),
+                role_name: "user".to_string(),
+            }]
+        );
+        let decision = service.check(&check, "users:delete").await.unwrap();
+        assert!(!decision.allowed);
+        assert_eq!(decision.reason, DecisionReason::NoGrant);
+
+        // 带附加条件的授予按上下文求值
+        let permission_id: String =
+            sqlx::query_scalar("SELECT id FROM permissions WHERE name = 'users:list'")
+                .fetch_one(&*pool)
+                .await
+                .unwrap();
+        state
+            .role_service
+            .set_permission_conditions(
+                "clh3000003",
+                &permission_id,
+                Some(PermissionCondition::parse(r#"{"ip_range": ["10.0.0.0/8"]}"#).unwrap()),
+            )
+            .await
+            .unwrap();
+        let office = AccessContext {
+            ip_address: Some("10.1.2.3".parse().unwrap()),
+            ..Default::default()
+        };
+        let decisions = service
+            .check_many(
+                &user_check(&user_id, office, false),
+                &["users:list".to_string(), "users:read".to_string()],
+            )
+            .await
+            .unwrap();
+        assert!(decisions.iter().all(|decision| decision.allowed));
+        let decision = service.check(&check, "users:list").await.unwrap();
+        assert!(!decision.allowed);
+        assert_eq!(decision.reason, DecisionReason::ConditionsNotMet);
+        assert_eq!(decision.matched_roles, Some(vec![]));
+
+        // 停用和不存在的主体一律拒绝
+        state
+            .user_service
+            .update_user(&user_id, None, Some(false))
+            .await
+            .unwrap();
+        let decision = service.check(&check, "users:read").await.unwrap();
+        assert_eq!(decision.reason, DecisionReason::SubjectInactive);
+        let decision = service
+            .check(&user_check("missing", AccessContext::default(), false), "users:read")
+            .await
+            .unwrap();
+        assert_eq!(decision.reason, DecisionReason::SubjectNotFound);
+
+        // 客户端主体按客户端权限判断
+        let (client, _) = create_client(
+            &state,
+            CreateClientRequest {
+                client_permissions: Some(vec!["orders:read".to_string()]),
+                ..client_request("orders api")
+            },
+        )
+        .await;
+        let client_check = AuthzCheck {
+            subject: Subject {
+                subject_type: SubjectType::Client,
+                id: client.client.client_id.clone(),
+            },
+            caller_tenant: None,
+            context: AccessContext::default(),
+            explain: false,
+        };
+        let decisions = service
+            .check_many(
+                &client_check,
+                &["orders:read".to_string(), "orders:delete".to_string()],
+            )
+            .await
+            .unwrap();
+        assert_eq!(decisions[0].reason, DecisionReason::ClientGrant);
+        assert!(!decisions[1].allowed);
+
+        let too_many = vec!["users:read".to_string(); MAX_BATCH_CHECKS + 1];
+        assert!(matches!(
+            service.check_many(&client_check, &too_many).await,
+            Err(ServiceError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_check_endpoints() {
+        let (state, pool) = setup_state().await;
+        let user_id = create_member(&state, "bob").await;
+        let (client, _) = create_client(&state, client_request("web")).await;
+        let user_token = state
+            .token_service
+            .issue_tokens(
+                &client,
+                Some(user_id.clone()),
+                "openid".to_string(),
+                vec!["users:read".to_string()],
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+            .access_token;
+        let (resource_server, _) = create_client(
+            &state,
+            CreateClientRequest {
+                client_permissions: Some(vec![AUTHZ_CHECK_PERMISSION.to_string()]),
+                ..client_request("resource-server")
+            },
+        )
+        .await;
+        let server_token = state
+            .token_service
+            .issue_tokens(
+                &resource_server,
+                None,
+                "openid".to_string(),
+                vec![AUTHZ_CHECK_PERMISSION.to_string()],
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+            .access_token;
+
+        // 普通用户只能在办公网络内列出用户
+        let permission_id: String =
+            sqlx::query_scalar("SELECT id FROM permissions WHERE name = 'users:list'")
+                .fetch_one(&*pool)
+                .await
+                .unwrap();
+        state
+            .role_service
+            .set_permission_conditions(
+                "clh3000003",
+                &permission_id,
+                Some(PermissionCondition::parse(r#"{"ip_range": ["10.0.0.0/8"]}"#).unwrap()),
+            )
+            .await
+            .unwrap();
+
+        let app = crate::app::create_app(pool, state.config.clone()).await;
+        let call = |uri: &str, token: &str, body: serde_json::Value| {
+            Request::post(uri)
+                .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let subject = serde_json::json!({"type": "user", "id": user_id});
+
+        // 请求方检查自身权限无需额外权限 (前端按权限显示菜单)
+        let response = app
+            .clone()
+            .oneshot(call(
+                "/api/v2/authz/check/batch",
+                &user_token,
+                serde_json::json!({
+                    "checks": [
+                        {"permission": "users:read"},
+                        {"resource": "users", "action": "delete"}
+                    ]
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["results"][0]["allowed"], true);
+        assert_eq!(body["results"][1]["permission"], "users:delete");
+        assert_eq!(body["results"][1]["reason"], "no_grant");
+
+        // 检查自身时，条件按本次请求的来源 IP 和客户端求值，不接受调用方声称的上下文
+        let self_check = |peer: [u8; 4]| {
+            let mut request = call(
+                "/api/v2/authz/check",
+                &user_token,
+                serde_json::json!({
+                    "permission": "users:list",
+                    "context": {"ip_address": "10.1.2.3", "client_id": "other-client"},
+                }),
+            );
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from((peer, 40000))));
+            request
+        };
+        let response = app.clone().oneshot(self_check([192, 0, 2, 1])).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["allowed"], false);
+        assert_eq!(body["reason"], "conditions_not_met");
+        let response = app.clone().oneshot(self_check([10, 9, 8, 7])).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["allowed"], true);
+
+        // 检查其他主体需要 authz:check 权限
+        let body = serde_json::json!({"subject": subject, "permission": "users:read"});
+        let server_id = resource_server.client.client_id.clone();
+        let other = serde_json::json!({
+            "subject": {"type": "client", "id": server_id},
+            "permission": AUTHZ_CHECK_PERMISSION,
+        });
+        let response = app
+            .clone()
+            .oneshot(call("/api/v2/authz/check", &user_token, other))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(call("/api/v2/authz/check", &server_token, body))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["allowed"], true);
+        assert_eq!(body["reason"], "role_grant");
+
+        // 用户属性和请求时间不能由调用方提供
+        let response = app
+            .clone()
+            .oneshot(call(
+                "/api/v2/authz/check",
+                &server_token,
+                serde_json::json!({
+                    "subject": subject,
+                    "permission": "users:read",
+                    "context": {"user_attributes": {"organization": "acme"}},
+                }),
+            ))
+            .await
+            .unwrap();
+        assert!(response.status().is_client_error());
+
+        let response = app
+            .oneshot(call(
+                "/api/v2/authz/check",
+                &server_token,
+                serde_json::json!({"subject": subject}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,13 +1,33 @@
 #![allow(clippy::uninlined_format_args)]
 use crate::error::ServiceError;
 use crate::models::client::{ClientType, OAuthClient, OAuthClientDetails};
+use crate::services::tenant_service::ensure_tenant_exists;
 use crate::utils::crypto;
 use async_trait::async_trait;
 use chrono::Utc;
-use sqlx::SqlitePool;
+use sqlx::{SqliteConnection, SqlitePool};
 use std::sync::Arc;
 use uuid::Uuid;
 
+/// 未指定时新建客户端的访问令牌有效期（秒），与 `oauth_clients` 表的默认值一致
+pub const DEFAULT_ACCESS_TOKEN_TTL: i64 = 3600;
+/// 未指定时新建客户端的刷新令牌有效期（秒），与 `oauth_clients` 表的默认值一致
+pub const DEFAULT_REFRESH_TOKEN_TTL: i64 = 2_592_000;
+/// 管理后台使用的内置客户端
+pub const INTERNAL_CLIENT_ID: &str = "auth-center-admin-client";
+
+/// Declarative client settings; every list replaces the stored one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientSettings {
+    pub name: String,
+    pub is_active: bool,
+    pub redirect_uris: Vec<String>,
+    pub grant_types: Vec<String>,
+    pub response_types: Vec<String>,
+    pub allowed_scopes: Vec<String>,
+    pub client_permissions: Vec<String>,
+}
+
 #[async_trait]
 pub trait ClientService: Send + Sync {
     async fn find_by_client_id(
@@ -23,11 +43,24 @@ pub trait ClientService: Send + Sync {
         &self,
         request: crate::routes::clients::CreateClientRequest,
     ) -> Result<(OAuthClientDetails, String), ServiceError>;
+    /// Creates a client with a caller-chosen `client_id` (e.g. from an RBAC document).
+    async fn create_client_with_id(
+        &self,
+        client_id: &str,
+        request: crate::routes::clients::CreateClientRequest,
+    ) -> Result<(OAuthClientDetails, String), ServiceError>;
     async fn list_clients(
         &self,
         limit: Option<i32>,
         offset: Option<i32>,
     ) -> Result<Vec<OAuthClientDetails>, ServiceError>;
+    /// Lists the clients owned by a tenant.
+    async fn list_clients_in_tenant(
+        &self,
+        tenant_id: &str,
+        limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> Result<Vec<OAuthClientDetails>, ServiceError>;
     async fn update_client(
         &self,
         client_id: &str,
@@ -36,6 +69,12 @@ pub trait ClientService: Send + Sync {
         allowed_scopes: Option<Vec<String>>,
         is_active: Option<bool>,
     ) -> Result<OAuthClientDetails, ServiceError>;
+    /// Replaces the name, status, URIs, grant/response types, scopes and permissions of a client.
+    async fn set_client_settings(
+        &self,
+        client_id: &str,
+        settings: ClientSettings,
+    ) -> Result<OAuthClientDetails, ServiceError>;
     async fn delete_client(&self, client_id: &str) -> Result<(), ServiceError>;
     async fn get_internal_client(&self) -> Result<OAuthClientDetails, ServiceError>;
 }
@@ -48,6 +87,192 @@ impl ClientServiceImpl {
     pub fn new(db: Arc<SqlitePool>) -> Self {
         Self { db }
     }
+
+    /// Lists clients page by page; when `tenant_id` is set only that tenant's clients are returned.
+    async fn query_clients(
+        &self,
+        tenant_id: Option<&str>,
+        limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> Result<Vec<OAuthClientDetails>, ServiceError> {
+        let limit = limit.unwrap_or(50).min(100);
+        let offset = offset.unwrap_or(0);
+
+        let clients = sqlx::query_as::<_, OAuthClient>(
+            "SELECT id, client_id, client_secret, name, description, client_type, logo_uri, \
+             policy_uri, tos_uri, jwks_uri, token_endpoint_auth_method, require_pkce, \
+             require_consent, is_active, created_at, updated_at, access_token_ttl, \
+             refresh_token_ttl, authorization_code_lifetime, strict_redirect_uri_matching, \
+             allow_localhost_redirect, require_https_redirect, tenant_id \
+             FROM oauth_clients WHERE (? IS NULL OR tenant_id = ?) ORDER BY created_at DESC LIMIT ? OFFSET ?",
+        )
+        .bind(tenant_id)
+        .bind(tenant_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&*self.db)
+        .await?;
+
+        // Optimize: Parallelize client detail fetches using futures::join_all
+        // This reduces I/O latency from sequential client fetches to concurrent requests
+        let client_ids: Vec<String> = clients
+            .into_iter()
+            .map(|client| client.client_id)
+            .collect();
+
+        let futures = client_ids
+            .iter()
+            .map(|id| self.find_by_client_id(id));
+
+        let results = futures::future::join_all(futures).await;
+
+        let detailed_clients = results
+            .into_iter()
+            .filter_map(|result| result.ok().flatten())
+            .collect();
+
+        Ok(detailed_clients)
+    }
+
+    /// Inserts a client and its related rows with the given public `client_id`.
+    async fn insert_client(
+        &self,
+        client_id: String,
+        request: crate::routes::clients::CreateClientRequest,
+    ) -> Result<(OAuthClientDetails, String), ServiceError> {
+        if let Some(tenant_id) = request.tenant_id.as_deref() {
+            ensure_tenant_exists(&self.db, tenant_id).await?;
+        }
+
+        let mut tx = self.db.begin().await?;
+        let plain_secret = insert_client_rows(&mut tx, &client_id, &request).await?;
+        tx.commit().await?;
+
+        let client_details = self.find_by_client_id(&client_id).await?.ok_or_else(|| {
+            ServiceError::Internal("Failed to retrieve created client".to_string())
+        })?;
+
+        Ok((client_details, plain_secret))
+    }
+}
+
+/// Inserts a client and its related rows on the given connection (usually a transaction)
+/// and returns the plain secret of a confidential client. The tenant must already exist.
+pub(crate) async fn insert_client_rows(
+    conn: &mut SqliteConnection,
+    client_id: &str,
+    request: &crate::routes::clients::CreateClientRequest,
+) -> Result<String, ServiceError> {
+    let client_type_enum = match request.client_type.to_uppercase().as_str() {
+        "PUBLIC" => ClientType::PUBLIC,
+        "CONFIDENTIAL" => ClientType::CONFIDENTIAL,
+        _ => {
+            return Err(ServiceError::ValidationError(format!(
+                "Invalid client_type: {}. Must be PUBLIC or CONFIDENTIAL",
+                request.client_type
+            )))
+        }
+    };
+
+    let id = Uuid::new_v4().to_string();
+
+    let (client_secret_hash, plain_secret) = if client_type_enum == ClientType::CONFIDENTIAL {
+        let secret = Uuid::new_v4().to_string();
+        let hash = crypto::hash_password(&secret)?;
+        (Some(hash), secret)
+    } else {
+        (None, String::new())
+    };
+
+    let now = Utc::now();
+    let client_type_str = client_type_enum.to_string();
+
+    sqlx::query(
+        r#"
+        INSERT INTO oauth_clients (
+            id, client_id, client_secret, name, client_type,
+            is_active, created_at, updated_at, access_token_ttl, refresh_token_ttl,
+            tenant_id
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&id)
+    .bind(client_id)
+    .bind(&client_secret_hash)
+    .bind(&request.name)
+    .bind(&client_type_str)
+    .bind(true) // is_active
+    .bind(now)
+    .bind(now)
+    .bind(request.access_token_ttl.unwrap_or(DEFAULT_ACCESS_TOKEN_TTL))
+    .bind(request.refresh_token_ttl.unwrap_or(DEFAULT_REFRESH_TOKEN_TTL))
+    .bind(&request.tenant_id)
+    .execute(&mut *conn)
+    .await?;
+
+    let client_permissions = request.client_permissions.clone().unwrap_or_default();
+    let related = [
+        ("client_redirect_uris", "uri", &request.redirect_uris),
+        ("client_grant_types", "grant_type", &request.grant_types),
+        ("client_response_types", "response_type", &request.response_types),
+        ("client_allowed_scopes", "scope", &request.allowed_scopes),
+        ("client_permissions", "permission", &client_permissions),
+    ];
+    for (table, column, values) in related {
+        for value in values {
+            sqlx::query(&format!(
+                "INSERT INTO {table} (client_id, {column}) VALUES (?, ?)"
+            ))
+            .bind(&id)
+            .bind(value)
+            .execute(&mut *conn)
+            .await?;
+        }
+    }
+
+    Ok(plain_secret)
+}
+
+/// Overwrites a client's name, status and related lists on the given connection.
+/// `id` is the internal primary key, not the public `client_id`.
+pub(crate) async fn write_client_settings(
+    conn: &mut SqliteConnection,
+    id: &str,
+    settings: &ClientSettings,
+) -> Result<(), ServiceError> {
+    sqlx::query(
+        "UPDATE oauth_clients SET name = ?, is_active = ?, updated_at = ? WHERE id = ?",
+    )
+    .bind(&settings.name)
+    .bind(settings.is_active)
+    .bind(Utc::now())
+    .bind(id)
+    .execute(&mut *conn)
+    .await?;
+
+    let related = [
+        ("client_redirect_uris", "uri", &settings.redirect_uris),
+        ("client_grant_types", "grant_type", &settings.grant_types),
+        ("client_response_types", "response_type", &settings.response_types),
+        ("client_allowed_scopes", "scope", &settings.allowed_scopes),
+        ("client_permissions", "permission", &settings.client_permissions),
+    ];
+    for (table, column, values) in related {
+        sqlx::query(&format!("DELETE FROM {table} WHERE client_id = ?"))
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+        for value in values {
+            sqlx::query(&format!(
+                "INSERT INTO {table} (client_id, {column}) VALUES (?, ?)"
+            ))
+            .bind(id)
+            .bind(value)
+            .execute(&mut *conn)
+            .await?;
+        }
+    }
+    Ok(())
 }
 
 #[async_trait]
@@ -62,7 +287,7 @@ impl ClientService for ClientServiceImpl {
                  policy_uri, tos_uri, jwks_uri, token_endpoint_auth_method, require_pkce, \
                  require_consent, is_active, created_at, updated_at, access_token_ttl, \
                  refresh_token_ttl, authorization_code_lifetime, strict_redirect_uri_matching, \
-                 allow_localhost_redirect, require_https_redirect \
+                 allow_localhost_redirect, require_https_redirect, tenant_id \
                  FROM oauth_clients WHERE client_id = ?"
             )
                 .bind(client_id)
@@ -176,111 +401,21 @@ impl ClientService for ClientServiceImpl {
         &self,
         request: crate::routes::clients::CreateClientRequest,
     ) -> Result<(OAuthClientDetails, String), ServiceError> {
-        let client_type_enum = match request.client_type.to_uppercase().as_str() {
-            "PUBLIC" => ClientType::PUBLIC,
-            "CONFIDENTIAL" => ClientType::CONFIDENTIAL,
-            _ => {
-                return Err(ServiceError::ValidationError(format!(
-                    "Invalid client_type: {}. Must be PUBLIC or CONFIDENTIAL",
-                    request.client_type
-                )))
-            }
-        };
-
-        let id = Uuid::new_v4().to_string();
-        let client_id = Uuid::new_v4().to_string();
-
-        let (client_secret_hash, plain_secret) = if client_type_enum == ClientType::CONFIDENTIAL {
-            let secret = Uuid::new_v4().to_string();
-            let hash = crypto::hash_password(&secret)?;
-            (Some(hash), secret)
-        } else {
-            (None, String::new())
-        };
-
-        let now = Utc::now();
-        let client_type_str = client_type_enum.to_string();
-
-        let mut tx = self.db.begin().await?;
-
-        sqlx::query(
-            r#"
-            INSERT INTO oauth_clients (
-                id, client_id, client_secret, name, client_type,
-                is_active, created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
-            "#,
-        )
-        .bind(&id)
-        .bind(&client_id)
-        .bind(&client_secret_hash)
-        .bind(&request.name)
-        .bind(&client_type_str)
-        .bind(true) // is_active
-        .bind(&now)
-        .bind(&now)
-        .execute(&mut *tx)
-        .await?;
-
-        for uri in &request.redirect_uris {
-            sqlx::query(
-                "INSERT INTO client_redirect_uris (client_id, uri) VALUES (?, ?)",
-            )
-            .bind(&id)
-            .bind(uri)
-            .execute(&mut *tx)
-            .await?;
-        }
-
-        for grant in &request.grant_types {
-            sqlx::query(
-                "INSERT INTO client_grant_types (client_id, grant_type) VALUES (?, ?)",
-            )
-            .bind(&id)
-            .bind(grant)
-            .execute(&mut *tx)
-            .await?;
-        }
-
-        for res_type in &request.response_types {
-            sqlx::query(
-                "INSERT INTO client_response_types (client_id, response_type) VALUES (?, ?)",
-            )
-            .bind(&id)
-            .bind(res_type)
-            .execute(&mut *tx)
-            .await?;
-        }
+        self.insert_client(Uuid::new_v4().to_string(), request).await
+    }
 
-        for scope in &request.allowed_scopes {
-            sqlx::query(
-                "INSERT INTO client_allowed_scopes (client_id, scope) VALUES (?, ?)",
-            )
-            .bind(&id)
-            .bind(scope)
-            .execute(&mut *tx)
-            .await?;
+    async fn create_client_with_id(
+        &self,
+        client_id: &str,
+        request: crate::routes::clients::CreateClientRequest,
+    ) -> Result<(OAuthClientDetails, String), ServiceError> {
+        if client_id.is_empty() {
+            return Err(ServiceError::ValidationError("client_id must not be empty".to_string()));
         }
-
-        if let Some(permissions) = &request.client_permissions {
-            for perm in permissions {
-                sqlx::query(
-                    "INSERT INTO client_permissions (client_id, permission) VALUES (?, ?)",
-                )
-                .bind(&id)
-                .bind(perm)
-                .execute(&mut *tx)
-                .await?;
-            }
+        if self.find_by_client_id(client_id).await?.is_some() {
+            return Err(ServiceError::Conflict(format!("Client '{client_id}' already exists")));
         }
-
-        tx.commit().await?;
-
-        let client_details = self.find_by_client_id(&client_id).await?.ok_or_else(|| {
-            ServiceError::Internal("Failed to retrieve created client".to_string())
-        })?;
-
-        Ok((client_details, plain_secret))
+        self.insert_client(client_id.to_string(), request).await
     }
 
     async fn list_clients(
@@ -288,41 +423,16 @@ impl ClientService for ClientServiceImpl {
         limit: Option<i32>,
         offset: Option<i32>,
     ) -> Result<Vec<OAuthClientDetails>, ServiceError> {
-        let limit = limit.unwrap_or(50).min(100);
-        let offset = offset.unwrap_or(0);
-
-        let clients = sqlx::query_as::<_, OAuthClient>(
-            "SELECT id, client_id, client_secret, name, description, client_type, logo_uri, \
-             policy_uri, tos_uri, jwks_uri, token_endpoint_auth_method, require_pkce, \
-             require_consent, is_active, created_at, updated_at, access_token_ttl, \
-             refresh_token_ttl, authorization_code_lifetime, strict_redirect_uri_matching, \
-             allow_localhost_redirect, require_https_redirect \
-             FROM oauth_clients ORDER BY created_at DESC LIMIT ? OFFSET ?",
-        )
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(&*self.db)
-        .await?;
-
-        // Optimize: Parallelize client detail fetches using futures::join_all
-        // This reduces I/O latency from sequential client fetches to concurrent requests
-        let client_ids: Vec<String> = clients
-            .into_iter()
-            .map(|client| client.client_id)
-            .collect();
-
-        let futures = client_ids
-            .iter()
-            .map(|id| self.find_by_client_id(id));
-
-        let results = futures::future::join_all(futures).await;
-
-        let detailed_clients = results
-            .into_iter()
-            .filter_map(|result| result.ok().flatten())
-            .collect();
+        self.query_clients(None, limit, offset).await
+    }
 
-        Ok(detailed_clients)
+    async fn list_clients_in_tenant(
+        &self,
+        tenant_id: &str,
+        limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> Result<Vec<OAuthClientDetails>, ServiceError> {
+        self.query_clients(Some(tenant_id), limit, offset).await
     }
 
     async fn update_client(
@@ -340,7 +450,7 @@ impl ClientService for ClientServiceImpl {
              policy_uri, tos_uri, jwks_uri, token_endpoint_auth_method, require_pkce, \
              require_consent, is_active, created_at, updated_at, access_token_ttl, \
              refresh_token_ttl, authorization_code_lifetime, strict_redirect_uri_matching, \
-             allow_localhost_redirect, require_https_redirect \
+             allow_localhost_redirect, require_https_redirect, tenant_id \
              FROM oauth_clients WHERE client_id = ? FOR UPDATE",
         )
         .bind(client_id)
@@ -405,6 +515,24 @@ impl ClientService for ClientServiceImpl {
             .ok_or_else(|| ServiceError::Internal("Failed to retrieve updated client".to_string()))
     }
 
+    async fn set_client_settings(
+        &self,
+        client_id: &str,
+        settings: ClientSettings,
+    ) -> Result<OAuthClientDetails, ServiceError> {
+        let client = self
+            .find_by_client_id(client_id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("Client '{client_id}' not found")))?;
+        let mut tx = self.db.begin().await?;
+        write_client_settings(&mut tx, &client.client.id, &settings).await?;
+        tx.commit().await?;
+
+        self.find_by_client_id(client_id)
+            .await?
+            .ok_or_else(|| ServiceError::Internal("Failed to retrieve updated client".to_string()))
+    }
+
     async fn delete_client(&self, client_id: &str) -> Result<(), ServiceError> {
         let client = self
             .find_by_client_id(client_id)
@@ -425,7 +553,7 @@ impl ClientService for ClientServiceImpl {
     }
 
     async fn get_internal_client(&self) -> Result<OAuthClientDetails, ServiceError> {
-        self.find_by_client_id("auth-center-admin-client")
+        self.find_by_client_id(INTERNAL_CLIENT_ID)
             .await?
             .ok_or_else(|| {
                 ServiceError::NotFound(
@@ -467,6 +595,9 @@ mod tests {
             response_types: vec!["code".to_string()],
             allowed_scopes: vec!["read".to_string(), "write".to_string()],
             client_permissions: None,
+            access_token_ttl: None,
+            refresh_token_ttl: None,
+            tenant_id: None,
         };
 
         let result = service.create_client(request).await;
@@ -500,6 +631,9 @@ mod tests {
             response_types: vec!["code".to_string()],
             allowed_scopes: vec!["read".to_string()],
             client_permissions: None,
+            access_token_ttl: None,
+            refresh_token_ttl: None,
+            tenant_id: None,
         };
 
         let result = service.create_client(request).await;
@@ -525,6 +659,9 @@ mod tests {
             response_types: vec!["code".to_string()],
             allowed_scopes: vec!["read".to_string()],
             client_permissions: None,
+            access_token_ttl: None,
+            refresh_token_ttl: None,
+            tenant_id: None,
         };
 
         let result = service.create_client(request).await;
@@ -551,6 +688,9 @@ mod tests {
             response_types: vec!["code".to_string()],
             allowed_scopes: vec!["read".to_string()],
             client_permissions: None,
+            access_token_ttl: None,
+            refresh_token_ttl: None,
+            tenant_id: None,
         };
 
         let (client_details, _) = service.create_client(request).await.unwrap();
@@ -588,6 +728,9 @@ mod tests {
             response_types: vec!["code".to_string()],
             allowed_scopes: vec!["read".to_string()],
             client_permissions: None,
+            access_token_ttl: None,
+            refresh_token_ttl: None,
+            tenant_id: None,
         };
 
         let (client_details, _) = service.create_client(request).await.unwrap();
@@ -611,6 +754,9 @@ mod tests {
             response_types: vec!["code".to_string()],
             allowed_scopes: vec!["read".to_string()],
             client_permissions: None,
+            access_token_ttl: None,
+            refresh_token_ttl: None,
+            tenant_id: None,
         };
 
         let (client_details, secret) = service.create_client(request).await.unwrap();
@@ -634,6 +780,9 @@ mod tests {
             response_types: vec!["code".to_string()],
             allowed_scopes: vec!["read".to_string()],
             client_permissions: None,
+            access_token_ttl: None,
+            refresh_token_ttl: None,
+            tenant_id: None,
         };
 
         let (client_details, _) = service.create_client(request).await.unwrap();
@@ -663,6 +812,9 @@ mod tests {
             response_types: vec!["code".to_string()],
             allowed_scopes: vec!["read".to_string()],
             client_permissions: None,
+            access_token_ttl: None,
+            refresh_token_ttl: None,
+            tenant_id: None,
         };
 
         let (client_details, _) = service.create_client(request).await.unwrap();
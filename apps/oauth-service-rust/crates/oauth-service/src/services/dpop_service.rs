@@ -0,0 +1,410 @@
+// DPoP 服务 (DPoP Service)
+//
+// 发送方约束的令牌 (RFC 9449):
+// - 客户端在令牌请求的 DPoP 请求头中附带用自己私钥签名的证明，令牌通过 `cnf.jkt`
+//   绑定到该公钥的 JWK 指纹，之后每次使用令牌都必须附带同一把密钥签名的新证明
+// - 令牌端点要求证明携带本服务签发的 nonce，缺失或过期时返回 use_dpop_nonce 和新的 nonce
+// - 每个证明只能使用一次，(jkt, jti) 在证明有效期内记录在数据库中用于发现重放
+
+use crate::error::ServiceError;
+use crate::utils::dpop::{self, VerifiedProof};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{Duration, Utc};
+use rand::RngCore;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+/// DPoP 证明的有效期 (秒)：iat 早于此时间的证明被拒绝，重放记录保留同样长的时间
+pub const PROOF_LIFETIME_SECS: i64 = 300;
+/// 允许的客户端时钟偏差 (秒)：iat 晚于当前时间超过此值的证明被拒绝
+pub const CLOCK_SKEW_SECS: i64 = 60;
+/// 服务端签发的 nonce 的有效期 (秒)
+pub const NONCE_LIFETIME_SECS: i64 = 300;
+
+/// 一个待验证的 DPoP 证明及其所在的请求
+#[derive(Debug, Clone, Copy)]
+pub struct DpopRequest<'a> {
+    /// DPoP 请求头的值
+    pub proof: &'a str,
+    /// 请求方法
+    pub method: &'a str,
+    /// 请求的完整 URI
+    pub uri: &'a str,
+    /// 随证明一起出示的访问令牌 (受保护资源)，证明的 ath 必须与之匹配
+    pub access_token: Option<&'a str>,
+    /// 是否要求证明携带本服务签发的 nonce
+    pub require_nonce: bool,
+}
+
+#[async_trait]
+pub trait DpopService: Send + Sync {
+    /// 签发新的 DPoP nonce
+    async fn issue_nonce(&self) -> Result<String, ServiceError>;
+    /// 验证 DPoP 证明并记录以防重放，返回证明公钥的 JWK 指纹 (jkt)
+    ///
+    /// 要求 nonce 而证明未携带有效 nonce 时返回 `UseDpopNonce` 和新的 nonce。
+    async fn validate_proof(&self, request: DpopRequest<'_>) -> Result<String, ServiceError>;
+}
+
+pub struct DpopServiceImpl {
+    db: Arc<SqlitePool>,
+}
+
+impl DpopServiceImpl {
+    pub fn new(db: Arc<SqlitePool>) -> Self {
+        Self { db }
+    }
+
+    async fn is_valid_nonce(&self, nonce: &str) -> Result<bool, ServiceError> {
+        let found: Option<String> =
+            sqlx::query_scalar("SELECT nonce FROM dpop_nonces WHERE nonce = ? AND expires_at > ?")
+                .bind(nonce)
+                .bind(Utc::now())
+                .fetch_optional(&*self.db)
+                .await?;
+        Ok(found.is_some())
+    }
+
+    /// 检查证明与请求的绑定 (htm、htu、ath) 和时效
+    fn check_request_binding(
+        proof: &VerifiedProof,
+        request: &DpopRequest<'_>,
+    ) -> Result<(), ServiceError> {
+        let claims = &proof.claims;
+        if !claims.htm.eq_ignore_ascii_case(request.method) {
+            return Err(ServiceError::InvalidDpopProof(
+                "DPoP proof htm does not match the request method".to_string(),
+            ));
+        }
+        match (dpop::normalize_htu(&claims.htu), dpop::normalize_htu(request.uri)) {
+            (Some(proof_uri), Some(request_uri)) if proof_uri == request_uri => {}
+            _ => {
+                return Err(ServiceError::InvalidDpopProof(
+                    "DPoP proof htu does not match the request URI".to_string(),
+                ))
+            }
+        }
+
+        let now = Utc::now().timestamp();
+        if claims.iat > now + CLOCK_SKEW_SECS || claims.iat < now - PROOF_LIFETIME_SECS {
+            return Err(ServiceError::InvalidDpopProof(
+                "DPoP proof is expired or issued in the future".to_string(),
+            ));
+        }
+
+        match request.access_token {
+            Some(token) if claims.ath.as_deref() != Some(dpop::access_token_hash(token).as_str()) => {
+                Err(ServiceError::InvalidDpopProof(
+                    "DPoP proof ath does not match the access token".to_string(),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl DpopService for DpopServiceImpl {
+    async fn issue_nonce(&self) -> Result<String, ServiceError> {
+        let mut bytes = [0u8; 24];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let nonce = URL_SAFE_NO_PAD.encode(bytes);
+        let now = Utc::now();
+        sqlx::query("INSERT INTO dpop_nonces (nonce, created_at, expires_at) VALUES (?, ?, ?)")
+            .bind(&nonce)
+            .bind(now)
+            .bind(now + Duration::seconds(NONCE_LIFETIME_SECS))
+            .execute(&*self.db)
+            .await?;
+        Ok(nonce)
+    }
+
+    async fn validate_proof(&self, request: DpopRequest<'_>) -> Result<String, ServiceError> {
+        let proof = dpop::verify_proof(request.proof)?;
+        Self::check_request_binding(&proof, &request)?;
+
+        if request.require_nonce {
+            let valid = match proof.claims.nonce.as_deref() {
+                Some(nonce) => self.is_valid_nonce(nonce).await?,
+                None => false,
+            };
+            if !valid {
+                return Err(ServiceError::UseDpopNonce(self.issue_nonce().await?));
+            }
+        }
+
+        // 每个证明只能使用一次
+        let result = sqlx::query(
+            "INSERT OR IGNORE INTO dpop_proof_jtis (jkt, jti, expires_at) VALUES (?, ?, ?)",
+        )
+        .bind(&proof.jkt)
+        .bind(&proof.claims.jti)
+        .bind(Utc::now() + Duration::seconds(PROOF_LIFETIME_SECS + CLOCK_SKEW_SECS))
+        .execute(&*self.db)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(ServiceError::InvalidDpopProof(
+                "DPoP proof has already been used".to_string(),
+            ));
+        }
+
+        Ok(proof.jkt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::routes::clients::CreateClientRequest;
+    use crate::services::role_service::RoleAssignmentOptions;
+    use crate::state::test_support::{
+        client_request, create_client, setup_state_with_config, TestAppState,
+    };
+    use crate::utils::dpop::test_support::TestDpopKey;
+    use crate::utils::dpop::DpopProofClaims;
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    const TOKEN_URI: &str = "https://as.example.com/api/v2/oauth/token";
+
+    async fn setup_state() -> (TestAppState, Arc<SqlitePool>) {
+        setup_state_with_config(Config {
+            issuer: "https://as.example.com".to_string(),
+            ..Config::default()
+        })
+        .await
+    }
+
+    fn token_request(proof: &str) -> DpopRequest<'_> {
+        DpopRequest {
+            proof,
+            method: "POST",
+            uri: TOKEN_URI,
+            access_token: None,
+            require_nonce: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validate_proof() {
+        let (state, _pool) = setup_state().await;
+        let service = &state.dpop_service;
+        let key = TestDpopKey::generate();
+
+        // 没有 nonce 时返回 use_dpop_nonce 和新签发的 nonce
+        let proof = key.proof("POST", TOKEN_URI, None, None);
+        let nonce = match service.validate_proof(token_request(&proof)).await {
+            Err(ServiceError::UseDpopNonce(nonce)) => nonce,
+            other => panic!("expected use_dpop_nonce, got {other:?}"),
+        };
+        let proof = key.proof("POST", TOKEN_URI, Some("made-up"), None);
+        assert!(matches!(
+            service.validate_proof(token_request(&proof)).await,
+            Err(ServiceError::UseDpopNonce(_))
+        ));
+
+        // 携带有效 nonce 的证明通过，返回公钥指纹；同一证明不能再次使用
+        let proof = key.proof("POST", TOKEN_URI, Some(&nonce), None);
+        assert_eq!(
+            service.validate_proof(token_request(&proof)).await.unwrap(),
+            key.thumbprint()
+        );
+        assert!(matches!(
+            service.validate_proof(token_request(&proof)).await,
+            Err(ServiceError::InvalidDpopProof(_))
+        ));
+
+        // htu 忽略查询参数，htm、htu、iat 不匹配时拒绝
+        let proof = key.proof("POST", &format!("{TOKEN_URI}?x=1"), Some(&nonce), None);
+        assert!(service.validate_proof(token_request(&proof)).await.is_ok());
+        let stale = key.sign(&DpopProofClaims {
+            jti: Uuid::new_v4().to_string(),
+            htm: "POST".to_string(),
+            htu: TOKEN_URI.to_string(),
+            iat: Utc::now().timestamp() - PROOF_LIFETIME_SECS - 1,
+            nonce: Some(nonce.clone()),
+            ath: None,
+        });
+        for proof in [
+            key.proof("GET", TOKEN_URI, Some(&nonce), None),
+            key.proof("POST", "https://rs.example.com/api", Some(&nonce), None),
+            stale,
+        ] {
+            assert!(matches!(
+                service.validate_proof(token_request(&proof)).await,
+                Err(ServiceError::InvalidDpopProof(_))
+            ));
+        }
+
+        // 出示访问令牌时 ath 必须匹配
+        let request = |proof| DpopRequest {
+            proof,
+            method: "GET",
+            uri: "https://as.example.com/api/v2/users/me",
+            access_token: Some("access-token"),
+            require_nonce: false,
+        };
+        let proof = key.proof("GET", "https://as.example.com/api/v2/users/me", None, Some("other"));
+        assert!(service.validate_proof(request(&proof)).await.is_err());
+        let proof = key.proof(
+            "GET",
+            "https://as.example.com/api/v2/users/me",
+            None,
+            Some("access-token"),
+        );
+        assert!(service.validate_proof(request(&proof)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_token_endpoint_issues_dpop_bound_tokens() {
+        let (state, pool) = setup_state().await;
+        let (client, secret) = create_client(&state, client_request("spa")).await;
+        let key = TestDpopKey::generate();
+        let app = crate::app::create_app(pool, state.config.clone()).await;
+        let call = |proof: String| {
+            Request::post("/api/v2/oauth/token")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(dpop::DPOP_HEADER, proof)
+                .body(Body::from(
+                    serde_json::json!({
+                        "grant_type": "client_credentials",
+                        "client_id": client.client.client_id,
+                        "client_secret": secret,
+                        "scope": "openid",
+                    })
+                    .to_string(),
+                ))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(call(key.proof("POST", TOKEN_URI, None, None)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let nonce = response.headers()[dpop::DPOP_NONCE_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"], "use_dpop_nonce");
+
+        let response = app
+            .oneshot(call(key.proof("POST", TOKEN_URI, Some(&nonce), None)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["token_type"], "DPoP");
+        let claims = state
+            .token_service
+            .introspect_token(body["access_token"].as_str().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(claims.cnf.unwrap().jkt, key.thumbprint());
+    }
+
+    #[tokio::test]
+    async fn test_dpop_bound_tokens_require_proof_of_possession() {
+        let (state, pool) = setup_state().await;
+        let (client, _) = create_client(
+            &state,
+            CreateClientRequest {
+                client_type: "PUBLIC".to_string(),
+                ..client_request("spa")
+            },
+        )
+        .await;
+        let user = state
+            .user_service
+            .create_user("spa-user".to_string(), "password123".to_string(), None)
+            .await
+            .unwrap();
+        state
+            .role_service
+            .assign_role_to_user(&user.id, "clh3000003", RoleAssignmentOptions::default())
+            .await
+            .unwrap();
+        let key = TestDpopKey::generate();
+        let tokens = state
+            .token_service
+            .issue_tokens(
+                &client,
+                Some(user.id.clone()),
+                "openid".to_string(),
+                vec![],
+                None,
+                None,
+                Some(key.thumbprint()),
+            )
+            .await
+            .unwrap();
+        let access_token = tokens.access_token;
+
+        // 受保护的端点: 必须使用 DPoP 方案并附带绑定密钥对该请求签名的证明
+        let me_uri = "https://as.example.com/api/v2/users/me";
+        let app = crate::app::create_app(pool, state.config.clone()).await;
+        let call = |authorization: String, proof: Option<String>| {
+            let mut builder = Request::get("/api/v2/users/me")
+                .header(header::AUTHORIZATION, authorization);
+            if let Some(proof) = proof {
+                builder = builder.header(dpop::DPOP_HEADER, proof);
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+        let other_key = TestDpopKey::generate();
+        let replayed = key.proof("GET", me_uri, None, Some(&access_token));
+        let response = app
+            .clone()
+            .oneshot(call(format!("DPoP {access_token}"), Some(replayed.clone())))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        for (authorization, proof) in [
+            (format!("Bearer {access_token}"), None),
+            (format!("DPoP {access_token}"), None),
+            (format!("DPoP {access_token}"), Some(replayed)),
+            (
+                format!("DPoP {access_token}"),
+                Some(other_key.proof("GET", me_uri, None, Some(&access_token))),
+            ),
+            (
+                format!("DPoP {access_token}"),
+                Some(key.proof("POST", me_uri, None, Some(&access_token))),
+            ),
+        ] {
+            let response = app.clone().oneshot(call(authorization, proof)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        // 公共客户端的刷新令牌同样绑定到该密钥
+        let refresh_token = tokens.refresh_token.unwrap();
+        for jkt in [None, Some(other_key.thumbprint())] {
+            assert!(matches!(
+                state
+                    .token_service
+                    .refresh_token(&refresh_token, None, jkt.as_deref())
+                    .await,
+                Err(ServiceError::InvalidDpopProof(_))
+            ));
+        }
+        let refreshed = state
+            .token_service
+            .refresh_token(&refresh_token, None, Some(&key.thumbprint()))
+            .await
+            .unwrap();
+        let claims = state
+            .token_service
+            .introspect_token(&refreshed.access_token)
+            .await
+            .unwrap();
+        assert_eq!(claims.cnf.unwrap().jkt, key.thumbprint());
+    }
+}
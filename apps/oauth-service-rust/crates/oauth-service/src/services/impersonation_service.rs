@@ -0,0 +1,569 @@
+// 模拟登录服务 (Impersonation Service)
+//
+// 拥有 `users:impersonate` 权限的用户 (通常是客服或运维人员) 可以获取一个代表其他用户的短期访问令牌:
+// - 令牌的 `sub` 是被模拟的用户，`act` 声明记录真实操作人 (RFC 8693)
+// - 只签发访问令牌，不签发刷新令牌和 ID 令牌，有效期不超过 `MAX_IMPERSONATION_SECS`
+// - 不能模拟拥有管理权限或操作人不具备的权限的用户，也不能在模拟会话中再次发起模拟
+// - 会话的开始和结束写入审计日志，会话期间的每个请求由审计中间件单独记录
+
+use crate::error::ServiceError;
+use crate::services::audit_log_service::{AuditLogService, NewAuditLogEntry};
+use crate::services::client_service::ClientService;
+use crate::services::rbac_service::RBACService;
+use crate::services::tenant_service::can_access;
+use crate::services::token_service::TokenService;
+use crate::services::user_service::UserService;
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 模拟会话的最长时间 (秒)，请求的时长超过该值时按该值签发
+pub const MAX_IMPERSONATION_SECS: i64 = 3600;
+
+/// 未指定时长时的默认会话时间 (秒)
+pub const DEFAULT_IMPERSONATION_SECS: i64 = 900;
+
+/// 发起模拟所需的权限，模拟令牌中永远不会包含该权限
+pub const IMPERSONATE_PERMISSION: &str = "users:impersonate";
+
+/// 拥有任一权限的用户不能被模拟，避免借助模拟提升权限
+pub const PRIVILEGED_PERMISSIONS: [&str; 2] = [IMPERSONATE_PERMISSION, "system:config"];
+
+/// 管理类权限的操作，拥有 `<resource>:<action>` 形式的此类权限的用户不能被模拟
+pub const ADMIN_ACTIONS: [&str; 5] = ["create", "update", "delete", "manage", "edit"];
+
+/// 权限是否为特权或管理类权限
+fn is_privileged(permission: &str) -> bool {
+    PRIVILEGED_PERMISSIONS.contains(&permission)
+        || permission
+            .rsplit_once(':')
+            .is_some_and(|(_, action)| ADMIN_ACTIONS.contains(&action))
+}
+
+/// 模拟会话，对应 `impersonation_sessions` 表
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ImpersonationSession {
+    pub id: String,
+    pub actor_user_id: String,
+    pub target_user_id: String,
+    pub client_id: String,
+    /// 模拟令牌的 JWT ID
+    #[serde(skip_serializing)]
+    pub jti: String,
+    pub reason: String,
+    pub tenant_id: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+/// 发起模拟的请求
+#[derive(Debug, Clone)]
+pub struct StartImpersonation {
+    pub actor_user_id: String,
+    /// 操作人所属租户，租户内的操作人只能模拟本租户的用户
+    pub actor_tenant: Option<String>,
+    /// 签发令牌的客户端 (公开的 client_id)
+    pub client_id: String,
+    pub target_user_id: String,
+    pub reason: String,
+    pub duration_secs: Option<i64>,
+}
+
+/// 模拟登录的结果
+#[derive(Debug)]
+pub struct ImpersonationGrant {
+    pub access_token: String,
+    pub expires_in: u64,
+    pub session: ImpersonationSession,
+}
+
+#[async_trait]
+pub trait ImpersonationService: Send + Sync {
+    /// 校验目标用户后签发模拟令牌并记录会话
+    async fn start(&self, request: StartImpersonation) -> Result<ImpersonationGrant, ServiceError>;
+    /// 提前结束模拟会话并吊销其令牌，会话不存在或已结束时返回 NotFound
+    async fn end_session(&self, jti: &str) -> Result<ImpersonationSession, ServiceError>;
+    /// 查找令牌对应的、尚未结束且未过期的模拟会话
+    async fn find_active_session(
+        &self,
+        jti: &str,
+    ) -> Result<Option<ImpersonationSession>, ServiceError>;
+}
+
+pub struct ImpersonationServiceImpl {
+    db: Arc<SqlitePool>,
+    user_service: Arc<dyn UserService>,
+    client_service: Arc<dyn ClientService>,
+    rbac_service: Arc<dyn RBACService>,
+    token_service: Arc<dyn TokenService>,
+    audit_log_service: Arc<dyn AuditLogService>,
+}
+
+impl ImpersonationServiceImpl {
+    pub fn new(
+        db: Arc<SqlitePool>,
+        user_service: Arc<dyn UserService>,
+        client_service: Arc<dyn ClientService>,
+        rbac_service: Arc<dyn RBACService>,
+        token_service: Arc<dyn TokenService>,
+        audit_log_service: Arc<dyn AuditLogService>,
+    ) -> Self {
+        Self {
+            db,
+            user_service,
+            client_service,
+            rbac_service,
+            token_service,
+            audit_log_service,
+        }
+    }
+
+    async fn find_session(&self, jti: &str) -> Result<Option<ImpersonationSession>, ServiceError> {
+        let session = sqlx::query_as::<_, ImpersonationSession>(
+            "SELECT id, actor_user_id, target_user_id, client_id, jti, reason, tenant_id, started_at, expires_at, ended_at
+             FROM impersonation_sessions WHERE jti = ?",
+        )
+        .bind(jti)
+        .fetch_optional(&*self.db)
+        .await?;
+        Ok(session)
+    }
+
+    async fn audit(&self, action: &str, session: &ImpersonationSession) {
+        let entry = NewAuditLogEntry {
+            user_id: Some(session.target_user_id.clone()),
+            actor_type: "user".to_string(),
+            actor_id: session.actor_user_id.clone(),
+            action: action.to_string(),
+            resource_type: Some("user".to_string()),
+            resource_id: Some(session.target_user_id.clone()),
+            details: Some(serde_json::json!({
+                "session_id": session.id,
+                "client_id": session.client_id,
+                "reason": session.reason,
+                "expires_at": session.expires_at.to_rfc3339(),
+            })),
+            status: "success".to_string(),
+            tenant_id: session.tenant_id.clone(),
+            ..Default::default()
+        };
+        if let Err(e) = self.audit_log_service.record(entry).await {
+            tracing::warn!("Failed to write impersonation audit log: {}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl ImpersonationService for ImpersonationServiceImpl {
+    async fn start(&self, request: StartImpersonation) -> Result<ImpersonationGrant, ServiceError> {
+        let reason = request.reason.trim().to_string();
+        if reason.is_empty() {
+            return Err(ServiceError::ValidationError(
+                "A reason is required to impersonate a user".to_string(),
+            ));
+        }
+        let duration = request.duration_secs.unwrap_or(DEFAULT_IMPERSONATION_SECS);
+        if duration <= 0 {
+            return Err(ServiceError::ValidationError(
+                "Impersonation duration must be positive".to_string(),
+            ));
+        }
+        let duration = duration.min(MAX_IMPERSONATION_SECS);
+
+        if request.actor_user_id == request.target_user_id {
+            return Err(ServiceError::ValidationError(
+                "Cannot impersonate yourself".to_string(),
+            ));
+        }
+
+        // 其他租户的用户视为不存在
+        let target = self
+            .user_service
+            .find_by_id(&request.target_user_id)
+            .await?
+            .filter(|user| can_access(request.actor_tenant.as_deref(), user.tenant_id.as_deref()))
+            .ok_or_else(|| ServiceError::NotFound("User not found".to_string()))?;
+        if !target.is_active {
+            return Err(ServiceError::ValidationError(
+                "Cannot impersonate an inactive user".to_string(),
+            ));
+        }
+
+        let client = self
+            .client_service
+            .find_by_client_id(&request.client_id)
+            .await?
+            .ok_or_else(|| {
+                ServiceError::NotFound(format!("Client {} not found", request.client_id))
+            })?;
+        let target_permissions = self
+            .rbac_service
+            .get_user_permissions_for_client(&target.id, &request.client_id)
+            .await?;
+        let actor_permissions = self
+            .rbac_service
+            .get_user_permissions(&request.actor_user_id)
+            .await?;
+        if target_permissions
+            .iter()
+            .any(|perm| is_privileged(perm) || !actor_permissions.contains(perm))
+        {
+            return Err(ServiceError::Forbidden(
+                "Privileged users cannot be impersonated".to_string(),
+            ));
+        }
+        let permissions: Vec<String> = target_permissions
+            .into_iter()
+            .filter(|perm| perm != IMPERSONATE_PERMISSION)
+            .collect();
+
+        let (access_token, claims) = self
+            .token_service
+            .issue_impersonation_token(
+                &client,
+                &target.id,
+                &request.actor_user_id,
+                permissions,
+                duration,
+            )
+            .await?;
+
+        let session = ImpersonationSession {
+            id: Uuid::new_v4().to_string(),
+            actor_user_id: request.actor_user_id,
+            target_user_id: target.id,
+            client_id: request.client_id,
+            jti: claims.jti,
+            reason,
+            tenant_id: claims.tenant,
+            started_at: Utc.timestamp_opt(claims.iat as i64, 0).unwrap(),
+            expires_at: Utc.timestamp_opt(claims.exp as i64, 0).unwrap(),
+            ended_at: None,
+        };
+        sqlx::query(
+            "INSERT INTO impersonation_sessions (id, actor_user_id, target_user_id, client_id, jti, reason, tenant_id, started_at, expires_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&session.id)
+        .bind(&session.actor_user_id)
+        .bind(&session.target_user_id)
+        .bind(&session.client_id)
+        .bind(&session.jti)
+        .bind(&session.reason)
+        .bind(&session.tenant_id)
+        .bind(session.started_at)
+        .bind(session.expires_at)
+        .execute(&*self.db)
+        .await?;
+
+        self.audit("impersonation.start", &session).await;
+
+        Ok(ImpersonationGrant {
+            access_token,
+            expires_in: duration as u64,
+            session,
+        })
+    }
+
+    async fn end_session(&self, jti: &str) -> Result<ImpersonationSession, ServiceError> {
+        let mut session = self
+            .find_session(jti)
+            .await?
+            .filter(|session| session.ended_at.is_none())
+            .ok_or_else(|| ServiceError::NotFound("Impersonation session not found".to_string()))?;
+        let now = Utc::now();
+
+        // 令牌加入黑名单直到其自然过期
+        let mut tx = self.db.begin().await?;
+        sqlx::query("UPDATE impersonation_sessions SET ended_at = ? WHERE id = ?")
+            .bind(now)
+            .bind(&session.id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT OR IGNORE INTO token_blacklist (id, jti, token_type, user_id, client_id, expires_at, reason, created_at)
+             VALUES (?, ?, 'access_token', ?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&session.jti)
+        .bind(&session.target_user_id)
+        .bind(&session.client_id)
+        .bind(session.expires_at)
+        .bind("Impersonation ended")
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        session.ended_at = Some(now);
+        self.audit("impersonation.end", &session).await;
+        Ok(session)
+    }
+
+    async fn find_active_session(
+        &self,
+        jti: &str,
+    ) -> Result<Option<ImpersonationSession>, ServiceError> {
+        Ok(self
+            .find_session(jti)
+            .await?
+            .filter(|session| session.ended_at.is_none() && session.expires_at > Utc::now()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::client::OAuthClientDetails;
+    use crate::services::role_service::RoleAssignmentOptions;
+    use crate::state::AppState;
+    use crate::state::test_support::{client_request, create_client, setup_state};
+    use crate::utils::jwt;
+    use axum::body::Body;
+    use axum::extract::ConnectInfo;
+    use axum::http::{Request, StatusCode};
+    use std::net::SocketAddr;
+    use tower::ServiceExt;
+
+    /// 创建一个客服 (操作人)、一个普通用户、一个超级管理员和一个管理员，返回四者的 ID
+    async fn setup_users(state: &AppState) -> (String, String, String, String) {
+        let mut ids = Vec::new();
+        for (username, role_id) in [
+            ("support", "clh3000003"),
+            ("carol", "clh3000003"),
+            ("root", "clh3000001"),
+            ("alice", "clh3000002"),
+        ] {
+            let user = state
+                .user_service
+                .create_user(username.to_string(), "password123".to_string(), None)
+                .await
+                .unwrap();
+            state
+                .role_service
+                .assign_role_to_user(&user.id, role_id, RoleAssignmentOptions::default())
+                .await
+                .unwrap();
+            ids.push(user.id);
+        }
+        (ids[0].clone(), ids[1].clone(), ids[2].clone(), ids[3].clone())
+    }
+
+    fn start_request(
+        client: &OAuthClientDetails,
+        actor: &str,
+        target: &str,
+        duration_secs: Option<i64>,
+    ) -> StartImpersonation {
+        StartImpersonation {
+            actor_user_id: actor.to_string(),
+            actor_tenant: None,
+            client_id: client.client.client_id.clone(),
+            target_user_id: target.to_string(),
+            reason: "Ticket #4711".to_string(),
+            duration_secs,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_impersonation_token_and_guards() {
+        let (state, _pool) = setup_state().await;
+        let (client, _) = create_client(&state, client_request("support console")).await;
+        let (support, carol, root, admin) = setup_users(&state).await;
+        let service = &state.impersonation_service;
+
+        // 超过上限的时长按上限签发
+        let long_grant = service
+            .start(start_request(&client, &support, &carol, Some(24 * 3600)))
+            .await
+            .unwrap();
+        assert_eq!(long_grant.expires_in, MAX_IMPERSONATION_SECS as u64);
+        let decoding_key = state.config.load_decoding_key().unwrap();
+        let claims = jwt::verify_token(&long_grant.access_token, &decoding_key).unwrap();
+        assert_eq!(claims.sub.as_deref(), Some(carol.as_str()));
+        assert_eq!(claims.act, Some(jwt::ActorClaim { sub: support.clone() }));
+        assert_eq!(claims.exp - claims.iat, MAX_IMPERSONATION_SECS as usize);
+        assert!(claims.permissions.contains(&"users:read".to_string()));
+        assert!(service.find_active_session(&claims.jti).await.unwrap().is_some());
+
+        let grant = service
+            .start(start_request(&client, &support, &carol, None))
+            .await
+            .unwrap();
+        assert_eq!(grant.expires_in, DEFAULT_IMPERSONATION_SECS as u64);
+
+        // 特权用户、自己、缺少原因和其他租户的用户都不能模拟
+        let result = service.start(start_request(&client, &support, &root, None)).await;
+        assert!(matches!(result.unwrap_err(), ServiceError::Forbidden(_)));
+        // 管理员角色 (没有 system:config) 拥有角色和客户端的管理权限，操作人不具备这些权限
+        let result = service.start(start_request(&client, &support, &admin, None)).await;
+        assert!(matches!(result.unwrap_err(), ServiceError::Forbidden(_)));
+        // 即使操作人拥有同样的权限，也不能模拟拥有管理权限的用户
+        let result = service.start(start_request(&client, &root, &admin, None)).await;
+        assert!(matches!(result.unwrap_err(), ServiceError::Forbidden(_)));
+        let result = service.start(start_request(&client, &support, &support, None)).await;
+        assert!(matches!(result.unwrap_err(), ServiceError::ValidationError(_)));
+        let result = service.start(start_request(&client, &support, &carol, Some(0))).await;
+        assert!(matches!(result.unwrap_err(), ServiceError::ValidationError(_)));
+        let mut request = start_request(&client, &support, &carol, None);
+        request.reason = "  ".to_string();
+        let result = service.start(request).await;
+        assert!(matches!(result.unwrap_err(), ServiceError::ValidationError(_)));
+        let mut request = start_request(&client, &support, &carol, None);
+        request.actor_tenant = Some("acme".to_string());
+        let result = service.start(request).await;
+        assert!(matches!(result.unwrap_err(), ServiceError::NotFound(_)));
+
+        // 结束会话后令牌被吊销，会话不能重复结束
+        service.end_session(&claims.jti).await.unwrap();
+        assert!(service.find_active_session(&claims.jti).await.unwrap().is_none());
+        assert!(state
+            .token_service
+            .introspect_token(&long_grant.access_token)
+            .await
+            .is_err());
+        assert!(state.token_service.introspect_token(&grant.access_token).await.is_ok());
+        assert!(matches!(
+            service.end_session(&claims.jti).await.unwrap_err(),
+            ServiceError::NotFound(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_impersonated_requests_are_audited() {
+        let (state, pool) = setup_state().await;
+        let (client, _) = create_client(&state, client_request("support console")).await;
+        let (support, carol, _root, _admin) = setup_users(&state).await;
+        let support_token = state
+            .token_service
+            .issue_tokens(
+                &client,
+                Some(support.clone()),
+                "".to_string(),
+                vec!["users:impersonate".to_string(), "users:read".to_string()],
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+            .access_token;
+
+        let app = crate::app::create_app(pool.clone(), state.config.clone()).await;
+        let call = |method: &str, uri: String, token: &str, body: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("authorization", format!("Bearer {token}"))
+                .header("content-type", "application/json")
+                .header("x-forwarded-for", "198.51.100.99")
+                .extension(ConnectInfo(SocketAddr::from(([203, 0, 113, 7], 40000))))
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let json = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(call(
+                "POST",
+                format!("/api/v2/admin/users/{carol}/impersonate"),
+                &support_token,
+                r#"{"reason": "Ticket #4711", "duration_secs": 600}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let grant = json(response).await;
+        assert_eq!(grant["expires_in"], 600);
+        let token = grant["access_token"].as_str().unwrap().to_string();
+
+        // 模拟会话中显示模拟横幅所需的信息
+        let response = app
+            .clone()
+            .oneshot(call("GET", "/api/v2/users/me".to_string(), &token, ""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let me = json(response).await;
+        assert_eq!(me["id"], carol.as_str());
+        assert_eq!(me["impersonated"], true);
+        assert_eq!(me["impersonator"]["id"], support.as_str());
+        assert_eq!(me["impersonator"]["username"], "support");
+        let response = app
+            .clone()
+            .oneshot(call("GET", "/api/v2/users/me".to_string(), &support_token, ""))
+            .await
+            .unwrap();
+        let me = json(response).await;
+        assert_eq!(me["impersonated"], false);
+        assert!(me["impersonator"].is_null());
+
+        // 模拟会话中不能再次发起模拟
+        let response = app
+            .clone()
+            .oneshot(call(
+                "POST",
+                format!("/api/v2/admin/users/{support}/impersonate"),
+                &token,
+                r#"{"reason": "chain"}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .clone()
+            .oneshot(call("POST", "/api/v2/users/me/impersonation/end".to_string(), &token, ""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app
+            .clone()
+            .oneshot(call("GET", "/api/v2/users/me".to_string(), &token, ""))
+            .await
+            .unwrap();
+        assert!(response.status().is_client_error());
+
+        // 每个模拟请求都记录真实操作人和被模拟的用户
+        let rows: Vec<(String, String, Option<String>, String)> = sqlx::query_as(
+            "SELECT action, actor_id, user_id, status FROM audit_logs
+             WHERE action LIKE 'impersonation.%' ORDER BY timestamp, rowid",
+        )
+        .fetch_all(&*pool)
+        .await
+        .unwrap();
+        let actions: Vec<&str> = rows.iter().map(|row| row.0.as_str()).collect();
+        assert_eq!(
+            actions,
+            [
+                "impersonation.start",
+                "impersonation.request",
+                "impersonation.request",
+                "impersonation.end",
+                "impersonation.request",
+            ]
+        );
+        assert!(rows
+            .iter()
+            .all(|row| row.1 == support && row.2.as_deref() == Some(carol.as_str())));
+        assert_eq!(rows[2].3, "failure");
+
+        // 记录连接的对端地址，而不是客户端自报的 x-forwarded-for
+        let ips: Vec<Option<String>> = sqlx::query_scalar(
+            "SELECT ip_address FROM audit_logs WHERE action = 'impersonation.request'",
+        )
+        .fetch_all(&*pool)
+        .await
+        .unwrap();
+        assert_eq!(ips.len(), 3);
+        assert!(ips.iter().all(|ip| ip.as_deref() == Some("203.0.113.7")));
+    }
+}
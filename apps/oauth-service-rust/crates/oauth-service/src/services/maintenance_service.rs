@@ -0,0 +1,354 @@
+// 数据维护服务 (Maintenance Service)
+
+use crate::error::ServiceError;
+use crate::middleware::login_rate_limit::LoginRateLimiter;
+use crate::middleware::rate_limit::RateLimiter;
+use crate::services::audit_log_service::{AuditLogService, NewAuditLogEntry};
+use crate::services::role_service::RoleService;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// 带有 `expires_at` 列、过期后可直接删除的表
+const EXPIRING_TABLES: &[&str] = &[
+    "authorization_codes",
+    "refresh_tokens",
+    "token_blacklist",
+    "revoked_auth_jtis",
+    "password_reset_requests",
+    "federated_login_states",
+    "webauthn_challenges",
+    "dpop_nonces",
+    "dpop_proof_jtis",
+];
+
+/// 一次清理的结果：每张表删除的行数
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PurgeReport {
+    pub tables: Vec<(String, u64)>,
+}
+
+impl PurgeReport {
+    /// 删除的总行数
+    pub fn total(&self) -> u64 {
+        self.tables.iter().map(|(_, count)| count).sum()
+    }
+}
+
+/// 维护任务的触发方式
+#[derive(Debug, Clone, PartialEq)]
+pub enum MaintenanceTrigger {
+    /// 后台定时任务
+    Scheduler,
+    /// 管理员通过管理 API 手动触发
+    Admin { user_id: Option<String>, client_id: String },
+}
+
+impl MaintenanceTrigger {
+    /// 指标和审计日志中使用的名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Scheduler => "scheduler",
+            Self::Admin { .. } => "admin_api",
+        }
+    }
+}
+
+/// 一次维护任务的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceReport {
+    pub trigger: &'static str,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub purged: PurgeReport,
+    pub total_purged: u64,
+}
+
+#[async_trait]
+pub trait MaintenanceService: Send + Sync {
+    /// 删除所有已过期的授权码、令牌、黑名单记录、密码重置请求和角色分配
+    async fn purge_expired_data(&self) -> Result<PurgeReport, ServiceError>;
+
+    /// 执行一次完整的维护：清理过期数据、整理限流器状态，并记录指标和审计日志。
+    /// 同一时间只会有一次维护在执行，并发的调用会等待前一次完成
+    async fn run(&self, trigger: MaintenanceTrigger) -> Result<MaintenanceReport, ServiceError>;
+}
+
+/// 定期执行维护任务
+pub fn spawn_maintenance_scheduler(
+    maintenance_service: Arc<dyn MaintenanceService>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = maintenance_service.run(MaintenanceTrigger::Scheduler).await {
+                tracing::warn!("Scheduled maintenance failed: {}", e);
+            }
+        }
+    })
+}
+
+pub struct MaintenanceServiceImpl {
+    db: Arc<SqlitePool>,
+    role_service: Arc<dyn RoleService>,
+    audit_log_service: Arc<dyn AuditLogService>,
+    rate_limiters: Vec<Arc<RateLimiter>>,
+    login_rate_limiter: Option<Arc<LoginRateLimiter>>,
+    batch_size: u32,
+    run_lock: tokio::sync::Mutex<()>,
+}
+
+impl MaintenanceServiceImpl {
+    pub fn new(
+        db: Arc<SqlitePool>,
+        role_service: Arc<dyn RoleService>,
+        audit_log_service: Arc<dyn AuditLogService>,
+        batch_size: u32,
+    ) -> Self {
+        Self {
+            db,
+            role_service,
+            audit_log_service,
+            rate_limiters: Vec::new(),
+            login_rate_limiter: None,
+            batch_size: batch_size.max(1),
+            run_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// 维护时一并清理这些限流器中已过窗口的记录
+    pub fn with_rate_limiters(
+        mut self,
+        rate_limiters: Vec<Arc<RateLimiter>>,
+        login_rate_limiter: Arc<LoginRateLimiter>,
+    ) -> Self {
+        self.rate_limiters = rate_limiters;
+        self.login_rate_limiter = Some(login_rate_limiter);
+        self
+    }
+
+    /// 分批删除 `table` 中已过期的记录，每批之间让出执行权以免长时间占用写锁
+    async fn purge_table(&self, table: &str, now: DateTime<Utc>) -> Result<u64, ServiceError> {
+        let sql = format!(
+            "DELETE FROM {table} WHERE rowid IN (
+                SELECT rowid FROM {table} WHERE datetime(expires_at) <= datetime(?) LIMIT ?
+            )"
+        );
+        let mut purged = 0;
+        loop {
+            let deleted = sqlx::query(&sql)
+                .bind(now)
+                .bind(self.batch_size as i64)
+                .execute(&*self.db)
+                .await?
+                .rows_affected();
+            purged += deleted;
+            if deleted < self.batch_size as u64 {
+                return Ok(purged);
+            }
+            tokio::task::yield_now().await;
+        }
+    }
+
+    async fn record_audit(
+        &self,
+        trigger: &MaintenanceTrigger,
+        status: &str,
+        details: serde_json::Value,
+    ) {
+        let (actor_type, actor_id, user_id) = match trigger {
+            MaintenanceTrigger::Scheduler => ("system", "scheduler".to_string(), None),
+            MaintenanceTrigger::Admin { user_id: Some(user_id), .. } => {
+                ("user", user_id.clone(), Some(user_id.clone()))
+            }
+            MaintenanceTrigger::Admin { user_id: None, client_id } => {
+                ("client", client_id.clone(), None)
+            }
+        };
+        let entry = NewAuditLogEntry {
+            user_id,
+            actor_type: actor_type.to_string(),
+            actor_id,
+            action: "maintenance.run".to_string(),
+            resource_type: Some("maintenance".to_string()),
+            details: Some(details),
+            status: status.to_string(),
+            ..Default::default()
+        };
+        if let Err(e) = self.audit_log_service.record(entry).await {
+            tracing::warn!("Failed to write maintenance audit log: {}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl MaintenanceService for MaintenanceServiceImpl {
+    async fn purge_expired_data(&self) -> Result<PurgeReport, ServiceError> {
+        let now = Utc::now();
+        let mut report = PurgeReport::default();
+
+        for table in EXPIRING_TABLES {
+            let purged = self.purge_table(table, now).await?;
+            report.tables.push((table.to_string(), purged));
+        }
+
+        // 角色分配需要同时清除权限缓存，交给 RoleService 处理
+        let assignments = self.role_service.purge_expired_assignments().await?;
+        report.tables.push(("user_roles".to_string(), assignments));
+
+        tracing::info!("Purged {} expired row(s): {:?}", report.total(), report.tables);
+        Ok(report)
+    }
+
+    async fn run(&self, trigger: MaintenanceTrigger) -> Result<MaintenanceReport, ServiceError> {
+        let _running = self.run_lock.lock().await;
+        let started_at = Utc::now();
+        let started = Instant::now();
+
+        let purged = match self.purge_expired_data().await {
+            Ok(purged) => purged,
+            Err(e) => {
+                crate::metrics::record_maintenance_run(trigger.as_str(), None);
+                self.record_audit(&trigger, "failure", serde_json::json!({ "error": e.to_string() }))
+                    .await;
+                return Err(e);
+            }
+        };
+
+        for rate_limiter in &self.rate_limiters {
+            rate_limiter.cleanup().await;
+        }
+        if let Some(login_rate_limiter) = &self.login_rate_limiter {
+            login_rate_limiter.cleanup().await;
+        }
+
+        let report = MaintenanceReport {
+            trigger: trigger.as_str(),
+            started_at,
+            duration_ms: started.elapsed().as_millis() as u64,
+            total_purged: purged.total(),
+            purged,
+        };
+        crate::metrics::record_maintenance_run(trigger.as_str(), Some(&report.purged.tables));
+        self.record_audit(
+            &trigger,
+            "success",
+            serde_json::to_value(&report).unwrap_or_default(),
+        )
+        .await;
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::permission_cache::InMemoryPermissionCache;
+    use crate::services::audit_log_service::AuditLogServiceImpl;
+    use crate::services::role_service::RoleServiceImpl;
+
+    async fn setup() -> (MaintenanceServiceImpl, Arc<SqlitePool>) {
+        let pool = Arc::new(SqlitePool::connect("sqlite::memory:").await.unwrap());
+        for table in EXPIRING_TABLES {
+            sqlx::query(&format!(
+                "CREATE TABLE {table} (id TEXT PRIMARY KEY, expires_at DATETIME NOT NULL)"
+            ))
+            .execute(&*pool)
+            .await
+            .unwrap();
+        }
+        sqlx::query(
+            "CREATE TABLE user_roles (user_id TEXT, role_id TEXT, expires_at DATETIME);
+             CREATE TABLE audit_logs (
+                id TEXT PRIMARY KEY, timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+                user_id TEXT, actor_type TEXT NOT NULL, actor_id TEXT NOT NULL,
+                action TEXT NOT NULL, resource_type TEXT, resource_id TEXT, details TEXT,
+                status TEXT NOT NULL, ip_address TEXT, user_agent TEXT, tenant_id TEXT
+             )",
+        )
+        .execute(&*pool)
+        .await
+        .unwrap();
+
+        let role_service = Arc::new(RoleServiceImpl::new(
+            pool.clone(),
+            Arc::new(InMemoryPermissionCache::new()),
+        ));
+        let audit_log_service = Arc::new(AuditLogServiceImpl::new(pool.clone()));
+        let service = MaintenanceServiceImpl::new(pool.clone(), role_service, audit_log_service, 2);
+        (service, pool)
+    }
+
+    #[tokio::test]
+    async fn test_run_purges_in_batches_and_audits() {
+        let (service, pool) = setup().await;
+        let expired = Utc::now() - chrono::Duration::hours(1);
+        let valid = Utc::now() + chrono::Duration::hours(1);
+        for i in 0..5 {
+            sqlx::query("INSERT INTO refresh_tokens (id, expires_at) VALUES (?, ?)")
+                .bind(format!("expired-{i}"))
+                .bind(expired)
+                .execute(&*pool)
+                .await
+                .unwrap();
+        }
+        sqlx::query("INSERT INTO refresh_tokens (id, expires_at) VALUES ('valid', ?)")
+            .bind(valid)
+            .execute(&*pool)
+            .await
+            .unwrap();
+
+        let report = service.run(MaintenanceTrigger::Scheduler).await.unwrap();
+        assert_eq!(report.trigger, "scheduler");
+        assert_eq!(report.total_purged, 5);
+        assert!(report
+            .purged
+            .tables
+            .contains(&("refresh_tokens".to_string(), 5)));
+
+        let remaining: Vec<String> = sqlx::query_scalar("SELECT id FROM refresh_tokens")
+            .fetch_all(&*pool)
+            .await
+            .unwrap();
+        assert_eq!(remaining, vec!["valid".to_string()]);
+
+        let (actor_type, actor_id, status, details): (String, String, String, String) =
+            sqlx::query_as(
+                "SELECT actor_type, actor_id, status, details FROM audit_logs WHERE action = 'maintenance.run'",
+            )
+            .fetch_one(&*pool)
+            .await
+            .unwrap();
+        assert_eq!((actor_type.as_str(), actor_id.as_str()), ("system", "scheduler"));
+        assert_eq!(status, "success");
+        assert!(details.contains("\"total_purged\":5"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_run_is_audited() {
+        let (service, pool) = setup().await;
+        sqlx::query("DROP TABLE token_blacklist").execute(&*pool).await.unwrap();
+
+        let trigger = MaintenanceTrigger::Admin {
+            user_id: Some("admin-1".to_string()),
+            client_id: "admin-portal".to_string(),
+        };
+        assert!(service.run(trigger).await.is_err());
+
+        let (actor_id, status): (String, String) = sqlx::query_as(
+            "SELECT actor_id, status FROM audit_logs WHERE action = 'maintenance.run'",
+        )
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+        assert_eq!(actor_id, "admin-1");
+        assert_eq!(status, "failure");
+    }
+}
@@ -0,0 +1,762 @@
+use crate::error::ServiceError;
+use crate::models::menu::{Menu, MenuNode};
+use crate::routes::menus::{CreateMenuRequest, UpdateMenuRequest};
+use crate::services::rbac_service::RBACService;
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+const MENU_COLUMNS: &str = "id, name, key, path, component, icon, \"order\", is_hidden, is_active, \
+                            parent_id, created_at, updated_at";
+
+#[async_trait]
+pub trait MenuService: Send + Sync {
+    /// 创建菜单
+    async fn create_menu(&self, request: CreateMenuRequest) -> Result<Menu, ServiceError>;
+
+    /// 根据ID查找菜单
+    async fn find_menu_by_id(&self, menu_id: &str) -> Result<Option<Menu>, ServiceError>;
+
+    /// 列出所有菜单 (按父菜单和排序值排列的扁平列表)
+    async fn list_menus(&self) -> Result<Vec<Menu>, ServiceError>;
+
+    /// 更新菜单
+    async fn update_menu(
+        &self,
+        menu_id: &str,
+        request: UpdateMenuRequest,
+    ) -> Result<Menu, ServiceError>;
+
+    /// 删除菜单 (存在子菜单时拒绝删除)
+    async fn delete_menu(&self, menu_id: &str) -> Result<(), ServiceError>;
+
+    /// 设置访问菜单所需的权限 (覆盖原有关联)
+    async fn set_menu_permissions(
+        &self,
+        menu_id: &str,
+        permission_ids: Vec<String>,
+    ) -> Result<(), ServiceError>;
+
+    /// 获取访问菜单所需的权限名称
+    async fn get_menu_permissions(&self, menu_id: &str) -> Result<Vec<String>, ServiceError>;
+
+    /// 获取完整菜单树 (管理视图，包含未激活菜单)
+    async fn get_menu_tree(&self) -> Result<Vec<MenuNode>, ServiceError>;
+
+    /// 获取用户有权查看的菜单树
+    async fn get_user_menu_tree(&self, user_id: &str) -> Result<Vec<MenuNode>, ServiceError>;
+}
+
+pub struct MenuServiceImpl {
+    db: Arc<SqlitePool>,
+    rbac_service: Arc<dyn RBACService>,
+}
+
+impl MenuServiceImpl {
+    pub fn new(db: Arc<SqlitePool>, rbac_service: Arc<dyn RBACService>) -> Self {
+        Self { db, rbac_service }
+    }
+
+    /// 加载所有菜单及其权限映射 (menu_id -> 权限名称)，以及未激活的权限名称
+    ///
+    /// 未激活的权限仍保留在菜单的权限要求中，只是任何用户都无法满足它，
+    /// 否则停用菜单唯一的权限会让菜单对所有人可见。
+    async fn load_menus_with_permissions(
+        &self,
+    ) -> Result<(Vec<Menu>, HashMap<String, Vec<String>>, HashSet<String>), ServiceError> {
+        let menus = self.list_menus().await?;
+
+        let rows: Vec<(String, String, bool)> = sqlx::query_as(
+            "SELECT mp.menu_id, p.name, p.is_active FROM menu_permissions mp
+             JOIN permissions p ON p.id = mp.permission_id
+             ORDER BY p.name",
+        )
+        .fetch_all(&*self.db)
+        .await?;
+
+        let mut permissions: HashMap<String, Vec<String>> = HashMap::new();
+        let mut inactive = HashSet::new();
+        for (menu_id, name, is_active) in rows {
+            if !is_active {
+                inactive.insert(name.clone());
+            }
+            permissions.entry(menu_id).or_default().push(name);
+        }
+
+        Ok((menus, permissions, inactive))
+    }
+
+    /// 校验父菜单存在，且不会形成环 (父菜单不能是自身或其后代)
+    async fn validate_parent(
+        &self,
+        menu_id: Option<&str>,
+        parent_id: &str,
+    ) -> Result<(), ServiceError> {
+        let menus = self.list_menus().await?;
+        let parents: HashMap<&str, Option<&str>> = menus
+            .iter()
+            .map(|m| (m.id.as_str(), m.parent_id.as_deref()))
+            .collect();
+
+        if !parents.contains_key(parent_id) {
+            return Err(ServiceError::ValidationError(format!(
+                "Parent menu '{parent_id}' not found"
+            )));
+        }
+
+        if let Some(menu_id) = menu_id {
+            let mut current = Some(parent_id);
+            let mut visited = HashSet::new();
+            while let Some(id) = current {
+                if id == menu_id {
+                    return Err(ServiceError::ValidationError(
+                        "A menu cannot be moved under itself or one of its descendants".to_string(),
+                    ));
+                }
+                if !visited.insert(id) {
+                    break;
+                }
+                current = parents.get(id).copied().flatten();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 根据扁平菜单列表构建菜单树
+///
+/// `is_visible` 决定单个菜单是否可见，不可见菜单的整个子树都会被剪除；
+/// 没有路径的分组菜单如果子菜单全部被剪除，也不再显示。
+fn build_menu_tree<F>(
+    menus: Vec<Menu>,
+    permissions: &HashMap<String, Vec<String>>,
+    is_visible: F,
+) -> Vec<MenuNode>
+where
+    F: Fn(&Menu, &[String]) -> bool,
+{
+    let known: HashSet<String> = menus.iter().map(|m| m.id.clone()).collect();
+    let mut children_of: HashMap<Option<String>, Vec<Menu>> = HashMap::new();
+    for menu in menus {
+        // 父菜单不存在的孤立菜单视为顶级菜单
+        let parent = menu.parent_id.clone().filter(|p| known.contains(p));
+        children_of.entry(parent).or_default().push(menu);
+    }
+
+    build_subtree(None, &mut children_of, permissions, &is_visible)
+}
+
+fn build_subtree<F>(
+    parent: Option<String>,
+    children_of: &mut HashMap<Option<String>, Vec<Menu>>,
+    permissions: &HashMap<String, Vec<String>>,
+    is_visible: &F,
+) -> Vec<MenuNode>
+where
+    F: Fn(&Menu, &[String]) -> bool,
+{
+    let mut siblings = children_of.remove(&parent).unwrap_or_default();
+    siblings.sort_by(|a, b| a.order.cmp(&b.order).then_with(|| a.name.cmp(&b.name)));
+
+    let mut nodes = Vec::with_capacity(siblings.len());
+    for menu in siblings {
+        let required = permissions.get(&menu.id).cloned().unwrap_or_default();
+        if !is_visible(&menu, &required) {
+            continue;
+        }
+
+        let has_children = children_of.contains_key(&Some(menu.id.clone()));
+        let children = build_subtree(Some(menu.id.clone()), children_of, permissions, is_visible);
+        if menu.path.is_none() && has_children && children.is_empty() {
+            continue;
+        }
+
+        nodes.push(MenuNode {
+            menu,
+            permissions: required,
+            children,
+        });
+    }
+    nodes
+}
+
+#[async_trait]
+impl MenuService for MenuServiceImpl {
+    async fn create_menu(&self, request: CreateMenuRequest) -> Result<Menu, ServiceError> {
+        let existing: Option<String> = sqlx::query_scalar("SELECT id FROM menus WHERE key = ?")
+            .bind(&request.key)
+            .fetch_optional(&*self.db)
+            .await?;
+        if existing.is_some() {
+            return Err(ServiceError::Conflict(format!(
+                "Menu with key '{}' already exists",
+                request.key
+            )));
+        }
+
+        if let Some(parent_id) = request.parent_id.as_deref() {
+            self.validate_parent(None, parent_id).await?;
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        sqlx::query(
+            r#"
+            INSERT INTO menus (id, name, key, path, component, icon, "order", is_hidden, is_active, parent_id, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(&request.name)
+        .bind(&request.key)
+        .bind(&request.path)
+        .bind(&request.component)
+        .bind(&request.icon)
+        .bind(request.order.unwrap_or(0))
+        .bind(request.is_hidden.unwrap_or(false))
+        .bind(request.is_active.unwrap_or(true))
+        .bind(&request.parent_id)
+        .bind(now)
+        .bind(now)
+        .execute(&*self.db)
+        .await?;
+
+        self.find_menu_by_id(&id)
+            .await?
+            .ok_or_else(|| ServiceError::Internal("Failed to retrieve created menu".to_string()))
+    }
+
+    async fn find_menu_by_id(&self, menu_id: &str) -> Result<Option<Menu>, ServiceError> {
+        let menu = sqlx::query_as::<_, Menu>(&format!(
+            "SELECT {MENU_COLUMNS} FROM menus WHERE id = ?"
+        ))
+        .bind(menu_id)
+        .fetch_optional(&*self.db)
+        .await?;
+
+        Ok(menu)
+    }
+
+    async fn list_menus(&self) -> Result<Vec<Menu>, ServiceError> {
+        let menus = sqlx::query_as::<_, Menu>(&format!(
+            "SELECT {MENU_COLUMNS} FROM menus ORDER BY parent_id, \"order\", name"
+        ))
+        .fetch_all(&*self.db)
+        .await?;
+
+        Ok(menus)
+    }
+
+    async fn update_menu(
+        &self,
+        menu_id: &str,
+        request: UpdateMenuRequest,
+    ) -> Result<Menu, ServiceError> {
+        let existing = self
+            .find_menu_by_id(menu_id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("Menu '{menu_id}' not found")))?;
+
+        if let Some(new_key) = &request.key {
+            if new_key != &existing.key {
+                let conflict: Option<String> =
+                    sqlx::query_scalar("SELECT id FROM menus WHERE key = ?")
+                        .bind(new_key)
+                        .fetch_optional(&*self.db)
+                        .await?;
+                if conflict.is_some() {
+                    return Err(ServiceError::Conflict(format!(
+                        "Menu with key '{new_key}' already exists"
+                    )));
+                }
+            }
+        }
+
+        // parent_id 为空字符串表示移动到顶级
+        let parent_id = match request.parent_id {
+            Some(parent_id) if parent_id.is_empty() => None,
+            Some(parent_id) => {
+                self.validate_parent(Some(menu_id), &parent_id).await?;
+                Some(parent_id)
+            }
+            None => existing.parent_id,
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE menus
+            SET name = ?, key = ?, path = ?, component = ?, icon = ?, "order" = ?,
+                is_hidden = ?, is_active = ?, parent_id = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(request.name.unwrap_or(existing.name))
+        .bind(request.key.unwrap_or(existing.key))
+        .bind(request.path.unwrap_or(existing.path))
+        .bind(request.component.unwrap_or(existing.component))
+        .bind(request.icon.unwrap_or(existing.icon))
+        .bind(request.order.unwrap_or(existing.order))
+        .bind(request.is_hidden.unwrap_or(existing.is_hidden))
+        .bind(request.is_active.unwrap_or(existing.is_active))
+        .bind(parent_id)
+        .bind(Utc::now())
+        .bind(menu_id)
+        .execute(&*self.db)
+        .await?;
+
+        self.find_menu_by_id(menu_id)
+            .await?
+            .ok_or_else(|| ServiceError::Internal("Failed to retrieve updated menu".to_string()))
+    }
+
+    async fn delete_menu(&self, menu_id: &str) -> Result<(), ServiceError> {
+        let _ = self
+            .find_menu_by_id(menu_id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("Menu '{menu_id}' not found")))?;
+
+        let has_children = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM menus WHERE parent_id = ?)",
+        )
+        .bind(menu_id)
+        .fetch_one(&*self.db)
+        .await?;
+
+        if has_children {
+            return Err(ServiceError::Conflict(
+                "Menu has child menus; delete or move them first".to_string(),
+            ));
+        }
+
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query("DELETE FROM menu_permissions WHERE menu_id = ?")
+            .bind(menu_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM menus WHERE id = ?")
+            .bind(menu_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn set_menu_permissions(
+        &self,
+        menu_id: &str,
+        permission_ids: Vec<String>,
+    ) -> Result<(), ServiceError> {
+        let _ = self
+            .find_menu_by_id(menu_id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("Menu '{menu_id}' not found")))?;
+
+        // 使用事务保护覆盖操作
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query("DELETE FROM menu_permissions WHERE menu_id = ?")
+            .bind(menu_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for permission_id in permission_ids {
+            let permission_exists = sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS(SELECT 1 FROM permissions WHERE id = ?)",
+            )
+            .bind(&permission_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            if !permission_exists {
+                tx.rollback().await?;
+                return Err(ServiceError::NotFound(format!(
+                    "Permission '{permission_id}' not found"
+                )));
+            }
+
+            // menu_permissions.permission_id 是唯一的：一个权限只能控制一个菜单
+            let bound_to: Option<String> = sqlx::query_scalar(
+                "SELECT menu_id FROM menu_permissions WHERE permission_id = ?",
+            )
+            .bind(&permission_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            if let Some(other_menu) = bound_to {
+                tx.rollback().await?;
+                return Err(ServiceError::Conflict(format!(
+                    "Permission '{permission_id}' is already bound to menu '{other_menu}'"
+                )));
+            }
+
+            sqlx::query("INSERT INTO menu_permissions (id, permission_id, menu_id) VALUES (?, ?, ?)")
+                .bind(Uuid::new_v4().to_string())
+                .bind(&permission_id)
+                .bind(menu_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_menu_permissions(&self, menu_id: &str) -> Result<Vec<String>, ServiceError> {
+        let permissions = sqlx::query_scalar::<_, String>(
+            "SELECT p.name FROM permissions p
+             JOIN menu_permissions mp ON p.id = mp.permission_id
+             WHERE mp.menu_id = ?
+             ORDER BY p.name",
+        )
+        .bind(menu_id)
+        .fetch_all(&*self.db)
+        .await?;
+
+        Ok(permissions)
+    }
+
+    async fn get_menu_tree(&self) -> Result<Vec<MenuNode>, ServiceError> {
+        let (menus, permissions, _) = self.load_menus_with_permissions().await?;
+        Ok(build_menu_tree(menus, &permissions, |_, _| true))
+    }
+
+    async fn get_user_menu_tree(&self, user_id: &str) -> Result<Vec<MenuNode>, ServiceError> {
+        let user_permissions: HashSet<String> = self
+            .rbac_service
+            .get_user_permissions(user_id)
+            .await?
+            .into_iter()
+            .collect();
+        let (menus, permissions, inactive) = self.load_menus_with_permissions().await?;
+
+        // 未关联权限的菜单对所有登录用户可见；否则需拥有其中任一已激活的权限
+        Ok(build_menu_tree(menus, &permissions, |menu, required| {
+            menu.is_active
+                && (required.is_empty()
+                    || required
+                        .iter()
+                        .any(|p| !inactive.contains(p) && user_permissions.contains(p)))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::permission_cache::InMemoryPermissionCache;
+    use crate::services::rbac_service::RBACServiceImpl;
+    use sqlx::SqlitePool;
+
+    async fn setup_test_db() -> SqlitePool {
+        let pool = SqlitePool::connect(":memory:")
+            .await
+            .expect("Failed to create in-memory database");
+
+        // 创建测试表结构
+        for ddl in [
+            r#"
+            CREATE TABLE menus (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                key TEXT UNIQUE NOT NULL,
+                path TEXT,
+                component TEXT,
+                icon TEXT,
+                "order" INTEGER DEFAULT 0,
+                is_hidden INTEGER DEFAULT 0,
+                is_active INTEGER DEFAULT 1,
+                parent_id TEXT,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+            r#"
+            CREATE TABLE permissions (
+                id TEXT PRIMARY KEY,
+                name TEXT UNIQUE NOT NULL,
+                is_active BOOLEAN DEFAULT 1
+            )
+            "#,
+            r#"
+            CREATE TABLE menu_permissions (
+                id TEXT PRIMARY KEY,
+                permission_id TEXT UNIQUE NOT NULL,
+                menu_id TEXT NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE role_permissions (
+                role_id TEXT NOT NULL,
+                permission_id TEXT NOT NULL,
+                conditions TEXT,
+                PRIMARY KEY (role_id, permission_id)
+            )
+            "#,
+            r#"
+            CREATE TABLE user_roles (
+                user_id TEXT NOT NULL,
+                role_id TEXT NOT NULL,
+                context TEXT,
+                expires_at DATETIME,
+                PRIMARY KEY (user_id, role_id)
+            )
+            "#,
+            r#"
+            CREATE TABLE role_inheritance (
+                role_id TEXT NOT NULL,
+                parent_role_id TEXT NOT NULL,
+                PRIMARY KEY (role_id, parent_role_id)
+            )
+            "#,
+        ] {
+            sqlx::query(ddl)
+                .execute(&pool)
+                .await
+                .expect("Failed to create test table");
+        }
+
+        pool
+    }
+
+    fn new_service(db: Arc<SqlitePool>) -> MenuServiceImpl {
+        let permission_cache = Arc::new(InMemoryPermissionCache::new());
+        let rbac_service = Arc::new(RBACServiceImpl::new(db.clone(), permission_cache));
+        MenuServiceImpl::new(db, rbac_service)
+    }
+
+    fn menu_request(key: &str, path: Option<&str>, parent_id: Option<&str>) -> CreateMenuRequest {
+        CreateMenuRequest {
+            name: key.to_string(),
+            key: key.to_string(),
+            path: path.map(str::to_string),
+            component: None,
+            icon: None,
+            order: None,
+            is_hidden: None,
+            is_active: None,
+            parent_id: parent_id.map(str::to_string),
+        }
+    }
+
+    async fn create_permission(pool: &SqlitePool, name: &str) -> String {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO permissions (id, name) VALUES (?, ?)")
+            .bind(&id)
+            .bind(name)
+            .execute(pool)
+            .await
+            .expect("Failed to create permission");
+        id
+    }
+
+    async fn grant_permission(pool: &SqlitePool, user_id: &str, permission_id: &str) {
+        let role_id = Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO role_permissions (role_id, permission_id) VALUES (?, ?)")
+            .bind(&role_id)
+            .bind(permission_id)
+            .execute(pool)
+            .await
+            .expect("Failed to assign permission to role");
+        sqlx::query("INSERT INTO user_roles (user_id, role_id) VALUES (?, ?)")
+            .bind(user_id)
+            .bind(&role_id)
+            .execute(pool)
+            .await
+            .expect("Failed to assign role to user");
+    }
+
+    #[tokio::test]
+    async fn test_create_duplicate_menu_key() {
+        let db = Arc::new(setup_test_db().await);
+        let service = new_service(db);
+
+        service
+            .create_menu(menu_request("dashboard", Some("/admin"), None))
+            .await
+            .unwrap();
+        let result = service
+            .create_menu(menu_request("dashboard", Some("/other"), None))
+            .await;
+
+        assert!(matches!(result.unwrap_err(), ServiceError::Conflict(_)));
+    }
+
+    #[tokio::test]
+    async fn test_update_menu_rejects_cycle() {
+        let db = Arc::new(setup_test_db().await);
+        let service = new_service(db);
+
+        let parent = service
+            .create_menu(menu_request("system", None, None))
+            .await
+            .unwrap();
+        let child = service
+            .create_menu(menu_request("system:user", Some("/admin/users"), Some(&parent.id)))
+            .await
+            .unwrap();
+
+        let result = service
+            .update_menu(
+                &parent.id,
+                UpdateMenuRequest {
+                    parent_id: Some(child.id.clone()),
+                    ..Default::default()
+                },
+            )
+            .await;
+
+        assert!(matches!(result.unwrap_err(), ServiceError::ValidationError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_delete_menu_with_children_fails() {
+        let db = Arc::new(setup_test_db().await);
+        let service = new_service(db);
+
+        let parent = service
+            .create_menu(menu_request("system", None, None))
+            .await
+            .unwrap();
+        service
+            .create_menu(menu_request("system:user", Some("/admin/users"), Some(&parent.id)))
+            .await
+            .unwrap();
+
+        let result = service.delete_menu(&parent.id).await;
+        assert!(matches!(result.unwrap_err(), ServiceError::Conflict(_)));
+    }
+
+    #[tokio::test]
+    async fn test_user_menu_tree_filters_by_permissions() {
+        let db = Arc::new(setup_test_db().await);
+        let service = new_service(db.clone());
+
+        let dashboard = service
+            .create_menu(menu_request("dashboard", Some("/admin"), None))
+            .await
+            .unwrap();
+        let system = service
+            .create_menu(menu_request("system", None, None))
+            .await
+            .unwrap();
+        let users = service
+            .create_menu(menu_request("system:user", Some("/admin/users"), Some(&system.id)))
+            .await
+            .unwrap();
+        let roles = service
+            .create_menu(menu_request("system:role", Some("/admin/roles"), Some(&system.id)))
+            .await
+            .unwrap();
+
+        let users_perm = create_permission(&db, "menu:system:user:view").await;
+        let roles_perm = create_permission(&db, "menu:system:role:view").await;
+        service
+            .set_menu_permissions(&users.id, vec![users_perm.clone()])
+            .await
+            .unwrap();
+        service
+            .set_menu_permissions(&roles.id, vec![roles_perm])
+            .await
+            .unwrap();
+
+        grant_permission(&db, "user1", &users_perm).await;
+
+        let tree = service.get_user_menu_tree("user1").await.unwrap();
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].menu.id, dashboard.id);
+        assert_eq!(tree[1].menu.id, system.id);
+        assert_eq!(tree[1].children.len(), 1);
+        assert_eq!(tree[1].children[0].menu.id, users.id);
+
+        // 没有任何子菜单权限时，分组菜单也被剪除
+        let tree = service.get_user_menu_tree("user2").await.unwrap();
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].menu.id, dashboard.id);
+    }
+
+    #[tokio::test]
+    async fn test_user_menu_tree_hides_menu_with_inactive_permission() {
+        let db = Arc::new(setup_test_db().await);
+        let service = new_service(db.clone());
+
+        let audit = service
+            .create_menu(menu_request("audit", Some("/admin/audit"), None))
+            .await
+            .unwrap();
+        let perm = create_permission(&db, "menu:audit:view").await;
+        service
+            .set_menu_permissions(&audit.id, vec![perm.clone()])
+            .await
+            .unwrap();
+        grant_permission(&db, "user1", &perm).await;
+
+        sqlx::query("UPDATE permissions SET is_active = 0 WHERE id = ?")
+            .bind(&perm)
+            .execute(&*db)
+            .await
+            .unwrap();
+
+        // 停用的权限不能让菜单变成对所有人可见
+        assert!(service.get_user_menu_tree("user1").await.unwrap().is_empty());
+        assert!(service.get_user_menu_tree("user2").await.unwrap().is_empty());
+
+        // 管理视图仍显示菜单及其权限要求
+        let tree = service.get_menu_tree().await.unwrap();
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].permissions, vec!["menu:audit:view".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_update_menu_clears_nullable_fields() {
+        let db = Arc::new(setup_test_db().await);
+        let service = new_service(db);
+
+        let mut request = menu_request("reports", Some("/admin/reports"), None);
+        request.icon = Some("chart".to_string());
+        let menu = service.create_menu(request).await.unwrap();
+
+        let request: UpdateMenuRequest =
+            serde_json::from_str(r#"{"name": "Reports", "path": null}"#).unwrap();
+        let updated = service.update_menu(&menu.id, request).await.unwrap();
+
+        assert_eq!(updated.name, "Reports");
+        assert_eq!(updated.path, None);
+        assert_eq!(updated.icon.as_deref(), Some("chart"));
+    }
+
+    #[tokio::test]
+    async fn test_set_menu_permissions_rejects_permission_bound_elsewhere() {
+        let db = Arc::new(setup_test_db().await);
+        let service = new_service(db.clone());
+
+        let first = service
+            .create_menu(menu_request("first", Some("/first"), None))
+            .await
+            .unwrap();
+        let second = service
+            .create_menu(menu_request("second", Some("/second"), None))
+            .await
+            .unwrap();
+        let perm = create_permission(&db, "menu:first:view").await;
+
+        service
+            .set_menu_permissions(&first.id, vec![perm.clone()])
+            .await
+            .unwrap();
+        let result = service.set_menu_permissions(&second.id, vec![perm]).await;
+
+        assert!(matches!(result.unwrap_err(), ServiceError::Conflict(_)));
+        assert_eq!(
+            service.get_menu_permissions(&first.id).await.unwrap(),
+            vec!["menu:first:view".to_string()]
+        );
+    }
+}
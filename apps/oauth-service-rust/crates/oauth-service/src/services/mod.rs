@@ -1,8 +1,23 @@
+pub mod access_request_service;
 pub mod audit_log_service;
 pub mod auth_code_service;
+pub mod authz_service;
 pub mod client_service;
+pub mod dpop_service;
+pub mod federation_service;
+pub mod impersonation_service;
+pub mod maintenance_service;
+pub mod menu_service;
 pub mod permission_service;
+pub mod rbac_definition_service;
 pub mod rbac_service;
+pub mod resource_server_service;
 pub mod role_service;
+pub mod scim_service;
+pub mod scope_service;
+pub mod system_config_service;
+pub mod tenant_service;
+pub mod token_revocation_service;
 pub mod token_service;
 pub mod user_service;
+pub mod webauthn_service;
@@ -22,6 +22,16 @@ pub trait PermissionService: Send + Sync {
     async fn delete_permission(&self, id: &str) -> Result<(), ServiceError>;
 }
 
+/// Derives resource and action from a permission name, e.g. "user:read" -> ("user", "read")
+pub(crate) fn split_permission_name(name: &str) -> (String, String) {
+    let parts: Vec<&str> = name.split(':').collect();
+    if parts.len() == 2 {
+        (parts[0].to_string(), parts[1].to_string())
+    } else {
+        (name.to_string(), "*".to_string()) // Default if not in resource:action format
+    }
+}
+
 pub struct PermissionServiceImpl {
     pool: Arc<SqlitePool>,
 }
@@ -43,13 +53,7 @@ impl PermissionService for PermissionServiceImpl {
         let id = uuid::Uuid::new_v4().to_string();
         let now = chrono::Utc::now();
 
-        // Derive resource and action from name, e.g., "user:read" -> resource "user", action "read"
-        let parts: Vec<&str> = name.split(':').collect();
-        let (resource, action) = if parts.len() == 2 {
-            (parts[0].to_string(), parts[1].to_string())
-        } else {
-            (name.clone(), "*".to_string()) // Default if not in resource:action format
-        };
+        let (resource, action) = split_permission_name(&name);
 
         let display_name = name.clone(); // For now, display_name is same as name
         let is_system_perm = false;
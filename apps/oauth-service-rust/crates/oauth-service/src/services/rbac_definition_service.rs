@@ -0,0 +1,1323 @@
+// RBAC 定义导入导出服务
+// 以文档形式 (JSON / YAML) 描述权限、角色、角色权限、角色继承、权限范围和客户端，
+// 便于在环境之间迁移，并把文档作为唯一来源对齐各环境的配置
+
+use crate::cache::permission_cache::PermissionCache;
+use crate::error::ServiceError;
+use crate::models::client::{ClientType, OAuthClientDetails};
+use crate::models::permission::{Permission, PermissionType};
+use crate::models::role::Role;
+use crate::models::scope::Scope;
+use crate::routes::clients::CreateClientRequest;
+use crate::routes::scopes::CreateScopeRequest;
+use crate::services::audit_log_service::{AuditLogService, NewAuditLogEntry};
+use crate::services::client_service::{
+    insert_client_rows, write_client_settings, ClientService, ClientSettings, INTERNAL_CLIENT_ID,
+};
+use crate::services::permission_service::{split_permission_name, PermissionService};
+use crate::services::role_service::{
+    add_role_permissions, insert_role_row, remove_role_permissions, write_parent_roles,
+    RoleService,
+};
+use crate::services::scope_service::{
+    insert_scope_row, write_scope_attributes, write_scope_permissions, ScopeService,
+};
+use crate::services::tenant_service::{can_use_role, ensure_tenant_exists};
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{Sqlite, SqliteConnection, SqlitePool, Transaction};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+/// 分页读取时每页的大小 (与 list_roles 的上限一致)
+const PAGE_SIZE: i32 = 100;
+
+/// RBAC 定义文档
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RbacDocument {
+    #[serde(default)]
+    pub permissions: Vec<PermissionDefinition>,
+    #[serde(default)]
+    pub roles: Vec<RoleDefinition>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<ScopeDefinition>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clients: Vec<ClientDefinition>,
+}
+
+/// 权限定义
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PermissionDefinition {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 只在创建时使用，已有权限的类型不会被修改
+    #[serde(default = "default_permission_type")]
+    pub r#type: PermissionType,
+}
+
+fn default_permission_type() -> PermissionType {
+    PermissionType::API
+}
+
+/// 角色定义，`permissions` 只包含直接分配的权限，继承的权限由 `parents` 表达
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RoleDefinition {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 所属租户，为空表示全局角色。只能在创建时设置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub permissions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parents: Vec<String>,
+}
+
+/// 权限范围定义，`permissions` 为该范围映射的权限
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScopeDefinition {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub is_public: bool,
+    #[serde(default)]
+    pub is_oidc_scope: bool,
+    #[serde(default = "default_true")]
+    pub is_active: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub permissions: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 客户端定义。不包含密钥: 新建的机密客户端会生成密钥并在导入结果中返回一次
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClientDefinition {
+    pub client_id: String,
+    pub name: String,
+    #[serde(default = "default_client_type")]
+    pub client_type: ClientType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redirect_uris: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub grant_types: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub response_types: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_scopes: Vec<String>,
+    /// client_credentials 令牌携带的权限
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub permissions: Vec<String>,
+}
+
+fn default_client_type() -> ClientType {
+    ClientType::CONFIDENTIAL
+}
+
+/// 导入选项
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RbacImportOptions {
+    /// 只计算差异，不写入数据库
+    pub dry_run: bool,
+    /// 删除文档中未声明的权限、角色、权限范围和关联，停用未声明的客户端。
+    /// 系统权限、系统角色和内置客户端始终保留
+    pub prune: bool,
+}
+
+/// 变更的对象类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RbacObjectKind {
+    Permission,
+    Role,
+    Scope,
+    Client,
+}
+
+impl RbacObjectKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Permission => "permission",
+            Self::Role => "role",
+            Self::Scope => "scope",
+            Self::Client => "client",
+        }
+    }
+}
+
+/// 变更动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RbacChangeAction {
+    Create,
+    Update,
+    Delete,
+}
+
+impl RbacChangeAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Create => "create",
+            Self::Update => "update",
+            Self::Delete => "delete",
+        }
+    }
+}
+
+/// 一个对象的变更，`details` 列出修改的字段和增删的关联 (如 `+ permission users:read`)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RbacChange {
+    pub action: RbacChangeAction,
+    pub kind: RbacObjectKind,
+    pub name: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<String>,
+}
+
+/// 以 diff 风格输出: `+` 创建、`~` 修改、`-` 删除
+impl fmt::Display for RbacChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = match self.action {
+            RbacChangeAction::Create => '+',
+            RbacChangeAction::Update => '~',
+            RbacChangeAction::Delete => '-',
+        };
+        write!(f, "{sign} {} {}", self.kind.as_str(), self.name)?;
+        for detail in &self.details {
+            write!(f, "\n    {detail}")?;
+        }
+        Ok(())
+    }
+}
+
+/// 导入结果
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RbacImportReport {
+    pub dry_run: bool,
+    pub changes: Vec<RbacChange>,
+    /// 新建的机密客户端的密钥 (client_id -> secret)，只在创建时返回一次
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub client_secrets: BTreeMap<String, String>,
+}
+
+impl RbacImportReport {
+    fn push(
+        &mut self,
+        action: RbacChangeAction,
+        kind: RbacObjectKind,
+        name: &str,
+        details: Vec<String>,
+    ) {
+        self.changes.push(RbacChange {
+            action,
+            kind,
+            name: name.to_string(),
+            details,
+        });
+    }
+
+    /// 某类动作的变更数量
+    pub fn count(&self, action: RbacChangeAction) -> usize {
+        self.changes.iter().filter(|c| c.action == action).count()
+    }
+}
+
+#[async_trait]
+pub trait RbacDefinitionService: Send + Sync {
+    /// 导出所有权限、角色、权限范围和启用的客户端 (按名称排序，不含客户端密钥)
+    async fn export_definitions(&self) -> Result<RbacDocument, ServiceError>;
+
+    /// 按文档对齐 RBAC 配置，返回逐个对象的差异。
+    /// 默认只新增和修改，不删除任何数据，因此可以重复执行；
+    /// `prune` 时额外删除文档中未声明的对象和关联。
+    /// 所有写入在同一个事务中执行，任一步失败都整体回滚；提交后为每个变更记录审计日志
+    async fn import_definitions(
+        &self,
+        document: &RbacDocument,
+        options: RbacImportOptions,
+    ) -> Result<RbacImportReport, ServiceError>;
+}
+
+pub struct RbacDefinitionServiceImpl {
+    db: Arc<SqlitePool>,
+    permission_cache: Arc<dyn PermissionCache>,
+    permission_service: Arc<dyn PermissionService>,
+    role_service: Arc<dyn RoleService>,
+    scope_service: Arc<dyn ScopeService>,
+    client_service: Arc<dyn ClientService>,
+    audit_log_service: Arc<dyn AuditLogService>,
+}
+
+/// 导入使用的事务，试运行时为空 (不写入数据库)
+type ImportTx = Option<Transaction<'static, Sqlite>>;
+
+/// 导入前数据库中的 RBAC 配置 (按名称索引)
+struct CurrentState {
+    permissions: HashMap<String, Permission>,
+    roles: HashMap<String, Role>,
+    scopes: HashMap<String, Scope>,
+    clients: HashMap<String, OAuthClientDetails>,
+    /// 角色直接分配的权限
+    role_permissions: HashMap<String, BTreeSet<String>>,
+    /// 角色的直接父角色
+    role_parents: HashMap<String, BTreeSet<String>>,
+    /// 权限范围映射的权限
+    scope_permissions: HashMap<String, BTreeSet<String>>,
+}
+
+/// 比较已有关联和文档声明的关联，返回变更说明和应用后的集合。
+/// 不清理时保留未声明的关联
+fn diff_set(
+    label: &str,
+    existing: &BTreeSet<String>,
+    declared: &[String],
+    prune: bool,
+) -> (Vec<String>, BTreeSet<String>) {
+    let declared: BTreeSet<String> = declared.iter().cloned().collect();
+    let mut details: Vec<String> = declared
+        .difference(existing)
+        .map(|value| format!("+ {label} {value}"))
+        .collect();
+    let target = if prune {
+        details.extend(
+            existing
+                .difference(&declared)
+                .map(|value| format!("- {label} {value}")),
+        );
+        declared
+    } else {
+        existing.union(&declared).cloned().collect()
+    };
+    (details, target)
+}
+
+/// 文档中的描述为空表示不管理该字段
+fn description_changed(declared: &Option<String>, existing: &Option<String>) -> bool {
+    declared.is_some() && declared != existing
+}
+
+fn sorted(values: &[String]) -> Vec<String> {
+    let mut values = values.to_vec();
+    values.sort();
+    values
+}
+
+/// 在导入事务中创建权限，返回其 ID
+async fn insert_permission(
+    conn: &mut SqliteConnection,
+    definition: &PermissionDefinition,
+) -> Result<String, ServiceError> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = Utc::now();
+    let (resource, action) = split_permission_name(&definition.name);
+
+    sqlx::query(
+        "INSERT INTO permissions (id, name, display_name, description, resource, action, type, is_system_perm, is_active, created_at, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(&definition.name)
+    .bind(&definition.name)
+    .bind(&definition.description)
+    .bind(&resource)
+    .bind(&action)
+    .bind(definition.r#type.clone())
+    .bind(false) // is_system_perm
+    .bind(true) // is_active
+    .bind(now)
+    .bind(now)
+    .execute(conn)
+    .await?;
+
+    Ok(id)
+}
+
+/// 修改权限或角色的描述
+async fn write_description(
+    conn: &mut SqliteConnection,
+    table: &str,
+    id: &str,
+    description: Option<&str>,
+) -> Result<(), ServiceError> {
+    sqlx::query(&format!(
+        "UPDATE {table} SET description = ?, updated_at = ? WHERE id = ?"
+    ))
+    .bind(description)
+    .bind(Utc::now())
+    .bind(id)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// 删除权限、角色或权限范围，关联记录通过外键级联删除
+async fn delete_row(conn: &mut SqliteConnection, table: &str, id: &str) -> Result<(), ServiceError> {
+    sqlx::query(&format!("DELETE FROM {table} WHERE id = ?"))
+        .bind(id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+impl RbacDefinitionServiceImpl {
+    pub fn new(
+        db: Arc<SqlitePool>,
+        permission_cache: Arc<dyn PermissionCache>,
+        permission_service: Arc<dyn PermissionService>,
+        role_service: Arc<dyn RoleService>,
+        scope_service: Arc<dyn ScopeService>,
+        client_service: Arc<dyn ClientService>,
+        audit_log_service: Arc<dyn AuditLogService>,
+    ) -> Self {
+        Self {
+            db,
+            permission_cache,
+            permission_service,
+            role_service,
+            scope_service,
+            client_service,
+            audit_log_service,
+        }
+    }
+
+    async fn list_all_permissions(&self) -> Result<Vec<Permission>, ServiceError> {
+        let mut permissions = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page = self
+                .permission_service
+                .list_permissions(Some(PAGE_SIZE), Some(offset))
+                .await?;
+            let len = page.len() as i32;
+            permissions.extend(page);
+            if len < PAGE_SIZE {
+                return Ok(permissions);
+            }
+            offset += len;
+        }
+    }
+
+    async fn list_all_roles(&self) -> Result<Vec<Role>, ServiceError> {
+        let mut roles = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page = self
+                .role_service
+                .list_roles(Some(PAGE_SIZE), Some(offset))
+                .await?;
+            let len = page.len() as i32;
+            roles.extend(page);
+            if len < PAGE_SIZE {
+                return Ok(roles);
+            }
+            offset += len;
+        }
+    }
+
+    /// 所有客户端 (包括已停用的)
+    async fn list_all_clients(&self) -> Result<Vec<OAuthClientDetails>, ServiceError> {
+        let mut clients = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page = self
+                .client_service
+                .list_clients(Some(PAGE_SIZE), Some(offset))
+                .await?;
+            let len = page.len() as i32;
+            clients.extend(page);
+            if len < PAGE_SIZE {
+                return Ok(clients);
+            }
+            offset += len;
+        }
+    }
+
+    /// 读取导入需要的全部现有配置。导入事务开始后不再通过其他服务读取数据库
+    async fn load_current_state(&self) -> Result<CurrentState, ServiceError> {
+        let roles: HashMap<String, Role> = self
+            .list_all_roles()
+            .await?
+            .into_iter()
+            .map(|r| (r.name.clone(), r))
+            .collect();
+        let mut role_permissions = HashMap::new();
+        let mut role_parents = HashMap::new();
+        for (name, role) in &roles {
+            let permissions = self.role_service.get_role_permissions(&role.id).await?;
+            role_permissions.insert(name.clone(), permissions.direct.into_iter().collect());
+            let parents = self.role_service.get_parent_roles(&role.id).await?;
+            role_parents.insert(name.clone(), parents.into_iter().map(|r| r.name).collect());
+        }
+
+        let scopes: HashMap<String, Scope> = self
+            .scope_service
+            .list_scopes()
+            .await?
+            .into_iter()
+            .map(|s| (s.name.clone(), s))
+            .collect();
+        let mut scope_permissions = HashMap::new();
+        for (name, scope) in &scopes {
+            let permissions = self.scope_service.get_scope_permissions(&scope.id).await?;
+            scope_permissions.insert(name.clone(), permissions.into_iter().collect());
+        }
+
+        Ok(CurrentState {
+            permissions: self
+                .list_all_permissions()
+                .await?
+                .into_iter()
+                .map(|p| (p.name.clone(), p))
+                .collect(),
+            roles,
+            scopes,
+            clients: self
+                .list_all_clients()
+                .await?
+                .into_iter()
+                .map(|c| (c.client.client_id.clone(), c))
+                .collect(),
+            role_permissions,
+            role_parents,
+            scope_permissions,
+        })
+    }
+
+    /// 写入前先校验文档中的引用，避免导入到一半失败。
+    /// 清理模式下被删除的对象不能再被引用
+    fn validate(
+        &self,
+        document: &RbacDocument,
+        current: &CurrentState,
+        prune: bool,
+    ) -> Result<(), ServiceError> {
+        let permissions: BTreeSet<&str> = document
+            .permissions
+            .iter()
+            .map(|p| p.name.as_str())
+            .chain(
+                current
+                    .permissions
+                    .values()
+                    .filter(|p| !prune || p.is_system_perm)
+                    .map(|p| p.name.as_str()),
+            )
+            .collect();
+        let roles: BTreeSet<&str> = document
+            .roles
+            .iter()
+            .map(|r| r.name.as_str())
+            .chain(
+                current
+                    .roles
+                    .values()
+                    .filter(|r| !prune || r.is_system_role)
+                    .map(|r| r.name.as_str()),
+            )
+            .collect();
+        let scopes: BTreeSet<&str> = document
+            .scopes
+            .iter()
+            .map(|s| s.name.as_str())
+            .chain(current.scopes.keys().filter(|_| !prune).map(String::as_str))
+            .collect();
+        // 角色所属的租户 (已有角色的租户不能修改，因此文档和数据库中的一致)
+        let role_tenant = |name: &str| -> Option<&str> {
+            match document.roles.iter().find(|r| r.name == name) {
+                Some(definition) => definition.tenant_id.as_deref(),
+                None => current.roles.get(name).and_then(|r| r.tenant_id.as_deref()),
+            }
+        };
+
+        let unknown = |owner: &str, kind: &str, name: &str| {
+            ServiceError::ValidationError(format!("{owner} references unknown {kind} '{name}'"))
+        };
+        for role in &document.roles {
+            let owner = format!("Role '{}'", role.name);
+            if let Some(p) = role
+                .permissions
+                .iter()
+                .find(|p| !permissions.contains(p.as_str()))
+            {
+                return Err(unknown(&owner, "permission", p));
+            }
+            if let Some(r) = role.parents.iter().find(|r| !roles.contains(r.as_str())) {
+                return Err(unknown(&owner, "parent role", r));
+            }
+            // 所属租户决定了角色的可见范围，与客户端一样只能在创建时设置
+            if let Some(existing) = current.roles.get(&role.name) {
+                if existing.tenant_id != role.tenant_id {
+                    return Err(ServiceError::ValidationError(format!(
+                        "{owner} cannot change its tenant_id"
+                    )));
+                }
+            }
+            // 只能继承全局角色或同一租户的角色
+            if let Some(r) = role
+                .parents
+                .iter()
+                .find(|r| !can_use_role(role_tenant(r), role.tenant_id.as_deref()))
+            {
+                return Err(ServiceError::ValidationError(format!(
+                    "{owner} cannot inherit from role '{r}' of another tenant"
+                )));
+            }
+        }
+        for scope in &document.scopes {
+            let owner = format!("Scope '{}'", scope.name);
+            if let Some(p) = scope
+                .permissions
+                .iter()
+                .find(|p| !permissions.contains(p.as_str()))
+            {
+                return Err(unknown(&owner, "permission", p));
+            }
+        }
+        for client in &document.clients {
+            if client.client_id.is_empty() {
+                return Err(ServiceError::ValidationError(
+                    "client_id must not be empty".to_string(),
+                ));
+            }
+            let owner = format!("Client '{}'", client.client_id);
+            if let Some(s) = client
+                .allowed_scopes
+                .iter()
+                .find(|s| !scopes.contains(s.as_str()))
+            {
+                return Err(unknown(&owner, "scope", s));
+            }
+            if let Some(p) = client
+                .permissions
+                .iter()
+                .find(|p| !permissions.contains(p.as_str()))
+            {
+                return Err(unknown(&owner, "permission", p));
+            }
+            // 客户端类型和所属租户决定了密钥和可见范围，只能在创建时设置
+            if let Some(existing) = current.clients.get(&client.client_id) {
+                if existing.client.client_type != client.client_type
+                    || existing.client.tenant_id != client.tenant_id
+                {
+                    return Err(ServiceError::ValidationError(format!(
+                        "{owner} cannot change its client_type or tenant_id"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 新建的租户角色和租户客户端所属的租户必须存在
+    async fn ensure_tenants_exist(
+        &self,
+        document: &RbacDocument,
+        current: &CurrentState,
+    ) -> Result<(), ServiceError> {
+        let tenants: BTreeSet<&str> = document
+            .roles
+            .iter()
+            .filter(|r| !current.roles.contains_key(&r.name))
+            .filter_map(|r| r.tenant_id.as_deref())
+            .chain(
+                document
+                    .clients
+                    .iter()
+                    .filter(|c| !current.clients.contains_key(&c.client_id))
+                    .filter_map(|c| c.tenant_id.as_deref()),
+            )
+            .collect();
+        for tenant_id in tenants {
+            ensure_tenant_exists(&self.db, tenant_id).await?;
+        }
+        Ok(())
+    }
+
+    async fn apply_permissions(
+        &self,
+        document: &RbacDocument,
+        current: &CurrentState,
+        tx: &mut ImportTx,
+        permission_ids: &mut HashMap<String, String>,
+        report: &mut RbacImportReport,
+    ) -> Result<(), ServiceError> {
+        for definition in &document.permissions {
+            match current.permissions.get(&definition.name) {
+                None => {
+                    report.push(
+                        RbacChangeAction::Create,
+                        RbacObjectKind::Permission,
+                        &definition.name,
+                        Vec::new(),
+                    );
+                    if let Some(tx) = tx.as_mut() {
+                        let id = insert_permission(tx, definition).await?;
+                        permission_ids.insert(definition.name.clone(), id);
+                    }
+                }
+                Some(existing) => {
+                    if !description_changed(&definition.description, &existing.description) {
+                        continue;
+                    }
+                    report.push(
+                        RbacChangeAction::Update,
+                        RbacObjectKind::Permission,
+                        &definition.name,
+                        vec!["~ description".to_string()],
+                    );
+                    if let Some(tx) = tx.as_mut() {
+                        write_description(
+                            tx,
+                            "permissions",
+                            &existing.id,
+                            definition.description.as_deref(),
+                        )
+                        .await?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn apply_roles(
+        &self,
+        document: &RbacDocument,
+        current: &CurrentState,
+        options: RbacImportOptions,
+        tx: &mut ImportTx,
+        permission_ids: &HashMap<String, String>,
+        report: &mut RbacImportReport,
+    ) -> Result<(), ServiceError> {
+        // 先创建所有角色，再处理权限和继承关系 (父角色可能在文档中排在后面)
+        let mut role_ids: HashMap<String, String> = current
+            .roles
+            .iter()
+            .map(|(name, role)| (name.clone(), role.id.clone()))
+            .collect();
+        let mut changes = Vec::with_capacity(document.roles.len());
+        for definition in &document.roles {
+            match current.roles.get(&definition.name) {
+                None => {
+                    if let Some(tx) = tx.as_mut() {
+                        let id = insert_role_row(
+                            &mut **tx,
+                            &definition.name,
+                            definition.description.as_deref(),
+                            definition.tenant_id.as_deref(),
+                        )
+                        .await?;
+                        role_ids.insert(definition.name.clone(), id);
+                    }
+                    changes.push((RbacChangeAction::Create, Vec::new()));
+                }
+                Some(existing) => {
+                    let mut details = Vec::new();
+                    if description_changed(&definition.description, &existing.description) {
+                        details.push("~ description".to_string());
+                        if let Some(tx) = tx.as_mut() {
+                            write_description(
+                                tx,
+                                "roles",
+                                &existing.id,
+                                definition.description.as_deref(),
+                            )
+                            .await?;
+                        }
+                    }
+                    changes.push((RbacChangeAction::Update, details));
+                }
+            }
+        }
+
+        let empty = BTreeSet::new();
+        for (definition, (action, mut details)) in document.roles.iter().zip(changes) {
+            let existing_permissions = current
+                .role_permissions
+                .get(&definition.name)
+                .unwrap_or(&empty);
+            let existing_parents = current.role_parents.get(&definition.name).unwrap_or(&empty);
+            let (permission_details, permissions) = diff_set(
+                "permission",
+                existing_permissions,
+                &definition.permissions,
+                options.prune,
+            );
+            let (parent_details, parents) = diff_set(
+                "parent",
+                existing_parents,
+                &definition.parents,
+                options.prune,
+            );
+            details.extend(permission_details);
+            details.extend(parent_details);
+
+            if let Some(tx) = tx.as_mut() {
+                let role_id = &role_ids[&definition.name];
+                let added: Vec<String> = permissions
+                    .difference(existing_permissions)
+                    .map(|p| permission_ids[p].clone())
+                    .collect();
+                if !added.is_empty() {
+                    add_role_permissions(tx, role_id, &added).await?;
+                }
+                let removed: Vec<String> = existing_permissions
+                    .difference(&permissions)
+                    .map(|p| permission_ids[p].clone())
+                    .collect();
+                if !removed.is_empty() {
+                    remove_role_permissions(tx, role_id, &removed).await?;
+                }
+                if &parents != existing_parents {
+                    let parent_ids: Vec<String> =
+                        parents.iter().map(|r| role_ids[r].clone()).collect();
+                    write_parent_roles(tx, role_id, definition.tenant_id.as_deref(), &parent_ids)
+                        .await
+                        .map_err(|e| match e {
+                            ServiceError::ValidationError(msg) => ServiceError::ValidationError(
+                                format!("Role '{}': {msg}", definition.name),
+                            ),
+                            other => other,
+                        })?;
+                }
+            }
+
+            if action == RbacChangeAction::Create || !details.is_empty() {
+                report.push(action, RbacObjectKind::Role, &definition.name, details);
+            }
+        }
+        Ok(())
+    }
+
+    async fn apply_scopes(
+        &self,
+        document: &RbacDocument,
+        current: &CurrentState,
+        options: RbacImportOptions,
+        tx: &mut ImportTx,
+        permission_ids: &HashMap<String, String>,
+        report: &mut RbacImportReport,
+    ) -> Result<(), ServiceError> {
+        let empty = BTreeSet::new();
+        for definition in &document.scopes {
+            let existing = current.scopes.get(&definition.name);
+            let mut details = Vec::new();
+            if let Some(scope) = existing {
+                if description_changed(&definition.description, &scope.description) {
+                    details.push("~ description".to_string());
+                }
+                for (field, declared, stored) in [
+                    ("is_public", definition.is_public, scope.is_public),
+                    (
+                        "is_oidc_scope",
+                        definition.is_oidc_scope,
+                        scope.is_oidc_scope,
+                    ),
+                    ("is_active", definition.is_active, scope.is_active),
+                ] {
+                    if declared != stored {
+                        details.push(format!("~ {field}"));
+                    }
+                }
+            }
+            let existing_permissions = current
+                .scope_permissions
+                .get(&definition.name)
+                .unwrap_or(&empty);
+            let attributes_changed = !details.is_empty();
+            let (permission_details, permissions) = diff_set(
+                "permission",
+                existing_permissions,
+                &definition.permissions,
+                options.prune,
+            );
+            details.extend(permission_details);
+
+            if let Some(tx) = tx.as_mut() {
+                let scope_id = match existing {
+                    Some(scope) => {
+                        if attributes_changed {
+                            write_scope_attributes(
+                                &mut **tx,
+                                &scope.id,
+                                definition.description.as_deref(),
+                                definition.is_public,
+                                definition.is_oidc_scope,
+                                definition.is_active,
+                            )
+                            .await?;
+                        }
+                        scope.id.clone()
+                    }
+                    None => {
+                        insert_scope_row(
+                            &mut **tx,
+                            &CreateScopeRequest {
+                                name: definition.name.clone(),
+                                description: definition.description.clone(),
+                                is_public: Some(definition.is_public),
+                                is_oidc_scope: Some(definition.is_oidc_scope),
+                                is_active: Some(definition.is_active),
+                            },
+                        )
+                        .await?
+                    }
+                };
+                if &permissions != existing_permissions {
+                    let ids = permissions
+                        .iter()
+                        .map(|p| permission_ids[p].clone())
+                        .collect();
+                    write_scope_permissions(tx, &scope_id, ids).await?;
+                }
+            }
+
+            match existing {
+                None => report.push(
+                    RbacChangeAction::Create,
+                    RbacObjectKind::Scope,
+                    &definition.name,
+                    details,
+                ),
+                Some(_) if !details.is_empty() => report.push(
+                    RbacChangeAction::Update,
+                    RbacObjectKind::Scope,
+                    &definition.name,
+                    details,
+                ),
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn apply_clients(
+        &self,
+        document: &RbacDocument,
+        current: &CurrentState,
+        options: RbacImportOptions,
+        tx: &mut ImportTx,
+        report: &mut RbacImportReport,
+    ) -> Result<(), ServiceError> {
+        for definition in &document.clients {
+            let Some(existing) = current.clients.get(&definition.client_id) else {
+                let empty = BTreeSet::new();
+                let details = [
+                    ("redirect_uri", &definition.redirect_uris),
+                    ("grant_type", &definition.grant_types),
+                    ("response_type", &definition.response_types),
+                    ("scope", &definition.allowed_scopes),
+                    ("permission", &definition.permissions),
+                ]
+                .into_iter()
+                .flat_map(|(label, values)| diff_set(label, &empty, values, false).0)
+                .collect();
+                report.push(
+                    RbacChangeAction::Create,
+                    RbacObjectKind::Client,
+                    &definition.client_id,
+                    details,
+                );
+                if let Some(tx) = tx.as_mut() {
+                    let secret = insert_client_rows(
+                        tx,
+                        &definition.client_id,
+                        &CreateClientRequest {
+                            name: definition.name.clone(),
+                            client_type: definition.client_type.to_string(),
+                            redirect_uris: definition.redirect_uris.clone(),
+                            grant_types: definition.grant_types.clone(),
+                            response_types: definition.response_types.clone(),
+                            allowed_scopes: definition.allowed_scopes.clone(),
+                            client_permissions: Some(definition.permissions.clone()),
+                            access_token_ttl: None,
+                            refresh_token_ttl: None,
+                            tenant_id: definition.tenant_id.clone(),
+                        },
+                    )
+                    .await?;
+                    if !secret.is_empty() {
+                        report
+                            .client_secrets
+                            .insert(definition.client_id.clone(), secret);
+                    }
+                }
+                continue;
+            };
+
+            let mut details = Vec::new();
+            if existing.client.name != definition.name {
+                details.push("~ name".to_string());
+            }
+            if !existing.client.is_active {
+                details.push("~ is_active".to_string());
+            }
+            let targets = [
+                (
+                    "redirect_uri",
+                    &existing.redirect_uris,
+                    &definition.redirect_uris,
+                ),
+                ("grant_type", &existing.grant_types, &definition.grant_types),
+                (
+                    "response_type",
+                    &existing.response_types,
+                    &definition.response_types,
+                ),
+                (
+                    "scope",
+                    &existing.allowed_scopes,
+                    &definition.allowed_scopes,
+                ),
+                (
+                    "permission",
+                    &existing.client_permissions,
+                    &definition.permissions,
+                ),
+            ]
+            .map(|(label, stored, declared)| {
+                let stored: BTreeSet<String> = stored.iter().cloned().collect();
+                let (changes, target) = diff_set(label, &stored, declared, options.prune);
+                details.extend(changes);
+                target.into_iter().collect::<Vec<_>>()
+            });
+            if details.is_empty() {
+                continue;
+            }
+
+            if let Some(tx) = tx.as_mut() {
+                let [redirect_uris, grant_types, response_types, allowed_scopes, client_permissions] =
+                    targets;
+                write_client_settings(
+                    tx,
+                    &existing.client.id,
+                    &ClientSettings {
+                        name: definition.name.clone(),
+                        is_active: true,
+                        redirect_uris,
+                        grant_types,
+                        response_types,
+                        allowed_scopes,
+                        client_permissions,
+                    },
+                )
+                .await?;
+            }
+            report.push(
+                RbacChangeAction::Update,
+                RbacObjectKind::Client,
+                &definition.client_id,
+                details,
+            );
+        }
+        Ok(())
+    }
+
+    /// 删除未声明的对象，按依赖关系倒序处理: 客户端、权限范围、角色、权限
+    async fn prune(
+        &self,
+        document: &RbacDocument,
+        current: &CurrentState,
+        tx: &mut ImportTx,
+        report: &mut RbacImportReport,
+    ) -> Result<(), ServiceError> {
+        let mut clients: Vec<&OAuthClientDetails> = current
+            .clients
+            .values()
+            .filter(|c| {
+                !document
+                    .clients
+                    .iter()
+                    .any(|d| d.client_id == c.client.client_id)
+            })
+            .collect();
+        clients.sort_by(|a, b| a.client.client_id.cmp(&b.client.client_id));
+        for client in &clients {
+            let client_id = &client.client.client_id;
+            if !client.client.is_active || client_id == INTERNAL_CLIENT_ID {
+                continue;
+            }
+            report.push(
+                RbacChangeAction::Delete,
+                RbacObjectKind::Client,
+                client_id,
+                Vec::new(),
+            );
+            // 客户端只停用不删除，与 ClientService::delete_client 一致
+            if let Some(tx) = tx.as_mut() {
+                sqlx::query("UPDATE oauth_clients SET is_active = ?, updated_at = ? WHERE id = ?")
+                    .bind(false)
+                    .bind(Utc::now())
+                    .bind(&client.client.id)
+                    .execute(&mut **tx)
+                    .await?;
+            }
+        }
+
+        // 停用的客户端仍保留允许的权限范围，这些范围无法删除
+        let scopes_in_use: BTreeSet<&str> = clients
+            .iter()
+            .flat_map(|c| c.allowed_scopes.iter().map(String::as_str))
+            .collect();
+        let mut scopes: Vec<&Scope> = current
+            .scopes
+            .values()
+            .filter(|s| !document.scopes.iter().any(|d| d.name == s.name))
+            .filter(|s| !scopes_in_use.contains(s.name.as_str()))
+            .collect();
+        scopes.sort_by(|a, b| a.name.cmp(&b.name));
+        for scope in scopes {
+            report.push(
+                RbacChangeAction::Delete,
+                RbacObjectKind::Scope,
+                &scope.name,
+                Vec::new(),
+            );
+            if let Some(tx) = tx.as_mut() {
+                delete_row(tx, "scopes", &scope.id).await?;
+            }
+        }
+
+        let mut roles: Vec<&Role> = current
+            .roles
+            .values()
+            .filter(|r| !r.is_system_role && !document.roles.iter().any(|d| d.name == r.name))
+            .collect();
+        roles.sort_by(|a, b| a.name.cmp(&b.name));
+        for role in roles {
+            report.push(
+                RbacChangeAction::Delete,
+                RbacObjectKind::Role,
+                &role.name,
+                Vec::new(),
+            );
+            if let Some(tx) = tx.as_mut() {
+                delete_row(tx, "roles", &role.id).await?;
+            }
+        }
+
+        let mut permissions: Vec<&Permission> = current
+            .permissions
+            .values()
+            .filter(|p| !p.is_system_perm && !document.permissions.iter().any(|d| d.name == p.name))
+            .collect();
+        permissions.sort_by(|a, b| a.name.cmp(&b.name));
+        for permission in permissions {
+            report.push(
+                RbacChangeAction::Delete,
+                RbacObjectKind::Permission,
+                &permission.name,
+                Vec::new(),
+            );
+            if let Some(tx) = tx.as_mut() {
+                delete_row(tx, "permissions", &permission.id).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 计算差异，并在有事务时把变更写入事务
+    async fn apply(
+        &self,
+        document: &RbacDocument,
+        current: &CurrentState,
+        options: RbacImportOptions,
+        tx: &mut ImportTx,
+        report: &mut RbacImportReport,
+    ) -> Result<(), ServiceError> {
+        let mut permission_ids: HashMap<String, String> = current
+            .permissions
+            .iter()
+            .map(|(name, p)| (name.clone(), p.id.clone()))
+            .collect();
+        self.apply_permissions(document, current, tx, &mut permission_ids, report)
+            .await?;
+        self.apply_roles(document, current, options, tx, &permission_ids, report)
+            .await?;
+        self.apply_scopes(document, current, options, tx, &permission_ids, report)
+            .await?;
+        self.apply_clients(document, current, options, tx, report)
+            .await?;
+        if options.prune {
+            self.prune(document, current, tx, report).await?;
+        }
+        Ok(())
+    }
+
+    async fn audit(&self, change: &RbacChange) {
+        let entry = NewAuditLogEntry {
+            actor_type: "system".to_string(),
+            actor_id: "rbac-import".to_string(),
+            action: format!("rbac.{}.{}", change.kind.as_str(), change.action.as_str()),
+            resource_type: Some(change.kind.as_str().to_string()),
+            resource_id: Some(change.name.clone()),
+            details: Some(serde_json::json!({ "changes": change.details })),
+            status: "success".to_string(),
+            ..Default::default()
+        };
+        if let Err(e) = self.audit_log_service.record(entry).await {
+            tracing::warn!("Failed to write RBAC import audit log: {}", e);
+        }
+    }
+
+    /// 导入失败并已回滚时记录一条失败日志
+    async fn audit_failure(&self, error: &ServiceError) {
+        let entry = NewAuditLogEntry {
+            actor_type: "system".to_string(),
+            actor_id: "rbac-import".to_string(),
+            action: "rbac.import".to_string(),
+            details: Some(serde_json::json!({ "error": error.to_string() })),
+            status: "failure".to_string(),
+            ..Default::default()
+        };
+        if let Err(e) = self.audit_log_service.record(entry).await {
+            tracing::warn!("Failed to write RBAC import audit log: {}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl RbacDefinitionService for RbacDefinitionServiceImpl {
+    async fn export_definitions(&self) -> Result<RbacDocument, ServiceError> {
+        let mut document = RbacDocument {
+            permissions: self
+                .list_all_permissions()
+                .await?
+                .into_iter()
+                .map(|p| PermissionDefinition {
+                    name: p.name,
+                    description: p.description,
+                    r#type: p.r#type,
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        for role in self.list_all_roles().await? {
+            let mut permissions = self
+                .role_service
+                .get_role_permissions(&role.id)
+                .await?
+                .direct;
+            permissions.sort();
+            let mut parents: Vec<String> = self
+                .role_service
+                .get_parent_roles(&role.id)
+                .await?
+                .into_iter()
+                .map(|r| r.name)
+                .collect();
+            parents.sort();
+            document.roles.push(RoleDefinition {
+                name: role.name,
+                description: role.description,
+                tenant_id: role.tenant_id,
+                permissions,
+                parents,
+            });
+        }
+
+        for scope in self.scope_service.list_scopes().await? {
+            let permissions = self.scope_service.get_scope_permissions(&scope.id).await?;
+            document.scopes.push(ScopeDefinition {
+                name: scope.name,
+                description: scope.description,
+                is_public: scope.is_public,
+                is_oidc_scope: scope.is_oidc_scope,
+                is_active: scope.is_active,
+                permissions,
+            });
+        }
+
+        // 停用的客户端视为已删除
+        for details in self.list_all_clients().await? {
+            if !details.client.is_active {
+                continue;
+            }
+            document.clients.push(ClientDefinition {
+                client_id: details.client.client_id,
+                name: details.client.name,
+                client_type: details.client.client_type,
+                tenant_id: details.client.tenant_id,
+                redirect_uris: sorted(&details.redirect_uris),
+                grant_types: sorted(&details.grant_types),
+                response_types: sorted(&details.response_types),
+                allowed_scopes: sorted(&details.allowed_scopes),
+                permissions: sorted(&details.client_permissions),
+            });
+        }
+
+        document.permissions.sort_by(|a, b| a.name.cmp(&b.name));
+        document.roles.sort_by(|a, b| a.name.cmp(&b.name));
+        document.scopes.sort_by(|a, b| a.name.cmp(&b.name));
+        document
+            .clients
+            .sort_by(|a, b| a.client_id.cmp(&b.client_id));
+        Ok(document)
+    }
+
+    async fn import_definitions(
+        &self,
+        document: &RbacDocument,
+        options: RbacImportOptions,
+    ) -> Result<RbacImportReport, ServiceError> {
+        let mut report = RbacImportReport {
+            dry_run: options.dry_run,
+            ..Default::default()
+        };
+        let current = self.load_current_state().await?;
+        self.validate(document, &current, options.prune)?;
+        self.ensure_tenants_exist(document, &current).await?;
+
+        // 所有写入在同一个事务中执行，不会留下导入了一半的配置
+        let mut tx = if options.dry_run {
+            None
+        } else {
+            Some(self.db.begin().await?)
+        };
+        let result = self
+            .apply(document, &current, options, &mut tx, &mut report)
+            .await;
+        match (result, tx) {
+            (Ok(()), Some(tx)) => tx.commit().await?,
+            (Ok(()), None) => {}
+            (Err(e), Some(tx)) => {
+                tx.rollback().await?;
+                self.audit_failure(&e).await;
+                return Err(e);
+            }
+            (Err(e), None) => return Err(e),
+        }
+
+        if !options.dry_run && !report.changes.is_empty() {
+            // 角色、权限和继承关系的变更会改变用户的有效权限
+            if let Err(e) = self.permission_cache.clear().await {
+                tracing::warn!("Failed to clear permission cache after RBAC import: {}", e);
+            }
+            for change in &report.changes {
+                self.audit(change).await;
+            }
+        }
+        tracing::info!(
+            "Imported RBAC definitions (dry_run: {}, prune: {}): {} change(s)",
+            options.dry_run,
+            options.prune,
+            report.changes.len()
+        );
+        Ok(report)
+    }
+}
@@ -1,24 +1,96 @@
 use crate::error::ServiceError;
 use crate::cache::permission_cache::PermissionCache;
+use crate::models::role::{RoleAssignmentContext, UserRoleAssignment};
+use crate::services::tenant_service::can_use_role;
+use crate::utils::permission_conditions::{AccessContext, PermissionCondition};
 use async_trait::async_trait;
+use serde::Serialize;
 use sqlx::SqlitePool;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 
 const PERMISSION_CACHE_TTL: i64 = 300; // 5 minutes
 
+/// 用户的有效角色：直接分配的角色加上沿 `role_inheritance` 向上的所有祖先角色。
+/// 使用 UNION 去重，即使存在异常的环路数据也能终止递归。
+///
+/// 只有未过期、且上下文与请求匹配的分配才会生效。参数依次为 user_id、organization、client_id；
+/// organization/client_id 绑定为 NULL 时，限定了对应范围的分配不会生效。
+const EFFECTIVE_ROLES_CTE: &str = r#"
+    WITH RECURSIVE effective_roles(role_id) AS (
+        SELECT role_id FROM user_roles
+        WHERE user_id = ?
+          AND (expires_at IS NULL OR datetime(expires_at) > datetime('now'))
+          AND (
+              context IS NULL
+              OR (
+                  (json_extract(context, '$.organization') IS NULL
+                   OR json_extract(context, '$.organization') = ?)
+                  AND (json_extract(context, '$.client_id') IS NULL
+                   OR json_extract(context, '$.client_id') = ?)
+              )
+          )
+        UNION
+        SELECT ri.parent_role_id FROM role_inheritance ri
+        JOIN effective_roles er ON ri.role_id = er.role_id
+    )
+"#;
+
 #[async_trait]
 pub trait RBACService: Send + Sync {
+    /// 获取用户通过全局角色分配获得的无条件权限 (不含限定范围的分配)
+    ///
+    /// 带有附加条件的权限依赖请求上下文，不会出现在结果 (以及签发的令牌) 中，
+    /// 需要通过 `has_permission` 在请求时求值。
     async fn get_user_permissions(&self, user_id: &str) -> Result<Vec<String>, ServiceError>;
+    /// 获取用户在指定上下文中的权限，包含全局分配和与上下文匹配的限定范围分配
+    async fn get_user_permissions_in_context(
+        &self,
+        user_id: &str,
+        context: &RoleAssignmentContext,
+    ) -> Result<Vec<String>, ServiceError>;
+    /// 获取为指定客户端签发令牌时用户的权限，上下文为该客户端和用户所属组织
+    async fn get_user_permissions_for_client(
+        &self,
+        user_id: &str,
+        client_id: &str,
+    ) -> Result<Vec<String>, ServiceError>;
+    /// 检查用户在请求上下文中是否拥有权限，会对权限的附加条件求值
     async fn has_permission(
         &self,
         user_id: &str,
         permission_name: &str,
+        context: &AccessContext,
     ) -> Result<bool, ServiceError>;
+    /// 检查用户是否通过带附加条件的授予在请求上下文中拥有权限
+    ///
+    /// 只对带条件的授予求值，无条件授予已在签发令牌时按 scope 收窄，不能在请求时重新授予。
+    async fn has_conditional_permission(
+        &self,
+        user_id: &str,
+        permission_name: &str,
+        context: &AccessContext,
+    ) -> Result<bool, ServiceError>;
+    /// 对权限检查求值并返回每个授予来源的结果，用于管理员模拟请求 (dry-run)
+    async fn evaluate_permission(
+        &self,
+        user_id: &str,
+        permission_name: &str,
+        context: &AccessContext,
+    ) -> Result<PermissionEvaluation, ServiceError>;
     async fn has_permission_for_client(
         &self,
         client_id: &str,
         permission_name: &str,
     ) -> Result<bool, ServiceError>;
+    /// 解释用户在指定上下文中的有效权限: 每个权限由哪条角色分配、经过哪些继承的角色授予；
+    /// `requested` 中用户不具备的权限列出可以授予它们的角色
+    async fn explain_permissions(
+        &self,
+        user_id: &str,
+        context: &RoleAssignmentContext,
+        requested: &[String],
+    ) -> Result<PermissionExplanation, ServiceError>;
 }
 
 pub struct RBACServiceImpl {
@@ -30,6 +102,73 @@ impl RBACServiceImpl {
     pub fn new(db: Arc<SqlitePool>, permission_cache: Arc<dyn PermissionCache>) -> Self {
         Self { db, permission_cache }
     }
+
+    async fn query_permissions(
+        &self,
+        user_id: &str,
+        context: &RoleAssignmentContext,
+    ) -> Result<Vec<String>, ServiceError> {
+        let permissions = sqlx::query_as::<_, Permission>(&format!(
+            "{EFFECTIVE_ROLES_CTE}
+             SELECT DISTINCT p.name FROM permissions p
+             JOIN role_permissions rp ON p.id = rp.permission_id
+             JOIN effective_roles er ON rp.role_id = er.role_id
+             WHERE rp.conditions IS NULL"
+        ))
+        .bind(user_id)
+        .bind(&context.organization)
+        .bind(&context.client_id)
+        .fetch_all(&*self.db)
+        .await?;
+
+        Ok(permissions.into_iter().map(|p| p.name).collect())
+    }
+
+    /// 加载角色继承关系和各角色直接授予的权限
+    async fn load_role_graph(&self) -> Result<RoleGraph, ServiceError> {
+        let roles: Vec<(String, String, Option<String>)> =
+            sqlx::query_as("SELECT id, name, tenant_id FROM roles")
+                .fetch_all(&*self.db)
+                .await?;
+        let edges: Vec<(String, String)> =
+            sqlx::query_as("SELECT role_id, parent_role_id FROM role_inheritance")
+                .fetch_all(&*self.db)
+                .await?;
+        let grants: Vec<(String, String, Option<String>)> = sqlx::query_as(
+            "SELECT rp.role_id, p.name, rp.conditions
+             FROM role_permissions rp
+             JOIN permissions p ON rp.permission_id = p.id
+             ORDER BY p.name",
+        )
+        .fetch_all(&*self.db)
+        .await?;
+
+        let mut graph = RoleGraph {
+            roles: roles
+                .into_iter()
+                .map(|(id, name, tenant_id)| (id, (name, tenant_id)))
+                .collect(),
+            parents: HashMap::new(),
+            children: HashMap::new(),
+            grants: HashMap::new(),
+        };
+        for (role_id, parent_role_id) in edges {
+            graph
+                .parents
+                .entry(role_id.clone())
+                .or_default()
+                .push(parent_role_id.clone());
+            graph.children.entry(parent_role_id).or_default().push(role_id);
+        }
+        for (role_id, permission, conditions) in grants {
+            graph
+                .grants
+                .entry(role_id)
+                .or_default()
+                .push((permission, conditions));
+        }
+        Ok(graph)
+    }
 }
 
 #[derive(sqlx::FromRow)]
@@ -37,6 +176,135 @@ struct Permission {
     name: String,
 }
 
+#[derive(sqlx::FromRow)]
+struct PermissionGrantRow {
+    role_id: String,
+    role_name: String,
+    conditions: Option<String>,
+}
+
+/// 权限检查的求值结果
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionEvaluation {
+    pub permission: String,
+    pub granted: bool,
+    /// 求值时使用的上下文 (已补全用户属性)
+    pub context: AccessContext,
+    /// 用户的有效角色中授予该权限的每一条记录
+    pub grants: Vec<PermissionGrant>,
+}
+
+/// 某个角色对权限的授予及其条件求值结果
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionGrant {
+    pub role_id: String,
+    pub role_name: String,
+    pub conditions: Option<serde_json::Value>,
+    pub matched: bool,
+    /// 条件无法解析时的错误信息
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 用户有效权限的解释
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionExplanation {
+    pub user_id: String,
+    /// 求值使用的分配范围
+    pub context: RoleAssignmentContext,
+    /// 用户的有效权限 (包括带附加条件的权限)，按名称排序
+    pub permissions: Vec<EffectivePermission>,
+    /// 请求解释、但用户不具备的权限
+    pub missing: Vec<MissingPermission>,
+}
+
+/// 用户的一个有效权限及其全部来源
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectivePermission {
+    pub permission: String,
+    pub sources: Vec<PermissionSource>,
+}
+
+/// 权限的一个来源: 角色分配，沿继承关系到授予该权限的角色
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionSource {
+    /// 带来该权限的角色分配 (分配人、过期时间、生效范围)
+    pub assignment: UserRoleAssignment,
+    /// 从分配的角色到授予权限的角色 (含两端)，直接授予时只有分配的角色
+    pub path: Vec<RoleRef>,
+    /// 授予的附加条件；不为空时权限不写入令牌，在请求时求值
+    pub conditions: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RoleRef {
+    pub role_id: String,
+    pub role_name: String,
+}
+
+/// 用户不具备的权限，以及分配后可以授予该权限的角色
+#[derive(Debug, Clone, Serialize)]
+pub struct MissingPermission {
+    pub permission: String,
+    pub granting_roles: Vec<GrantingRole>,
+}
+
+/// 可以授予某个权限的角色
+#[derive(Debug, Clone, Serialize)]
+pub struct GrantingRole {
+    pub role_id: String,
+    pub role_name: String,
+    /// 从该角色到直接授予权限的角色 (含两端)
+    pub path: Vec<RoleRef>,
+    pub conditions: Option<serde_json::Value>,
+}
+
+/// 角色继承关系和各角色直接授予的权限，用于追溯权限的来源
+struct RoleGraph {
+    /// role_id -> (角色名, 所属租户)
+    roles: HashMap<String, (String, Option<String>)>,
+    parents: HashMap<String, Vec<String>>,
+    children: HashMap<String, Vec<String>>,
+    /// role_id -> (权限名, 附加条件)
+    grants: HashMap<String, Vec<(String, Option<String>)>>,
+}
+
+impl RoleGraph {
+    fn role_ref(&self, role_id: &str) -> RoleRef {
+        RoleRef {
+            role_id: role_id.to_string(),
+            role_name: self
+                .roles
+                .get(role_id)
+                .map(|(name, _)| name.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// 从 `start` 沿边广度优先遍历，返回到每个可达角色 (含自身) 的最短路径
+    fn walk(&self, start: &str, edges: &HashMap<String, Vec<String>>) -> Vec<Vec<String>> {
+        let mut visited = HashSet::from([start.to_string()]);
+        let mut queue = VecDeque::from([vec![start.to_string()]]);
+        let mut paths = Vec::new();
+        while let Some(path) = queue.pop_front() {
+            let last = path.last().expect("paths are never empty");
+            for next in edges.get(last).into_iter().flatten() {
+                if visited.insert(next.clone()) {
+                    let mut extended = path.clone();
+                    extended.push(next.clone());
+                    queue.push_back(extended);
+                }
+            }
+            paths.push(path);
+        }
+        paths
+    }
+}
+
+fn parse_conditions(raw: &Option<String>) -> Option<serde_json::Value> {
+    raw.as_deref().and_then(|raw| serde_json::from_str(raw).ok())
+}
+
 #[async_trait]
 impl RBACService for RBACServiceImpl {
     async fn get_user_permissions(&self, user_id: &str) -> Result<Vec<String>, ServiceError> {
@@ -48,17 +316,9 @@ impl RBACService for RBACServiceImpl {
 
         // 缓存未命中，从数据库查询
         tracing::debug!("Permission cache miss for user: {}, querying database", user_id);
-        let permissions = sqlx::query_as::<_, Permission>(
-            "SELECT p.name FROM permissions p
-             JOIN role_permissions rp ON p.id = rp.permission_id
-             JOIN user_roles ur ON rp.role_id = ur.role_id
-             WHERE ur.user_id = ?",
-        )
-        .bind(user_id)
-        .fetch_all(&*self.db)
-        .await?;
-
-        let permission_names: Vec<String> = permissions.into_iter().map(|p| p.name).collect();
+        let permission_names = self
+            .query_permissions(user_id, &RoleAssignmentContext::default())
+            .await?;
 
         // 缓存查询结果
         if let Err(e) = self.permission_cache
@@ -71,30 +331,151 @@ impl RBACService for RBACServiceImpl {
         Ok(permission_names)
     }
 
+    async fn get_user_permissions_in_context(
+        &self,
+        user_id: &str,
+        context: &RoleAssignmentContext,
+    ) -> Result<Vec<String>, ServiceError> {
+        // 缓存按用户失效，因此只缓存全局权限；无上下文时复用缓存
+        if context.is_empty() {
+            return self.get_user_permissions(user_id).await;
+        }
+
+        self.query_permissions(user_id, context).await
+    }
+
+    async fn get_user_permissions_for_client(
+        &self,
+        user_id: &str,
+        client_id: &str,
+    ) -> Result<Vec<String>, ServiceError> {
+        let organization: Option<String> =
+            sqlx::query_scalar("SELECT organization FROM users WHERE id = ?")
+                .bind(user_id)
+                .fetch_optional(&*self.db)
+                .await?
+                .flatten();
+
+        let context = RoleAssignmentContext {
+            organization,
+            client_id: Some(client_id.to_string()),
+        };
+        self.get_user_permissions_in_context(user_id, &context).await
+    }
+
     async fn has_permission(
         &self,
         user_id: &str,
         permission_name: &str,
+        context: &AccessContext,
     ) -> Result<bool, ServiceError> {
-        let has_perm = sqlx::query_scalar::<_, bool>(
-            r#"
-            SELECT EXISTS (
-                SELECT 1
-                FROM users u
-                JOIN user_roles ur ON u.id = ur.user_id
-                JOIN roles r ON ur.role_id = r.id
-                JOIN role_permissions rp ON r.id = rp.role_id
-                JOIN permissions p ON rp.permission_id = p.id
-                WHERE u.id = ? AND p.name = ?
-            )
-            "#,
-        )
+        let evaluation = self
+            .evaluate_permission(user_id, permission_name, context)
+            .await?;
+
+        Ok(evaluation.granted)
+    }
+
+    async fn has_conditional_permission(
+        &self,
+        user_id: &str,
+        permission_name: &str,
+        context: &AccessContext,
+    ) -> Result<bool, ServiceError> {
+        let evaluation = self
+            .evaluate_permission(user_id, permission_name, context)
+            .await?;
+
+        Ok(evaluation
+            .grants
+            .iter()
+            .any(|grant| grant.matched && grant.conditions.is_some()))
+    }
+
+    async fn evaluate_permission(
+        &self,
+        user_id: &str,
+        permission_name: &str,
+        context: &AccessContext,
+    ) -> Result<PermissionEvaluation, ServiceError> {
+        // 用户资料中的属性总是以存储的值为准，调用方提供的同名属性会被覆盖
+        // (organization 同时决定哪些限定组织的角色分配生效)
+        let mut context = context.clone();
+        let (organization, department): (Option<String>, Option<String>) =
+            sqlx::query_as("SELECT organization, department FROM users WHERE id = ?")
+                .bind(user_id)
+                .fetch_optional(&*self.db)
+                .await?
+                .unwrap_or_default();
+        for (name, value) in [("organization", organization), ("department", department)] {
+            match value {
+                Some(value) => {
+                    context.user_attributes.insert(name.to_string(), value);
+                }
+                None => {
+                    context.user_attributes.remove(name);
+                }
+            }
+        }
+
+        let rows = sqlx::query_as::<_, PermissionGrantRow>(&format!(
+            "{EFFECTIVE_ROLES_CTE}
+             SELECT r.id AS role_id, r.name AS role_name, rp.conditions
+             FROM effective_roles er
+             JOIN roles r ON er.role_id = r.id
+             JOIN role_permissions rp ON r.id = rp.role_id
+             JOIN permissions p ON rp.permission_id = p.id
+             WHERE p.name = ?
+             ORDER BY r.name"
+        ))
         .bind(user_id)
+        .bind(context.user_attributes.get("organization"))
+        .bind(&context.client_id)
         .bind(permission_name)
-        .fetch_one(&*self.db)
+        .fetch_all(&*self.db)
         .await?;
 
-        Ok(has_perm)
+        let grants: Vec<PermissionGrant> = rows
+            .into_iter()
+            .map(|row| {
+                let Some(raw) = row.conditions else {
+                    return PermissionGrant {
+                        role_id: row.role_id,
+                        role_name: row.role_name,
+                        conditions: None,
+                        matched: true,
+                        error: None,
+                    };
+                };
+                let (matched, error) = match PermissionCondition::parse(&raw) {
+                    Ok(condition) => (condition.evaluate(&context), None),
+                    Err(e) => {
+                        // 无法解析的条件按拒绝处理
+                        tracing::warn!(
+                            "Invalid conditions on role {} for permission {}: {}",
+                            row.role_name,
+                            permission_name,
+                            e
+                        );
+                        (false, Some(e.to_string()))
+                    }
+                };
+                PermissionGrant {
+                    role_id: row.role_id,
+                    role_name: row.role_name,
+                    conditions: serde_json::from_str(&raw).ok(),
+                    matched,
+                    error,
+                }
+            })
+            .collect();
+
+        Ok(PermissionEvaluation {
+            permission: permission_name.to_string(),
+            granted: grants.iter().any(|grant| grant.matched),
+            context,
+            grants,
+        })
     }
 
     async fn has_permission_for_client(
@@ -129,6 +510,119 @@ impl RBACService for RBACServiceImpl {
             Ok(false)
         }
     }
+
+    async fn explain_permissions(
+        &self,
+        user_id: &str,
+        context: &RoleAssignmentContext,
+        requested: &[String],
+    ) -> Result<PermissionExplanation, ServiceError> {
+        let user_tenant: Option<String> =
+            sqlx::query_scalar("SELECT tenant_id FROM users WHERE id = ?")
+                .bind(user_id)
+                .fetch_optional(&*self.db)
+                .await?
+                .flatten();
+
+        // 与 EFFECTIVE_ROLES_CTE 相同的分配生效条件
+        let assignments = sqlx::query_as::<_, UserRoleAssignment>(
+            "SELECT ur.user_id, ur.role_id, r.name AS role_name, r.display_name AS role_display_name,
+                    ur.context, ur.expires_at, ur.assigned_by, ur.assigned_at
+             FROM user_roles ur
+             JOIN roles r ON r.id = ur.role_id
+             WHERE ur.user_id = ?
+               AND (ur.expires_at IS NULL OR datetime(ur.expires_at) > datetime('now'))
+               AND (
+                   ur.context IS NULL
+                   OR (
+                       (json_extract(ur.context, '$.organization') IS NULL
+                        OR json_extract(ur.context, '$.organization') = ?)
+                       AND (json_extract(ur.context, '$.client_id') IS NULL
+                        OR json_extract(ur.context, '$.client_id') = ?)
+                   )
+               )
+             ORDER BY r.name",
+        )
+        .bind(user_id)
+        .bind(&context.organization)
+        .bind(&context.client_id)
+        .fetch_all(&*self.db)
+        .await?;
+
+        let graph = self.load_role_graph().await?;
+
+        let mut effective: BTreeMap<String, Vec<PermissionSource>> = BTreeMap::new();
+        for assignment in &assignments {
+            for path in graph.walk(&assignment.role_id, &graph.parents) {
+                let granting_role = path.last().expect("paths are never empty");
+                let grants = graph.grants.get(granting_role).into_iter().flatten();
+                for (permission, conditions) in grants {
+                    effective
+                        .entry(permission.clone())
+                        .or_default()
+                        .push(PermissionSource {
+                            assignment: assignment.clone(),
+                            path: path.iter().map(|id| graph.role_ref(id)).collect(),
+                            conditions: parse_conditions(conditions),
+                        });
+                }
+            }
+        }
+
+        // 反向追溯: 直接授予权限的角色，以及继承了这些角色的角色 (限于用户可以分配的角色)
+        let mut missing = Vec::new();
+        for permission in requested {
+            if effective.contains_key(permission) {
+                continue;
+            }
+            // 同一角色可能经多条路径获得权限，保留最短的一条
+            let mut candidates: HashMap<String, GrantingRole> = HashMap::new();
+            for (role_id, grants) in &graph.grants {
+                for (_, conditions) in grants.iter().filter(|(name, _)| name == permission) {
+                    for mut path in graph.walk(role_id, &graph.children) {
+                        path.reverse();
+                        let candidate = &path[0];
+                        let usable = graph.roles.get(candidate).is_some_and(|(_, tenant)| {
+                            can_use_role(tenant.as_deref(), user_tenant.as_deref())
+                        });
+                        let shorter = candidates
+                            .get(candidate)
+                            .is_none_or(|existing| path.len() < existing.path.len());
+                        if usable && shorter {
+                            let role = graph.role_ref(candidate);
+                            candidates.insert(
+                                candidate.clone(),
+                                GrantingRole {
+                                    role_id: role.role_id,
+                                    role_name: role.role_name,
+                                    path: path.iter().map(|id| graph.role_ref(id)).collect(),
+                                    conditions: parse_conditions(conditions),
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+            let mut granting_roles: Vec<GrantingRole> = candidates.into_values().collect();
+            granting_roles.sort_by(|a, b| {
+                (a.path.len(), &a.role_name).cmp(&(b.path.len(), &b.role_name))
+            });
+            missing.push(MissingPermission {
+                permission: permission.clone(),
+                granting_roles,
+            });
+        }
+
+        Ok(PermissionExplanation {
+            user_id: user_id.to_string(),
+            context: context.clone(),
+            permissions: effective
+                .into_iter()
+                .map(|(permission, sources)| EffectivePermission { permission, sources })
+                .collect(),
+            missing,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -174,6 +668,7 @@ mod tests {
                 description TEXT,
                 is_system_role BOOLEAN DEFAULT 0,
                 is_active BOOLEAN DEFAULT 1,
+                tenant_id TEXT,
                 created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
                 updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
             )
@@ -206,6 +701,9 @@ mod tests {
                 id TEXT PRIMARY KEY,
                 username TEXT UNIQUE NOT NULL,
                 password_hash TEXT NOT NULL,
+                organization TEXT,
+                department TEXT,
+                tenant_id TEXT,
                 is_active BOOLEAN DEFAULT 1,
                 created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
                 updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
@@ -235,6 +733,22 @@ mod tests {
         .await
         .expect("Failed to create user_roles table");
 
+        sqlx::query(
+            r#"
+            CREATE TABLE role_inheritance (
+                role_id TEXT NOT NULL,
+                parent_role_id TEXT NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (role_id, parent_role_id),
+                FOREIGN KEY (role_id) REFERENCES roles(id) ON DELETE CASCADE,
+                FOREIGN KEY (parent_role_id) REFERENCES roles(id) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create role_inheritance table");
+
         pool
     }
 
@@ -301,6 +815,15 @@ mod tests {
             .expect("Failed to assign role to user");
     }
 
+    async fn inherit_role(pool: &SqlitePool, role_id: &str, parent_role_id: &str) {
+        sqlx::query("INSERT INTO role_inheritance (role_id, parent_role_id) VALUES (?, ?)")
+            .bind(role_id)
+            .bind(parent_role_id)
+            .execute(pool)
+            .await
+            .expect("Failed to add role inheritance");
+    }
+
     #[tokio::test]
     async fn test_get_user_permissions_no_roles() {
         let db = Arc::new(setup_test_db().await);
@@ -486,4 +1009,288 @@ mod tests {
         // 这是一个潜在的bug，但我们先测试现有行为
         assert!(permissions.contains(&"user:read".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_get_user_permissions_inherited_transitively() {
+        let db = Arc::new(setup_test_db().await);
+        let permission_cache = Arc::new(InMemoryPermissionCache::new());
+        let service = RBACServiceImpl::new(db.clone(), permission_cache);
+
+        let user_id = "user7";
+        let read_id = create_permission(&db, "user:read", "api").await;
+        let write_id = create_permission(&db, "user:write", "api").await;
+        let delete_id = create_permission(&db, "user:delete", "api").await;
+
+        // admin -> editor -> viewer
+        let viewer_id = create_role(&db, "viewer").await;
+        let editor_id = create_role(&db, "editor").await;
+        let admin_id = create_role(&db, "admin").await;
+        assign_permission_to_role(&db, &viewer_id, &read_id).await;
+        assign_permission_to_role(&db, &editor_id, &write_id).await;
+        assign_permission_to_role(&db, &admin_id, &delete_id).await;
+        // editor 也直接拥有 user:read，结果中不应重复
+        assign_permission_to_role(&db, &editor_id, &read_id).await;
+        inherit_role(&db, &editor_id, &viewer_id).await;
+        inherit_role(&db, &admin_id, &editor_id).await;
+
+        create_user(&db, user_id).await;
+        assign_role_to_user(&db, user_id, &admin_id).await;
+
+        let mut permissions = service.get_user_permissions(user_id).await.unwrap();
+        permissions.sort();
+        assert_eq!(permissions, vec!["user:delete", "user:read", "user:write"]);
+
+        assert!(service.has_permission(user_id, "user:read", &AccessContext::default()).await.unwrap());
+        assert!(!service.has_permission(user_id, "client:read", &AccessContext::default()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_expired_assignment_is_ignored() {
+        let db = Arc::new(setup_test_db().await);
+        let permission_cache = Arc::new(InMemoryPermissionCache::new());
+        let service = RBACServiceImpl::new(db.clone(), permission_cache);
+
+        let user_id = "user8";
+        let perm_id = create_permission(&db, "user:read", "api").await;
+        let role_id = create_role(&db, "temp_reader").await;
+        assign_permission_to_role(&db, &role_id, &perm_id).await;
+        create_user(&db, user_id).await;
+
+        sqlx::query("INSERT INTO user_roles (user_id, role_id, expires_at) VALUES (?, ?, ?)")
+            .bind(user_id)
+            .bind(&role_id)
+            .bind(chrono::Utc::now() - chrono::Duration::minutes(1))
+            .execute(&*db)
+            .await
+            .unwrap();
+
+        assert!(service.get_user_permissions(user_id).await.unwrap().is_empty());
+        assert!(!service.has_permission(user_id, "user:read", &AccessContext::default()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_context_scoped_assignment() {
+        let db = Arc::new(setup_test_db().await);
+        let permission_cache = Arc::new(InMemoryPermissionCache::new());
+        let service = RBACServiceImpl::new(db.clone(), permission_cache);
+
+        let user_id = "user9";
+        let perm_id = create_permission(&db, "report:read", "api").await;
+        let role_id = create_role(&db, "reporter").await;
+        assign_permission_to_role(&db, &role_id, &perm_id).await;
+        create_user(&db, user_id).await;
+        sqlx::query("UPDATE users SET organization = ? WHERE id = ?")
+            .bind("acme")
+            .bind(user_id)
+            .execute(&*db)
+            .await
+            .unwrap();
+
+        sqlx::query("INSERT INTO user_roles (user_id, role_id, context) VALUES (?, ?, ?)")
+            .bind(user_id)
+            .bind(&role_id)
+            .bind(r#"{"organization":"acme","client_id":"reports-app"}"#)
+            .execute(&*db)
+            .await
+            .unwrap();
+
+        // 全局权限不包含限定范围的分配
+        assert!(service.get_user_permissions(user_id).await.unwrap().is_empty());
+        assert!(!service.has_permission(user_id, "report:read", &AccessContext::default()).await.unwrap());
+
+        let permissions = service
+            .get_user_permissions_for_client(user_id, "reports-app")
+            .await
+            .unwrap();
+        assert_eq!(permissions, vec!["report:read"]);
+
+        let permissions = service
+            .get_user_permissions_for_client(user_id, "other-app")
+            .await
+            .unwrap();
+        assert!(permissions.is_empty());
+
+        let other_org = RoleAssignmentContext {
+            organization: Some("globex".to_string()),
+            client_id: Some("reports-app".to_string()),
+        };
+        assert!(service
+            .get_user_permissions_in_context(user_id, &other_org)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_conditional_permission_evaluated_against_context() {
+        let db = Arc::new(setup_test_db().await);
+        let permission_cache = Arc::new(InMemoryPermissionCache::new());
+        let service = RBACServiceImpl::new(db.clone(), permission_cache);
+
+        let user_id = "user10";
+        let read_id = create_permission(&db, "users:read", "api").await;
+        let delete_id = create_permission(&db, "users:delete", "api").await;
+        let role_id = create_role(&db, "user_admin").await;
+        assign_permission_to_role(&db, &role_id, &read_id).await;
+        assign_permission_to_role(&db, &role_id, &delete_id).await;
+        sqlx::query("UPDATE role_permissions SET conditions = ? WHERE permission_id = ?")
+            .bind(
+                r#"{"all": [
+                    {"ip_range": ["10.0.0.0/8"]},
+                    {"user_attribute": {"name": "department", "in": ["IT"]}}
+                ]}"#,
+            )
+            .bind(&delete_id)
+            .execute(&*db)
+            .await
+            .unwrap();
+
+        create_user(&db, user_id).await;
+        sqlx::query("UPDATE users SET department = ? WHERE id = ?")
+            .bind("IT")
+            .bind(user_id)
+            .execute(&*db)
+            .await
+            .unwrap();
+        assign_role_to_user(&db, user_id, &role_id).await;
+
+        // 有条件的权限不会出现在无上下文的权限列表中
+        assert_eq!(service.get_user_permissions(user_id).await.unwrap(), vec!["users:read"]);
+
+        let office = AccessContext {
+            ip_address: Some("10.20.30.40".parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(service.has_permission(user_id, "users:delete", &office).await.unwrap());
+
+        let remote = AccessContext {
+            ip_address: Some("203.0.113.5".parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(!service.has_permission(user_id, "users:delete", &remote).await.unwrap());
+
+        // 请求时只重新求值带条件的授予，无条件授予不会绕过令牌的 scope
+        assert!(service
+            .has_conditional_permission(user_id, "users:delete", &office)
+            .await
+            .unwrap());
+        assert!(!service
+            .has_conditional_permission(user_id, "users:read", &office)
+            .await
+            .unwrap());
+
+        // 调用方提供的同名属性不能覆盖用户资料
+        let mut spoofed = office.clone();
+        spoofed
+            .user_attributes
+            .insert("department".to_string(), "Sales".to_string());
+        let evaluation = service
+            .evaluate_permission(user_id, "users:delete", &spoofed)
+            .await
+            .unwrap();
+        assert!(evaluation.granted);
+        assert_eq!(evaluation.context.user_attributes["department"], "IT");
+
+        // dry-run 可以模拟请求来源
+        let evaluation = service
+            .evaluate_permission(user_id, "users:delete", &remote)
+            .await
+            .unwrap();
+        assert!(!evaluation.granted);
+        assert_eq!(evaluation.grants.len(), 1);
+        assert_eq!(evaluation.grants[0].role_name, "user_admin");
+        assert!(!evaluation.grants[0].matched);
+        assert!(evaluation.grants[0].conditions.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_explain_permissions() {
+        let db = Arc::new(setup_test_db().await);
+        let permission_cache = Arc::new(InMemoryPermissionCache::new());
+        let service = RBACServiceImpl::new(db.clone(), permission_cache);
+
+        let user_id = "user11";
+        let read_id = create_permission(&db, "user:read", "api").await;
+        let write_id = create_permission(&db, "user:write", "api").await;
+        let delete_id = create_permission(&db, "user:delete", "api").await;
+
+        // admin -> editor -> viewer，superadmin -> admin
+        let viewer_id = create_role(&db, "viewer").await;
+        let editor_id = create_role(&db, "editor").await;
+        let admin_id = create_role(&db, "admin").await;
+        let superadmin_id = create_role(&db, "superadmin").await;
+        assign_permission_to_role(&db, &viewer_id, &read_id).await;
+        assign_permission_to_role(&db, &editor_id, &write_id).await;
+        assign_permission_to_role(&db, &admin_id, &delete_id).await;
+        inherit_role(&db, &editor_id, &viewer_id).await;
+        inherit_role(&db, &admin_id, &editor_id).await;
+        inherit_role(&db, &superadmin_id, &admin_id).await;
+        sqlx::query("UPDATE role_permissions SET conditions = ? WHERE permission_id = ?")
+            .bind(r#"{"ip_range": ["10.0.0.0/8"]}"#)
+            .bind(&write_id)
+            .execute(&*db)
+            .await
+            .unwrap();
+
+        create_user(&db, user_id).await;
+        sqlx::query("INSERT INTO user_roles (user_id, role_id, assigned_by) VALUES (?, ?, ?)")
+            .bind(user_id)
+            .bind(&editor_id)
+            .bind("admin-user")
+            .execute(&*db)
+            .await
+            .unwrap();
+        // 直接授予的 user:read 来源与继承的来源分别列出；过期的分配不是来源
+        assign_role_to_user(&db, user_id, &viewer_id).await;
+        sqlx::query("INSERT INTO user_roles (user_id, role_id, expires_at) VALUES (?, ?, ?)")
+            .bind(user_id)
+            .bind(&admin_id)
+            .bind(chrono::Utc::now() - chrono::Duration::minutes(1))
+            .execute(&*db)
+            .await
+            .unwrap();
+
+        let explanation = service
+            .explain_permissions(
+                user_id,
+                &RoleAssignmentContext::default(),
+                &["user:read".to_string(), "user:delete".to_string()],
+            )
+            .await
+            .unwrap();
+        let names: Vec<&str> = explanation
+            .permissions
+            .iter()
+            .map(|p| p.permission.as_str())
+            .collect();
+        assert_eq!(names, vec!["user:read", "user:write"]);
+
+        let read = &explanation.permissions[0].sources;
+        assert_eq!(read.len(), 2);
+        let role_names = |path: &[RoleRef]| -> Vec<String> {
+            path.iter().map(|role| role.role_name.clone()).collect()
+        };
+        assert_eq!(role_names(&read[0].path), vec!["editor", "viewer"]);
+        assert_eq!(read[0].assignment.assigned_by.as_deref(), Some("admin-user"));
+        assert_eq!(role_names(&read[1].path), vec!["viewer"]);
+
+        // 带条件的授予同样列出，并附带条件
+        let write = &explanation.permissions[1].sources;
+        assert_eq!(write[0].conditions.as_ref().unwrap()["ip_range"][0], "10.0.0.0/8");
+
+        // 缺少的权限: 直接授予的角色在前，继承它的角色附带继承路径
+        assert_eq!(explanation.missing.len(), 1);
+        let missing = &explanation.missing[0];
+        assert_eq!(missing.permission, "user:delete");
+        let granting: Vec<&str> = missing
+            .granting_roles
+            .iter()
+            .map(|role| role.role_name.as_str())
+            .collect();
+        assert_eq!(granting, vec!["admin", "superadmin"]);
+        assert_eq!(
+            role_names(&missing.granting_roles[1].path),
+            vec!["superadmin", "admin"]
+        );
+    }
 }
@@ -0,0 +1,626 @@
+// 资源服务器服务 (Resource Server Service)
+//
+// 受众限定的访问令牌 (RFC 8707 资源指示符 + RFC 9068 JWT 访问令牌):
+// - 授权和令牌请求通过 `resource` 参数指定目标资源服务器，必须是已注册且激活的资源服务器
+// - 请求的 scope 必须都在资源服务器允许的范围内，OpenID Connect scope 除外 (它们由本服务处理)
+// - 访问令牌的 `aud` 为资源服务器的标识符；未指定资源时为本服务的 issuer，只能用于本服务的 API
+
+use crate::error::ServiceError;
+use crate::models::resource_server::ResourceServer;
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait ResourceServerService: Send + Sync {
+    /// 注册资源服务器，标识符创建后不可修改。
+    /// `client_id` 为资源服务器调用内省端点时认证的客户端
+    async fn create_resource_server(
+        &self,
+        identifier: String,
+        name: String,
+        allowed_scopes: Vec<String>,
+        client_id: Option<String>,
+    ) -> Result<ResourceServer, ServiceError>;
+    async fn find_resource_server(&self, id: &str) -> Result<Option<ResourceServer>, ServiceError>;
+    async fn find_by_identifier(
+        &self,
+        identifier: &str,
+    ) -> Result<Option<ResourceServer>, ServiceError>;
+    /// 查找以该客户端调用内省端点的资源服务器
+    async fn find_by_client_id(
+        &self,
+        client_id: &str,
+    ) -> Result<Option<ResourceServer>, ServiceError>;
+    async fn list_resource_servers(&self) -> Result<Vec<ResourceServer>, ServiceError>;
+    /// 重命名资源服务器、替换其允许的 scope 或内省客户端，或启用、停用
+    async fn update_resource_server(
+        &self,
+        id: &str,
+        name: Option<String>,
+        allowed_scopes: Option<Vec<String>>,
+        client_id: Option<String>,
+        is_active: Option<bool>,
+    ) -> Result<ResourceServer, ServiceError>;
+    async fn delete_resource_server(&self, id: &str) -> Result<(), ServiceError>;
+    /// 校验请求的资源和 scope，返回访问令牌的目标资源
+    ///
+    /// 未指定资源时返回 `None` (令牌的受众为本服务)，资源未注册、已停用或不接受
+    /// 请求的 scope 时返回 `InvalidTarget`。
+    async fn resolve_resource(
+        &self,
+        resource: Option<&str>,
+        scope: &str,
+    ) -> Result<Option<String>, ServiceError>;
+}
+
+const RESOURCE_SERVER_COLUMNS: &str =
+    "id, identifier, name, allowed_scopes, client_id, is_active, created_at, updated_at";
+
+/// 资源标识符必须是绝对 URI，且不含 fragment (RFC 8707 §2)
+pub fn is_valid_resource_identifier(identifier: &str) -> bool {
+    url::Url::parse(identifier).is_ok_and(|url| url.fragment().is_none())
+}
+
+/// 规范化 scope 列表: 去掉空白项和重复项，拒绝包含空白的 scope
+fn normalize_scopes(scopes: Vec<String>) -> Result<String, ServiceError> {
+    let mut normalized: Vec<String> = Vec::new();
+    for scope in scopes {
+        let scope = scope.trim().to_string();
+        if scope.is_empty() || normalized.contains(&scope) {
+            continue;
+        }
+        if scope.chars().any(char::is_whitespace) {
+            return Err(ServiceError::ValidationError(format!(
+                "Scope '{scope}' must not contain whitespace"
+            )));
+        }
+        normalized.push(scope);
+    }
+    Ok(normalized.join(" "))
+}
+
+pub struct ResourceServerServiceImpl {
+    db: Arc<SqlitePool>,
+}
+
+impl ResourceServerServiceImpl {
+    pub fn new(db: Arc<SqlitePool>) -> Self {
+        Self { db }
+    }
+
+    /// 内省客户端必须存在，且不能同时登记给其他资源服务器
+    async fn ensure_client_available(
+        &self,
+        client_id: &str,
+        server_id: Option<&str>,
+    ) -> Result<(), ServiceError> {
+        let exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM oauth_clients WHERE client_id = ?)",
+        )
+        .bind(client_id)
+        .fetch_one(&*self.db)
+        .await?;
+        if !exists {
+            return Err(ServiceError::ValidationError(format!(
+                "Client '{client_id}' not found"
+            )));
+        }
+        match self.find_by_client_id(client_id).await? {
+            Some(server) if Some(server.id.as_str()) != server_id => {
+                Err(ServiceError::Conflict(format!(
+                    "Client '{client_id}' is already used by resource server '{}'",
+                    server.identifier
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl ResourceServerService for ResourceServerServiceImpl {
+    async fn create_resource_server(
+        &self,
+        identifier: String,
+        name: String,
+        allowed_scopes: Vec<String>,
+        client_id: Option<String>,
+    ) -> Result<ResourceServer, ServiceError> {
+        if !is_valid_resource_identifier(&identifier) {
+            return Err(ServiceError::ValidationError(format!(
+                "Resource identifier '{identifier}' must be an absolute URI without a fragment"
+            )));
+        }
+        if name.trim().is_empty() {
+            return Err(ServiceError::ValidationError(
+                "Resource server name is required".to_string(),
+            ));
+        }
+        if self.find_by_identifier(&identifier).await?.is_some() {
+            return Err(ServiceError::Conflict(format!(
+                "Resource server '{identifier}' already exists"
+            )));
+        }
+        if let Some(client_id) = client_id.as_deref() {
+            self.ensure_client_available(client_id, None).await?;
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        sqlx::query(
+            "INSERT INTO resource_servers (id, identifier, name, allowed_scopes, client_id, is_active, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&id)
+        .bind(&identifier)
+        .bind(name.trim())
+        .bind(normalize_scopes(allowed_scopes)?)
+        .bind(&client_id)
+        .bind(true)
+        .bind(now)
+        .bind(now)
+        .execute(&*self.db)
+        .await?;
+
+        self.find_resource_server(&id).await?.ok_or_else(|| {
+            ServiceError::Internal("Failed to retrieve created resource server".to_string())
+        })
+    }
+
+    async fn find_resource_server(&self, id: &str) -> Result<Option<ResourceServer>, ServiceError> {
+        let server = sqlx::query_as::<_, ResourceServer>(&format!(
+            "SELECT {RESOURCE_SERVER_COLUMNS} FROM resource_servers WHERE id = ?"
+        ))
+        .bind(id)
+        .fetch_optional(&*self.db)
+        .await?;
+        Ok(server)
+    }
+
+    async fn find_by_identifier(
+        &self,
+        identifier: &str,
+    ) -> Result<Option<ResourceServer>, ServiceError> {
+        let server = sqlx::query_as::<_, ResourceServer>(&format!(
+            "SELECT {RESOURCE_SERVER_COLUMNS} FROM resource_servers WHERE identifier = ?"
+        ))
+        .bind(identifier)
+        .fetch_optional(&*self.db)
+        .await?;
+        Ok(server)
+    }
+
+    async fn find_by_client_id(
+        &self,
+        client_id: &str,
+    ) -> Result<Option<ResourceServer>, ServiceError> {
+        let server = sqlx::query_as::<_, ResourceServer>(&format!(
+            "SELECT {RESOURCE_SERVER_COLUMNS} FROM resource_servers WHERE client_id = ?"
+        ))
+        .bind(client_id)
+        .fetch_optional(&*self.db)
+        .await?;
+        Ok(server)
+    }
+
+    async fn list_resource_servers(&self) -> Result<Vec<ResourceServer>, ServiceError> {
+        let servers = sqlx::query_as::<_, ResourceServer>(&format!(
+            "SELECT {RESOURCE_SERVER_COLUMNS} FROM resource_servers ORDER BY identifier"
+        ))
+        .fetch_all(&*self.db)
+        .await?;
+        Ok(servers)
+    }
+
+    async fn update_resource_server(
+        &self,
+        id: &str,
+        name: Option<String>,
+        allowed_scopes: Option<Vec<String>>,
+        client_id: Option<String>,
+        is_active: Option<bool>,
+    ) -> Result<ResourceServer, ServiceError> {
+        let existing = self
+            .find_resource_server(id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Resource server not found".to_string()))?;
+        let name = match name {
+            Some(name) if name.trim().is_empty() => {
+                return Err(ServiceError::ValidationError(
+                    "Resource server name is required".to_string(),
+                ))
+            }
+            Some(name) => name.trim().to_string(),
+            None => existing.name,
+        };
+        let allowed_scopes = match allowed_scopes {
+            Some(scopes) => normalize_scopes(scopes)?,
+            None => existing.allowed_scopes,
+        };
+        if let Some(client_id) = client_id.as_deref() {
+            self.ensure_client_available(client_id, Some(id)).await?;
+        }
+
+        sqlx::query(
+            "UPDATE resource_servers SET name = ?, allowed_scopes = ?, client_id = ?, is_active = ?, updated_at = ? WHERE id = ?",
+        )
+        .bind(&name)
+        .bind(&allowed_scopes)
+        .bind(client_id.or(existing.client_id))
+        .bind(is_active.unwrap_or(existing.is_active))
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&*self.db)
+        .await?;
+
+        self.find_resource_server(id).await?.ok_or_else(|| {
+            ServiceError::Internal("Failed to retrieve updated resource server".to_string())
+        })
+    }
+
+    async fn delete_resource_server(&self, id: &str) -> Result<(), ServiceError> {
+        let result = sqlx::query("DELETE FROM resource_servers WHERE id = ?")
+            .bind(id)
+            .execute(&*self.db)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(ServiceError::NotFound("Resource server not found".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn resolve_resource(
+        &self,
+        resource: Option<&str>,
+        scope: &str,
+    ) -> Result<Option<String>, ServiceError> {
+        let Some(resource) = resource else {
+            return Ok(None);
+        };
+        if !is_valid_resource_identifier(resource) {
+            return Err(ServiceError::InvalidTarget(format!(
+                "Resource '{resource}' must be an absolute URI without a fragment"
+            )));
+        }
+        let server = self
+            .find_by_identifier(resource)
+            .await?
+            .filter(|server| server.is_active)
+            .ok_or_else(|| {
+                ServiceError::InvalidTarget(format!("Unknown resource '{resource}'"))
+            })?;
+
+        let oidc_scopes: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM scopes WHERE is_oidc_scope = 1")
+                .fetch_all(&*self.db)
+                .await?;
+        if let Some(rejected) = scope
+            .split_whitespace()
+            .find(|s| !server.allows_scope(s) && !oidc_scopes.iter().any(|oidc| oidc == s))
+        {
+            return Err(ServiceError::InvalidTarget(format!(
+                "Scope '{rejected}' is not accepted by resource '{resource}'"
+            )));
+        }
+        Ok(Some(server.identifier))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::clients::CreateClientRequest;
+    use crate::services::role_service::RoleAssignmentOptions;
+    use crate::state::test_support::{client_request, create_client, setup_state};
+    use crate::utils::jwt::{self, ExpectedClaims};
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use tower::ServiceExt;
+
+    const ORDERS_API: &str = "https://orders.example.com";
+
+    #[tokio::test]
+    async fn test_resource_registry_and_resolution() {
+        let (state, _pool) = setup_state().await;
+        let service = &state.resource_server_service;
+
+        // 标识符必须是不含 fragment 的绝对 URI，且不能重复
+        for identifier in ["orders", "https://orders.example.com#v1"] {
+            let result = service
+                .create_resource_server(identifier.to_string(), "Orders".to_string(), vec![], None)
+                .await;
+            assert!(matches!(result, Err(ServiceError::ValidationError(_))));
+        }
+        let server = service
+            .create_resource_server(
+                ORDERS_API.to_string(),
+                " Orders API ".to_string(),
+                vec!["orders:read".to_string(), " orders:read ".to_string()],
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(server.name, "Orders API");
+        assert_eq!(server.allowed_scopes, "orders:read");
+        assert!(matches!(
+            service
+                .create_resource_server(ORDERS_API.to_string(), "Again".to_string(), vec![], None)
+                .await,
+            Err(ServiceError::Conflict(_))
+        ));
+
+        // 未指定资源时令牌面向本服务；OIDC scope 不受资源服务器的限制
+        assert_eq!(service.resolve_resource(None, "anything").await.unwrap(), None);
+        assert_eq!(
+            service
+                .resolve_resource(Some(ORDERS_API), "openid orders:read")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some(ORDERS_API)
+        );
+        for (resource, scope) in [
+            (ORDERS_API, "orders:write"),
+            ("https://unknown.example.com", "orders:read"),
+            ("not a uri", "orders:read"),
+        ] {
+            assert!(matches!(
+                service.resolve_resource(Some(resource), scope).await,
+                Err(ServiceError::InvalidTarget(_))
+            ));
+        }
+
+        // 停用后不再接受该资源
+        service
+            .update_resource_server(&server.id, None, None, None, Some(false))
+            .await
+            .unwrap();
+        assert!(matches!(
+            service.resolve_resource(Some(ORDERS_API), "orders:read").await,
+            Err(ServiceError::InvalidTarget(_))
+        ));
+        service.delete_resource_server(&server.id).await.unwrap();
+        assert!(service.list_resource_servers().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_access_tokens_are_audience_restricted() {
+        let (state, pool) = setup_state().await;
+        state
+            .resource_server_service
+            .create_resource_server(
+                ORDERS_API.to_string(),
+                "Orders API".to_string(),
+                vec!["orders:read".to_string()],
+                None,
+            )
+            .await
+            .unwrap();
+        let (client, _) = create_client(
+            &state,
+            CreateClientRequest {
+                allowed_scopes: vec!["openid".to_string(), "orders:read".to_string()],
+                ..client_request("orders console")
+            },
+        )
+        .await;
+        let admin = state
+            .user_service
+            .create_user("root".to_string(), "password123".to_string(), None)
+            .await
+            .unwrap();
+        state
+            .role_service
+            .assign_role_to_user(&admin.id, "clh3000001", RoleAssignmentOptions::default())
+            .await
+            .unwrap();
+        let permissions = state.rbac_service.get_user_permissions(&admin.id).await.unwrap();
+
+        let issue = |resource: Option<&str>| {
+            state.token_service.issue_tokens(
+                &client,
+                Some(admin.id.clone()),
+                "orders:read".to_string(),
+                permissions.clone(),
+                None,
+                resource.map(str::to_string),
+                None,
+            )
+        };
+        let orders_tokens = issue(Some(ORDERS_API)).await.unwrap();
+        let own_tokens = issue(None).await.unwrap();
+
+        // 访问令牌是 at+jwt，带有 iss 和目标资源的 aud
+        let header = jsonwebtoken::decode_header(&orders_tokens.access_token).unwrap();
+        assert_eq!(header.typ.as_deref(), Some(jwt::ACCESS_TOKEN_TYPE));
+        let claims = state
+            .token_service
+            .verify_access_token(&orders_tokens.access_token, ORDERS_API)
+            .await
+            .unwrap();
+        assert_eq!(claims.iss.as_deref(), Some(state.config.issuer.as_str()));
+        assert_eq!(claims.aud.as_deref(), Some(ORDERS_API));
+
+        // 面向其他受众或其他签发者的令牌被拒绝
+        assert!(state
+            .token_service
+            .verify_access_token(&orders_tokens.access_token, &state.config.issuer)
+            .await
+            .is_err());
+        assert!(state
+            .token_service
+            .verify_access_token(&own_tokens.access_token, ORDERS_API)
+            .await
+            .is_err());
+        let decoding_key = state.config.load_decoding_key().unwrap();
+        let other_issuer = ExpectedClaims {
+            issuer: "https://other-issuer.example.com",
+            audience: None,
+        };
+        assert!(jwt::verify_token_with_algorithm(
+            &own_tokens.access_token,
+            &decoding_key,
+            state.config.jwt_algorithm,
+            Some(&other_issuer),
+        )
+        .is_err());
+
+        // 刷新令牌不是访问令牌，不能用于任何资源
+        let refresh_token = orders_tokens.refresh_token.unwrap();
+        assert!(state
+            .token_service
+            .verify_access_token(&refresh_token, ORDERS_API)
+            .await
+            .is_err());
+
+        // 刷新后的访问令牌保持原来的资源，不能换成其他资源
+        assert!(matches!(
+            state
+                .token_service
+                .refresh_token(&refresh_token, Some("https://billing.example.com"), None)
+                .await,
+            Err(ServiceError::InvalidTarget(_))
+        ));
+        let refreshed = state
+            .token_service
+            .refresh_token(&refresh_token, None, None)
+            .await
+            .unwrap();
+        assert!(state
+            .token_service
+            .verify_access_token(&refreshed.access_token, ORDERS_API)
+            .await
+            .is_ok());
+
+        // 本服务的 API 只接受以本服务为受众的令牌
+        let app = crate::app::create_app(pool, state.config.clone()).await;
+        let call = |token: &str| {
+            Request::builder()
+                .uri("/api/v2/admin/resource-servers")
+                .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = app
+            .clone()
+            .oneshot(call(&own_tokens.access_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.oneshot(call(&orders_tokens.access_token)).await.unwrap();
+        assert!(response.status().is_client_error());
+    }
+
+    #[tokio::test]
+    async fn test_introspection_is_limited_to_the_callers_audience() {
+        let (state, pool) = setup_state().await;
+        let (client, _) = create_client(
+            &state,
+            CreateClientRequest {
+                allowed_scopes: vec!["openid".to_string(), "orders:read".to_string()],
+                ..client_request("orders console")
+            },
+        )
+        .await;
+        let (orders_client, orders_secret) =
+            create_client(&state, client_request("orders api")).await;
+        let (other_client, other_secret) =
+            create_client(&state, client_request("billing api")).await;
+        let orders_client_id = orders_client.client.client_id.clone();
+        state
+            .resource_server_service
+            .create_resource_server(
+                ORDERS_API.to_string(),
+                "Orders API".to_string(),
+                vec!["orders:read".to_string()],
+                Some(orders_client_id.clone()),
+            )
+            .await
+            .unwrap();
+
+        // 一个客户端只能登记给一个资源服务器
+        assert!(matches!(
+            state
+                .resource_server_service
+                .create_resource_server(
+                    "https://billing.example.com".to_string(),
+                    "Billing API".to_string(),
+                    vec![],
+                    Some(orders_client_id.clone()),
+                )
+                .await,
+            Err(ServiceError::Conflict(_))
+        ));
+
+        let issue = |resource: Option<&str>| {
+            state.token_service.issue_tokens(
+                &client,
+                None,
+                "orders:read".to_string(),
+                vec![],
+                None,
+                resource.map(str::to_string),
+                None,
+            )
+        };
+        let orders_token = issue(Some(ORDERS_API)).await.unwrap().access_token;
+        let own_token = issue(None).await.unwrap().access_token;
+
+        let app = crate::app::create_app(pool, state.config.clone()).await;
+        let introspect = |token: &str, client_id: &str, secret: &str, resource: Option<&str>| {
+            let mut form = url::form_urlencoded::Serializer::new(String::new());
+            form.append_pair("token", token)
+                .append_pair("client_id", client_id)
+                .append_pair("client_secret", secret);
+            if let Some(resource) = resource {
+                form.append_pair("resource", resource);
+            }
+            let request = Request::builder()
+                .method("POST")
+                .uri("/api/v2/oauth/introspect")
+                .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from(form.finish()))
+                .unwrap();
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                let active = serde_json::from_slice::<serde_json::Value>(&body)
+                    .ok()
+                    .and_then(|json| json["active"].as_bool());
+                (status, active)
+            }
+        };
+
+        // 资源服务器只能内省以自己为受众的令牌
+        let other_client_id = other_client.client.client_id.as_str();
+        assert_eq!(
+            introspect(&orders_token, &orders_client_id, &orders_secret, Some(ORDERS_API)).await,
+            (StatusCode::OK, Some(true))
+        );
+        assert_eq!(
+            introspect(&orders_token, other_client_id, &other_secret, Some(ORDERS_API)).await,
+            (StatusCode::OK, Some(false))
+        );
+
+        // 未指定资源时只接受以本服务为受众的令牌
+        assert_eq!(
+            introspect(&orders_token, &orders_client_id, &orders_secret, None).await,
+            (StatusCode::OK, Some(false))
+        );
+        assert_eq!(
+            introspect(&own_token, other_client_id, &other_secret, None).await,
+            (StatusCode::OK, Some(true))
+        );
+
+        // 调用方必须通过客户端认证
+        let (status, _) = introspect(&own_token, other_client_id, "wrong", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -1,12 +1,32 @@
 #![allow(clippy::uninlined_format_args)]
-use crate::{error::ServiceError, models::role::Role};
+use crate::{
+    error::ServiceError,
+    models::role::{
+        InheritedPermission, Role, RoleAssignmentContext, RolePermissions, UserRoleAssignment,
+    },
+};
 use crate::cache::permission_cache::PermissionCache;
+use crate::services::tenant_service::{can_use_role, ensure_tenant_exists};
+use crate::utils::permission_conditions::PermissionCondition;
 use async_trait::async_trait;
-use chrono::Utc;
-use sqlx::SqlitePool;
+use chrono::{DateTime, Utc};
+use sqlx::{Sqlite, SqliteConnection, SqlitePool};
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::time::Duration;
 use uuid::Uuid;
 
+/// 给用户分配角色时的附加选项
+#[derive(Debug, Clone, Default)]
+pub struct RoleAssignmentOptions {
+    /// 过期时间，为空表示永久有效
+    pub expires_at: Option<DateTime<Utc>>,
+    /// 生效范围，为空表示全局分配
+    pub context: Option<RoleAssignmentContext>,
+    /// 执行分配的用户 (或客户端) ID
+    pub assigned_by: Option<String>,
+}
+
 #[async_trait]
 pub trait RoleService: Send + Sync {
     /// 创建新角色
@@ -16,6 +36,14 @@ pub trait RoleService: Send + Sync {
         description: Option<String>,
     ) -> Result<Role, ServiceError>;
 
+    /// 在租户中创建角色 (`create_role` 创建的是所有租户共用的全局角色)
+    async fn create_role_in_tenant(
+        &self,
+        name: String,
+        description: Option<String>,
+        tenant_id: &str,
+    ) -> Result<Role, ServiceError>;
+
     /// 根据ID查找角色
     async fn find_role_by_id(&self, role_id: &str) -> Result<Option<Role>, ServiceError>;
 
@@ -29,6 +57,14 @@ pub trait RoleService: Send + Sync {
         offset: Option<i32>,
     ) -> Result<Vec<Role>, ServiceError>;
 
+    /// 列出租户可见的角色: 全局角色和该租户自己的角色
+    async fn list_roles_in_tenant(
+        &self,
+        tenant_id: &str,
+        limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> Result<Vec<Role>, ServiceError>;
+
     /// 更新角色
     async fn update_role(
         &self,
@@ -54,18 +90,68 @@ pub trait RoleService: Send + Sync {
         permission_ids: Vec<String>,
     ) -> Result<(), ServiceError>;
 
-    /// 获取角色的所有权限
-    async fn get_role_permissions(&self, role_id: &str) -> Result<Vec<String>, ServiceError>;
+    /// 设置角色权限的附加条件，传入 None 表示取消条件
+    async fn set_permission_conditions(
+        &self,
+        role_id: &str,
+        permission_id: &str,
+        conditions: Option<PermissionCondition>,
+    ) -> Result<(), ServiceError>;
+
+    /// 获取角色的所有权限，区分直接分配与从父角色继承
+    async fn get_role_permissions(&self, role_id: &str) -> Result<RolePermissions, ServiceError>;
+
+    /// 设置角色的父角色 (覆盖原有继承关系)，拒绝形成环路的继承
+    async fn set_parent_roles(
+        &self,
+        role_id: &str,
+        parent_role_ids: Vec<String>,
+    ) -> Result<(), ServiceError>;
 
-    /// 给用户分配角色
-    async fn assign_role_to_user(&self, user_id: &str, role_id: &str) -> Result<(), ServiceError>;
+    /// 获取角色的直接父角色
+    async fn get_parent_roles(&self, role_id: &str) -> Result<Vec<Role>, ServiceError>;
+
+    /// 给用户分配角色，可设置过期时间和生效范围
+    async fn assign_role_to_user(
+        &self,
+        user_id: &str,
+        role_id: &str,
+        options: RoleAssignmentOptions,
+    ) -> Result<(), ServiceError>;
 
     /// 从用户移除角色
     async fn remove_role_from_user(&self, user_id: &str, role_id: &str)
         -> Result<(), ServiceError>;
 
-    /// 获取用户的所有角色
+    /// 获取用户当前未过期的所有角色 (含限定范围的分配)
     async fn get_user_roles(&self, user_id: &str) -> Result<Vec<Role>, ServiceError>;
+
+    /// 获取用户的角色分配明细 (含已过期但尚未清理的分配)
+    async fn get_user_role_assignments(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<UserRoleAssignment>, ServiceError>;
+
+    /// 删除所有已过期的角色分配并清除相关用户的权限缓存，返回删除的数量
+    async fn purge_expired_assignments(&self) -> Result<u64, ServiceError>;
+}
+
+/// 启动后台任务，按固定间隔清理过期的角色分配
+pub fn spawn_expired_assignment_sweeper(
+    role_service: Arc<dyn RoleService>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match role_service.purge_expired_assignments().await {
+                Ok(0) => {}
+                Ok(purged) => tracing::info!("Purged {} expired role assignments", purged),
+                Err(e) => tracing::warn!("Failed to purge expired role assignments: {}", e),
+            }
+        }
+    })
 }
 
 pub struct RoleServiceImpl {
@@ -77,16 +163,19 @@ impl RoleServiceImpl {
     pub fn new(db: Arc<SqlitePool>, permission_cache: Arc<dyn PermissionCache>) -> Self {
         Self { db, permission_cache }
     }
-}
 
-#[async_trait]
-impl RoleService for RoleServiceImpl {
-    async fn create_role(
+    /// 创建角色，`tenant_id` 为空时创建全局角色
+    async fn insert_role(
         &self,
         name: String,
         description: Option<String>,
+        tenant_id: Option<&str>,
     ) -> Result<Role, ServiceError> {
-        // 检查角色名称是否已存在
+        if let Some(tenant_id) = tenant_id {
+            ensure_tenant_exists(&self.db, tenant_id).await?;
+        }
+
+        // 角色名称在整个部署内唯一 (不同租户之间也不能重复)
         let existing = self.find_role_by_name(&name).await?;
         if existing.is_some() {
             return Err(ServiceError::Conflict(format!(
@@ -94,25 +183,7 @@ impl RoleService for RoleServiceImpl {
             )));
         }
 
-        let id = Uuid::new_v4().to_string();
-        let now = Utc::now();
-
-        sqlx::query(
-            r#"
-            INSERT INTO roles (id, name, display_name, description, is_system_role, is_active, created_at, updated_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
-            "#,
-        )
-        .bind(&id)
-        .bind(&name)
-        .bind(&name) // display_name 默认与 name 相同
-        .bind(&description)
-        .bind(false) // is_system_role
-        .bind(true) // is_active
-        .bind(&now)
-        .bind(&now)
-        .execute(&*self.db)
-        .await?;
+        let id = insert_role_row(&*self.db, &name, description.as_deref(), tenant_id).await?;
 
         // 返回创建的角色
         let role = self
@@ -123,10 +194,249 @@ impl RoleService for RoleServiceImpl {
         Ok(role)
     }
 
+    /// 分页查询角色，指定租户时返回全局角色和该租户的角色
+    async fn query_roles(
+        &self,
+        tenant_id: Option<&str>,
+        limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> Result<Vec<Role>, ServiceError> {
+        let limit = limit.unwrap_or(50).min(100); // 最大100条
+        let offset = offset.unwrap_or(0);
+
+        let roles = sqlx::query_as::<_, Role>(
+            "SELECT id, name, display_name, description, is_system_role, is_active, \
+             created_at, updated_at, tenant_id FROM roles \
+             WHERE (? IS NULL OR tenant_id IS NULL OR tenant_id = ?) ORDER BY created_at DESC LIMIT ? OFFSET ?",
+        )
+        .bind(tenant_id)
+        .bind(tenant_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&*self.db)
+        .await?;
+
+        Ok(roles)
+    }
+
+    /// 获取受角色变更影响的所有用户：直接拥有该角色或其任一后代角色的用户
+    async fn find_affected_user_ids(&self, role_id: &str) -> Result<Vec<String>, ServiceError> {
+        let user_ids: Vec<String> = sqlx::query_scalar(
+            r#"
+            WITH RECURSIVE descendants(role_id) AS (
+                SELECT ?
+                UNION
+                SELECT ri.role_id FROM role_inheritance ri
+                JOIN descendants d ON ri.parent_role_id = d.role_id
+            )
+            SELECT DISTINCT ur.user_id FROM user_roles ur
+            JOIN descendants d ON ur.role_id = d.role_id
+            "#,
+        )
+        .bind(role_id)
+        .fetch_all(&*self.db)
+        .await?;
+
+        Ok(user_ids)
+    }
+
+    /// 清除用户的权限缓存，单个失败不影响其他用户
+    async fn invalidate_users(&self, user_ids: Vec<String>, role_id: &str) {
+        for user_id in user_ids {
+            if let Err(e) = self.permission_cache.invalidate(&user_id).await {
+                tracing::warn!(
+                    "Failed to invalidate permission cache for user {} after changing role {}: {}",
+                    user_id,
+                    role_id,
+                    e
+                );
+            } else {
+                tracing::debug!("Invalidated permission cache for user {} after changing role {}", user_id, role_id);
+            }
+        }
+    }
+}
+
+/// 插入一条角色记录并返回其 ID，`tenant_id` 为空时为全局角色。调用方负责检查名称和租户
+pub(crate) async fn insert_role_row<'e, E>(
+    executor: E,
+    name: &str,
+    description: Option<&str>,
+    tenant_id: Option<&str>,
+) -> Result<String, ServiceError>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now();
+
+    sqlx::query(
+        r#"
+        INSERT INTO roles (id, name, display_name, description, is_system_role, is_active, created_at, updated_at, tenant_id)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&id)
+    .bind(name)
+    .bind(name) // display_name 默认与 name 相同
+    .bind(description)
+    .bind(false) // is_system_role
+    .bind(true) // is_active
+    .bind(now)
+    .bind(now)
+    .bind(tenant_id)
+    .execute(executor)
+    .await?;
+
+    Ok(id)
+}
+
+/// 给角色添加权限，已有的关联 (及其条件) 保持不变
+pub(crate) async fn add_role_permissions(
+    conn: &mut SqliteConnection,
+    role_id: &str,
+    permission_ids: &[String],
+) -> Result<(), ServiceError> {
+    for permission_id in permission_ids {
+        // 检查权限是否存在
+        let permission_exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM permissions WHERE id = ?)",
+        )
+        .bind(permission_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        if !permission_exists {
+            return Err(ServiceError::NotFound(format!(
+                "Permission '{permission_id}' not found"
+            )));
+        }
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO role_permissions (role_id, permission_id, assigned_at) VALUES (?, ?, ?)",
+        )
+        .bind(role_id)
+        .bind(permission_id)
+        .bind(Utc::now())
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// 移除角色的权限
+pub(crate) async fn remove_role_permissions(
+    conn: &mut SqliteConnection,
+    role_id: &str,
+    permission_ids: &[String],
+) -> Result<(), ServiceError> {
+    for permission_id in permission_ids {
+        sqlx::query("DELETE FROM role_permissions WHERE role_id = ? AND permission_id = ?")
+            .bind(role_id)
+            .bind(permission_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// 覆盖角色的父角色，拒绝跨租户和形成环路的继承。
+/// 出错时调用方应回滚事务
+pub(crate) async fn write_parent_roles(
+    conn: &mut SqliteConnection,
+    role_id: &str,
+    role_tenant: Option<&str>,
+    parent_role_ids: &[String],
+) -> Result<(), ServiceError> {
+    sqlx::query("DELETE FROM role_inheritance WHERE role_id = ?")
+        .bind(role_id)
+        .execute(&mut *conn)
+        .await?;
+
+    for parent_role_id in parent_role_ids {
+        if parent_role_id == role_id {
+            return Err(ServiceError::ValidationError(
+                "A role cannot inherit from itself".to_string(),
+            ));
+        }
+
+        let parent_tenant: Option<Option<String>> =
+            sqlx::query_scalar("SELECT tenant_id FROM roles WHERE id = ?")
+                .bind(parent_role_id)
+                .fetch_optional(&mut *conn)
+                .await?;
+
+        let Some(parent_tenant) = parent_tenant else {
+            return Err(ServiceError::NotFound(format!(
+                "Role '{parent_role_id}' not found"
+            )));
+        };
+
+        // 只能继承全局角色或同一租户的角色
+        if !can_use_role(parent_tenant.as_deref(), role_tenant) {
+            return Err(ServiceError::ValidationError(format!(
+                "Role '{parent_role_id}' belongs to another tenant"
+            )));
+        }
+
+        // 环路检测：如果当前角色已经是父角色的祖先，则继承会形成环
+        let creates_cycle = sqlx::query_scalar::<_, bool>(
+            r#"
+            WITH RECURSIVE ancestors(role_id) AS (
+                SELECT ?
+                UNION
+                SELECT ri.parent_role_id FROM role_inheritance ri
+                JOIN ancestors a ON ri.role_id = a.role_id
+            )
+            SELECT EXISTS(SELECT 1 FROM ancestors WHERE role_id = ?)
+            "#,
+        )
+        .bind(parent_role_id)
+        .bind(role_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        if creates_cycle {
+            return Err(ServiceError::ValidationError(format!(
+                "Inheriting from role '{parent_role_id}' would create a cycle"
+            )));
+        }
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO role_inheritance (role_id, parent_role_id, created_at) VALUES (?, ?, ?)",
+        )
+        .bind(role_id)
+        .bind(parent_role_id)
+        .bind(Utc::now())
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl RoleService for RoleServiceImpl {
+    async fn create_role(
+        &self,
+        name: String,
+        description: Option<String>,
+    ) -> Result<Role, ServiceError> {
+        self.insert_role(name, description, None).await
+    }
+
+    async fn create_role_in_tenant(
+        &self,
+        name: String,
+        description: Option<String>,
+        tenant_id: &str,
+    ) -> Result<Role, ServiceError> {
+        self.insert_role(name, description, Some(tenant_id)).await
+    }
+
     async fn find_role_by_id(&self, role_id: &str) -> Result<Option<Role>, ServiceError> {
         let role = sqlx::query_as::<_, Role>(
             "SELECT id, name, display_name, description, is_system_role, is_active, \
-             created_at, updated_at FROM roles WHERE id = ?"
+             created_at, updated_at, tenant_id FROM roles WHERE id = ?"
         )
             .bind(role_id)
             .fetch_optional(&*self.db)
@@ -138,7 +448,7 @@ impl RoleService for RoleServiceImpl {
     async fn find_role_by_name(&self, name: &str) -> Result<Option<Role>, ServiceError> {
         let role = sqlx::query_as::<_, Role>(
             "SELECT id, name, display_name, description, is_system_role, is_active, \
-             created_at, updated_at FROM roles WHERE name = ?"
+             created_at, updated_at, tenant_id FROM roles WHERE name = ?"
         )
             .bind(name)
             .fetch_optional(&*self.db)
@@ -152,19 +462,16 @@ impl RoleService for RoleServiceImpl {
         limit: Option<i32>,
         offset: Option<i32>,
     ) -> Result<Vec<Role>, ServiceError> {
-        let limit = limit.unwrap_or(50).min(100); // 最大100条
-        let offset = offset.unwrap_or(0);
-
-        let roles = sqlx::query_as::<_, Role>(
-            "SELECT id, name, display_name, description, is_system_role, is_active, \
-             created_at, updated_at FROM roles ORDER BY created_at DESC LIMIT ? OFFSET ?",
-        )
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(&*self.db)
-        .await?;
+        self.query_roles(None, limit, offset).await
+    }
 
-        Ok(roles)
+    async fn list_roles_in_tenant(
+        &self,
+        tenant_id: &str,
+        limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> Result<Vec<Role>, ServiceError> {
+        self.query_roles(Some(tenant_id), limit, offset).await
     }
 
     async fn update_role(
@@ -224,13 +531,8 @@ impl RoleService for RoleServiceImpl {
             .await?
             .ok_or_else(|| ServiceError::NotFound(format!("Role '{role_id}' not found")))?;
 
-        // 获取该角色关联的所有用户 ID，以便清除他们的权限缓存
-        let user_ids: Vec<String> = sqlx::query_scalar(
-            "SELECT DISTINCT user_id FROM user_roles WHERE role_id = ?"
-        )
-        .bind(role_id)
-        .fetch_all(&*self.db)
-        .await?;
+        // 获取该角色 (及继承它的后代角色) 关联的所有用户 ID，以便清除他们的权限缓存
+        let user_ids = self.find_affected_user_ids(role_id).await?;
 
         // 物理删除角色
         // 注意：由于外键约束，关联的role_permissions和user_roles也会被删除
@@ -240,19 +542,7 @@ impl RoleService for RoleServiceImpl {
             .await?;
 
         // 清除所有相关用户的权限缓存
-        for user_id in user_ids {
-            if let Err(e) = self.permission_cache.invalidate(&user_id).await {
-                tracing::warn!(
-                    "Failed to invalidate permission cache for user {} after deleting role {}: {}",
-                    user_id,
-                    role_id,
-                    e
-                );
-                // 继续清除其他用户的缓存，即使一个失败了
-            } else {
-                tracing::debug!("Invalidated permission cache for user {} after deleting role {}", user_id, role_id);
-            }
-        }
+        self.invalidate_users(user_ids, role_id).await;
 
         Ok(())
     }
@@ -270,46 +560,13 @@ impl RoleService for RoleServiceImpl {
 
         // 使用事务保护多步骤操作
         let mut tx = self.db.begin().await?;
+        add_role_permissions(&mut tx, role_id, &permission_ids).await?;
+        tx.commit().await?;
 
-        for permission_id in permission_ids {
-            // 检查权限是否存在
-            let permission_exists = sqlx::query_scalar::<_, bool>(
-                "SELECT EXISTS(SELECT 1 FROM permissions WHERE id = ?)",
-            )
-            .bind(&permission_id)
-            .fetch_one(&mut *tx)
-            .await?;
-
-            if !permission_exists {
-                tx.rollback().await?;
-                return Err(ServiceError::NotFound(format!(
-                    "Permission '{permission_id}' not found"
-                )));
-            }
-
-            // 检查是否已存在关联
-            let exists = sqlx::query_scalar::<_, bool>(
-                "SELECT EXISTS(SELECT 1 FROM role_permissions WHERE role_id = ? AND permission_id = ?)"
-            )
-            .bind(role_id)
-            .bind(&permission_id)
-            .fetch_one(&mut *tx)
-            .await?;
-
-            if !exists {
-                let now = Utc::now();
-                sqlx::query(
-                    "INSERT INTO role_permissions (role_id, permission_id, assigned_at) VALUES (?, ?, ?)",
-                )
-                .bind(role_id)
-                .bind(&permission_id)
-                .bind(&now)
-                .execute(&mut *tx)
-                .await?;
-            }
-        }
+        // 权限会被后代角色继承，需要清除所有受影响用户的缓存
+        let user_ids = self.find_affected_user_ids(role_id).await?;
+        self.invalidate_users(user_ids, role_id).await;
 
-        tx.commit().await?;
         Ok(())
     }
 
@@ -326,23 +583,54 @@ impl RoleService for RoleServiceImpl {
 
         // 使用事务保护批量删除操作
         let mut tx = self.db.begin().await?;
+        remove_role_permissions(&mut tx, role_id, &permission_ids).await?;
 
-        for permission_id in permission_ids {
-            sqlx::query(
-                "DELETE FROM role_permissions WHERE role_id = ? AND permission_id = ?",
-            )
-            .bind(role_id)
-            .bind(&permission_id)
-            .execute(&mut *tx)
-            .await?;
+        tx.commit().await?;
+
+        let user_ids = self.find_affected_user_ids(role_id).await?;
+        self.invalidate_users(user_ids, role_id).await;
+
+        Ok(())
+    }
+
+    async fn set_permission_conditions(
+        &self,
+        role_id: &str,
+        permission_id: &str,
+        conditions: Option<PermissionCondition>,
+    ) -> Result<(), ServiceError> {
+        if let Some(conditions) = &conditions {
+            conditions.validate()?;
         }
+        let conditions = conditions
+            .map(|c| serde_json::to_string(&c))
+            .transpose()
+            .map_err(|e| ServiceError::Internal(format!("Failed to serialize conditions: {}", e)))?;
+
+        let result = sqlx::query(
+            "UPDATE role_permissions SET conditions = ? WHERE role_id = ? AND permission_id = ?",
+        )
+        .bind(&conditions)
+        .bind(role_id)
+        .bind(permission_id)
+        .execute(&*self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(ServiceError::NotFound(
+                "Permission is not assigned to this role".to_string(),
+            ));
+        }
+
+        // 有条件的权限不会进入缓存的权限列表，因此需要清除受影响用户的缓存
+        let user_ids = self.find_affected_user_ids(role_id).await?;
+        self.invalidate_users(user_ids, role_id).await;
 
-        tx.commit().await?;
         Ok(())
     }
 
-    async fn get_role_permissions(&self, role_id: &str) -> Result<Vec<String>, ServiceError> {
-        let permissions = sqlx::query_scalar::<_, String>(
+    async fn get_role_permissions(&self, role_id: &str) -> Result<RolePermissions, ServiceError> {
+        let direct = sqlx::query_scalar::<_, String>(
             "SELECT p.name FROM permissions p
              JOIN role_permissions rp ON p.id = rp.permission_id
              WHERE rp.role_id = ?",
@@ -351,39 +639,139 @@ impl RoleService for RoleServiceImpl {
         .fetch_all(&*self.db)
         .await?;
 
-        Ok(permissions)
+        // 沿继承链向上收集祖先角色的权限 (UNION 去重，防止异常数据导致无限递归)
+        let ancestor_permissions: Vec<(String, String)> = sqlx::query_as(
+            r#"
+            WITH RECURSIVE ancestors(role_id) AS (
+                SELECT parent_role_id FROM role_inheritance WHERE role_id = ?
+                UNION
+                SELECT ri.parent_role_id FROM role_inheritance ri
+                JOIN ancestors a ON ri.role_id = a.role_id
+            )
+            SELECT p.name, r.name FROM ancestors a
+            JOIN roles r ON r.id = a.role_id
+            JOIN role_permissions rp ON rp.role_id = r.id
+            JOIN permissions p ON p.id = rp.permission_id
+            ORDER BY p.name, r.name
+            "#,
+        )
+        .bind(role_id)
+        .fetch_all(&*self.db)
+        .await?;
+
+        let mut inherited: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (permission, source_role) in ancestor_permissions {
+            if !direct.contains(&permission) {
+                inherited.entry(permission).or_default().push(source_role);
+            }
+        }
+
+        Ok(RolePermissions {
+            direct,
+            inherited: inherited
+                .into_iter()
+                .map(|(name, inherited_from)| InheritedPermission {
+                    name,
+                    inherited_from,
+                })
+                .collect(),
+        })
+    }
+
+    async fn set_parent_roles(
+        &self,
+        role_id: &str,
+        parent_role_ids: Vec<String>,
+    ) -> Result<(), ServiceError> {
+        // 检查角色是否存在
+        let role = self
+            .find_role_by_id(role_id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("Role '{role_id}' not found")))?;
+
+        // 使用事务保护覆盖操作
+        let mut tx = self.db.begin().await?;
+        write_parent_roles(&mut tx, role_id, role.tenant_id.as_deref(), &parent_role_ids).await?;
+
+        tx.commit().await?;
+
+        // 继承关系变化会影响该角色及其所有后代角色的用户
+        let user_ids = self.find_affected_user_ids(role_id).await?;
+        self.invalidate_users(user_ids, role_id).await;
+
+        Ok(())
+    }
+
+    async fn get_parent_roles(&self, role_id: &str) -> Result<Vec<Role>, ServiceError> {
+        let roles = sqlx::query_as::<_, Role>(
+            "SELECT r.id, r.name, r.display_name, r.description, r.is_system_role, r.is_active, \
+             r.created_at, r.updated_at, r.tenant_id FROM roles r
+             JOIN role_inheritance ri ON r.id = ri.parent_role_id
+             WHERE ri.role_id = ?
+             ORDER BY r.name",
+        )
+        .bind(role_id)
+        .fetch_all(&*self.db)
+        .await?;
+
+        Ok(roles)
     }
 
-    async fn assign_role_to_user(&self, user_id: &str, role_id: &str) -> Result<(), ServiceError> {
+    async fn assign_role_to_user(
+        &self,
+        user_id: &str,
+        role_id: &str,
+        options: RoleAssignmentOptions,
+    ) -> Result<(), ServiceError> {
+        let now = Utc::now();
+        if options.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err(ServiceError::ValidationError(
+                "expires_at must be in the future".to_string(),
+            ));
+        }
+        // 空的上下文等同于全局分配
+        let context = options
+            .context
+            .filter(|context| !context.is_empty())
+            .map(sqlx::types::Json);
+
         // 使用事务保护验证和插入操作
         let mut tx = self.db.begin().await?;
 
         // 检查角色是否存在
-        let role_exists =
-            sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM roles WHERE id = ?)")
+        let role_tenant: Option<Option<String>> =
+            sqlx::query_scalar("SELECT tenant_id FROM roles WHERE id = ?")
                 .bind(role_id)
-                .fetch_one(&mut *tx)
+                .fetch_optional(&mut *tx)
                 .await?;
 
-        if !role_exists {
+        let Some(role_tenant) = role_tenant else {
             tx.rollback().await?;
             return Err(ServiceError::NotFound(format!(
                 "Role '{role_id}' not found"
             )));
-        }
+        };
 
         // 检查用户是否存在
-        let user_exists =
-            sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE id = ?)")
+        let user_tenant: Option<Option<String>> =
+            sqlx::query_scalar("SELECT tenant_id FROM users WHERE id = ?")
                 .bind(user_id)
-                .fetch_one(&mut *tx)
+                .fetch_optional(&mut *tx)
                 .await?;
 
-        if !user_exists {
+        let Some(user_tenant) = user_tenant else {
             tx.rollback().await?;
             return Err(ServiceError::NotFound(format!(
                 "User '{user_id}' not found"
             )));
+        };
+
+        // 租户角色只能分配给同一租户的用户
+        if !can_use_role(role_tenant.as_deref(), user_tenant.as_deref()) {
+            tx.rollback().await?;
+            return Err(ServiceError::ValidationError(format!(
+                "Role '{role_id}' belongs to another tenant"
+            )));
         }
 
         // 检查是否已存在关联
@@ -402,12 +790,15 @@ impl RoleService for RoleServiceImpl {
             ));
         }
 
-        let now = Utc::now();
         sqlx::query(
-            "INSERT INTO user_roles (user_id, role_id, assigned_at) VALUES (?, ?, ?)",
+            "INSERT INTO user_roles (user_id, role_id, context, expires_at, assigned_by, assigned_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
         )
         .bind(user_id)
         .bind(role_id)
+        .bind(&context)
+        .bind(options.expires_at)
+        .bind(&options.assigned_by)
         .bind(&now)
         .execute(&mut *tx)
         .await?;
@@ -466,7 +857,8 @@ impl RoleService for RoleServiceImpl {
         let roles = sqlx::query_as::<_, Role>(
             "SELECT r.* FROM roles r
              JOIN user_roles ur ON r.id = ur.role_id
-             WHERE ur.user_id = ?",
+             WHERE ur.user_id = ?
+               AND (ur.expires_at IS NULL OR datetime(ur.expires_at) > datetime('now'))",
         )
         .bind(user_id)
         .fetch_all(&*self.db)
@@ -474,6 +866,66 @@ impl RoleService for RoleServiceImpl {
 
         Ok(roles)
     }
+
+    async fn get_user_role_assignments(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<UserRoleAssignment>, ServiceError> {
+        let assignments = sqlx::query_as::<_, UserRoleAssignment>(
+            "SELECT ur.user_id, ur.role_id, r.name AS role_name, r.display_name AS role_display_name,
+                    ur.context, ur.expires_at, ur.assigned_by, ur.assigned_at
+             FROM user_roles ur
+             JOIN roles r ON r.id = ur.role_id
+             WHERE ur.user_id = ?
+             ORDER BY ur.assigned_at, r.name",
+        )
+        .bind(user_id)
+        .fetch_all(&*self.db)
+        .await?;
+
+        Ok(assignments)
+    }
+
+    async fn purge_expired_assignments(&self) -> Result<u64, ServiceError> {
+        let now = Utc::now();
+        let mut tx = self.db.begin().await?;
+
+        let user_ids: Vec<String> = sqlx::query_scalar(
+            "SELECT DISTINCT user_id FROM user_roles
+             WHERE expires_at IS NOT NULL AND datetime(expires_at) <= datetime(?)",
+        )
+        .bind(now)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        if user_ids.is_empty() {
+            tx.rollback().await?;
+            return Ok(0);
+        }
+
+        let result = sqlx::query(
+            "DELETE FROM user_roles
+             WHERE expires_at IS NOT NULL AND datetime(expires_at) <= datetime(?)",
+        )
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        // 过期的分配在查询时已被忽略，这里清除缓存以确保缓存中的权限同步失效
+        for user_id in user_ids {
+            if let Err(e) = self.permission_cache.invalidate(&user_id).await {
+                tracing::warn!(
+                    "Failed to invalidate permission cache for user {} after purging expired roles: {}",
+                    user_id,
+                    e
+                );
+            }
+        }
+
+        Ok(result.rows_affected())
+    }
 }
 
 #[cfg(test)]
@@ -498,7 +950,8 @@ mod tests {
                 is_system_role BOOLEAN DEFAULT 0,
                 is_active BOOLEAN DEFAULT 1,
                 created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                tenant_id TEXT
             )
             "#,
         )
@@ -563,6 +1016,22 @@ mod tests {
         .await
         .expect("Failed to create user_roles table");
 
+        sqlx::query(
+            r#"
+            CREATE TABLE role_inheritance (
+                role_id TEXT NOT NULL,
+                parent_role_id TEXT NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (role_id, parent_role_id),
+                FOREIGN KEY (role_id) REFERENCES roles(id) ON DELETE CASCADE,
+                FOREIGN KEY (parent_role_id) REFERENCES roles(id) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("Failed to create role_inheritance table");
+
         sqlx::query(
             r#"
             CREATE TABLE users (
@@ -570,7 +1039,8 @@ mod tests {
                 username TEXT UNIQUE NOT NULL,
                 password_hash TEXT NOT NULL,
                 is_active BOOLEAN DEFAULT 1,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                tenant_id TEXT
             )
             "#,
         )
@@ -578,6 +1048,11 @@ mod tests {
         .await
         .expect("Failed to create users table");
 
+        sqlx::query("CREATE TABLE tenants (id TEXT PRIMARY KEY, name TEXT NOT NULL)")
+            .execute(&pool)
+            .await
+            .expect("Failed to create tenants table");
+
         pool
     }
 
@@ -665,9 +1140,10 @@ mod tests {
 
         // 验证权限
         let permissions = service.get_role_permissions(&role.id).await.unwrap();
-        assert_eq!(permissions.len(), 2);
-        assert!(permissions.contains(&"user:read".to_string()));
-        assert!(permissions.contains(&"user:write".to_string()));
+        assert_eq!(permissions.direct.len(), 2);
+        assert!(permissions.direct.contains(&"user:read".to_string()));
+        assert!(permissions.direct.contains(&"user:write".to_string()));
+        assert!(permissions.inherited.is_empty());
     }
 
     #[tokio::test]
@@ -695,7 +1171,7 @@ mod tests {
 
         // 分配角色
         service
-            .assign_role_to_user(&user_id, &role.id)
+            .assign_role_to_user(&user_id, &role.id, RoleAssignmentOptions::default())
             .await
             .unwrap();
 
@@ -704,4 +1180,315 @@ mod tests {
         assert_eq!(user_roles.len(), 1);
         assert_eq!(user_roles[0].name, "admin");
     }
+
+    #[tokio::test]
+    async fn test_set_parent_roles_rejects_cycle() {
+        let db = Arc::new(setup_test_db().await);
+        let permission_cache = Arc::new(InMemoryPermissionCache::new());
+        let service = RoleServiceImpl::new(db, permission_cache);
+
+        let viewer = service.create_role("viewer".to_string(), None).await.unwrap();
+        let editor = service.create_role("editor".to_string(), None).await.unwrap();
+        let admin = service.create_role("admin".to_string(), None).await.unwrap();
+
+        service
+            .set_parent_roles(&editor.id, vec![viewer.id.clone()])
+            .await
+            .unwrap();
+        service
+            .set_parent_roles(&admin.id, vec![editor.id.clone()])
+            .await
+            .unwrap();
+
+        // viewer -> admin 会形成 admin -> editor -> viewer -> admin 的环
+        let result = service
+            .set_parent_roles(&viewer.id, vec![admin.id.clone()])
+            .await;
+        assert!(matches!(result.unwrap_err(), ServiceError::ValidationError(_)));
+
+        let result = service
+            .set_parent_roles(&viewer.id, vec![viewer.id.clone()])
+            .await;
+        assert!(matches!(result.unwrap_err(), ServiceError::ValidationError(_)));
+
+        // 失败的操作不应破坏原有继承关系
+        let parents = service.get_parent_roles(&admin.id).await.unwrap();
+        assert_eq!(parents.len(), 1);
+        assert_eq!(parents[0].name, "editor");
+    }
+
+    #[tokio::test]
+    async fn test_get_role_permissions_inherited() {
+        let db = Arc::new(setup_test_db().await);
+        let permission_cache = Arc::new(InMemoryPermissionCache::new());
+        let service = RoleServiceImpl::new(db.clone(), permission_cache);
+
+        let viewer = service.create_role("viewer".to_string(), None).await.unwrap();
+        let editor = service.create_role("editor".to_string(), None).await.unwrap();
+
+        for (id, name) in [("perm-read", "user:read"), ("perm-write", "user:write")] {
+            sqlx::query(
+                "INSERT INTO permissions (id, name, display_name, resource, action, type) VALUES (?, ?, ?, ?, ?, ?)",
+            )
+            .bind(id)
+            .bind(name)
+            .bind(name)
+            .bind("user")
+            .bind("*")
+            .bind("API")
+            .execute(&*db)
+            .await
+            .unwrap();
+        }
+
+        service
+            .assign_permissions_to_role(&viewer.id, vec!["perm-read".to_string()])
+            .await
+            .unwrap();
+        service
+            .assign_permissions_to_role(&editor.id, vec!["perm-write".to_string()])
+            .await
+            .unwrap();
+        service
+            .set_parent_roles(&editor.id, vec![viewer.id.clone()])
+            .await
+            .unwrap();
+
+        let permissions = service.get_role_permissions(&editor.id).await.unwrap();
+        assert_eq!(permissions.direct, vec!["user:write".to_string()]);
+        assert_eq!(permissions.inherited.len(), 1);
+        assert_eq!(permissions.inherited[0].name, "user:read");
+        assert_eq!(permissions.inherited[0].inherited_from, vec!["viewer".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_set_parent_roles_invalidates_descendant_users() {
+        let db = Arc::new(setup_test_db().await);
+        let permission_cache = Arc::new(InMemoryPermissionCache::new());
+        let service = RoleServiceImpl::new(db.clone(), permission_cache.clone());
+
+        let viewer = service.create_role("viewer".to_string(), None).await.unwrap();
+        let editor = service.create_role("editor".to_string(), None).await.unwrap();
+        let admin = service.create_role("admin".to_string(), None).await.unwrap();
+        service
+            .set_parent_roles(&admin.id, vec![editor.id.clone()])
+            .await
+            .unwrap();
+
+        let user_id = Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO users (id, username, password_hash) VALUES (?, ?, ?)")
+            .bind(&user_id)
+            .bind("admin_user")
+            .bind("hash")
+            .execute(&*db)
+            .await
+            .unwrap();
+        service
+            .assign_role_to_user(&user_id, &admin.id, RoleAssignmentOptions::default())
+            .await
+            .unwrap();
+
+        permission_cache
+            .set(&user_id, vec!["stale".to_string()], 3600)
+            .await
+            .unwrap();
+
+        // 修改 editor 的父角色会影响继承 editor 的 admin 角色的用户
+        service
+            .set_parent_roles(&editor.id, vec![viewer.id.clone()])
+            .await
+            .unwrap();
+
+        assert!(permission_cache.get(&user_id).await.is_none());
+    }
+
+    async fn insert_test_user(db: &SqlitePool, username: &str) -> String {
+        let user_id = Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO users (id, username, password_hash) VALUES (?, ?, ?)")
+            .bind(&user_id)
+            .bind(username)
+            .bind("hash")
+            .execute(db)
+            .await
+            .unwrap();
+        user_id
+    }
+
+    #[tokio::test]
+    async fn test_assign_role_with_options() {
+        let db = Arc::new(setup_test_db().await);
+        let permission_cache = Arc::new(InMemoryPermissionCache::new());
+        let service = RoleServiceImpl::new(db.clone(), permission_cache);
+
+        let role = service.create_role("auditor".to_string(), None).await.unwrap();
+        let user_id = insert_test_user(&db, "temp_user").await;
+
+        // 过期时间必须在未来
+        let result = service
+            .assign_role_to_user(
+                &user_id,
+                &role.id,
+                RoleAssignmentOptions {
+                    expires_at: Some(Utc::now() - chrono::Duration::hours(1)),
+                    ..Default::default()
+                },
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::ValidationError(_))));
+
+        let expires_at = Utc::now() + chrono::Duration::hours(1);
+        service
+            .assign_role_to_user(
+                &user_id,
+                &role.id,
+                RoleAssignmentOptions {
+                    expires_at: Some(expires_at),
+                    context: Some(RoleAssignmentContext {
+                        organization: Some("acme".to_string()),
+                        client_id: None,
+                    }),
+                    assigned_by: Some("admin-user".to_string()),
+                },
+            )
+            .await
+            .unwrap();
+
+        let assignments = service.get_user_role_assignments(&user_id).await.unwrap();
+        assert_eq!(assignments.len(), 1);
+        let assignment = &assignments[0];
+        assert_eq!(assignment.role_name, "auditor");
+        assert_eq!(assignment.assigned_by.as_deref(), Some("admin-user"));
+        assert_eq!(
+            assignment.context.as_ref().and_then(|c| c.organization.as_deref()),
+            Some("acme")
+        );
+        assert_eq!(assignment.expires_at.map(|t| t.timestamp()), Some(expires_at.timestamp()));
+        assert!(!assignment.is_expired());
+    }
+
+    #[tokio::test]
+    async fn test_purge_expired_assignments() {
+        let db = Arc::new(setup_test_db().await);
+        let permission_cache = Arc::new(InMemoryPermissionCache::new());
+        let service = RoleServiceImpl::new(db.clone(), permission_cache.clone());
+
+        let temp = service.create_role("temp".to_string(), None).await.unwrap();
+        let permanent = service.create_role("permanent".to_string(), None).await.unwrap();
+        let user_id = insert_test_user(&db, "expiring_user").await;
+
+        service
+            .assign_role_to_user(&user_id, &permanent.id, RoleAssignmentOptions::default())
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO user_roles (user_id, role_id, expires_at) VALUES (?, ?, ?)")
+            .bind(&user_id)
+            .bind(&temp.id)
+            .bind(Utc::now() - chrono::Duration::minutes(5))
+            .execute(&*db)
+            .await
+            .unwrap();
+
+        // 过期但尚未清理的分配不再视为用户的角色
+        let roles = service.get_user_roles(&user_id).await.unwrap();
+        assert_eq!(roles.len(), 1);
+        assert_eq!(roles[0].name, "permanent");
+
+        permission_cache
+            .set(&user_id, vec!["stale".to_string()], 3600)
+            .await
+            .unwrap();
+
+        assert_eq!(service.purge_expired_assignments().await.unwrap(), 1);
+        assert!(permission_cache.get(&user_id).await.is_none());
+
+        let assignments = service.get_user_role_assignments(&user_id).await.unwrap();
+        assert_eq!(assignments.len(), 1);
+        assert_eq!(assignments[0].role_name, "permanent");
+
+        assert_eq!(service.purge_expired_assignments().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_tenant_roles_stay_within_tenant() {
+        let db = Arc::new(setup_test_db().await);
+        let permission_cache = Arc::new(InMemoryPermissionCache::new());
+        let service = RoleServiceImpl::new(db.clone(), permission_cache);
+
+        for tenant in ["acme", "globex"] {
+            sqlx::query("INSERT INTO tenants (id, name) VALUES (?, ?)")
+                .bind(tenant)
+                .bind(tenant)
+                .execute(&*db)
+                .await
+                .unwrap();
+        }
+
+        let global = service.create_role("viewer".to_string(), None).await.unwrap();
+        let acme_role = service
+            .create_role_in_tenant("acme-editor".to_string(), None, "acme")
+            .await
+            .unwrap();
+        let globex_role = service
+            .create_role_in_tenant("globex-editor".to_string(), None, "globex")
+            .await
+            .unwrap();
+        assert_eq!(acme_role.tenant_id.as_deref(), Some("acme"));
+
+        let result = service
+            .create_role_in_tenant("ghost".to_string(), None, "missing")
+            .await;
+        assert!(matches!(result.unwrap_err(), ServiceError::NotFound(_)));
+
+        // 租户只能看到全局角色和自己的角色
+        let names: Vec<String> = service
+            .list_roles_in_tenant("acme", None, None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|role| role.name)
+            .collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"viewer".to_string()));
+        assert!(names.contains(&"acme-editor".to_string()));
+        assert_eq!(service.list_roles(None, None).await.unwrap().len(), 3);
+
+        let acme_user = insert_test_user(&db, "alice").await;
+        sqlx::query("UPDATE users SET tenant_id = 'acme' WHERE id = ?")
+            .bind(&acme_user)
+            .execute(&*db)
+            .await
+            .unwrap();
+        let platform_user = insert_test_user(&db, "root").await;
+
+        service
+            .assign_role_to_user(&acme_user, &global.id, RoleAssignmentOptions::default())
+            .await
+            .unwrap();
+        service
+            .assign_role_to_user(&acme_user, &acme_role.id, RoleAssignmentOptions::default())
+            .await
+            .unwrap();
+        let result = service
+            .assign_role_to_user(&acme_user, &globex_role.id, RoleAssignmentOptions::default())
+            .await;
+        assert!(matches!(result.unwrap_err(), ServiceError::ValidationError(_)));
+        let result = service
+            .assign_role_to_user(&platform_user, &acme_role.id, RoleAssignmentOptions::default())
+            .await;
+        assert!(matches!(result.unwrap_err(), ServiceError::ValidationError(_)));
+
+        // 租户角色可以继承全局角色，但不能继承其他租户的角色，全局角色也不能继承租户角色
+        service
+            .set_parent_roles(&acme_role.id, vec![global.id.clone()])
+            .await
+            .unwrap();
+        let result = service
+            .set_parent_roles(&acme_role.id, vec![globex_role.id.clone()])
+            .await;
+        assert!(matches!(result.unwrap_err(), ServiceError::ValidationError(_)));
+        let result = service
+            .set_parent_roles(&global.id, vec![acme_role.id.clone()])
+            .await;
+        assert!(matches!(result.unwrap_err(), ServiceError::ValidationError(_)));
+    }
 }
@@ -0,0 +1,1471 @@
+// SCIM 2.0 用户与组同步服务 (SCIM Service)
+//
+// - SCIM User 对应 `users`，SCIM Group 对应角色 (`roles`)，组成员即全局的角色分配
+// - 停用用户 (`active = false`) 会撤销其全部刷新令牌，访问令牌在认证中间件中失效
+// - `meta.version` (ETag) 由资源内容计算，内容不变则版本不变
+
+use crate::error::ServiceError;
+use crate::services::role_service::{RoleAssignmentOptions, RoleService};
+use crate::services::token_service::TokenService;
+use crate::services::user_service::UserService;
+use crate::utils::crypto;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+pub const USER_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+pub const GROUP_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:Group";
+pub const LIST_RESPONSE_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+pub const PATCH_OP_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:PatchOp";
+pub const ERROR_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+
+/// 列表查询每页最多返回的资源数
+pub const MAX_PAGE_SIZE: u32 = 200;
+
+/// 未提供密码时为新用户生成的随机密码长度 (用户需通过重置密码或联合登录使用账号)
+const GENERATED_PASSWORD_LENGTH: usize = 32;
+
+/// PATCH 路径中允许出现的属性名，用于把大小写不同的属性名还原为规范写法
+const KNOWN_ATTRIBUTES: &[&str] = &[
+    "userName",
+    "name",
+    "givenName",
+    "familyName",
+    "formatted",
+    "displayName",
+    "active",
+    "emails",
+    "externalId",
+    "password",
+    "members",
+    "value",
+    "display",
+    "type",
+    "primary",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimName {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub formatted: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub given_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub family_name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScimEmail {
+    pub value: String,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub email_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary: Option<bool>,
+}
+
+/// 组成员 (或用户所属的组)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScimMember {
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display: Option<String>,
+    #[serde(rename = "$ref", default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimMeta {
+    pub resource_type: String,
+    pub created: DateTime<Utc>,
+    pub last_modified: DateTime<Utc>,
+    pub location: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUser {
+    #[serde(default)]
+    pub schemas: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    #[serde(default)]
+    pub user_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<ScimName>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default = "default_active", deserialize_with = "deserialize_active")]
+    pub active: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub emails: Vec<ScimEmail>,
+    /// 只写属性，不会出现在响应中
+    #[serde(default, skip_serializing)]
+    pub password: Option<String>,
+    /// 只读属性，由组成员关系决定
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<ScimMember>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ScimMeta>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimGroup {
+    #[serde(default)]
+    pub schemas: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    #[serde(default)]
+    pub display_name: String,
+    #[serde(default)]
+    pub members: Vec<ScimMember>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ScimMeta>,
+}
+
+/// 列表查询的结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimListResponse<T> {
+    pub schemas: Vec<String>,
+    pub total_results: u64,
+    pub start_index: u32,
+    pub items_per_page: u32,
+    #[serde(rename = "Resources")]
+    pub resources: Vec<T>,
+}
+
+impl<T> ScimListResponse<T> {
+    pub fn new(resources: Vec<T>, total_results: u64, start_index: u32) -> Self {
+        Self {
+            schemas: vec![LIST_RESPONSE_SCHEMA.to_string()],
+            total_results,
+            start_index,
+            items_per_page: resources.len() as u32,
+            resources,
+        }
+    }
+}
+
+/// PATCH 请求体
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScimPatchRequest {
+    #[serde(default)]
+    pub schemas: Vec<String>,
+    #[serde(rename = "Operations")]
+    pub operations: Vec<ScimPatchOperation>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScimPatchOperation {
+    pub op: String,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub value: Option<Value>,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+/// 部分身份源把 `active` 作为字符串 ("True" / "False") 发送
+fn deserialize_active<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Bool(active) => Ok(active),
+        Value::String(s) if s.eq_ignore_ascii_case("true") => Ok(true),
+        Value::String(s) if s.eq_ignore_ascii_case("false") => Ok(false),
+        Value::Null => Ok(true),
+        other => Err(serde::de::Error::custom(format!("invalid value for active: {other}"))),
+    }
+}
+
+// ===============================
+// 过滤表达式
+// ===============================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterOperator {
+    Eq,
+    Ne,
+    Co,
+    Sw,
+    Ew,
+    Pr,
+}
+
+/// 单个比较条件，属性名统一转为小写
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterClause {
+    pub attribute: String,
+    pub operator: FilterOperator,
+    pub value: Option<Value>,
+}
+
+/// SCIM 过滤表达式，支持以 `and` 连接的 eq / ne / co / sw / ew / pr 比较
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScimFilter {
+    pub clauses: Vec<FilterClause>,
+}
+
+impl ScimFilter {
+    pub fn parse(filter: &str) -> Result<Self, String> {
+        let tokens = tokenize_filter(filter)?;
+        let mut clauses = Vec::new();
+        let mut tokens = tokens.into_iter().peekable();
+
+        while let Some(attribute) = tokens.next() {
+            let FilterToken::Word(attribute) = attribute else {
+                return Err("expected an attribute name".to_string());
+            };
+            let operator = match tokens.next() {
+                Some(FilterToken::Word(op)) => match op.to_ascii_lowercase().as_str() {
+                    "eq" => FilterOperator::Eq,
+                    "ne" => FilterOperator::Ne,
+                    "co" => FilterOperator::Co,
+                    "sw" => FilterOperator::Sw,
+                    "ew" => FilterOperator::Ew,
+                    "pr" => FilterOperator::Pr,
+                    other => return Err(format!("unsupported operator '{other}'")),
+                },
+                _ => return Err(format!("expected an operator after '{attribute}'")),
+            };
+            let value = if operator == FilterOperator::Pr {
+                None
+            } else {
+                match tokens.next() {
+                    Some(FilterToken::String(s)) => Some(Value::String(s)),
+                    Some(FilterToken::Word(w)) => Some(match w.to_ascii_lowercase().as_str() {
+                        "true" => Value::Bool(true),
+                        "false" => Value::Bool(false),
+                        "null" => Value::Null,
+                        _ => w
+                            .parse::<serde_json::Number>()
+                            .map(Value::Number)
+                            .map_err(|_| format!("invalid comparison value '{w}'"))?,
+                    }),
+                    None => return Err(format!("expected a value after '{attribute}'")),
+                }
+            };
+            clauses.push(FilterClause {
+                attribute: attribute.to_ascii_lowercase(),
+                operator,
+                value,
+            });
+
+            match tokens.next() {
+                None => break,
+                Some(FilterToken::Word(w)) if w.eq_ignore_ascii_case("and") => {
+                    if tokens.peek().is_none() {
+                        return Err("expected a comparison after 'and'".to_string());
+                    }
+                }
+                Some(FilterToken::Word(w)) if w.eq_ignore_ascii_case("or") => {
+                    return Err("'or' is not supported".to_string())
+                }
+                Some(_) => return Err("expected 'and'".to_string()),
+            }
+        }
+
+        if clauses.is_empty() {
+            return Err("empty filter".to_string());
+        }
+        Ok(Self { clauses })
+    }
+
+    /// 判断 JSON 对象 (如多值属性中的一项) 是否满足全部条件
+    fn matches(&self, item: &Value) -> bool {
+        self.clauses.iter().all(|clause| {
+            let actual = item
+                .as_object()
+                .and_then(|object| get_ignore_case(object, &clause.attribute));
+            match (clause.operator, actual, &clause.value) {
+                (FilterOperator::Pr, actual, _) => actual.is_some_and(|v| !v.is_null()),
+                (op, Some(Value::String(actual)), Some(Value::String(expected))) => {
+                    let (actual, expected) = (actual.to_lowercase(), expected.to_lowercase());
+                    match op {
+                        FilterOperator::Eq => actual == expected,
+                        FilterOperator::Ne => actual != expected,
+                        FilterOperator::Co => actual.contains(&expected),
+                        FilterOperator::Sw => actual.starts_with(&expected),
+                        FilterOperator::Ew => actual.ends_with(&expected),
+                        FilterOperator::Pr => unreachable!(),
+                    }
+                }
+                (FilterOperator::Eq, actual, Some(expected)) => actual == Some(expected),
+                (FilterOperator::Ne, actual, Some(expected)) => actual != Some(expected),
+                _ => false,
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FilterToken {
+    Word(String),
+    String(String),
+}
+
+fn tokenize_filter(filter: &str) -> Result<Vec<FilterToken>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = filter.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut value = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some(escaped) => value.push(escaped),
+                        None => return Err("unterminated string".to_string()),
+                    },
+                    Some(c) => value.push(c),
+                    None => return Err("unterminated string".to_string()),
+                }
+            }
+            tokens.push(FilterToken::String(value));
+        } else if c == '(' || c == ')' || c == '[' || c == ']' {
+            return Err("grouping is not supported".to_string());
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '"' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(FilterToken::Word(word));
+        }
+    }
+    Ok(tokens)
+}
+
+/// 把过滤条件翻译为 SQL 条件，`columns` 为 (SCIM 属性名小写, 列)。
+/// 多值属性可映射为含 `{cond}` 的子查询模板，比较条件作用于子查询中的 `v` 列
+fn filter_to_sql(
+    filter: &ScimFilter,
+    columns: &[(&str, &str)],
+) -> Result<(String, Vec<Value>), ServiceError> {
+    let mut conditions = Vec::new();
+    let mut binds = Vec::new();
+
+    for clause in &filter.clauses {
+        let mapping = columns
+            .iter()
+            .find(|(attribute, _)| *attribute == clause.attribute)
+            .map(|(_, column)| *column)
+            .ok_or_else(|| {
+                ServiceError::ValidationError(format!(
+                    "Filtering on '{}' is not supported",
+                    clause.attribute
+                ))
+            })?;
+        let column = if mapping.contains("{cond}") { "v" } else { mapping };
+
+        let value = clause.value.clone().unwrap_or(Value::Null);
+        let like = |pattern: String| {
+            let escaped = value
+                .as_str()
+                .unwrap_or_default()
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            Value::String(pattern.replace("{}", &escaped))
+        };
+        let condition = match clause.operator {
+            FilterOperator::Pr => format!("({column} IS NOT NULL AND {column} <> '')"),
+            FilterOperator::Eq if value.is_null() => format!("{column} IS NULL"),
+            FilterOperator::Ne if value.is_null() => format!("{column} IS NOT NULL"),
+            FilterOperator::Eq => {
+                binds.push(value.clone());
+                format!("{column} = ? COLLATE NOCASE")
+            }
+            FilterOperator::Ne => {
+                binds.push(value.clone());
+                format!("({column} IS NULL OR {column} <> ? COLLATE NOCASE)")
+            }
+            FilterOperator::Co => {
+                binds.push(like("%{}%".to_string()));
+                format!("{column} LIKE ? ESCAPE '\\'")
+            }
+            FilterOperator::Sw => {
+                binds.push(like("{}%".to_string()));
+                format!("{column} LIKE ? ESCAPE '\\'")
+            }
+            FilterOperator::Ew => {
+                binds.push(like("%{}".to_string()));
+                format!("{column} LIKE ? ESCAPE '\\'")
+            }
+        };
+        conditions.push(if mapping.contains("{cond}") {
+            mapping.replace("{cond}", &condition)
+        } else {
+            condition
+        });
+    }
+
+    if conditions.is_empty() {
+        Ok(("1 = 1".to_string(), binds))
+    } else {
+        Ok((conditions.join(" AND "), binds))
+    }
+}
+
+// ===============================
+// PATCH
+// ===============================
+
+fn get_ignore_case<'a>(object: &'a Map<String, Value>, key: &str) -> Option<&'a Value> {
+    object
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v)
+}
+
+/// 返回对象中实际使用的键名；不存在时使用规范写法
+fn resolve_key(object: &Map<String, Value>, key: &str) -> String {
+    object
+        .keys()
+        .find(|k| k.eq_ignore_ascii_case(key))
+        .cloned()
+        .or_else(|| {
+            KNOWN_ATTRIBUTES
+                .iter()
+                .find(|known| known.eq_ignore_ascii_case(key))
+                .map(|known| known.to_string())
+        })
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// 解析后的 PATCH 路径: `attr[.sub]` 或 `attr[filter][.sub]`
+struct PatchPath {
+    attribute: String,
+    filter: Option<ScimFilter>,
+    sub_attribute: Option<String>,
+}
+
+impl PatchPath {
+    fn parse(path: &str) -> Result<Self, ServiceError> {
+        let invalid = |detail: String| ServiceError::ValidationError(format!("Invalid path '{path}': {detail}"));
+        // 去掉可能带有的 schema URN 前缀
+        let path = path
+            .strip_prefix(USER_SCHEMA)
+            .or_else(|| path.strip_prefix(GROUP_SCHEMA))
+            .map(|rest| rest.trim_start_matches(':'))
+            .unwrap_or(path);
+
+        if let Some((attribute, rest)) = path.split_once('[') {
+            let (filter, rest) = rest
+                .split_once(']')
+                .ok_or_else(|| invalid("missing ']'".to_string()))?;
+            let sub_attribute = match rest {
+                "" => None,
+                rest => Some(
+                    rest.strip_prefix('.')
+                        .ok_or_else(|| invalid("expected '.' after ']'".to_string()))?
+                        .to_string(),
+                ),
+            };
+            Ok(Self {
+                attribute: attribute.to_string(),
+                filter: Some(ScimFilter::parse(filter).map_err(invalid)?),
+                sub_attribute,
+            })
+        } else {
+            let (attribute, sub_attribute) = match path.split_once('.') {
+                Some((attribute, sub)) => (attribute.to_string(), Some(sub.to_string())),
+                None => (path.to_string(), None),
+            };
+            Ok(Self {
+                attribute,
+                filter: None,
+                sub_attribute,
+            })
+        }
+    }
+}
+
+/// 多值属性中判断两项是否相同 (按 `value` 比较)
+fn same_item(a: &Value, b: &Value) -> bool {
+    match (a.get("value"), b.get("value")) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn as_items(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(items) => items,
+        item => vec![item],
+    }
+}
+
+/// 在资源的 JSON 表示上执行 PATCH 操作 (RFC 7644 3.5.2)
+pub fn apply_patch_operations(
+    resource: &mut Value,
+    operations: &[ScimPatchOperation],
+) -> Result<(), ServiceError> {
+    for operation in operations {
+        let op = operation.op.to_ascii_lowercase();
+        if !matches!(op.as_str(), "add" | "replace" | "remove") {
+            return Err(ServiceError::ValidationError(format!(
+                "Unsupported patch operation '{}'",
+                operation.op
+            )));
+        }
+
+        match &operation.path {
+            Some(path) => {
+                let path = PatchPath::parse(path)?;
+                apply_path_operation(resource, &op, &path, operation.value.clone())?;
+            }
+            None => {
+                // 没有 path 时 value 为对象，每个键 (可以是 `name.givenName` 形式) 视为一个路径
+                if op == "remove" {
+                    return Err(ServiceError::ValidationError(
+                        "Remove operations require a path".to_string(),
+                    ));
+                }
+                let Some(Value::Object(values)) = operation.value.clone() else {
+                    return Err(ServiceError::ValidationError(
+                        "Operations without a path require an object value".to_string(),
+                    ));
+                };
+                for (key, value) in values {
+                    let path = PatchPath::parse(&key)?;
+                    apply_path_operation(resource, &op, &path, Some(value))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn apply_path_operation(
+    resource: &mut Value,
+    op: &str,
+    path: &PatchPath,
+    value: Option<Value>,
+) -> Result<(), ServiceError> {
+    let object = resource
+        .as_object_mut()
+        .ok_or_else(|| ServiceError::Internal("SCIM resource is not an object".to_string()))?;
+    let key = resolve_key(object, &path.attribute);
+    let missing_value =
+        || ServiceError::ValidationError(format!("Operation '{op}' on '{key}' requires a value"));
+
+    match (&path.filter, &path.sub_attribute) {
+        // attr[filter] / attr[filter].sub
+        (Some(filter), sub_attribute) => {
+            let items = object
+                .entry(key.clone())
+                .or_insert_with(|| Value::Array(Vec::new()));
+            let Value::Array(items) = items else {
+                return Err(ServiceError::ValidationError(format!("'{key}' is not multi-valued")));
+            };
+            match (op, sub_attribute) {
+                ("remove", None) => items.retain(|item| !filter.matches(item)),
+                ("remove", Some(sub)) => {
+                    for item in items.iter_mut().filter(|item| filter.matches(item)) {
+                        if let Some(item) = item.as_object_mut() {
+                            let sub = resolve_key(item, sub);
+                            item.remove(&sub);
+                        }
+                    }
+                }
+                (_, None) => {
+                    let value = value.ok_or_else(missing_value)?;
+                    for item in items.iter_mut().filter(|item| filter.matches(item)) {
+                        *item = value.clone();
+                    }
+                }
+                (_, Some(sub)) => {
+                    let value = value.ok_or_else(missing_value)?;
+                    let mut matched = false;
+                    for item in items.iter_mut().filter(|item| filter.matches(item)) {
+                        if let Some(item) = item.as_object_mut() {
+                            item.insert(resolve_key(item, sub), value.clone());
+                            matched = true;
+                        }
+                    }
+                    // 如 `emails[type eq "work"].value`：不存在匹配项时按过滤条件新建一项
+                    if !matched {
+                        let mut item = Map::new();
+                        for clause in &filter.clauses {
+                            if let (FilterOperator::Eq, Some(expected)) = (clause.operator, &clause.value) {
+                                item.insert(resolve_key(&item, &clause.attribute), expected.clone());
+                            }
+                        }
+                        item.insert(resolve_key(&item, sub), value);
+                        items.push(Value::Object(item));
+                    }
+                }
+            }
+        }
+        // attr.sub
+        (None, Some(sub)) => {
+            if op == "remove" {
+                if let Some(Value::Object(parent)) = object.get_mut(&key) {
+                    let sub = resolve_key(parent, sub);
+                    parent.remove(&sub);
+                }
+            } else {
+                let value = value.ok_or_else(missing_value)?;
+                let parent = object.entry(key.clone()).or_insert_with(|| Value::Object(Map::new()));
+                if parent.is_null() {
+                    *parent = Value::Object(Map::new());
+                }
+                let Value::Object(parent) = parent else {
+                    return Err(ServiceError::ValidationError(format!("'{key}' is not complex")));
+                };
+                parent.insert(resolve_key(parent, sub), value);
+            }
+        }
+        // attr
+        (None, None) => match (op, object.get_mut(&key)) {
+            ("remove", Some(Value::Array(items))) if value.is_some() => {
+                // 带 value 的 remove 只移除列出的项 (如 members)
+                let removed = as_items(value.unwrap_or_default());
+                items.retain(|item| !removed.iter().any(|r| same_item(item, r)));
+            }
+            ("remove", _) => {
+                object.remove(&key);
+            }
+            ("add", Some(Value::Array(items))) => {
+                for item in as_items(value.ok_or_else(missing_value)?) {
+                    if !items.iter().any(|existing| same_item(existing, &item)) {
+                        items.push(item);
+                    }
+                }
+            }
+            ("add", Some(Value::Object(existing))) => match value.ok_or_else(missing_value)? {
+                Value::Object(values) => existing.extend(values),
+                value => {
+                    object.insert(key.clone(), value);
+                }
+            },
+            _ => {
+                let value = value.ok_or_else(missing_value)?;
+                object.insert(key, value);
+            }
+        },
+    }
+    Ok(())
+}
+
+// ===============================
+// 服务
+// ===============================
+
+/// ETag 比较，`*` 匹配任意版本
+fn check_version(expected: Option<&str>, current: &str) -> Result<(), ServiceError> {
+    match expected {
+        Some(expected) if expected != "*" && expected != current => Err(
+            ServiceError::PreconditionFailed("Resource has been modified".to_string()),
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// 由资源内容计算弱 ETag
+fn compute_version<T: Serialize>(resource: &T) -> String {
+    let content = serde_json::to_vec(resource).unwrap_or_default();
+    let digest = Sha256::digest(&content);
+    let hex: String = digest.iter().take(10).map(|b| format!("{b:02x}")).collect();
+    format!("W/\"{hex}\"")
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// 首选邮箱: 标记为 primary 的一项，否则为第一项
+fn primary_email(emails: &[ScimEmail]) -> Option<String> {
+    emails
+        .iter()
+        .find(|email| email.primary == Some(true))
+        .or_else(|| emails.first())
+        .and_then(|email| non_empty(Some(email.value.clone())))
+}
+
+#[derive(sqlx::FromRow)]
+struct UserRow {
+    id: String,
+    username: String,
+    is_active: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    display_name: Option<String>,
+    first_name: Option<String>,
+    last_name: Option<String>,
+    external_id: Option<String>,
+    email: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct GroupRow {
+    id: String,
+    name: String,
+    external_id: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+const USER_COLUMNS: &str = "id, username, is_active, created_at, updated_at, display_name, \
+     first_name, last_name, external_id, email";
+
+const USER_FILTER_COLUMNS: &[(&str, &str)] = &[
+    ("id", "id"),
+    ("username", "username"),
+    ("externalid", "external_id"),
+    ("displayname", "display_name"),
+    ("name.givenname", "first_name"),
+    ("name.familyname", "last_name"),
+    ("emails", "email"),
+    ("emails.value", "email"),
+    ("active", "is_active"),
+];
+
+/// 当前有效的全局角色分配 (组成员关系)
+const ACTIVE_MEMBERSHIP: &str =
+    "ur.context IS NULL AND (ur.expires_at IS NULL OR datetime(ur.expires_at) > datetime('now'))";
+
+const GROUP_FILTER_COLUMNS: &[(&str, &str)] = &[
+    ("id", "r.id"),
+    ("displayname", "r.name"),
+    ("externalid", "r.external_id"),
+    (
+        "members.value",
+        "EXISTS (SELECT 1 FROM (SELECT ur.user_id AS v FROM user_roles ur WHERE ur.role_id = r.id \
+         AND ur.context IS NULL AND (ur.expires_at IS NULL OR datetime(ur.expires_at) > datetime('now'))) \
+         WHERE {cond})",
+    ),
+];
+
+#[async_trait]
+pub trait ScimService: Send + Sync {
+    /// 按过滤条件分页列出用户，`start_index` 从 1 开始，返回 (当前页, 总数)
+    async fn list_users(
+        &self,
+        filter: Option<&ScimFilter>,
+        start_index: u32,
+        count: u32,
+    ) -> Result<(Vec<ScimUser>, u64), ServiceError>;
+    async fn get_user(&self, id: &str) -> Result<ScimUser, ServiceError>;
+    async fn create_user(&self, user: ScimUser) -> Result<ScimUser, ServiceError>;
+    /// 整体替换用户属性，`if_match` 为客户端提供的 ETag
+    async fn replace_user(
+        &self,
+        id: &str,
+        user: ScimUser,
+        if_match: Option<&str>,
+    ) -> Result<ScimUser, ServiceError>;
+    async fn patch_user(
+        &self,
+        id: &str,
+        operations: &[ScimPatchOperation],
+        if_match: Option<&str>,
+    ) -> Result<ScimUser, ServiceError>;
+    /// 删除用户，删除前撤销其全部令牌
+    async fn delete_user(&self, id: &str, if_match: Option<&str>) -> Result<(), ServiceError>;
+
+    async fn list_groups(
+        &self,
+        filter: Option<&ScimFilter>,
+        start_index: u32,
+        count: u32,
+    ) -> Result<(Vec<ScimGroup>, u64), ServiceError>;
+    async fn get_group(&self, id: &str) -> Result<ScimGroup, ServiceError>;
+    async fn create_group(&self, group: ScimGroup) -> Result<ScimGroup, ServiceError>;
+    async fn replace_group(
+        &self,
+        id: &str,
+        group: ScimGroup,
+        if_match: Option<&str>,
+    ) -> Result<ScimGroup, ServiceError>;
+    async fn patch_group(
+        &self,
+        id: &str,
+        operations: &[ScimPatchOperation],
+        if_match: Option<&str>,
+    ) -> Result<ScimGroup, ServiceError>;
+    async fn delete_group(&self, id: &str, if_match: Option<&str>) -> Result<(), ServiceError>;
+}
+
+pub struct ScimServiceImpl {
+    db: Arc<SqlitePool>,
+    user_service: Arc<dyn UserService>,
+    role_service: Arc<dyn RoleService>,
+    token_service: Arc<dyn TokenService>,
+    /// 资源 `meta.location` 的前缀，如 `https://auth.example.com/scim/v2`
+    base_url: String,
+}
+
+impl ScimServiceImpl {
+    pub fn new(
+        db: Arc<SqlitePool>,
+        user_service: Arc<dyn UserService>,
+        role_service: Arc<dyn RoleService>,
+        token_service: Arc<dyn TokenService>,
+        issuer: &str,
+    ) -> Self {
+        Self {
+            db,
+            user_service,
+            role_service,
+            token_service,
+            base_url: format!("{}/scim/v2", issuer.trim_end_matches('/')),
+        }
+    }
+
+    async fn user_groups(&self, user_id: &str) -> Result<Vec<ScimMember>, ServiceError> {
+        let groups: Vec<(String, String)> = sqlx::query_as(&format!(
+            "SELECT r.id, r.name FROM user_roles ur JOIN roles r ON r.id = ur.role_id
+             WHERE ur.user_id = ? AND {ACTIVE_MEMBERSHIP} ORDER BY r.name"
+        ))
+        .bind(user_id)
+        .fetch_all(&*self.db)
+        .await?;
+        Ok(groups
+            .into_iter()
+            .map(|(id, name)| ScimMember {
+                reference: Some(format!("{}/Groups/{id}", self.base_url)),
+                value: id,
+                display: Some(name),
+            })
+            .collect())
+    }
+
+    async fn group_members(&self, role_id: &str) -> Result<Vec<ScimMember>, ServiceError> {
+        let members: Vec<(String, String)> = sqlx::query_as(&format!(
+            "SELECT u.id, u.username FROM user_roles ur JOIN users u ON u.id = ur.user_id
+             WHERE ur.role_id = ? AND {ACTIVE_MEMBERSHIP} ORDER BY u.username"
+        ))
+        .bind(role_id)
+        .fetch_all(&*self.db)
+        .await?;
+        Ok(members
+            .into_iter()
+            .map(|(id, username)| ScimMember {
+                reference: Some(format!("{}/Users/{id}", self.base_url)),
+                value: id,
+                display: Some(username),
+            })
+            .collect())
+    }
+
+    async fn to_scim_user(&self, row: UserRow) -> Result<ScimUser, ServiceError> {
+        let name = (row.first_name.is_some() || row.last_name.is_some()).then_some(ScimName {
+            formatted: None,
+            given_name: row.first_name,
+            family_name: row.last_name,
+        });
+        let mut user = ScimUser {
+            schemas: vec![USER_SCHEMA.to_string()],
+            groups: self.user_groups(&row.id).await?,
+            id: Some(row.id.clone()),
+            external_id: row.external_id,
+            user_name: row.username,
+            name,
+            display_name: row.display_name,
+            active: row.is_active,
+            emails: row
+                .email
+                .map(|value| {
+                    vec![ScimEmail {
+                        value,
+                        email_type: Some("work".to_string()),
+                        primary: Some(true),
+                    }]
+                })
+                .unwrap_or_default(),
+            password: None,
+            meta: None,
+        };
+        user.meta = Some(ScimMeta {
+            resource_type: "User".to_string(),
+            created: row.created_at,
+            last_modified: row.updated_at,
+            location: format!("{}/Users/{}", self.base_url, row.id),
+            version: compute_version(&user),
+        });
+        Ok(user)
+    }
+
+    async fn to_scim_group(&self, row: GroupRow) -> Result<ScimGroup, ServiceError> {
+        let mut group = ScimGroup {
+            schemas: vec![GROUP_SCHEMA.to_string()],
+            members: self.group_members(&row.id).await?,
+            id: Some(row.id.clone()),
+            external_id: row.external_id,
+            display_name: row.name,
+            meta: None,
+        };
+        group.meta = Some(ScimMeta {
+            resource_type: "Group".to_string(),
+            created: row.created_at,
+            last_modified: row.updated_at,
+            location: format!("{}/Groups/{}", self.base_url, row.id),
+            version: compute_version(&group),
+        });
+        Ok(group)
+    }
+
+    async fn find_user_row(&self, id: &str) -> Result<UserRow, ServiceError> {
+        sqlx::query_as::<_, UserRow>(&format!("SELECT {USER_COLUMNS} FROM users WHERE id = ?"))
+            .bind(id)
+            .fetch_optional(&*self.db)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("User '{id}' not found")))
+    }
+
+    async fn find_group_row(&self, id: &str) -> Result<GroupRow, ServiceError> {
+        sqlx::query_as::<_, GroupRow>(
+            "SELECT id, name, external_id, created_at, updated_at FROM roles WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&*self.db)
+        .await?
+        .ok_or_else(|| ServiceError::NotFound(format!("Group '{id}' not found")))
+    }
+
+    /// 检查唯一属性是否被其他资源占用
+    async fn ensure_unique(
+        &self,
+        table: &str,
+        column: &str,
+        value: Option<&str>,
+        except_id: Option<&str>,
+    ) -> Result<(), ServiceError> {
+        let Some(value) = value else { return Ok(()) };
+        let taken: Option<(String,)> = sqlx::query_as(&format!(
+            "SELECT id FROM {table} WHERE {column} = ? COLLATE NOCASE AND id <> ?"
+        ))
+        .bind(value)
+        .bind(except_id.unwrap_or_default())
+        .fetch_optional(&*self.db)
+        .await?;
+        match taken {
+            Some(_) => Err(ServiceError::Conflict(format!("{column} '{value}' is already in use"))),
+            None => Ok(()),
+        }
+    }
+
+    /// 写入用户的全部可写属性 (PUT 语义：未提供的属性被清空)
+    async fn write_user(&self, id: &str, user: &ScimUser, was_active: bool) -> Result<(), ServiceError> {
+        let user_name = non_empty(Some(user.user_name.clone()))
+            .ok_or_else(|| ServiceError::ValidationError("userName is required".to_string()))?;
+        let external_id = non_empty(user.external_id.clone());
+        let email = primary_email(&user.emails);
+        self.ensure_unique("users", "username", Some(&user_name), Some(id)).await?;
+        self.ensure_unique("users", "external_id", external_id.as_deref(), Some(id))
+            .await?;
+
+        let name = user.name.clone().unwrap_or_default();
+        sqlx::query(
+            "UPDATE users SET username = ?, display_name = ?, first_name = ?, last_name = ?,
+                 external_id = ?, email = ?, is_active = ?, updated_at = ?
+             WHERE id = ?",
+        )
+        .bind(&user_name)
+        .bind(non_empty(user.display_name.clone()))
+        .bind(non_empty(name.given_name))
+        .bind(non_empty(name.family_name))
+        .bind(&external_id)
+        .bind(&email)
+        .bind(user.active)
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&*self.db)
+        .await?;
+
+        if let Some(password) = &user.password {
+            self.user_service.set_password(id, password, false).await?;
+        }
+
+        // 停用账号时撤销已发放的令牌
+        if was_active && !user.active {
+            let revoked = self.token_service.revoke_all_for_user(id).await?;
+            tracing::info!("SCIM deactivated user {}, revoked {} token(s)", id, revoked);
+        }
+        Ok(())
+    }
+
+    /// 把组成员设置为 `members` 中列出的用户
+    async fn write_members(&self, role_id: &str, members: &[ScimMember]) -> Result<(), ServiceError> {
+        let wanted: BTreeSet<String> = members.iter().map(|m| m.value.clone()).collect();
+        for user_id in &wanted {
+            let exists: Option<(String,)> = sqlx::query_as("SELECT id FROM users WHERE id = ?")
+                .bind(user_id)
+                .fetch_optional(&*self.db)
+                .await?;
+            if exists.is_none() {
+                return Err(ServiceError::ValidationError(format!(
+                    "Member '{user_id}' is not a known user"
+                )));
+            }
+        }
+
+        let current: BTreeSet<String> = self
+            .group_members(role_id)
+            .await?
+            .into_iter()
+            .map(|m| m.value)
+            .collect();
+        for user_id in current.difference(&wanted) {
+            self.role_service.remove_role_from_user(user_id, role_id).await?;
+        }
+        for user_id in wanted.difference(&current) {
+            self.role_service
+                .assign_role_to_user(
+                    user_id,
+                    role_id,
+                    RoleAssignmentOptions {
+                        assigned_by: Some("scim".to_string()),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn write_group(&self, id: &str, group: &ScimGroup) -> Result<(), ServiceError> {
+        let display_name = non_empty(Some(group.display_name.clone()))
+            .ok_or_else(|| ServiceError::ValidationError("displayName is required".to_string()))?;
+        let external_id = non_empty(group.external_id.clone());
+        self.ensure_unique("roles", "external_id", external_id.as_deref(), Some(id))
+            .await?;
+
+        let role = self
+            .role_service
+            .find_role_by_id(id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("Group '{id}' not found")))?;
+        if role.name != display_name {
+            self.role_service
+                .update_role(id, Some(display_name), None)
+                .await?;
+        }
+        sqlx::query("UPDATE roles SET external_id = ?, updated_at = ? WHERE id = ?")
+            .bind(&external_id)
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&*self.db)
+            .await?;
+
+        self.write_members(id, &group.members).await
+    }
+}
+
+/// 列表分页参数：`start_index` 从 1 开始，`count` 不超过 MAX_PAGE_SIZE
+fn page(start_index: u32, count: u32) -> (i64, i64) {
+    (count.min(MAX_PAGE_SIZE) as i64, start_index.max(1) as i64 - 1)
+}
+
+#[async_trait]
+impl ScimService for ScimServiceImpl {
+    async fn list_users(
+        &self,
+        filter: Option<&ScimFilter>,
+        start_index: u32,
+        count: u32,
+    ) -> Result<(Vec<ScimUser>, u64), ServiceError> {
+        let (condition, binds) = match filter {
+            Some(filter) => filter_to_sql(filter, USER_FILTER_COLUMNS)?,
+            None => ("1 = 1".to_string(), Vec::new()),
+        };
+        let (limit, offset) = page(start_index, count);
+
+        let sql = format!("SELECT COUNT(*) FROM users WHERE {condition}");
+        let mut count_query = sqlx::query_scalar::<_, i64>(&sql);
+        for value in &binds {
+            count_query = match value {
+                Value::Bool(b) => count_query.bind(*b),
+                Value::Number(n) => count_query.bind(n.as_f64()),
+                Value::String(s) => count_query.bind(s.clone()),
+                other => count_query.bind(other.to_string()),
+            };
+        }
+        let total = count_query.fetch_one(&*self.db).await?;
+
+        let sql = format!(
+            "SELECT {USER_COLUMNS} FROM users WHERE {condition} ORDER BY created_at, id LIMIT ? OFFSET ?"
+        );
+        let mut query = sqlx::query_as::<_, UserRow>(&sql);
+        for value in &binds {
+            query = match value {
+                Value::Bool(b) => query.bind(*b),
+                Value::Number(n) => query.bind(n.as_f64()),
+                Value::String(s) => query.bind(s.clone()),
+                other => query.bind(other.to_string()),
+            };
+        }
+        let rows = query.bind(limit).bind(offset).fetch_all(&*self.db).await?;
+
+        let mut users = Vec::with_capacity(rows.len());
+        for row in rows {
+            users.push(self.to_scim_user(row).await?);
+        }
+        Ok((users, total as u64))
+    }
+
+    async fn get_user(&self, id: &str) -> Result<ScimUser, ServiceError> {
+        let row = self.find_user_row(id).await?;
+        self.to_scim_user(row).await
+    }
+
+    async fn create_user(&self, user: ScimUser) -> Result<ScimUser, ServiceError> {
+        let user_name = non_empty(Some(user.user_name.clone()))
+            .ok_or_else(|| ServiceError::ValidationError("userName is required".to_string()))?;
+        self.ensure_unique("users", "username", Some(&user_name), None).await?;
+        self.ensure_unique("users", "external_id", user.external_id.as_deref(), None)
+            .await?;
+
+        let password = user
+            .password
+            .clone()
+            .unwrap_or_else(|| crypto::generate_random_string(GENERATED_PASSWORD_LENGTH));
+        let created = self
+            .user_service
+            .create_user(user_name, password, user.display_name.clone())
+            .await?;
+        // 由身份源管理的账号不强制首次登录修改密码
+        sqlx::query("UPDATE users SET must_change_password = 0, created_by = 'scim' WHERE id = ?")
+            .bind(&created.id)
+            .execute(&*self.db)
+            .await?;
+
+        let user = ScimUser { password: None, ..user };
+        self.write_user(&created.id, &user, true).await?;
+        tracing::info!("SCIM provisioned user {}", created.id);
+        self.get_user(&created.id).await
+    }
+
+    async fn replace_user(
+        &self,
+        id: &str,
+        user: ScimUser,
+        if_match: Option<&str>,
+    ) -> Result<ScimUser, ServiceError> {
+        let current = self.get_user(id).await?;
+        check_version(if_match, &current.meta.as_ref().map(|m| m.version.clone()).unwrap_or_default())?;
+        self.write_user(id, &user, current.active).await?;
+        self.get_user(id).await
+    }
+
+    async fn patch_user(
+        &self,
+        id: &str,
+        operations: &[ScimPatchOperation],
+        if_match: Option<&str>,
+    ) -> Result<ScimUser, ServiceError> {
+        let current = self.get_user(id).await?;
+        check_version(if_match, &current.meta.as_ref().map(|m| m.version.clone()).unwrap_or_default())?;
+
+        let mut resource = serde_json::to_value(&current)
+            .map_err(|e| ServiceError::Internal(e.to_string()))?;
+        apply_patch_operations(&mut resource, operations)?;
+        let patched: ScimUser = serde_json::from_value(resource)
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid patch result: {e}")))?;
+
+        self.write_user(id, &patched, current.active).await?;
+        self.get_user(id).await
+    }
+
+    async fn delete_user(&self, id: &str, if_match: Option<&str>) -> Result<(), ServiceError> {
+        let current = self.get_user(id).await?;
+        check_version(if_match, &current.meta.as_ref().map(|m| m.version.clone()).unwrap_or_default())?;
+
+        self.token_service.revoke_all_for_user(id).await?;
+        sqlx::query("DELETE FROM users WHERE id = ?")
+            .bind(id)
+            .execute(&*self.db)
+            .await?;
+        tracing::info!("SCIM deleted user {}", id);
+        Ok(())
+    }
+
+    async fn list_groups(
+        &self,
+        filter: Option<&ScimFilter>,
+        start_index: u32,
+        count: u32,
+    ) -> Result<(Vec<ScimGroup>, u64), ServiceError> {
+        let (condition, binds) = match filter {
+            Some(filter) => filter_to_sql(filter, GROUP_FILTER_COLUMNS)?,
+            None => ("1 = 1".to_string(), Vec::new()),
+        };
+        let (limit, offset) = page(start_index, count);
+
+        let sql = format!("SELECT COUNT(*) FROM roles r WHERE {condition}");
+        let mut count_query = sqlx::query_scalar::<_, i64>(&sql);
+        for value in &binds {
+            count_query = match value {
+                Value::Bool(b) => count_query.bind(*b),
+                Value::Number(n) => count_query.bind(n.as_f64()),
+                Value::String(s) => count_query.bind(s.clone()),
+                other => count_query.bind(other.to_string()),
+            };
+        }
+        let total = count_query.fetch_one(&*self.db).await?;
+
+        let sql = format!(
+            "SELECT r.id, r.name, r.external_id, r.created_at, r.updated_at FROM roles r
+             WHERE {condition} ORDER BY r.created_at, r.id LIMIT ? OFFSET ?"
+        );
+        let mut query = sqlx::query_as::<_, GroupRow>(&sql);
+        for value in &binds {
+            query = match value {
+                Value::Bool(b) => query.bind(*b),
+                Value::Number(n) => query.bind(n.as_f64()),
+                Value::String(s) => query.bind(s.clone()),
+                other => query.bind(other.to_string()),
+            };
+        }
+        let rows = query.bind(limit).bind(offset).fetch_all(&*self.db).await?;
+
+        let mut groups = Vec::with_capacity(rows.len());
+        for row in rows {
+            groups.push(self.to_scim_group(row).await?);
+        }
+        Ok((groups, total as u64))
+    }
+
+    async fn get_group(&self, id: &str) -> Result<ScimGroup, ServiceError> {
+        let row = self.find_group_row(id).await?;
+        self.to_scim_group(row).await
+    }
+
+    async fn create_group(&self, group: ScimGroup) -> Result<ScimGroup, ServiceError> {
+        let display_name = non_empty(Some(group.display_name.clone()))
+            .ok_or_else(|| ServiceError::ValidationError("displayName is required".to_string()))?;
+        self.ensure_unique("roles", "external_id", group.external_id.as_deref(), None)
+            .await?;
+        let role = self.role_service.create_role(display_name, None).await?;
+        self.write_group(&role.id, &group).await?;
+        tracing::info!("SCIM provisioned group {}", role.id);
+        self.get_group(&role.id).await
+    }
+
+    async fn replace_group(
+        &self,
+        id: &str,
+        group: ScimGroup,
+        if_match: Option<&str>,
+    ) -> Result<ScimGroup, ServiceError> {
+        let current = self.get_group(id).await?;
+        check_version(if_match, &current.meta.as_ref().map(|m| m.version.clone()).unwrap_or_default())?;
+        self.write_group(id, &group).await?;
+        self.get_group(id).await
+    }
+
+    async fn patch_group(
+        &self,
+        id: &str,
+        operations: &[ScimPatchOperation],
+        if_match: Option<&str>,
+    ) -> Result<ScimGroup, ServiceError> {
+        let current = self.get_group(id).await?;
+        check_version(if_match, &current.meta.as_ref().map(|m| m.version.clone()).unwrap_or_default())?;
+
+        let mut resource = serde_json::to_value(&current)
+            .map_err(|e| ServiceError::Internal(e.to_string()))?;
+        apply_patch_operations(&mut resource, operations)?;
+        let patched: ScimGroup = serde_json::from_value(resource)
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid patch result: {e}")))?;
+
+        self.write_group(id, &patched).await?;
+        self.get_group(id).await
+    }
+
+    async fn delete_group(&self, id: &str, if_match: Option<&str>) -> Result<(), ServiceError> {
+        let current = self.get_group(id).await?;
+        check_version(if_match, &current.meta.as_ref().map(|m| m.version.clone()).unwrap_or_default())?;
+        self.role_service.delete_role(id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::test_support::setup_state;
+    use serde_json::json;
+
+    fn patch(operations: Value) -> Vec<ScimPatchOperation> {
+        serde_json::from_value(operations).unwrap()
+    }
+
+    fn new_user(user_name: &str, external_id: &str) -> ScimUser {
+        serde_json::from_value(json!({
+            "schemas": [USER_SCHEMA],
+            "userName": user_name,
+            "externalId": external_id,
+            "name": { "givenName": "Ada", "familyName": "Lovelace" },
+            "emails": [{ "value": format!("{user_name}@example.com"), "type": "work", "primary": true }],
+            "active": "True"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_filter_parsing() {
+        let filter = ScimFilter::parse(r#"userName eq "ada" and emails.value co "@example""#).unwrap();
+        assert_eq!(filter.clauses.len(), 2);
+        assert_eq!(filter.clauses[0].attribute, "username");
+        assert_eq!(filter.clauses[1].operator, FilterOperator::Co);
+
+        let filter = ScimFilter::parse("externalId pr").unwrap();
+        assert_eq!(filter.clauses[0].value, None);
+
+        assert!(ScimFilter::parse(r#"userName eq "a" or userName eq "b""#).is_err());
+        assert!(ScimFilter::parse(r#"userName gt "a""#).is_err());
+        assert!(ScimFilter::parse(r#"userName eq "unterminated"#).is_err());
+        assert!(ScimFilter::parse("").is_err());
+    }
+
+    #[test]
+    fn test_apply_patch_operations() {
+        let mut resource = json!({
+            "userName": "ada",
+            "emails": [{ "value": "ada@old.example", "type": "work" }],
+            "members": [{ "value": "u1" }, { "value": "u2" }]
+        });
+        apply_patch_operations(
+            &mut resource,
+            &patch(json!([
+                { "op": "replace", "path": "emails[type eq \"work\"].value", "value": "ada@new.example" },
+                { "op": "add", "path": "emails[type eq \"home\"].value", "value": "ada@home.example" },
+                { "op": "Replace", "value": { "name.givenName": "Ada", "ACTIVE": false } },
+                { "op": "add", "path": "members", "value": [{ "value": "u2" }, { "value": "u3" }] },
+                { "op": "remove", "path": "members[value eq \"u1\"]" }
+            ])),
+        )
+        .unwrap();
+
+        assert_eq!(resource["emails"][0]["value"], "ada@new.example");
+        assert_eq!(resource["emails"][1], json!({ "type": "home", "value": "ada@home.example" }));
+        assert_eq!(resource["name"]["givenName"], "Ada");
+        assert_eq!(resource["active"], false);
+        assert_eq!(resource["members"], json!([{ "value": "u2" }, { "value": "u3" }]));
+
+        let unsupported = patch(json!([{ "op": "move", "path": "userName" }]));
+        assert!(apply_patch_operations(&mut resource, &unsupported).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_user_provisioning_lifecycle() {
+        let (state, pool) = setup_state().await;
+        let scim = &state.scim_service;
+
+        let user = scim.create_user(new_user("ada", "hr-1")).await.unwrap();
+        let id = user.id.clone().unwrap();
+        assert!(user.active);
+        assert_eq!(user.emails[0].value, "ada@example.com");
+        assert!(user.meta.as_ref().unwrap().location.ends_with(&format!("/scim/v2/Users/{id}")));
+        assert!(matches!(
+            scim.create_user(new_user("ADA", "hr-2")).await,
+            Err(ServiceError::Conflict(_))
+        ));
+
+        let filter = ScimFilter::parse(r#"userName eq "ADA""#).unwrap();
+        let (found, total) = scim.list_users(Some(&filter), 1, 10).await.unwrap();
+        assert_eq!((total, found[0].id.clone()), (1, Some(id.clone())));
+        let filter = ScimFilter::parse(r#"password eq "x""#).unwrap();
+        assert!(scim.list_users(Some(&filter), 1, 10).await.is_err());
+
+        // 版本不匹配时拒绝修改
+        let version = user.meta.unwrap().version;
+        let rename = patch(json!([{ "op": "replace", "path": "displayName", "value": "Ada L." }]));
+        assert!(matches!(
+            scim.patch_user(&id, &rename, Some("W/\"stale\"")).await,
+            Err(ServiceError::PreconditionFailed(_))
+        ));
+        let renamed = scim.patch_user(&id, &rename, Some(&version)).await.unwrap();
+        assert_eq!(renamed.display_name.as_deref(), Some("Ada L."));
+        assert_ne!(renamed.meta.unwrap().version, version);
+
+        // 停用账号时撤销刷新令牌
+        let client_id: String = sqlx::query_scalar("SELECT id FROM oauth_clients LIMIT 1")
+            .fetch_one(&*pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO refresh_tokens (id, token_hash, jti, user_id, client_id, scope, expires_at)
+             VALUES ('rt-ada', 'rt-ada', 'rt-ada', ?, ?, 'openid', ?)",
+        )
+        .bind(&id)
+        .bind(&client_id)
+        .bind(Utc::now() + chrono::Duration::hours(1))
+        .execute(&*pool)
+        .await
+        .unwrap();
+        let deactivate = patch(json!([{ "op": "replace", "value": { "active": false } }]));
+        let deactivated = scim.patch_user(&id, &deactivate, None).await.unwrap();
+        assert!(!deactivated.active);
+        let revoked: bool = sqlx::query_scalar("SELECT is_revoked FROM refresh_tokens WHERE id = 'rt-ada'")
+            .fetch_one(&*pool)
+            .await
+            .unwrap();
+        assert!(revoked);
+
+        scim.delete_user(&id, None).await.unwrap();
+        assert!(matches!(scim.get_user(&id).await, Err(ServiceError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_groups_map_to_roles() {
+        let (state, _pool) = setup_state().await;
+        let scim = &state.scim_service;
+        let ada = scim.create_user(new_user("ada", "hr-1")).await.unwrap().id.unwrap();
+        let bob = scim.create_user(new_user("bob", "hr-2")).await.unwrap().id.unwrap();
+
+        let group: ScimGroup = serde_json::from_value(json!({
+            "schemas": [GROUP_SCHEMA],
+            "displayName": "engineering",
+            "externalId": "grp-eng",
+            "members": [{ "value": ada }]
+        }))
+        .unwrap();
+        let group = scim.create_group(group).await.unwrap();
+        let group_id = group.id.clone().unwrap();
+        assert_eq!(group.members.len(), 1);
+        assert!(state.role_service.find_role_by_name("engineering").await.unwrap().is_some());
+
+        let operations = patch(json!([
+            { "op": "add", "path": "members", "value": [{ "value": bob }] },
+            { "op": "remove", "path": format!("members[value eq \"{ada}\"]") },
+            { "op": "replace", "path": "displayName", "value": "platform" }
+        ]));
+        let group = scim.patch_group(&group_id, &operations, None).await.unwrap();
+        assert_eq!(group.display_name, "platform");
+        assert_eq!(group.members.iter().map(|m| m.value.clone()).collect::<Vec<_>>(), vec![bob.clone()]);
+
+        let bob_user = scim.get_user(&bob).await.unwrap();
+        assert_eq!(bob_user.groups[0].display.as_deref(), Some("platform"));
+        assert!(scim.get_user(&ada).await.unwrap().groups.is_empty());
+
+        let filter = ScimFilter::parse(&format!(r#"members.value eq "{bob}""#)).unwrap();
+        let (groups, total) = scim.list_groups(Some(&filter), 1, 10).await.unwrap();
+        assert_eq!((total, groups[0].id.clone()), (1, Some(group_id.clone())));
+
+        scim.delete_group(&group_id, None).await.unwrap();
+        assert!(state.role_service.find_role_by_id(&group_id).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,747 @@
+use crate::error::ServiceError;
+use crate::models::scope::{Scope, ScopeConsentInfo, ScopeLocalization};
+use crate::routes::scopes::{CreateScopeRequest, UpdateScopeRequest};
+use crate::utils::scopes::get_scope_metadata;
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::{Sqlite, SqliteConnection, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 请求的语言环境没有对应文案时使用的默认语言环境
+pub const DEFAULT_SCOPE_LOCALE: &str = "zh-CN";
+
+const SCOPE_COLUMNS: &str =
+    "id, name, description, is_public, is_oidc_scope, is_active, created_at, updated_at";
+
+const RISK_LEVELS: [&str; 3] = ["low", "medium", "high"];
+
+#[async_trait]
+pub trait ScopeService: Send + Sync {
+    /// 列出所有权限范围
+    async fn list_scopes(&self) -> Result<Vec<Scope>, ServiceError>;
+
+    /// 根据ID查找权限范围
+    async fn find_scope_by_id(&self, scope_id: &str) -> Result<Option<Scope>, ServiceError>;
+
+    /// 创建权限范围
+    async fn create_scope(&self, request: CreateScopeRequest) -> Result<Scope, ServiceError>;
+
+    /// 更新权限范围
+    async fn update_scope(
+        &self,
+        scope_id: &str,
+        request: UpdateScopeRequest,
+    ) -> Result<Scope, ServiceError>;
+
+    /// 删除权限范围 (仍被客户端允许使用时拒绝删除)
+    async fn delete_scope(&self, scope_id: &str) -> Result<(), ServiceError>;
+
+    /// 获取权限范围映射的权限名称
+    async fn get_scope_permissions(&self, scope_id: &str) -> Result<Vec<String>, ServiceError>;
+
+    /// 设置权限范围映射的权限 (覆盖原有映射)
+    async fn set_scope_permissions(
+        &self,
+        scope_id: &str,
+        permission_ids: Vec<String>,
+    ) -> Result<(), ServiceError>;
+
+    /// 获取权限范围的多语言同意文案
+    async fn get_scope_localizations(
+        &self,
+        scope_id: &str,
+    ) -> Result<Vec<ScopeLocalization>, ServiceError>;
+
+    /// 设置权限范围的多语言同意文案 (覆盖原有文案)
+    async fn set_scope_localizations(
+        &self,
+        scope_id: &str,
+        localizations: Vec<ScopeLocalization>,
+    ) -> Result<(), ServiceError>;
+
+    /// 获取同意页面上展示的权限范围信息
+    ///
+    /// 按 请求语言 -> 同一语言的其他地区 -> 默认语言 -> 内置元数据 的顺序选择文案。
+    async fn get_consent_scopes(
+        &self,
+        scope: &str,
+        locale: &str,
+    ) -> Result<Vec<ScopeConsentInfo>, ServiceError>;
+
+    /// 将权限限制在授予的权限范围所映射的权限之内
+    async fn restrict_permissions_to_scope(
+        &self,
+        scope: &str,
+        permissions: Vec<String>,
+    ) -> Result<Vec<String>, ServiceError>;
+}
+
+pub struct ScopeServiceImpl {
+    db: Arc<SqlitePool>,
+}
+
+impl ScopeServiceImpl {
+    pub fn new(db: Arc<SqlitePool>) -> Self {
+        Self { db }
+    }
+
+    async fn require_scope(&self, scope_id: &str) -> Result<Scope, ServiceError> {
+        self.find_scope_by_id(scope_id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("Scope '{scope_id}' not found")))
+    }
+}
+
+/// 插入一条权限范围记录并返回其 ID。调用方负责检查名称是否重复
+pub(crate) async fn insert_scope_row<'e, E>(
+    executor: E,
+    request: &CreateScopeRequest,
+) -> Result<String, ServiceError>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now();
+
+    sqlx::query(
+        "INSERT INTO scopes (id, name, description, is_public, is_oidc_scope, is_active, created_at, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(&request.name)
+    .bind(&request.description)
+    .bind(request.is_public.unwrap_or(false))
+    .bind(request.is_oidc_scope.unwrap_or(false))
+    .bind(request.is_active.unwrap_or(true))
+    .bind(now)
+    .bind(now)
+    .execute(executor)
+    .await?;
+
+    Ok(id)
+}
+
+/// 更新权限范围的属性，描述为空时保持不变
+pub(crate) async fn write_scope_attributes<'e, E>(
+    executor: E,
+    scope_id: &str,
+    description: Option<&str>,
+    is_public: bool,
+    is_oidc_scope: bool,
+    is_active: bool,
+) -> Result<(), ServiceError>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query(
+        "UPDATE scopes
+         SET description = COALESCE(?, description), is_public = ?, is_oidc_scope = ?, is_active = ?, updated_at = ?
+         WHERE id = ?",
+    )
+    .bind(description)
+    .bind(is_public)
+    .bind(is_oidc_scope)
+    .bind(is_active)
+    .bind(Utc::now())
+    .bind(scope_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// 覆盖权限范围映射的权限，出错时调用方应回滚事务
+pub(crate) async fn write_scope_permissions(
+    conn: &mut SqliteConnection,
+    scope_id: &str,
+    permission_ids: Vec<String>,
+) -> Result<(), ServiceError> {
+    sqlx::query("DELETE FROM scope_permissions WHERE scope_id = ?")
+        .bind(scope_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let permission_ids: HashSet<String> = permission_ids.into_iter().collect();
+    for permission_id in permission_ids {
+        let permission_exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM permissions WHERE id = ?)",
+        )
+        .bind(&permission_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        if !permission_exists {
+            return Err(ServiceError::NotFound(format!(
+                "Permission '{permission_id}' not found"
+            )));
+        }
+
+        sqlx::query(
+            "INSERT INTO scope_permissions (scope_id, permission_id, assigned_at) VALUES (?, ?, ?)",
+        )
+        .bind(scope_id)
+        .bind(&permission_id)
+        .bind(Utc::now())
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// 拆分 scope 字符串并去重，保持原有顺序
+fn split_scope(scope: &str) -> Vec<&str> {
+    let mut seen = HashSet::new();
+    scope
+        .split_whitespace()
+        .filter(|name| seen.insert(*name))
+        .collect()
+}
+
+/// 从候选文案中选择最匹配请求语言环境的一条
+fn select_localization<'a>(
+    candidates: &'a [ScopeLocalization],
+    locale: &str,
+) -> Option<&'a ScopeLocalization> {
+    let language = locale.split(['-', '_']).next().unwrap_or(locale);
+    let same_language = |l: &&ScopeLocalization| {
+        l.locale
+            .split(['-', '_'])
+            .next()
+            .is_some_and(|lang| lang.eq_ignore_ascii_case(language))
+    };
+
+    candidates
+        .iter()
+        .find(|l| l.locale.eq_ignore_ascii_case(locale))
+        .or_else(|| candidates.iter().find(same_language))
+        .or_else(|| {
+            candidates
+                .iter()
+                .find(|l| l.locale.eq_ignore_ascii_case(DEFAULT_SCOPE_LOCALE))
+        })
+        .or_else(|| candidates.first())
+}
+
+#[async_trait]
+impl ScopeService for ScopeServiceImpl {
+    async fn list_scopes(&self) -> Result<Vec<Scope>, ServiceError> {
+        let scopes = sqlx::query_as::<_, Scope>(&format!(
+            "SELECT {SCOPE_COLUMNS} FROM scopes ORDER BY name"
+        ))
+        .fetch_all(&*self.db)
+        .await?;
+
+        Ok(scopes)
+    }
+
+    async fn find_scope_by_id(&self, scope_id: &str) -> Result<Option<Scope>, ServiceError> {
+        let scope = sqlx::query_as::<_, Scope>(&format!(
+            "SELECT {SCOPE_COLUMNS} FROM scopes WHERE id = ?"
+        ))
+        .bind(scope_id)
+        .fetch_optional(&*self.db)
+        .await?;
+
+        Ok(scope)
+    }
+
+    async fn create_scope(&self, request: CreateScopeRequest) -> Result<Scope, ServiceError> {
+        let existing: Option<String> = sqlx::query_scalar("SELECT id FROM scopes WHERE name = ?")
+            .bind(&request.name)
+            .fetch_optional(&*self.db)
+            .await?;
+        if existing.is_some() {
+            return Err(ServiceError::Conflict(format!(
+                "Scope '{}' already exists",
+                request.name
+            )));
+        }
+
+        let id = insert_scope_row(&*self.db, &request).await?;
+
+        self.find_scope_by_id(&id)
+            .await?
+            .ok_or_else(|| ServiceError::Internal("Failed to retrieve created scope".to_string()))
+    }
+
+    async fn update_scope(
+        &self,
+        scope_id: &str,
+        request: UpdateScopeRequest,
+    ) -> Result<Scope, ServiceError> {
+        let existing = self.require_scope(scope_id).await?;
+
+        write_scope_attributes(
+            &*self.db,
+            scope_id,
+            request.description.as_deref(),
+            request.is_public.unwrap_or(existing.is_public),
+            request.is_oidc_scope.unwrap_or(existing.is_oidc_scope),
+            request.is_active.unwrap_or(existing.is_active),
+        )
+        .await?;
+
+        self.find_scope_by_id(scope_id)
+            .await?
+            .ok_or_else(|| ServiceError::Internal("Failed to retrieve updated scope".to_string()))
+    }
+
+    async fn delete_scope(&self, scope_id: &str) -> Result<(), ServiceError> {
+        let scope = self.require_scope(scope_id).await?;
+
+        let in_use = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM client_allowed_scopes WHERE scope = ?)",
+        )
+        .bind(&scope.name)
+        .fetch_one(&*self.db)
+        .await?;
+
+        if in_use {
+            return Err(ServiceError::Conflict(format!(
+                "Scope '{}' is still allowed for one or more clients",
+                scope.name
+            )));
+        }
+
+        // scope_permissions 和 scope_localizations 通过外键级联删除
+        sqlx::query("DELETE FROM scopes WHERE id = ?")
+            .bind(scope_id)
+            .execute(&*self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_scope_permissions(&self, scope_id: &str) -> Result<Vec<String>, ServiceError> {
+        self.require_scope(scope_id).await?;
+
+        let permissions: Vec<String> = sqlx::query_scalar(
+            "SELECT p.name FROM scope_permissions sp
+             JOIN permissions p ON p.id = sp.permission_id
+             WHERE sp.scope_id = ?
+             ORDER BY p.name",
+        )
+        .bind(scope_id)
+        .fetch_all(&*self.db)
+        .await?;
+
+        Ok(permissions)
+    }
+
+    async fn set_scope_permissions(
+        &self,
+        scope_id: &str,
+        permission_ids: Vec<String>,
+    ) -> Result<(), ServiceError> {
+        self.require_scope(scope_id).await?;
+
+        let mut tx = self.db.begin().await?;
+        write_scope_permissions(&mut tx, scope_id, permission_ids).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_scope_localizations(
+        &self,
+        scope_id: &str,
+    ) -> Result<Vec<ScopeLocalization>, ServiceError> {
+        self.require_scope(scope_id).await?;
+
+        let localizations = sqlx::query_as::<_, ScopeLocalization>(
+            "SELECT locale, display_name, description, risk_level, category
+             FROM scope_localizations WHERE scope_id = ? ORDER BY locale",
+        )
+        .bind(scope_id)
+        .fetch_all(&*self.db)
+        .await?;
+
+        Ok(localizations)
+    }
+
+    async fn set_scope_localizations(
+        &self,
+        scope_id: &str,
+        localizations: Vec<ScopeLocalization>,
+    ) -> Result<(), ServiceError> {
+        self.require_scope(scope_id).await?;
+
+        let mut locales = HashSet::new();
+        for localization in &localizations {
+            if localization.locale.trim().is_empty()
+                || localization.display_name.trim().is_empty()
+            {
+                return Err(ServiceError::ValidationError(
+                    "Locale and display_name are required".to_string(),
+                ));
+            }
+            if !RISK_LEVELS.contains(&localization.risk_level.as_str()) {
+                return Err(ServiceError::ValidationError(format!(
+                    "Invalid risk_level '{}', expected one of: low, medium, high",
+                    localization.risk_level
+                )));
+            }
+            if !locales.insert(localization.locale.to_lowercase()) {
+                return Err(ServiceError::ValidationError(format!(
+                    "Duplicate locale '{}'",
+                    localization.locale
+                )));
+            }
+        }
+
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query("DELETE FROM scope_localizations WHERE scope_id = ?")
+            .bind(scope_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let now = Utc::now();
+        for localization in localizations {
+            sqlx::query(
+                "INSERT INTO scope_localizations
+                     (scope_id, locale, display_name, description, risk_level, category, created_at, updated_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(scope_id)
+            .bind(&localization.locale)
+            .bind(&localization.display_name)
+            .bind(&localization.description)
+            .bind(&localization.risk_level)
+            .bind(&localization.category)
+            .bind(now)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_consent_scopes(
+        &self,
+        scope: &str,
+        locale: &str,
+    ) -> Result<Vec<ScopeConsentInfo>, ServiceError> {
+        let names = split_scope(scope);
+        let names_json = serde_json::to_string(&names)
+            .map_err(|e| ServiceError::Internal(format!("Failed to encode scopes: {e}")))?;
+
+        let rows: Vec<(String, String, String, String, String, Option<String>)> = sqlx::query_as(
+            "SELECT s.name, l.locale, l.display_name, l.description, l.risk_level, l.category
+             FROM scope_localizations l
+             JOIN scopes s ON s.id = l.scope_id
+             WHERE s.name IN (SELECT value FROM json_each(?))
+             ORDER BY l.locale",
+        )
+        .bind(names_json)
+        .fetch_all(&*self.db)
+        .await?;
+
+        let mut by_scope: HashMap<String, Vec<ScopeLocalization>> = HashMap::new();
+        for (name, locale, display_name, description, risk_level, category) in rows {
+            by_scope.entry(name).or_default().push(ScopeLocalization {
+                locale,
+                display_name,
+                description,
+                risk_level,
+                category,
+            });
+        }
+
+        let prefers_chinese = locale.to_lowercase().starts_with("zh");
+        let consent_scopes = names
+            .into_iter()
+            .map(|name| {
+                let localized = by_scope
+                    .get(name)
+                    .and_then(|candidates| select_localization(candidates, locale));
+                if let Some(l) = localized {
+                    return ScopeConsentInfo {
+                        name: name.to_string(),
+                        display_name: l.display_name.clone(),
+                        description: l.description.clone(),
+                        risk_level: l.risk_level.clone(),
+                        category: l.category.clone(),
+                    };
+                }
+
+                match get_scope_metadata(name) {
+                    Some(meta) => ScopeConsentInfo {
+                        name: name.to_string(),
+                        display_name: meta.display_name.clone(),
+                        description: if prefers_chinese {
+                            meta.description.clone()
+                        } else {
+                            meta.description_en.clone()
+                        },
+                        risk_level: meta.risk_level.clone(),
+                        category: Some(meta.category.clone()),
+                    },
+                    None => ScopeConsentInfo {
+                        name: name.to_string(),
+                        display_name: name.to_string(),
+                        description: if prefers_chinese {
+                            format!("权限 {name}")
+                        } else {
+                            format!("Scope {name}")
+                        },
+                        // 未登记的权限范围按中等风险提示用户
+                        risk_level: "medium".to_string(),
+                        category: None,
+                    },
+                }
+            })
+            .collect();
+
+        Ok(consent_scopes)
+    }
+
+    async fn restrict_permissions_to_scope(
+        &self,
+        scope: &str,
+        permissions: Vec<String>,
+    ) -> Result<Vec<String>, ServiceError> {
+        let names = split_scope(scope);
+        let names_json = serde_json::to_string(&names)
+            .map_err(|e| ServiceError::Internal(format!("Failed to encode scopes: {e}")))?;
+
+        let allowed: HashSet<String> = sqlx::query_scalar::<_, String>(
+            "SELECT DISTINCT p.name FROM scope_permissions sp
+             JOIN scopes s ON s.id = sp.scope_id
+             JOIN permissions p ON p.id = sp.permission_id
+             WHERE s.is_active = 1 AND s.name IN (SELECT value FROM json_each(?))",
+        )
+        .bind(names_json)
+        .fetch_all(&*self.db)
+        .await?
+        .into_iter()
+        .collect();
+
+        Ok(permissions
+            .into_iter()
+            .filter(|permission| allowed.contains(permission))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn setup_test_db() -> SqlitePool {
+        let pool = SqlitePool::connect(":memory:")
+            .await
+            .expect("Failed to create in-memory database");
+
+        for ddl in [
+            r#"
+            CREATE TABLE scopes (
+                id TEXT PRIMARY KEY,
+                name TEXT UNIQUE NOT NULL,
+                description TEXT,
+                is_public INTEGER DEFAULT 0,
+                is_oidc_scope INTEGER DEFAULT 0,
+                is_active INTEGER DEFAULT 1,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+            r#"
+            CREATE TABLE permissions (
+                id TEXT PRIMARY KEY,
+                name TEXT UNIQUE NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE scope_permissions (
+                scope_id TEXT NOT NULL,
+                permission_id TEXT NOT NULL,
+                assigned_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (scope_id, permission_id)
+            )
+            "#,
+            r#"
+            CREATE TABLE scope_localizations (
+                scope_id TEXT NOT NULL,
+                locale TEXT NOT NULL,
+                display_name TEXT NOT NULL,
+                description TEXT NOT NULL,
+                risk_level TEXT NOT NULL DEFAULT 'low',
+                category TEXT,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (scope_id, locale)
+            )
+            "#,
+            r#"
+            CREATE TABLE client_allowed_scopes (
+                client_id TEXT NOT NULL,
+                scope TEXT NOT NULL,
+                PRIMARY KEY (client_id, scope)
+            )
+            "#,
+        ] {
+            sqlx::query(ddl)
+                .execute(&pool)
+                .await
+                .expect("Failed to create test table");
+        }
+
+        pool
+    }
+
+    async fn create_permission(pool: &SqlitePool, name: &str) -> String {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO permissions (id, name) VALUES (?, ?)")
+            .bind(&id)
+            .bind(name)
+            .execute(pool)
+            .await
+            .expect("Failed to create permission");
+        id
+    }
+
+    fn scope_request(name: &str) -> CreateScopeRequest {
+        CreateScopeRequest {
+            name: name.to_string(),
+            description: None,
+            is_public: None,
+            is_oidc_scope: None,
+            is_active: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restrict_permissions_to_scope() {
+        let db = Arc::new(setup_test_db().await);
+        let service = ScopeServiceImpl::new(db.clone());
+
+        let read_id = create_permission(&db, "users:read").await;
+        let list_id = create_permission(&db, "users:list").await;
+        let delete_id = create_permission(&db, "users:delete").await;
+
+        let read_users = service.create_scope(scope_request("read:users")).await.unwrap();
+        service
+            .set_scope_permissions(&read_users.id, vec![read_id, list_id])
+            .await
+            .unwrap();
+        let write_users = service.create_scope(scope_request("write:users")).await.unwrap();
+        service
+            .set_scope_permissions(&write_users.id, vec![delete_id])
+            .await
+            .unwrap();
+
+        let user_permissions = vec![
+            "users:read".to_string(),
+            "users:list".to_string(),
+            "users:delete".to_string(),
+        ];
+
+        let restricted = service
+            .restrict_permissions_to_scope("openid read:users", user_permissions.clone())
+            .await
+            .unwrap();
+        assert_eq!(restricted, vec!["users:read", "users:list"]);
+
+        // 未激活的权限范围不授予任何权限
+        service
+            .update_scope(
+                &read_users.id,
+                UpdateScopeRequest {
+                    is_active: Some(false),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        let restricted = service
+            .restrict_permissions_to_scope("read:users", user_permissions)
+            .await
+            .unwrap();
+        assert!(restricted.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_consent_scopes_locale_fallback() {
+        let db = Arc::new(setup_test_db().await);
+        let service = ScopeServiceImpl::new(db.clone());
+
+        let reports = service.create_scope(scope_request("read:reports")).await.unwrap();
+        service
+            .set_scope_localizations(
+                &reports.id,
+                vec![
+                    ScopeLocalization {
+                        locale: "zh-CN".to_string(),
+                        display_name: "读取报表".to_string(),
+                        description: "查看业务报表".to_string(),
+                        risk_level: "medium".to_string(),
+                        category: Some("Access".to_string()),
+                    },
+                    ScopeLocalization {
+                        locale: "en-US".to_string(),
+                        display_name: "Read reports".to_string(),
+                        description: "View business reports".to_string(),
+                        risk_level: "medium".to_string(),
+                        category: Some("Access".to_string()),
+                    },
+                ],
+            )
+            .await
+            .unwrap();
+
+        let scopes = service
+            .get_consent_scopes("read:reports openid custom", "en")
+            .await
+            .unwrap();
+        assert_eq!(scopes.len(), 3);
+        assert_eq!(scopes[0].description, "View business reports");
+        assert_eq!(scopes[0].risk_level, "medium");
+        // 数据库中没有文案时使用内置元数据
+        assert_eq!(scopes[1].description, "OpenID Connect authentication");
+        assert_eq!(scopes[2].description, "Scope custom");
+
+        let scopes = service.get_consent_scopes("read:reports", "fr").await.unwrap();
+        assert_eq!(scopes[0].description, "查看业务报表");
+    }
+
+    #[tokio::test]
+    async fn test_scope_validation_and_conflicts() {
+        let db = Arc::new(setup_test_db().await);
+        let service = ScopeServiceImpl::new(db.clone());
+
+        let scope = service.create_scope(scope_request("read:audit")).await.unwrap();
+        assert!(matches!(
+            service.create_scope(scope_request("read:audit")).await,
+            Err(ServiceError::Conflict(_))
+        ));
+
+        let invalid = service
+            .set_scope_localizations(
+                &scope.id,
+                vec![ScopeLocalization {
+                    locale: "en".to_string(),
+                    display_name: "Read audit".to_string(),
+                    description: "View audit logs".to_string(),
+                    risk_level: "critical".to_string(),
+                    category: None,
+                }],
+            )
+            .await;
+        assert!(matches!(invalid, Err(ServiceError::ValidationError(_))));
+
+        sqlx::query("INSERT INTO client_allowed_scopes (client_id, scope) VALUES (?, ?)")
+            .bind("client-1")
+            .bind("read:audit")
+            .execute(&*db)
+            .await
+            .unwrap();
+        assert!(matches!(
+            service.delete_scope(&scope.id).await,
+            Err(ServiceError::Conflict(_))
+        ));
+    }
+}
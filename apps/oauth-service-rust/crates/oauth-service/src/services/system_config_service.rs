@@ -0,0 +1,540 @@
+use crate::config::{parse_origin, Config, RateLimit};
+use crate::error::ServiceError;
+use crate::models::system_config::SystemConfiguration;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// 运行时配置项的键
+pub mod keys {
+    pub const ACCESS_TOKEN_TTL: &str = "auth.token.access_ttl";
+    pub const REFRESH_TOKEN_TTL: &str = "auth.token.refresh_ttl";
+    pub const API_RATE_LIMIT_MAX_REQUESTS: &str = "rate_limit.api.max_requests";
+    pub const API_RATE_LIMIT_WINDOW_SECS: &str = "rate_limit.api.window_secs";
+    pub const TOKEN_RATE_LIMIT_MAX_REQUESTS: &str = "rate_limit.token.max_requests";
+    pub const TOKEN_RATE_LIMIT_WINDOW_SECS: &str = "rate_limit.token.window_secs";
+    pub const LOGIN_RATE_LIMIT_MAX_ATTEMPTS: &str = "rate_limit.login.max_attempts";
+    pub const LOGIN_RATE_LIMIT_WINDOW_SECS: &str = "rate_limit.login.window_secs";
+    pub const CORS_ALLOWED_ORIGINS: &str = "cors.allowed_origins";
+    pub const LOGIN_REDIRECT_ORIGINS: &str = "auth.login.redirect_origins";
+    pub const ADMIN_PORTAL_URL: &str = "system.admin_portal_url";
+    pub const ADMIN_PORTAL_PROXY_URL: &str = "system.admin_portal_proxy_url";
+}
+
+const CONFIG_COLUMNS: &str = "id, key, value, description, type, is_editable, is_sensitive, \
+                              category, created_at, updated_at";
+
+/// 从 `system_configurations` 表解析出的运行时配置快照
+///
+/// 修改配置后会发布新的快照，各组件读取最新快照即可在不重启的情况下生效。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuntimeSettings {
+    /// 新建客户端默认的访问令牌有效期（秒）
+    pub access_token_ttl: i64,
+    /// 新建客户端默认的刷新令牌有效期（秒）
+    pub refresh_token_ttl: i64,
+    /// 全局 API 限流 (按 IP)
+    pub api_rate_limit: RateLimit,
+    /// 令牌端点限流 (按 IP)
+    pub token_rate_limit: RateLimit,
+    /// 登录端点限流 (按 IP)
+    pub login_rate_limit: RateLimit,
+    /// 允许跨域访问的源
+    pub cors_allowed_origins: Vec<String>,
+    /// 登录成功后允许重定向到的源
+    pub login_redirect_origins: Vec<String>,
+    /// Admin Portal 地址 (登录和同意页面所在的前端)
+    pub admin_portal_url: String,
+    /// 经 Pingora 代理访问 Admin Portal 的地址
+    pub admin_portal_proxy_url: String,
+}
+
+impl RuntimeSettings {
+    /// 由启动配置得到运行时配置的初始值，数据库中没有对应配置项时使用
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            access_token_ttl: config.tokens.access_token_ttl,
+            refresh_token_ttl: config.tokens.refresh_token_ttl,
+            api_rate_limit: config.rate_limits.api,
+            token_rate_limit: config.rate_limits.token,
+            login_rate_limit: config.rate_limits.login,
+            cors_allowed_origins: config.cors.allowed_origins.iter().filter_map(|o| parse_origin(o)).collect(),
+            login_redirect_origins: config.login.redirect_origins.iter().filter_map(|o| parse_origin(o)).collect(),
+            admin_portal_url: config.admin_portal.url.clone(),
+            admin_portal_proxy_url: config.admin_portal.proxy_url.clone(),
+        }
+    }
+}
+
+impl Default for RuntimeSettings {
+    fn default() -> Self {
+        Self::from_config(&Config::default())
+    }
+}
+
+impl RuntimeSettings {
+    /// 将一个配置项应用到快照上，未知的配置键会被忽略
+    pub fn apply(&mut self, key: &str, value: &Value) -> Result<(), ServiceError> {
+        match key {
+            keys::ACCESS_TOKEN_TTL => self.access_token_ttl = positive_integer(key, value)? as i64,
+            keys::REFRESH_TOKEN_TTL => self.refresh_token_ttl = positive_integer(key, value)? as i64,
+            keys::API_RATE_LIMIT_MAX_REQUESTS => {
+                self.api_rate_limit.max_requests = positive_integer(key, value)? as usize
+            }
+            keys::API_RATE_LIMIT_WINDOW_SECS => {
+                self.api_rate_limit.window_secs = positive_integer(key, value)?
+            }
+            keys::TOKEN_RATE_LIMIT_MAX_REQUESTS => {
+                self.token_rate_limit.max_requests = positive_integer(key, value)? as usize
+            }
+            keys::TOKEN_RATE_LIMIT_WINDOW_SECS => {
+                self.token_rate_limit.window_secs = positive_integer(key, value)?
+            }
+            keys::LOGIN_RATE_LIMIT_MAX_ATTEMPTS => {
+                self.login_rate_limit.max_requests = positive_integer(key, value)? as usize
+            }
+            keys::LOGIN_RATE_LIMIT_WINDOW_SECS => {
+                self.login_rate_limit.window_secs = positive_integer(key, value)?
+            }
+            keys::CORS_ALLOWED_ORIGINS => self.cors_allowed_origins = origin_list(key, value)?,
+            keys::LOGIN_REDIRECT_ORIGINS => self.login_redirect_origins = origin_list(key, value)?,
+            keys::ADMIN_PORTAL_URL => self.admin_portal_url = base_url(key, value)?,
+            keys::ADMIN_PORTAL_PROXY_URL => self.admin_portal_proxy_url = base_url(key, value)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// 判断源是否在允许跨域访问的列表中
+    pub fn is_cors_origin_allowed(&self, origin: &str) -> bool {
+        self.cors_allowed_origins.iter().any(|allowed| allowed == origin)
+    }
+
+    /// 判断登录后的重定向地址是否允许
+    ///
+    /// 允许站内相对路径 (不允许 `//host` 形式)，绝对地址的源必须与配置完全一致。
+    pub fn is_login_redirect_allowed(&self, redirect: &str) -> bool {
+        if redirect.starts_with('/') {
+            return !redirect.starts_with("//") && !redirect.starts_with("/\\");
+        }
+
+        url::Url::parse(redirect)
+            .map(|url| {
+                self.login_redirect_origins
+                    .contains(&url.origin().ascii_serialization())
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// 需要写入数据库的运行时配置项定义
+pub struct ConfigDefinition {
+    pub key: &'static str,
+    pub value: Value,
+    pub value_type: &'static str,
+    pub category: &'static str,
+    pub description: &'static str,
+}
+
+/// 运行时配置项的初始定义，用于初始化 `system_configurations` 表
+pub fn default_configurations(defaults: &RuntimeSettings) -> Vec<ConfigDefinition> {
+    let definition = |key, value, value_type, category, description| ConfigDefinition {
+        key,
+        value,
+        value_type,
+        category,
+        description,
+    };
+
+    vec![
+        definition(keys::ACCESS_TOKEN_TTL, json!(defaults.access_token_ttl), "number", "auth", "Access token TTL (seconds)"),
+        definition(keys::REFRESH_TOKEN_TTL, json!(defaults.refresh_token_ttl), "number", "auth", "Refresh token TTL (seconds)"),
+        definition(keys::API_RATE_LIMIT_MAX_REQUESTS, json!(defaults.api_rate_limit.max_requests), "number", "rate_limit", "Max API requests per IP within the window"),
+        definition(keys::API_RATE_LIMIT_WINDOW_SECS, json!(defaults.api_rate_limit.window_secs), "number", "rate_limit", "API rate limit window (seconds)"),
+        definition(keys::TOKEN_RATE_LIMIT_MAX_REQUESTS, json!(defaults.token_rate_limit.max_requests), "number", "rate_limit", "Max token endpoint requests per IP within the window"),
+        definition(keys::TOKEN_RATE_LIMIT_WINDOW_SECS, json!(defaults.token_rate_limit.window_secs), "number", "rate_limit", "Token endpoint rate limit window (seconds)"),
+        definition(keys::LOGIN_RATE_LIMIT_MAX_ATTEMPTS, json!(defaults.login_rate_limit.max_requests), "number", "rate_limit", "Max login attempts per IP within the window"),
+        definition(keys::LOGIN_RATE_LIMIT_WINDOW_SECS, json!(defaults.login_rate_limit.window_secs), "number", "rate_limit", "Login rate limit window (seconds)"),
+        definition(keys::CORS_ALLOWED_ORIGINS, json!(defaults.cors_allowed_origins), "array", "cors", "Origins allowed to make cross-origin requests"),
+        definition(keys::LOGIN_REDIRECT_ORIGINS, json!(defaults.login_redirect_origins), "array", "auth", "Origins allowed as redirect target after login"),
+        definition(keys::ADMIN_PORTAL_URL, json!(defaults.admin_portal_url), "string", "system", "Admin Portal URL (login and consent pages)"),
+        definition(keys::ADMIN_PORTAL_PROXY_URL, json!(defaults.admin_portal_proxy_url), "string", "system", "Admin Portal URL through the Pingora proxy"),
+    ]
+}
+
+#[async_trait]
+pub trait SystemConfigService: Send + Sync {
+    /// 列出配置项，可按分类过滤
+    async fn list_configurations(
+        &self,
+        category: Option<&str>,
+    ) -> Result<Vec<SystemConfiguration>, ServiceError>;
+
+    /// 根据配置键查找配置项
+    async fn find_configuration(&self, key: &str)
+        -> Result<Option<SystemConfiguration>, ServiceError>;
+
+    /// 更新配置项的值并立即发布新的运行时配置
+    ///
+    /// 值必须符合配置项声明的类型，已知配置键还会校验取值范围；不可编辑的配置项拒绝修改。
+    async fn update_configuration(
+        &self,
+        key: &str,
+        value: Value,
+    ) -> Result<SystemConfiguration, ServiceError>;
+
+    /// 从数据库重新加载运行时配置 (例如直接修改了数据库之后)
+    async fn reload(&self) -> Result<Arc<RuntimeSettings>, ServiceError>;
+
+    /// 当前生效的运行时配置
+    fn current(&self) -> Arc<RuntimeSettings>;
+
+    /// 订阅运行时配置的变更
+    fn subscribe(&self) -> watch::Receiver<Arc<RuntimeSettings>>;
+}
+
+/// 启动后台任务，在运行时配置变更时调用 `apply`
+pub fn spawn_settings_watcher<F>(
+    system_config_service: Arc<dyn SystemConfigService>,
+    apply: F,
+) -> tokio::task::JoinHandle<()>
+where
+    F: Fn(&RuntimeSettings) + Send + 'static,
+{
+    let mut receiver = system_config_service.subscribe();
+    tokio::spawn(async move {
+        while receiver.changed().await.is_ok() {
+            let settings = receiver.borrow_and_update().clone();
+            apply(&settings);
+            tracing::info!("Applied updated runtime settings");
+        }
+    })
+}
+
+pub struct SystemConfigServiceImpl {
+    db: Arc<SqlitePool>,
+    defaults: RuntimeSettings,
+    settings: watch::Sender<Arc<RuntimeSettings>>,
+}
+
+impl SystemConfigServiceImpl {
+    /// 创建服务，`defaults` 用于数据库中缺少的配置项，在调用 `reload` 之前直接生效
+    pub fn new(db: Arc<SqlitePool>, defaults: RuntimeSettings) -> Self {
+        let (settings, _) = watch::channel(Arc::new(defaults.clone()));
+        Self {
+            db,
+            defaults,
+            settings,
+        }
+    }
+}
+
+#[async_trait]
+impl SystemConfigService for SystemConfigServiceImpl {
+    async fn list_configurations(
+        &self,
+        category: Option<&str>,
+    ) -> Result<Vec<SystemConfiguration>, ServiceError> {
+        let configurations = match category {
+            Some(category) => {
+                sqlx::query_as::<_, SystemConfiguration>(&format!(
+                    "SELECT {CONFIG_COLUMNS} FROM system_configurations WHERE category = ? ORDER BY key"
+                ))
+                .bind(category)
+                .fetch_all(&*self.db)
+                .await?
+            }
+            None => {
+                sqlx::query_as::<_, SystemConfiguration>(&format!(
+                    "SELECT {CONFIG_COLUMNS} FROM system_configurations ORDER BY category, key"
+                ))
+                .fetch_all(&*self.db)
+                .await?
+            }
+        };
+
+        Ok(configurations)
+    }
+
+    async fn find_configuration(
+        &self,
+        key: &str,
+    ) -> Result<Option<SystemConfiguration>, ServiceError> {
+        let configuration = sqlx::query_as::<_, SystemConfiguration>(&format!(
+            "SELECT {CONFIG_COLUMNS} FROM system_configurations WHERE key = ?"
+        ))
+        .bind(key)
+        .fetch_optional(&*self.db)
+        .await?;
+
+        Ok(configuration)
+    }
+
+    async fn update_configuration(
+        &self,
+        key: &str,
+        value: Value,
+    ) -> Result<SystemConfiguration, ServiceError> {
+        let existing = self
+            .find_configuration(key)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("Configuration '{key}' not found")))?;
+
+        if !existing.is_editable {
+            return Err(ServiceError::Forbidden(format!(
+                "Configuration '{key}' is not editable"
+            )));
+        }
+
+        validate_value_type(key, existing.value_type.as_deref().unwrap_or("string"), &value)?;
+
+        // 先在当前快照的副本上应用，校验已知配置键的取值
+        let mut candidate = (*self.current()).clone();
+        candidate.apply(key, &value)?;
+
+        sqlx::query("UPDATE system_configurations SET value = ?, updated_at = ? WHERE key = ?")
+            .bind(value.to_string())
+            .bind(Utc::now())
+            .bind(key)
+            .execute(&*self.db)
+            .await?;
+
+        self.reload().await?;
+
+        self.find_configuration(key)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("Configuration '{key}' not found")))
+    }
+
+    async fn reload(&self) -> Result<Arc<RuntimeSettings>, ServiceError> {
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT key, value FROM system_configurations")
+                .fetch_all(&*self.db)
+                .await?;
+
+        let mut settings = self.defaults.clone();
+        for (key, raw_value) in rows {
+            let value = serde_json::from_str(&raw_value).unwrap_or(Value::String(raw_value));
+            // 数据库中的无效值不应阻止服务启动，保留默认值并记录警告
+            if let Err(e) = settings.apply(&key, &value) {
+                tracing::warn!("Ignoring invalid configuration '{}': {}", key, e);
+            }
+        }
+
+        let settings = Arc::new(settings);
+        self.settings.send_replace(settings.clone());
+        Ok(settings)
+    }
+
+    fn current(&self) -> Arc<RuntimeSettings> {
+        self.settings.borrow().clone()
+    }
+
+    fn subscribe(&self) -> watch::Receiver<Arc<RuntimeSettings>> {
+        self.settings.subscribe()
+    }
+}
+
+/// 校验值是否符合配置项声明的类型
+fn validate_value_type(key: &str, value_type: &str, value: &Value) -> Result<(), ServiceError> {
+    let matches = match value_type {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        // 未声明或未知类型不做限制
+        _ => true,
+    };
+
+    if matches {
+        Ok(())
+    } else {
+        Err(ServiceError::ValidationError(format!(
+            "Configuration '{key}' expects a value of type {value_type}"
+        )))
+    }
+}
+
+fn positive_integer(key: &str, value: &Value) -> Result<u64, ServiceError> {
+    value
+        .as_u64()
+        .filter(|v| *v > 0 && *v <= i64::MAX as u64)
+        .ok_or_else(|| {
+            ServiceError::ValidationError(format!(
+                "Configuration '{key}' must be a positive integer"
+            ))
+        })
+}
+
+/// 解析源列表，每一项必须是不带路径的 http(s) 源 (如 "https://admin.example.com")
+fn origin_list(key: &str, value: &Value) -> Result<Vec<String>, ServiceError> {
+    let invalid = |entry: &Value| {
+        ServiceError::ValidationError(format!(
+            "Configuration '{key}' must be an array of origins, got {entry}"
+        ))
+    };
+
+    value
+        .as_array()
+        .ok_or_else(|| invalid(value))?
+        .iter()
+        .map(|entry| {
+            entry
+                .as_str()
+                .and_then(parse_origin)
+                .ok_or_else(|| invalid(entry))
+        })
+        .collect()
+}
+
+/// 解析 http(s) 基础地址，去掉末尾的斜杠以便拼接路径
+fn base_url(key: &str, value: &Value) -> Result<String, ServiceError> {
+    value
+        .as_str()
+        .filter(|url| {
+            url::Url::parse(url)
+                .map(|parsed| matches!(parsed.scheme(), "http" | "https"))
+                .unwrap_or(false)
+        })
+        .map(|url| url.trim_end_matches('/').to_string())
+        .ok_or_else(|| {
+            ServiceError::ValidationError(format!(
+                "Configuration '{key}' must be an http(s) URL"
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn setup_test_db() -> SqlitePool {
+        let pool = SqlitePool::connect(":memory:")
+            .await
+            .expect("Failed to create in-memory database");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE system_configurations (
+                id TEXT PRIMARY KEY,
+                key TEXT UNIQUE NOT NULL,
+                value TEXT NOT NULL,
+                description TEXT,
+                type TEXT DEFAULT 'string',
+                is_editable INTEGER DEFAULT 1,
+                is_sensitive INTEGER DEFAULT 0,
+                category TEXT,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        for (id, key, value, value_type, category, is_editable, is_sensitive) in [
+            ("cfg1", keys::ACCESS_TOKEN_TTL, "1800", "number", "auth", true, false),
+            ("cfg2", keys::API_RATE_LIMIT_MAX_REQUESTS, "100", "number", "rate_limit", true, false),
+            ("cfg3", keys::LOGIN_REDIRECT_ORIGINS, r#"["https://portal.example.com"]"#, "array", "auth", true, false),
+            ("cfg4", "system.version", r#""1.0.0""#, "string", "general", false, false),
+            ("cfg5", "smtp.password", r#""secret""#, "string", "mail", true, true),
+        ] {
+            sqlx::query(
+                "INSERT INTO system_configurations (id, key, value, type, category, is_editable, is_sensitive)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(id)
+            .bind(key)
+            .bind(value)
+            .bind(value_type)
+            .bind(category)
+            .bind(is_editable)
+            .bind(is_sensitive)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_reload_applies_stored_values() {
+        let service = SystemConfigServiceImpl::new(Arc::new(setup_test_db().await), RuntimeSettings::default());
+        assert_eq!(service.current().access_token_ttl, 3600);
+
+        let settings = service.reload().await.unwrap();
+        assert_eq!(settings.access_token_ttl, 1800);
+        assert_eq!(settings.login_redirect_origins, vec!["https://portal.example.com"]);
+        // 数据库中没有的配置项保留默认值
+        assert_eq!(settings.token_rate_limit.max_requests, 20);
+
+        assert!(settings.is_login_redirect_allowed("https://portal.example.com/admin"));
+        assert!(settings.is_login_redirect_allowed("/oauth/consent"));
+        assert!(!settings.is_login_redirect_allowed("//evil.example.com"));
+        assert!(!settings.is_login_redirect_allowed("https://portal.example.com.evil.com/"));
+
+        let sensitive = service.find_configuration("smtp.password").await.unwrap().unwrap();
+        assert_eq!(sensitive.display_value(), json!("******"));
+    }
+
+    #[tokio::test]
+    async fn test_update_publishes_new_settings() {
+        let service = SystemConfigServiceImpl::new(Arc::new(setup_test_db().await), RuntimeSettings::default());
+        service.reload().await.unwrap();
+        let mut receiver = service.subscribe();
+
+        let updated = service
+            .update_configuration(keys::API_RATE_LIMIT_MAX_REQUESTS, json!(250))
+            .await
+            .unwrap();
+        assert_eq!(updated.parsed_value(), json!(250));
+
+        assert!(receiver.has_changed().unwrap());
+        assert_eq!(receiver.borrow_and_update().api_rate_limit.max_requests, 250);
+        assert_eq!(service.current().api_rate_limit.max_requests, 250);
+    }
+
+    #[tokio::test]
+    async fn test_update_rejects_invalid_values() {
+        let service = SystemConfigServiceImpl::new(Arc::new(setup_test_db().await), RuntimeSettings::default());
+        service.reload().await.unwrap();
+
+        // 类型不匹配
+        let result = service
+            .update_configuration(keys::ACCESS_TOKEN_TTL, json!("1800"))
+            .await;
+        assert!(matches!(result, Err(ServiceError::ValidationError(_))));
+
+        // 取值超出范围
+        let result = service
+            .update_configuration(keys::API_RATE_LIMIT_MAX_REQUESTS, json!(0))
+            .await;
+        assert!(matches!(result, Err(ServiceError::ValidationError(_))));
+
+        // 带路径的地址不是合法的源
+        let result = service
+            .update_configuration(keys::LOGIN_REDIRECT_ORIGINS, json!(["https://a.example.com/path"]))
+            .await;
+        assert!(matches!(result, Err(ServiceError::ValidationError(_))));
+
+        // 不可编辑
+        let result = service
+            .update_configuration("system.version", json!("2.0.0"))
+            .await;
+        assert!(matches!(result, Err(ServiceError::Forbidden(_))));
+
+        let result = service.update_configuration("missing.key", json!(1)).await;
+        assert!(matches!(result, Err(ServiceError::NotFound(_))));
+
+        // 失败的更新不会改变当前配置
+        assert_eq!(service.current().access_token_ttl, 1800);
+        assert_eq!(service.current().api_rate_limit.max_requests, 100);
+    }
+}
@@ -0,0 +1,574 @@
+// 租户服务 (Tenant Service)
+//
+// 隔离规则:
+// - 平台级请求方 (不属于任何租户) 可以访问所有租户的资源
+// - 租户内的请求方只能访问本租户的资源，全局角色对其只读可见
+// - 租户内的用户只能使用本租户或平台级的客户端，反之平台级用户不能使用租户的客户端
+
+use crate::error::ServiceError;
+use crate::models::tenant::Tenant;
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+/// 租户 ID 的最大长度 (与 DNS 标签一致)
+pub const MAX_TENANT_ID_LENGTH: usize = 63;
+
+#[async_trait]
+pub trait TenantService: Send + Sync {
+    /// 创建租户，ID 创建后不可修改
+    async fn create_tenant(&self, id: String, name: String) -> Result<Tenant, ServiceError>;
+    async fn find_tenant(&self, id: &str) -> Result<Option<Tenant>, ServiceError>;
+    async fn list_tenants(&self) -> Result<Vec<Tenant>, ServiceError>;
+    /// 重命名、启用或停用租户
+    async fn update_tenant(
+        &self,
+        id: &str,
+        name: Option<String>,
+        is_active: Option<bool>,
+    ) -> Result<Tenant, ServiceError>;
+    /// 删除租户，仍有用户、客户端或角色属于该租户时拒绝删除
+    async fn delete_tenant(&self, id: &str) -> Result<(), ServiceError>;
+    /// 检查租户是否存在且已激活，`None` (平台级) 始终通过
+    async fn ensure_active(&self, tenant_id: Option<&str>) -> Result<(), ServiceError>;
+}
+
+/// 请求方是否可以访问属于 `resource_tenant` 的资源
+pub fn can_access(caller_tenant: Option<&str>, resource_tenant: Option<&str>) -> bool {
+    caller_tenant.is_none() || caller_tenant == resource_tenant
+}
+
+/// 用户是否可以通过该客户端登录或获取令牌
+pub fn can_use_client(client_tenant: Option<&str>, user_tenant: Option<&str>) -> bool {
+    client_tenant.is_none() || client_tenant == user_tenant
+}
+
+/// 角色能否分配给该用户或被该角色继承: 全局角色对所有人可用，租户角色仅限同一租户
+pub fn can_use_role(role_tenant: Option<&str>, subject_tenant: Option<&str>) -> bool {
+    role_tenant.is_none() || role_tenant == subject_tenant
+}
+
+/// 校验租户 ID: 小写字母、数字和连字符，不以连字符开头或结尾
+pub fn validate_tenant_id(id: &str) -> Result<(), ServiceError> {
+    let valid = !id.is_empty()
+        && id.len() <= MAX_TENANT_ID_LENGTH
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !id.starts_with('-')
+        && !id.ends_with('-');
+    if valid {
+        Ok(())
+    } else {
+        Err(ServiceError::ValidationError(format!(
+            "Invalid tenant id '{id}': use 1-{MAX_TENANT_ID_LENGTH} lowercase letters, digits or hyphens"
+        )))
+    }
+}
+
+/// 确认租户存在，供其他服务在创建属于租户的资源前调用
+pub(crate) async fn ensure_tenant_exists(db: &SqlitePool, tenant_id: &str) -> Result<(), ServiceError> {
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM tenants WHERE id = ?)")
+        .bind(tenant_id)
+        .fetch_one(db)
+        .await?;
+    if exists {
+        Ok(())
+    } else {
+        Err(ServiceError::NotFound(format!("Tenant '{tenant_id}' not found")))
+    }
+}
+
+/// 确认租户存在且已激活，`None` (平台级) 始终通过
+pub(crate) async fn ensure_tenant_active(
+    db: &SqlitePool,
+    tenant_id: Option<&str>,
+) -> Result<(), ServiceError> {
+    let Some(tenant_id) = tenant_id else {
+        return Ok(());
+    };
+    let is_active: Option<bool> = sqlx::query_scalar("SELECT is_active FROM tenants WHERE id = ?")
+        .bind(tenant_id)
+        .fetch_optional(db)
+        .await?;
+    match is_active {
+        Some(true) => Ok(()),
+        _ => Err(ServiceError::Unauthorized(format!("Tenant '{tenant_id}' is disabled"))),
+    }
+}
+
+pub struct TenantServiceImpl {
+    db: Arc<SqlitePool>,
+}
+
+impl TenantServiceImpl {
+    pub fn new(db: Arc<SqlitePool>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl TenantService for TenantServiceImpl {
+    async fn create_tenant(&self, id: String, name: String) -> Result<Tenant, ServiceError> {
+        validate_tenant_id(&id)?;
+        if name.trim().is_empty() {
+            return Err(ServiceError::ValidationError("Tenant name is required".to_string()));
+        }
+        if self.find_tenant(&id).await?.is_some() {
+            return Err(ServiceError::Conflict(format!("Tenant '{id}' already exists")));
+        }
+
+        let now = Utc::now();
+        sqlx::query(
+            "INSERT INTO tenants (id, name, is_active, created_at, updated_at) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&id)
+        .bind(name.trim())
+        .bind(true)
+        .bind(now)
+        .bind(now)
+        .execute(&*self.db)
+        .await?;
+
+        self.find_tenant(&id)
+            .await?
+            .ok_or_else(|| ServiceError::Internal("Failed to retrieve created tenant".to_string()))
+    }
+
+    async fn find_tenant(&self, id: &str) -> Result<Option<Tenant>, ServiceError> {
+        let tenant = sqlx::query_as::<_, Tenant>(
+            "SELECT id, name, is_active, created_at, updated_at FROM tenants WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&*self.db)
+        .await?;
+        Ok(tenant)
+    }
+
+    async fn list_tenants(&self) -> Result<Vec<Tenant>, ServiceError> {
+        let tenants = sqlx::query_as::<_, Tenant>(
+            "SELECT id, name, is_active, created_at, updated_at FROM tenants ORDER BY id",
+        )
+        .fetch_all(&*self.db)
+        .await?;
+        Ok(tenants)
+    }
+
+    async fn update_tenant(
+        &self,
+        id: &str,
+        name: Option<String>,
+        is_active: Option<bool>,
+    ) -> Result<Tenant, ServiceError> {
+        let existing = self
+            .find_tenant(id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("Tenant '{id}' not found")))?;
+        let name = match name {
+            Some(name) if name.trim().is_empty() => {
+                return Err(ServiceError::ValidationError("Tenant name is required".to_string()))
+            }
+            Some(name) => name.trim().to_string(),
+            None => existing.name,
+        };
+
+        sqlx::query("UPDATE tenants SET name = ?, is_active = ?, updated_at = ? WHERE id = ?")
+            .bind(&name)
+            .bind(is_active.unwrap_or(existing.is_active))
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&*self.db)
+            .await?;
+
+        self.find_tenant(id)
+            .await?
+            .ok_or_else(|| ServiceError::Internal("Failed to retrieve updated tenant".to_string()))
+    }
+
+    async fn delete_tenant(&self, id: &str) -> Result<(), ServiceError> {
+        if self.find_tenant(id).await?.is_none() {
+            return Err(ServiceError::NotFound(format!("Tenant '{id}' not found")));
+        }
+
+        for table in ["users", "oauth_clients", "roles"] {
+            let count: i64 =
+                sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {table} WHERE tenant_id = ?"))
+                    .bind(id)
+                    .fetch_one(&*self.db)
+                    .await?;
+            if count > 0 {
+                return Err(ServiceError::Conflict(format!(
+                    "Tenant '{id}' still owns {count} record(s) in {table}"
+                )));
+            }
+        }
+
+        sqlx::query("DELETE FROM tenants WHERE id = ?")
+            .bind(id)
+            .execute(&*self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn ensure_active(&self, tenant_id: Option<&str>) -> Result<(), ServiceError> {
+        ensure_tenant_active(&self.db, tenant_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::routes::clients::CreateClientRequest;
+    use crate::state::AppState;
+    use crate::state::test_support::{
+        client_request, create_client, setup_state_with_config, TestAppState,
+    };
+    use crate::utils::jwt;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    async fn setup_state() -> (TestAppState, Arc<SqlitePool>) {
+        setup_state_with_config(Config {
+            issuer: "https://auth.example.com/".to_string(),
+            ..Config::default()
+        })
+        .await
+    }
+
+    /// 创建 acme 和 globex 两个租户，各有一个用户
+    async fn setup_tenants(state: &AppState) -> (String, String) {
+        for (id, name) in [("acme", "Acme Corp"), ("globex", "Globex")] {
+            state
+                .tenant_service
+                .create_tenant(id.to_string(), name.to_string())
+                .await
+                .unwrap();
+        }
+        let alice = state
+            .user_service
+            .create_user_in_tenant("alice".to_string(), "password123".to_string(), None, "acme")
+            .await
+            .unwrap();
+        let bob = state
+            .user_service
+            .create_user_in_tenant("bob".to_string(), "password123".to_string(), None, "globex")
+            .await
+            .unwrap();
+        (alice.id, bob.id)
+    }
+
+    #[test]
+    fn test_validate_tenant_id() {
+        assert!(validate_tenant_id("acme").is_ok());
+        assert!(validate_tenant_id("business-unit-42").is_ok());
+        assert!(validate_tenant_id("").is_err());
+        assert!(validate_tenant_id("Acme").is_err());
+        assert!(validate_tenant_id("-acme").is_err());
+        assert!(validate_tenant_id("acme-").is_err());
+        assert!(validate_tenant_id("ac/me").is_err());
+        assert!(validate_tenant_id(&"a".repeat(MAX_TENANT_ID_LENGTH + 1)).is_err());
+
+        assert!(can_access(None, Some("acme")));
+        assert!(can_access(Some("acme"), Some("acme")));
+        assert!(!can_access(Some("acme"), Some("globex")));
+        assert!(!can_access(Some("acme"), None));
+        assert!(can_use_client(None, Some("acme")));
+        assert!(!can_use_client(Some("acme"), None));
+    }
+
+    #[tokio::test]
+    async fn test_tenant_lifecycle() {
+        let (state, _pool) = setup_state().await;
+        let service = &state.tenant_service;
+
+        let tenant = service
+            .create_tenant("acme".to_string(), "Acme Corp".to_string())
+            .await
+            .unwrap();
+        assert!(tenant.is_active);
+        let result = service.create_tenant("acme".to_string(), "Again".to_string()).await;
+        assert!(matches!(result.unwrap_err(), ServiceError::Conflict(_)));
+        let result = service.create_tenant("Bad Id".to_string(), "Bad".to_string()).await;
+        assert!(matches!(result.unwrap_err(), ServiceError::ValidationError(_)));
+
+        let tenant = service
+            .update_tenant("acme", Some("Acme Inc".to_string()), Some(false))
+            .await
+            .unwrap();
+        assert_eq!(tenant.name, "Acme Inc");
+        assert!(!tenant.is_active);
+        assert!(service.ensure_active(Some("acme")).await.is_err());
+        assert!(service.ensure_active(Some("missing")).await.is_err());
+        assert!(service.ensure_active(None).await.is_ok());
+
+        // 仍有用户的租户不能删除 (停用的用户同样保留租户归属)
+        state
+            .user_service
+            .create_user_in_tenant("alice".to_string(), "password123".to_string(), None, "acme")
+            .await
+            .unwrap();
+        let result = service.delete_tenant("acme").await;
+        assert!(matches!(result.unwrap_err(), ServiceError::Conflict(_)));
+
+        service
+            .create_tenant("initech".to_string(), "Initech".to_string())
+            .await
+            .unwrap();
+        service.delete_tenant("initech").await.unwrap();
+        let tenants = service.list_tenants().await.unwrap();
+        assert_eq!(tenants.len(), 1);
+        assert_eq!(tenants[0].id, "acme");
+
+        let result = state
+            .user_service
+            .create_user_in_tenant("bob".to_string(), "password123".to_string(), None, "initech")
+            .await;
+        assert!(matches!(result.unwrap_err(), ServiceError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_tokens_carry_tenant_and_stay_isolated() {
+        let (state, _pool) = setup_state().await;
+        let (alice, _bob) = setup_tenants(&state).await;
+        let acme_client = create_client(
+            &state,
+            CreateClientRequest {
+                tenant_id: Some("acme".to_string()),
+                ..client_request("acme client")
+            },
+        )
+        .await
+        .0;
+        let globex_client = create_client(
+            &state,
+            CreateClientRequest {
+                tenant_id: Some("globex".to_string()),
+                ..client_request("globex client")
+            },
+        )
+        .await
+        .0;
+        let platform_client = create_client(&state, client_request("platform client")).await.0;
+
+        let tokens = state
+            .token_service
+            .issue_tokens(&acme_client, Some(alice.clone()), "openid".to_string(), vec![], None, None, None)
+            .await
+            .unwrap();
+        let decoding_key = state.config.load_decoding_key().unwrap();
+        let claims = jwt::verify_token(&tokens.access_token, &decoding_key).unwrap();
+        assert_eq!(claims.tenant.as_deref(), Some("acme"));
+
+        let mut validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::HS256);
+        validation.set_audience(&[acme_client.client.client_id.as_str()]);
+        let id_token = jsonwebtoken::decode::<jwt::IdTokenClaims>(
+            tokens.id_token.as_deref().unwrap(),
+            &decoding_key,
+            &validation,
+        )
+        .unwrap()
+        .claims;
+        assert_eq!(id_token.iss, "https://auth.example.com/tenants/acme");
+        assert_eq!(id_token.tenant.as_deref(), Some("acme"));
+
+        // 租户用户可以使用平台级客户端，令牌仍属于用户的租户
+        let tokens = state
+            .token_service
+            .issue_tokens(&platform_client, Some(alice.clone()), "openid".to_string(), vec![], None, None, None)
+            .await
+            .unwrap();
+        let claims = jwt::verify_token(&tokens.access_token, &decoding_key).unwrap();
+        assert_eq!(claims.tenant.as_deref(), Some("acme"));
+
+        // 其他租户的客户端不可用
+        let result = state
+            .token_service
+            .issue_tokens(&globex_client, Some(alice.clone()), "openid".to_string(), vec![], None, None, None)
+            .await;
+        assert!(matches!(result.unwrap_err(), ServiceError::Unauthorized(_)));
+
+        // 客户端凭证令牌属于客户端的租户
+        let tokens = state
+            .token_service
+            .issue_tokens(&globex_client, None, "".to_string(), vec![], None, None, None)
+            .await
+            .unwrap();
+        let claims = jwt::verify_token(&tokens.access_token, &decoding_key).unwrap();
+        assert_eq!(claims.tenant.as_deref(), Some("globex"));
+
+        // 停用的租户不再签发令牌
+        state
+            .tenant_service
+            .update_tenant("acme", None, Some(false))
+            .await
+            .unwrap();
+        let result = state
+            .token_service
+            .issue_tokens(&acme_client, Some(alice), "openid".to_string(), vec![], None, None, None)
+            .await;
+        assert!(matches!(result.unwrap_err(), ServiceError::Unauthorized(_)));
+    }
+
+    #[tokio::test]
+    async fn test_tenant_admin_is_confined_to_tenant() {
+        let (state, pool) = setup_state().await;
+        let (alice, bob) = setup_tenants(&state).await;
+        let acme_client = create_client(
+            &state,
+            CreateClientRequest {
+                tenant_id: Some("acme".to_string()),
+                ..client_request("acme client")
+            },
+        )
+        .await
+        .0;
+        let platform_client = create_client(&state, client_request("platform client")).await.0;
+        let root = state
+            .user_service
+            .create_user("root".to_string(), "password123".to_string(), None)
+            .await
+            .unwrap();
+
+        let permissions: Vec<String> = ["users:read", "roles:read", "roles:update", "tenants:read"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let tenant_token = state
+            .token_service
+            .issue_tokens(&acme_client, Some(alice.clone()), "".to_string(), permissions.clone(), None, None, None)
+            .await
+            .unwrap()
+            .access_token;
+        let platform_token = state
+            .token_service
+            .issue_tokens(&platform_client, Some(root.id), "".to_string(), permissions, None, None, None)
+            .await
+            .unwrap()
+            .access_token;
+
+        let global_role = state
+            .role_service
+            .create_role("viewer".to_string(), None)
+            .await
+            .unwrap();
+        let globex_role = state
+            .role_service
+            .create_role_in_tenant("globex-admin".to_string(), None, "globex")
+            .await
+            .unwrap();
+
+        let app = crate::app::create_app(pool, state.config.clone()).await;
+        let call = |method: &str, uri: String, token: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("authorization", format!("Bearer {token}"))
+                .header("content-type", "application/json")
+                .body(Body::from("{}"))
+                .unwrap()
+        };
+        let json = |response: axum::response::Response| async move {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        // 租户管理员只能看到本租户的用户，即使显式指定其他租户
+        let response = app
+            .clone()
+            .oneshot(call("GET", "/api/v2/admin/users?tenant_id=globex".to_string(), &tenant_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let users = json(response).await;
+        assert_eq!(users.as_array().unwrap().len(), 1);
+        assert_eq!(users[0]["id"], alice.as_str());
+
+        let response = app
+            .clone()
+            .oneshot(call("GET", format!("/api/v2/admin/users/{bob}"), &tenant_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = app
+            .clone()
+            .oneshot(call("GET", format!("/api/v2/admin/roles/{}", globex_role.id), &tenant_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // 全局角色对租户管理员只读
+        let response = app
+            .clone()
+            .oneshot(call("GET", format!("/api/v2/admin/roles/{}", global_role.id), &tenant_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app
+            .clone()
+            .oneshot(call("PUT", format!("/api/v2/admin/roles/{}", global_role.id), &tenant_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // 平台级端点对租户管理员关闭，即使令牌中有对应权限
+        let response = app
+            .clone()
+            .oneshot(call("GET", "/api/v2/admin/tenants".to_string(), &tenant_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // 平台管理员可以按租户过滤
+        let response = app
+            .clone()
+            .oneshot(call("GET", "/api/v2/admin/users?tenant_id=globex".to_string(), &platform_token))
+            .await
+            .unwrap();
+        let users = json(response).await;
+        assert_eq!(users.as_array().unwrap().len(), 1);
+        assert_eq!(users[0]["id"], bob.as_str());
+        let response = app
+            .clone()
+            .oneshot(call("GET", "/api/v2/admin/tenants".to_string(), &platform_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // 租户的发现文档公开可访问
+        let response = app
+            .clone()
+            .oneshot(
+                Request::get("/tenants/acme/.well-known/openid-configuration")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(json(response).await["issuer"], "https://auth.example.com/tenants/acme");
+
+        // 租户停用后，已签发的令牌立即失效
+        state
+            .tenant_service
+            .update_tenant("acme", None, Some(false))
+            .await
+            .unwrap();
+        let response = app
+            .clone()
+            .oneshot(call("GET", "/api/v2/admin/users".to_string(), &tenant_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app
+            .oneshot(
+                Request::get("/tenants/acme/.well-known/openid-configuration")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,463 @@
+// 全局吊销服务 (Token Revocation Service)
+//
+// 设备丢失或客户端密钥泄露时，一次性吊销某个用户或客户端的全部令牌:
+// - 吊销全部刷新令牌，并记录吊销水位线，此前签发的访问令牌在内省和认证中间件中立即失效
+//   (见 `TokenService::revoke_all_for_user` / `revoke_all_for_client`)
+// - 结束相关的模拟会话，作废尚未兑换的授权码
+// - 每次吊销写入审计日志
+
+use crate::error::ServiceError;
+use crate::services::audit_log_service::{AuditLogService, NewAuditLogEntry};
+use crate::services::client_service::ClientService;
+use crate::services::token_service::TokenService;
+use crate::services::user_service::UserService;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+/// 发起吊销的操作人，写入审计日志
+#[derive(Debug, Clone)]
+pub struct RevocationActor {
+    /// `user` / `client` / `system`
+    pub actor_type: String,
+    pub actor_id: String,
+}
+
+/// 一次全局吊销的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct RevocationSummary {
+    /// `user` / `client`
+    pub subject_type: String,
+    /// 用户 ID 或公开的 client_id
+    pub subject_id: String,
+    /// 在此时间之前签发的令牌均已失效
+    pub revoked_at: DateTime<Utc>,
+    pub refresh_tokens_revoked: u64,
+    pub sessions_ended: u64,
+    pub authorization_codes_invalidated: u64,
+}
+
+#[async_trait]
+pub trait TokenRevocationService: Send + Sync {
+    /// 吊销用户的全部令牌和会话 (包括以该用户身份或由该用户发起的模拟会话)
+    async fn revoke_user(
+        &self,
+        user_id: &str,
+        actor: &RevocationActor,
+        reason: Option<String>,
+    ) -> Result<RevocationSummary, ServiceError>;
+    /// 吊销客户端 (公开的 client_id) 签发的全部令牌和会话
+    async fn revoke_client(
+        &self,
+        client_id: &str,
+        actor: &RevocationActor,
+        reason: Option<String>,
+    ) -> Result<RevocationSummary, ServiceError>;
+}
+
+pub struct TokenRevocationServiceImpl {
+    db: Arc<SqlitePool>,
+    user_service: Arc<dyn UserService>,
+    client_service: Arc<dyn ClientService>,
+    token_service: Arc<dyn TokenService>,
+    audit_log_service: Arc<dyn AuditLogService>,
+}
+
+impl TokenRevocationServiceImpl {
+    pub fn new(
+        db: Arc<SqlitePool>,
+        user_service: Arc<dyn UserService>,
+        client_service: Arc<dyn ClientService>,
+        token_service: Arc<dyn TokenService>,
+        audit_log_service: Arc<dyn AuditLogService>,
+    ) -> Self {
+        Self {
+            db,
+            user_service,
+            client_service,
+            token_service,
+            audit_log_service,
+        }
+    }
+
+    async fn audit(
+        &self,
+        actor: &RevocationActor,
+        summary: &RevocationSummary,
+        reason: Option<&str>,
+        tenant_id: Option<String>,
+    ) {
+        let entry = NewAuditLogEntry {
+            user_id: (summary.subject_type == "user").then(|| summary.subject_id.clone()),
+            actor_type: actor.actor_type.clone(),
+            actor_id: actor.actor_id.clone(),
+            action: "tokens.revoke_all".to_string(),
+            resource_type: Some(summary.subject_type.clone()),
+            resource_id: Some(summary.subject_id.clone()),
+            details: Some(serde_json::json!({
+                "reason": reason,
+                "revoked_at": summary.revoked_at.to_rfc3339(),
+                "refresh_tokens_revoked": summary.refresh_tokens_revoked,
+                "sessions_ended": summary.sessions_ended,
+                "authorization_codes_invalidated": summary.authorization_codes_invalidated,
+            })),
+            status: "success".to_string(),
+            tenant_id,
+            ..Default::default()
+        };
+        if let Err(e) = self.audit_log_service.record(entry).await {
+            tracing::warn!("Failed to write token revocation audit log: {}", e);
+        }
+    }
+}
+
+/// 去掉首尾空白，空的理由视为未填写
+fn normalize_reason(reason: Option<String>) -> Option<String> {
+    reason
+        .map(|reason| reason.trim().to_string())
+        .filter(|reason| !reason.is_empty())
+}
+
+#[async_trait]
+impl TokenRevocationService for TokenRevocationServiceImpl {
+    async fn revoke_user(
+        &self,
+        user_id: &str,
+        actor: &RevocationActor,
+        reason: Option<String>,
+    ) -> Result<RevocationSummary, ServiceError> {
+        let user = self
+            .user_service
+            .find_by_id(user_id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("User not found".to_string()))?;
+        let reason = normalize_reason(reason);
+        let revoked_at = Utc::now();
+
+        let refresh_tokens_revoked = self.token_service.revoke_all_for_user(&user.id).await?;
+        let sessions_ended = sqlx::query(
+            "UPDATE impersonation_sessions SET ended_at = ?
+             WHERE (actor_user_id = ? OR target_user_id = ?) AND ended_at IS NULL AND expires_at > ?",
+        )
+        .bind(revoked_at)
+        .bind(&user.id)
+        .bind(&user.id)
+        .bind(revoked_at)
+        .execute(&*self.db)
+        .await?
+        .rows_affected();
+        let authorization_codes_invalidated = sqlx::query(
+            "UPDATE authorization_codes SET is_used = TRUE
+             WHERE user_id = ? AND is_used = 0 AND expires_at > ?",
+        )
+        .bind(&user.id)
+        .bind(revoked_at)
+        .execute(&*self.db)
+        .await?
+        .rows_affected();
+
+        let summary = RevocationSummary {
+            subject_type: "user".to_string(),
+            subject_id: user.id.clone(),
+            revoked_at,
+            refresh_tokens_revoked,
+            sessions_ended,
+            authorization_codes_invalidated,
+        };
+        self.audit(actor, &summary, reason.as_deref(), user.tenant_id)
+            .await;
+        Ok(summary)
+    }
+
+    async fn revoke_client(
+        &self,
+        client_id: &str,
+        actor: &RevocationActor,
+        reason: Option<String>,
+    ) -> Result<RevocationSummary, ServiceError> {
+        let client = self
+            .client_service
+            .find_by_client_id(client_id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Client not found".to_string()))?;
+        let reason = normalize_reason(reason);
+        let revoked_at = Utc::now();
+
+        let refresh_tokens_revoked = self.token_service.revoke_all_for_client(client_id).await?;
+        let sessions_ended = sqlx::query(
+            "UPDATE impersonation_sessions SET ended_at = ?
+             WHERE client_id = ? AND ended_at IS NULL AND expires_at > ?",
+        )
+        .bind(revoked_at)
+        .bind(client_id)
+        .bind(revoked_at)
+        .execute(&*self.db)
+        .await?
+        .rows_affected();
+        // 授权码关联的是客户端的内部 ID
+        let authorization_codes_invalidated = sqlx::query(
+            "UPDATE authorization_codes SET is_used = TRUE
+             WHERE client_id = ? AND is_used = 0 AND expires_at > ?",
+        )
+        .bind(&client.client.id)
+        .bind(revoked_at)
+        .execute(&*self.db)
+        .await?
+        .rows_affected();
+
+        let summary = RevocationSummary {
+            subject_type: "client".to_string(),
+            subject_id: client_id.to_string(),
+            revoked_at,
+            refresh_tokens_revoked,
+            sessions_ended,
+            authorization_codes_invalidated,
+        };
+        self.audit(actor, &summary, reason.as_deref(), client.client.tenant_id)
+            .await;
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::client::OAuthClientDetails;
+    use crate::services::token_service::TokenPair;
+    use crate::state::AppState;
+    use crate::state::test_support::{client_request, create_client, create_user, setup_state};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use chrono::Duration;
+    use tower::ServiceExt;
+
+    async fn issue(
+        state: &AppState,
+        client: &OAuthClientDetails,
+        user_id: &str,
+        permissions: Vec<String>,
+    ) -> TokenPair {
+        state
+            .token_service
+            .issue_tokens(
+                client,
+                Some(user_id.to_string()),
+                "".to_string(),
+                permissions,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_revoke_user_invalidates_tokens_and_sessions() {
+        let (state, pool) = setup_state().await;
+        let (client, _) = create_client(&state, client_request("web")).await;
+        let alice = create_user(&state, "alice").await;
+        let bob = create_user(&state, "bob").await;
+        let alice_tokens = issue(&state, &client, &alice, vec![]).await;
+        let bob_tokens = issue(&state, &client, &bob, vec![]).await;
+
+        // 一个未兑换的授权码和一个由 bob 模拟 alice 的会话
+        let expires_at = Utc::now() + Duration::minutes(10);
+        sqlx::query(
+            "INSERT INTO authorization_codes (id, code, user_id, client_id, redirect_uri, scope, expires_at)
+             VALUES ('code-1', 'code-1', ?, ?, 'https://app.example.com/callback', 'openid', ?)",
+        )
+        .bind(&alice)
+        .bind(&client.client.id)
+        .bind(expires_at)
+        .execute(&*pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO impersonation_sessions (id, actor_user_id, target_user_id, client_id, jti, reason, expires_at)
+             VALUES ('session-1', ?, ?, ?, 'imp-1', 'Ticket', ?)",
+        )
+        .bind(&bob)
+        .bind(&alice)
+        .bind(&client.client.client_id)
+        .bind(expires_at)
+        .execute(&*pool)
+        .await
+        .unwrap();
+
+        let actor = RevocationActor {
+            actor_type: "user".to_string(),
+            actor_id: bob.clone(),
+        };
+        let summary = state
+            .token_revocation_service
+            .revoke_user(&alice, &actor, Some("  Laptop stolen ".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(summary.subject_id, alice);
+        assert_eq!(summary.refresh_tokens_revoked, 1);
+        assert_eq!(summary.sessions_ended, 1);
+        assert_eq!(summary.authorization_codes_invalidated, 1);
+
+        // 已签发的访问令牌和刷新令牌立即失效，其他用户的令牌不受影响
+        let token_service = &state.token_service;
+        assert!(token_service
+            .introspect_token(&alice_tokens.access_token)
+            .await
+            .is_err());
+        assert!(token_service
+            .introspect_token(alice_tokens.refresh_token.as_deref().unwrap())
+            .await
+            .is_err());
+        assert!(token_service
+            .verify_access_token(&alice_tokens.access_token, &state.config.issuer)
+            .await
+            .is_err());
+        assert!(token_service
+            .introspect_token(&bob_tokens.access_token)
+            .await
+            .is_ok());
+
+        // iat 的精度为一秒，等到下一秒后重新登录签发的令牌有效
+        tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+        let fresh = issue(&state, &client, &alice, vec![]).await;
+        assert!(token_service
+            .introspect_token(&fresh.access_token)
+            .await
+            .is_ok());
+
+        let (actor_id, resource_id, details): (String, String, String) = sqlx::query_as(
+            "SELECT actor_id, resource_id, details FROM audit_logs WHERE action = 'tokens.revoke_all'",
+        )
+        .fetch_one(&*pool)
+        .await
+        .unwrap();
+        assert_eq!(actor_id, bob);
+        assert_eq!(resource_id, alice);
+        let details: serde_json::Value = serde_json::from_str(&details).unwrap();
+        assert_eq!(details["reason"], "Laptop stolen");
+        assert_eq!(details["sessions_ended"], 1);
+
+        assert!(matches!(
+            state
+                .token_revocation_service
+                .revoke_user("missing", &actor, None)
+                .await,
+            Err(ServiceError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_revoke_client_tokens_route() {
+        let (state, pool) = setup_state().await;
+        let (console, _) = create_client(&state, client_request("console")).await;
+        let (leaky, _) = create_client(&state, client_request("leaky")).await;
+        let security = create_user(&state, "security").await;
+        let bob = create_user(&state, "bob").await;
+        let admin_token = issue(
+            &state,
+            &console,
+            &security,
+            vec!["tokens:revoke".to_string()],
+        )
+        .await
+        .access_token;
+        let bob_console_token = issue(&state, &console, &bob, vec![]).await.access_token;
+        let bob_leaky_token = issue(&state, &leaky, &bob, vec![]).await.access_token;
+
+        let app = crate::app::create_app(pool.clone(), state.config.clone()).await;
+        let call = |method: &str, uri: String, token: &str, body: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("authorization", format!("Bearer {token}"))
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let uri = format!(
+            "/api/v2/admin/clients/{}/revoke-tokens",
+            leaky.client.client_id
+        );
+        let body = r#"{"reason": "Client secret leaked"}"#;
+
+        // 需要 tokens:revoke 权限
+        let response = app
+            .clone()
+            .oneshot(call("POST", uri.clone(), &bob_console_token, body))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .clone()
+            .oneshot(call("POST", uri, &admin_token, body))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let summary: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(summary["subject_type"], "client");
+        assert_eq!(summary["refresh_tokens_revoked"], 1);
+
+        // 该客户端签发的访问令牌在认证中间件中被拒绝，其他客户端的令牌仍然有效
+        let response = app
+            .clone()
+            .oneshot(call(
+                "GET",
+                "/api/v2/users/me".to_string(),
+                &bob_leaky_token,
+                "",
+            ))
+            .await
+            .unwrap();
+        assert!(response.status().is_client_error());
+        let response = app
+            .clone()
+            .oneshot(call(
+                "GET",
+                "/api/v2/users/me".to_string(),
+                &bob_console_token,
+                "",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(call(
+                "POST",
+                "/api/v2/admin/clients/missing/revoke-tokens".to_string(),
+                &admin_token,
+                "{}",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // 不带请求体的 POST 也可以吊销，原因为空
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/v2/admin/users/{bob}/revoke-tokens"))
+                    .header("authorization", format!("Bearer {admin_token}"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let summary: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(summary["subject_type"], "user");
+    }
+}
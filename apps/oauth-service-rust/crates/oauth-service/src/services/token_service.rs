@@ -1,15 +1,19 @@
 #![allow(clippy::uninlined_format_args)]
 use crate::config::Config;
 use crate::error::ServiceError;
-use crate::models::client::OAuthClientDetails;
+use crate::models::client::{ClientType, OAuthClientDetails};
 use crate::services::client_service::ClientService;
 use crate::services::rbac_service::RBACService;
+use crate::services::scope_service::ScopeService;
+use crate::services::tenant_service::{can_use_client, ensure_tenant_active};
 use crate::services::user_service::UserService;
-use crate::utils::jwt::{self, TokenClaims};
+use crate::telemetry::db_span;
+use crate::utils::jwt::{self, ExpectedClaims, TokenClaims};
 use async_trait::async_trait;
 use chrono::{Duration, Utc};
 use sqlx::SqlitePool;
 use std::sync::Arc;
+use tracing::Instrument;
 use uuid::Uuid;
 
 /// Represents the pair of tokens issued.
@@ -24,6 +28,12 @@ pub struct TokenPair {
 /// The `TokenService` trait defines the logic for issuing, refreshing, and introspecting tokens.
 #[async_trait]
 pub trait TokenService: Send + Sync {
+    /// Issues an access token (plus a refresh token and, for `openid`, an ID token for users).
+    ///
+    /// The access token's audience is `resource` (a registered resource server, RFC 8707),
+    /// or this service's issuer when no resource was requested. With `dpop_jkt` the access
+    /// token (and, for public clients, the refresh token) is bound to that DPoP key (RFC 9449).
+    #[allow(clippy::too_many_arguments)]
     async fn issue_tokens(
         &self,
         client: &OAuthClientDetails,
@@ -31,12 +41,47 @@ pub trait TokenService: Send + Sync {
         scope: String,
         permissions: Vec<String>,
         nonce: Option<String>,
+        resource: Option<String>,
+        dpop_jkt: Option<String>,
+    ) -> Result<TokenPair, ServiceError>;
+
+    /// Rotates a refresh token. The new access token keeps the resource the refresh token
+    /// was issued for; a requested `resource` must match it. A DPoP-bound refresh token
+    /// can only be used with a proof (`dpop_jkt`) from the same key.
+    async fn refresh_token(
+        &self,
+        refresh_token: &str,
+        resource: Option<&str>,
+        dpop_jkt: Option<&str>,
     ) -> Result<TokenPair, ServiceError>;
 
-    async fn refresh_token(&self, refresh_token: &str) -> Result<TokenPair, ServiceError>;
+    /// Issues an access token for `user_id` on behalf of `actor_id` (impersonation).
+    ///
+    /// The token carries an RFC 8693 `act` claim naming the real actor. No refresh
+    /// token or ID token is issued, so the session ends when the token expires.
+    ///
+    /// # Returns
+    /// * `Ok((token, claims))` - The signed access token and its claims
+    async fn issue_impersonation_token(
+        &self,
+        client: &OAuthClientDetails,
+        user_id: &str,
+        actor_id: &str,
+        permissions: Vec<String>,
+        ttl_secs: i64,
+    ) -> Result<(String, TokenClaims), ServiceError>;
 
+    /// Verifies a token issued by this service (signature, expiry, issuer and revocation).
     async fn introspect_token(&self, token: &str) -> Result<TokenClaims, ServiceError>;
 
+    /// Verifies an access token presented to the resource server identified by `audience`:
+    /// in addition to `introspect_token`, it must be an `at+jwt` token whose `aud` is `audience`.
+    async fn verify_access_token(
+        &self,
+        token: &str,
+        audience: &str,
+    ) -> Result<TokenClaims, ServiceError>;
+
     /// Revokes a token (access or refresh token).
     ///
     /// According to RFC 7009, the revocation endpoint allows a client to notify
@@ -66,6 +111,40 @@ pub trait TokenService: Send + Sync {
     /// * `Ok(false)` if the token is not revoked
     /// * `Err(ServiceError)` if the check fails
     async fn is_token_revoked(&self, jti: &str) -> Result<bool, ServiceError>;
+
+    /// Revokes every active refresh token issued to a user.
+    ///
+    /// Also records a revocation watermark: tokens issued to the user (or acting as the user
+    /// in an impersonation session) at or before this moment, including stateless access
+    /// tokens, are rejected by `introspect_token` and `verify_access_token`.
+    ///
+    /// # Returns
+    /// * `Ok(count)` - The number of refresh tokens revoked
+    async fn revoke_all_for_user(&self, user_id: &str) -> Result<u64, ServiceError>;
+
+    /// Revokes every active refresh token issued to a client (by its public `client_id`).
+    ///
+    /// Also records a revocation watermark: tokens issued to the client at or before this
+    /// moment, including stateless access tokens, are rejected.
+    ///
+    /// # Returns
+    /// * `Ok(count)` - The number of refresh tokens revoked
+    /// * `Err(ServiceError::NotFound)` if the client does not exist
+    async fn revoke_all_for_client(&self, client_id: &str) -> Result<u64, ServiceError>;
+}
+
+/// The DPoP key a new refresh token is bound to.
+///
+/// Only public clients get DPoP-bound refresh tokens; refresh tokens of confidential clients
+/// are already bound to the client's credentials (RFC 9449 §5).
+fn refresh_token_binding<'a>(
+    client: &OAuthClientDetails,
+    dpop_jkt: Option<&'a str>,
+) -> Option<&'a str> {
+    match client.client.client_type {
+        ClientType::PUBLIC => dpop_jkt,
+        ClientType::CONFIDENTIAL => None,
+    }
 }
 
 pub struct TokenServiceImpl {
@@ -73,6 +152,7 @@ pub struct TokenServiceImpl {
     client_service: Arc<dyn ClientService>,
     rbac_service: Arc<dyn RBACService>,
     user_service: Arc<dyn UserService>,
+    scope_service: Arc<dyn ScopeService>,
     config: Arc<Config>,
 }
 
@@ -82,6 +162,7 @@ impl TokenServiceImpl {
         client_service: Arc<dyn ClientService>,
         rbac_service: Arc<dyn RBACService>,
         user_service: Arc<dyn UserService>,
+        scope_service: Arc<dyn ScopeService>,
         config: Arc<Config>,
     ) -> Self {
         Self {
@@ -89,12 +170,205 @@ impl TokenServiceImpl {
             client_service,
             rbac_service,
             user_service,
+            scope_service,
             config,
         }
     }
 
+    /// Revokes the active refresh tokens matching `refresh_tokens.<column> = value`
+    /// and blacklists their JTIs until the tokens would have expired.
+    ///
+    /// The `(subject_type, subject_id)` watermark is moved to now, so that outstanding
+    /// access tokens of the subject are rejected as well.
+    async fn revoke_refresh_tokens_where(
+        &self,
+        column: &'static str,
+        value: &str,
+        subject: (&str, &str),
+        reason: &str,
+    ) -> Result<u64, ServiceError> {
+        let now = Utc::now();
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO token_revocations (subject_type, subject_id, revoked_before, reason, updated_at)
+             VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT (subject_type, subject_id)
+             DO UPDATE SET revoked_before = excluded.revoked_before, reason = excluded.reason,
+                           updated_at = excluded.updated_at",
+        )
+        .bind(subject.0)
+        .bind(subject.1)
+        .bind(now)
+        .bind(reason)
+        .bind(now)
+        .execute(&mut *tx)
+        .instrument(db_span("INSERT", "token_revocations"))
+        .await?;
+
+        let tokens: Vec<(Option<String>, String, String, chrono::DateTime<Utc>)> = sqlx::query_as(
+            &format!(
+                "SELECT rt.jti, rt.user_id, c.client_id, rt.expires_at
+                 FROM refresh_tokens rt JOIN oauth_clients c ON c.id = rt.client_id
+                 WHERE rt.{column} = ? AND rt.is_revoked = 0 AND rt.expires_at > ?"
+            ),
+        )
+        .bind(value)
+        .bind(now)
+        .fetch_all(&mut *tx)
+        .instrument(db_span("SELECT", "refresh_tokens"))
+        .await?;
+
+        for (jti, user_id, client_id, expires_at) in &tokens {
+            let Some(jti) = jti else { continue };
+            sqlx::query(
+                "INSERT OR IGNORE INTO token_blacklist (id, jti, token_type, user_id, client_id, expires_at, reason, created_at)
+                 VALUES (?, ?, 'refresh_token', ?, ?, ?, ?, ?)",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(jti)
+            .bind(user_id)
+            .bind(client_id)
+            .bind(expires_at)
+            .bind(reason)
+            .bind(now)
+            .execute(&mut *tx)
+            .instrument(db_span("INSERT", "token_blacklist"))
+            .await?;
+        }
+
+        let result = sqlx::query(&format!(
+            "UPDATE refresh_tokens SET is_revoked = TRUE, revoked_at = ?
+             WHERE {column} = ? AND is_revoked = 0 AND expires_at > ?"
+        ))
+        .bind(now)
+        .bind(value)
+        .bind(now)
+        .execute(&mut *tx)
+        .instrument(db_span("UPDATE", "refresh_tokens"))
+        .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    /// The audience of access tokens issued without a `resource`: this service itself.
+    fn default_audience(&self) -> String {
+        self.config.issuer.clone()
+    }
+
+    /// Verifies a token and checks it has not been revoked.
+    async fn verify_and_check_revocation(
+        &self,
+        token: &str,
+        audience: Option<&str>,
+    ) -> Result<TokenClaims, ServiceError> {
+        let decoding_key = self.config.load_decoding_key()?;
+        // 1. Verify the token signature, expiration, issuer and (for access tokens) audience
+        let expected = ExpectedClaims {
+            issuer: &self.config.issuer,
+            audience,
+        };
+        let claims = jwt::verify_token_with_algorithm(
+            token,
+            &decoding_key,
+            self.config.jwt_algorithm,
+            Some(&expected),
+        )?;
+
+        // 2. Check if token is in blacklist
+        let is_revoked = self.is_token_revoked(&claims.jti).await?;
+        if is_revoked {
+            return Err(ServiceError::ValidationError(
+                "Token has been revoked".to_string(),
+            ));
+        }
+
+        // 3. Check it was not issued before a global revocation of its user, actor or client
+        if self.issued_before_revocation(&claims).await? {
+            return Err(ServiceError::ValidationError(
+                "Token has been revoked".to_string(),
+            ));
+        }
+
+        // 4. If it might be a refresh token (check by JTI), see if it has been revoked.
+        // This is a simplified check. A full implementation would distinguish token types more robustly.
+        if let Some(stored_token) = sqlx::query_as::<_, crate::models::refresh_token::RefreshToken>(
+            "SELECT id, token, token_hash, jti, user_id, client_id, scope, expires_at, \
+             is_revoked, revoked_at, created_at, previous_token_id, resource, dpop_jkt \
+             FROM refresh_tokens WHERE jti = ?",
+        )
+        .bind(&claims.jti)
+        .fetch_optional(&*self.db)
+        .instrument(db_span("SELECT", "refresh_tokens"))
+        .await?
+        {
+            if stored_token.is_revoked {
+                return Err(ServiceError::ValidationError(
+                    "Token has been revoked".to_string(),
+                ));
+            }
+        }
+
+        Ok(claims)
+    }
+
+    /// Whether the token was issued at or before the revocation watermark of its subject,
+    /// its impersonation actor or its client.
+    ///
+    /// `iat` has a resolution of one second, so tokens issued within the same second as the
+    /// revocation are rejected too.
+    async fn issued_before_revocation(&self, claims: &TokenClaims) -> Result<bool, ServiceError> {
+        let watermarks: Vec<chrono::DateTime<Utc>> = sqlx::query_scalar(
+            "SELECT revoked_before FROM token_revocations
+             WHERE (subject_type = 'user' AND subject_id IN (?, ?))
+                OR (subject_type = 'client' AND subject_id = ?)",
+        )
+        .bind(&claims.sub)
+        .bind(claims.act.as_ref().map(|act| &act.sub))
+        .bind(&claims.client_id)
+        .fetch_all(&*self.db)
+        .instrument(db_span("SELECT", "token_revocations"))
+        .await?;
+
+        Ok(watermarks
+            .iter()
+            .any(|revoked_before| claims.iat as i64 <= revoked_before.timestamp()))
+    }
+
+    /// Determines the tenant a token belongs to: the user's tenant for user tokens,
+    /// the client's tenant otherwise. Rejects clients from another tenant and
+    /// disabled tenants.
+    async fn resolve_tenant(
+        &self,
+        client: &OAuthClientDetails,
+        user_id: Option<&str>,
+    ) -> Result<Option<String>, ServiceError> {
+        let tenant = match user_id {
+            Some(user_id) => {
+                let user_tenant: Option<String> =
+                    sqlx::query_scalar("SELECT tenant_id FROM users WHERE id = ?")
+                        .bind(user_id)
+                        .fetch_optional(&*self.db)
+                        .instrument(db_span("SELECT", "users"))
+                        .await?
+                        .flatten();
+                if !can_use_client(client.client.tenant_id.as_deref(), user_tenant.as_deref()) {
+                    return Err(ServiceError::Unauthorized(
+                        "Client is not available to the user's tenant".to_string(),
+                    ));
+                }
+                user_tenant
+            }
+            None => client.client.tenant_id.clone(),
+        };
+        ensure_tenant_active(&self.db, tenant.as_deref()).await?;
+        Ok(tenant)
+    }
+
     /// Issue tokens within a database transaction (for atomicity)
     /// This is a private helper method used by refresh_token to ensure atomic operations
+    #[allow(clippy::too_many_arguments)]
     async fn issue_tokens_tx(
         &self,
         tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
@@ -103,23 +377,32 @@ impl TokenServiceImpl {
         scope: String,
         permissions: Vec<String>,
         nonce: Option<String>,
+        tenant: Option<String>,
+        resource: Option<String>,
+        dpop_jkt: Option<String>,
     ) -> Result<TokenPair, ServiceError> {
         let encoding_key = self.config.load_encoding_key()?;
         let now = Utc::now();
         let access_token_ttl = client.client.access_token_ttl as u64;
         let access_token_exp = now + Duration::seconds(access_token_ttl as i64);
 
+        let issuer = self.config.tenant_issuer(tenant.as_deref());
         let access_token_claims = TokenClaims {
+            iss: Some(issuer.clone()),
             sub: user_id.clone(),
+            aud: Some(resource.clone().unwrap_or_else(|| self.default_audience())),
             client_id: client.client.client_id.clone(),
             scope: scope.clone(),
             permissions,
             exp: access_token_exp.timestamp() as usize,
             iat: now.timestamp() as usize,
             jti: Uuid::new_v4().to_string(),
+            tenant: tenant.clone(),
+            act: None,
+            cnf: dpop_jkt.clone().map(|jkt| jwt::Confirmation { jkt }),
         };
 
-        let access_token = jwt::generate_token_with_algorithm(
+        let access_token = jwt::generate_access_token_with_algorithm(
             &access_token_claims,
             &encoding_key,
             self.config.jwt_algorithm,
@@ -135,13 +418,18 @@ impl TokenServiceImpl {
             let refresh_jti = Uuid::new_v4().to_string();
 
             let refresh_token_claims = TokenClaims {
+                iss: Some(issuer.clone()),
                 sub: Some(uid.clone()),
+                aud: None,
                 client_id: client.client.client_id.clone(),
                 scope: scope.clone(),
                 permissions: vec![], // Refresh tokens don't carry permissions
                 exp: refresh_token_exp.timestamp() as usize,
                 iat: now.timestamp() as usize,
                 jti: refresh_jti.clone(),
+                tenant: tenant.clone(),
+                act: None,
+                cnf: None,
             };
 
             let refresh_token = jwt::generate_token_with_algorithm(
@@ -154,7 +442,7 @@ impl TokenServiceImpl {
 
             // Insert refresh token within transaction
             sqlx::query(
-                "INSERT INTO refresh_tokens (id, token, token_hash, jti, user_id, client_id, scope, expires_at, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                "INSERT INTO refresh_tokens (id, token, token_hash, jti, user_id, client_id, scope, resource, dpop_jkt, expires_at, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             )
             .bind(&refresh_id)
             .bind(&refresh_token)
@@ -163,9 +451,12 @@ impl TokenServiceImpl {
             .bind(&uid)
             .bind(&client.client.id)
             .bind(&scope)
+            .bind(&resource)
+            .bind(refresh_token_binding(client, dpop_jkt.as_deref()))
             .bind(&refresh_token_exp)
             .bind(&now)
             .execute(&mut **tx)
+            .instrument(db_span("INSERT", "refresh_tokens"))
             .await?;
 
             issued_refresh_token = Some(refresh_token);
@@ -178,7 +469,7 @@ impl TokenServiceImpl {
                         &user,
                         &client.client.client_id,
                         &scope,
-                        &self.config.issuer,
+                        &issuer,
                         nonce.as_deref(),
                         &encoding_key,
                         access_token_ttl,
@@ -200,6 +491,7 @@ impl TokenServiceImpl {
 
 #[async_trait]
 impl TokenService for TokenServiceImpl {
+    #[tracing::instrument(name = "token.issue", skip_all, fields(client_id = %client.client.client_id, user_id = ?user_id, dpop = dpop_jkt.is_some()))]
     async fn issue_tokens(
         &self,
         client: &OAuthClientDetails,
@@ -207,23 +499,32 @@ impl TokenService for TokenServiceImpl {
         scope: String,
         permissions: Vec<String>,
         nonce: Option<String>,
+        resource: Option<String>,
+        dpop_jkt: Option<String>,
     ) -> Result<TokenPair, ServiceError> {
+        let tenant = self.resolve_tenant(client, user_id.as_deref()).await?;
         let encoding_key = self.config.load_encoding_key()?;
         let now = Utc::now();
         let access_token_ttl = client.client.access_token_ttl as u64;
         let access_token_exp = now + Duration::seconds(access_token_ttl as i64);
 
+        let issuer = self.config.tenant_issuer(tenant.as_deref());
         let access_token_claims = TokenClaims {
+            iss: Some(issuer.clone()),
             sub: user_id.clone(),
+            aud: Some(resource.clone().unwrap_or_else(|| self.default_audience())),
             client_id: client.client.client_id.clone(),
             scope: scope.clone(),
             permissions,
             exp: access_token_exp.timestamp() as usize,
             iat: now.timestamp() as usize,
             jti: Uuid::new_v4().to_string(),
+            tenant: tenant.clone(),
+            act: None,
+            cnf: dpop_jkt.clone().map(|jkt| jwt::Confirmation { jkt }),
         };
 
-        let access_token = jwt::generate_token_with_algorithm(
+        let access_token = jwt::generate_access_token_with_algorithm(
             &access_token_claims,
             &encoding_key,
             self.config.jwt_algorithm,
@@ -239,13 +540,18 @@ impl TokenService for TokenServiceImpl {
             let refresh_jti = Uuid::new_v4().to_string();
 
             let refresh_token_claims = TokenClaims {
+                iss: Some(issuer.clone()),
                 sub: Some(uid.clone()),
+                aud: None,
                 client_id: client.client.client_id.clone(),
                 scope: scope.clone(),
                 permissions: vec![], // Refresh tokens don't carry permissions
                 exp: refresh_token_exp.timestamp() as usize,
                 iat: now.timestamp() as usize,
                 jti: refresh_jti.clone(),
+                tenant: tenant.clone(),
+                act: None,
+                cnf: None,
             };
 
             let refresh_token = jwt::generate_token_with_algorithm(
@@ -257,7 +563,7 @@ impl TokenService for TokenServiceImpl {
             let refresh_id = Uuid::new_v4().to_string();
 
             sqlx::query(
-                "INSERT INTO refresh_tokens (id, token, token_hash, jti, user_id, client_id, scope, expires_at, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                "INSERT INTO refresh_tokens (id, token, token_hash, jti, user_id, client_id, scope, resource, dpop_jkt, expires_at, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             )
             .bind(&refresh_id)
             .bind(&refresh_token)
@@ -266,9 +572,12 @@ impl TokenService for TokenServiceImpl {
             .bind(&uid)
             .bind(&client.client.id)
             .bind(&scope)
+            .bind(&resource)
+            .bind(refresh_token_binding(client, dpop_jkt.as_deref()))
             .bind(&refresh_token_exp)
             .bind(&now)
             .execute(&*self.db)
+            .instrument(db_span("INSERT", "refresh_tokens"))
             .await?;
 
             issued_refresh_token = Some(refresh_token);
@@ -281,7 +590,7 @@ impl TokenService for TokenServiceImpl {
                         &user,
                         &client.client.client_id,
                         &scope,
-                        &self.config.issuer,
+                        &issuer,
                         nonce.as_deref(),
                         &encoding_key,
                         access_token_ttl,
@@ -300,20 +609,36 @@ impl TokenService for TokenServiceImpl {
         })
     }
 
-    async fn refresh_token(&self, refresh_token: &str) -> Result<TokenPair, ServiceError> {
+    #[tracing::instrument(name = "token.refresh", skip_all)]
+    async fn refresh_token(
+        &self,
+        refresh_token: &str,
+        resource: Option<&str>,
+        dpop_jkt: Option<&str>,
+    ) -> Result<TokenPair, ServiceError> {
         let decoding_key = self.config.load_decoding_key()?;
         // 1. Verify the incoming refresh token
-        let claims = jwt::verify_token(refresh_token, &decoding_key)?;
+        let expected = ExpectedClaims {
+            issuer: &self.config.issuer,
+            audience: None,
+        };
+        let claims = jwt::verify_token_with_algorithm(
+            refresh_token,
+            &decoding_key,
+            self.config.jwt_algorithm,
+            Some(&expected),
+        )?;
 
         // 2. Find the token in the database by its JTI and ensure it's valid
         let jti = claims.jti.clone();
         let stored_token = sqlx::query_as::<_, crate::models::refresh_token::RefreshToken>(
             "SELECT id, token, token_hash, jti, user_id, client_id, scope, expires_at, \
-             is_revoked, revoked_at, created_at, previous_token_id \
+             is_revoked, revoked_at, created_at, previous_token_id, resource, dpop_jkt \
              FROM refresh_tokens WHERE jti = ?",
         )
         .bind(&jti)
         .fetch_optional(&*self.db)
+        .instrument(db_span("SELECT", "refresh_tokens"))
         .await?
         .ok_or_else(|| ServiceError::Unauthorized("Invalid refresh token".to_string()))?;
 
@@ -329,6 +654,22 @@ impl TokenService for TokenServiceImpl {
             ));
         }
 
+        // RFC 9449: a DPoP-bound refresh token requires a proof from the same key
+        if let Some(bound_jkt) = stored_token.dpop_jkt.as_deref() {
+            if dpop_jkt != Some(bound_jkt) {
+                return Err(ServiceError::InvalidDpopProof(
+                    "Refresh token is bound to a different DPoP key".to_string(),
+                ));
+            }
+        }
+
+        // RFC 8707: the refreshed access token can only target the resource originally granted
+        if resource.is_some() && resource != stored_token.resource.as_deref() {
+            return Err(ServiceError::InvalidTarget(
+                "Resource does not match the refresh token".to_string(),
+            ));
+        }
+
         // Get client and user info before starting transaction
         let client = self
             .client_service
@@ -341,7 +682,18 @@ impl TokenService for TokenServiceImpl {
         let user_id = claims.sub.ok_or_else(|| {
             ServiceError::ValidationError("User ID missing in refresh token claims".to_string())
         })?;
-        let permissions = self.rbac_service.get_user_permissions(&user_id).await?;
+        let permissions = self
+            .rbac_service
+            .get_user_permissions_for_client(&user_id, &claims.client_id)
+            .await?;
+        // 令牌只携带授予的 scope 所映射的权限
+        let permissions = self
+            .scope_service
+            .restrict_permissions_to_scope(&claims.scope, permissions)
+            .await?;
+
+        // A disabled tenant can no longer refresh its tokens
+        let tenant = self.resolve_tenant(&client, Some(&user_id)).await?;
 
         // 3. Use transaction to ensure atomicity: revoke old token and issue new tokens
         let mut tx = self.db.begin().await?;
@@ -354,6 +706,7 @@ impl TokenService for TokenServiceImpl {
         .bind(&now)
         .bind(&stored_token.id)
         .execute(&mut *tx)
+        .instrument(db_span("UPDATE", "refresh_tokens"))
         .await?;
 
         // 4. Issue new tokens within transaction
@@ -364,6 +717,9 @@ impl TokenService for TokenServiceImpl {
             claims.scope,
             permissions,
             None, // No nonce for refresh token flow
+            tenant,
+            stored_token.resource,
+            dpop_jkt.map(str::to_string),
         )
         .await?;
 
@@ -373,40 +729,59 @@ impl TokenService for TokenServiceImpl {
         Ok(token_pair)
     }
 
-    async fn introspect_token(&self, token: &str) -> Result<TokenClaims, ServiceError> {
-        let decoding_key = self.config.load_decoding_key()?;
-        // 1. Verify the token signature and expiration
-        let claims = jwt::verify_token(token, &decoding_key)?;
+    #[tracing::instrument(name = "token.impersonate", skip_all, fields(client_id = %client.client.client_id, user_id = %user_id, actor_id = %actor_id))]
+    async fn issue_impersonation_token(
+        &self,
+        client: &OAuthClientDetails,
+        user_id: &str,
+        actor_id: &str,
+        permissions: Vec<String>,
+        ttl_secs: i64,
+    ) -> Result<(String, TokenClaims), ServiceError> {
+        let tenant = self.resolve_tenant(client, Some(user_id)).await?;
+        let encoding_key = self.config.load_encoding_key()?;
+        let now = Utc::now();
 
-        // 2. Check if token is in blacklist
-        let is_revoked = self.is_token_revoked(&claims.jti).await?;
-        if is_revoked {
-            return Err(ServiceError::ValidationError(
-                "Token has been revoked".to_string(),
-            ));
-        }
+        let claims = TokenClaims {
+            iss: Some(self.config.tenant_issuer(tenant.as_deref())),
+            sub: Some(user_id.to_string()),
+            aud: Some(self.default_audience()),
+            client_id: client.client.client_id.clone(),
+            scope: String::new(),
+            permissions,
+            exp: (now + Duration::seconds(ttl_secs)).timestamp() as usize,
+            iat: now.timestamp() as usize,
+            jti: Uuid::new_v4().to_string(),
+            tenant,
+            act: Some(jwt::ActorClaim {
+                sub: actor_id.to_string(),
+            }),
+            cnf: None,
+        };
 
-        // 3. If it might be a refresh token (check by JTI), see if it has been revoked.
-        // This is a simplified check. A full implementation would distinguish token types more robustly.
-        if let Some(stored_token) = sqlx::query_as::<_, crate::models::refresh_token::RefreshToken>(
-            "SELECT id, token, token_hash, jti, user_id, client_id, scope, expires_at, \
-             is_revoked, revoked_at, created_at, previous_token_id \
-             FROM refresh_tokens WHERE jti = ?",
-        )
-        .bind(&claims.jti)
-        .fetch_optional(&*self.db)
-        .await?
-        {
-            if stored_token.is_revoked {
-                return Err(ServiceError::ValidationError(
-                    "Token has been revoked".to_string(),
-                ));
-            }
-        }
+        let token = jwt::generate_access_token_with_algorithm(
+            &claims,
+            &encoding_key,
+            self.config.jwt_algorithm,
+        )?;
+        Ok((token, claims))
+    }
 
-        Ok(claims)
+    #[tracing::instrument(name = "token.introspect", skip_all)]
+    async fn introspect_token(&self, token: &str) -> Result<TokenClaims, ServiceError> {
+        self.verify_and_check_revocation(token, None).await
+    }
+
+    #[tracing::instrument(name = "token.verify_access", skip_all, fields(audience = %audience))]
+    async fn verify_access_token(
+        &self,
+        token: &str,
+        audience: &str,
+    ) -> Result<TokenClaims, ServiceError> {
+        self.verify_and_check_revocation(token, Some(audience)).await
     }
 
+    #[tracing::instrument(name = "token.revoke", skip_all, fields(token_type_hint = ?token_type_hint))]
     async fn revoke_token(
         &self,
         token: &str,
@@ -447,6 +822,7 @@ impl TokenService for TokenServiceImpl {
         .bind("User initiated revocation")
         .bind(&now)
         .execute(&*self.db)
+        .instrument(db_span("INSERT", "token_blacklist"))
         .await?;
 
         // 4. If it's a refresh token, also mark it as revoked in refresh_tokens table
@@ -457,6 +833,7 @@ impl TokenService for TokenServiceImpl {
             .bind(&now)
             .bind(jti)
             .execute(&*self.db)
+            .instrument(db_span("UPDATE", "refresh_tokens"))
             .await?;
         }
 
@@ -471,6 +848,7 @@ impl TokenService for TokenServiceImpl {
         Ok(())
     }
 
+    #[tracing::instrument(name = "token.is_revoked", skip_all, fields(jti = %jti))]
     async fn is_token_revoked(&self, jti: &str) -> Result<bool, ServiceError> {
         let now = Utc::now();
 
@@ -481,10 +859,44 @@ impl TokenService for TokenServiceImpl {
         .bind(jti)
         .bind(&now)
         .fetch_optional(&*self.db)
+        .instrument(db_span("SELECT", "token_blacklist"))
         .await?;
 
         Ok(blacklist_entry.is_some())
     }
+
+    #[tracing::instrument(name = "token.revoke_all_for_user", skip_all, fields(user_id = %user_id))]
+    async fn revoke_all_for_user(&self, user_id: &str) -> Result<u64, ServiceError> {
+        let revoked = self
+            .revoke_refresh_tokens_where(
+                "user_id",
+                user_id,
+                ("user", user_id),
+                "All tokens of user revoked",
+            )
+            .await?;
+        tracing::info!("Revoked {} refresh token(s) of user {}", revoked, user_id);
+        Ok(revoked)
+    }
+
+    #[tracing::instrument(name = "token.revoke_all_for_client", skip_all, fields(client_id = %client_id))]
+    async fn revoke_all_for_client(&self, client_id: &str) -> Result<u64, ServiceError> {
+        let client = self
+            .client_service
+            .find_by_client_id(client_id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("Client {} not found", client_id)))?;
+        let revoked = self
+            .revoke_refresh_tokens_where(
+                "client_id",
+                &client.client.id,
+                ("client", client_id),
+                "All tokens of client revoked",
+            )
+            .await?;
+        tracing::info!("Revoked {} refresh token(s) of client {}", revoked, client_id);
+        Ok(revoked)
+    }
 }
 
 #[cfg(test)]
@@ -496,6 +908,7 @@ mod tests {
     use crate::routes::clients::CreateClientRequest;
     use crate::services::client_service::{ClientService, ClientServiceImpl};
     use crate::services::rbac_service::{RBACService, RBACServiceImpl};
+    use crate::services::scope_service::{ScopeService, ScopeServiceImpl};
     use crate::services::user_service::{UserService, UserServiceImpl};
     use jsonwebtoken::{DecodingKey, EncodingKey};
     use sqlx::SqlitePool;
@@ -532,6 +945,9 @@ mod tests {
             response_types: vec!["code".to_string()],
             allowed_scopes: vec!["read".to_string(), "write".to_string()],
             client_permissions: None,
+            access_token_ttl: None,
+            refresh_token_ttl: None,
+            tenant_id: None,
         };
         let (client, _) = client_service
             .create_client(request)
@@ -564,6 +980,7 @@ mod tests {
             jwt_public_key_path: "".to_string(),
             issuer: "test_issuer".to_string(),
             jwt_algorithm: crate::config::JwtAlgorithm::HS256,
+            ..Default::default()
         }
     }
 
@@ -577,6 +994,7 @@ mod tests {
         let client_service = Arc::new(ClientServiceImpl::new(db.clone())) as Arc<dyn ClientService>;
         let rbac_service = Arc::new(RBACServiceImpl::new(db.clone(), permission_cache)) as Arc<dyn RBACService>;
         let user_service = Arc::new(UserServiceImpl::new(db.clone())) as Arc<dyn UserService>;
+        let scope_service = Arc::new(ScopeServiceImpl::new(db.clone())) as Arc<dyn ScopeService>;
         let config = Arc::new(create_test_config());
 
         let token_service = TokenServiceImpl::new(
@@ -584,6 +1002,7 @@ mod tests {
             client_service,
             rbac_service,
             user_service,
+            scope_service,
             config,
         );
 
@@ -597,6 +1016,8 @@ mod tests {
                 "read write".to_string(),
                 vec!["read:data".to_string(), "write:data".to_string()],
                 None,
+                None,
+                None,
             )
             .await;
 
@@ -617,6 +1038,7 @@ mod tests {
         let client_service = Arc::new(ClientServiceImpl::new(db.clone())) as Arc<dyn ClientService>;
         let rbac_service = Arc::new(RBACServiceImpl::new(db.clone(), permission_cache)) as Arc<dyn RBACService>;
         let user_service = Arc::new(UserServiceImpl::new(db.clone())) as Arc<dyn UserService>;
+        let scope_service = Arc::new(ScopeServiceImpl::new(db.clone())) as Arc<dyn ScopeService>;
         let config = Arc::new(create_test_config());
 
         let token_service = TokenServiceImpl::new(
@@ -624,6 +1046,7 @@ mod tests {
             client_service,
             rbac_service,
             user_service,
+            scope_service,
             config,
         );
 
@@ -636,6 +1059,8 @@ mod tests {
                 "read".to_string(),
                 vec![],
                 None,
+                None,
+                None,
             )
             .await;
 
@@ -655,6 +1080,7 @@ mod tests {
         let client_service = Arc::new(ClientServiceImpl::new(db.clone())) as Arc<dyn ClientService>;
         let rbac_service = Arc::new(RBACServiceImpl::new(db.clone(), permission_cache)) as Arc<dyn RBACService>;
         let user_service = Arc::new(UserServiceImpl::new(db.clone())) as Arc<dyn UserService>;
+        let scope_service = Arc::new(ScopeServiceImpl::new(db.clone())) as Arc<dyn ScopeService>;
         let config = Arc::new(create_test_config());
 
         let token_service = TokenServiceImpl::new(
@@ -662,6 +1088,7 @@ mod tests {
             client_service,
             rbac_service,
             user_service,
+            scope_service,
             config,
         );
 
@@ -676,6 +1103,8 @@ mod tests {
                 "read write".to_string(),
                 vec!["read:data".to_string(), "write:data".to_string()],
                 None,
+                None,
+                None,
             )
             .await
             .expect("Failed to issue tokens");
@@ -713,6 +1142,7 @@ mod tests {
         let client_service = Arc::new(ClientServiceImpl::new(db.clone())) as Arc<dyn ClientService>;
         let rbac_service = Arc::new(RBACServiceImpl::new(db.clone(), permission_cache)) as Arc<dyn RBACService>;
         let user_service = Arc::new(UserServiceImpl::new(db.clone())) as Arc<dyn UserService>;
+        let scope_service = Arc::new(ScopeServiceImpl::new(db.clone())) as Arc<dyn ScopeService>;
         let config = Arc::new(create_test_config());
 
         let token_service = TokenServiceImpl::new(
@@ -720,6 +1150,7 @@ mod tests {
             client_service,
             rbac_service,
             user_service,
+            scope_service,
             config,
         );
 
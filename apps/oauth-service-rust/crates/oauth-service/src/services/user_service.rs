@@ -1,5 +1,6 @@
 use crate::error::ServiceError;
 use crate::models::user::User;
+use crate::services::tenant_service::ensure_tenant_exists;
 use crate::utils::crypto;
 use async_trait::async_trait;
 use chrono::Utc;
@@ -17,6 +18,14 @@ pub trait UserService: Send + Sync {
         password: String,
         display_name: Option<String>,
     ) -> Result<User, ServiceError>;
+    /// 在租户中创建用户 (`create_user` 创建的是平台级用户)
+    async fn create_user_in_tenant(
+        &self,
+        username: String,
+        password: String,
+        display_name: Option<String>,
+        tenant_id: &str,
+    ) -> Result<User, ServiceError>;
     async fn authenticate(&self, username: &str, password: &str) -> Result<User, ServiceError>;
     async fn update_last_login(&self, user_id: &str) -> Result<(), ServiceError>;
     async fn list_users(
@@ -24,6 +33,13 @@ pub trait UserService: Send + Sync {
         limit: Option<i32>,
         offset: Option<i32>,
     ) -> Result<Vec<User>, ServiceError>;
+    /// 列出属于租户的用户
+    async fn list_users_in_tenant(
+        &self,
+        tenant_id: &str,
+        limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> Result<Vec<User>, ServiceError>;
     async fn update_user(
         &self,
         user_id: &str,
@@ -31,6 +47,19 @@ pub trait UserService: Send + Sync {
         is_active: Option<bool>,
     ) -> Result<User, ServiceError>;
     async fn delete_user(&self, user_id: &str) -> Result<(), ServiceError>;
+    /// 重置用户密码，可要求用户下次登录时修改密码
+    async fn set_password(
+        &self,
+        user_id: &str,
+        password: &str,
+        must_change_password: bool,
+    ) -> Result<(), ServiceError>;
+    /// 锁定用户直到指定时间，传入 None 表示解锁 (同时清零失败登录次数)
+    async fn set_locked_until(
+        &self,
+        user_id: &str,
+        locked_until: Option<chrono::DateTime<Utc>>,
+    ) -> Result<(), ServiceError>;
 }
 
 pub struct UserServiceImpl {
@@ -41,43 +70,20 @@ impl UserServiceImpl {
     pub fn new(db: Arc<SqlitePool>) -> Self {
         Self { db }
     }
-}
-
-#[async_trait]
-impl UserService for UserServiceImpl {
-    async fn find_by_username(&self, username: &str) -> Result<Option<User>, ServiceError> {
-        let user = sqlx::query_as::<_, User>(
-            "SELECT id, username, password_hash, is_active, created_at, updated_at, last_login_at, \
-             display_name, first_name, last_name, avatar, organization, department, \
-             must_change_password, failed_login_attempts, locked_until, created_by \
-             FROM users WHERE username = ?"
-        )
-            .bind(username)
-            .fetch_optional(&*self.db)
-            .await?;
-        Ok(user)
-    }
-
-    async fn find_by_id(&self, id: &str) -> Result<Option<User>, ServiceError> {
-        let user = sqlx::query_as::<_, User>(
-            "SELECT id, username, password_hash, is_active, created_at, updated_at, last_login_at, \
-             display_name, first_name, last_name, avatar, organization, department, \
-             must_change_password, failed_login_attempts, locked_until, created_by \
-             FROM users WHERE id = ?"
-        )
-            .bind(id)
-            .fetch_optional(&*self.db)
-            .await?;
-        Ok(user)
-    }
 
-    async fn create_user(
+    /// 创建用户，`tenant_id` 为空时创建平台级用户
+    async fn insert_user(
         &self,
         username: String,
         password: String,
         display_name: Option<String>,
+        tenant_id: Option<&str>,
     ) -> Result<User, ServiceError> {
-        // 检查用户名是否已存在
+        if let Some(tenant_id) = tenant_id {
+            ensure_tenant_exists(&self.db, tenant_id).await?;
+        }
+
+        // 用户名在整个部署内唯一 (不同租户之间也不能重复)
         if self.find_by_username(&username).await?.is_some() {
             return Err(ServiceError::ValidationError(
                 "Username already exists".to_string(),
@@ -96,8 +102,8 @@ impl UserService for UserServiceImpl {
             INSERT INTO users (
                 id, username, password_hash, is_active,
                 created_at, updated_at, display_name,
-                must_change_password, failed_login_attempts
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                must_change_password, failed_login_attempts, tenant_id
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&id)
@@ -109,6 +115,7 @@ impl UserService for UserServiceImpl {
         .bind(display_name)
         .bind(false)
         .bind(0)
+        .bind(tenant_id)
         .execute(&*self.db)
         .await?;
 
@@ -118,6 +125,80 @@ impl UserService for UserServiceImpl {
             .ok_or_else(|| ServiceError::Internal("Failed to retrieve created user".to_string()))
     }
 
+    /// 分页查询用户，指定租户时只返回该租户的用户
+    async fn query_users(
+        &self,
+        tenant_id: Option<&str>,
+        limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> Result<Vec<User>, ServiceError> {
+        let limit = limit.unwrap_or(50).min(100);
+        let offset = offset.unwrap_or(0);
+
+        let users = sqlx::query_as::<_, User>(
+            "SELECT id, username, password_hash, is_active, created_at, updated_at, last_login_at, \
+             display_name, first_name, last_name, avatar, organization, department, \
+             must_change_password, failed_login_attempts, locked_until, created_by, tenant_id \
+             FROM users WHERE (? IS NULL OR tenant_id = ?) ORDER BY created_at DESC LIMIT ? OFFSET ?",
+        )
+        .bind(tenant_id)
+        .bind(tenant_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&*self.db)
+        .await?;
+
+        Ok(users)
+    }
+}
+
+#[async_trait]
+impl UserService for UserServiceImpl {
+    async fn find_by_username(&self, username: &str) -> Result<Option<User>, ServiceError> {
+        let user = sqlx::query_as::<_, User>(
+            "SELECT id, username, password_hash, is_active, created_at, updated_at, last_login_at, \
+             display_name, first_name, last_name, avatar, organization, department, \
+             must_change_password, failed_login_attempts, locked_until, created_by, tenant_id \
+             FROM users WHERE username = ?"
+        )
+            .bind(username)
+            .fetch_optional(&*self.db)
+            .await?;
+        Ok(user)
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<User>, ServiceError> {
+        let user = sqlx::query_as::<_, User>(
+            "SELECT id, username, password_hash, is_active, created_at, updated_at, last_login_at, \
+             display_name, first_name, last_name, avatar, organization, department, \
+             must_change_password, failed_login_attempts, locked_until, created_by, tenant_id \
+             FROM users WHERE id = ?"
+        )
+            .bind(id)
+            .fetch_optional(&*self.db)
+            .await?;
+        Ok(user)
+    }
+
+    async fn create_user(
+        &self,
+        username: String,
+        password: String,
+        display_name: Option<String>,
+    ) -> Result<User, ServiceError> {
+        self.insert_user(username, password, display_name, None).await
+    }
+
+    async fn create_user_in_tenant(
+        &self,
+        username: String,
+        password: String,
+        display_name: Option<String>,
+        tenant_id: &str,
+    ) -> Result<User, ServiceError> {
+        self.insert_user(username, password, display_name, Some(tenant_id)).await
+    }
+
     async fn authenticate(&self, username: &str, password: &str) -> Result<User, ServiceError> {
         let user = self.find_by_username(username).await?.ok_or_else(|| {
             ServiceError::Unauthorized("Invalid username or password".to_string())
@@ -191,21 +272,16 @@ impl UserService for UserServiceImpl {
         limit: Option<i32>,
         offset: Option<i32>,
     ) -> Result<Vec<User>, ServiceError> {
-        let limit = limit.unwrap_or(50).min(100);
-        let offset = offset.unwrap_or(0);
-
-        let users = sqlx::query_as::<_, User>(
-            "SELECT id, username, password_hash, is_active, created_at, updated_at, last_login_at, \
-             display_name, first_name, last_name, avatar, organization, department, \
-             must_change_password, failed_login_attempts, locked_until, created_by \
-             FROM users ORDER BY created_at DESC LIMIT ? OFFSET ?",
-        )
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(&*self.db)
-        .await?;
+        self.query_users(None, limit, offset).await
+    }
 
-        Ok(users)
+    async fn list_users_in_tenant(
+        &self,
+        tenant_id: &str,
+        limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> Result<Vec<User>, ServiceError> {
+        self.query_users(Some(tenant_id), limit, offset).await
     }
 
     async fn update_user(
@@ -262,6 +338,62 @@ impl UserService for UserServiceImpl {
 
         Ok(())
     }
+
+    async fn set_password(
+        &self,
+        user_id: &str,
+        password: &str,
+        must_change_password: bool,
+    ) -> Result<(), ServiceError> {
+        let password_hash = crypto::hash_password(password)?;
+        let result = sqlx::query(
+            "UPDATE users SET password_hash = ?, must_change_password = ?, \
+             failed_login_attempts = 0, locked_until = NULL, updated_at = ? WHERE id = ?",
+        )
+        .bind(&password_hash)
+        .bind(must_change_password)
+        .bind(Utc::now())
+        .bind(user_id)
+        .execute(&*self.db)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(ServiceError::NotFound(format!("User '{user_id}' not found")));
+        }
+        Ok(())
+    }
+
+    async fn set_locked_until(
+        &self,
+        user_id: &str,
+        locked_until: Option<chrono::DateTime<Utc>>,
+    ) -> Result<(), ServiceError> {
+        let result = match locked_until {
+            Some(until) => {
+                sqlx::query("UPDATE users SET locked_until = ?, updated_at = ? WHERE id = ?")
+                    .bind(until)
+                    .bind(Utc::now())
+                    .bind(user_id)
+                    .execute(&*self.db)
+                    .await?
+            }
+            None => {
+                sqlx::query(
+                    "UPDATE users SET locked_until = NULL, failed_login_attempts = 0, \
+                     updated_at = ? WHERE id = ?",
+                )
+                .bind(Utc::now())
+                .bind(user_id)
+                .execute(&*self.db)
+                .await?
+            }
+        };
+
+        if result.rows_affected() == 0 {
+            return Err(ServiceError::NotFound(format!("User '{user_id}' not found")));
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -294,7 +426,8 @@ mod tests {
                 must_change_password BOOLEAN DEFAULT 1,
                 failed_login_attempts INTEGER DEFAULT 0,
                 locked_until DATETIME,
-                created_by TEXT
+                created_by TEXT,
+                tenant_id TEXT
             )
             "#,
         )
@@ -566,4 +699,35 @@ mod tests {
         let updated_user = service.find_by_id(&user.id).await.unwrap().unwrap();
         assert!(updated_user.last_login_at.is_some());
     }
+
+    #[tokio::test]
+    async fn test_lock_unlock_and_set_password() {
+        let db = Arc::new(setup_test_db().await);
+        let service = UserServiceImpl::new(db);
+
+        let user = service
+            .create_user("lockme".to_string(), "old_password".to_string(), None)
+            .await
+            .unwrap();
+
+        // 管理员锁定后无法登录
+        service
+            .set_locked_until(&user.id, Some(Utc::now() + chrono::Duration::hours(1)))
+            .await
+            .unwrap();
+        assert!(service.authenticate("lockme", "old_password").await.is_err());
+
+        // 解锁并重置密码后可以使用新密码登录
+        service.set_locked_until(&user.id, None).await.unwrap();
+        service.set_password(&user.id, "new_password", true).await.unwrap();
+        assert!(service.authenticate("lockme", "old_password").await.is_err());
+        let user = service.authenticate("lockme", "new_password").await.unwrap();
+        assert!(user.must_change_password);
+
+        // 不存在的用户
+        assert!(matches!(
+            service.set_locked_until("missing", None).await,
+            Err(ServiceError::NotFound(_))
+        ));
+    }
 }
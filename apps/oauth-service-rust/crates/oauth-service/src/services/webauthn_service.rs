@@ -0,0 +1,1293 @@
+// 通行密钥服务 (WebAuthn Service)
+//
+// 实现 WebAuthn 注册和断言仪式 (只接受 "none" 证明):
+// 1. 注册: `start_registration` 生成挑战和 PublicKeyCredentialCreationOptions，
+//    `finish_registration` 校验 clientDataJSON、证明对象和认证器数据后保存 COSE 公钥
+// 2. 断言: `start_authentication` 生成 PublicKeyCredentialRequestOptions，
+//    `finish_authentication` 校验签名和签名计数器后返回对应的用户
+//
+// 断言既可以作为无密码登录的唯一因素 (要求用户验证)，也可以作为密码登录后的第二因素。
+
+use crate::config::Config;
+use crate::error::ServiceError;
+use crate::models::user::User;
+use crate::services::user_service::UserService;
+use async_trait::async_trait;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Duration, Utc};
+use ciborium::value::Value as CborValue;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 从生成挑战到完成仪式的最长时间
+const CHALLENGE_TTL_MINUTES: i64 = 5;
+
+/// 挑战的随机字节数
+const CHALLENGE_LENGTH: usize = 32;
+
+/// 凭据 ID 的最大长度 (WebAuthn 规范的上限)
+const MAX_CREDENTIAL_ID_LENGTH: usize = 1023;
+
+/// COSE 算法标识
+const COSE_ALG_ES256: i64 = -7;
+const COSE_ALG_EDDSA: i64 = -8;
+const COSE_ALG_RS256: i64 = -257;
+
+/// 按优先顺序提供给认证器的算法
+const SUPPORTED_ALGORITHMS: [i64; 3] = [COSE_ALG_ES256, COSE_ALG_EDDSA, COSE_ALG_RS256];
+
+/// 认证器数据中的标志位
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+/// WebAuthn 仪式的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ceremony {
+    /// 为当前用户注册认证器
+    Registration,
+    /// 无密码登录，通行密钥是唯一的因素，要求用户验证
+    Passwordless,
+    /// 密码验证通过后的第二因素
+    SecondFactor,
+}
+
+impl Ceremony {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Ceremony::Registration => "registration",
+            Ceremony::Passwordless => "passwordless",
+            Ceremony::SecondFactor => "second_factor",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "registration" => Some(Ceremony::Registration),
+            "passwordless" => Some(Ceremony::Passwordless),
+            "second_factor" => Some(Ceremony::SecondFactor),
+            _ => None,
+        }
+    }
+}
+
+/// 依赖方 (本服务) 的标识和允许发起仪式的页面源
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelyingParty {
+    pub id: String,
+    pub name: String,
+    pub origins: Vec<String>,
+}
+
+impl RelyingParty {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            id: config.webauthn_rp_id(),
+            name: config.webauthn.rp_name.clone(),
+            origins: config.webauthn_origins(),
+        }
+    }
+}
+
+/// 已注册的通行密钥
+#[derive(Debug, Clone, Serialize)]
+pub struct WebAuthnCredential {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    /// base64url 编码的凭据 ID
+    pub credential_id: String,
+    /// COSE 算法标识
+    pub algorithm: i64,
+    pub sign_count: i64,
+    pub transports: Vec<String>,
+    pub aaguid: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// 一次完成的通行密钥断言
+#[derive(Debug, Clone)]
+pub struct PasskeyLogin {
+    pub user: User,
+    pub ceremony: Ceremony,
+    /// 发起仪式时携带的重定向地址
+    pub redirect: Option<String>,
+}
+
+// --- 传给浏览器 navigator.credentials.create() / get() 的选项 ---
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialCreationOptions {
+    /// 完成注册时需要回传的挑战标识
+    pub challenge_id: String,
+    pub public_key: PublicKeyCredentialCreationOptions,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicKeyCredentialCreationOptions {
+    pub rp: RelyingPartyEntity,
+    pub user: UserEntity,
+    pub challenge: String,
+    pub pub_key_cred_params: Vec<CredentialParameters>,
+    pub timeout: u64,
+    pub exclude_credentials: Vec<CredentialDescriptor>,
+    pub authenticator_selection: AuthenticatorSelection,
+    pub attestation: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RelyingPartyEntity {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserEntity {
+    /// base64url 编码的用户句柄 (用户 ID)
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CredentialParameters {
+    pub r#type: String,
+    pub alg: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CredentialDescriptor {
+    pub r#type: String,
+    pub id: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub transports: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticatorSelection {
+    pub resident_key: String,
+    pub user_verification: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialRequestOptions {
+    /// 完成断言时需要回传的挑战标识
+    pub challenge_id: String,
+    pub public_key: PublicKeyCredentialRequestOptions,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicKeyCredentialRequestOptions {
+    pub challenge: String,
+    pub timeout: u64,
+    pub rp_id: String,
+    pub allow_credentials: Vec<CredentialDescriptor>,
+    pub user_verification: String,
+}
+
+// --- 浏览器返回的凭据 (PublicKeyCredential.toJSON() 格式，二进制字段为 base64url) ---
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RegistrationCredential {
+    pub id: String,
+    pub response: AuthenticatorAttestationResponse,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticatorAttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub attestation_object: String,
+    #[serde(default)]
+    pub transports: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthenticationCredential {
+    pub id: String,
+    pub response: AuthenticatorAssertionResponse,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticatorAssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub authenticator_data: String,
+    pub signature: String,
+    #[serde(default)]
+    pub user_handle: Option<String>,
+}
+
+#[async_trait]
+pub trait WebAuthnService: Send + Sync {
+    /// 为用户生成注册选项，已注册的认证器会被排除
+    async fn start_registration(&self, user: &User) -> Result<CredentialCreationOptions, ServiceError>;
+
+    /// 校验注册响应并保存通行密钥
+    async fn finish_registration(
+        &self,
+        user_id: &str,
+        challenge_id: &str,
+        name: Option<String>,
+        credential: &RegistrationCredential,
+    ) -> Result<WebAuthnCredential, ServiceError>;
+
+    /// 生成断言选项。第二因素必须指定用户；无密码登录未指定用户时使用可发现凭据
+    async fn start_authentication(
+        &self,
+        ceremony: Ceremony,
+        user_id: Option<&str>,
+        redirect: Option<String>,
+    ) -> Result<CredentialRequestOptions, ServiceError>;
+
+    /// 校验断言，更新签名计数器并返回对应的用户
+    async fn finish_authentication(
+        &self,
+        challenge_id: &str,
+        credential: &AuthenticationCredential,
+    ) -> Result<PasskeyLogin, ServiceError>;
+
+    /// 用户已注册的通行密钥
+    async fn list_credentials(&self, user_id: &str) -> Result<Vec<WebAuthnCredential>, ServiceError>;
+
+    /// 删除用户的一个通行密钥
+    async fn delete_credential(&self, user_id: &str, id: &str) -> Result<(), ServiceError>;
+}
+
+pub struct WebAuthnServiceImpl {
+    db: Arc<SqlitePool>,
+    user_service: Arc<dyn UserService>,
+    relying_party: RelyingParty,
+}
+
+impl WebAuthnServiceImpl {
+    pub fn new(
+        db: Arc<SqlitePool>,
+        user_service: Arc<dyn UserService>,
+        relying_party: RelyingParty,
+    ) -> Self {
+        Self {
+            db,
+            user_service,
+            relying_party,
+        }
+    }
+
+    /// 保存新挑战，返回 (挑战标识, base64url 编码的挑战)
+    async fn create_challenge(
+        &self,
+        ceremony: Ceremony,
+        user_id: Option<&str>,
+        redirect: Option<String>,
+    ) -> Result<(String, String), ServiceError> {
+        let mut bytes = [0u8; CHALLENGE_LENGTH];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let challenge = URL_SAFE_NO_PAD.encode(bytes);
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        sqlx::query(
+            "INSERT INTO webauthn_challenges (id, challenge, ceremony, user_id, redirect, created_at, expires_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&id)
+        .bind(&challenge)
+        .bind(ceremony.as_str())
+        .bind(user_id)
+        .bind(redirect)
+        .bind(now)
+        .bind(now + Duration::minutes(CHALLENGE_TTL_MINUTES))
+        .execute(&*self.db)
+        .await?;
+
+        Ok((id, challenge))
+    }
+
+    /// 消费挑战 (只能使用一次)，返回 (挑战, 仪式, 用户, 重定向地址)
+    async fn consume_challenge(
+        &self,
+        challenge_id: &str,
+    ) -> Result<(String, Ceremony, Option<String>, Option<String>), ServiceError> {
+        let pending: Option<(String, String, Option<String>, Option<String>)> = sqlx::query_as(
+            "DELETE FROM webauthn_challenges
+             WHERE id = ? AND datetime(expires_at) > datetime(?)
+             RETURNING challenge, ceremony, user_id, redirect",
+        )
+        .bind(challenge_id)
+        .bind(Utc::now())
+        .fetch_optional(&*self.db)
+        .await?;
+        let (challenge, ceremony, user_id, redirect) = pending.ok_or_else(|| {
+            ServiceError::ValidationError("Unknown or expired WebAuthn challenge".to_string())
+        })?;
+        let ceremony = Ceremony::parse(&ceremony)
+            .ok_or_else(|| ServiceError::Internal(format!("Unknown WebAuthn ceremony '{ceremony}'")))?;
+        Ok((challenge, ceremony, user_id, redirect))
+    }
+
+    async fn find_credential(&self, credential_id: &str) -> Result<Option<CredentialRow>, ServiceError> {
+        Ok(sqlx::query_as::<_, CredentialRow>(&format!(
+            "SELECT {CREDENTIAL_COLUMNS} FROM webauthn_credentials WHERE credential_id = ?"
+        ))
+        .bind(credential_id)
+        .fetch_optional(&*self.db)
+        .await?)
+    }
+
+    fn descriptors(credentials: &[WebAuthnCredential]) -> Vec<CredentialDescriptor> {
+        credentials
+            .iter()
+            .map(|credential| CredentialDescriptor {
+                r#type: "public-key".to_string(),
+                id: credential.credential_id.clone(),
+                transports: credential.transports.clone(),
+            })
+            .collect()
+    }
+
+    /// 校验 clientDataJSON 的类型、挑战和页面源
+    fn verify_client_data(
+        &self,
+        client_data_json: &[u8],
+        expected_type: &str,
+        expected_challenge: &str,
+    ) -> Result<(), ServiceError> {
+        let client_data: ClientData = serde_json::from_slice(client_data_json)
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid clientDataJSON: {e}")))?;
+        if client_data.r#type != expected_type {
+            return Err(ServiceError::ValidationError(format!(
+                "Unexpected client data type '{}'",
+                client_data.r#type
+            )));
+        }
+        if client_data.challenge != expected_challenge {
+            return Err(ServiceError::Unauthorized("WebAuthn challenge mismatch".to_string()));
+        }
+        if !self.relying_party.origins.contains(&client_data.origin) {
+            return Err(ServiceError::Unauthorized(format!(
+                "Origin '{}' is not allowed for WebAuthn",
+                client_data.origin
+            )));
+        }
+        if client_data.cross_origin == Some(true) {
+            return Err(ServiceError::Unauthorized(
+                "Cross-origin WebAuthn ceremonies are not allowed".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 校验认证器数据中的 RP ID 摘要和用户在场/用户验证标志
+    fn verify_authenticator_flags(
+        &self,
+        authenticator_data: &AuthenticatorData,
+        require_user_verification: bool,
+    ) -> Result<(), ServiceError> {
+        if authenticator_data.rp_id_hash[..] != Sha256::digest(self.relying_party.id.as_bytes())[..] {
+            return Err(ServiceError::Unauthorized("RP ID hash mismatch".to_string()));
+        }
+        if authenticator_data.flags & FLAG_USER_PRESENT == 0 {
+            return Err(ServiceError::Unauthorized("User presence is required".to_string()));
+        }
+        if require_user_verification && authenticator_data.flags & FLAG_USER_VERIFIED == 0 {
+            return Err(ServiceError::Unauthorized("User verification is required".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl WebAuthnService for WebAuthnServiceImpl {
+    async fn start_registration(&self, user: &User) -> Result<CredentialCreationOptions, ServiceError> {
+        let existing = self.list_credentials(&user.id).await?;
+        let (challenge_id, challenge) = self
+            .create_challenge(Ceremony::Registration, Some(&user.id), None)
+            .await?;
+
+        Ok(CredentialCreationOptions {
+            challenge_id,
+            public_key: PublicKeyCredentialCreationOptions {
+                rp: RelyingPartyEntity {
+                    id: self.relying_party.id.clone(),
+                    name: self.relying_party.name.clone(),
+                },
+                user: UserEntity {
+                    id: URL_SAFE_NO_PAD.encode(user.id.as_bytes()),
+                    name: user.username.clone(),
+                    display_name: user.display_name.clone().unwrap_or_else(|| user.username.clone()),
+                },
+                challenge,
+                pub_key_cred_params: SUPPORTED_ALGORITHMS
+                    .iter()
+                    .map(|alg| CredentialParameters {
+                        r#type: "public-key".to_string(),
+                        alg: *alg,
+                    })
+                    .collect(),
+                timeout: (CHALLENGE_TTL_MINUTES * 60 * 1000) as u64,
+                exclude_credentials: Self::descriptors(&existing),
+                // 优先创建可发现凭据，使其可以用于无密码登录
+                authenticator_selection: AuthenticatorSelection {
+                    resident_key: "preferred".to_string(),
+                    user_verification: "preferred".to_string(),
+                },
+                attestation: "none".to_string(),
+            },
+        })
+    }
+
+    async fn finish_registration(
+        &self,
+        user_id: &str,
+        challenge_id: &str,
+        name: Option<String>,
+        credential: &RegistrationCredential,
+    ) -> Result<WebAuthnCredential, ServiceError> {
+        let (challenge, ceremony, challenge_user_id, _) = self.consume_challenge(challenge_id).await?;
+        if ceremony != Ceremony::Registration || challenge_user_id.as_deref() != Some(user_id) {
+            return Err(ServiceError::ValidationError(
+                "WebAuthn challenge was not issued for this registration".to_string(),
+            ));
+        }
+
+        let client_data_json = decode_base64url("clientDataJSON", &credential.response.client_data_json)?;
+        self.verify_client_data(&client_data_json, "webauthn.create", &challenge)?;
+
+        let attestation_object =
+            decode_base64url("attestationObject", &credential.response.attestation_object)?;
+        let auth_data = parse_attestation_object(&attestation_object)?;
+        let authenticator_data = AuthenticatorData::parse(&auth_data)?;
+        self.verify_authenticator_flags(&authenticator_data, false)?;
+
+        let attested = authenticator_data.attested_credential.ok_or_else(|| {
+            ServiceError::ValidationError("Attested credential data is missing".to_string())
+        })?;
+        let credential_id = URL_SAFE_NO_PAD.encode(&attested.credential_id);
+        if credential_id != credential.id.trim_end_matches('=') {
+            return Err(ServiceError::ValidationError(
+                "Credential ID does not match the attested credential".to_string(),
+            ));
+        }
+        let algorithm = CosePublicKey::parse(&attested.public_key)?.algorithm();
+
+        if self.find_credential(&credential_id).await?.is_some() {
+            return Err(ServiceError::Conflict("Credential is already registered".to_string()));
+        }
+
+        let name = match name.map(|name| name.trim().to_string()).filter(|name| !name.is_empty()) {
+            Some(name) => name,
+            None => {
+                let count = self.list_credentials(user_id).await?.len();
+                format!("通行密钥 {}", count + 1)
+            }
+        };
+        let aaguid = (attested.aaguid != [0u8; 16]).then(|| Uuid::from_bytes(attested.aaguid).to_string());
+        let transports = serde_json::to_string(&credential.response.transports)
+            .map_err(|e| ServiceError::Internal(e.to_string()))?;
+        let id = Uuid::new_v4().to_string();
+
+        sqlx::query(
+            "INSERT INTO webauthn_credentials
+                (id, user_id, credential_id, public_key, algorithm, sign_count, transports, aaguid, name, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&id)
+        .bind(user_id)
+        .bind(&credential_id)
+        .bind(&attested.public_key)
+        .bind(algorithm)
+        .bind(authenticator_data.sign_count as i64)
+        .bind(transports)
+        .bind(aaguid)
+        .bind(name)
+        .bind(Utc::now())
+        .execute(&*self.db)
+        .await?;
+
+        tracing::info!("Registered WebAuthn credential {} for user {}", id, user_id);
+
+        self.list_credentials(user_id)
+            .await?
+            .into_iter()
+            .find(|credential| credential.id == id)
+            .ok_or_else(|| ServiceError::Internal("Registered credential not found".to_string()))
+    }
+
+    async fn start_authentication(
+        &self,
+        ceremony: Ceremony,
+        user_id: Option<&str>,
+        redirect: Option<String>,
+    ) -> Result<CredentialRequestOptions, ServiceError> {
+        let allow_credentials = match (ceremony, user_id) {
+            (Ceremony::Registration, _) => {
+                return Err(ServiceError::ValidationError(
+                    "Registration is not an authentication ceremony".to_string(),
+                ))
+            }
+            (Ceremony::SecondFactor, None) => {
+                return Err(ServiceError::ValidationError(
+                    "A user is required for second-factor authentication".to_string(),
+                ))
+            }
+            (_, Some(user_id)) => {
+                let credentials = self.list_credentials(user_id).await?;
+                if credentials.is_empty() {
+                    return Err(ServiceError::ValidationError(
+                        "User has no registered passkeys".to_string(),
+                    ));
+                }
+                Self::descriptors(&credentials)
+            }
+            (Ceremony::Passwordless, None) => Vec::new(),
+        };
+
+        let (challenge_id, challenge) = self.create_challenge(ceremony, user_id, redirect).await?;
+        let user_verification = if ceremony == Ceremony::Passwordless {
+            "required"
+        } else {
+            "preferred"
+        };
+
+        Ok(CredentialRequestOptions {
+            challenge_id,
+            public_key: PublicKeyCredentialRequestOptions {
+                challenge,
+                timeout: (CHALLENGE_TTL_MINUTES * 60 * 1000) as u64,
+                rp_id: self.relying_party.id.clone(),
+                allow_credentials,
+                user_verification: user_verification.to_string(),
+            },
+        })
+    }
+
+    async fn finish_authentication(
+        &self,
+        challenge_id: &str,
+        credential: &AuthenticationCredential,
+    ) -> Result<PasskeyLogin, ServiceError> {
+        let (challenge, ceremony, challenge_user_id, redirect) =
+            self.consume_challenge(challenge_id).await?;
+        if ceremony == Ceremony::Registration {
+            return Err(ServiceError::ValidationError(
+                "WebAuthn challenge was not issued for authentication".to_string(),
+            ));
+        }
+
+        let stored = self
+            .find_credential(credential.id.trim_end_matches('='))
+            .await?
+            .ok_or_else(|| ServiceError::Unauthorized("Unknown credential".to_string()))?;
+        // 第二因素的凭据必须属于已通过密码验证的用户
+        if challenge_user_id.as_deref().is_some_and(|user_id| user_id != stored.user_id) {
+            return Err(ServiceError::Unauthorized(
+                "Credential does not belong to this user".to_string(),
+            ));
+        }
+        if let Some(user_handle) = credential.response.user_handle.as_deref().filter(|h| !h.is_empty()) {
+            if decode_base64url("userHandle", user_handle)? != stored.user_id.as_bytes() {
+                return Err(ServiceError::Unauthorized(
+                    "User handle does not match the credential".to_string(),
+                ));
+            }
+        }
+
+        let client_data_json = decode_base64url("clientDataJSON", &credential.response.client_data_json)?;
+        self.verify_client_data(&client_data_json, "webauthn.get", &challenge)?;
+
+        let auth_data = decode_base64url("authenticatorData", &credential.response.authenticator_data)?;
+        let authenticator_data = AuthenticatorData::parse(&auth_data)?;
+        self.verify_authenticator_flags(&authenticator_data, ceremony == Ceremony::Passwordless)?;
+
+        // 签名覆盖 authenticatorData || SHA-256(clientDataJSON)
+        let mut signed = auth_data.clone();
+        signed.extend_from_slice(&Sha256::digest(&client_data_json));
+        let signature = decode_base64url("signature", &credential.response.signature)?;
+        CosePublicKey::parse(&stored.public_key)?.verify(&signed, &signature)?;
+
+        // 计数器不支持时始终为 0；否则必须递增，回退说明认证器可能被克隆
+        let sign_count = authenticator_data.sign_count as i64;
+        if (sign_count != 0 || stored.sign_count != 0) && sign_count <= stored.sign_count {
+            tracing::warn!(
+                "WebAuthn sign counter for credential {} went from {} to {}, possible cloned authenticator",
+                stored.id,
+                stored.sign_count,
+                sign_count
+            );
+            return Err(ServiceError::Unauthorized(
+                "Authenticator sign counter did not increase".to_string(),
+            ));
+        }
+        sqlx::query("UPDATE webauthn_credentials SET sign_count = ?, last_used_at = ? WHERE id = ?")
+            .bind(sign_count)
+            .bind(Utc::now())
+            .bind(&stored.id)
+            .execute(&*self.db)
+            .await?;
+
+        let user = self
+            .user_service
+            .find_by_id(&stored.user_id)
+            .await?
+            .ok_or_else(|| ServiceError::Unauthorized("Unknown credential".to_string()))?;
+        if !user.is_active {
+            return Err(ServiceError::Unauthorized("User account is inactive".to_string()));
+        }
+        if let Some(locked_until) = user.locked_until {
+            if locked_until > Utc::now() {
+                return Err(ServiceError::Unauthorized(format!(
+                    "Account is locked until {locked_until}"
+                )));
+            }
+        }
+
+        Ok(PasskeyLogin {
+            user,
+            ceremony,
+            redirect,
+        })
+    }
+
+    async fn list_credentials(&self, user_id: &str) -> Result<Vec<WebAuthnCredential>, ServiceError> {
+        let rows = sqlx::query_as::<_, CredentialRow>(&format!(
+            "SELECT {CREDENTIAL_COLUMNS} FROM webauthn_credentials WHERE user_id = ? ORDER BY created_at"
+        ))
+        .bind(user_id)
+        .fetch_all(&*self.db)
+        .await?;
+        Ok(rows.into_iter().map(CredentialRow::into_credential).collect())
+    }
+
+    async fn delete_credential(&self, user_id: &str, id: &str) -> Result<(), ServiceError> {
+        let result = sqlx::query("DELETE FROM webauthn_credentials WHERE id = ? AND user_id = ?")
+            .bind(id)
+            .bind(user_id)
+            .execute(&*self.db)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(ServiceError::NotFound(format!("Passkey {id} not found")));
+        }
+        Ok(())
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct CredentialRow {
+    id: String,
+    user_id: String,
+    credential_id: String,
+    public_key: Vec<u8>,
+    algorithm: i64,
+    sign_count: i64,
+    transports: Option<String>,
+    aaguid: Option<String>,
+    name: String,
+    created_at: DateTime<Utc>,
+    last_used_at: Option<DateTime<Utc>>,
+}
+
+const CREDENTIAL_COLUMNS: &str = "id, user_id, credential_id, public_key, algorithm, sign_count, \
+     transports, aaguid, name, created_at, last_used_at";
+
+impl CredentialRow {
+    fn into_credential(self) -> WebAuthnCredential {
+        WebAuthnCredential {
+            id: self.id,
+            user_id: self.user_id,
+            name: self.name,
+            credential_id: self.credential_id,
+            algorithm: self.algorithm,
+            sign_count: self.sign_count,
+            transports: self
+                .transports
+                .and_then(|transports| serde_json::from_str(&transports).ok())
+                .unwrap_or_default(),
+            aaguid: self.aaguid,
+            created_at: self.created_at,
+            last_used_at: self.last_used_at,
+        }
+    }
+}
+
+/// clientDataJSON 中用到的字段
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClientData {
+    r#type: String,
+    challenge: String,
+    origin: String,
+    #[serde(default)]
+    cross_origin: Option<bool>,
+}
+
+fn decode_base64url(field: &str, value: &str) -> Result<Vec<u8>, ServiceError> {
+    URL_SAFE_NO_PAD
+        .decode(value.trim_end_matches('='))
+        .map_err(|_| ServiceError::ValidationError(format!("{field} is not valid base64url")))
+}
+
+fn cbor_error(e: impl std::fmt::Display) -> ServiceError {
+    ServiceError::ValidationError(format!("Invalid CBOR: {e}"))
+}
+
+/// 解析证明对象，只接受 "none" 证明，返回其中的认证器数据
+fn parse_attestation_object(bytes: &[u8]) -> Result<Vec<u8>, ServiceError> {
+    let value: CborValue = ciborium::de::from_reader(bytes).map_err(cbor_error)?;
+    let entries = value
+        .into_map()
+        .map_err(|_| ServiceError::ValidationError("Attestation object is not a map".to_string()))?;
+
+    let (mut fmt, mut att_stmt, mut auth_data) = (None, None, None);
+    for (key, value) in entries {
+        match key.as_text() {
+            Some("fmt") => fmt = value.into_text().ok(),
+            Some("attStmt") => att_stmt = value.into_map().ok(),
+            Some("authData") => auth_data = value.into_bytes().ok(),
+            _ => {}
+        }
+    }
+    if fmt.as_deref() != Some("none") || !att_stmt.is_some_and(|statement| statement.is_empty()) {
+        return Err(ServiceError::ValidationError(
+            "Only \"none\" attestation is supported".to_string(),
+        ));
+    }
+    auth_data.ok_or_else(|| ServiceError::ValidationError("authData is missing".to_string()))
+}
+
+/// 认证器数据 (authenticatorData)
+#[derive(Debug)]
+struct AuthenticatorData {
+    rp_id_hash: Vec<u8>,
+    flags: u8,
+    sign_count: u32,
+    attested_credential: Option<AttestedCredential>,
+}
+
+#[derive(Debug)]
+struct AttestedCredential {
+    aaguid: [u8; 16],
+    credential_id: Vec<u8>,
+    /// COSE 格式的公钥
+    public_key: Vec<u8>,
+}
+
+impl AuthenticatorData {
+    fn parse(data: &[u8]) -> Result<Self, ServiceError> {
+        let truncated = || ServiceError::ValidationError("Authenticator data is truncated".to_string());
+        if data.len() < 37 {
+            return Err(truncated());
+        }
+        let flags = data[32];
+        let sign_count = u32::from_be_bytes([data[33], data[34], data[35], data[36]]);
+
+        let attested_credential = if flags & FLAG_ATTESTED_CREDENTIAL_DATA != 0 {
+            let rest = &data[37..];
+            if rest.len() < 18 {
+                return Err(truncated());
+            }
+            let aaguid: [u8; 16] = rest[..16].try_into().map_err(|_| truncated())?;
+            let id_len = u16::from_be_bytes([rest[16], rest[17]]) as usize;
+            if id_len > MAX_CREDENTIAL_ID_LENGTH || rest.len() < 18 + id_len {
+                return Err(truncated());
+            }
+            let credential_id = rest[18..18 + id_len].to_vec();
+
+            // 公钥是一个 CBOR 项，其后可能还有扩展数据
+            let key_bytes = &rest[18 + id_len..];
+            let mut reader = key_bytes;
+            let _: CborValue = ciborium::de::from_reader(&mut reader).map_err(cbor_error)?;
+            let public_key = key_bytes[..key_bytes.len() - reader.len()].to_vec();
+
+            Some(AttestedCredential {
+                aaguid,
+                credential_id,
+                public_key,
+            })
+        } else {
+            None
+        };
+
+        Ok(Self {
+            rp_id_hash: data[..32].to_vec(),
+            flags,
+            sign_count,
+            attested_credential,
+        })
+    }
+}
+
+/// 支持的 COSE 公钥
+#[derive(Debug)]
+enum CosePublicKey {
+    /// P-256 上的 ECDSA，使用 SHA-256
+    Es256 { x: Vec<u8>, y: Vec<u8> },
+    /// Ed25519
+    EdDsa { x: Vec<u8> },
+    /// RSASSA-PKCS1-v1_5，使用 SHA-256
+    Rs256 { n: Vec<u8>, e: Vec<u8> },
+}
+
+impl CosePublicKey {
+    fn parse(bytes: &[u8]) -> Result<Self, ServiceError> {
+        let value: CborValue = ciborium::de::from_reader(bytes).map_err(cbor_error)?;
+        let entries = value
+            .into_map()
+            .map_err(|_| ServiceError::ValidationError("COSE key is not a map".to_string()))?;
+        let param = |label: i64| {
+            entries
+                .iter()
+                .find(|(key, _)| key.as_integer() == Some(label.into()))
+                .map(|(_, value)| value)
+        };
+        let int_param = |label: i64| {
+            param(label)
+                .and_then(|value| value.as_integer())
+                .and_then(|value| i64::try_from(value).ok())
+        };
+        let bytes_param = |label: i64| param(label).and_then(|value| value.as_bytes()).cloned();
+
+        let invalid = || ServiceError::ValidationError("Unsupported or malformed COSE key".to_string());
+        // kty: 1 = OKP, 2 = EC2, 3 = RSA; crv: 1 = P-256, 6 = Ed25519
+        match (int_param(1), int_param(3)) {
+            (Some(2), Some(COSE_ALG_ES256)) if int_param(-1) == Some(1) => {
+                let (x, y) = (bytes_param(-2).ok_or_else(invalid)?, bytes_param(-3).ok_or_else(invalid)?);
+                if x.len() != 32 || y.len() != 32 {
+                    return Err(invalid());
+                }
+                Ok(CosePublicKey::Es256 { x, y })
+            }
+            (Some(1), Some(COSE_ALG_EDDSA)) if int_param(-1) == Some(6) => {
+                let x = bytes_param(-2).ok_or_else(invalid)?;
+                if x.len() != 32 {
+                    return Err(invalid());
+                }
+                Ok(CosePublicKey::EdDsa { x })
+            }
+            (Some(3), Some(COSE_ALG_RS256)) => Ok(CosePublicKey::Rs256 {
+                n: bytes_param(-1).ok_or_else(invalid)?,
+                e: bytes_param(-2).ok_or_else(invalid)?,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+
+    fn algorithm(&self) -> i64 {
+        match self {
+            CosePublicKey::Es256 { .. } => COSE_ALG_ES256,
+            CosePublicKey::EdDsa { .. } => COSE_ALG_EDDSA,
+            CosePublicKey::Rs256 { .. } => COSE_ALG_RS256,
+        }
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), ServiceError> {
+        use ring::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+
+        let result = match self {
+            CosePublicKey::Es256 { x, y } => {
+                let mut point = vec![0x04];
+                point.extend_from_slice(x);
+                point.extend_from_slice(y);
+                UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_ASN1, point).verify(message, signature)
+            }
+            CosePublicKey::EdDsa { x } => {
+                UnparsedPublicKey::new(&signature::ED25519, x).verify(message, signature)
+            }
+            CosePublicKey::Rs256 { n, e } => RsaPublicKeyComponents { n, e }.verify(
+                &signature::RSA_PKCS1_2048_8192_SHA256,
+                message,
+                signature,
+            ),
+        };
+        result.map_err(|_| ServiceError::Unauthorized("Invalid WebAuthn signature".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::test_support::{setup_state, TestAppState};
+    use ring::rand::SystemRandom;
+    use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING};
+    use serde_json::json;
+
+    const RP_ID: &str = "localhost";
+    const ORIGIN: &str = "https://localhost:3443";
+
+    /// 软件实现的认证器：P-256 密钥、"none" 证明、递增的签名计数器
+    struct SoftwareAuthenticator {
+        key_pair: EcdsaKeyPair,
+        credential_id: Vec<u8>,
+        sign_count: u32,
+        origin: String,
+    }
+
+    impl SoftwareAuthenticator {
+        fn new() -> Self {
+            let rng = SystemRandom::new();
+            let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+            let mut credential_id = vec![0u8; 16];
+            rand::thread_rng().fill_bytes(&mut credential_id);
+            Self {
+                key_pair: EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng)
+                    .unwrap(),
+                credential_id,
+                sign_count: 0,
+                origin: ORIGIN.to_string(),
+            }
+        }
+
+        fn id(&self) -> String {
+            URL_SAFE_NO_PAD.encode(&self.credential_id)
+        }
+
+        fn cose_key(&self) -> Vec<u8> {
+            let point = self.key_pair.public_key().as_ref();
+            let key = CborValue::Map(vec![
+                (1.into(), 2.into()),
+                (3.into(), COSE_ALG_ES256.into()),
+                ((-1).into(), 1.into()),
+                ((-2).into(), CborValue::Bytes(point[1..33].to_vec())),
+                ((-3).into(), CborValue::Bytes(point[33..].to_vec())),
+            ]);
+            let mut bytes = Vec::new();
+            ciborium::ser::into_writer(&key, &mut bytes).unwrap();
+            bytes
+        }
+
+        fn authenticator_data(&self, flags: u8, attested: bool) -> Vec<u8> {
+            let mut data = Sha256::digest(RP_ID.as_bytes()).to_vec();
+            data.push(flags);
+            data.extend_from_slice(&self.sign_count.to_be_bytes());
+            if attested {
+                data.extend_from_slice(&[0u8; 16]);
+                data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+                data.extend_from_slice(&self.credential_id);
+                data.extend_from_slice(&self.cose_key());
+            }
+            data
+        }
+
+        fn client_data(&self, r#type: &str, challenge: &str) -> Vec<u8> {
+            serde_json::to_vec(&json!({ "type": r#type, "challenge": challenge, "origin": self.origin }))
+                .unwrap()
+        }
+
+        fn register(&self, options: &CredentialCreationOptions) -> RegistrationCredential {
+            let auth_data = self.authenticator_data(FLAG_USER_PRESENT | FLAG_ATTESTED_CREDENTIAL_DATA, true);
+            let attestation = CborValue::Map(vec![
+                ("fmt".into(), "none".into()),
+                ("attStmt".into(), CborValue::Map(vec![])),
+                ("authData".into(), CborValue::Bytes(auth_data)),
+            ]);
+            let mut attestation_object = Vec::new();
+            ciborium::ser::into_writer(&attestation, &mut attestation_object).unwrap();
+            RegistrationCredential {
+                id: self.id(),
+                response: AuthenticatorAttestationResponse {
+                    client_data_json: URL_SAFE_NO_PAD
+                        .encode(self.client_data("webauthn.create", &options.public_key.challenge)),
+                    attestation_object: URL_SAFE_NO_PAD.encode(attestation_object),
+                    transports: vec!["internal".to_string()],
+                },
+            }
+        }
+
+        /// 签名一次断言；`user_handle` 为可发现凭据返回的用户句柄
+        fn assert(
+            &mut self,
+            options: &CredentialRequestOptions,
+            user_verified: bool,
+            user_handle: Option<&str>,
+        ) -> AuthenticationCredential {
+            self.sign_count += 1;
+            let flags = FLAG_USER_PRESENT | if user_verified { FLAG_USER_VERIFIED } else { 0 };
+            let auth_data = self.authenticator_data(flags, false);
+            let client_data = self.client_data("webauthn.get", &options.public_key.challenge);
+            let mut signed = auth_data.clone();
+            signed.extend_from_slice(&Sha256::digest(&client_data));
+            let signature = self.key_pair.sign(&SystemRandom::new(), &signed).unwrap();
+            AuthenticationCredential {
+                id: self.id(),
+                response: AuthenticatorAssertionResponse {
+                    client_data_json: URL_SAFE_NO_PAD.encode(client_data),
+                    authenticator_data: URL_SAFE_NO_PAD.encode(auth_data),
+                    signature: URL_SAFE_NO_PAD.encode(signature.as_ref()),
+                    user_handle: user_handle.map(|id| URL_SAFE_NO_PAD.encode(id.as_bytes())),
+                },
+            }
+        }
+    }
+
+    async fn setup() -> (TestAppState, WebAuthnServiceImpl) {
+        let (state, pool) = setup_state().await;
+        let service = WebAuthnServiceImpl::new(
+            pool,
+            state.user_service.clone(),
+            RelyingParty {
+                id: RP_ID.to_string(),
+                name: "Test".to_string(),
+                origins: vec![ORIGIN.to_string()],
+            },
+        );
+        (state, service)
+    }
+
+    async fn register(service: &WebAuthnServiceImpl, user: &User, authenticator: &SoftwareAuthenticator) {
+        let options = service.start_registration(user).await.unwrap();
+        service
+            .finish_registration(&user.id, &options.challenge_id, None, &authenticator.register(&options))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_registration_and_passwordless_login() {
+        let (state, service) = setup().await;
+        let user = state
+            .user_service
+            .create_user("alice".to_string(), "password123".to_string(), None)
+            .await
+            .unwrap();
+
+        let phone = SoftwareAuthenticator::new();
+        let options = service.start_registration(&user).await.unwrap();
+        assert_eq!(options.public_key.attestation, "none");
+        assert_eq!(options.public_key.user.id, URL_SAFE_NO_PAD.encode(user.id.as_bytes()));
+        let credential = service
+            .finish_registration(&user.id, &options.challenge_id, Some("Phone".to_string()), &phone.register(&options))
+            .await
+            .unwrap();
+        assert_eq!(credential.name, "Phone");
+        assert_eq!(credential.credential_id, phone.id());
+        assert_eq!(credential.algorithm, COSE_ALG_ES256);
+        assert_eq!(credential.transports, vec!["internal"]);
+
+        // 同一用户可以注册多个认证器，已注册的会被排除
+        let mut laptop = SoftwareAuthenticator::new();
+        let options = service.start_registration(&user).await.unwrap();
+        assert_eq!(options.public_key.exclude_credentials[0].id, phone.id());
+        service
+            .finish_registration(&user.id, &options.challenge_id, None, &laptop.register(&options))
+            .await
+            .unwrap();
+        let credentials = service.list_credentials(&user.id).await.unwrap();
+        assert_eq!(credentials.len(), 2);
+        assert_eq!(credentials[1].name, "通行密钥 2");
+
+        // 同一凭据不能重复注册
+        let options = service.start_registration(&user).await.unwrap();
+        assert!(matches!(
+            service
+                .finish_registration(&user.id, &options.challenge_id, None, &laptop.register(&options))
+                .await,
+            Err(ServiceError::Conflict(_))
+        ));
+
+        // 无密码登录 (可发现凭据) 要求用户验证
+        let options = service
+            .start_authentication(Ceremony::Passwordless, None, Some("/authorize".to_string()))
+            .await
+            .unwrap();
+        assert!(options.public_key.allow_credentials.is_empty());
+        assert_eq!(options.public_key.user_verification, "required");
+        let login = service
+            .finish_authentication(&options.challenge_id, &laptop.assert(&options, true, Some(&user.id)))
+            .await
+            .unwrap();
+        assert_eq!(login.user.id, user.id);
+        assert_eq!(login.ceremony, Ceremony::Passwordless);
+        assert_eq!(login.redirect.as_deref(), Some("/authorize"));
+        let stored = service.list_credentials(&user.id).await.unwrap();
+        assert_eq!(stored[1].sign_count, 1);
+        assert!(stored[1].last_used_at.is_some());
+
+        let options = service.start_authentication(Ceremony::Passwordless, None, None).await.unwrap();
+        assert!(matches!(
+            service
+                .finish_authentication(&options.challenge_id, &laptop.assert(&options, false, None))
+                .await,
+            Err(ServiceError::Unauthorized(_))
+        ));
+
+        service.delete_credential(&user.id, &credentials[0].id).await.unwrap();
+        assert_eq!(service.list_credentials(&user.id).await.unwrap().len(), 1);
+        assert!(matches!(
+            service.delete_credential(&user.id, &credentials[0].id).await,
+            Err(ServiceError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_second_factor_assertions() {
+        let (state, service) = setup().await;
+        let alice = state
+            .user_service
+            .create_user("alice".to_string(), "password123".to_string(), None)
+            .await
+            .unwrap();
+        let bob = state
+            .user_service
+            .create_user("bob".to_string(), "password123".to_string(), None)
+            .await
+            .unwrap();
+        let mut alice_key = SoftwareAuthenticator::new();
+        let mut bob_key = SoftwareAuthenticator::new();
+        register(&service, &alice, &alice_key).await;
+        register(&service, &bob, &bob_key).await;
+
+        assert!(matches!(
+            service.start_authentication(Ceremony::SecondFactor, None, None).await,
+            Err(ServiceError::ValidationError(_))
+        ));
+
+        // 第二因素不要求用户验证，但凭据必须属于通过密码验证的用户
+        let options = service
+            .start_authentication(Ceremony::SecondFactor, Some(&alice.id), None)
+            .await
+            .unwrap();
+        assert_eq!(options.public_key.allow_credentials.len(), 1);
+        assert_eq!(options.public_key.allow_credentials[0].id, alice_key.id());
+        assert!(matches!(
+            service
+                .finish_authentication(&options.challenge_id, &bob_key.assert(&options, false, None))
+                .await,
+            Err(ServiceError::Unauthorized(_))
+        ));
+
+        let options = service
+            .start_authentication(Ceremony::SecondFactor, Some(&alice.id), None)
+            .await
+            .unwrap();
+        let assertion = alice_key.assert(&options, false, None);
+        let login = service.finish_authentication(&options.challenge_id, &assertion).await.unwrap();
+        assert_eq!(login.user.id, alice.id);
+        assert_eq!(login.ceremony, Ceremony::SecondFactor);
+
+        // 挑战只能使用一次
+        assert!(matches!(
+            service.finish_authentication(&options.challenge_id, &assertion).await,
+            Err(ServiceError::ValidationError(_))
+        ));
+
+        // 签名计数器回退说明认证器可能被克隆
+        alice_key.sign_count = 0;
+        let options = service
+            .start_authentication(Ceremony::SecondFactor, Some(&alice.id), None)
+            .await
+            .unwrap();
+        assert!(matches!(
+            service
+                .finish_authentication(&options.challenge_id, &alice_key.assert(&options, false, None))
+                .await,
+            Err(ServiceError::Unauthorized(_))
+        ));
+
+        // 不允许的页面源
+        alice_key.sign_count = 10;
+        alice_key.origin = "https://evil.example".to_string();
+        let options = service
+            .start_authentication(Ceremony::SecondFactor, Some(&alice.id), None)
+            .await
+            .unwrap();
+        assert!(matches!(
+            service
+                .finish_authentication(&options.challenge_id, &alice_key.assert(&options, false, None))
+                .await,
+            Err(ServiceError::Unauthorized(_))
+        ));
+
+        // 停用的用户不能登录
+        sqlx::query("UPDATE users SET is_active = 0 WHERE id = ?")
+            .bind(&bob.id)
+            .execute(&*service.db)
+            .await
+            .unwrap();
+        let options = service.start_authentication(Ceremony::Passwordless, None, None).await.unwrap();
+        assert!(matches!(
+            service
+                .finish_authentication(&options.challenge_id, &bob_key.assert(&options, true, Some(&bob.id)))
+                .await,
+            Err(ServiceError::Unauthorized(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_registrations() {
+        let (state, service) = setup().await;
+        let alice = state
+            .user_service
+            .create_user("alice".to_string(), "password123".to_string(), None)
+            .await
+            .unwrap();
+        let bob = state
+            .user_service
+            .create_user("bob".to_string(), "password123".to_string(), None)
+            .await
+            .unwrap();
+        let authenticator = SoftwareAuthenticator::new();
+
+        // 为其他用户生成的挑战
+        let options = service.start_registration(&alice).await.unwrap();
+        assert!(matches!(
+            service
+                .finish_registration(&bob.id, &options.challenge_id, None, &authenticator.register(&options))
+                .await,
+            Err(ServiceError::ValidationError(_))
+        ));
+
+        // 断言挑战不能用于注册
+        let options = service.start_authentication(Ceremony::Passwordless, None, None).await.unwrap();
+        let creation = service.start_registration(&alice).await.unwrap();
+        let mut credential = authenticator.register(&creation);
+        assert!(matches!(
+            service
+                .finish_registration(&alice.id, &options.challenge_id, None, &credential)
+                .await,
+            Err(ServiceError::ValidationError(_))
+        ));
+
+        // 只接受 "none" 证明
+        let attestation = CborValue::Map(vec![
+            ("fmt".into(), "packed".into()),
+            ("attStmt".into(), CborValue::Map(vec![("alg".into(), COSE_ALG_ES256.into())])),
+            (
+                "authData".into(),
+                CborValue::Bytes(authenticator.authenticator_data(FLAG_USER_PRESENT | FLAG_ATTESTED_CREDENTIAL_DATA, true)),
+            ),
+        ]);
+        let mut attestation_object = Vec::new();
+        ciborium::ser::into_writer(&attestation, &mut attestation_object).unwrap();
+        credential.response.attestation_object = URL_SAFE_NO_PAD.encode(attestation_object);
+        assert!(matches!(
+            service
+                .finish_registration(&alice.id, &creation.challenge_id, None, &credential)
+                .await,
+            Err(ServiceError::ValidationError(_))
+        ));
+
+        // 凭据 ID 与证明中的不一致
+        let options = service.start_registration(&alice).await.unwrap();
+        let mut credential = authenticator.register(&options);
+        credential.id = URL_SAFE_NO_PAD.encode(b"another-id");
+        assert!(matches!(
+            service
+                .finish_registration(&alice.id, &options.challenge_id, None, &credential)
+                .await,
+            Err(ServiceError::ValidationError(_))
+        ));
+        assert!(service.list_credentials(&alice.id).await.unwrap().is_empty());
+    }
+}
@@ -1,23 +1,42 @@
 use crate::config::Config;
+use crate::db::connect_database;
 use crate::error::AppError;
 use crate::middleware::rate_limit::RateLimiter;
 use crate::middleware::login_rate_limit::LoginRateLimiter;
 use crate::services::{
+    access_request_service::{AccessRequestService, AccessRequestServiceImpl},
     audit_log_service::{AuditLogService, AuditLogServiceImpl},
     auth_code_service::{AuthCodeService, AuthCodeServiceImpl},
+    authz_service::{AuthzService, AuthzServiceImpl},
     client_service::{ClientService, ClientServiceImpl},
+    dpop_service::{DpopService, DpopServiceImpl},
+    federation_service::{FederationService, FederationServiceImpl},
+    impersonation_service::{ImpersonationService, ImpersonationServiceImpl},
+    maintenance_service::{MaintenanceService, MaintenanceServiceImpl},
+    menu_service::{MenuService, MenuServiceImpl},
     permission_service::{PermissionService, PermissionServiceImpl},
+    rbac_definition_service::{RbacDefinitionService, RbacDefinitionServiceImpl},
     rbac_service::{RBACService, RBACServiceImpl},
+    resource_server_service::{ResourceServerService, ResourceServerServiceImpl},
     role_service::{RoleService, RoleServiceImpl},
+    scim_service::{ScimService, ScimServiceImpl},
+    scope_service::{ScopeService, ScopeServiceImpl},
+    system_config_service::{RuntimeSettings, SystemConfigService, SystemConfigServiceImpl},
+    tenant_service::{TenantService, TenantServiceImpl},
+    token_revocation_service::{TokenRevocationService, TokenRevocationServiceImpl},
     token_service::{TokenService, TokenServiceImpl},
     user_service::{UserService, UserServiceImpl},
+    webauthn_service::{RelyingParty, WebAuthnService, WebAuthnServiceImpl},
 };
 use crate::cache::permission_cache::{PermissionCache, InMemoryPermissionCache};
-use sqlx::sqlite::SqlitePoolOptions;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// The application state, containing all shared services and resources.
 pub struct AppState {
+    pub config: Arc<Config>,
+    /// 数据库连接池 (用于连接池指标等)
+    pub db_pool: Arc<sqlx::SqlitePool>,
     pub user_service: Arc<dyn UserService>,
     pub client_service: Arc<dyn ClientService>,
     pub token_service: Arc<dyn TokenService>,
@@ -25,7 +44,22 @@ pub struct AppState {
     pub rbac_service: Arc<dyn RBACService>,
     pub permission_service: Arc<dyn PermissionService>,
     pub role_service: Arc<dyn RoleService>,
+    pub menu_service: Arc<dyn MenuService>,
+    pub scope_service: Arc<dyn ScopeService>,
+    pub system_config_service: Arc<dyn SystemConfigService>,
     pub audit_log_service: Arc<dyn AuditLogService>,
+    pub maintenance_service: Arc<dyn MaintenanceService>,
+    pub rbac_definition_service: Arc<dyn RbacDefinitionService>,
+    pub scim_service: Arc<dyn ScimService>,
+    pub federation_service: Arc<dyn FederationService>,
+    pub webauthn_service: Arc<dyn WebAuthnService>,
+    pub tenant_service: Arc<dyn TenantService>,
+    pub impersonation_service: Arc<dyn ImpersonationService>,
+    pub resource_server_service: Arc<dyn ResourceServerService>,
+    pub dpop_service: Arc<dyn DpopService>,
+    pub authz_service: Arc<dyn AuthzService>,
+    pub access_request_service: Arc<dyn AccessRequestService>,
+    pub token_revocation_service: Arc<dyn TokenRevocationService>,
     pub permission_cache: Arc<dyn PermissionCache>,
     pub rate_limiter: Arc<RateLimiter>,
     pub login_rate_limiter: Arc<LoginRateLimiter>,
@@ -36,24 +70,33 @@ impl AppState {
     /// Creates a new instance of the application state.
     pub async fn new(config: Config) -> Result<Self, AppError> {
         // Create a connection pool for the database
-        let pool = SqlitePoolOptions::new()
-            .max_connections(10)
-            .connect(&config.database_url)
-            .await?;
-        let db_pool = Arc::new(pool);
+        let db_pool = Arc::new(connect_database(&config).await?);
         let config = Arc::new(config);
 
         // Initialize cache with 1000 user capacity
         let permission_cache = Arc::new(InMemoryPermissionCache::with_capacity(1000));
 
-        // Initialize rate limiter (100 req/min per IP)
-        let rate_limiter = Arc::new(RateLimiter::new(100, 60));
+        // Load runtime settings (rate limits, CORS origins, ...) from system_configurations
+        let system_config_service = Arc::new(SystemConfigServiceImpl::new(
+            db_pool.clone(),
+            RuntimeSettings::from_config(&config),
+        ));
+        let settings = system_config_service.reload().await?;
 
-        // Initialize login rate limiter (5 attempts / 5 min per IP)
+        // Initialize rate limiters (API, token endpoint, login) from runtime settings
+        let rate_limiter = Arc::new(RateLimiter::new(
+            settings.api_rate_limit.max_requests,
+            settings.api_rate_limit.window_secs,
+        ));
         let login_rate_limiter = Arc::new(LoginRateLimiter::new());
-
-        // Initialize token rate limiter (20 req/min per IP)
-        let token_rate_limiter = Arc::new(RateLimiter::new(20, 60));
+        login_rate_limiter.set_limits(
+            settings.login_rate_limit.max_requests,
+            Duration::from_secs(settings.login_rate_limit.window_secs),
+        );
+        let token_rate_limiter = Arc::new(RateLimiter::new(
+            settings.token_rate_limit.max_requests,
+            settings.token_rate_limit.window_secs,
+        ));
 
         // Initialize services
         let user_service = Arc::new(UserServiceImpl::new(db_pool.clone()));
@@ -61,11 +104,14 @@ impl AppState {
         let rbac_service = Arc::new(RBACServiceImpl::new(db_pool.clone(), permission_cache.clone()));
         let permission_service = Arc::new(PermissionServiceImpl::new(db_pool.clone()));
         let role_service = Arc::new(RoleServiceImpl::new(db_pool.clone(), permission_cache.clone()));
+        let menu_service = Arc::new(MenuServiceImpl::new(db_pool.clone(), rbac_service.clone()));
+        let scope_service = Arc::new(ScopeServiceImpl::new(db_pool.clone()));
         let token_service = Arc::new(TokenServiceImpl::new(
             db_pool.clone(),
             client_service.clone(),
             rbac_service.clone(),
             user_service.clone(),
+            scope_service.clone(),
             config.clone(),
         ));
         let auth_code_service = Arc::new(AuthCodeServiceImpl::new(
@@ -73,8 +119,79 @@ impl AppState {
             client_service.clone(),
         ));
         let audit_log_service = Arc::new(AuditLogServiceImpl::new(db_pool.clone()));
+        let maintenance_service = Arc::new(
+            MaintenanceServiceImpl::new(
+                db_pool.clone(),
+                role_service.clone(),
+                audit_log_service.clone(),
+                config.maintenance.batch_size,
+            )
+            .with_rate_limiters(
+                vec![rate_limiter.clone(), token_rate_limiter.clone()],
+                login_rate_limiter.clone(),
+            ),
+        );
+        let rbac_definition_service = Arc::new(RbacDefinitionServiceImpl::new(
+            db_pool.clone(),
+            permission_cache.clone(),
+            permission_service.clone(),
+            role_service.clone(),
+            scope_service.clone(),
+            client_service.clone(),
+            audit_log_service.clone(),
+        ));
+        let scim_service = Arc::new(ScimServiceImpl::new(
+            db_pool.clone(),
+            user_service.clone(),
+            role_service.clone(),
+            token_service.clone(),
+            &config.issuer,
+        ));
+        let federation_service = Arc::new(FederationServiceImpl::new(
+            db_pool.clone(),
+            user_service.clone(),
+            role_service.clone(),
+            config.federation.providers.clone(),
+            &config.issuer,
+        ));
+        let webauthn_service = Arc::new(WebAuthnServiceImpl::new(
+            db_pool.clone(),
+            user_service.clone(),
+            RelyingParty::from_config(&config),
+        ));
+        let tenant_service = Arc::new(TenantServiceImpl::new(db_pool.clone()));
+        let resource_server_service = Arc::new(ResourceServerServiceImpl::new(db_pool.clone()));
+        let dpop_service = Arc::new(DpopServiceImpl::new(db_pool.clone()));
+        let authz_service = Arc::new(AuthzServiceImpl::new(
+            user_service.clone(),
+            client_service.clone(),
+            rbac_service.clone(),
+        ));
+        let impersonation_service = Arc::new(ImpersonationServiceImpl::new(
+            db_pool.clone(),
+            user_service.clone(),
+            client_service.clone(),
+            rbac_service.clone(),
+            token_service.clone(),
+            audit_log_service.clone(),
+        ));
+        let access_request_service = Arc::new(AccessRequestServiceImpl::new(
+            db_pool.clone(),
+            user_service.clone(),
+            role_service.clone(),
+            audit_log_service.clone(),
+        ));
+        let token_revocation_service = Arc::new(TokenRevocationServiceImpl::new(
+            db_pool.clone(),
+            user_service.clone(),
+            client_service.clone(),
+            token_service.clone(),
+            audit_log_service.clone(),
+        ));
 
         Ok(Self {
+            config,
+            db_pool,
             user_service,
             client_service,
             token_service,
@@ -82,7 +199,22 @@ impl AppState {
             rbac_service,
             permission_service,
             role_service,
+            menu_service,
+            scope_service,
+            system_config_service,
             audit_log_service,
+            maintenance_service,
+            rbac_definition_service,
+            scim_service,
+            federation_service,
+            webauthn_service,
+            tenant_service,
+            impersonation_service,
+            resource_server_service,
+            dpop_service,
+            authz_service,
+            access_request_service,
+            token_revocation_service,
             permission_cache,
             rate_limiter,
             login_rate_limiter,
@@ -98,14 +230,27 @@ impl AppState {
         // Initialize cache with 1000 user capacity
         let permission_cache = Arc::new(InMemoryPermissionCache::with_capacity(1000));
 
-        // Initialize rate limiter (100 req/min per IP)
-        let rate_limiter = Arc::new(RateLimiter::new(100, 60));
+        // Load runtime settings (rate limits, CORS origins, ...) from system_configurations
+        let system_config_service = Arc::new(SystemConfigServiceImpl::new(
+            pool.clone(),
+            RuntimeSettings::from_config(&config),
+        ));
+        let settings = system_config_service.reload().await?;
 
-        // Initialize login rate limiter (5 attempts / 5 min per IP)
+        // Initialize rate limiters (API, token endpoint, login) from runtime settings
+        let rate_limiter = Arc::new(RateLimiter::new(
+            settings.api_rate_limit.max_requests,
+            settings.api_rate_limit.window_secs,
+        ));
         let login_rate_limiter = Arc::new(LoginRateLimiter::new());
-
-        // Initialize token rate limiter (20 req/min per IP)
-        let token_rate_limiter = Arc::new(RateLimiter::new(20, 60));
+        login_rate_limiter.set_limits(
+            settings.login_rate_limit.max_requests,
+            Duration::from_secs(settings.login_rate_limit.window_secs),
+        );
+        let token_rate_limiter = Arc::new(RateLimiter::new(
+            settings.token_rate_limit.max_requests,
+            settings.token_rate_limit.window_secs,
+        ));
 
         // Initialize services
         let user_service = Arc::new(UserServiceImpl::new(pool.clone()));
@@ -113,11 +258,14 @@ impl AppState {
         let rbac_service = Arc::new(RBACServiceImpl::new(pool.clone(), permission_cache.clone()));
         let permission_service = Arc::new(PermissionServiceImpl::new(pool.clone()));
         let role_service = Arc::new(RoleServiceImpl::new(pool.clone(), permission_cache.clone()));
+        let menu_service = Arc::new(MenuServiceImpl::new(pool.clone(), rbac_service.clone()));
+        let scope_service = Arc::new(ScopeServiceImpl::new(pool.clone()));
         let token_service = Arc::new(TokenServiceImpl::new(
             pool.clone(),
             client_service.clone(),
             rbac_service.clone(),
             user_service.clone(),
+            scope_service.clone(),
             config.clone(),
         ));
         let auth_code_service = Arc::new(AuthCodeServiceImpl::new(
@@ -125,8 +273,79 @@ impl AppState {
             client_service.clone(),
         ));
         let audit_log_service = Arc::new(AuditLogServiceImpl::new(pool.clone()));
+        let maintenance_service = Arc::new(
+            MaintenanceServiceImpl::new(
+                pool.clone(),
+                role_service.clone(),
+                audit_log_service.clone(),
+                config.maintenance.batch_size,
+            )
+            .with_rate_limiters(
+                vec![rate_limiter.clone(), token_rate_limiter.clone()],
+                login_rate_limiter.clone(),
+            ),
+        );
+        let rbac_definition_service = Arc::new(RbacDefinitionServiceImpl::new(
+            pool.clone(),
+            permission_cache.clone(),
+            permission_service.clone(),
+            role_service.clone(),
+            scope_service.clone(),
+            client_service.clone(),
+            audit_log_service.clone(),
+        ));
+        let scim_service = Arc::new(ScimServiceImpl::new(
+            pool.clone(),
+            user_service.clone(),
+            role_service.clone(),
+            token_service.clone(),
+            &config.issuer,
+        ));
+        let federation_service = Arc::new(FederationServiceImpl::new(
+            pool.clone(),
+            user_service.clone(),
+            role_service.clone(),
+            config.federation.providers.clone(),
+            &config.issuer,
+        ));
+        let webauthn_service = Arc::new(WebAuthnServiceImpl::new(
+            pool.clone(),
+            user_service.clone(),
+            RelyingParty::from_config(&config),
+        ));
+        let tenant_service = Arc::new(TenantServiceImpl::new(pool.clone()));
+        let resource_server_service = Arc::new(ResourceServerServiceImpl::new(pool.clone()));
+        let dpop_service = Arc::new(DpopServiceImpl::new(pool.clone()));
+        let authz_service = Arc::new(AuthzServiceImpl::new(
+            user_service.clone(),
+            client_service.clone(),
+            rbac_service.clone(),
+        ));
+        let impersonation_service = Arc::new(ImpersonationServiceImpl::new(
+            pool.clone(),
+            user_service.clone(),
+            client_service.clone(),
+            rbac_service.clone(),
+            token_service.clone(),
+            audit_log_service.clone(),
+        ));
+        let access_request_service = Arc::new(AccessRequestServiceImpl::new(
+            pool.clone(),
+            user_service.clone(),
+            role_service.clone(),
+            audit_log_service.clone(),
+        ));
+        let token_revocation_service = Arc::new(TokenRevocationServiceImpl::new(
+            pool.clone(),
+            user_service.clone(),
+            client_service.clone(),
+            token_service.clone(),
+            audit_log_service.clone(),
+        ));
 
         Ok(Self {
+            config,
+            db_pool: pool,
             user_service,
             client_service,
             token_service,
@@ -134,11 +353,145 @@ impl AppState {
             rbac_service,
             permission_service,
             role_service,
+            menu_service,
+            scope_service,
+            system_config_service,
             audit_log_service,
+            maintenance_service,
+            rbac_definition_service,
+            scim_service,
+            federation_service,
+            webauthn_service,
+            tenant_service,
+            impersonation_service,
+            resource_server_service,
+            dpop_service,
+            authz_service,
+            access_request_service,
+            token_revocation_service,
             permission_cache,
             rate_limiter,
             login_rate_limiter,
             token_rate_limiter,
         })
     }
+
+    /// Applies updated runtime settings to the long-lived components (rate limiters).
+    ///
+    /// Settings read per request (CORS origins, redirect origins, portal URLs) need no action.
+    pub fn apply_runtime_settings(&self, settings: &RuntimeSettings) {
+        self.rate_limiter.set_limits(
+            settings.api_rate_limit.max_requests,
+            settings.api_rate_limit.window_secs,
+        );
+        self.token_rate_limiter.set_limits(
+            settings.token_rate_limit.max_requests,
+            settings.token_rate_limit.window_secs,
+        );
+        self.login_rate_limiter.set_limits(
+            settings.login_rate_limit.max_requests,
+            Duration::from_secs(settings.login_rate_limit.window_secs),
+        );
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+    use crate::migrations::Migrator;
+    use crate::models::client::OAuthClientDetails;
+    use crate::routes::clients::CreateClientRequest;
+    use sqlx::SqlitePool;
+    use std::ops::Deref;
+    use std::path::{Path, PathBuf};
+
+    /// 测试用的应用状态，同时持有签发令牌用的临时 HS256 密钥文件。
+    ///
+    /// 密钥在签发令牌时才读取，因此文件与状态同生命周期，drop 时删除。
+    pub(crate) struct TestAppState {
+        state: AppState,
+        key_path: PathBuf,
+    }
+
+    impl Deref for TestAppState {
+        type Target = AppState;
+
+        fn deref(&self) -> &AppState {
+            &self.state
+        }
+    }
+
+    impl Drop for TestAppState {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.key_path);
+        }
+    }
+
+    /// 应用工作区的迁移 (含种子数据) 后构建应用状态
+    pub(crate) async fn setup_state() -> (TestAppState, Arc<SqlitePool>) {
+        setup_state_with_config(Config::default()).await
+    }
+
+    /// 同 [`setup_state`]，`jwt_private_key_path` 以外的配置取自 `config`
+    pub(crate) async fn setup_state_with_config(
+        config: Config,
+    ) -> (TestAppState, Arc<SqlitePool>) {
+        let pool = Arc::new(SqlitePool::connect("sqlite::memory:").await.unwrap());
+        let migrations = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../migrations");
+        Migrator::load(pool.clone(), &migrations)
+            .unwrap()
+            .up()
+            .await
+            .unwrap();
+
+        let key_path =
+            std::env::temp_dir().join(format!("oauth-service-test-{}.key", uuid::Uuid::new_v4()));
+        std::fs::write(&key_path, "oauth_service_test_secret_key_for_testing_only").unwrap();
+        let config = Config {
+            jwt_private_key_path: key_path.to_string_lossy().into_owned(),
+            ..config
+        };
+        let state = AppState::new_with_pool_and_config(pool.clone(), Arc::new(config))
+            .await
+            .unwrap();
+        (TestAppState { state, key_path }, pool)
+    }
+
+    /// 机密客户端的创建请求，支持授权码、刷新令牌和客户端凭证授权
+    pub(crate) fn client_request(name: &str) -> CreateClientRequest {
+        CreateClientRequest {
+            name: name.to_string(),
+            client_type: "CONFIDENTIAL".to_string(),
+            redirect_uris: vec!["https://app.example.com/callback".to_string()],
+            grant_types: vec![
+                "authorization_code".to_string(),
+                "refresh_token".to_string(),
+                "client_credentials".to_string(),
+            ],
+            response_types: vec!["code".to_string()],
+            allowed_scopes: vec!["openid".to_string()],
+            client_permissions: None,
+            access_token_ttl: None,
+            refresh_token_ttl: None,
+            tenant_id: None,
+        }
+    }
+
+    /// 创建客户端，返回客户端详情和明文密钥
+    pub(crate) async fn create_client(
+        state: &AppState,
+        request: CreateClientRequest,
+    ) -> (OAuthClientDetails, String) {
+        state.client_service.create_client(request).await.unwrap()
+    }
+
+    /// 创建没有角色的用户，返回用户 ID
+    pub(crate) async fn create_user(state: &AppState, username: &str) -> String {
+        state
+            .user_service
+            .create_user(username.to_string(), "password123".to_string(), None)
+            .await
+            .unwrap()
+            .id
+    }
 }
@@ -0,0 +1,102 @@
+//! 日志与分布式追踪初始化
+//!
+//! 所有 `tracing` span 都通过 OpenTelemetry 生成 W3C trace context；配置了
+//! `tracing.otlp_endpoint` 时批量导出到 OTLP/HTTP 收集器 (`<endpoint>/v1/traces`)。
+
+use crate::config::TracingConfig;
+use opentelemetry::{global, trace::TraceContextExt, trace::TracerProvider as _, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    runtime,
+    trace::{self as sdktrace, Sampler, TracerProvider},
+    Resource,
+};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt};
+
+/// 初始化返回的句柄，drop 时导出剩余的 span
+pub struct TelemetryGuard {
+    provider: TracerProvider,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        for result in self.provider.force_flush() {
+            if let Err(e) = result {
+                eprintln!("Failed to flush spans: {e}");
+            }
+        }
+        global::shutdown_tracer_provider();
+    }
+}
+
+/// 按配置构建 TracerProvider：采样遵循上游 traceparent 的决定，未配置收集器时只在本地生成 span
+pub fn build_tracer_provider(config: &TracingConfig) -> Result<TracerProvider, anyhow::Error> {
+    let trace_config = sdktrace::config()
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio,
+        ))))
+        .with_resource(Resource::new([KeyValue::new(
+            "service.name",
+            config.service_name.clone(),
+        )]));
+
+    let builder = TracerProvider::builder().with_config(trace_config);
+    let provider = match &config.otlp_endpoint {
+        Some(endpoint) => {
+            let exporter = opentelemetry_otlp::new_exporter()
+                .http()
+                .with_endpoint(endpoint.trim_end_matches('/'))
+                .build_span_exporter()?;
+            builder.with_batch_exporter(exporter, runtime::Tokio).build()
+        }
+        None => builder.build(),
+    };
+
+    Ok(provider)
+}
+
+/// 初始化全局日志和追踪。`log_to_stderr` 用于子命令，使 stdout 只包含命令输出
+pub fn init(config: &TracingConfig, log_to_stderr: bool) -> Result<TelemetryGuard, anyhow::Error> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let provider = build_tracer_provider(config)?;
+    let tracer = provider.tracer("oauth-service");
+    global::set_tracer_provider(provider.clone());
+
+    let fmt_layer = tracing_subscriber::fmt::layer().with_writer(if log_to_stderr {
+        tracing_subscriber::fmt::writer::BoxMakeWriter::new(std::io::stderr)
+    } else {
+        tracing_subscriber::fmt::writer::BoxMakeWriter::new(std::io::stdout)
+    });
+
+    tracing_subscriber::registry()
+        .with(LevelFilter::INFO)
+        .with(fmt_layer)
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .try_init()?;
+
+    Ok(TelemetryGuard { provider })
+}
+
+/// 当前 span 所属的 trace ID (32 位十六进制)，没有有效的追踪上下文时返回 None
+pub fn current_trace_id() -> Option<String> {
+    let context = tracing::Span::current().context();
+    let span_context = context.span().span_context().clone();
+    span_context
+        .is_valid()
+        .then(|| span_context.trace_id().to_string())
+}
+
+/// 数据库查询 span，`operation` 如 SELECT / INSERT，`table` 为主要操作的表
+pub fn db_span(operation: &'static str, table: &'static str) -> tracing::Span {
+    tracing::info_span!(
+        "db.query",
+        otel.name = %format!("{operation} {table}"),
+        otel.kind = "client",
+        db.system = "sqlite",
+        db.operation = operation,
+        db.sql.table = table,
+    )
+}
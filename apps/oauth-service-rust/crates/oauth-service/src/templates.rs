@@ -17,6 +17,8 @@ pub struct ConsentTemplate {
     pub client_name: String,
     pub user_email: String,
     pub scope_list: Vec<String>,
+    /// 用户已注册的通行密钥数量，为 0 时提示添加
+    pub passkey_count: usize,
 }
 
 /// 错误页面模板上下文
@@ -60,4 +62,23 @@ mod tests {
         assert_eq!(template.error_message, Some("Invalid credentials".to_string()));
         assert!(template.redirect_url.is_none());
     }
+
+    #[test]
+    fn test_consent_template_passkey_prompt() {
+        let template = ConsentTemplate {
+            client_name: "Test Client".to_string(),
+            user_email: "alice".to_string(),
+            scope_list: vec!["openid".to_string()],
+            passkey_count: 0,
+        };
+        assert!(template.render().unwrap().contains("id=\"passkey-register\""));
+
+        let template = ConsentTemplate {
+            passkey_count: 2,
+            ..template
+        };
+        let html = template.render().unwrap();
+        assert!(!html.contains("passkey-register"));
+        assert!(html.contains("已启用 2 个通行密钥"));
+    }
 }
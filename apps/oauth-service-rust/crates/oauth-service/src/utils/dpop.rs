@@ -0,0 +1,281 @@
+use crate::error::ServiceError;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use jsonwebtoken::jwk::{AlgorithmParameters, Jwk};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// The `typ` header of DPoP proofs (RFC 9449 §4.2).
+pub const DPOP_PROOF_TYPE: &str = "dpop+jwt";
+
+/// The request header carrying the DPoP proof.
+pub const DPOP_HEADER: &str = "dpop";
+
+/// The response header carrying a server-provided nonce.
+pub const DPOP_NONCE_HEADER: &str = "dpop-nonce";
+
+/// Asymmetric algorithms accepted for DPoP proofs.
+pub const SUPPORTED_ALGORITHMS: [Algorithm; 8] = [
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::EdDSA,
+];
+
+/// The claims of a DPoP proof JWT.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct DpopProofClaims {
+    /// Unique identifier of the proof, used to detect replays.
+    pub jti: String,
+    /// HTTP method of the request the proof was created for.
+    pub htm: String,
+    /// HTTP URI of the request, without query and fragment.
+    pub htu: String,
+    /// Creation time of the proof.
+    pub iat: i64,
+    /// Server-provided nonce, when the server requires one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    /// Hash of the access token the proof is presented with (protected resources only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ath: Option<String>,
+}
+
+/// A DPoP proof whose signature has been verified with the public key in its header.
+#[derive(Debug, Clone)]
+pub struct VerifiedProof {
+    /// JWK SHA-256 thumbprint of the proof's key: the value tokens are bound to.
+    pub jkt: String,
+    pub claims: DpopProofClaims,
+}
+
+fn invalid(message: &str) -> ServiceError {
+    ServiceError::InvalidDpopProof(message.to_string())
+}
+
+/// Computes the JWK SHA-256 thumbprint (RFC 7638) of a public key.
+///
+/// Only the required members are hashed, serialized in lexicographic order without whitespace.
+pub fn jwk_thumbprint(jwk: &Jwk) -> Result<String, ServiceError> {
+    let curve_name = |curve| {
+        serde_json::to_value(curve)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .ok_or_else(|| invalid("Unsupported curve in DPoP key"))
+    };
+    let members = match &jwk.algorithm {
+        AlgorithmParameters::EllipticCurve(params) => serde_json::json!({
+            "crv": curve_name(&params.curve)?,
+            "kty": "EC",
+            "x": params.x,
+            "y": params.y,
+        }),
+        AlgorithmParameters::RSA(params) => serde_json::json!({
+            "e": params.e,
+            "kty": "RSA",
+            "n": params.n,
+        }),
+        AlgorithmParameters::OctetKeyPair(params) => serde_json::json!({
+            "crv": curve_name(&params.curve)?,
+            "kty": "OKP",
+            "x": params.x,
+        }),
+        AlgorithmParameters::OctetKey(_) => {
+            return Err(invalid("DPoP proofs must use an asymmetric key"))
+        }
+    };
+    // Members are listed in lexicographic order, the canonical form RFC 7638 requires
+    let canonical = serde_json::to_string(&members)
+        .map_err(|e| ServiceError::Internal(format!("Failed to serialize JWK: {e}")))?;
+    Ok(URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes())))
+}
+
+/// The `ath` value for an access token: base64url-encoded SHA-256 hash of the token.
+pub fn access_token_hash(access_token: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(access_token.as_bytes()))
+}
+
+/// Normalizes an `htu` value for comparison: query and fragment are ignored (RFC 9449 §4.3).
+pub fn normalize_htu(uri: &str) -> Option<String> {
+    let mut url = url::Url::parse(uri).ok()?;
+    url.set_query(None);
+    url.set_fragment(None);
+    Some(url.to_string())
+}
+
+/// Checks that the proof header does not embed private key material.
+fn has_private_key_members(proof: &str) -> bool {
+    let Some(encoded_header) = proof.split('.').next() else {
+        return true;
+    };
+    let Ok(header) = URL_SAFE_NO_PAD
+        .decode(encoded_header)
+        .map_err(|_| ())
+        .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).map_err(|_| ()))
+    else {
+        return true;
+    };
+    ["d", "p", "q", "dp", "dq", "qi", "k"]
+        .iter()
+        .any(|member| header["jwk"].get(member).is_some())
+}
+
+/// Verifies the structure and signature of a DPoP proof.
+///
+/// Checks the `typ` and `alg` headers and the signature against the embedded public key.
+/// Request binding (`htm`, `htu`, `ath`), freshness, nonce and replay checks are up to the caller.
+pub fn verify_proof(proof: &str) -> Result<VerifiedProof, ServiceError> {
+    let header = decode_header(proof).map_err(|_| invalid("Malformed DPoP proof"))?;
+    if header.typ.as_deref() != Some(DPOP_PROOF_TYPE) {
+        return Err(invalid("DPoP proof must have typ dpop+jwt"));
+    }
+    if !SUPPORTED_ALGORITHMS.contains(&header.alg) {
+        return Err(invalid("Unsupported DPoP proof algorithm"));
+    }
+    let jwk = header
+        .jwk
+        .ok_or_else(|| invalid("DPoP proof must embed the public key (jwk)"))?;
+    if has_private_key_members(proof) {
+        return Err(invalid("DPoP proof must not contain a private key"));
+    }
+    let jkt = jwk_thumbprint(&jwk)?;
+    let key = DecodingKey::from_jwk(&jwk).map_err(|_| invalid("Invalid DPoP public key"))?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.validate_exp = false;
+    validation.validate_aud = false;
+    validation.required_spec_claims.clear();
+    let claims = decode::<DpopProofClaims>(proof, &key, &validation)
+        .map_err(|_| invalid("DPoP proof signature is invalid"))?
+        .claims;
+
+    Ok(VerifiedProof { jkt, claims })
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use ring::rand::SystemRandom;
+    use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+
+    /// A P-256 DPoP key for signing test proofs.
+    pub struct TestDpopKey {
+        pub jwk: Jwk,
+        encoding_key: EncodingKey,
+    }
+
+    impl TestDpopKey {
+        pub fn generate() -> Self {
+            let rng = SystemRandom::new();
+            let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+            let key_pair =
+                EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng)
+                    .unwrap();
+            // Uncompressed point: 0x04 || x || y
+            let point = key_pair.public_key().as_ref();
+            let jwk = serde_json::from_value(serde_json::json!({
+                "kty": "EC",
+                "crv": "P-256",
+                "x": URL_SAFE_NO_PAD.encode(&point[1..33]),
+                "y": URL_SAFE_NO_PAD.encode(&point[33..65]),
+            }))
+            .unwrap();
+            Self {
+                jwk,
+                encoding_key: EncodingKey::from_ec_der(pkcs8.as_ref()),
+            }
+        }
+
+        pub fn thumbprint(&self) -> String {
+            jwk_thumbprint(&self.jwk).unwrap()
+        }
+
+        pub fn sign(&self, claims: &DpopProofClaims) -> String {
+            let mut header = Header::new(Algorithm::ES256);
+            header.typ = Some(DPOP_PROOF_TYPE.to_string());
+            header.jwk = Some(self.jwk.clone());
+            encode(&header, claims, &self.encoding_key).unwrap()
+        }
+
+        /// Signs a fresh proof for the given request.
+        pub fn proof(
+            &self,
+            htm: &str,
+            htu: &str,
+            nonce: Option<&str>,
+            access_token: Option<&str>,
+        ) -> String {
+            self.sign(&DpopProofClaims {
+                jti: uuid::Uuid::new_v4().to_string(),
+                htm: htm.to_string(),
+                htu: htu.to_string(),
+                iat: chrono::Utc::now().timestamp(),
+                nonce: nonce.map(str::to_string),
+                ath: access_token.map(access_token_hash),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::TestDpopKey;
+    use super::*;
+
+    #[test]
+    fn test_jwk_thumbprint_matches_rfc7638_example() {
+        // RFC 7638 §3.1
+        let jwk: Jwk = serde_json::from_value(serde_json::json!({
+            "kty": "RSA",
+            "n": "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw",
+            "e": "AQAB",
+            "alg": "RS256",
+            "kid": "2011-04-29"
+        }))
+        .unwrap();
+        assert_eq!(
+            jwk_thumbprint(&jwk).unwrap(),
+            "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs"
+        );
+    }
+
+    #[test]
+    fn test_verify_proof() {
+        let key = TestDpopKey::generate();
+        let proof = key.proof("POST", "https://as.example.com/token", None, None);
+        let verified = verify_proof(&proof).unwrap();
+        assert_eq!(verified.jkt, key.thumbprint());
+        assert_eq!(verified.claims.htm, "POST");
+
+        // A proof with a swapped payload no longer verifies
+        let mut parts: Vec<&str> = proof.split('.').collect();
+        let other = key.proof("GET", "https://as.example.com/token", None, None);
+        parts[1] = other.split('.').nth(1).unwrap();
+        assert!(verify_proof(&parts.join(".")).is_err());
+
+        // Symmetric keys are never accepted
+        let mut header = jsonwebtoken::Header::new(Algorithm::HS256);
+        header.typ = Some(DPOP_PROOF_TYPE.to_string());
+        let hmac_proof = jsonwebtoken::encode(
+            &header,
+            &verified.claims,
+            &jsonwebtoken::EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+        assert!(verify_proof(&hmac_proof).is_err());
+    }
+
+    #[test]
+    fn test_normalize_htu() {
+        assert_eq!(
+            normalize_htu("https://as.example.com/token?x=1#frag").as_deref(),
+            Some("https://as.example.com/token")
+        );
+        assert_eq!(normalize_htu("not a uri"), None);
+    }
+}
@@ -1,19 +1,60 @@
-use crate::config::JwtAlgorithm;
+use crate::config::{issuer_for_tenant, JwtAlgorithm};
 use crate::error::ServiceError;
 use crate::models::user::User;
 use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
 
+/// The `typ` header of JWT access tokens (RFC 9068).
+pub const ACCESS_TOKEN_TYPE: &str = "at+jwt";
+
 /// The claims present in the JWT.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct TokenClaims {
+    /// Issuer: the platform issuer, or the tenant issuer for tenant tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
     pub sub: Option<String>, // Subject (user_id)
+    /// Audience: the resource server the access token is intended for (RFC 8707).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
     pub client_id: String,
     pub scope: String,
     pub permissions: Vec<String>,
     pub exp: usize,  // Expiration time
     pub iat: usize,  // Issued at
     pub jti: String, // JWT ID
+    /// Tenant the token was issued in; absent for platform-level tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    /// The party actually acting when the token was issued through impersonation (RFC 8693).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<ActorClaim>,
+    /// Confirmation: the key the token is bound to via DPoP (RFC 9449); absent for bearer tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cnf: Option<Confirmation>,
+}
+
+/// The `act` (actor) claim: identifies the user acting on behalf of `sub`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ActorClaim {
+    pub sub: String,
+}
+
+/// The `cnf` (confirmation) claim of a DPoP-bound token.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Confirmation {
+    /// JWK SHA-256 thumbprint (RFC 7638) of the client's DPoP key.
+    pub jkt: String,
+}
+
+/// The issuer and audience a token must carry to be accepted.
+#[derive(Debug, Clone, Copy)]
+pub struct ExpectedClaims<'a> {
+    /// The platform issuer; tokens with a `tenant` claim must carry that tenant's issuer.
+    pub issuer: &'a str,
+    /// The audience the token must be intended for. Requiring an audience means the
+    /// token is used as an access token, so the `at+jwt` type is required as well.
+    pub audience: Option<&'a str>,
 }
 
 /// The claims present in the ID Token (OpenID Connect).
@@ -39,6 +80,8 @@ pub struct IdTokenClaims {
     // Custom claims
     pub client_id: String,
     pub scope: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
 }
 
 /// Generates a new JWT token with specified algorithm.
@@ -58,6 +101,24 @@ pub fn generate_token_with_algorithm(
     })
 }
 
+/// Generates a new JWT access token (`typ: at+jwt`, RFC 9068) with specified algorithm.
+pub fn generate_access_token_with_algorithm(
+    claims: &TokenClaims,
+    encoding_key: &EncodingKey,
+    algorithm: JwtAlgorithm,
+) -> Result<String, ServiceError> {
+    let algo = match algorithm {
+        JwtAlgorithm::HS256 => Algorithm::HS256,
+        JwtAlgorithm::RS256 => Algorithm::RS256,
+    };
+    let mut header = Header::new(algo);
+    header.typ = Some(ACCESS_TOKEN_TYPE.to_string());
+    encode(&header, claims, encoding_key).map_err(|e| {
+        tracing::error!("JWT encoding with {} failed: {:?}", algorithm.as_str(), e);
+        ServiceError::JwtError(e.to_string())
+    })
+}
+
 /// Generates a new JWT token using HS256 algorithm (default for backward compatibility).
 pub fn generate_token(
     claims: &TokenClaims,
@@ -67,10 +128,14 @@ pub fn generate_token(
 }
 
 /// Verifies a JWT token and returns its claims with specified algorithm.
+///
+/// With `expected`, the issuer must match (the tenant issuer for tenant tokens) and,
+/// when an audience is required, the token must be an `at+jwt` access token for it.
 pub fn verify_token_with_algorithm(
     token: &str,
     decoding_key: &DecodingKey,
     algorithm: JwtAlgorithm,
+    expected: Option<&ExpectedClaims>,
 ) -> Result<TokenClaims, ServiceError> {
     let algo = match algorithm {
         JwtAlgorithm::HS256 => Algorithm::HS256,
@@ -78,17 +143,34 @@ pub fn verify_token_with_algorithm(
     };
     let mut validation = Validation::new(algo);
     validation.validate_exp = true;
-    decode::<TokenClaims>(token, decoding_key, &validation)
-        .map(|data| data.claims)
-        .map_err(|e| {
-            tracing::error!("JWT decoding with {} failed: {:?}", algorithm.as_str(), e);
-            ServiceError::JwtError(e.to_string())
-        })
+    // `aud` is checked below against `expected`
+    validation.validate_aud = false;
+    let data = decode::<TokenClaims>(token, decoding_key, &validation).map_err(|e| {
+        tracing::error!("JWT decoding with {} failed: {:?}", algorithm.as_str(), e);
+        ServiceError::JwtError(e.to_string())
+    })?;
+
+    if let Some(expected) = expected {
+        let claims = &data.claims;
+        let issuer = issuer_for_tenant(expected.issuer, claims.tenant.as_deref());
+        if claims.iss.as_deref() != Some(issuer.as_str()) {
+            return Err(ServiceError::JwtError("Invalid issuer".to_string()));
+        }
+        if let Some(audience) = expected.audience {
+            if data.header.typ.as_deref() != Some(ACCESS_TOKEN_TYPE) {
+                return Err(ServiceError::JwtError("Not an access token".to_string()));
+            }
+            if claims.aud.as_deref() != Some(audience) {
+                return Err(ServiceError::JwtError("Invalid audience".to_string()));
+            }
+        }
+    }
+    Ok(data.claims)
 }
 
 /// Verifies a JWT token and returns its claims using HS256 (default for backward compatibility).
 pub fn verify_token(token: &str, decoding_key: &DecodingKey) -> Result<TokenClaims, ServiceError> {
-    verify_token_with_algorithm(token, decoding_key, JwtAlgorithm::HS256)
+    verify_token_with_algorithm(token, decoding_key, JwtAlgorithm::HS256, None)
 }
 
 /// Generates a new ID Token (OpenID Connect) with specified algorithm.
@@ -134,6 +216,7 @@ pub fn generate_id_token_with_algorithm(
         picture: user.avatar.clone(),
         client_id: client_id.to_string(),
         scope: scope.to_string(),
+        tenant: user.tenant_id.clone(),
     };
 
     let algo = match algorithm {
@@ -1,5 +1,7 @@
 pub mod crypto;
+pub mod dpop;
 pub mod jwt;
+pub mod permission_conditions;
 pub mod pkce;
 pub mod scopes;
 pub mod validation;
@@ -0,0 +1,438 @@
+#![allow(clippy::uninlined_format_args)]
+// 角色权限的附加条件 (基于属性的访问控制)
+// 条件以 JSON 形式存储在 role_permissions.conditions 中，在请求时根据访问上下文求值。
+//
+// 示例: 仅允许在工作日 9:00-18:00 (UTC+8) 从办公网络访问
+// {"all": [
+//     {"time_window": {"start": "09:00", "end": "18:00",
+//                      "days": ["mon", "tue", "wed", "thu", "fri"], "utc_offset": "+08:00"}},
+//     {"ip_range": ["10.0.0.0/8", "192.168.1.0/24"]}
+// ]}
+
+use crate::error::ServiceError;
+use chrono::{DateTime, Datelike, FixedOffset, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// 权限条件表达式
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionCondition {
+    /// 所有子条件都满足
+    All(Vec<PermissionCondition>),
+    /// 任一子条件满足
+    Any(Vec<PermissionCondition>),
+    /// 子条件不满足
+    Not(Box<PermissionCondition>),
+    /// 请求时间位于时间窗口内
+    TimeWindow(TimeWindow),
+    /// 请求来源 IP 位于任一网段内 (CIDR 或单个 IP)
+    IpRange(Vec<String>),
+    /// 请求来自任一指定客户端
+    ClientId(Vec<String>),
+    /// 用户属性 (如 department) 的值在允许列表中
+    UserAttribute(UserAttributeCondition),
+}
+
+/// 每日时间窗口，start 晚于 end 时表示跨越午夜
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeWindow {
+    /// 开始时间 (HH:MM，含)
+    pub start: String,
+    /// 结束时间 (HH:MM，不含)
+    pub end: String,
+    /// 允许的星期，为空表示每天
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<Weekday>,
+    /// 时区偏移 (如 "+08:00")，默认 UTC
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub utc_offset: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserAttributeCondition {
+    /// 属性名称 (如 "department"、"organization")
+    pub name: String,
+    /// 允许的属性值
+    #[serde(rename = "in")]
+    pub values: Vec<String>,
+}
+
+/// 权限条件求值时的请求上下文
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccessContext {
+    /// 请求时间，为空表示当前时间
+    #[serde(default)]
+    pub time: Option<DateTime<Utc>>,
+    /// 请求来源 IP
+    #[serde(default)]
+    pub ip_address: Option<IpAddr>,
+    /// 发起请求的客户端
+    #[serde(default)]
+    pub client_id: Option<String>,
+    /// 用户属性，organization 和 department 由 RBACService 按用户资料中存储的值填充
+    #[serde(default)]
+    pub user_attributes: HashMap<String, String>,
+}
+
+impl PermissionCondition {
+    /// 解析并校验条件 JSON
+    pub fn parse(raw: &str) -> Result<Self, ServiceError> {
+        let condition: Self = serde_json::from_str(raw).map_err(|e| {
+            ServiceError::ValidationError(format!("Invalid permission condition: {}", e))
+        })?;
+        condition.validate()?;
+        Ok(condition)
+    }
+
+    /// 校验条件中的时间、时区和网段格式
+    pub fn validate(&self) -> Result<(), ServiceError> {
+        match self {
+            Self::All(conditions) | Self::Any(conditions) => {
+                if conditions.is_empty() {
+                    return Err(ServiceError::ValidationError(
+                        "Condition groups must not be empty".to_string(),
+                    ));
+                }
+                conditions.iter().try_for_each(Self::validate)
+            }
+            Self::Not(condition) => condition.validate(),
+            Self::TimeWindow(window) => {
+                for time in [&window.start, &window.end] {
+                    parse_time(time).ok_or_else(|| {
+                        ServiceError::ValidationError(format!(
+                            "Invalid time '{}', expected HH:MM",
+                            time
+                        ))
+                    })?;
+                }
+                if let Some(offset) = &window.utc_offset {
+                    offset.parse::<FixedOffset>().map_err(|_| {
+                        ServiceError::ValidationError(format!(
+                            "Invalid utc_offset '{}', expected +HH:MM",
+                            offset
+                        ))
+                    })?;
+                }
+                Ok(())
+            }
+            Self::IpRange(ranges) => {
+                if ranges.is_empty() {
+                    return Err(ServiceError::ValidationError(
+                        "ip_range must not be empty".to_string(),
+                    ));
+                }
+                for range in ranges {
+                    parse_cidr(range).ok_or_else(|| {
+                        ServiceError::ValidationError(format!("Invalid IP range '{}'", range))
+                    })?;
+                }
+                Ok(())
+            }
+            Self::ClientId(_) | Self::UserAttribute(_) => Ok(()),
+        }
+    }
+
+    /// 根据请求上下文求值，只有确定满足时才授予权限。
+    /// 上下文缺少条件所需的信息时结果为未知，未知在 `not` 和 `any` 中同样不会变成满足
+    pub fn evaluate(&self, context: &AccessContext) -> bool {
+        self.evaluate_known(context) == Some(true)
+    }
+
+    /// 三值求值: `None` 表示上下文缺少信息、无法判断
+    fn evaluate_known(&self, context: &AccessContext) -> Option<bool> {
+        match self {
+            // 任一子条件不满足则不满足，否则有未知时为未知
+            Self::All(conditions) => {
+                let results: Vec<_> = conditions
+                    .iter()
+                    .map(|c| c.evaluate_known(context))
+                    .collect();
+                if results.contains(&Some(false)) {
+                    Some(false)
+                } else if results.contains(&None) {
+                    None
+                } else {
+                    Some(true)
+                }
+            }
+            // 任一子条件满足则满足，否则有未知时为未知
+            Self::Any(conditions) => {
+                let results: Vec<_> = conditions
+                    .iter()
+                    .map(|c| c.evaluate_known(context))
+                    .collect();
+                if results.contains(&Some(true)) {
+                    Some(true)
+                } else if results.contains(&None) {
+                    None
+                } else {
+                    Some(false)
+                }
+            }
+            Self::Not(condition) => condition.evaluate_known(context).map(|result| !result),
+            Self::TimeWindow(window) => window.contains(context.time.unwrap_or_else(Utc::now)),
+            Self::IpRange(ranges) => context.ip_address.map(|ip| {
+                ranges
+                    .iter()
+                    .filter_map(|range| parse_cidr(range))
+                    .any(|(network, prefix)| ip_in_network(ip, network, prefix))
+            }),
+            Self::ClientId(client_ids) => context
+                .client_id
+                .as_ref()
+                .map(|client_id| client_ids.contains(client_id)),
+            Self::UserAttribute(condition) => context
+                .user_attributes
+                .get(&condition.name)
+                .map(|value| condition.values.contains(value)),
+        }
+    }
+}
+
+impl TimeWindow {
+    /// 时间位于窗口内；窗口本身无法解析时结果为未知
+    fn contains(&self, time: DateTime<Utc>) -> Option<bool> {
+        let start = parse_time(&self.start)?;
+        let end = parse_time(&self.end)?;
+        let offset = match &self.utc_offset {
+            Some(offset) => offset.parse::<FixedOffset>().ok()?,
+            None => FixedOffset::east_opt(0).expect("zero offset is valid"),
+        };
+
+        let local = time.with_timezone(&offset);
+        if !self.days.is_empty() && !self.days.contains(&local.weekday()) {
+            return Some(false);
+        }
+
+        let now = local.time();
+        Some(if start <= end {
+            start <= now && now < end
+        } else {
+            now >= start || now < end
+        })
+    }
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value, "%H:%M").ok()
+}
+
+/// 解析 CIDR (如 "10.0.0.0/8")，单个 IP 视为完整前缀
+fn parse_cidr(value: &str) -> Option<(IpAddr, u8)> {
+    let (address, prefix) = match value.split_once('/') {
+        Some((address, prefix)) => (address, Some(prefix)),
+        None => (value, None),
+    };
+    let network: IpAddr = address.trim().parse().ok()?;
+    let max_prefix = if network.is_ipv4() { 32 } else { 128 };
+    let prefix = match prefix {
+        Some(prefix) => prefix.trim().parse::<u8>().ok()?,
+        None => max_prefix,
+    };
+    (prefix <= max_prefix).then_some((network, prefix))
+}
+
+fn ip_in_network(ip: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    // IPv4 映射的 IPv6 地址按 IPv4 比较
+    let ip = match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        v4 => v4,
+    };
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn office_hours_condition() -> PermissionCondition {
+        PermissionCondition::parse(
+            r#"{"all": [
+                {"time_window": {"start": "09:00", "end": "18:00",
+                                 "days": ["mon", "tue", "wed", "thu", "fri"], "utc_offset": "+08:00"}},
+                {"ip_range": ["10.0.0.0/8", "192.168.1.0/24"]}
+            ]}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_office_network_during_business_hours() {
+        let condition = office_hours_condition();
+        // 2024-01-08 是星期一，UTC 02:00 即 UTC+8 的 10:00
+        let monday_morning = Utc.with_ymd_and_hms(2024, 1, 8, 2, 0, 0).unwrap();
+        let mut context = AccessContext {
+            time: Some(monday_morning),
+            ip_address: Some("10.1.2.3".parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(condition.evaluate(&context));
+
+        context.ip_address = Some("8.8.8.8".parse().unwrap());
+        assert!(!condition.evaluate(&context));
+
+        context.ip_address = Some("192.168.1.20".parse().unwrap());
+        // UTC 12:00 即 UTC+8 的 20:00，已过下班时间
+        context.time = Some(Utc.with_ymd_and_hms(2024, 1, 8, 12, 0, 0).unwrap());
+        assert!(!condition.evaluate(&context));
+
+        // 星期六
+        context.time = Some(Utc.with_ymd_and_hms(2024, 1, 13, 2, 0, 0).unwrap());
+        assert!(!condition.evaluate(&context));
+
+        // 缺少来源 IP 时不满足
+        context.time = Some(monday_morning);
+        context.ip_address = None;
+        assert!(!condition.evaluate(&context));
+    }
+
+    #[test]
+    fn test_overnight_time_window() {
+        let condition = PermissionCondition::TimeWindow(TimeWindow {
+            start: "22:00".to_string(),
+            end: "06:00".to_string(),
+            days: vec![],
+            utc_offset: None,
+        });
+        let at = |hour| AccessContext {
+            time: Some(Utc.with_ymd_and_hms(2024, 1, 8, hour, 0, 0).unwrap()),
+            ..Default::default()
+        };
+        assert!(condition.evaluate(&at(23)));
+        assert!(condition.evaluate(&at(5)));
+        assert!(!condition.evaluate(&at(12)));
+    }
+
+    #[test]
+    fn test_client_and_user_attribute_conditions() {
+        let condition = PermissionCondition::parse(
+            r#"{"any": [
+                {"client_id": ["admin-portal-client"]},
+                {"not": {"user_attribute": {"name": "department", "in": ["Sales"]}}}
+            ]}"#,
+        )
+        .unwrap();
+
+        let mut context = AccessContext {
+            client_id: Some("admin-portal-client".to_string()),
+            ..Default::default()
+        };
+        context
+            .user_attributes
+            .insert("department".to_string(), "Sales".to_string());
+        assert!(condition.evaluate(&context));
+
+        context.client_id = Some("other-client".to_string());
+        assert!(!condition.evaluate(&context));
+
+        context
+            .user_attributes
+            .insert("department".to_string(), "IT".to_string());
+        assert!(condition.evaluate(&context));
+    }
+
+    #[test]
+    fn test_missing_context_is_never_granted() {
+        // 拒绝名单: 不允许从 10.0.0.0/8 访问，来源 IP 未知时同样拒绝
+        let deny_list =
+            PermissionCondition::parse(r#"{"not": {"ip_range": ["10.0.0.0/8"]}}"#).unwrap();
+        let mut context = AccessContext::default();
+        assert!(!deny_list.evaluate(&context));
+        context.ip_address = Some("203.0.113.9".parse().unwrap());
+        assert!(deny_list.evaluate(&context));
+        context.ip_address = Some("10.0.0.1".parse().unwrap());
+        assert!(!deny_list.evaluate(&context));
+
+        // any 中的未知不会被另一个不满足的分支变成满足，确定满足的分支仍然生效
+        let condition = PermissionCondition::parse(
+            r#"{"any": [
+                {"not": {"client_id": ["legacy-client"]}},
+                {"user_attribute": {"name": "department", "in": ["IT"]}}
+            ]}"#,
+        )
+        .unwrap();
+        let mut context = AccessContext::default();
+        assert!(!condition.evaluate(&context));
+        assert!(!PermissionCondition::Not(Box::new(condition.clone())).evaluate(&context));
+        context
+            .user_attributes
+            .insert("department".to_string(), "IT".to_string());
+        assert!(condition.evaluate(&context));
+
+        // all 中任一分支确定不满足时整体不满足
+        let condition = PermissionCondition::parse(
+            r#"{"not": {"all": [{"ip_range": ["10.0.0.0/8"]}, {"client_id": ["web"]}]}}"#,
+        )
+        .unwrap();
+        let context = AccessContext {
+            client_id: Some("mobile".to_string()),
+            ..Default::default()
+        };
+        assert!(condition.evaluate(&context));
+    }
+
+    #[test]
+    fn test_ip_range_matching() {
+        let network = parse_cidr("2001:db8::/32").unwrap();
+        assert!(ip_in_network(
+            "2001:db8::1".parse().unwrap(),
+            network.0,
+            network.1
+        ));
+        assert!(!ip_in_network(
+            "2001:db9::1".parse().unwrap(),
+            network.0,
+            network.1
+        ));
+
+        let network = parse_cidr("10.0.0.0/8").unwrap();
+        assert!(ip_in_network(
+            "::ffff:10.0.0.1".parse().unwrap(),
+            network.0,
+            network.1
+        ));
+
+        let any = parse_cidr("0.0.0.0/0").unwrap();
+        assert!(ip_in_network("203.0.113.9".parse().unwrap(), any.0, any.1));
+
+        let single = parse_cidr("203.0.113.9").unwrap();
+        assert!(ip_in_network(
+            "203.0.113.9".parse().unwrap(),
+            single.0,
+            single.1
+        ));
+        assert!(!ip_in_network(
+            "203.0.113.10".parse().unwrap(),
+            single.0,
+            single.1
+        ));
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_conditions() {
+        assert!(PermissionCondition::parse(r#"{"ip_range": ["10.0.0.0/33"]}"#).is_err());
+        assert!(
+            PermissionCondition::parse(r#"{"time_window": {"start": "9am", "end": "18:00"}}"#)
+                .is_err()
+        );
+        assert!(PermissionCondition::parse(
+            r#"{"time_window": {"start": "09:00", "end": "18:00", "utc_offset": "CST"}}"#
+        )
+        .is_err());
+        assert!(PermissionCondition::parse(r#"{"all": []}"#).is_err());
+        assert!(PermissionCondition::parse(r#"{"weather": "sunny"}"#).is_err());
+    }
+}
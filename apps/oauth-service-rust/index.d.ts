@@ -133,6 +133,18 @@ export class NapiOAuthSdk {
    * * `Result<bool>` - 撤销是否成功 (Revocation Success)
    */
   rbacRevokeRole(userId: string, roleId: string): Promise<boolean>
+  /**
+   * 解释用户的有效权限 (Explain User's Effective Permissions)
+   *
+   * # Arguments
+   * * `user_id` - 用户ID (User ID)
+   * * `client_id` - 按该客户端的范围求值 (Evaluate for this client, optional)
+   * * `permissions` - 需要解释的权限 (Permissions to explain when missing, optional)
+   *
+   * # Returns
+   * * `Result<serde_json::Value>` - 权限来源和可授予缺少权限的角色 (Permission sources and granting roles)
+   */
+  rbacExplainPermissions(userId: string, clientId?: string | undefined | null, permissions?: Array<string> | undefined | null): Promise<any>
   /**
    * 获取客户端列表 (Get Client List)
    *
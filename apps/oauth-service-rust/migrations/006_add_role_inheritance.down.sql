@@ -0,0 +1,3 @@
+-- Role Inheritance Migration (rollback)
+DROP INDEX IF EXISTS idx_role_inheritance_parent_role_id;
+DROP TABLE IF EXISTS role_inheritance;
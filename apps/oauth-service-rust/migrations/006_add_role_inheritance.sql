@@ -0,0 +1,23 @@
+-- Role Inheritance Migration
+-- Version 1: Add parent roles so that permissions can be inherited instead of duplicated
+-- 说明: 一个角色可以有多个父角色，继承父角色 (及其祖先) 的全部权限
+--       环路检测由 RoleService 在写入时完成
+
+-- ===============================
+-- 角色继承关系 (Role Inheritance)
+-- ===============================
+
+-- 角色继承表: role_id 继承 parent_role_id 的权限
+CREATE TABLE IF NOT EXISTS role_inheritance (
+    role_id TEXT NOT NULL,
+    parent_role_id TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (role_id, parent_role_id),
+    CHECK (role_id <> parent_role_id),
+    FOREIGN KEY (role_id) REFERENCES roles(id) ON DELETE CASCADE,
+    FOREIGN KEY (parent_role_id) REFERENCES roles(id) ON DELETE CASCADE
+);
+
+-- 按父角色查找子角色 (用于计算受影响的用户和后代角色)
+CREATE INDEX IF NOT EXISTS idx_role_inheritance_parent_role_id ON role_inheritance(parent_role_id);
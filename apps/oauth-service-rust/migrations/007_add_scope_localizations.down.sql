@@ -0,0 +1,3 @@
+-- Scope Registry Migration (rollback)
+DROP INDEX IF EXISTS idx_scope_permissions_permission_id;
+DROP TABLE IF EXISTS scope_localizations;
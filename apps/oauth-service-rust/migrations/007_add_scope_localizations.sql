@@ -0,0 +1,26 @@
+-- Scope Registry Migration
+-- Version 1: Store consent texts and risk level for scopes per locale
+-- 说明: 同意页面的权限说明从 utils::scopes 的静态表迁移到数据库，
+--       静态表仅作为数据库中缺少对应语言文案时的后备
+
+-- ===============================
+-- 权限范围本地化文案 (Scope Localizations)
+-- ===============================
+
+CREATE TABLE IF NOT EXISTS scope_localizations (
+    scope_id TEXT NOT NULL,
+    locale TEXT NOT NULL,
+    display_name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    risk_level TEXT NOT NULL DEFAULT 'low',
+    category TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (scope_id, locale),
+    CHECK (risk_level IN ('low', 'medium', 'high')),
+    FOREIGN KEY (scope_id) REFERENCES scopes(id) ON DELETE CASCADE
+);
+
+-- 按权限反查映射到它的权限范围
+CREATE INDEX IF NOT EXISTS idx_scope_permissions_permission_id ON scope_permissions(permission_id);
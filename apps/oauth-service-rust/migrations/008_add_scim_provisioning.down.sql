@@ -0,0 +1,10 @@
+-- SCIM Provisioning Migration (rollback)
+DELETE FROM scope_permissions WHERE permission_id IN (SELECT id FROM permissions WHERE name = 'scim:provision');
+DELETE FROM client_permissions WHERE permission = 'scim:provision';
+DELETE FROM permissions WHERE name = 'scim:provision';
+DROP INDEX IF EXISTS idx_roles_external_id;
+DROP INDEX IF EXISTS idx_users_email;
+DROP INDEX IF EXISTS idx_users_external_id;
+ALTER TABLE roles DROP COLUMN external_id;
+ALTER TABLE users DROP COLUMN email;
+ALTER TABLE users DROP COLUMN external_id;
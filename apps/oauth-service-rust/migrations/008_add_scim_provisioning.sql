@@ -0,0 +1,32 @@
+-- SCIM Provisioning Migration
+-- Version 1: Store SCIM attributes of users and groups, add the scim:provision permission
+-- 说明: SCIM 的 Group 对应角色 (roles)，externalId 由身份源 (如 HR 系统) 提供，
+--       用于在重复同步时关联已存在的用户和角色
+
+-- ===============================
+-- SCIM 属性 (SCIM Attributes)
+-- ===============================
+
+ALTER TABLE users ADD COLUMN external_id TEXT;
+ALTER TABLE users ADD COLUMN email TEXT;
+ALTER TABLE roles ADD COLUMN external_id TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_external_id ON users(external_id);
+CREATE INDEX IF NOT EXISTS idx_users_email ON users(email);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_roles_external_id ON roles(external_id);
+
+-- ===============================
+-- SCIM 权限 (SCIM Permission)
+-- ===============================
+
+-- 调用 /scim/v2 端点所需的权限，授予专用的 client_credentials 客户端，例如:
+--   oauth-service client create hr-scim --grant-type client_credentials --scope admin --permission scim:provision
+INSERT OR IGNORE INTO permissions (
+    id, name, display_name, description, resource, action, type, is_system_perm, is_active
+) VALUES
+    ('clh4000901', 'scim:provision', 'SCIM Provisioning', 'Provision users and groups via SCIM 2.0', 'scim', 'provision', 'API', true, true);
+
+-- 权限范围: 映射到 admin scope，否则令牌签发时会按 scope 去掉该权限
+INSERT OR IGNORE INTO scope_permissions (scope_id, permission_id)
+SELECT s.id, p.id FROM scopes s, permissions p
+WHERE s.name = 'admin' AND p.name = 'scim:provision';
@@ -0,0 +1,5 @@
+-- Federated Login Migration (rollback)
+DROP INDEX IF EXISTS idx_federated_login_states_expires_at;
+DROP TABLE IF EXISTS federated_login_states;
+DROP INDEX IF EXISTS idx_federated_identities_user_id;
+DROP TABLE IF EXISTS federated_identities;
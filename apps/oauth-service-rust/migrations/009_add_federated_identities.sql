@@ -0,0 +1,39 @@
+-- Federated Login Migration
+-- Version 1: Link local users to upstream OIDC identities and track pending logins
+-- 说明: 上游身份以 (provider_id, subject) 唯一标识，provider_id 对应配置中的
+--       federation.providers[].id；登录状态在回调时一次性消费，过期记录由维护任务清理
+
+-- ===============================
+-- 联合身份 (Federated Identities)
+-- ===============================
+
+CREATE TABLE IF NOT EXISTS federated_identities (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    provider_id TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    email TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    last_login_at DATETIME,
+
+    UNIQUE (provider_id, subject),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_federated_identities_user_id ON federated_identities(user_id);
+
+-- ===============================
+-- 进行中的联合登录 (Federated Login States)
+-- ===============================
+
+CREATE TABLE IF NOT EXISTS federated_login_states (
+    state TEXT PRIMARY KEY,
+    provider_id TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    code_verifier TEXT NOT NULL,
+    redirect TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    expires_at DATETIME NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_federated_login_states_expires_at ON federated_login_states(expires_at);
@@ -0,0 +1,46 @@
+-- WebAuthn Migration
+-- Version 1: Store passkeys (WebAuthn credentials) and pending registration / authentication ceremonies
+-- 说明: 每个用户可以注册多个认证器；credential_id 为 base64url 编码的凭据 ID，
+--       public_key 为 COSE 格式的公钥，sign_count 用于发现被克隆的认证器；
+--       挑战在完成仪式时一次性消费，过期记录由维护任务清理
+
+-- ===============================
+-- 通行密钥 (WebAuthn Credentials)
+-- ===============================
+
+CREATE TABLE IF NOT EXISTS webauthn_credentials (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    credential_id TEXT NOT NULL UNIQUE,
+    public_key BLOB NOT NULL,
+    algorithm INTEGER NOT NULL,
+    sign_count INTEGER NOT NULL DEFAULT 0,
+    transports TEXT,
+    aaguid TEXT,
+    name TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    last_used_at DATETIME,
+
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_webauthn_credentials_user_id ON webauthn_credentials(user_id);
+
+-- ===============================
+-- 进行中的仪式 (WebAuthn Challenges)
+-- ===============================
+
+-- ceremony: registration (注册认证器) / passwordless (无密码登录) / second_factor (密码登录后的第二因素)
+CREATE TABLE IF NOT EXISTS webauthn_challenges (
+    id TEXT PRIMARY KEY,
+    challenge TEXT NOT NULL,
+    ceremony TEXT NOT NULL CHECK (ceremony IN ('registration', 'passwordless', 'second_factor')),
+    user_id TEXT,
+    redirect TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    expires_at DATETIME NOT NULL,
+
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_webauthn_challenges_expires_at ON webauthn_challenges(expires_at);
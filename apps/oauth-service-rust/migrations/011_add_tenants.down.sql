@@ -0,0 +1,13 @@
+-- Multi-Tenancy Migration (rollback)
+DELETE FROM scope_permissions WHERE permission_id IN (SELECT id FROM permissions WHERE resource = 'tenants');
+DELETE FROM role_permissions WHERE permission_id IN (SELECT id FROM permissions WHERE resource = 'tenants');
+DELETE FROM permissions WHERE resource = 'tenants';
+DROP INDEX IF EXISTS idx_audit_logs_tenant_id;
+DROP INDEX IF EXISTS idx_roles_tenant_id;
+DROP INDEX IF EXISTS idx_oauth_clients_tenant_id;
+DROP INDEX IF EXISTS idx_users_tenant_id;
+ALTER TABLE audit_logs DROP COLUMN tenant_id;
+ALTER TABLE roles DROP COLUMN tenant_id;
+ALTER TABLE oauth_clients DROP COLUMN tenant_id;
+ALTER TABLE users DROP COLUMN tenant_id;
+DROP TABLE IF EXISTS tenants;
@@ -0,0 +1,54 @@
+-- Multi-Tenancy Migration
+-- Version 1: Add tenants and scope users, clients, roles and audit logs to a tenant
+-- 说明: tenant_id 为空表示平台级 (全局) 资源；租户 ID 同时作为令牌的 tenant 声明
+--       和租户 issuer 路径 (<issuer>/tenants/<id>)，创建后不可修改。
+--       用户名和角色名仍在整个部署内唯一，租户归属由 TenantService 校验
+--       (不使用外键约束，以便回滚时可以删除这些列)
+
+-- ===============================
+-- 租户 (Tenants)
+-- ===============================
+
+CREATE TABLE IF NOT EXISTS tenants (
+    id TEXT PRIMARY KEY, -- 小写字母、数字和连字符，如 "acme"
+    name TEXT NOT NULL,
+    is_active INTEGER DEFAULT 1 NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- ===============================
+-- 租户归属 (Tenant Ownership)
+-- ===============================
+
+ALTER TABLE users ADD COLUMN tenant_id TEXT;
+ALTER TABLE oauth_clients ADD COLUMN tenant_id TEXT;
+ALTER TABLE roles ADD COLUMN tenant_id TEXT;
+ALTER TABLE audit_logs ADD COLUMN tenant_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_users_tenant_id ON users(tenant_id);
+CREATE INDEX IF NOT EXISTS idx_oauth_clients_tenant_id ON oauth_clients(tenant_id);
+CREATE INDEX IF NOT EXISTS idx_roles_tenant_id ON roles(tenant_id);
+CREATE INDEX IF NOT EXISTS idx_audit_logs_tenant_id ON audit_logs(tenant_id);
+
+-- ===============================
+-- 租户管理权限 (Tenant Permissions)
+-- ===============================
+
+-- 只有平台级用户 (不属于任何租户) 可以管理租户
+INSERT OR IGNORE INTO permissions (
+    id, name, display_name, description, resource, action, type, is_system_perm, is_active
+) VALUES
+    ('clh4001101', 'tenants:read', 'Read Tenants', 'View tenants', 'tenants', 'read', 'API', true, true),
+    ('clh4001102', 'tenants:create', 'Create Tenants', 'Create tenants', 'tenants', 'create', 'API', true, true),
+    ('clh4001103', 'tenants:update', 'Update Tenants', 'Rename, enable or disable tenants', 'tenants', 'update', 'API', true, true),
+    ('clh4001104', 'tenants:delete', 'Delete Tenants', 'Delete empty tenants', 'tenants', 'delete', 'API', true, true);
+
+-- 超级管理员: 添加租户管理权限
+INSERT OR IGNORE INTO role_permissions (role_id, permission_id)
+SELECT 'clh3000001', id FROM permissions WHERE resource = 'tenants';
+
+-- 权限范围: 映射到 admin scope，否则令牌签发时会按 scope 去掉该权限
+INSERT OR IGNORE INTO scope_permissions (scope_id, permission_id)
+SELECT s.id, p.id FROM scopes s, permissions p
+WHERE s.name = 'admin' AND p.resource = 'tenants';
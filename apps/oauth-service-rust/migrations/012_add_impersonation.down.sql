@@ -0,0 +1,7 @@
+-- Impersonation Migration (rollback)
+DELETE FROM scope_permissions WHERE permission_id IN (SELECT id FROM permissions WHERE id = 'clh4001201');
+DELETE FROM role_permissions WHERE permission_id = 'clh4001201';
+DELETE FROM permissions WHERE id = 'clh4001201';
+DROP INDEX IF EXISTS idx_impersonation_sessions_target;
+DROP INDEX IF EXISTS idx_impersonation_sessions_actor;
+DROP TABLE IF EXISTS impersonation_sessions;
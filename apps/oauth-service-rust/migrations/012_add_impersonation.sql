@@ -0,0 +1,44 @@
+-- Impersonation Migration
+-- Version 1: Record support staff impersonating users
+-- 说明: 每次模拟登录签发一个短期访问令牌 (不签发刷新令牌)，令牌的 act 声明记录真实操作人；
+--       会话记录用于 /api/v2/users/me 展示模拟状态，以及提前结束模拟时吊销令牌
+
+-- ===============================
+-- 模拟会话 (Impersonation Sessions)
+-- ===============================
+
+CREATE TABLE IF NOT EXISTS impersonation_sessions (
+    id TEXT PRIMARY KEY,
+    actor_user_id TEXT NOT NULL, -- 发起模拟的真实用户
+    target_user_id TEXT NOT NULL, -- 被模拟的用户
+    client_id TEXT NOT NULL, -- 签发令牌的客户端 (公开的 client_id)
+    jti TEXT NOT NULL UNIQUE, -- 模拟令牌的 JWT ID
+    reason TEXT NOT NULL,
+    tenant_id TEXT,
+    started_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    expires_at DATETIME NOT NULL,
+    ended_at DATETIME,
+
+    FOREIGN KEY (actor_user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (target_user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_impersonation_sessions_actor ON impersonation_sessions(actor_user_id);
+CREATE INDEX IF NOT EXISTS idx_impersonation_sessions_target ON impersonation_sessions(target_user_id);
+
+-- ===============================
+-- 模拟登录权限 (Impersonation Permission)
+-- ===============================
+
+INSERT OR IGNORE INTO permissions (
+    id, name, display_name, description, resource, action, type, is_system_perm, is_active
+) VALUES
+    ('clh4001201', 'users:impersonate', 'Impersonate Users', 'Obtain a short-lived token acting as another user', 'users', 'impersonate', 'API', true, true);
+
+-- 超级管理员: 添加模拟登录权限
+INSERT OR IGNORE INTO role_permissions (role_id, permission_id) VALUES ('clh3000001', 'clh4001201');
+
+-- 权限范围: 映射到 admin 和 manage_users scope，否则令牌签发时会按 scope 去掉该权限
+INSERT OR IGNORE INTO scope_permissions (scope_id, permission_id)
+SELECT s.id, p.id FROM scopes s, permissions p
+WHERE s.name IN ('admin', 'manage_users') AND p.id = 'clh4001201';
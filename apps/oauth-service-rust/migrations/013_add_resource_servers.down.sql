@@ -0,0 +1,7 @@
+-- Resource Servers Migration (rollback)
+DELETE FROM scope_permissions WHERE permission_id IN (SELECT id FROM permissions WHERE resource = 'resource_servers');
+DELETE FROM role_permissions WHERE permission_id IN (SELECT id FROM permissions WHERE resource = 'resource_servers');
+DELETE FROM permissions WHERE resource = 'resource_servers';
+ALTER TABLE refresh_tokens DROP COLUMN resource;
+ALTER TABLE authorization_codes DROP COLUMN resource;
+DROP TABLE IF EXISTS resource_servers;
@@ -0,0 +1,50 @@
+-- Resource Servers Migration
+-- Version 1: Register resource servers and restrict access tokens to an audience (RFC 8707 / RFC 9068)
+-- 说明: 授权和令牌请求可以通过 resource 参数指定目标资源服务器，签发的访问令牌
+--       aud 为该资源服务器的标识符；未指定时 aud 为本服务的 issuer。
+--       资源服务器只接受其允许的 scope (OpenID Connect scope 除外)。
+--       资源服务器以登记的客户端调用内省端点，只能内省以自己为受众的令牌。
+--       授权码和刷新令牌记录请求的资源，换取和刷新令牌时沿用
+
+-- ===============================
+-- 资源服务器 (Resource Servers)
+-- ===============================
+
+CREATE TABLE IF NOT EXISTS resource_servers (
+    id TEXT PRIMARY KEY,
+    identifier TEXT NOT NULL UNIQUE, -- 资源标识符 (绝对 URI)，即访问令牌的 aud，创建后不可修改
+    name TEXT NOT NULL,
+    allowed_scopes TEXT NOT NULL DEFAULT '', -- Space-separated string
+    client_id TEXT UNIQUE, -- 调用内省端点时认证的客户端 (oauth_clients.client_id)
+    is_active INTEGER DEFAULT 1 NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- ===============================
+-- 请求的资源 (Requested Resource)
+-- ===============================
+
+ALTER TABLE authorization_codes ADD COLUMN resource TEXT;
+ALTER TABLE refresh_tokens ADD COLUMN resource TEXT;
+
+-- ===============================
+-- 资源服务器管理权限 (Resource Server Permissions)
+-- ===============================
+
+INSERT OR IGNORE INTO permissions (
+    id, name, display_name, description, resource, action, type, is_system_perm, is_active
+) VALUES
+    ('clh4001301', 'resource_servers:read', 'Read Resource Servers', 'View registered resource servers', 'resource_servers', 'read', 'API', true, true),
+    ('clh4001302', 'resource_servers:create', 'Create Resource Servers', 'Register resource servers', 'resource_servers', 'create', 'API', true, true),
+    ('clh4001303', 'resource_servers:update', 'Update Resource Servers', 'Change the scopes of resource servers, enable or disable them', 'resource_servers', 'update', 'API', true, true),
+    ('clh4001304', 'resource_servers:delete', 'Delete Resource Servers', 'Delete resource servers', 'resource_servers', 'delete', 'API', true, true);
+
+-- 超级管理员: 添加资源服务器管理权限
+INSERT OR IGNORE INTO role_permissions (role_id, permission_id)
+SELECT 'clh3000001', id FROM permissions WHERE resource = 'resource_servers';
+
+-- 权限范围: 映射到 admin scope，否则令牌签发时会按 scope 去掉该权限
+INSERT OR IGNORE INTO scope_permissions (scope_id, permission_id)
+SELECT s.id, p.id FROM scopes s, permissions p
+WHERE s.name = 'admin' AND p.resource = 'resource_servers';
@@ -0,0 +1,6 @@
+-- DPoP Migration (rollback)
+ALTER TABLE refresh_tokens DROP COLUMN dpop_jkt;
+DROP INDEX IF EXISTS idx_dpop_proof_jtis_expires_at;
+DROP TABLE IF EXISTS dpop_proof_jtis;
+DROP INDEX IF EXISTS idx_dpop_nonces_expires_at;
+DROP TABLE IF EXISTS dpop_nonces;
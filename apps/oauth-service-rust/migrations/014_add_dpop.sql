@@ -0,0 +1,37 @@
+-- DPoP Migration
+-- Version 1: Sender-constrained access and refresh tokens (RFC 9449)
+-- 说明: 令牌端点签发的 DPoP nonce 在有效期内可重复使用；已使用过的 DPoP 证明
+--       按 (jkt, jti) 记录，在证明有效期内拒绝重放；公共客户端的刷新令牌记录
+--       签发时的 JWK 指纹 (dpop_jkt)，刷新时必须使用同一把密钥的证明。
+--       过期记录由维护任务清理
+
+-- ===============================
+-- DPoP Nonce
+-- ===============================
+
+CREATE TABLE IF NOT EXISTS dpop_nonces (
+    nonce TEXT PRIMARY KEY,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    expires_at DATETIME NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_dpop_nonces_expires_at ON dpop_nonces(expires_at);
+
+-- ===============================
+-- 已使用的 DPoP 证明 (Replay Cache)
+-- ===============================
+
+CREATE TABLE IF NOT EXISTS dpop_proof_jtis (
+    jkt TEXT NOT NULL,
+    jti TEXT NOT NULL,
+    expires_at DATETIME NOT NULL,
+    PRIMARY KEY (jkt, jti)
+);
+
+CREATE INDEX IF NOT EXISTS idx_dpop_proof_jtis_expires_at ON dpop_proof_jtis(expires_at);
+
+-- ===============================
+-- 绑定到密钥的刷新令牌 (DPoP-bound Refresh Tokens)
+-- ===============================
+
+ALTER TABLE refresh_tokens ADD COLUMN dpop_jkt TEXT;
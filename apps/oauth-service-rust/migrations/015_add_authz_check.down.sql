@@ -0,0 +1,4 @@
+-- Authorization Decision API Migration (rollback)
+DELETE FROM scope_permissions WHERE permission_id IN (SELECT id FROM permissions WHERE resource = 'authz');
+DELETE FROM role_permissions WHERE permission_id IN (SELECT id FROM permissions WHERE resource = 'authz');
+DELETE FROM permissions WHERE resource = 'authz';
@@ -0,0 +1,24 @@
+-- Authorization Decision API Migration
+-- Version 1: Let resource servers ask the authorization server for permission decisions
+-- 说明: 资源服务器调用 /api/v2/authz/check 判断主体 (用户或客户端) 能否执行某个操作，
+--       不再自行解码访问令牌中的 permissions。
+--       请求方检查自身权限 (如前端按权限显示菜单和按钮) 无需额外权限；
+--       检查其他主体需要 authz:check 权限，资源服务器通过客户端权限获得
+
+-- ===============================
+-- 授权决策权限 (Authorization Decision Permissions)
+-- ===============================
+
+INSERT OR IGNORE INTO permissions (
+    id, name, display_name, description, resource, action, type, is_system_perm, is_active
+) VALUES
+    ('clh4001401', 'authz:check', 'Check Authorization', 'Ask whether any user or client holds a permission', 'authz', 'check', 'API', true, true);
+
+-- 超级管理员: 添加授权决策权限
+INSERT OR IGNORE INTO role_permissions (role_id, permission_id)
+SELECT 'clh3000001', id FROM permissions WHERE resource = 'authz';
+
+-- 权限范围: 映射到 admin scope，否则令牌签发时会按 scope 去掉该权限
+INSERT OR IGNORE INTO scope_permissions (scope_id, permission_id)
+SELECT s.id, p.id FROM scopes s, permissions p
+WHERE s.name = 'admin' AND p.resource = 'authz';
@@ -0,0 +1,9 @@
+-- Access Requests Migration (rollback)
+DELETE FROM scope_permissions WHERE permission_id IN (SELECT id FROM permissions WHERE resource = 'access_requests');
+DELETE FROM role_permissions WHERE permission_id IN (SELECT id FROM permissions WHERE resource = 'access_requests');
+DELETE FROM permissions WHERE resource = 'access_requests';
+DROP INDEX IF EXISTS idx_access_requests_role_status;
+DROP INDEX IF EXISTS idx_access_requests_user;
+DROP TABLE IF EXISTS access_requests;
+DROP INDEX IF EXISTS idx_role_approvers_user;
+DROP TABLE IF EXISTS role_approvers;
@@ -0,0 +1,65 @@
+-- Access Requests Migration
+-- Version 1: Let users request roles and designated approvers decide
+-- 说明: 用户申请角色时填写理由和可选的有效期，由该角色指定的审批人批准或拒绝；
+--       批准后通过 RoleService::assign_role_to_user 创建 (可带过期时间的) 角色分配。
+--       没有审批人的角色不接受申请。申请、撤回、批准、拒绝和审批人变更都写入审计日志
+
+-- ===============================
+-- 角色审批人 (Role Approvers)
+-- ===============================
+
+CREATE TABLE IF NOT EXISTS role_approvers (
+    role_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (role_id, user_id),
+    FOREIGN KEY (role_id) REFERENCES roles(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_role_approvers_user ON role_approvers(user_id);
+
+-- ===============================
+-- 角色申请 (Access Requests)
+-- ===============================
+
+CREATE TABLE IF NOT EXISTS access_requests (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL, -- 申请人
+    role_id TEXT NOT NULL, -- 申请的角色
+    justification TEXT NOT NULL,
+    duration_secs INTEGER, -- 批准后角色分配的有效期，为空表示永久有效
+    status TEXT NOT NULL DEFAULT 'pending', -- pending / approved / rejected / cancelled
+    decided_by TEXT, -- 批准或拒绝的审批人
+    decision_comment TEXT,
+    decided_at DATETIME,
+    expires_at DATETIME, -- 批准时计算的角色分配过期时间
+    tenant_id TEXT, -- 申请人所属租户
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (role_id) REFERENCES roles(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_access_requests_user ON access_requests(user_id);
+CREATE INDEX IF NOT EXISTS idx_access_requests_role_status ON access_requests(role_id, status);
+
+-- ===============================
+-- 角色申请管理权限 (Access Request Permissions)
+-- ===============================
+
+INSERT OR IGNORE INTO permissions (
+    id, name, display_name, description, resource, action, type, is_system_perm, is_active
+) VALUES
+    ('clh4001601', 'access_requests:read', 'Read Access Requests', 'View and filter every role access request', 'access_requests', 'read', 'API', true, true),
+    ('clh4001602', 'access_requests:manage', 'Manage Role Approvers', 'Designate the users who approve access requests for a role', 'access_requests', 'manage', 'API', true, true);
+
+-- 超级管理员: 添加角色申请管理权限
+INSERT OR IGNORE INTO role_permissions (role_id, permission_id)
+SELECT 'clh3000001', id FROM permissions WHERE resource = 'access_requests';
+
+-- 权限范围: 映射到 admin scope，否则令牌签发时会按 scope 去掉该权限
+INSERT OR IGNORE INTO scope_permissions (scope_id, permission_id)
+SELECT s.id, p.id FROM scopes s, permissions p
+WHERE s.name = 'admin' AND p.resource = 'access_requests';
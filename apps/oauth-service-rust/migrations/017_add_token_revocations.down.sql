@@ -0,0 +1,5 @@
+-- Token Revocations Migration (rollback)
+DELETE FROM scope_permissions WHERE permission_id IN (SELECT id FROM permissions WHERE id = 'clh4001701');
+DELETE FROM role_permissions WHERE permission_id = 'clh4001701';
+DELETE FROM permissions WHERE id = 'clh4001701';
+DROP TABLE IF EXISTS token_revocations;
@@ -0,0 +1,35 @@
+-- Token Revocations Migration
+-- Version 1: Revoke every token of a user or client in one operation
+-- 说明: 访问令牌是无状态的 JWT，无法逐个列出；全局吊销时记录一条水位线，
+--       签发时间 (iat) 不晚于水位线的令牌在内省和认证中间件中一律视为已吊销
+
+-- ===============================
+-- 吊销水位线 (Token Revocations)
+-- ===============================
+
+CREATE TABLE IF NOT EXISTS token_revocations (
+    subject_type TEXT NOT NULL CHECK (subject_type IN ('user', 'client')),
+    subject_id TEXT NOT NULL, -- 用户 ID 或公开的 client_id
+    revoked_before DATETIME NOT NULL, -- 在此之前签发的令牌无效
+    reason TEXT,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+
+    PRIMARY KEY (subject_type, subject_id)
+);
+
+-- ===============================
+-- 全局吊销权限 (Token Revocation Permission)
+-- ===============================
+
+INSERT OR IGNORE INTO permissions (
+    id, name, display_name, description, resource, action, type, is_system_perm, is_active
+) VALUES
+    ('clh4001701', 'tokens:revoke', 'Revoke All Tokens', 'Revoke every token and session of a user or client', 'tokens', 'revoke', 'API', true, true);
+
+-- 超级管理员: 添加全局吊销权限
+INSERT OR IGNORE INTO role_permissions (role_id, permission_id) VALUES ('clh3000001', 'clh4001701');
+
+-- 权限范围: 映射到 admin scope，否则令牌签发时会按 scope 去掉该权限
+INSERT OR IGNORE INTO scope_permissions (scope_id, permission_id)
+SELECT s.id, p.id FROM scopes s, permissions p
+WHERE s.name = 'admin' AND p.id = 'clh4001701';
@@ -149,6 +149,53 @@ export interface UserRole {
   assigned_at: string;
 }
 
+/**
+ * 角色引用接口 (Role Reference Interface)
+ */
+export interface RoleRef {
+  /** 角色ID (Role ID) */
+  role_id: string;
+  /** 角色名称 (Role Name) */
+  role_name: string;
+}
+
+/**
+ * 权限来源接口 (Permission Source Interface)
+ *
+ * 角色分配 → 继承路径 → 授予权限的角色 (Role assignment → inheritance path → granting role)
+ */
+export interface PermissionSource {
+  /** 带来该权限的角色分配 (Role assignment that brings the permission) */
+  assignment: {
+    role_id: string;
+    role_name: string;
+    /** 生效范围，为空表示全局分配 (Assignment scope; null for global assignments) */
+    context: { organization?: string; client_id?: string } | null;
+    expires_at: string | null;
+    assigned_by: string | null;
+    assigned_at: string;
+  };
+  /** 从分配的角色到授予权限的角色 (From the assigned role to the granting role) */
+  path: RoleRef[];
+  /** 附加条件 (Conditions evaluated per request) */
+  conditions: any | null;
+}
+
+/**
+ * 用户有效权限解释接口 (Effective Permission Explanation Interface)
+ */
+export interface PermissionExplanation {
+  /** 用户ID (User ID) */
+  user_id: string;
+  /** 有效权限及其来源 (Effective permissions and their sources) */
+  permissions: Array<{ permission: string; sources: PermissionSource[] }>;
+  /** 缺少的权限及可授予它们的角色 (Missing permissions and roles that would grant them) */
+  missing: Array<{
+    permission: string;
+    granting_roles: Array<RoleRef & { path: RoleRef[]; conditions: any | null }>;
+  }>;
+}
+
 /**
  * 客户端信息接口 (Client Info Interface)
  */
@@ -301,6 +348,20 @@ export class OAuthSDK {
    */
   rbacRevokeRole(userId: string, roleId: string): Promise<boolean>;
 
+  /**
+   * 解释用户的有效权限 (Explain User's Effective Permissions)
+   *
+   * @param userId - 用户ID (User ID)
+   * @param clientId - 按该客户端的范围求值 (Evaluate for this client)
+   * @param permissions - 需要解释的权限 (Permissions to explain when missing)
+   * @returns 权限来源和可授予缺少权限的角色 (Permission sources and granting roles)
+   */
+  rbacExplainPermissions(
+    userId: string,
+    clientId?: string,
+    permissions?: string[]
+  ): Promise<PermissionExplanation>;
+
   /**
    * 获取客户端列表 (Get Client List)
    *
@@ -0,0 +1,163 @@
+// 登录和同意页面的通行密钥 (WebAuthn) 交互
+// CSP 不允许内联脚本，因此以静态文件提供
+(function () {
+    'use strict';
+
+    function toBuffer(base64url) {
+        var base64 = base64url.replace(/-/g, '+').replace(/_/g, '/');
+        var binary = atob(base64 + '==='.slice((base64.length + 3) % 4));
+        var bytes = new Uint8Array(binary.length);
+        for (var i = 0; i < binary.length; i++) {
+            bytes[i] = binary.charCodeAt(i);
+        }
+        return bytes.buffer;
+    }
+
+    function toBase64url(buffer) {
+        var bytes = new Uint8Array(buffer);
+        var binary = '';
+        for (var i = 0; i < bytes.length; i++) {
+            binary += String.fromCharCode(bytes[i]);
+        }
+        return btoa(binary).replace(/\+/g, '-').replace(/\//g, '_').replace(/=+$/, '');
+    }
+
+    function decodeDescriptors(descriptors) {
+        return (descriptors || []).map(function (descriptor) {
+            return Object.assign({}, descriptor, { id: toBuffer(descriptor.id) });
+        });
+    }
+
+    async function postJson(url, body) {
+        var response = await fetch(url, {
+            method: 'POST',
+            credentials: 'same-origin',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify(body),
+        });
+        var data = await response.json().catch(function () { return {}; });
+        if (!response.ok) {
+            throw new Error(data.error || data.message || ('请求失败 (' + response.status + ')'));
+        }
+        return data;
+    }
+
+    /** 对断言选项调用 navigator.credentials.get() 并完成登录 */
+    async function assertAndLogin(options) {
+        var publicKey = Object.assign({}, options.publicKey, {
+            challenge: toBuffer(options.publicKey.challenge),
+            allowCredentials: decodeDescriptors(options.publicKey.allowCredentials),
+        });
+        var credential = await navigator.credentials.get({ publicKey: publicKey });
+        var result = await postJson('/api/v2/auth/webauthn/login', {
+            challenge_id: options.challengeId,
+            credential: {
+                id: credential.id,
+                response: {
+                    clientDataJSON: toBase64url(credential.response.clientDataJSON),
+                    authenticatorData: toBase64url(credential.response.authenticatorData),
+                    signature: toBase64url(credential.response.signature),
+                    userHandle: credential.response.userHandle
+                        ? toBase64url(credential.response.userHandle)
+                        : null,
+                },
+            },
+        });
+        window.location.href = result.redirect_url;
+    }
+
+    /** 为当前登录的用户注册通行密钥 */
+    async function register() {
+        var options = await postJson('/api/v2/auth/webauthn/register/options', {});
+        var publicKey = Object.assign({}, options.publicKey, {
+            challenge: toBuffer(options.publicKey.challenge),
+            user: Object.assign({}, options.publicKey.user, { id: toBuffer(options.publicKey.user.id) }),
+            excludeCredentials: decodeDescriptors(options.publicKey.excludeCredentials),
+        });
+        var credential = await navigator.credentials.create({ publicKey: publicKey });
+        return postJson('/api/v2/auth/webauthn/register', {
+            challenge_id: options.challengeId,
+            credential: {
+                id: credential.id,
+                response: {
+                    clientDataJSON: toBase64url(credential.response.clientDataJSON),
+                    attestationObject: toBase64url(credential.response.attestationObject),
+                    transports: credential.response.getTransports ? credential.response.getTransports() : [],
+                },
+            },
+        });
+    }
+
+    function showError(element, error) {
+        if (element) {
+            element.textContent = error.message || String(error);
+            element.classList.add('show');
+        }
+    }
+
+    document.addEventListener('DOMContentLoaded', function () {
+        var supported = !!window.PublicKeyCredential;
+        var errorElement = document.getElementById('login-error');
+
+        // 登录页：密码登录 (需要时继续完成通行密钥第二因素)
+        var loginForm = document.getElementById('login-form');
+        if (loginForm) {
+            loginForm.addEventListener('submit', async function (event) {
+                event.preventDefault();
+                var redirect = loginForm.elements.redirect_url ? loginForm.elements.redirect_url.value : null;
+                try {
+                    var result = await postJson('/api/v2/auth/login', {
+                        username: loginForm.elements.username.value,
+                        password: loginForm.elements.password.value,
+                        redirect: redirect,
+                    });
+                    if (result.passkey_challenge) {
+                        if (!supported) {
+                            throw new Error('此账户需要通行密钥验证，但当前浏览器不支持通行密钥');
+                        }
+                        await assertAndLogin(result.passkey_challenge);
+                    } else {
+                        window.location.href = result.redirect_url;
+                    }
+                } catch (error) {
+                    showError(errorElement, error);
+                }
+            });
+        }
+
+        // 登录页：无密码登录
+        var passkeyLogin = document.getElementById('passkey-login');
+        if (passkeyLogin && supported) {
+            passkeyLogin.hidden = false;
+            document.getElementById('passkey-login-button').addEventListener('click', async function () {
+                try {
+                    var options = await postJson('/api/v2/auth/webauthn/login/options', {
+                        username: loginForm ? loginForm.elements.username.value || null : null,
+                        redirect: loginForm && loginForm.elements.redirect_url
+                            ? loginForm.elements.redirect_url.value
+                            : null,
+                    });
+                    await assertAndLogin(options);
+                } catch (error) {
+                    showError(errorElement, error);
+                }
+            });
+        }
+
+        // 同意页：为当前账户添加通行密钥
+        var passkeyRegister = document.getElementById('passkey-register');
+        if (passkeyRegister && supported) {
+            passkeyRegister.hidden = false;
+            passkeyRegister.addEventListener('click', async function () {
+                var status = document.getElementById('passkey-status');
+                try {
+                    await register();
+                    passkeyRegister.hidden = true;
+                    status.textContent = '已添加通行密钥，下次可以直接使用通行密钥登录';
+                } catch (error) {
+                    status.textContent = error.message || String(error);
+                }
+            });
+        }
+    });
+})();